@@ -0,0 +1,899 @@
+// c0406226  run()
+// 14b13e62  fib(int256)
+// 84da15e1  even(int256)
+// 1e71552a  collatz(int256)
+
+creation:
+0000  PUSH2 0x06a3
+0003  DUP1
+0004  PUSH1 0x0a
+0006  PUSH0
+0007  CODECOPY
+0008  PUSH0
+0009  RETURN
+
+runtime:
+0000  PUSH1 0x80
+0002  PUSH0
+0003  MSTORE
+0004  PUSH1 0x04
+0006  CALLDATASIZE
+0007  LT
+0008  PUSH2 0x003d
+000b  JUMPI
+000c  PUSH0
+000d  CALLDATALOAD
+000e  PUSH1 0xe0
+0010  SHR
+0011  DUP1
+0012  PUSH4 0xc0406226
+0017  EQ
+0018  PUSH2 0x0041
+001b  JUMPI
+001c  DUP1
+001d  PUSH4 0x14b13e62
+0022  EQ
+0023  PUSH2 0x0057
+0026  JUMPI
+0027  DUP1
+0028  PUSH4 0x84da15e1
+002d  EQ
+002e  PUSH2 0x0074
+0031  JUMPI
+0032  DUP1
+0033  PUSH4 0x1e71552a
+0038  EQ
+0039  PUSH2 0x0091
+003c  JUMPI
+003d  JUMPDEST
+003e  PUSH0
+003f  PUSH0
+0040  REVERT
+0041  JUMPDEST
+0042  POP
+0043  CALLDATASIZE
+0044  PUSH1 0x04
+0046  GT
+0047  PUSH2 0x003d
+004a  JUMPI
+004b  PUSH2 0x0052
+004e  PUSH2 0x00ae
+0051  JUMP
+0052  JUMPDEST
+0053  POP
+0054  PUSH0
+0055  PUSH0
+0056  RETURN
+0057  JUMPDEST
+0058  POP
+0059  CALLDATASIZE
+005a  PUSH1 0x24
+005c  GT
+005d  PUSH2 0x003d
+0060  JUMPI
+0061  PUSH2 0x006b
+0064  PUSH1 0x04
+0066  CALLDATALOAD
+0067  PUSH2 0x0438
+006a  JUMP
+006b  JUMPDEST
+006c  PUSH1 0x40
+006e  MSTORE
+006f  PUSH1 0x20
+0071  PUSH1 0x40
+0073  RETURN
+0074  JUMPDEST
+0075  POP
+0076  CALLDATASIZE
+0077  PUSH1 0x24
+0079  GT
+007a  PUSH2 0x003d
+007d  JUMPI
+007e  PUSH2 0x0088
+0081  PUSH1 0x04
+0083  CALLDATALOAD
+0084  PUSH2 0x04af
+0087  JUMP
+0088  JUMPDEST
+0089  PUSH1 0x40
+008b  MSTORE
+008c  PUSH1 0x20
+008e  PUSH1 0x40
+0090  RETURN
+0091  JUMPDEST
+0092  POP
+0093  CALLDATASIZE
+0094  PUSH1 0x24
+0096  GT
+0097  PUSH2 0x003d
+009a  JUMPI
+009b  PUSH2 0x00a5
+009e  PUSH1 0x04
+00a0  CALLDATALOAD
+00a1  PUSH2 0x056f
+00a4  JUMP
+00a5  JUMPDEST
+00a6  PUSH1 0x40
+00a8  MSTORE
+00a9  PUSH1 0x20
+00ab  PUSH1 0x40
+00ad  RETURN
+00ae  JUMPDEST
+00af  PUSH0
+00b0  MLOAD
+00b1  PUSH1 0x20
+00b3  MLOAD
+00b4  DUP2
+00b5  MSTORE
+00b6  DUP1
+00b7  PUSH1 0x20
+00b9  MSTORE
+00ba  PUSH2 0x0120
+00bd  ADD
+00be  PUSH0
+00bf  MSTORE
+00c0  PUSH0
+00c1  PUSH1 0x20
+00c3  MLOAD
+00c4  PUSH1 0x20
+00c6  ADD
+00c7  MSTORE
+00c8  PUSH1 0x0c
+00ca  PUSH1 0x20
+00cc  MLOAD
+00cd  PUSH1 0x40
+00cf  ADD
+00d0  MSTORE
+00d1  PUSH1 0x01
+00d3  PUSH1 0x20
+00d5  MLOAD
+00d6  PUSH1 0x60
+00d8  ADD
+00d9  MSTORE
+00da  PUSH1 0x20
+00dc  MLOAD
+00dd  PUSH1 0x60
+00df  ADD
+00e0  MLOAD
+00e1  PUSH2 0x014f
+00e4  JUMPI
+00e5  PUSH0
+00e6  MLOAD
+00e7  PUSH4 0x08c379a0
+00ec  PUSH1 0xe0
+00ee  SHL
+00ef  DUP2
+00f0  MSTORE
+00f1  PUSH1 0x20
+00f3  DUP2
+00f4  PUSH1 0x04
+00f6  ADD
+00f7  MSTORE
+00f8  PUSH1 0x27
+00fa  DUP2
+00fb  PUSH1 0x24
+00fd  ADD
+00fe  MSTORE
+00ff  PUSH32 0x52756e74696d65206572726f723a2052616e676520737465702063616e277420
+0120  DUP2
+0121  PUSH1 0x44
+0123  ADD
+0124  MSTORE
+0125  PUSH32 0x6265207a65726f00000000000000000000000000000000000000000000000000
+0146  DUP2
+0147  PUSH1 0x64
+0149  ADD
+014a  MSTORE
+014b  PUSH1 0x84
+014d  SWAP1
+014e  REVERT
+014f  JUMPDEST
+0150  JUMPDEST
+0151  PUSH1 0x20
+0153  MLOAD
+0154  PUSH1 0x40
+0156  ADD
+0157  MLOAD
+0158  PUSH1 0x20
+015a  MLOAD
+015b  PUSH1 0x20
+015d  ADD
+015e  MLOAD
+015f  SLT
+0160  PUSH0
+0161  PUSH1 0x20
+0163  MLOAD
+0164  PUSH1 0x60
+0166  ADD
+0167  MLOAD
+0168  SGT
+0169  AND
+016a  PUSH1 0x20
+016c  MLOAD
+016d  PUSH1 0x40
+016f  ADD
+0170  MLOAD
+0171  PUSH1 0x20
+0173  MLOAD
+0174  PUSH1 0x20
+0176  ADD
+0177  MLOAD
+0178  SGT
+0179  PUSH0
+017a  PUSH1 0x20
+017c  MLOAD
+017d  PUSH1 0x60
+017f  ADD
+0180  MLOAD
+0181  SLT
+0182  AND
+0183  OR
+0184  ISZERO
+0185  PUSH2 0x021c
+0188  JUMPI
+0189  PUSH1 0x20
+018b  MLOAD
+018c  PUSH1 0x20
+018e  ADD
+018f  MLOAD
+0190  PUSH1 0x20
+0192  MLOAD
+0193  PUSH1 0x80
+0195  ADD
+0196  MSTORE
+0197  PUSH1 0x20
+0199  MLOAD
+019a  PUSH1 0x80
+019c  ADD
+019d  MLOAD
+019e  PUSH1 0x03
+01a0  EQ
+01a1  ISZERO
+01a2  PUSH2 0x01ae
+01a5  JUMPI
+01a6  PUSH2 0x0201
+01a9  JUMP
+01aa  PUSH2 0x01af
+01ad  JUMP
+01ae  JUMPDEST
+01af  JUMPDEST
+01b0  PUSH1 0x20
+01b2  MLOAD
+01b3  PUSH1 0x80
+01b5  ADD
+01b6  MLOAD
+01b7  PUSH1 0x0a
+01b9  EQ
+01ba  ISZERO
+01bb  PUSH2 0x01c7
+01be  JUMPI
+01bf  PUSH2 0x021c
+01c2  JUMP
+01c3  PUSH2 0x01c8
+01c6  JUMP
+01c7  JUMPDEST
+01c8  JUMPDEST
+01c9  PUSH2 0x01d7
+01cc  PUSH1 0x20
+01ce  MLOAD
+01cf  PUSH1 0x80
+01d1  ADD
+01d2  MLOAD
+01d3  PUSH2 0x0438
+01d6  JUMP
+01d7  JUMPDEST
+01d8  PUSH0
+01d9  MLOAD
+01da  MSTORE
+01db  PUSH32 0xe4ee9fcafd07f338ab74589c44ec7c63d31664f86e2c24a7bef2beaf5bb4e720
+01fc  PUSH1 0x20
+01fe  PUSH0
+01ff  MLOAD
+0200  LOG1
+0201  JUMPDEST
+0202  PUSH1 0x20
+0204  MLOAD
+0205  PUSH1 0x60
+0207  ADD
+0208  MLOAD
+0209  PUSH1 0x20
+020b  MLOAD
+020c  PUSH1 0x20
+020e  ADD
+020f  MLOAD
+0210  ADD
+0211  PUSH1 0x20
+0213  MLOAD
+0214  PUSH1 0x20
+0216  ADD
+0217  MSTORE
+0218  PUSH2 0x0150
+021b  JUMP
+021c  JUMPDEST
+021d  PUSH1 0x0a
+021f  PUSH1 0x20
+0221  MLOAD
+0222  PUSH1 0xa0
+0224  ADD
+0225  MSTORE
+0226  PUSH0
+0227  PUSH1 0x20
+0229  MLOAD
+022a  PUSH1 0xc0
+022c  ADD
+022d  MSTORE
+022e  PUSH32 0xfffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffd
+024f  PUSH1 0x20
+0251  MLOAD
+0252  PUSH1 0xe0
+0254  ADD
+0255  MSTORE
+0256  PUSH1 0x20
+0258  MLOAD
+0259  PUSH1 0xe0
+025b  ADD
+025c  MLOAD
+025d  PUSH2 0x02cb
+0260  JUMPI
+0261  PUSH0
+0262  MLOAD
+0263  PUSH4 0x08c379a0
+0268  PUSH1 0xe0
+026a  SHL
+026b  DUP2
+026c  MSTORE
+026d  PUSH1 0x20
+026f  DUP2
+0270  PUSH1 0x04
+0272  ADD
+0273  MSTORE
+0274  PUSH1 0x27
+0276  DUP2
+0277  PUSH1 0x24
+0279  ADD
+027a  MSTORE
+027b  PUSH32 0x52756e74696d65206572726f723a2052616e676520737465702063616e277420
+029c  DUP2
+029d  PUSH1 0x44
+029f  ADD
+02a0  MSTORE
+02a1  PUSH32 0x6265207a65726f00000000000000000000000000000000000000000000000000
+02c2  DUP2
+02c3  PUSH1 0x64
+02c5  ADD
+02c6  MSTORE
+02c7  PUSH1 0x84
+02c9  SWAP1
+02ca  REVERT
+02cb  JUMPDEST
+02cc  JUMPDEST
+02cd  PUSH1 0x20
+02cf  MLOAD
+02d0  PUSH1 0xc0
+02d2  ADD
+02d3  MLOAD
+02d4  PUSH1 0x20
+02d6  MLOAD
+02d7  PUSH1 0xa0
+02d9  ADD
+02da  MLOAD
+02db  SLT
+02dc  PUSH0
+02dd  PUSH1 0x20
+02df  MLOAD
+02e0  PUSH1 0xe0
+02e2  ADD
+02e3  MLOAD
+02e4  SGT
+02e5  AND
+02e6  PUSH1 0x20
+02e8  MLOAD
+02e9  PUSH1 0xc0
+02eb  ADD
+02ec  MLOAD
+02ed  PUSH1 0x20
+02ef  MLOAD
+02f0  PUSH1 0xa0
+02f2  ADD
+02f3  MLOAD
+02f4  SGT
+02f5  PUSH0
+02f6  PUSH1 0x20
+02f8  MLOAD
+02f9  PUSH1 0xe0
+02fb  ADD
+02fc  MLOAD
+02fd  SLT
+02fe  AND
+02ff  OR
+0300  ISZERO
+0301  PUSH2 0x0360
+0304  JUMPI
+0305  PUSH1 0x20
+0307  MLOAD
+0308  PUSH1 0xa0
+030a  ADD
+030b  MLOAD
+030c  PUSH1 0x20
+030e  MLOAD
+030f  PUSH2 0x0100
+0312  ADD
+0313  MSTORE
+0314  PUSH1 0x20
+0316  MLOAD
+0317  PUSH2 0x0100
+031a  ADD
+031b  MLOAD
+031c  PUSH0
+031d  MLOAD
+031e  MSTORE
+031f  PUSH32 0xe4ee9fcafd07f338ab74589c44ec7c63d31664f86e2c24a7bef2beaf5bb4e720
+0340  PUSH1 0x20
+0342  PUSH0
+0343  MLOAD
+0344  LOG1
+0345  JUMPDEST
+0346  PUSH1 0x20
+0348  MLOAD
+0349  PUSH1 0xe0
+034b  ADD
+034c  MLOAD
+034d  PUSH1 0x20
+034f  MLOAD
+0350  PUSH1 0xa0
+0352  ADD
+0353  MLOAD
+0354  ADD
+0355  PUSH1 0x20
+0357  MLOAD
+0358  PUSH1 0xa0
+035a  ADD
+035b  MSTORE
+035c  PUSH2 0x02cc
+035f  JUMP
+0360  JUMPDEST
+0361  PUSH1 0x1b
+0363  PUSH0
+0364  SSTORE
+0365  PUSH2 0x036e
+0368  PUSH0
+0369  SLOAD
+036a  PUSH2 0x056f
+036d  JUMP
+036e  JUMPDEST
+036f  PUSH0
+0370  MLOAD
+0371  MSTORE
+0372  PUSH32 0xe4ee9fcafd07f338ab74589c44ec7c63d31664f86e2c24a7bef2beaf5bb4e720
+0393  PUSH1 0x20
+0395  PUSH0
+0396  MLOAD
+0397  LOG1
+0398  PUSH2 0x03a1
+039b  PUSH0
+039c  SLOAD
+039d  PUSH2 0x04af
+03a0  JUMP
+03a1  JUMPDEST
+03a2  PUSH0
+03a3  MLOAD
+03a4  MSTORE
+03a5  PUSH32 0xc9b5f89833845a18161f53409688987899c231d45b3f39cf99c2945a862dba89
+03c6  PUSH1 0x20
+03c8  PUSH0
+03c9  MLOAD
+03ca  LOG1
+03cb  PUSH0
+03cc  PUSH1 0x01
+03ce  SSTORE
+03cf  JUMPDEST
+03d0  PUSH1 0x01
+03d2  ISZERO
+03d3  PUSH2 0x03fc
+03d6  JUMPI
+03d7  PUSH1 0x01
+03d9  SLOAD
+03da  PUSH1 0x01
+03dc  ADD
+03dd  DUP1
+03de  PUSH1 0x01
+03e0  SSTORE
+03e1  POP
+03e2  PUSH1 0x01
+03e4  SLOAD
+03e5  PUSH1 0x05
+03e7  SGT
+03e8  ISZERO
+03e9  ISZERO
+03ea  PUSH2 0x03f6
+03ed  JUMPI
+03ee  PUSH2 0x03fc
+03f1  JUMP
+03f2  PUSH2 0x03f7
+03f5  JUMP
+03f6  JUMPDEST
+03f7  JUMPDEST
+03f8  PUSH2 0x03cf
+03fb  JUMP
+03fc  JUMPDEST
+03fd  PUSH1 0x01
+03ff  SLOAD
+0400  PUSH0
+0401  MLOAD
+0402  MSTORE
+0403  PUSH32 0xe4ee9fcafd07f338ab74589c44ec7c63d31664f86e2c24a7bef2beaf5bb4e720
+0424  PUSH1 0x20
+0426  PUSH0
+0427  MLOAD
+0428  LOG1
+0429  PUSH0
+042a  PUSH1 0x20
+042c  MLOAD
+042d  PUSH0
+042e  MSTORE
+042f  PUSH1 0x20
+0431  MLOAD
+0432  MLOAD
+0433  PUSH1 0x20
+0435  MSTORE
+0436  SWAP1
+0437  JUMP
+0438  JUMPDEST
+0439  PUSH0
+043a  MLOAD
+043b  PUSH1 0x20
+043d  MLOAD
+043e  DUP2
+043f  MSTORE
+0440  DUP1
+0441  PUSH1 0x20
+0443  MSTORE
+0444  PUSH1 0x40
+0446  ADD
+0447  PUSH0
+0448  MSTORE
+0449  PUSH1 0x20
+044b  MLOAD
+044c  PUSH1 0x20
+044e  ADD
+044f  MSTORE
+0450  PUSH1 0x20
+0452  MLOAD
+0453  PUSH1 0x20
+0455  ADD
+0456  MLOAD
+0457  PUSH1 0x02
+0459  SGT
+045a  ISZERO
+045b  PUSH2 0x0478
+045e  JUMPI
+045f  PUSH1 0x20
+0461  MLOAD
+0462  PUSH1 0x20
+0464  ADD
+0465  MLOAD
+0466  PUSH1 0x20
+0468  MLOAD
+0469  PUSH0
+046a  MSTORE
+046b  PUSH1 0x20
+046d  MLOAD
+046e  MLOAD
+046f  PUSH1 0x20
+0471  MSTORE
+0472  SWAP1
+0473  JUMP
+0474  PUSH2 0x0479
+0477  JUMP
+0478  JUMPDEST
+0479  JUMPDEST
+047a  PUSH2 0x048c
+047d  PUSH1 0x20
+047f  MLOAD
+0480  PUSH1 0x20
+0482  ADD
+0483  MLOAD
+0484  PUSH1 0x01
+0486  SWAP1
+0487  SUB
+0488  PUSH2 0x0438
+048b  JUMP
+048c  JUMPDEST
+048d  PUSH2 0x049f
+0490  PUSH1 0x20
+0492  MLOAD
+0493  PUSH1 0x20
+0495  ADD
+0496  MLOAD
+0497  PUSH1 0x02
+0499  SWAP1
+049a  SUB
+049b  PUSH2 0x0438
+049e  JUMP
+049f  JUMPDEST
+04a0  ADD
+04a1  PUSH1 0x20
+04a3  MLOAD
+04a4  PUSH0
+04a5  MSTORE
+04a6  PUSH1 0x20
+04a8  MLOAD
+04a9  MLOAD
+04aa  PUSH1 0x20
+04ac  MSTORE
+04ad  SWAP1
+04ae  JUMP
+04af  JUMPDEST
+04b0  PUSH0
+04b1  MLOAD
+04b2  PUSH1 0x20
+04b4  MLOAD
+04b5  DUP2
+04b6  MSTORE
+04b7  DUP1
+04b8  PUSH1 0x20
+04ba  MSTORE
+04bb  PUSH1 0x40
+04bd  ADD
+04be  PUSH0
+04bf  MSTORE
+04c0  PUSH1 0x20
+04c2  MLOAD
+04c3  PUSH1 0x20
+04c5  ADD
+04c6  MSTORE
+04c7  PUSH1 0x20
+04c9  MLOAD
+04ca  PUSH1 0x20
+04cc  ADD
+04cd  MLOAD
+04ce  PUSH1 0x02
+04d0  DUP1
+04d1  PUSH2 0x053f
+04d4  JUMPI
+04d5  PUSH0
+04d6  MLOAD
+04d7  PUSH4 0x08c379a0
+04dc  PUSH1 0xe0
+04de  SHL
+04df  DUP2
+04e0  MSTORE
+04e1  PUSH1 0x20
+04e3  DUP2
+04e4  PUSH1 0x04
+04e6  ADD
+04e7  MSTORE
+04e8  PUSH1 0x29
+04ea  DUP2
+04eb  PUSH1 0x24
+04ed  ADD
+04ee  MSTORE
+04ef  PUSH32 0x52756e74696d65206572726f723a20596f75206a75737420676f742072656b74
+0510  DUP2
+0511  PUSH1 0x44
+0513  ADD
+0514  MSTORE
+0515  PUSH32 0x206279207a65726f210000000000000000000000000000000000000000000000
+0536  DUP2
+0537  PUSH1 0x64
+0539  ADD
+053a  MSTORE
+053b  PUSH1 0x84
+053d  SWAP1
+053e  REVERT
+053f  JUMPDEST
+0540  PUSH1 0x60
+0542  MSTORE
+0543  PUSH1 0x40
+0545  MSTORE
+0546  PUSH1 0x60
+0548  MLOAD
+0549  PUSH1 0x40
+054b  MLOAD
+054c  SMOD
+054d  DUP1
+054e  ISZERO
+054f  ISZERO
+0550  PUSH0
+0551  DUP3
+0552  SLT
+0553  PUSH0
+0554  PUSH1 0x60
+0556  MLOAD
+0557  SLT
+0558  XOR
+0559  AND
+055a  PUSH1 0x60
+055c  MLOAD
+055d  MUL
+055e  ADD
+055f  PUSH0
+0560  EQ
+0561  PUSH1 0x20
+0563  MLOAD
+0564  PUSH0
+0565  MSTORE
+0566  PUSH1 0x20
+0568  MLOAD
+0569  MLOAD
+056a  PUSH1 0x20
+056c  MSTORE
+056d  SWAP1
+056e  JUMP
+056f  JUMPDEST
+0570  PUSH0
+0571  MLOAD
+0572  PUSH1 0x20
+0574  MLOAD
+0575  DUP2
+0576  MSTORE
+0577  DUP1
+0578  PUSH1 0x20
+057a  MSTORE
+057b  PUSH1 0x60
+057d  ADD
+057e  PUSH0
+057f  MSTORE
+0580  PUSH1 0x20
+0582  MLOAD
+0583  PUSH1 0x20
+0585  ADD
+0586  MSTORE
+0587  PUSH0
+0588  PUSH1 0x20
+058a  MLOAD
+058b  PUSH1 0x40
+058d  ADD
+058e  MSTORE
+058f  JUMPDEST
+0590  PUSH1 0x20
+0592  MLOAD
+0593  PUSH1 0x20
+0595  ADD
+0596  MLOAD
+0597  PUSH1 0x01
+0599  EQ
+059a  ISZERO
+059b  ISZERO
+059c  PUSH2 0x068d
+059f  JUMPI
+05a0  PUSH2 0x05ae
+05a3  PUSH1 0x20
+05a5  MLOAD
+05a6  PUSH1 0x20
+05a8  ADD
+05a9  MLOAD
+05aa  PUSH2 0x04af
+05ad  JUMP
+05ae  JUMPDEST
+05af  ISZERO
+05b0  PUSH2 0x065e
+05b3  JUMPI
+05b4  PUSH1 0x20
+05b6  MLOAD
+05b7  PUSH1 0x20
+05b9  ADD
+05ba  MLOAD
+05bb  PUSH1 0x02
+05bd  DUP1
+05be  PUSH2 0x062c
+05c1  JUMPI
+05c2  PUSH0
+05c3  MLOAD
+05c4  PUSH4 0x08c379a0
+05c9  PUSH1 0xe0
+05cb  SHL
+05cc  DUP2
+05cd  MSTORE
+05ce  PUSH1 0x20
+05d0  DUP2
+05d1  PUSH1 0x04
+05d3  ADD
+05d4  MSTORE
+05d5  PUSH1 0x29
+05d7  DUP2
+05d8  PUSH1 0x24
+05da  ADD
+05db  MSTORE
+05dc  PUSH32 0x52756e74696d65206572726f723a20596f75206a75737420676f742072656b74
+05fd  DUP2
+05fe  PUSH1 0x44
+0600  ADD
+0601  MSTORE
+0602  PUSH32 0x206279207a65726f210000000000000000000000000000000000000000000000
+0623  DUP2
+0624  PUSH1 0x64
+0626  ADD
+0627  MSTORE
+0628  PUSH1 0x84
+062a  SWAP1
+062b  REVERT
+062c  JUMPDEST
+062d  PUSH1 0x60
+062f  MSTORE
+0630  PUSH1 0x40
+0632  MSTORE
+0633  PUSH1 0x60
+0635  MLOAD
+0636  PUSH1 0x40
+0638  MLOAD
+0639  SDIV
+063a  PUSH1 0x60
+063c  MLOAD
+063d  PUSH1 0x40
+063f  MLOAD
+0640  SMOD
+0641  ISZERO
+0642  ISZERO
+0643  PUSH0
+0644  PUSH1 0x40
+0646  MLOAD
+0647  SLT
+0648  PUSH0
+0649  PUSH1 0x60
+064b  MLOAD
+064c  SLT
+064d  XOR
+064e  AND
+064f  SWAP1
+0650  SUB
+0651  DUP1
+0652  PUSH1 0x20
+0654  MLOAD
+0655  PUSH1 0x20
+0657  ADD
+0658  MSTORE
+0659  POP
+065a  PUSH2 0x0675
+065d  JUMP
+065e  JUMPDEST
+065f  PUSH1 0x03
+0661  PUSH1 0x20
+0663  MLOAD
+0664  PUSH1 0x20
+0666  ADD
+0667  MLOAD
+0668  MUL
+0669  PUSH1 0x01
+066b  ADD
+066c  DUP1
+066d  PUSH1 0x20
+066f  MLOAD
+0670  PUSH1 0x20
+0672  ADD
+0673  MSTORE
+0674  POP
+0675  JUMPDEST
+0676  PUSH1 0x20
+0678  MLOAD
+0679  PUSH1 0x40
+067b  ADD
+067c  MLOAD
+067d  PUSH1 0x01
+067f  ADD
+0680  DUP1
+0681  PUSH1 0x20
+0683  MLOAD
+0684  PUSH1 0x40
+0686  ADD
+0687  MSTORE
+0688  POP
+0689  PUSH2 0x058f
+068c  JUMP
+068d  JUMPDEST
+068e  PUSH1 0x20
+0690  MLOAD
+0691  PUSH1 0x40
+0693  ADD
+0694  MLOAD
+0695  PUSH1 0x20
+0697  MLOAD
+0698  PUSH0
+0699  MSTORE
+069a  PUSH1 0x20
+069c  MLOAD
+069d  MLOAD
+069e  PUSH1 0x20
+06a0  MSTORE
+06a1  SWAP1
+06a2  JUMP
@@ -0,0 +1,220 @@
+// c0406226  run()
+// 241c5912  check(bool)
+
+creation:
+0000  PUSH2 0x01c3
+0003  DUP1
+0004  PUSH1 0x0a
+0006  PUSH0
+0007  CODECOPY
+0008  PUSH0
+0009  RETURN
+
+runtime:
+0000  PUSH1 0x80
+0002  PUSH0
+0003  MSTORE
+0004  PUSH1 0x04
+0006  CALLDATASIZE
+0007  LT
+0008  PUSH2 0x0027
+000b  JUMPI
+000c  PUSH0
+000d  CALLDATALOAD
+000e  PUSH1 0xe0
+0010  SHR
+0011  DUP1
+0012  PUSH4 0xc0406226
+0017  EQ
+0018  PUSH2 0x002b
+001b  JUMPI
+001c  DUP1
+001d  PUSH4 0x241c5912
+0022  EQ
+0023  PUSH2 0x0041
+0026  JUMPI
+0027  JUMPDEST
+0028  PUSH0
+0029  PUSH0
+002a  REVERT
+002b  JUMPDEST
+002c  POP
+002d  CALLDATASIZE
+002e  PUSH1 0x04
+0030  GT
+0031  PUSH2 0x0027
+0034  JUMPI
+0035  PUSH2 0x003c
+0038  PUSH2 0x0060
+003b  JUMP
+003c  JUMPDEST
+003d  POP
+003e  PUSH0
+003f  PUSH0
+0040  RETURN
+0041  JUMPDEST
+0042  POP
+0043  CALLDATASIZE
+0044  PUSH1 0x24
+0046  GT
+0047  PUSH2 0x0027
+004a  JUMPI
+004b  PUSH2 0x0057
+004e  PUSH1 0x04
+0050  CALLDATALOAD
+0051  ISZERO
+0052  ISZERO
+0053  PUSH2 0x0187
+0056  JUMP
+0057  JUMPDEST
+0058  PUSH1 0x40
+005a  MSTORE
+005b  PUSH1 0x20
+005d  PUSH1 0x40
+005f  RETURN
+0060  JUMPDEST
+0061  PUSH0
+0062  MLOAD
+0063  PUSH1 0x20
+0065  MLOAD
+0066  DUP2
+0067  MSTORE
+0068  DUP1
+0069  PUSH1 0x20
+006b  MSTORE
+006c  PUSH1 0x20
+006e  ADD
+006f  PUSH0
+0070  MSTORE
+0071  PUSH2 0x007a
+0074  PUSH1 0x01
+0076  PUSH2 0x0187
+0079  JUMP
+007a  JUMPDEST
+007b  PUSH0
+007c  MLOAD
+007d  MSTORE
+007e  PUSH32 0xe4ee9fcafd07f338ab74589c44ec7c63d31664f86e2c24a7bef2beaf5bb4e720
+009f  PUSH1 0x20
+00a1  PUSH0
+00a2  MLOAD
+00a3  LOG1
+00a4  PUSH2 0x00ac
+00a7  PUSH0
+00a8  PUSH2 0x0187
+00ab  JUMP
+00ac  JUMPDEST
+00ad  PUSH0
+00ae  MLOAD
+00af  MSTORE
+00b0  PUSH32 0xe4ee9fcafd07f338ab74589c44ec7c63d31664f86e2c24a7bef2beaf5bb4e720
+00d1  PUSH1 0x20
+00d3  PUSH0
+00d4  MLOAD
+00d5  LOG1
+00d6  PUSH1 0x01
+00d8  PUSH1 0x02
+00da  SGT
+00db  PUSH0
+00dc  SSTORE
+00dd  PUSH0
+00de  SLOAD
+00df  PUSH0
+00e0  MLOAD
+00e1  MSTORE
+00e2  PUSH32 0xc9b5f89833845a18161f53409688987899c231d45b3f39cf99c2945a862dba89
+0103  PUSH1 0x20
+0105  PUSH0
+0106  MLOAD
+0107  LOG1
+0108  PUSH1 0x0a
+010a  PUSH0
+010b  SLOAD
+010c  POP
+010d  POP
+010e  PUSH0
+010f  MLOAD
+0110  PUSH4 0x08c379a0
+0115  PUSH1 0xe0
+0117  SHL
+0118  DUP2
+0119  MSTORE
+011a  PUSH1 0x20
+011c  DUP2
+011d  PUSH1 0x04
+011f  ADD
+0120  MSTORE
+0121  PUSH1 0x2b
+0123  DUP2
+0124  PUSH1 0x24
+0126  ADD
+0127  MSTORE
+0128  PUSH32 0x54797065206572726f723a20457870656374656420766962652c206275742066
+0149  DUP2
+014a  PUSH1 0x44
+014c  ADD
+014d  MSTORE
+014e  PUSH32 0x6f756e64207369676e616c000000000000000000000000000000000000000000
+016f  DUP2
+0170  PUSH1 0x64
+0172  ADD
+0173  MSTORE
+0174  PUSH1 0x84
+0176  SWAP1
+0177  REVERT
+0178  PUSH0
+0179  PUSH1 0x20
+017b  MLOAD
+017c  PUSH0
+017d  MSTORE
+017e  PUSH1 0x20
+0180  MLOAD
+0181  MLOAD
+0182  PUSH1 0x20
+0184  MSTORE
+0185  SWAP1
+0186  JUMP
+0187  JUMPDEST
+0188  PUSH0
+0189  MLOAD
+018a  PUSH1 0x20
+018c  MLOAD
+018d  DUP2
+018e  MSTORE
+018f  DUP1
+0190  PUSH1 0x20
+0192  MSTORE
+0193  PUSH1 0x40
+0195  ADD
+0196  PUSH0
+0197  MSTORE
+0198  PUSH1 0x20
+019a  MLOAD
+019b  PUSH1 0x20
+019d  ADD
+019e  MSTORE
+019f  PUSH1 0x20
+01a1  MLOAD
+01a2  PUSH1 0x20
+01a4  ADD
+01a5  MLOAD
+01a6  ISZERO
+01a7  PUSH2 0x01b1
+01aa  JUMPI
+01ab  PUSH1 0x01
+01ad  PUSH2 0x01b4
+01b0  JUMP
+01b1  JUMPDEST
+01b2  PUSH1 0x02
+01b4  JUMPDEST
+01b5  PUSH1 0x20
+01b7  MLOAD
+01b8  PUSH0
+01b9  MSTORE
+01ba  PUSH1 0x20
+01bc  MLOAD
+01bd  MLOAD
+01be  PUSH1 0x20
+01c0  MSTORE
+01c1  SWAP1
+01c2  JUMP
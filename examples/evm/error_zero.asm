@@ -0,0 +1,283 @@
+// c0406226  run()
+
+creation:
+0000  PUSH2 0x0252
+0003  DUP1
+0004  PUSH1 0x0a
+0006  PUSH0
+0007  CODECOPY
+0008  PUSH0
+0009  RETURN
+
+runtime:
+0000  PUSH1 0x80
+0002  PUSH0
+0003  MSTORE
+0004  PUSH1 0x04
+0006  CALLDATASIZE
+0007  LT
+0008  PUSH2 0x001c
+000b  JUMPI
+000c  PUSH0
+000d  CALLDATALOAD
+000e  PUSH1 0xe0
+0010  SHR
+0011  DUP1
+0012  PUSH4 0xc0406226
+0017  EQ
+0018  PUSH2 0x0020
+001b  JUMPI
+001c  JUMPDEST
+001d  PUSH0
+001e  PUSH0
+001f  REVERT
+0020  JUMPDEST
+0021  POP
+0022  CALLDATASIZE
+0023  PUSH1 0x04
+0025  GT
+0026  PUSH2 0x001c
+0029  JUMPI
+002a  PUSH2 0x0031
+002d  PUSH2 0x0036
+0030  JUMP
+0031  JUMPDEST
+0032  POP
+0033  PUSH0
+0034  PUSH0
+0035  RETURN
+0036  JUMPDEST
+0037  PUSH0
+0038  MLOAD
+0039  PUSH1 0x20
+003b  MLOAD
+003c  DUP2
+003d  MSTORE
+003e  DUP1
+003f  PUSH1 0x20
+0041  MSTORE
+0042  PUSH1 0xa0
+0044  ADD
+0045  PUSH0
+0046  MSTORE
+0047  PUSH1 0x0a
+0049  PUSH0
+004a  SSTORE
+004b  PUSH1 0x03
+004d  PUSH1 0x20
+004f  MLOAD
+0050  PUSH1 0x20
+0052  ADD
+0053  MSTORE
+0054  PUSH32 0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff
+0075  PUSH1 0x20
+0077  MLOAD
+0078  PUSH1 0x40
+007a  ADD
+007b  MSTORE
+007c  PUSH32 0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff
+009d  PUSH1 0x20
+009f  MLOAD
+00a0  PUSH1 0x60
+00a2  ADD
+00a3  MSTORE
+00a4  PUSH1 0x20
+00a6  MLOAD
+00a7  PUSH1 0x60
+00a9  ADD
+00aa  MLOAD
+00ab  PUSH2 0x0119
+00ae  JUMPI
+00af  PUSH0
+00b0  MLOAD
+00b1  PUSH4 0x08c379a0
+00b6  PUSH1 0xe0
+00b8  SHL
+00b9  DUP2
+00ba  MSTORE
+00bb  PUSH1 0x20
+00bd  DUP2
+00be  PUSH1 0x04
+00c0  ADD
+00c1  MSTORE
+00c2  PUSH1 0x27
+00c4  DUP2
+00c5  PUSH1 0x24
+00c7  ADD
+00c8  MSTORE
+00c9  PUSH32 0x52756e74696d65206572726f723a2052616e676520737465702063616e277420
+00ea  DUP2
+00eb  PUSH1 0x44
+00ed  ADD
+00ee  MSTORE
+00ef  PUSH32 0x6265207a65726f00000000000000000000000000000000000000000000000000
+0110  DUP2
+0111  PUSH1 0x64
+0113  ADD
+0114  MSTORE
+0115  PUSH1 0x84
+0117  SWAP1
+0118  REVERT
+0119  JUMPDEST
+011a  JUMPDEST
+011b  PUSH1 0x20
+011d  MLOAD
+011e  PUSH1 0x40
+0120  ADD
+0121  MLOAD
+0122  PUSH1 0x20
+0124  MLOAD
+0125  PUSH1 0x20
+0127  ADD
+0128  MLOAD
+0129  SLT
+012a  PUSH0
+012b  PUSH1 0x20
+012d  MLOAD
+012e  PUSH1 0x60
+0130  ADD
+0131  MLOAD
+0132  SGT
+0133  AND
+0134  PUSH1 0x20
+0136  MLOAD
+0137  PUSH1 0x40
+0139  ADD
+013a  MLOAD
+013b  PUSH1 0x20
+013d  MLOAD
+013e  PUSH1 0x20
+0140  ADD
+0141  MLOAD
+0142  SGT
+0143  PUSH0
+0144  PUSH1 0x20
+0146  MLOAD
+0147  PUSH1 0x60
+0149  ADD
+014a  MLOAD
+014b  SLT
+014c  AND
+014d  OR
+014e  ISZERO
+014f  PUSH2 0x0242
+0152  JUMPI
+0153  PUSH1 0x20
+0155  MLOAD
+0156  PUSH1 0x20
+0158  ADD
+0159  MLOAD
+015a  PUSH1 0x20
+015c  MLOAD
+015d  PUSH1 0x80
+015f  ADD
+0160  MSTORE
+0161  PUSH0
+0162  SLOAD
+0163  PUSH1 0x20
+0165  MLOAD
+0166  PUSH1 0x80
+0168  ADD
+0169  MLOAD
+016a  DUP1
+016b  PUSH2 0x01d9
+016e  JUMPI
+016f  PUSH0
+0170  MLOAD
+0171  PUSH4 0x08c379a0
+0176  PUSH1 0xe0
+0178  SHL
+0179  DUP2
+017a  MSTORE
+017b  PUSH1 0x20
+017d  DUP2
+017e  PUSH1 0x04
+0180  ADD
+0181  MSTORE
+0182  PUSH1 0x29
+0184  DUP2
+0185  PUSH1 0x24
+0187  ADD
+0188  MSTORE
+0189  PUSH32 0x52756e74696d65206572726f723a20596f75206a75737420676f742072656b74
+01aa  DUP2
+01ab  PUSH1 0x44
+01ad  ADD
+01ae  MSTORE
+01af  PUSH32 0x206279207a65726f210000000000000000000000000000000000000000000000
+01d0  DUP2
+01d1  PUSH1 0x64
+01d3  ADD
+01d4  MSTORE
+01d5  PUSH1 0x84
+01d7  SWAP1
+01d8  REVERT
+01d9  JUMPDEST
+01da  PUSH1 0x60
+01dc  MSTORE
+01dd  PUSH1 0x40
+01df  MSTORE
+01e0  PUSH1 0x60
+01e2  MLOAD
+01e3  PUSH1 0x40
+01e5  MLOAD
+01e6  SDIV
+01e7  PUSH1 0x60
+01e9  MLOAD
+01ea  PUSH1 0x40
+01ec  MLOAD
+01ed  SMOD
+01ee  ISZERO
+01ef  ISZERO
+01f0  PUSH0
+01f1  PUSH1 0x40
+01f3  MLOAD
+01f4  SLT
+01f5  PUSH0
+01f6  PUSH1 0x60
+01f8  MLOAD
+01f9  SLT
+01fa  XOR
+01fb  AND
+01fc  SWAP1
+01fd  SUB
+01fe  PUSH0
+01ff  MLOAD
+0200  MSTORE
+0201  PUSH32 0xe4ee9fcafd07f338ab74589c44ec7c63d31664f86e2c24a7bef2beaf5bb4e720
+0222  PUSH1 0x20
+0224  PUSH0
+0225  MLOAD
+0226  LOG1
+0227  JUMPDEST
+0228  PUSH1 0x20
+022a  MLOAD
+022b  PUSH1 0x60
+022d  ADD
+022e  MLOAD
+022f  PUSH1 0x20
+0231  MLOAD
+0232  PUSH1 0x20
+0234  ADD
+0235  MLOAD
+0236  ADD
+0237  PUSH1 0x20
+0239  MLOAD
+023a  PUSH1 0x20
+023c  ADD
+023d  MSTORE
+023e  PUSH2 0x011a
+0241  JUMP
+0242  JUMPDEST
+0243  PUSH0
+0244  PUSH1 0x20
+0246  MLOAD
+0247  PUSH0
+0248  MSTORE
+0249  PUSH1 0x20
+024b  MLOAD
+024c  MLOAD
+024d  PUSH1 0x20
+024f  MSTORE
+0250  SWAP1
+0251  JUMP
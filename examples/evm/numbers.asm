@@ -0,0 +1,722 @@
+// c0406226  run()
+
+creation:
+0000  PUSH2 0x0837
+0003  DUP1
+0004  PUSH1 0x0a
+0006  PUSH0
+0007  CODECOPY
+0008  PUSH0
+0009  RETURN
+
+runtime:
+0000  PUSH1 0x80
+0002  PUSH0
+0003  MSTORE
+0004  PUSH1 0x04
+0006  CALLDATASIZE
+0007  LT
+0008  PUSH2 0x001c
+000b  JUMPI
+000c  PUSH0
+000d  CALLDATALOAD
+000e  PUSH1 0xe0
+0010  SHR
+0011  DUP1
+0012  PUSH4 0xc0406226
+0017  EQ
+0018  PUSH2 0x0020
+001b  JUMPI
+001c  JUMPDEST
+001d  PUSH0
+001e  PUSH0
+001f  REVERT
+0020  JUMPDEST
+0021  POP
+0022  CALLDATASIZE
+0023  PUSH1 0x04
+0025  GT
+0026  PUSH2 0x001c
+0029  JUMPI
+002a  PUSH2 0x0031
+002d  PUSH2 0x0036
+0030  JUMP
+0031  JUMPDEST
+0032  POP
+0033  PUSH0
+0034  PUSH0
+0035  RETURN
+0036  JUMPDEST
+0037  PUSH0
+0038  MLOAD
+0039  PUSH1 0x20
+003b  MLOAD
+003c  DUP2
+003d  MSTORE
+003e  DUP1
+003f  PUSH1 0x20
+0041  MSTORE
+0042  PUSH1 0x20
+0044  ADD
+0045  PUSH0
+0046  MSTORE
+0047  PUSH1 0x07
+0049  PUSH0
+004a  SSTORE
+004b  PUSH1 0x02
+004d  PUSH1 0x01
+004f  SSTORE
+0050  PUSH0
+0051  SLOAD
+0052  PUSH1 0x01
+0054  SLOAD
+0055  PUSH1 0x03
+0057  MUL
+0058  ADD
+0059  PUSH0
+005a  MLOAD
+005b  MSTORE
+005c  PUSH32 0xe4ee9fcafd07f338ab74589c44ec7c63d31664f86e2c24a7bef2beaf5bb4e720
+007d  PUSH1 0x20
+007f  PUSH0
+0080  MLOAD
+0081  LOG1
+0082  PUSH0
+0083  SLOAD
+0084  PUSH1 0x01
+0086  SLOAD
+0087  PUSH1 0x05
+0089  MUL
+008a  SWAP1
+008b  SUB
+008c  PUSH0
+008d  MLOAD
+008e  MSTORE
+008f  PUSH32 0xe4ee9fcafd07f338ab74589c44ec7c63d31664f86e2c24a7bef2beaf5bb4e720
+00b0  PUSH1 0x20
+00b2  PUSH0
+00b3  MLOAD
+00b4  LOG1
+00b5  PUSH0
+00b6  SLOAD
+00b7  PUSH1 0x01
+00b9  SLOAD
+00ba  DUP1
+00bb  PUSH2 0x0129
+00be  JUMPI
+00bf  PUSH0
+00c0  MLOAD
+00c1  PUSH4 0x08c379a0
+00c6  PUSH1 0xe0
+00c8  SHL
+00c9  DUP2
+00ca  MSTORE
+00cb  PUSH1 0x20
+00cd  DUP2
+00ce  PUSH1 0x04
+00d0  ADD
+00d1  MSTORE
+00d2  PUSH1 0x29
+00d4  DUP2
+00d5  PUSH1 0x24
+00d7  ADD
+00d8  MSTORE
+00d9  PUSH32 0x52756e74696d65206572726f723a20596f75206a75737420676f742072656b74
+00fa  DUP2
+00fb  PUSH1 0x44
+00fd  ADD
+00fe  MSTORE
+00ff  PUSH32 0x206279207a65726f210000000000000000000000000000000000000000000000
+0120  DUP2
+0121  PUSH1 0x64
+0123  ADD
+0124  MSTORE
+0125  PUSH1 0x84
+0127  SWAP1
+0128  REVERT
+0129  JUMPDEST
+012a  PUSH1 0x60
+012c  MSTORE
+012d  PUSH1 0x40
+012f  MSTORE
+0130  PUSH1 0x60
+0132  MLOAD
+0133  PUSH1 0x40
+0135  MLOAD
+0136  SMOD
+0137  DUP1
+0138  ISZERO
+0139  ISZERO
+013a  PUSH0
+013b  DUP3
+013c  SLT
+013d  PUSH0
+013e  PUSH1 0x60
+0140  MLOAD
+0141  SLT
+0142  XOR
+0143  AND
+0144  PUSH1 0x60
+0146  MLOAD
+0147  MUL
+0148  ADD
+0149  PUSH0
+014a  MLOAD
+014b  MSTORE
+014c  PUSH32 0xe4ee9fcafd07f338ab74589c44ec7c63d31664f86e2c24a7bef2beaf5bb4e720
+016d  PUSH1 0x20
+016f  PUSH0
+0170  MLOAD
+0171  LOG1
+0172  PUSH32 0xfffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff9
+0193  PUSH1 0x03
+0195  DUP1
+0196  PUSH2 0x0204
+0199  JUMPI
+019a  PUSH0
+019b  MLOAD
+019c  PUSH4 0x08c379a0
+01a1  PUSH1 0xe0
+01a3  SHL
+01a4  DUP2
+01a5  MSTORE
+01a6  PUSH1 0x20
+01a8  DUP2
+01a9  PUSH1 0x04
+01ab  ADD
+01ac  MSTORE
+01ad  PUSH1 0x29
+01af  DUP2
+01b0  PUSH1 0x24
+01b2  ADD
+01b3  MSTORE
+01b4  PUSH32 0x52756e74696d65206572726f723a20596f75206a75737420676f742072656b74
+01d5  DUP2
+01d6  PUSH1 0x44
+01d8  ADD
+01d9  MSTORE
+01da  PUSH32 0x206279207a65726f210000000000000000000000000000000000000000000000
+01fb  DUP2
+01fc  PUSH1 0x64
+01fe  ADD
+01ff  MSTORE
+0200  PUSH1 0x84
+0202  SWAP1
+0203  REVERT
+0204  JUMPDEST
+0205  PUSH1 0x60
+0207  MSTORE
+0208  PUSH1 0x40
+020a  MSTORE
+020b  PUSH1 0x60
+020d  MLOAD
+020e  PUSH1 0x40
+0210  MLOAD
+0211  SMOD
+0212  DUP1
+0213  ISZERO
+0214  ISZERO
+0215  PUSH0
+0216  DUP3
+0217  SLT
+0218  PUSH0
+0219  PUSH1 0x60
+021b  MLOAD
+021c  SLT
+021d  XOR
+021e  AND
+021f  PUSH1 0x60
+0221  MLOAD
+0222  MUL
+0223  ADD
+0224  PUSH0
+0225  MLOAD
+0226  MSTORE
+0227  PUSH32 0xe4ee9fcafd07f338ab74589c44ec7c63d31664f86e2c24a7bef2beaf5bb4e720
+0248  PUSH1 0x20
+024a  PUSH0
+024b  MLOAD
+024c  LOG1
+024d  PUSH1 0x07
+024f  PUSH32 0xfffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffd
+0270  DUP1
+0271  PUSH2 0x02df
+0274  JUMPI
+0275  PUSH0
+0276  MLOAD
+0277  PUSH4 0x08c379a0
+027c  PUSH1 0xe0
+027e  SHL
+027f  DUP2
+0280  MSTORE
+0281  PUSH1 0x20
+0283  DUP2
+0284  PUSH1 0x04
+0286  ADD
+0287  MSTORE
+0288  PUSH1 0x29
+028a  DUP2
+028b  PUSH1 0x24
+028d  ADD
+028e  MSTORE
+028f  PUSH32 0x52756e74696d65206572726f723a20596f75206a75737420676f742072656b74
+02b0  DUP2
+02b1  PUSH1 0x44
+02b3  ADD
+02b4  MSTORE
+02b5  PUSH32 0x206279207a65726f210000000000000000000000000000000000000000000000
+02d6  DUP2
+02d7  PUSH1 0x64
+02d9  ADD
+02da  MSTORE
+02db  PUSH1 0x84
+02dd  SWAP1
+02de  REVERT
+02df  JUMPDEST
+02e0  PUSH1 0x60
+02e2  MSTORE
+02e3  PUSH1 0x40
+02e5  MSTORE
+02e6  PUSH1 0x60
+02e8  MLOAD
+02e9  PUSH1 0x40
+02eb  MLOAD
+02ec  SMOD
+02ed  DUP1
+02ee  ISZERO
+02ef  ISZERO
+02f0  PUSH0
+02f1  DUP3
+02f2  SLT
+02f3  PUSH0
+02f4  PUSH1 0x60
+02f6  MLOAD
+02f7  SLT
+02f8  XOR
+02f9  AND
+02fa  PUSH1 0x60
+02fc  MLOAD
+02fd  MUL
+02fe  ADD
+02ff  PUSH0
+0300  MLOAD
+0301  MSTORE
+0302  PUSH32 0xe4ee9fcafd07f338ab74589c44ec7c63d31664f86e2c24a7bef2beaf5bb4e720
+0323  PUSH1 0x20
+0325  PUSH0
+0326  MLOAD
+0327  LOG1
+0328  PUSH32 0xfffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff9
+0349  PUSH1 0x02
+034b  DUP1
+034c  PUSH2 0x03ba
+034f  JUMPI
+0350  PUSH0
+0351  MLOAD
+0352  PUSH4 0x08c379a0
+0357  PUSH1 0xe0
+0359  SHL
+035a  DUP2
+035b  MSTORE
+035c  PUSH1 0x20
+035e  DUP2
+035f  PUSH1 0x04
+0361  ADD
+0362  MSTORE
+0363  PUSH1 0x29
+0365  DUP2
+0366  PUSH1 0x24
+0368  ADD
+0369  MSTORE
+036a  PUSH32 0x52756e74696d65206572726f723a20596f75206a75737420676f742072656b74
+038b  DUP2
+038c  PUSH1 0x44
+038e  ADD
+038f  MSTORE
+0390  PUSH32 0x206279207a65726f210000000000000000000000000000000000000000000000
+03b1  DUP2
+03b2  PUSH1 0x64
+03b4  ADD
+03b5  MSTORE
+03b6  PUSH1 0x84
+03b8  SWAP1
+03b9  REVERT
+03ba  JUMPDEST
+03bb  PUSH1 0x60
+03bd  MSTORE
+03be  PUSH1 0x40
+03c0  MSTORE
+03c1  PUSH1 0x60
+03c3  MLOAD
+03c4  PUSH1 0x40
+03c6  MLOAD
+03c7  SDIV
+03c8  PUSH1 0x60
+03ca  MLOAD
+03cb  PUSH1 0x40
+03cd  MLOAD
+03ce  SMOD
+03cf  ISZERO
+03d0  ISZERO
+03d1  PUSH0
+03d2  PUSH1 0x40
+03d4  MLOAD
+03d5  SLT
+03d6  PUSH0
+03d7  PUSH1 0x60
+03d9  MLOAD
+03da  SLT
+03db  XOR
+03dc  AND
+03dd  SWAP1
+03de  SUB
+03df  PUSH0
+03e0  MLOAD
+03e1  MSTORE
+03e2  PUSH32 0xe4ee9fcafd07f338ab74589c44ec7c63d31664f86e2c24a7bef2beaf5bb4e720
+0403  PUSH1 0x20
+0405  PUSH0
+0406  MLOAD
+0407  LOG1
+0408  PUSH1 0x07
+040a  PUSH32 0xfffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffe
+042b  DUP1
+042c  PUSH2 0x049a
+042f  JUMPI
+0430  PUSH0
+0431  MLOAD
+0432  PUSH4 0x08c379a0
+0437  PUSH1 0xe0
+0439  SHL
+043a  DUP2
+043b  MSTORE
+043c  PUSH1 0x20
+043e  DUP2
+043f  PUSH1 0x04
+0441  ADD
+0442  MSTORE
+0443  PUSH1 0x29
+0445  DUP2
+0446  PUSH1 0x24
+0448  ADD
+0449  MSTORE
+044a  PUSH32 0x52756e74696d65206572726f723a20596f75206a75737420676f742072656b74
+046b  DUP2
+046c  PUSH1 0x44
+046e  ADD
+046f  MSTORE
+0470  PUSH32 0x206279207a65726f210000000000000000000000000000000000000000000000
+0491  DUP2
+0492  PUSH1 0x64
+0494  ADD
+0495  MSTORE
+0496  PUSH1 0x84
+0498  SWAP1
+0499  REVERT
+049a  JUMPDEST
+049b  PUSH1 0x60
+049d  MSTORE
+049e  PUSH1 0x40
+04a0  MSTORE
+04a1  PUSH1 0x60
+04a3  MLOAD
+04a4  PUSH1 0x40
+04a6  MLOAD
+04a7  SDIV
+04a8  PUSH1 0x60
+04aa  MLOAD
+04ab  PUSH1 0x40
+04ad  MLOAD
+04ae  SMOD
+04af  ISZERO
+04b0  ISZERO
+04b1  PUSH0
+04b2  PUSH1 0x40
+04b4  MLOAD
+04b5  SLT
+04b6  PUSH0
+04b7  PUSH1 0x60
+04b9  MLOAD
+04ba  SLT
+04bb  XOR
+04bc  AND
+04bd  SWAP1
+04be  SUB
+04bf  PUSH0
+04c0  MLOAD
+04c1  MSTORE
+04c2  PUSH32 0xe4ee9fcafd07f338ab74589c44ec7c63d31664f86e2c24a7bef2beaf5bb4e720
+04e3  PUSH1 0x20
+04e5  PUSH0
+04e6  MLOAD
+04e7  LOG1
+04e8  PUSH32 0xfffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff8
+0509  PUSH1 0x02
+050b  DUP1
+050c  PUSH2 0x057a
+050f  JUMPI
+0510  PUSH0
+0511  MLOAD
+0512  PUSH4 0x08c379a0
+0517  PUSH1 0xe0
+0519  SHL
+051a  DUP2
+051b  MSTORE
+051c  PUSH1 0x20
+051e  DUP2
+051f  PUSH1 0x04
+0521  ADD
+0522  MSTORE
+0523  PUSH1 0x29
+0525  DUP2
+0526  PUSH1 0x24
+0528  ADD
+0529  MSTORE
+052a  PUSH32 0x52756e74696d65206572726f723a20596f75206a75737420676f742072656b74
+054b  DUP2
+054c  PUSH1 0x44
+054e  ADD
+054f  MSTORE
+0550  PUSH32 0x206279207a65726f210000000000000000000000000000000000000000000000
+0571  DUP2
+0572  PUSH1 0x64
+0574  ADD
+0575  MSTORE
+0576  PUSH1 0x84
+0578  SWAP1
+0579  REVERT
+057a  JUMPDEST
+057b  PUSH1 0x60
+057d  MSTORE
+057e  PUSH1 0x40
+0580  MSTORE
+0581  PUSH1 0x60
+0583  MLOAD
+0584  PUSH1 0x40
+0586  MLOAD
+0587  SDIV
+0588  PUSH1 0x60
+058a  MLOAD
+058b  PUSH1 0x40
+058d  MLOAD
+058e  SMOD
+058f  ISZERO
+0590  ISZERO
+0591  PUSH0
+0592  PUSH1 0x40
+0594  MLOAD
+0595  SLT
+0596  PUSH0
+0597  PUSH1 0x60
+0599  MLOAD
+059a  SLT
+059b  XOR
+059c  AND
+059d  SWAP1
+059e  SUB
+059f  PUSH0
+05a0  MLOAD
+05a1  MSTORE
+05a2  PUSH32 0xe4ee9fcafd07f338ab74589c44ec7c63d31664f86e2c24a7bef2beaf5bb4e720
+05c3  PUSH1 0x20
+05c5  PUSH0
+05c6  MLOAD
+05c7  LOG1
+05c8  PUSH0
+05c9  SLOAD
+05ca  PUSH1 0x03
+05cc  AND
+05cd  PUSH0
+05ce  MLOAD
+05cf  MSTORE
+05d0  PUSH32 0xe4ee9fcafd07f338ab74589c44ec7c63d31664f86e2c24a7bef2beaf5bb4e720
+05f1  PUSH1 0x20
+05f3  PUSH0
+05f4  MLOAD
+05f5  LOG1
+05f6  PUSH0
+05f7  SLOAD
+05f8  PUSH1 0x08
+05fa  OR
+05fb  PUSH0
+05fc  MLOAD
+05fd  MSTORE
+05fe  PUSH32 0xe4ee9fcafd07f338ab74589c44ec7c63d31664f86e2c24a7bef2beaf5bb4e720
+061f  PUSH1 0x20
+0621  PUSH0
+0622  MLOAD
+0623  LOG1
+0624  PUSH0
+0625  SLOAD
+0626  PUSH1 0x01
+0628  SLOAD
+0629  XOR
+062a  PUSH0
+062b  MLOAD
+062c  MSTORE
+062d  PUSH32 0xe4ee9fcafd07f338ab74589c44ec7c63d31664f86e2c24a7bef2beaf5bb4e720
+064e  PUSH1 0x20
+0650  PUSH0
+0651  MLOAD
+0652  LOG1
+0653  PUSH0
+0654  SLOAD
+0655  NOT
+0656  PUSH0
+0657  MLOAD
+0658  MSTORE
+0659  PUSH32 0xe4ee9fcafd07f338ab74589c44ec7c63d31664f86e2c24a7bef2beaf5bb4e720
+067a  PUSH1 0x20
+067c  PUSH0
+067d  MLOAD
+067e  LOG1
+067f  PUSH0
+0680  SLOAD
+0681  PUSH1 0x01
+0683  SLOAD
+0684  SGT
+0685  PUSH0
+0686  MLOAD
+0687  MSTORE
+0688  PUSH32 0xc9b5f89833845a18161f53409688987899c231d45b3f39cf99c2945a862dba89
+06a9  PUSH1 0x20
+06ab  PUSH0
+06ac  MLOAD
+06ad  LOG1
+06ae  PUSH0
+06af  SLOAD
+06b0  PUSH1 0x07
+06b2  SGT
+06b3  ISZERO
+06b4  PUSH0
+06b5  MLOAD
+06b6  MSTORE
+06b7  PUSH32 0xc9b5f89833845a18161f53409688987899c231d45b3f39cf99c2945a862dba89
+06d8  PUSH1 0x20
+06da  PUSH0
+06db  MLOAD
+06dc  LOG1
+06dd  PUSH0
+06de  SLOAD
+06df  PUSH1 0x07
+06e1  EQ
+06e2  PUSH0
+06e3  MLOAD
+06e4  MSTORE
+06e5  PUSH32 0xc9b5f89833845a18161f53409688987899c231d45b3f39cf99c2945a862dba89
+0706  PUSH1 0x20
+0708  PUSH0
+0709  MLOAD
+070a  LOG1
+070b  PUSH0
+070c  SLOAD
+070d  PUSH1 0x01
+070f  SLOAD
+0710  EQ
+0711  ISZERO
+0712  ISZERO
+0713  PUSH0
+0714  MLOAD
+0715  MSTORE
+0716  PUSH32 0xc9b5f89833845a18161f53409688987899c231d45b3f39cf99c2945a862dba89
+0737  PUSH1 0x20
+0739  PUSH0
+073a  MLOAD
+073b  LOG1
+073c  PUSH0
+073d  SLOAD
+073e  PUSH1 0x01
+0740  SLOAD
+0741  SLT
+0742  ISZERO
+0743  PUSH2 0x074d
+0746  JUMPI
+0747  PUSH0
+0748  SLOAD
+0749  PUSH2 0x0751
+074c  JUMP
+074d  JUMPDEST
+074e  PUSH1 0x01
+0750  SLOAD
+0751  JUMPDEST
+0752  PUSH0
+0753  MLOAD
+0754  MSTORE
+0755  PUSH32 0xe4ee9fcafd07f338ab74589c44ec7c63d31664f86e2c24a7bef2beaf5bb4e720
+0776  PUSH1 0x20
+0778  PUSH0
+0779  MLOAD
+077a  LOG1
+077b  PUSH0
+077c  PUSH1 0x02
+077e  SSTORE
+077f  PUSH1 0x02
+0781  SLOAD
+0782  PUSH0
+0783  SLOAD
+0784  ADD
+0785  DUP1
+0786  PUSH1 0x02
+0788  SSTORE
+0789  POP
+078a  PUSH1 0x02
+078c  SLOAD
+078d  PUSH1 0x03
+078f  MUL
+0790  DUP1
+0791  PUSH1 0x02
+0793  SSTORE
+0794  POP
+0795  PUSH1 0x02
+0797  SLOAD
+0798  PUSH1 0x01
+079a  SWAP1
+079b  SUB
+079c  DUP1
+079d  PUSH1 0x02
+079f  SSTORE
+07a0  POP
+07a1  PUSH1 0x02
+07a3  SLOAD
+07a4  PUSH0
+07a5  MLOAD
+07a6  MSTORE
+07a7  PUSH32 0xe4ee9fcafd07f338ab74589c44ec7c63d31664f86e2c24a7bef2beaf5bb4e720
+07c8  PUSH1 0x20
+07ca  PUSH0
+07cb  MLOAD
+07cc  LOG1
+07cd  PUSH0
+07ce  MLOAD
+07cf  PUSH1 0x20
+07d1  DUP2
+07d2  PUSH0
+07d3  ADD
+07d4  MSTORE
+07d5  PUSH1 0x0f
+07d7  DUP2
+07d8  PUSH1 0x20
+07da  ADD
+07db  MSTORE
+07dc  PUSH32 0x676d2066726f6d207468652045564d0000000000000000000000000000000000
+07fd  DUP2
+07fe  PUSH1 0x40
+0800  ADD
+0801  MSTORE
+0802  PUSH32 0x9328e51774c9ccc9bf9f6c2fffbf4ac2752b584aa63775a8317772016029a90e
+0823  SWAP1
+0824  PUSH1 0x60
+0826  SWAP1
+0827  LOG1
+0828  PUSH0
+0829  PUSH1 0x20
+082b  MLOAD
+082c  PUSH0
+082d  MSTORE
+082e  PUSH1 0x20
+0830  MLOAD
+0831  MLOAD
+0832  PUSH1 0x20
+0834  MSTORE
+0835  SWAP1
+0836  JUMP
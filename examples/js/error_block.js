@@ -0,0 +1,10 @@
+// Generated by gm-language from error_block.gm.
+// Broadcasts go to the function passed to the default export.
+import { chain } from "./gm_runtime.js";
+
+export default chain(($) => {
+  const LIMITS = $.mapping([["daily", 100]]);
+  $.broadcast($.index(LIMITS, "daily"));
+  $.locked("LIMITS", 200);
+  $.broadcast("unreachable");
+});
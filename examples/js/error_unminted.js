@@ -0,0 +1,12 @@
+// Generated by gm-language from error_unminted.gm.
+// Broadcasts go to the function passed to the default export.
+import { chain } from "./gm_runtime.js";
+
+export default chain(($) => {
+  function early() {
+    return later;
+  }
+  $.broadcast("before");
+  $.broadcast(early());
+  let later = 1;
+});
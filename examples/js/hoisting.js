@@ -0,0 +1,73 @@
+// Generated by gm-language from hoisting.gm.
+// Broadcasts go to the function passed to the default export.
+import { chain } from "./gm_runtime.js";
+
+export default chain(($) => {
+  let big, last;
+  let x = 10;
+  function bump(x) {
+    x = $.plus(x, 1);
+    return x;
+  }
+  $.broadcast(bump(1));
+  if ($.gt(x, 5)) {
+    big = "big";
+  } else {
+    big = "small";
+  }
+  $.broadcast($.minted(big, "big"));
+  let round = 0;
+  while ($.lt(round, 3)) {
+    last = round;
+    round = $.plus(round, 1);
+  }
+  $.broadcast($.minted(last, "last"));
+  let items = [1, 2, 3];
+  for (let items$1 of $.items(items)) {
+    $.broadcast(items$1);
+  }
+  {
+    $.broadcast(x);
+    let x$1 = "inner";
+    $.broadcast(x$1);
+  }
+  let ledger = $.mapping([["a", 1]]);
+  let calls = 0;
+  function key() {
+    calls = $.plus(calls, 1);
+    return "a";
+  }
+  $.broadcast((() => {
+    const $key1 = key();
+    ledger = $.update(ledger, [$key1], $.plus, 5);
+    return $.index(ledger, $key1);
+  })());
+  $.broadcast(calls);
+  $.broadcast($.times((x = 3), 2));
+  function twice(f) {
+    return $.plus($.call(f), $.call(f));
+  }
+  $.broadcast(twice($.lambda(() => {
+    return x;
+  })));
+  function even(n) {
+    if ($.eq(n, 0)) {
+      return true;
+    }
+    return odd($.minus(n, 1));
+  }
+  function odd(n) {
+    if ($.eq(n, 0)) {
+      return false;
+    }
+    return even($.minus(n, 1));
+  }
+  $.broadcast(even(10));
+  let pick = $.lambda((a) => {
+    return a;
+  });
+  pick = $.lambda((a) => {
+    return $.times(a, 100);
+  });
+  $.broadcast($.call(pick, 2));
+});
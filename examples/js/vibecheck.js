@@ -0,0 +1,70 @@
+// Generated by gm-language from vibecheck.gm.
+// Broadcasts go to the function passed to the default export.
+import { chain, P } from "./gm_runtime.js";
+
+export default chain(($) => {
+  $.declare("Point", { x: "number", y: "number" });
+  function classify(p) {
+    {
+      let $bound;
+      if ($.match(p, P.struct("Point", { x: P.lit(0), y: P.lit(0) }))) {
+        return "origin";
+      } else if (($bound = $.match(p, P.struct("Point", { x: P.lit(0), y: P.bind("y") })))) {
+        let { y } = $bound;
+        return `on the y axis at ${$.show(y)}`;
+      } else if (($bound = $.match(p, P.struct("Point", { x: P.bind("x"), y: P.lit(0) })))) {
+        let { x } = $bound;
+        return `on the x axis at ${$.show(x)}`;
+      } else {
+        let other = p;
+        let sum = $.plus($.field(other, "x"), $.field(other, "y"));
+        return `off axis, sum ${$.show(sum)}`;
+      }
+    }
+  }
+  $.broadcast(classify($.record("Point", { x: 0, y: 0 })));
+  $.broadcast(classify($.record("Point", { x: 0, y: 7 })));
+  $.broadcast(classify($.record("Point", { x: 3, y: 0 })));
+  $.broadcast(classify($.record("Point", { x: 2, y: 5 })));
+  function size(xs) {
+    {
+      const $subject = $.len(xs);
+      if ($.match($subject, P.lit(0))) {
+        return "none";
+      } else if ($.match($subject, P.or(P.lit(1), P.lit(2), P.lit(3)))) {
+        return "few";
+      } else if ($.match($subject, P.range(4, 100))) {
+        return "some";
+      } else {
+        return "lots";
+      }
+    }
+  }
+  for (let n of $.items([0, 2, 50, 500])) {
+    $.broadcast(size($.range(n)));
+  }
+  let head = (() => {
+    const $subject = [7, 8, 9];
+    let $bound;
+    if (($bound = $.match($subject, P.array([P.bind("a"), P.bind("b")], "rest")))) {
+      let { a, b, rest } = $bound;
+      return $.plus($.plus($.times(a, 10), b), $.len(rest));
+    } else {
+      return 0;
+    }
+  })();
+  $.broadcast(head);
+  let total = 0;
+  for (let v of $.items([1, "two", 3, true])) {
+    if ($.match(v, P.lit(true))) {
+      $.broadcast("signal");
+    } else if ($.match(v, P.lit("two"))) {
+      $.broadcast("skipping two");
+      continue;
+    } else {
+      let n = v;
+      total = $.plus(total, n);
+    }
+  }
+  $.broadcast(total);
+});
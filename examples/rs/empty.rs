@@ -0,0 +1,25 @@
+// Generated by gm-language from empty.gm.
+// Broadcasts go to the function passed to `run`.
+#![allow(unused_mut, unused_variables, unused_assignments, unused_imports, unused_braces, unused_parens)]
+#![allow(unreachable_code, non_snake_case, clippy::all)]
+
+use gm_runtime::{self as gm, Cell, Chain, Step, Value};
+
+pub fn run(emit: impl FnMut(&str, &Value) + 'static) -> Result<(), gm::Rekt> {
+    let ch = &Chain::new(emit);
+    let mut m = gm::mapping(vec![])?;
+    ch.broadcast(&gm::builtin(ch, "len", vec![m.clone()])?);
+    ch.broadcast(&(|| -> Result<Value, gm::Rekt> {
+        let subject = gm::mapping(vec![])?;
+        {
+            return Ok(Value::Number(1.0));
+        }
+    })()?);
+    let mut xs = gm::array(vec![]);
+    ch.broadcast(&gm::builtin(ch, "len", vec![xs.clone()])?);
+    let path = [Step::Key(gm::text("gm"))];
+    let value = Value::Number(1.0);
+    gm::set(&mut m, &path, value)?;
+    ch.broadcast(&gm::index(m.clone(), gm::text("gm"))?);
+    Ok(())
+}
@@ -0,0 +1,23 @@
+// Generated by gm-language from error_shared.gm.
+// Broadcasts go to the function passed to `run`.
+#![allow(unused_mut, unused_variables, unused_assignments, unused_imports, unused_braces, unused_parens)]
+#![allow(unreachable_code, non_snake_case, clippy::all)]
+
+use gm_runtime::{self as gm, Cell, Chain, Step, Value};
+
+pub fn run(emit: impl FnMut(&str, &Value) + 'static) -> Result<(), gm::Rekt> {
+    let ch = &Chain::new(emit);
+    let limit = Cell::new();
+    limit.set(Value::Number(3.0));
+    let mut raise = gm::contract("raise", 0, {
+        let limit = limit.clone();
+        move |ch: &Chain, args: Vec<Value>| {
+            gm::locked("limit", Value::Number(4.0))?;
+            return Ok(Value::Null);
+        }
+    });
+    ch.broadcast(&limit.get("limit")?);
+    ch.call(raise.clone(), vec![])?;
+    ch.broadcast(&gm::text("unreachable"));
+    Ok(())
+}
@@ -0,0 +1,42 @@
+// Generated by gm-language from names.gm.
+// Broadcasts go to the function passed to `run`.
+#![allow(unused_mut, unused_variables, unused_assignments, unused_imports, unused_braces, unused_parens)]
+#![allow(unreachable_code, non_snake_case, clippy::all)]
+
+use gm_runtime::{self as gm, Cell, Chain, Step, Value};
+
+pub fn run(emit: impl FnMut(&str, &Value) + 'static) -> Result<(), gm::Rekt> {
+    let ch = &Chain::new(emit);
+    let mut type_ = Value::Number(1.0);
+    let mut ref_ = Value::Number(2.0);
+    let mut move_ = gm::add(type_.clone(), ref_.clone())?;
+    ch.broadcast(&move_.clone());
+    let mut impl_ = gm::contract("impl", 2, |ch: &Chain, args: Vec<Value>| {
+        let [mut self_, mut value_] = gm::params::<2>(args);
+        return Ok(gm::mul(self_.clone(), value_.clone())?);
+    });
+    ch.broadcast(&ch.call(impl_.clone(), vec![Value::Number(3.0), Value::Number(4.0)])?);
+    let mut path_ = gm::array(vec![Value::Number(1.0), Value::Number(2.0), Value::Number(3.0)]);
+    let path = [Step::Key(Value::Number(0.0))];
+    let value = Value::Number(9.0);
+    gm::set(&mut path_, &path, value)?;
+    ch.broadcast(&path_.clone());
+    for mut mod_ in gm::items(gm::builtin(ch, "range", vec![Value::Number(3.0)])?)? {
+        ch.broadcast(&mod_.clone());
+    }
+    let mut x = gm::text("outer");
+    {
+        let x_1_ = Cell::new();
+        ch.broadcast(&x.clone());
+        x_1_.set(gm::text("inner"));
+        let mut show = gm::contract("show", 0, {
+            let x_1_ = x_1_.clone();
+            move |ch: &Chain, args: Vec<Value>| {
+                return Ok(x_1_.get("x")?);
+            }
+        });
+        ch.broadcast(&ch.call(show.clone(), vec![])?);
+    }
+    ch.broadcast(&x.clone());
+    Ok(())
+}
@@ -0,0 +1,82 @@
+// Generated by gm-language from sharing.gm.
+// Broadcasts go to the function passed to `run`.
+#![allow(unused_mut, unused_variables, unused_assignments, unused_imports, unused_braces, unused_parens)]
+#![allow(unreachable_code, non_snake_case, clippy::all)]
+
+use gm_runtime::{self as gm, Cell, Chain, Step, Value};
+
+pub fn run(emit: impl FnMut(&str, &Value) + 'static) -> Result<(), gm::Rekt> {
+    let ch = &Chain::new(emit);
+    let count = Cell::new();
+    let book = Cell::new();
+    let w = Cell::new();
+    let late = Cell::new();
+    count.set(Value::Number(0.0));
+    let mut tick = gm::contract("tick", 0, {
+        let count = count.clone();
+        move |ch: &Chain, args: Vec<Value>| {
+            count.put("count", gm::add(count.get("count")?, Value::Number(1.0))?)?;
+            return Ok(count.get("count")?);
+        }
+    });
+    ch.call(tick.clone(), vec![])?;
+    ch.call(tick.clone(), vec![])?;
+    ch.broadcast(&count.get("count")?);
+    let mut counter = gm::contract("counter", 1, |ch: &Chain, args: Vec<Value>| {
+        let [mut start] = gm::params::<1>(args);
+        let n = Cell::new();
+        n.set(start.clone());
+        return Ok(gm::contract("anonymous", 0, {
+            let n = n.clone();
+            move |ch: &Chain, args: Vec<Value>| {
+                n.put("n", gm::add(n.get("n")?, Value::Number(1.0))?)?;
+                return Ok(n.get("n")?);
+            }
+        }));
+    });
+    let mut a = ch.call(counter.clone(), vec![Value::Number(10.0)])?;
+    let mut b = ch.call(counter.clone(), vec![Value::Number(100.0)])?;
+    ch.call(a.clone(), vec![])?;
+    ch.broadcast(&ch.call(a.clone(), vec![])?);
+    ch.broadcast(&ch.call(b.clone(), vec![])?);
+    book.set(gm::mapping(vec![(gm::text("alice"), gm::mapping(vec![(gm::text("coins"), Value::Number(5.0))])?)])?);
+    let mut pay = gm::contract("pay", 2, {
+        let book = book.clone();
+        move |ch: &Chain, args: Vec<Value>| {
+            let [mut who, mut amount] = gm::params::<2>(args);
+            let path = [Step::Key(who.clone()), Step::Key(gm::text("coins"))];
+            let value = gm::add(gm::read(&book.get("book")?, &path)?, amount.clone())?;
+            book.update("book", |root| gm::set(root, &path, value.clone()))?;
+            return Ok(value);
+        }
+    });
+    ch.call(pay.clone(), vec![gm::text("alice"), Value::Number(3.0)])?;
+    let path = [Step::Key(gm::text("bob"))];
+    let value = gm::mapping(vec![(gm::text("coins"), Value::Number(0.0))])?;
+    book.update("book", |root| gm::set(root, &path, value))?;
+    ch.call(pay.clone(), vec![gm::text("bob"), Value::Number(2.0)])?;
+    ch.broadcast(&gm::index(gm::index(book.get("book")?, gm::text("alice"))?, gm::text("coins"))?);
+    ch.broadcast(&gm::index(gm::index(book.get("book")?, gm::text("bob"))?, gm::text("coins"))?);
+    ch.declare("Wallet", vec![("owner", Some(gm::Type::Text)), ("coins", Some(gm::Type::Number))]);
+    w.set(ch.record("Wallet", vec![("owner", gm::text("carol")), ("coins", Value::Number(1.0))])?);
+    let mut rename = gm::contract("rename", 1, {
+        let w = w.clone();
+        move |ch: &Chain, args: Vec<Value>| {
+            let [mut name] = gm::params::<1>(args);
+            let value = name.clone();
+            w.update_minted("w", |root| ch.set_field(root, &[], "owner", value.clone()))?;
+            return Ok(value);
+        }
+    });
+    ch.call(rename.clone(), vec![gm::text("dave")])?;
+    ch.broadcast(&gm::field(w.get("w")?, "owner")?);
+    let mut greet = gm::contract("greet", 0, {
+        let late = late.clone();
+        move |ch: &Chain, args: Vec<Value>| {
+            return Ok(Value::Text(format!("gm {}", late.get("late")?.render())));
+        }
+    });
+    late.set(gm::text("fren"));
+    ch.broadcast(&ch.call(greet.clone(), vec![])?);
+    Ok(())
+}
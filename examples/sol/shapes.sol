@@ -0,0 +1,115 @@
+// SPDX-License-Identifier: MIT
+// Generated by gm-language from shapes.gm.
+pragma solidity ^0.8.20;
+
+contract Shapes {
+    struct Point {
+        int256 x;
+        int256 y;
+    }
+
+    event BroadcastInt(int256 value);
+    event Broadcast(string value);
+
+    constructor() {
+        emit Broadcast(classify(Point({x: 2, y: 5})));
+        emit Broadcast(size(fib(7)));
+    }
+
+    function classify(Point memory p) public pure returns (string memory) {
+        if (p.x == 0 && p.y == 0) {
+            return "origin";
+        } else if (p.x == 0) {
+            int256 y = p.y;
+            return string.concat("on the y axis at ", _intText(y));
+        } else if (p.y == 0) {
+            int256 x = p.x;
+            return string.concat("on the x axis at ", _intText(x));
+        } else {
+            Point memory other = p;
+            int256 sum = other.x + other.y;
+            return string.concat("off axis, sum ", _intText(sum));
+        }
+    }
+
+    function size(int256 n) public pure returns (string memory) {
+        if (n == 0) {
+            return "none";
+        } else if (n == 1 || n == 2 || n == 3) {
+            return "few";
+        } else if (n >= 4 && n < 100) {
+            return "some";
+        } else {
+            return "lots";
+        }
+    }
+
+    function fib(int256 n) public pure returns (int256) {
+        if (n < 2) {
+            return n;
+        }
+        return fib(n - 1) + fib(n - 2);
+    }
+
+    function steps(int256 limit) public returns (int256) {
+        int256 total = 0;
+        for (int256 i = 0; i < limit; i += 2) {
+            if (_floorMod(i, 3) == 0) {
+                continue;
+            }
+            total += _floorDiv(i, 2);
+        }
+        for (int256 i = 10; i > 0; i -= 5) {
+            emit BroadcastInt(i);
+        }
+        return total;
+    }
+
+    function corners() public returns (Point memory) {
+        Point[] memory points = new Point[](2);
+        points[0] = Point({x: 0, y: 0});
+        points[1] = Point({x: 3, y: 4});
+        for (uint256 $i = 0; $i < points.length; $i++) {
+            int256 index = int256($i);
+            Point memory p = points[$i];
+            emit Broadcast(string.concat(_intText(index), ": ", classify(p)));
+        }
+        return points[1];
+    }
+
+    // `~/` rounds down, where Solidity's `/` rounds toward zero
+    function _floorDiv(int256 a, int256 b) private pure returns (int256) {
+        int256 q = a / b;
+        if (a % b != 0 && (a < 0) != (b < 0)) {
+            q -= 1;
+        }
+        return q;
+    }
+
+    // `%` takes the divisor's sign
+    function _floorMod(int256 a, int256 b) private pure returns (int256) {
+        return a - b * _floorDiv(a, b);
+    }
+
+    function _intText(int256 x) private pure returns (string memory) {
+        if (x >= 0) {
+            return _uintText(uint256(x));
+        }
+        return string.concat("-", _uintText(uint256(-(x + 1)) + 1));
+    }
+
+    function _uintText(uint256 x) private pure returns (string memory) {
+        if (x == 0) {
+            return "0";
+        }
+        uint256 digits;
+        for (uint256 rest = x; rest != 0; rest /= 10) {
+            digits++;
+        }
+        bytes memory text = new bytes(digits);
+        for (; x != 0; x /= 10) {
+            text[--digits] = bytes1(uint8(48 + x % 10));
+        }
+        return string(text);
+    }
+}
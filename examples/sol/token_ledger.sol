@@ -0,0 +1,73 @@
+// SPDX-License-Identifier: MIT
+// Generated by gm-language from token_ledger.gm.
+pragma solidity ^0.8.20;
+
+contract TokenLedger {
+    event Broadcast(string value);
+    event BroadcastUint(uint256 value);
+
+    string public constant NAME = "GM Coin";
+    int256 public constant DECIMALS = 18;
+    uint256 public supply = 0;
+    mapping(int256 => uint256) public balances;
+    int256[] public holders;
+
+    constructor() {
+        holders.push(1);
+        holders.push(2);
+        holders.push(3);
+        mint(1, 1000);
+    }
+
+    function mint(int256 to, uint256 amount) public {
+        balances[to] += amount;
+        supply += amount;
+        emit Broadcast(string.concat("minted ", _uintText(amount), " to ", _intText(to)));
+    }
+
+    function transfer(int256 from, int256 to, uint256 amount) public returns (bool) {
+        if (balances[from] < amount) {
+            emit Broadcast(string.concat("not enough ", NAME));
+            return false;
+        }
+        balances[from] -= amount;
+        balances[to] += amount;
+        emit BroadcastUint(amount);
+        return true;
+    }
+
+    function balance_of(int256 who) public view returns (uint256) {
+        return balances[who];
+    }
+
+    function total_held() public view returns (uint256) {
+        uint256 total = 0;
+        for (uint256 $i = 0; $i < holders.length; $i++) {
+            int256 who = holders[$i];
+            total += balance_of(who);
+        }
+        return total;
+    }
+
+    function _intText(int256 x) private pure returns (string memory) {
+        if (x >= 0) {
+            return _uintText(uint256(x));
+        }
+        return string.concat("-", _uintText(uint256(-(x + 1)) + 1));
+    }
+
+    function _uintText(uint256 x) private pure returns (string memory) {
+        if (x == 0) {
+            return "0";
+        }
+        uint256 digits;
+        for (uint256 rest = x; rest != 0; rest /= 10) {
+            digits++;
+        }
+        bytes memory text = new bytes(digits);
+        for (; x != 0; x /= 10) {
+            text[--digits] = bytes1(uint8(48 + x % 10));
+        }
+        return string(text);
+    }
+}
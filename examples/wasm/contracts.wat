@@ -0,0 +1,372 @@
+(module
+  (import "gm" "broadcast" (func $gm.broadcast (param i32 i32)))
+  (import "gm" "number_text" (func $gm.number_text (param f64 i32 i32) (result i32)))
+  (import "gm" "rekt" (func $gm.rekt (param i32 i32 i32)))
+  (memory (export "memory") 1)
+  (global $n (mut f64) (f64.const 0.0))
+  (global $gm.heap (mut i32) (i32.const 172))
+  (data (i32.const 8) "\07\00\00\00nothing\00\05\00\00\00a few\00\00\00\04\00\00\00some\05\00\00\00loads\00\00\00\03\00\00\00gm \00\0b\00\00\00, you have \00\08\00\00\00 letters\18\00\00\00Range step can't be zero\04\00\00\00fib(\04\00\00\00) = \04\00\00\00fren\06\00\00\00\e2\98\95 gm\00\00\04\00\00\00true\05\00\00\00false\00\00\00")
+  (func $fib (export "fib") (param $n f64) (result f64)
+    local.get $n
+    f64.const 2.0
+    f64.lt
+    if
+      local.get $n
+      return
+    end
+    local.get $n
+    f64.const 1.0
+    f64.sub
+    call $fib
+    local.get $n
+    f64.const 2.0
+    f64.sub
+    call $fib
+    f64.add
+    return
+    unreachable
+  )
+  (func $describe (export "describe") (param $n f64) (result i32)
+    (local $gm.subject f64)
+    local.get $n
+    local.set $gm.subject
+    block
+      local.get $gm.subject
+      f64.const 0.0
+      f64.eq
+      if
+        i32.const 8
+        return
+        br 1
+      end
+      local.get $gm.subject
+      f64.const 1.0
+      f64.eq
+      local.get $gm.subject
+      f64.const 2.0
+      f64.eq
+      i32.or
+      local.get $gm.subject
+      f64.const 3.0
+      f64.eq
+      i32.or
+      if
+        i32.const 20
+        return
+        br 1
+      end
+      local.get $gm.subject
+      f64.const 4.0
+      f64.ge
+      local.get $gm.subject
+      f64.const 10.0
+      f64.lt
+      i32.and
+      if
+        i32.const 32
+        return
+        br 1
+      end
+      i32.const 40
+      return
+      br 0
+    end
+    unreachable
+  )
+  (func $greet (export "greet") (param $who i32) (result i32)
+    i32.const 52
+    local.get $who
+    call $gm.concat
+    i32.const 60
+    call $gm.concat
+    local.get $who
+    call $gm.text_len
+    call $gm.text_of_number
+    call $gm.concat
+    i32.const 76
+    call $gm.concat
+    return
+    unreachable
+  )
+  (func $gm.run (export "run")
+    (local $gm.start f64)
+    (local $gm.end f64)
+    (local $gm.step f64)
+    (local $i f64)
+    f64.const 0.0
+    local.set $gm.start
+    f64.const 10.0
+    local.set $gm.end
+    f64.const 1.0
+    local.set $gm.step
+    local.get $gm.step
+    f64.const 0.0
+    f64.eq
+    if
+      i32.const 2
+      i32.const 88
+      call $gm.fail
+      unreachable
+    end
+    block
+      loop
+        local.get $gm.step
+        f64.const 0.0
+        f64.gt
+        local.get $gm.start
+        local.get $gm.end
+        f64.lt
+        i32.and
+        local.get $gm.step
+        f64.const 0.0
+        f64.lt
+        local.get $gm.start
+        local.get $gm.end
+        f64.gt
+        i32.and
+        i32.or
+        i32.eqz
+        br_if 1
+        local.get $gm.start
+        local.set $i
+        block
+          local.get $i
+          f64.const 3.0
+          call $gm.num_eq
+          if
+            br 1
+          end
+          local.get $i
+          f64.const 8.0
+          call $gm.num_eq
+          if
+            br 3
+          end
+          i32.const 116
+          local.get $i
+          call $gm.text_of_number
+          call $gm.concat
+          i32.const 124
+          call $gm.concat
+          local.get $i
+          call $fib
+          call $gm.text_of_number
+          call $gm.concat
+          call $gm.emit
+        end
+        local.get $gm.start
+        local.get $gm.step
+        f64.add
+        local.set $gm.start
+        br 0
+      end
+    end
+    f64.const 0.0
+    global.set $n
+    block
+      loop
+        global.get $n
+        f64.const 12.0
+        f64.lt
+        i32.eqz
+        br_if 1
+        global.get $n
+        call $describe
+        call $gm.emit
+        global.get $n
+        f64.const 5.0
+        f64.add
+        global.set $n
+        br 0
+      end
+    end
+    i32.const 132
+    call $greet
+    call $gm.emit
+    i32.const 140
+    call $greet
+    call $gm.emit
+    i32.const 1
+    call $gm.text_of_signal
+    call $gm.emit
+  )
+  (func $gm.alloc (param $size i32) (result i32)
+    (local $ptr i32)
+    (local $end i32)
+    global.get $gm.heap
+    local.set $ptr
+    local.get $ptr
+    local.get $size
+    i32.add
+    i32.const 3
+    i32.add
+    i32.const -4
+    i32.and
+    local.set $end
+    local.get $end
+    memory.size
+    i32.const 65536
+    i32.mul
+    i32.gt_u
+    if
+      local.get $end
+      memory.size
+      i32.const 65536
+      i32.mul
+      i32.sub
+      i32.const 65535
+      i32.add
+      i32.const 16
+      i32.shr_u
+      memory.grow
+      i32.const -1
+      i32.eq
+      if
+        unreachable
+      end
+    end
+    local.get $end
+    global.set $gm.heap
+    local.get $ptr
+  )
+  (func $gm.fail (param $kind i32) (param $text i32)
+    local.get $kind
+    local.get $text
+    i32.const 4
+    i32.add
+    local.get $text
+    i32.load
+    call $gm.rekt
+    unreachable
+  )
+  (func $gm.emit (param $text i32)
+    local.get $text
+    i32.const 4
+    i32.add
+    local.get $text
+    i32.load
+    call $gm.broadcast
+  )
+  (func $gm.text_of_number (param $n f64) (result i32)
+    (local $ptr i32)
+    (local $len i32)
+    i32.const 344
+    call $gm.alloc
+    local.set $ptr
+    local.get $n
+    local.get $ptr
+    i32.const 4
+    i32.add
+    i32.const 340
+    call $gm.number_text
+    local.set $len
+    local.get $ptr
+    local.get $len
+    i32.store
+    local.get $ptr
+    local.get $len
+    i32.add
+    i32.const 7
+    i32.add
+    i32.const -4
+    i32.and
+    global.set $gm.heap
+    local.get $ptr
+  )
+  (func $gm.text_of_signal (param $b i32) (result i32)
+    i32.const 152
+    i32.const 160
+    local.get $b
+    select
+  )
+  (func $gm.concat (param $a i32) (param $b i32) (result i32)
+    (local $la i32)
+    (local $lb i32)
+    (local $ptr i32)
+    local.get $a
+    i32.load
+    local.set $la
+    local.get $b
+    i32.load
+    local.set $lb
+    local.get $la
+    local.get $lb
+    i32.add
+    i32.const 4
+    i32.add
+    call $gm.alloc
+    local.set $ptr
+    local.get $ptr
+    local.get $la
+    local.get $lb
+    i32.add
+    i32.store
+    local.get $ptr
+    i32.const 4
+    i32.add
+    local.get $a
+    i32.const 4
+    i32.add
+    local.get $la
+    memory.copy
+    local.get $ptr
+    i32.const 4
+    i32.add
+    local.get $la
+    i32.add
+    local.get $b
+    i32.const 4
+    i32.add
+    local.get $lb
+    memory.copy
+    local.get $ptr
+  )
+  (func $gm.text_len (param $text i32) (result f64)
+    (local $i i32)
+    (local $count f64)
+    block
+      loop
+        local.get $i
+        local.get $text
+        i32.load
+        i32.ge_u
+        br_if 1
+        local.get $text
+        local.get $i
+        i32.add
+        i32.load8_u offset=4
+        i32.const 192
+        i32.and
+        i32.const 128
+        i32.ne
+        if
+          local.get $count
+          f64.const 1.0
+          f64.add
+          local.set $count
+        end
+        local.get $i
+        i32.const 1
+        i32.add
+        local.set $i
+        br 0
+      end
+    end
+    local.get $count
+  )
+  (func $gm.num_eq (param $a f64) (param $b f64) (result i32)
+    local.get $a
+    i64.reinterpret_f64
+    local.get $b
+    i64.reinterpret_f64
+    i64.eq
+    local.get $a
+    local.get $a
+    f64.ne
+    local.get $b
+    local.get $b
+    f64.ne
+    i32.and
+    i32.or
+  )
+)
@@ -0,0 +1,43 @@
+(module
+  (import "gm" "broadcast" (func $gm.broadcast (param i32 i32)))
+  (import "gm" "rekt" (func $gm.rekt (param i32 i32 i32)))
+  (memory (export "memory") 1)
+  (global $gm.heap (mut i32) (i32.const 96))
+  (data (i32.const 8) "\01\00\00\00!\00\00\00\1d\00\00\00Expected vibe, but found text\00\00\00\06\00\00\00before\00\00\02\00\00\00gm\00\00\04\00\00\00null\0b\00\00\00unreachable\00")
+  (func $shout (export "shout") (param $word i32)
+    local.get $word
+    i32.const 8
+    i32.const 3
+    i32.const 16
+    call $gm.fail
+    unreachable
+  )
+  (func $gm.run (export "run")
+    i32.const 52
+    call $gm.emit
+    i32.const 64
+    call $shout
+    i32.const 72
+    call $gm.emit
+    i32.const 80
+    call $gm.emit
+  )
+  (func $gm.fail (param $kind i32) (param $text i32)
+    local.get $kind
+    local.get $text
+    i32.const 4
+    i32.add
+    local.get $text
+    i32.load
+    call $gm.rekt
+    unreachable
+  )
+  (func $gm.emit (param $text i32)
+    local.get $text
+    i32.const 4
+    i32.add
+    local.get $text
+    i32.load
+    call $gm.broadcast
+  )
+)
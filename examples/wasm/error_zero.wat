@@ -0,0 +1,256 @@
+(module
+  (import "gm" "broadcast" (func $gm.broadcast (param i32 i32)))
+  (import "gm" "number_text" (func $gm.number_text (param f64 i32 i32) (result i32)))
+  (import "gm" "rekt" (func $gm.rekt (param i32 i32 i32)))
+  (memory (export "memory") 1)
+  (global $x (mut f64) (f64.const 0.0))
+  (global $gm.heap (mut i32) (i32.const 108))
+  (data (i32.const 8) "\18\00\00\00Range step can't be zero$\00\00\00'~/' needs integers, but found vibe \1a\00\00\00You just got rekt by zero!\00\00")
+  (func $gm.run (export "run")
+    (local $gm.start f64)
+    (local $gm.end f64)
+    (local $gm.step f64)
+    (local $i f64)
+    (local $gm.rhs f64)
+    f64.const 10.0
+    global.set $x
+    f64.const 3.0
+    local.set $gm.start
+    f64.const -1.0
+    local.set $gm.end
+    f64.const -1.0
+    local.set $gm.step
+    local.get $gm.step
+    f64.const 0.0
+    f64.eq
+    if
+      i32.const 2
+      i32.const 8
+      call $gm.fail
+      unreachable
+    end
+    block
+      loop
+        local.get $gm.step
+        f64.const 0.0
+        f64.gt
+        local.get $gm.start
+        local.get $gm.end
+        f64.lt
+        i32.and
+        local.get $gm.step
+        f64.const 0.0
+        f64.lt
+        local.get $gm.start
+        local.get $gm.end
+        f64.gt
+        i32.and
+        i32.or
+        i32.eqz
+        br_if 1
+        local.get $gm.start
+        local.set $i
+        block
+          global.get $x
+          local.get $i
+          local.set $gm.rhs
+          i32.const 36
+          call $gm.whole
+          local.get $gm.rhs
+          i32.const 36
+          call $gm.whole
+          call $gm.floor_div
+          f64.convert_i64_s
+          call $gm.text_of_number
+          call $gm.emit
+        end
+        local.get $gm.start
+        local.get $gm.step
+        f64.add
+        local.set $gm.start
+        br 0
+      end
+    end
+  )
+  (func $gm.alloc (param $size i32) (result i32)
+    (local $ptr i32)
+    (local $end i32)
+    global.get $gm.heap
+    local.set $ptr
+    local.get $ptr
+    local.get $size
+    i32.add
+    i32.const 3
+    i32.add
+    i32.const -4
+    i32.and
+    local.set $end
+    local.get $end
+    memory.size
+    i32.const 65536
+    i32.mul
+    i32.gt_u
+    if
+      local.get $end
+      memory.size
+      i32.const 65536
+      i32.mul
+      i32.sub
+      i32.const 65535
+      i32.add
+      i32.const 16
+      i32.shr_u
+      memory.grow
+      i32.const -1
+      i32.eq
+      if
+        unreachable
+      end
+    end
+    local.get $end
+    global.set $gm.heap
+    local.get $ptr
+  )
+  (func $gm.fail (param $kind i32) (param $text i32)
+    local.get $kind
+    local.get $text
+    i32.const 4
+    i32.add
+    local.get $text
+    i32.load
+    call $gm.rekt
+    unreachable
+  )
+  (func $gm.emit (param $text i32)
+    local.get $text
+    i32.const 4
+    i32.add
+    local.get $text
+    i32.load
+    call $gm.broadcast
+  )
+  (func $gm.text_of_number (param $n f64) (result i32)
+    (local $ptr i32)
+    (local $len i32)
+    i32.const 344
+    call $gm.alloc
+    local.set $ptr
+    local.get $n
+    local.get $ptr
+    i32.const 4
+    i32.add
+    i32.const 340
+    call $gm.number_text
+    local.set $len
+    local.get $ptr
+    local.get $len
+    i32.store
+    local.get $ptr
+    local.get $len
+    i32.add
+    i32.const 7
+    i32.add
+    i32.const -4
+    i32.and
+    global.set $gm.heap
+    local.get $ptr
+  )
+  (func $gm.concat (param $a i32) (param $b i32) (result i32)
+    (local $la i32)
+    (local $lb i32)
+    (local $ptr i32)
+    local.get $a
+    i32.load
+    local.set $la
+    local.get $b
+    i32.load
+    local.set $lb
+    local.get $la
+    local.get $lb
+    i32.add
+    i32.const 4
+    i32.add
+    call $gm.alloc
+    local.set $ptr
+    local.get $ptr
+    local.get $la
+    local.get $lb
+    i32.add
+    i32.store
+    local.get $ptr
+    i32.const 4
+    i32.add
+    local.get $a
+    i32.const 4
+    i32.add
+    local.get $la
+    memory.copy
+    local.get $ptr
+    i32.const 4
+    i32.add
+    local.get $la
+    i32.add
+    local.get $b
+    i32.const 4
+    i32.add
+    local.get $lb
+    memory.copy
+    local.get $ptr
+  )
+  (func $gm.whole (param $n f64) (param $prefix i32) (result i64)
+    local.get $n
+    f64.trunc
+    local.get $n
+    f64.eq
+    local.get $n
+    f64.abs
+    f64.const 9007199254740992.0
+    f64.le
+    i32.and
+    i32.eqz
+    if
+      i32.const 3
+      local.get $prefix
+      local.get $n
+      call $gm.text_of_number
+      call $gm.concat
+      call $gm.fail
+    end
+    local.get $n
+    i64.trunc_f64_s
+  )
+  (func $gm.floor_div (param $a i64) (param $b i64) (result i64)
+    (local $q i64)
+    local.get $b
+    i64.eqz
+    if
+      i32.const 2
+      i32.const 76
+      call $gm.fail
+    end
+    local.get $a
+    local.get $b
+    i64.div_s
+    local.set $q
+    local.get $a
+    local.get $b
+    i64.rem_s
+    i64.const 0
+    i64.ne
+    local.get $a
+    i64.const 0
+    i64.lt_s
+    local.get $b
+    i64.const 0
+    i64.lt_s
+    i32.ne
+    i32.and
+    if
+      local.get $q
+      i64.const 1
+      i64.sub
+      local.set $q
+    end
+    local.get $q
+  )
+)
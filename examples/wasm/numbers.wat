@@ -0,0 +1,506 @@
+(module
+  (import "gm" "broadcast" (func $gm.broadcast (param i32 i32)))
+  (import "gm" "number_text" (func $gm.number_text (param f64 i32 i32) (result i32)))
+  (import "gm" "rekt" (func $gm.rekt (param i32 i32 i32)))
+  (memory (export "memory") 1)
+  (global $a (mut f64) (f64.const 0.0))
+  (global $b (mut f64) (f64.const 0.0))
+  (global $total (mut f64) (f64.const 0.0))
+  (global $gm.heap (mut i32) (i32.const 484))
+  (data (i32.const 8) "\1a\00\00\00You just got rekt by zero!\00\00#\00\00\00'%' needs integers, but found vibe \00$\00\00\00'~/' needs integers, but found vibe #\00\00\00'&' needs integers, but found vibe \00#\00\00\00'|' needs integers, but found vibe \00#\00\00\00'^' needs integers, but found vibe \00$\00\00\00'<<' needs integers, but found vibe $\00\00\00'>>' needs integers, but found vibe #\00\00\00'~' needs integers, but found vibe \00\10\00\00\00Cannot shift by \18\00\00\00, shifts go from 0 to 63\04\00\00\00 << )\00\00\00 is too big for a number, use a uint \f0\9f\93\89\00\00\00\04\00\00\00true\05\00\00\00false\00\00\00")
+  (func $gm.run (export "run")
+    (local $gm.divisor f64)
+    (local $gm.rhs f64)
+    (local $gm.a i64)
+    (local $gm.b i64)
+    (local $gm.rhs.1 f64)
+    (local $gm.a.1 i64)
+    (local $gm.b.1 i64)
+    (local $gm.rhs.2 f64)
+    (local $gm.rhs.3 f64)
+    (local $gm.rhs.4 f64)
+    (local $gm.rhs.5 f64)
+    (local $gm.rhs.6 f64)
+    (local $gm.rhs.7 f64)
+    (local $gm.divisor.1 f64)
+    f64.const 7.0
+    global.set $a
+    f64.const 2.0
+    global.set $b
+    global.get $a
+    global.get $b
+    f64.const 3.0
+    f64.mul
+    f64.add
+    call $gm.text_of_number
+    call $gm.emit
+    global.get $a
+    global.get $b
+    local.set $gm.divisor
+    local.get $gm.divisor
+    f64.const 0.0
+    f64.eq
+    if
+      i32.const 0
+      i32.const 8
+      call $gm.fail
+      unreachable
+    end
+    local.get $gm.divisor
+    f64.div
+    call $gm.text_of_number
+    call $gm.emit
+    global.get $a
+    global.get $b
+    local.set $gm.rhs
+    i32.const 40
+    call $gm.whole
+    local.get $gm.rhs
+    i32.const 40
+    call $gm.whole
+    local.set $gm.b
+    local.set $gm.a
+    local.get $gm.a
+    local.get $gm.a
+    local.get $gm.b
+    call $gm.floor_div
+    local.get $gm.b
+    i64.mul
+    i64.sub
+    f64.convert_i64_s
+    call $gm.text_of_number
+    call $gm.emit
+    f64.const -7.0
+    f64.const 3.0
+    local.set $gm.rhs.1
+    i32.const 40
+    call $gm.whole
+    local.get $gm.rhs.1
+    i32.const 40
+    call $gm.whole
+    local.set $gm.b.1
+    local.set $gm.a.1
+    local.get $gm.a.1
+    local.get $gm.a.1
+    local.get $gm.b.1
+    call $gm.floor_div
+    local.get $gm.b.1
+    i64.mul
+    i64.sub
+    f64.convert_i64_s
+    call $gm.text_of_number
+    call $gm.emit
+    f64.const -7.0
+    f64.const 2.0
+    local.set $gm.rhs.2
+    i32.const 80
+    call $gm.whole
+    local.get $gm.rhs.2
+    i32.const 80
+    call $gm.whole
+    call $gm.floor_div
+    f64.convert_i64_s
+    call $gm.text_of_number
+    call $gm.emit
+    global.get $a
+    f64.const 3.0
+    local.set $gm.rhs.3
+    i32.const 120
+    call $gm.whole
+    local.get $gm.rhs.3
+    i32.const 120
+    call $gm.whole
+    i64.and
+    f64.convert_i64_s
+    call $gm.text_of_number
+    call $gm.emit
+    global.get $a
+    f64.const 8.0
+    local.set $gm.rhs.4
+    i32.const 160
+    call $gm.whole
+    local.get $gm.rhs.4
+    i32.const 160
+    call $gm.whole
+    i64.or
+    f64.convert_i64_s
+    call $gm.text_of_number
+    call $gm.emit
+    global.get $a
+    global.get $b
+    local.set $gm.rhs.5
+    i32.const 200
+    call $gm.whole
+    local.get $gm.rhs.5
+    i32.const 200
+    call $gm.whole
+    i64.xor
+    f64.convert_i64_s
+    call $gm.text_of_number
+    call $gm.emit
+    f64.const 1.0
+    f64.const 10.0
+    local.set $gm.rhs.6
+    i32.const 240
+    call $gm.whole
+    local.get $gm.rhs.6
+    i32.const 240
+    call $gm.whole
+    call $gm.shl
+    f64.convert_i64_s
+    call $gm.text_of_number
+    call $gm.emit
+    f64.const -16.0
+    f64.const 2.0
+    local.set $gm.rhs.7
+    i32.const 280
+    call $gm.whole
+    local.get $gm.rhs.7
+    i32.const 280
+    call $gm.whole
+    call $gm.shr
+    f64.convert_i64_s
+    call $gm.text_of_number
+    call $gm.emit
+    global.get $a
+    i32.const 320
+    call $gm.whole
+    i64.const -1
+    i64.xor
+    f64.convert_i64_s
+    call $gm.text_of_number
+    call $gm.emit
+    f64.const 0.1
+    f64.const 0.2
+    f64.add
+    call $gm.text_of_number
+    call $gm.emit
+    f64.const 1.0
+    f64.const 3.0
+    local.set $gm.divisor.1
+    local.get $gm.divisor.1
+    f64.const 0.0
+    f64.eq
+    if
+      i32.const 0
+      i32.const 8
+      call $gm.fail
+      unreachable
+    end
+    local.get $gm.divisor.1
+    f64.div
+    call $gm.text_of_number
+    call $gm.emit
+    f64.const 1e21
+    f64.const 10.0
+    f64.mul
+    call $gm.text_of_number
+    call $gm.emit
+    global.get $a
+    global.get $b
+    f64.lt
+    call $gm.text_of_signal
+    call $gm.emit
+    global.get $a
+    f64.const 7.0
+    f64.lt
+    i32.eqz
+    call $gm.text_of_signal
+    call $gm.emit
+    global.get $a
+    f64.const 7.0
+    call $gm.num_eq
+    call $gm.text_of_signal
+    call $gm.emit
+    global.get $a
+    global.get $b
+    call $gm.num_eq
+    i32.eqz
+    i32.eqz
+    call $gm.text_of_signal
+    call $gm.emit
+    f64.const 0.0
+    global.set $total
+    global.get $total
+    global.get $a
+    f64.add
+    global.set $total
+    global.get $total
+    f64.const 3.0
+    f64.mul
+    global.set $total
+    global.get $total
+    call $gm.text_of_number
+    call $gm.emit
+  )
+  (func $gm.alloc (param $size i32) (result i32)
+    (local $ptr i32)
+    (local $end i32)
+    global.get $gm.heap
+    local.set $ptr
+    local.get $ptr
+    local.get $size
+    i32.add
+    i32.const 3
+    i32.add
+    i32.const -4
+    i32.and
+    local.set $end
+    local.get $end
+    memory.size
+    i32.const 65536
+    i32.mul
+    i32.gt_u
+    if
+      local.get $end
+      memory.size
+      i32.const 65536
+      i32.mul
+      i32.sub
+      i32.const 65535
+      i32.add
+      i32.const 16
+      i32.shr_u
+      memory.grow
+      i32.const -1
+      i32.eq
+      if
+        unreachable
+      end
+    end
+    local.get $end
+    global.set $gm.heap
+    local.get $ptr
+  )
+  (func $gm.fail (param $kind i32) (param $text i32)
+    local.get $kind
+    local.get $text
+    i32.const 4
+    i32.add
+    local.get $text
+    i32.load
+    call $gm.rekt
+    unreachable
+  )
+  (func $gm.emit (param $text i32)
+    local.get $text
+    i32.const 4
+    i32.add
+    local.get $text
+    i32.load
+    call $gm.broadcast
+  )
+  (func $gm.text_of_number (param $n f64) (result i32)
+    (local $ptr i32)
+    (local $len i32)
+    i32.const 344
+    call $gm.alloc
+    local.set $ptr
+    local.get $n
+    local.get $ptr
+    i32.const 4
+    i32.add
+    i32.const 340
+    call $gm.number_text
+    local.set $len
+    local.get $ptr
+    local.get $len
+    i32.store
+    local.get $ptr
+    local.get $len
+    i32.add
+    i32.const 7
+    i32.add
+    i32.const -4
+    i32.and
+    global.set $gm.heap
+    local.get $ptr
+  )
+  (func $gm.text_of_signal (param $b i32) (result i32)
+    i32.const 464
+    i32.const 472
+    local.get $b
+    select
+  )
+  (func $gm.concat (param $a i32) (param $b i32) (result i32)
+    (local $la i32)
+    (local $lb i32)
+    (local $ptr i32)
+    local.get $a
+    i32.load
+    local.set $la
+    local.get $b
+    i32.load
+    local.set $lb
+    local.get $la
+    local.get $lb
+    i32.add
+    i32.const 4
+    i32.add
+    call $gm.alloc
+    local.set $ptr
+    local.get $ptr
+    local.get $la
+    local.get $lb
+    i32.add
+    i32.store
+    local.get $ptr
+    i32.const 4
+    i32.add
+    local.get $a
+    i32.const 4
+    i32.add
+    local.get $la
+    memory.copy
+    local.get $ptr
+    i32.const 4
+    i32.add
+    local.get $la
+    i32.add
+    local.get $b
+    i32.const 4
+    i32.add
+    local.get $lb
+    memory.copy
+    local.get $ptr
+  )
+  (func $gm.num_eq (param $a f64) (param $b f64) (result i32)
+    local.get $a
+    i64.reinterpret_f64
+    local.get $b
+    i64.reinterpret_f64
+    i64.eq
+    local.get $a
+    local.get $a
+    f64.ne
+    local.get $b
+    local.get $b
+    f64.ne
+    i32.and
+    i32.or
+  )
+  (func $gm.whole (param $n f64) (param $prefix i32) (result i64)
+    local.get $n
+    f64.trunc
+    local.get $n
+    f64.eq
+    local.get $n
+    f64.abs
+    f64.const 9007199254740992.0
+    f64.le
+    i32.and
+    i32.eqz
+    if
+      i32.const 3
+      local.get $prefix
+      local.get $n
+      call $gm.text_of_number
+      call $gm.concat
+      call $gm.fail
+    end
+    local.get $n
+    i64.trunc_f64_s
+  )
+  (func $gm.floor_div (param $a i64) (param $b i64) (result i64)
+    (local $q i64)
+    local.get $b
+    i64.eqz
+    if
+      i32.const 2
+      i32.const 8
+      call $gm.fail
+    end
+    local.get $a
+    local.get $b
+    i64.div_s
+    local.set $q
+    local.get $a
+    local.get $b
+    i64.rem_s
+    i64.const 0
+    i64.ne
+    local.get $a
+    i64.const 0
+    i64.lt_s
+    local.get $b
+    i64.const 0
+    i64.lt_s
+    i32.ne
+    i32.and
+    if
+      local.get $q
+      i64.const 1
+      i64.sub
+      local.set $q
+    end
+    local.get $q
+  )
+  (func $gm.shl (param $a i64) (param $b i64) (result i64)
+    (local $abs i64)
+    local.get $b
+    i64.const 64
+    i64.ge_u
+    if
+      i32.const 2
+      i32.const 360
+      local.get $b
+      f64.convert_i64_s
+      call $gm.text_of_number
+      call $gm.concat
+      i32.const 380
+      call $gm.concat
+      call $gm.fail
+    end
+    i64.const 0
+    local.get $a
+    i64.sub
+    local.get $a
+    local.get $a
+    i64.const 0
+    i64.lt_s
+    select
+    local.set $abs
+    local.get $abs
+    i64.const 9007199254740992
+    local.get $b
+    i64.shr_s
+    i64.gt_s
+    if
+      i32.const 2
+      local.get $a
+      f64.convert_i64_s
+      call $gm.text_of_number
+      i32.const 408
+      local.get $b
+      f64.convert_i64_s
+      call $gm.text_of_number
+      call $gm.concat
+      call $gm.concat
+      i32.const 416
+      call $gm.concat
+      call $gm.fail
+    end
+    local.get $a
+    local.get $b
+    i64.shl
+  )
+  (func $gm.shr (param $a i64) (param $b i64) (result i64)
+    local.get $b
+    i64.const 64
+    i64.ge_u
+    if
+      i32.const 2
+      i32.const 360
+      local.get $b
+      f64.convert_i64_s
+      call $gm.text_of_number
+      call $gm.concat
+      i32.const 380
+      call $gm.concat
+      call $gm.fail
+    end
+    local.get $a
+    local.get $b
+    i64.shr_s
+  )
+)
@@ -0,0 +1,486 @@
+// Native contracts that ship with every chain: the tree walker, the VM and
+// transpiled Rust all answer to the same names with the same checks and
+// messages.
+
+use std::cmp::Ordering;
+use crate::error::Rekt;
+use crate::numeric::{Decimal, Rounding, MAX_SCALE, U256};
+use crate::ops::compare;
+use crate::shape::{GmValue, Key, View};
+
+// Every name `call_builtin` answers to.
+pub const BUILTINS: &[&str] = &[
+    "len", "keys", "values", "entries", "has", "get", "zip", "enumerate", "range", "sort",
+    "map", "filter", "reduce", "sort_by", "any", "all",
+    "uint", "float", "text", "hex", "decimal", "round", "div",
+    "checked_add", "checked_sub", "checked_mul", "checked_div",
+    "wrapping_add", "wrapping_sub", "wrapping_mul",
+    "saturating_add", "saturating_sub", "saturating_mul",
+];
+
+// Anything that can call a contract value gets the builtins for free.
+pub trait NativeContracts<V: GmValue> {
+    // Calls a smart contract value with already-evaluated arguments.
+    fn invoke(&mut self, contract: V, args: Vec<V>) -> Result<V, Rekt>;
+
+    // Returns `None` when `name` isn't a builtin so the caller can report an
+    // unknown contract.
+    fn call_builtin(&mut self, name: &str, args: Vec<V>) -> Option<Result<V, Rekt>> {
+        let result = match name {
+            "len" => builtin_len(args),
+            "keys" => builtin_keys(args),
+            "values" => builtin_values(args),
+            "entries" => builtin_entries(args),
+            "has" => builtin_has(args),
+            "get" => builtin_get(args),
+            "zip" => builtin_zip(args),
+            "enumerate" => builtin_enumerate(args),
+            "range" => builtin_range(args),
+            "sort" => builtin_sort(args),
+            "map" => self.builtin_map(args),
+            "filter" => self.builtin_filter(args),
+            "reduce" => self.builtin_reduce(args),
+            "sort_by" => self.builtin_sort_by(args),
+            "any" => self.builtin_any(args),
+            "all" => self.builtin_all(args),
+            "uint" => builtin_uint(args),
+            "float" => builtin_float(args),
+            "text" => builtin_text(args),
+            "hex" => builtin_hex(args),
+            "decimal" => builtin_decimal(args),
+            "round" => builtin_round(args),
+            "div" => builtin_div(args),
+            "checked_add" | "checked_sub" | "checked_mul" | "checked_div"
+            | "wrapping_add" | "wrapping_sub" | "wrapping_mul"
+            | "saturating_add" | "saturating_sub" | "saturating_mul" => builtin_uint_op(name, args),
+            _ => return None,
+        };
+        Some(result.map_err(|e| e.traced(&format!("builtin '{}'", name))))
+    }
+
+    // map(xs, f): a new array with f applied to every item.
+    fn builtin_map(&mut self, args: Vec<V>) -> Result<V, Rekt> {
+        expect_args("map", &args, 2)?;
+        let (items, f) = array_and_contract(args)?;
+        let mut mapped = Vec::new();
+        for item in items {
+            mapped.push(self.invoke(f.clone(), vec![item])?);
+        }
+        Ok(V::from_array(mapped))
+    }
+
+    // filter(xs, pred): the items for which pred returns true.
+    fn builtin_filter(&mut self, args: Vec<V>) -> Result<V, Rekt> {
+        expect_args("filter", &args, 2)?;
+        let (items, pred) = array_and_contract(args)?;
+        let mut kept = Vec::new();
+        for item in items {
+            if self.test(&pred, item.clone(), "filter")? {
+                kept.push(item);
+            }
+        }
+        Ok(V::from_array(kept))
+    }
+
+    // reduce(xs, f, initial): folds left with f(acc, item).
+    fn builtin_reduce(&mut self, mut args: Vec<V>) -> Result<V, Rekt> {
+        expect_args("reduce", &args, 3)?;
+        let mut acc = args.pop().unwrap_or_else(V::null);
+        let (items, f) = array_and_contract(args)?;
+        for item in items {
+            acc = self.invoke(f.clone(), vec![acc, item])?;
+        }
+        Ok(acc)
+    }
+
+    // sort_by(xs, cmp): stable sort where cmp(a, b) returns a number that is
+    // negative when a comes first, zero when tied and positive otherwise.
+    fn builtin_sort_by(&mut self, args: Vec<V>) -> Result<V, Rekt> {
+        expect_args("sort_by", &args, 2)?;
+        let (items, cmp) = array_and_contract(args)?;
+        let sorted = merge_sort(items, &mut |a, b| {
+            let verdict = self.invoke(cmp.clone(), vec![a.clone(), b.clone()])?;
+            let n = verdict.as_number().ok_or_else(|| {
+                Rekt::Type(format!("sort_by comparator must return a number, but returned {}", verdict))
+            })?;
+            Ok(n.partial_cmp(&0.0).unwrap_or(Ordering::Equal))
+        })?;
+        Ok(V::from_array(sorted))
+    }
+
+    fn builtin_any(&mut self, args: Vec<V>) -> Result<V, Rekt> {
+        expect_args("any", &args, 2)?;
+        let (items, pred) = array_and_contract(args)?;
+        for item in items {
+            if self.test(&pred, item, "any")? {
+                return Ok(V::from_signal(true));
+            }
+        }
+        Ok(V::from_signal(false))
+    }
+
+    fn builtin_all(&mut self, args: Vec<V>) -> Result<V, Rekt> {
+        expect_args("all", &args, 2)?;
+        let (items, pred) = array_and_contract(args)?;
+        for item in items {
+            if !self.test(&pred, item, "all")? {
+                return Ok(V::from_signal(false));
+            }
+        }
+        Ok(V::from_signal(true))
+    }
+
+    fn test(&mut self, pred: &V, item: V, name: &str) -> Result<bool, Rekt> {
+        let verdict = self.invoke(pred.clone(), vec![item])?;
+        match verdict.view() {
+            View::Signal(b) => Ok(b),
+            _ => Err(Rekt::Type(format!(
+                "{} predicate must return a signal, but returned {}",
+                name, verdict
+            ))),
+        }
+    }
+}
+
+fn expect_args<V>(name: &str, args: &[V], count: usize) -> Result<(), Rekt> {
+    if args.len() != count {
+        return Err(Rekt::Runtime(format!(
+            "'{}' expected {} args, got {}.",
+            name,
+            count,
+            args.len()
+        )));
+    }
+    Ok(())
+}
+
+fn array_and_contract<V: GmValue>(mut args: Vec<V>) -> Result<(Vec<V>, V), Rekt> {
+    let contract = args.pop().unwrap_or_else(V::null);
+    let items = match args.pop() {
+        Some(array) => match array.view() {
+            View::Array(items) => items.to_vec(),
+            _ => return Err(Rekt::Type(format!("Expected array, but found {}", array))),
+        },
+        None => Vec::new(),
+    };
+    match contract.view() {
+        View::Contract => Ok((items, contract)),
+        _ => Err(Rekt::Type(format!("Expected smart contract, but found {}", contract))),
+    }
+}
+
+// A stable merge sort that lets the comparator fail, since user contracts
+// can error out (or disagree with themselves) halfway through a sort.
+fn merge_sort<V>(
+    items: Vec<V>,
+    cmp: &mut dyn FnMut(&V, &V) -> Result<Ordering, Rekt>
+) -> Result<Vec<V>, Rekt> {
+    if items.len() <= 1 {
+        return Ok(items);
+    }
+    let mut left = items;
+    let right = left.split_off(left.len() / 2);
+    let left = merge_sort(left, cmp)?;
+    let right = merge_sort(right, cmp)?;
+
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    while let (Some(a), Some(b)) = (left.peek(), right.peek()) {
+        if cmp(a, b)? == Ordering::Greater {
+            merged.extend(right.next());
+        } else {
+            merged.extend(left.next());
+        }
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
+
+fn builtin_len<V: GmValue>(args: Vec<V>) -> Result<V, Rekt> {
+    expect_args("len", &args, 1)?;
+    let len = match args[0].view() {
+        View::Array(items) => items.len(),
+        View::Map(entries) => entries.len(),
+        View::Text(s) => s.chars().count(),
+        _ => return Err(Rekt::Type(format!("Cannot take the length of {}", args[0]))),
+    };
+    Ok(V::from_number(len as f64))
+}
+
+fn builtin_keys<V: GmValue>(args: Vec<V>) -> Result<V, Rekt> {
+    expect_args("keys", &args, 1)?;
+    match args[0].view() {
+        View::Map(entries) => Ok(V::from_array(entries.keys().map(Key::to_value).collect())),
+        _ => Err(Rekt::Type(format!("Expected mapping, but found {}", args[0]))),
+    }
+}
+
+fn builtin_values<V: GmValue>(args: Vec<V>) -> Result<V, Rekt> {
+    expect_args("values", &args, 1)?;
+    match args[0].view() {
+        View::Map(entries) => Ok(V::from_array(entries.values().cloned().collect())),
+        _ => Err(Rekt::Type(format!("Expected mapping, but found {}", args[0]))),
+    }
+}
+
+fn builtin_entries<V: GmValue>(args: Vec<V>) -> Result<V, Rekt> {
+    expect_args("entries", &args, 1)?;
+    match args[0].view() {
+        View::Map(entries) => Ok(V::from_array(
+            entries
+                .iter()
+                .map(|(k, v)| V::from_array(vec![k.to_value(), v.clone()]))
+                .collect(),
+        )),
+        _ => Err(Rekt::Type(format!("Expected mapping, but found {}", args[0]))),
+    }
+}
+
+fn builtin_has<V: GmValue>(args: Vec<V>) -> Result<V, Rekt> {
+    expect_args("has", &args, 2)?;
+    match args[0].view() {
+        View::Map(entries) => Ok(V::from_signal(entries.contains_key(&Key::from_value(&args[1])?))),
+        _ => Err(Rekt::Type(format!("Expected mapping, but found {}", args[0]))),
+    }
+}
+
+// get(m, key, fallback): like m[key], but with a caller-chosen default.
+fn builtin_get<V: GmValue>(args: Vec<V>) -> Result<V, Rekt> {
+    expect_args("get", &args, 3)?;
+    match args[0].view() {
+        View::Map(entries) => Ok(entries
+            .get(&Key::from_value(&args[1])?)
+            .cloned()
+            .unwrap_or_else(|| args[2].clone())),
+        _ => Err(Rekt::Type(format!("Expected mapping, but found {}", args[0]))),
+    }
+}
+
+fn builtin_zip<V: GmValue>(args: Vec<V>) -> Result<V, Rekt> {
+    expect_args("zip", &args, 2)?;
+    match (args[0].view(), args[1].view()) {
+        (View::Array(a), View::Array(b)) => Ok(V::from_array(
+            a.iter()
+                .zip(b.iter())
+                .map(|(x, y)| V::from_array(vec![x.clone(), y.clone()]))
+                .collect(),
+        )),
+        _ => Err(Rekt::Type(format!("zip expects two arrays, but found {} and {}", args[0], args[1]))),
+    }
+}
+
+fn builtin_enumerate<V: GmValue>(args: Vec<V>) -> Result<V, Rekt> {
+    expect_args("enumerate", &args, 1)?;
+    match args[0].view() {
+        View::Array(items) => Ok(V::from_array(
+            items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| V::from_array(vec![V::from_number(i as f64), item.clone()]))
+                .collect(),
+        )),
+        _ => Err(Rekt::Type(format!("Expected array, but found {}", args[0]))),
+    }
+}
+
+// range(end), range(start, end) or range(start, end, step); end is exclusive.
+fn builtin_range<V: GmValue>(args: Vec<V>) -> Result<V, Rekt> {
+    let mut bounds = Vec::new();
+    for arg in &args {
+        bounds.push(
+            arg.as_number().ok_or_else(|| Rekt::Type(format!("range expects numbers, but found {}", arg)))?
+        );
+    }
+    let (start, end, step) = match bounds[..] {
+        [end] => (0.0, end, 1.0),
+        [start, end] => (start, end, 1.0),
+        [start, end, step] => (start, end, step),
+        _ => return Err(Rekt::Runtime(format!("'range' expected 1 to 3 args, got {}.", args.len()))),
+    };
+    if step == 0.0 {
+        return Err(Rekt::Runtime("range step can't be zero".to_string()));
+    }
+
+    let mut items = Vec::new();
+    let mut current = start;
+    while (step > 0.0 && current < end) || (step < 0.0 && current > end) {
+        items.push(V::from_number(current));
+        current += step;
+    }
+    Ok(V::from_array(items))
+}
+
+fn builtin_sort<V: GmValue>(args: Vec<V>) -> Result<V, Rekt> {
+    expect_args("sort", &args, 1)?;
+    match args[0].view() {
+        View::Array(items) => Ok(V::from_array(merge_sort(items.to_vec(), &mut |a, b| compare(a, b))?)),
+        _ => Err(Rekt::Type(format!("Expected array, but found {}", args[0]))),
+    }
+}
+
+// uint(x): an exact uint from a whole non-negative number, or from decimal
+// or 0x/0b/0o text.
+fn builtin_uint<V: GmValue>(args: Vec<V>) -> Result<V, Rekt> {
+    expect_args("uint", &args, 1)?;
+    let converted = match args[0].view() {
+        View::Uint(n) => Some(n),
+        View::Text(s) => U256::parse(s),
+        View::Decimal(d) => d.whole().and_then(|n| U256::parse(&n.to_string())),
+        _ => args[0].as_number().and_then(U256::from_f64),
+    };
+    converted
+        .map(V::from_uint)
+        .ok_or_else(|| Rekt::Type(format!("{} {} is not a whole number in uint range", args[0], args[0].render())))
+}
+
+// float(x): the nearest float; huge uints lose their low digits.
+fn builtin_float<V: GmValue>(args: Vec<V>) -> Result<V, Rekt> {
+    expect_args("float", &args, 1)?;
+    let converted = match args[0].view() {
+        View::Text(s) => s.trim().parse::<f64>().ok(),
+        _ => args[0].as_number(),
+    };
+    converted
+        .map(V::from_number)
+        .ok_or_else(|| Rekt::Type(format!("Cannot turn {} into a float", args[0])))
+}
+
+fn builtin_text<V: GmValue>(args: Vec<V>) -> Result<V, Rekt> {
+    expect_args("text", &args, 1)?;
+    Ok(V::from_text(args[0].render()))
+}
+
+fn builtin_hex<V: GmValue>(args: Vec<V>) -> Result<V, Rekt> {
+    expect_args("hex", &args, 1)?;
+    match args[0].view() {
+        View::Uint(n) => Ok(V::from_text(format!("0x{:x}", n))),
+        _ => Err(Rekt::Type(format!("hex expects a uint, but found {}", args[0]))),
+    }
+}
+
+// checked_* return null instead of failing, wrapping_* wrap around 2^256 and
+// saturating_* stick at 0 or the max uint.
+fn builtin_uint_op<V: GmValue>(name: &str, args: Vec<V>) -> Result<V, Rekt> {
+    expect_args(name, &args, 2)?;
+    let (a, b) = match (args[0].view(), args[1].view()) {
+        (View::Uint(a), View::Uint(b)) => (a, b),
+        _ => return Err(Rekt::Type(format!("{} expects two uints, but found {} and {}", name, args[0], args[1]))),
+    };
+    let checked = |result: Option<U256>| result.map(V::from_uint).unwrap_or_else(V::null);
+    Ok(match name {
+        "checked_add" => checked(a.checked_add(b)),
+        "checked_sub" => checked(a.checked_sub(b)),
+        "checked_mul" => checked(a.checked_mul(b)),
+        "checked_div" => checked(a.checked_div(b)),
+        "wrapping_add" => V::from_uint(a.wrapping_add(b)),
+        "wrapping_sub" => V::from_uint(a.wrapping_sub(b)),
+        "wrapping_mul" => V::from_uint(a.wrapping_mul(b)),
+        "saturating_add" => V::from_uint(a.saturating_add(b)),
+        "saturating_sub" => V::from_uint(a.saturating_sub(b)),
+        _ => V::from_uint(a.saturating_mul(b)),
+    })
+}
+
+// decimal(x) or decimal(x, scale): an exact decimal from a number or text.
+// With a scale, the value is rounded half-even to that many places.
+fn builtin_decimal<V: GmValue>(args: Vec<V>) -> Result<V, Rekt> {
+    if args.is_empty() || args.len() > 2 {
+        return Err(Rekt::Runtime(format!("'decimal' expected 1 or 2 args, got {}.", args.len())));
+    }
+    let converted = match args[0].view() {
+        View::Decimal(d) => Some(d),
+        View::Text(s) => Decimal::parse(s),
+        View::Vibe(_) | View::Uint(_) => Decimal::parse(&args[0].render()),
+        _ => None,
+    };
+    let value = converted
+        .ok_or_else(|| Rekt::Type(format!("Cannot turn {} {} into a decimal", args[0], args[0].render())))?;
+    match args.get(1) {
+        Some(scale) => round_to(value, scale_arg(scale)?, Rounding::HalfEven),
+        None => Ok(V::from_decimal(value)),
+    }
+}
+
+// round(x, scale, mode): x rescaled to `scale` places. mode is one of "down",
+// "up", "half_up", "half_even" (the default), "floor" or "ceil".
+fn builtin_round<V: GmValue>(args: Vec<V>) -> Result<V, Rekt> {
+    if args.len() < 2 || args.len() > 3 {
+        return Err(Rekt::Runtime(format!("'round' expected 2 or 3 args, got {}.", args.len())));
+    }
+    let value = decimal_arg(&args[0])?;
+    round_to(value, scale_arg(&args[1])?, rounding_arg(args.get(2))?)
+}
+
+// div(a, b, scale, mode): a / b to `scale` places, rounded as told.
+fn builtin_div<V: GmValue>(args: Vec<V>) -> Result<V, Rekt> {
+    if args.len() < 3 || args.len() > 4 {
+        return Err(Rekt::Runtime(format!("'div' expected 3 or 4 args, got {}.", args.len())));
+    }
+    let (a, b) = (decimal_arg(&args[0])?, decimal_arg(&args[1])?);
+    if b.is_zero() {
+        return Err(Rekt::Runtime("You just got rekt by zero!".to_string()));
+    }
+    a.checked_div(b, scale_arg(&args[2])?, rounding_arg(args.get(3))?)
+        .map(V::from_decimal)
+        .ok_or_else(|| Rekt::Runtime(format!("decimal overflow: {} / {} 📉", a, b)))
+}
+
+fn round_to<V: GmValue>(value: Decimal, scale: u32, rounding: Rounding) -> Result<V, Rekt> {
+    value
+        .round(scale, rounding)
+        .map(V::from_decimal)
+        .ok_or_else(|| Rekt::Runtime(format!("decimal overflow rounding {} to {} places 📉", value, scale)))
+}
+
+fn decimal_arg<V: GmValue>(value: &V) -> Result<Decimal, Rekt> {
+    match value.view() {
+        View::Decimal(d) => Some(d),
+        View::Vibe(_) | View::Uint(_) => Decimal::parse(&value.render()),
+        _ => None,
+    }
+    .ok_or_else(|| Rekt::Type(format!("Expected decimal, but found {}", value)))
+}
+
+fn scale_arg<V: GmValue>(value: &V) -> Result<u32, Rekt> {
+    value
+        .as_number()
+        .filter(|n| n.fract() == 0.0 && *n >= 0.0 && *n <= MAX_SCALE as f64)
+        .map(|n| n as u32)
+        .ok_or_else(|| Rekt::Type(format!("Scale must be a whole number from 0 to {}, but found {}", MAX_SCALE, value.render())))
+}
+
+fn rounding_arg<V: GmValue>(value: Option<&V>) -> Result<Rounding, Rekt> {
+    match value.map(|v| (v, v.view())) {
+        None => Ok(Rounding::HalfEven),
+        Some((_, View::Text(mode))) => Rounding::parse(mode)
+            .ok_or_else(|| Rekt::Type(format!("Unknown rounding mode \"{}\"", mode))),
+        Some((other, _)) => Err(Rekt::Type(format!("Rounding mode must be text, but found {}", other))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chain::{builtin, Chain};
+    use crate::value::{array, text, Value};
+
+    fn numbers(ns: &[f64]) -> Value {
+        array(ns.iter().map(|n| Value::Number(*n)).collect())
+    }
+
+    #[test]
+    fn sort_compares_by_value() {
+        let ch = Chain::new(|_, _| {});
+        assert_eq!(builtin(&ch, "sort", vec![numbers(&[10.0, 9.0, 1.0])]).unwrap(), numbers(&[1.0, 9.0, 10.0]));
+        assert_eq!(builtin(&ch, "range", vec![Value::Number(3.0)]).unwrap(), numbers(&[0.0, 1.0, 2.0]));
+    }
+
+    #[test]
+    fn unknown_builtins_are_unknown_contracts() {
+        let ch = Chain::new(|_, _| {});
+        let err = builtin(&ch, "moon", vec![]).unwrap_err();
+        assert!(err.to_string().contains("Unknown smart contract 'moon'"), "{}", err);
+        assert!(builtin(&ch, "len", vec![text("gm"), text("gn")]).is_err());
+    }
+}
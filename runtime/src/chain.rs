@@ -0,0 +1,204 @@
+// What a running script carries around: where broadcasts go, the struct
+// shapes declared so far, and the cells contracts share bindings through.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use crate::builtins::NativeContracts;
+use crate::error::Rekt;
+use crate::slots::{read, set};
+use crate::value::{Contract, Record, Step, Type, Value};
+
+type Fields = Vec<(String, Option<Type>)>;
+type Emit = Box<dyn FnMut(&str, &Value)>;
+
+pub struct Chain {
+    emit: RefCell<Emit>,
+    structs: RefCell<HashMap<String, Fields>>,
+}
+
+impl Chain {
+    pub fn new(emit: impl FnMut(&str, &Value) + 'static) -> Self {
+        Chain {
+            emit: RefCell::new(Box::new(emit)),
+            structs: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn broadcast(&self, value: &Value) {
+        (self.emit.borrow_mut())(&value.render(), value);
+    }
+
+    pub fn declare(&self, name: &str, fields: Vec<(&str, Option<Type>)>) {
+        let fields = fields.into_iter().map(|(f, t)| (f.to_string(), t)).collect();
+        self.structs.borrow_mut().insert(name.to_string(), fields);
+    }
+
+    // Calls a contract value with already-evaluated arguments.
+    pub fn call(&self, contract: Value, args: Vec<Value>) -> Result<Value, Rekt> {
+        match contract {
+            Value::Contract(contract) => {
+                if contract.arity != args.len() {
+                    return Err(Rekt::Lexer(format!("Expected {} args, got {}.", contract.arity, args.len())));
+                }
+                (contract.body)(self, args).map_err(|e| e.traced(&format!("contract '{}'", contract.name)))
+            }
+            other => Err(Rekt::Lexer(format!("{} is not a contract", other))),
+        }
+    }
+
+    // A struct literal, its fields already evaluated in the order written.
+    // The transpiler has already turned away fields set twice.
+    pub fn record(&self, name: &str, given: Vec<(&str, Value)>) -> Result<Value, Rekt> {
+        let structs = self.structs.borrow();
+        let declared = structs
+            .get(name)
+            .ok_or_else(|| Rekt::Runtime(format!("Unknown struct '{}'", name)))?;
+        for (field, value) in &given {
+            if !declared.iter().any(|(f, _)| f == field) {
+                return Err(Rekt::Type(format!("'{}' has no field '{}'", name, field)));
+            }
+            check_field_type(declared, name, field, value)?;
+        }
+        let mut fields = Vec::new();
+        for (field, _) in declared {
+            let value = given
+                .iter()
+                .find(|(f, _)| f == field)
+                .map(|(_, v)| v.clone())
+                .ok_or_else(|| Rekt::Runtime(format!("Missing field '{}' in '{}'", field, name)))?;
+            fields.push((field.clone(), value));
+        }
+        Ok(Value::Struct(Rc::new(Record { name: name.to_string(), fields })))
+    }
+
+    // `target.field = value`, where `path` leads from `root` to the target.
+    // Fields of a declared struct keep their declared type.
+    pub fn set_field(&self, root: &mut Value, path: &[Step], field: &'static str, value: Value) -> Result<(), Rekt> {
+        if let Value::Struct(record) = read(root, path)? {
+            if let Some(declared) = self.structs.borrow().get(&record.name) {
+                check_field_type(declared, &record.name, field, &value)?;
+            }
+        }
+        let mut path = path.to_vec();
+        path.push(Step::Field(field));
+        set(root, &path, value)
+    }
+}
+
+// Builtins call contracts back through the chain they run on.
+impl NativeContracts<Value> for &Chain {
+    fn invoke(&mut self, contract: Value, args: Vec<Value>) -> Result<Value, Rekt> {
+        self.call(contract, args)
+    }
+}
+
+// Generated code only calls a builtin when the script never mints a
+// contract by that name, just as the tree walker only falls back to them.
+pub fn builtin(ch: &Chain, name: &str, args: Vec<Value>) -> Result<Value, Rekt> {
+    let mut ch = ch;
+    ch.call_builtin(name, args)
+        .unwrap_or_else(|| Err(Rekt::Lexer(format!("Unknown smart contract '{}'", name))))
+}
+
+fn check_field_type(declared: &Fields, record: &str, field: &str, value: &Value) -> Result<(), Rekt> {
+    let expected = declared.iter().find(|(f, _)| f == field).and_then(|(_, t)| t.as_ref());
+    match expected {
+        Some(expected) if !expected.accepts(value) => Err(Rekt::Type(format!(
+            "Field '{}.{}' expects {}, but found {}",
+            record, field, expected, value
+        ))),
+        _ => Ok(()),
+    }
+}
+
+// A binding that contracts share, or that may not be minted yet: one GM
+// wallet slot.
+#[derive(Clone, Default)]
+pub struct Cell(Rc<RefCell<Option<Value>>>);
+
+impl Cell {
+    pub fn new() -> Self {
+        Cell::default()
+    }
+
+    pub fn with(value: Value) -> Self {
+        Cell(Rc::new(RefCell::new(Some(value))))
+    }
+
+    pub fn get(&self, name: &str) -> Result<Value, Rekt> {
+        self.0
+            .borrow()
+            .clone()
+            .ok_or_else(|| Rekt::Lexer(format!("Token '{}' not found.", name)))
+    }
+
+    // Mints the binding, whether or not it was already.
+    pub fn set(&self, value: Value) {
+        *self.0.borrow_mut() = Some(value);
+    }
+
+    // Transfers to the binding, which has to be minted already.
+    pub fn put(&self, name: &str, value: Value) -> Result<(), Rekt> {
+        self.update(name, |slot| {
+            *slot = value;
+            Ok(())
+        })
+    }
+
+    pub fn update<R>(&self, name: &str, f: impl FnOnce(&mut Value) -> Result<R, Rekt>) -> Result<R, Rekt> {
+        match self.0.borrow_mut().as_mut() {
+            Some(slot) => f(slot),
+            None => Err(Rekt::Runtime(format!("Wallet address '{}' not found.", name))),
+        }
+    }
+
+    // Like `update`, but reading the binding first, so an unminted one is
+    // reported the way a read reports it.
+    pub fn update_minted<R>(&self, name: &str, f: impl FnOnce(&mut Value) -> Result<R, Rekt>) -> Result<R, Rekt> {
+        match self.0.borrow_mut().as_mut() {
+            Some(slot) => f(slot),
+            None => Err(Rekt::Lexer(format!("Token '{}' not found.", name))),
+        }
+    }
+}
+
+pub fn contract(
+    name: &str,
+    arity: usize,
+    body: impl Fn(&Chain, Vec<Value>) -> Result<Value, Rekt> + 'static,
+) -> Value {
+    Value::Contract(Contract { name: name.to_string(), arity, body: Rc::new(body) })
+}
+
+// A contract's arguments, which `Chain::call` has already counted.
+pub fn params<const N: usize>(args: Vec<Value>) -> [Value; N] {
+    args.try_into().unwrap_or_else(|_| std::array::from_fn(|_| Value::Null))
+}
+
+// `sus`, `grind` and `?` only take signals.
+pub fn cond(value: Value) -> Result<bool, Rekt> {
+    match value {
+        Value::Signal(b) => Ok(b),
+        _ => Err(Rekt::Lexer("Condition must be bullish or bearish (yes/no)".to_string())),
+    }
+}
+
+// The ways a binding can't be read or written, for the names the
+// transpiler can already tell about. Each one evaluates to the error.
+
+pub fn unminted(name: &str) -> Result<Value, Rekt> {
+    Err(Rekt::Lexer(format!("Token '{}' not found.", name)))
+}
+
+pub fn locked(name: &str, _value: Value) -> Result<Value, Rekt> {
+    Err(Rekt::Runtime(format!("'{}' is a block constant and can't be changed.", name)))
+}
+
+pub fn no_wallet(name: &str, _value: Value) -> Result<Value, Rekt> {
+    Err(Rekt::Runtime(format!("Wallet address '{}' not found.", name)))
+}
+
+pub fn unmatched(value: &Value) -> Result<Value, Rekt> {
+    Err(Rekt::Runtime(format!("No vibecheck arm matched {}", value.render())))
+}
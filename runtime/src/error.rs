@@ -0,0 +1,50 @@
+use std::fmt;
+
+// The four kinds of failure GM reports, from gm-language and from
+// transpiled code alike.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rekt {
+    Lexer(String),
+    Parser(String),
+    Runtime(String),
+    Type(String),
+}
+
+impl Rekt {
+    // Adds a line of call-stack context to the message, keeping the kind.
+    pub fn traced(self, frame: &str) -> Rekt {
+        self.map(|msg| format!("{}\n  ↳ in {}", msg, frame))
+    }
+
+    // Adds the source line the error came from. Only the innermost line of
+    // each call is kept, so a statement nested in a loop doesn't also get
+    // the loop's line.
+    pub fn at_line(self, line: usize) -> Rekt {
+        self.map(|msg| match msg.rsplit_once("\n  ↳ ") {
+            Some((_, last)) if last.starts_with("at line ") => msg,
+            _ => format!("{}\n  ↳ at line {}", msg, line),
+        })
+    }
+
+    fn map(self, f: impl FnOnce(String) -> String) -> Rekt {
+        match self {
+            Rekt::Lexer(msg) => Rekt::Lexer(f(msg)),
+            Rekt::Parser(msg) => Rekt::Parser(f(msg)),
+            Rekt::Runtime(msg) => Rekt::Runtime(f(msg)),
+            Rekt::Type(msg) => Rekt::Type(f(msg)),
+        }
+    }
+}
+
+impl fmt::Display for Rekt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rekt::Lexer(msg) => write!(f, "Lexer error: {}", msg),
+            Rekt::Parser(msg) => write!(f, "Parser error: {}", msg),
+            Rekt::Runtime(msg) => write!(f, "Runtime error: {}", msg),
+            Rekt::Type(msg) => write!(f, "Type error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Rekt {}
@@ -0,0 +1,124 @@
+// runtime/src/lib.rs
+//
+// What GM scripts transpiled to Rust run on. A generated module keeps the
+// shape of the script (contracts become closures, loops `for`/`while`,
+// vibechecks if/else chains) and hands everything whose meaning is GM's
+// own to this crate: every operator, indexing, struct checks and the
+// builtins. gm-language's tree walker and VM run the very same code over
+// their own values (see `GmValue`), so a transpiled script does exactly
+// what `gm-language --tree` does, down to the error messages.
+//
+// Values map onto Rust like this:
+//
+//   number (vibe)   -> f64           uint     -> U256
+//   text            -> String        decimal  -> Decimal
+//   signal          -> bool          array    -> Rc<Vec<Value>>
+//   lonely          -> Value::Null   mapping  -> Rc<BTreeMap<Key, Value>>
+//   struct          -> Rc<Record>    contract -> Contract (a closure)
+//
+// Collections are shared until written to, and copied then if anything
+// else still holds them, which is what gives GM its copy semantics.
+
+pub mod numeric;
+pub mod patterns;
+pub mod slots;
+
+mod builtins;
+mod chain;
+mod error;
+mod ops;
+mod shape;
+mod value;
+
+pub use builtins::{NativeContracts, BUILTINS};
+pub use chain::{builtin, cond, contract, locked, no_wallet, params, unmatched, unminted, Cell, Chain};
+pub use error::Rekt;
+pub use numeric::{Decimal, Rounding, U256};
+pub use ops::{
+    add, and, binary, bitand, bitnot, bitor, bitxor, chop, compare, div, eq, ge, gt, le, lt, mul, ne, not, or, rem,
+    shl, shr, sub, unary,
+};
+pub use patterns::{bind, matches, Bound};
+pub use shape::{GmValue, Key, View, ViewMut};
+pub use slots::{bound, delete, field, index, items, pairs, read, set, steps};
+pub use value::{array, dec, mapping, text, uint, Contract, Pattern, Record, Step, Type, Value};
+
+// Generated code calls the generic functions with nothing but `Value`s and
+// literals around them, so each call here is spelled the way the Rust
+// transpiler writes it and has to infer on its own.
+#[cfg(test)]
+mod tests {
+    use crate::{self as gm, Cell, Chain, Step, Value};
+
+    #[test]
+    fn empty_literals_infer() -> Result<(), gm::Rekt> {
+        let m = gm::mapping(vec![])?;
+        let ch = Chain::new(|_, _| {});
+        assert_eq!(gm::builtin(&ch, "len", vec![m.clone()])?, Value::Number(0.0));
+        assert_eq!(gm::builtin(&ch, "len", vec![gm::array(vec![])])?, Value::Number(0.0));
+        assert!(gm::matches(&gm::mapping(vec![])?, &gm::Pattern::Wildcard).is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn operators_infer() -> Result<(), gm::Rekt> {
+        assert_eq!(gm::add(Value::Number(1.0), Value::Number(2.0))?, Value::Number(3.0));
+        assert_eq!(gm::chop(gm::uint("7"), gm::uint("2"))?, gm::uint("3"));
+        assert_eq!(gm::eq(gm::text("a"), gm::text("a"))?, Value::Signal(true));
+        assert_eq!(gm::not(Value::Signal(true))?, Value::Signal(false));
+        assert_eq!(gm::bitnot(gm::uint("0"))?, gm::bitnot(gm::uint("0"))?);
+        Ok(())
+    }
+
+    #[test]
+    fn reads_infer() -> Result<(), gm::Rekt> {
+        let m = gm::mapping(vec![(gm::text("a"), gm::array(vec![Value::Number(1.0)]))])?;
+        assert_eq!(gm::index(m.clone(), gm::text("a"))?, gm::array(vec![Value::Number(1.0)]));
+        let path = [Step::Key(gm::text("a")), Step::Key(Value::Number(0.0))];
+        assert_eq!(gm::read(&m, &path)?, Value::Number(1.0));
+        let ch = Chain::new(|_, _| {});
+        ch.declare("P", vec![("x", None)]);
+        let record = ch.record("P", vec![("x", Value::Number(2.0))])?;
+        assert_eq!(gm::field(record, "x")?, Value::Number(2.0));
+        Ok(())
+    }
+
+    #[test]
+    fn writes_infer() -> Result<(), gm::Rekt> {
+        let mut m = gm::mapping(vec![])?;
+        let path = [Step::Key(gm::text("a"))];
+        gm::set(&mut m, &path, Value::Number(1.0))?;
+        let cell = Cell::with(m);
+        cell.update("m", |root| gm::delete(root, &path))?;
+        assert_eq!(cell.get("m")?, gm::mapping(vec![])?);
+        Ok(())
+    }
+
+    #[test]
+    fn loops_infer() -> Result<(), gm::Rekt> {
+        let mut seen = 0.0;
+        for mut item in gm::items(gm::array(vec![Value::Number(1.0)]))? {
+            item = gm::add(item, Value::Number(1.0))?;
+            seen += gm::bound(&item, "item")?;
+        }
+        for (_, mut item) in gm::pairs(gm::mapping(vec![(gm::text("k"), Value::Number(2.0))])?)? {
+            item = gm::mul(item, Value::Number(2.0))?;
+            seen += gm::bound(&item, "item")?;
+        }
+        for mut i in gm::steps(Value::Number(0.0), Value::Number(3.0), Value::Number(1.0))? {
+            i = gm::sub(i, Value::Number(0.0))?;
+            seen += gm::bound(&i, "i")?;
+        }
+        assert_eq!(seen, 9.0);
+        Ok(())
+    }
+
+    #[test]
+    fn patterns_infer() {
+        let pattern = gm::Pattern::Array { items: vec![gm::Pattern::Binding("head")], rest: Some("rest") };
+        let Some(mut bound) = gm::matches(&gm::array(vec![Value::Number(1.0)]), &pattern) else {
+            panic!("[head, ..rest] should match [1]");
+        };
+        assert_eq!(bound.take("head"), Value::Number(1.0));
+    }
+}
@@ -0,0 +1,280 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+// How a division or rescale settles the digits it can't keep.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rounding {
+    Down,     // toward zero
+    Up,       // away from zero
+    HalfUp,   // nearest, ties away from zero
+    HalfEven, // nearest, ties to the even digit (banker's rounding)
+    Floor,    // toward negative infinity
+    Ceiling,  // toward positive infinity
+}
+
+impl Rounding {
+    pub fn parse(name: &str) -> Option<Rounding> {
+        match name {
+            "down" => Some(Rounding::Down),
+            "up" => Some(Rounding::Up),
+            "half_up" => Some(Rounding::HalfUp),
+            "half_even" => Some(Rounding::HalfEven),
+            "floor" => Some(Rounding::Floor),
+            "ceil" => Some(Rounding::Ceiling),
+            _ => None,
+        }
+    }
+}
+
+// Fixed-point decimal: `units / 10^scale`. The scale is part of the value,
+// so `1.50d` stays "1.50" when printed, but it doesn't affect equality.
+#[derive(Clone, Copy, Debug)]
+pub struct Decimal {
+    units: i128,
+    scale: u32,
+}
+
+// Past this many fractional digits an i128 has little room left for the
+// whole part anyway.
+pub const MAX_SCALE: u32 = 30;
+
+fn pow10(exp: u32) -> Option<i128> {
+    10i128.checked_pow(exp)
+}
+
+impl Decimal {
+    pub fn new(units: i128, scale: u32) -> Option<Decimal> {
+        if scale > MAX_SCALE {
+            None
+        } else {
+            Some(Decimal { units, scale })
+        }
+    }
+
+    pub fn units(&self) -> i128 {
+        self.units
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.units == 0
+    }
+
+    // Parses `-12.340`; the number of fractional digits becomes the scale.
+    pub fn parse(text: &str) -> Option<Decimal> {
+        let text = text.trim().replace('_', "");
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text.as_str()),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if whole.is_empty() || !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let units: i128 = format!("{}{}", whole, fraction).parse().ok()?;
+        Decimal::new(if negative { -units } else { units }, fraction.len() as u32)
+    }
+
+    // The same value written with `scale` fractional digits, if that only
+    // adds zeros.
+    fn widen(self, scale: u32) -> Option<Decimal> {
+        let factor = pow10(scale.checked_sub(self.scale)?)?;
+        Decimal::new(self.units.checked_mul(factor)?, scale)
+    }
+
+    fn align(self, other: Decimal) -> Option<(i128, i128, u32)> {
+        let scale = self.scale.max(other.scale);
+        Some((self.widen(scale)?.units, other.widen(scale)?.units, scale))
+    }
+
+    pub fn checked_add(self, other: Decimal) -> Option<Decimal> {
+        let (a, b, scale) = self.align(other)?;
+        Decimal::new(a.checked_add(b)?, scale)
+    }
+
+    pub fn checked_sub(self, other: Decimal) -> Option<Decimal> {
+        let (a, b, scale) = self.align(other)?;
+        Decimal::new(a.checked_sub(b)?, scale)
+    }
+
+    pub fn checked_mul(self, other: Decimal) -> Option<Decimal> {
+        Decimal::new(self.units.checked_mul(other.units)?, self.scale + other.scale)
+    }
+
+    // self / other with `scale` fractional digits. None on a zero divisor
+    // or when the result doesn't fit.
+    pub fn checked_div(self, other: Decimal, scale: u32, rounding: Rounding) -> Option<Decimal> {
+        if other.is_zero() {
+            return None;
+        }
+        // units = a * 10^(sb + scale) / (b * 10^sa)
+        let numerator = self.units.checked_mul(pow10(other.scale + scale)?)?;
+        let denominator = other.units.checked_mul(pow10(self.scale)?)?;
+        Decimal::new(divide(numerator, denominator, rounding), scale)
+    }
+
+    pub fn round(self, scale: u32, rounding: Rounding) -> Option<Decimal> {
+        if scale >= self.scale {
+            return self.widen(scale);
+        }
+        let units = divide(self.units, pow10(self.scale - scale)?, rounding);
+        Decimal::new(units, scale)
+    }
+
+    // The value as a whole number, if it has no fractional part.
+    pub fn whole(&self) -> Option<i128> {
+        let factor = pow10(self.scale)?;
+        if self.units % factor == 0 {
+            Some(self.units / factor)
+        } else {
+            None
+        }
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+}
+
+// Integer division that rounds the way it's told to instead of truncating.
+fn divide(n: i128, d: i128, rounding: Rounding) -> i128 {
+    let (q, r) = (n / d, n % d);
+    if r == 0 {
+        return q;
+    }
+    let negative = (n < 0) != (d < 0);
+    let away = if negative { q - 1 } else { q + 1 };
+    // Compare the remainder against half the divisor without overflowing
+    let twice = r.unsigned_abs().saturating_mul(2);
+    let half = twice.cmp(&d.unsigned_abs());
+    let round_away = match rounding {
+        Rounding::Down => false,
+        Rounding::Up => true,
+        Rounding::Floor => negative,
+        Rounding::Ceiling => !negative,
+        Rounding::HalfUp => half != Ordering::Less,
+        Rounding::HalfEven => half == Ordering::Greater || (half == Ordering::Equal && q % 2 != 0),
+    };
+    if round_away {
+        away
+    } else {
+        q
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.align(*other) {
+            Some((a, b, _)) => a.cmp(&b),
+            // Too wide to line up exactly; floats are close enough to order
+            None => self.to_f64().total_cmp(&other.to_f64()),
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.units.unsigned_abs().to_string();
+        let scale = self.scale as usize;
+        let padded = format!("{:0>width$}", digits, width = scale + 1);
+        let (whole, fraction) = padded.split_at(padded.len() - scale);
+        let sign = if self.units < 0 { "-" } else { "" };
+        if fraction.is_empty() {
+            write!(f, "{}{}", sign, whole)
+        } else {
+            write!(f, "{}{}.{}", sign, whole, fraction)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODES: [Rounding; 6] = [
+        Rounding::Down,
+        Rounding::Up,
+        Rounding::HalfUp,
+        Rounding::HalfEven,
+        Rounding::Floor,
+        Rounding::Ceiling,
+    ];
+
+    fn d(text: &str) -> Decimal {
+        Decimal::parse(text).unwrap()
+    }
+
+    // Each value rounded to a whole number under MODES, in order.
+    #[test]
+    fn every_mode_on_ties_and_negatives() {
+        let table: [(&str, [&str; 6]); 8] = [
+            ("2.5", ["2", "3", "3", "2", "2", "3"]),
+            ("-2.5", ["-2", "-3", "-3", "-2", "-3", "-2"]),
+            ("3.5", ["3", "4", "4", "4", "3", "4"]),
+            ("-3.5", ["-3", "-4", "-4", "-4", "-4", "-3"]),
+            ("2.4", ["2", "3", "2", "2", "2", "3"]),
+            ("-2.4", ["-2", "-3", "-2", "-2", "-3", "-2"]),
+            ("-2.6", ["-2", "-3", "-3", "-3", "-3", "-2"]),
+            ("-0.5", ["0", "-1", "-1", "0", "-1", "0"]),
+        ];
+        for (value, expected) in table {
+            for (mode, want) in MODES.iter().zip(expected) {
+                let got = d(value).round(0, *mode).unwrap();
+                assert_eq!(got.to_string(), want, "{} rounded {:?}", value, mode);
+            }
+        }
+    }
+
+    #[test]
+    fn division_rounds_negative_quotients() {
+        let (one, three) = (d("-1"), d("3"));
+        let expected = ["-0.33", "-0.34", "-0.33", "-0.33", "-0.34", "-0.33"];
+        for (mode, want) in MODES.iter().zip(expected) {
+            assert_eq!(one.checked_div(three, 2, *mode).unwrap().to_string(), want, "{:?}", mode);
+        }
+        // -0.125 sits exactly between -0.12 and -0.13
+        let tie = d("-1").checked_div(d("8"), 2, Rounding::HalfEven).unwrap();
+        assert_eq!(tie.to_string(), "-0.12");
+        let tie = d("-1").checked_div(d("8"), 2, Rounding::HalfUp).unwrap();
+        assert_eq!(tie.to_string(), "-0.13");
+    }
+
+    #[test]
+    fn widening_the_scale_only_adds_zeros() {
+        let price = d("-1.5");
+        for mode in MODES {
+            let wide = price.round(4, mode).unwrap();
+            assert_eq!(wide.to_string(), "-1.5000");
+            assert_eq!(wide, price);
+        }
+        assert_eq!(d("1.50").checked_add(d("0.125")).unwrap().to_string(), "1.625");
+        assert_eq!(d("1").round(MAX_SCALE + 1, Rounding::Down), None);
+        assert_eq!(d("170141183460469231731687303715884105727").round(1, Rounding::Down), None);
+    }
+
+    #[test]
+    fn dividing_by_zero_is_none() {
+        for mode in MODES {
+            assert_eq!(d("1.5").checked_div(d("0"), 2, mode), None);
+            assert_eq!(d("-1.5").checked_div(d("0.00"), 2, mode), None);
+            assert_eq!(d("0").checked_div(d("0"), 0, mode), None);
+        }
+    }
+}
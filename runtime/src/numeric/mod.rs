@@ -0,0 +1,10 @@
+// Exact number types that sit alongside the float-backed tokens.
+
+mod decimal;
+// Named like the std::ops methods on purpose, but kept as plain methods so
+// every operation stays checked or spelled out where it's used.
+#[allow(clippy::should_implement_trait)]
+mod u256;
+
+pub use decimal::{Decimal, Rounding, MAX_SCALE};
+pub use u256::U256;
@@ -0,0 +1,445 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+// Unsigned 256-bit integer, the width Solidity uses for token amounts.
+// Limbs are little-endian: limbs[0] holds the lowest 64 bits.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct U256 {
+    limbs: [u64; 4],
+}
+
+impl U256 {
+    pub const ZERO: U256 = U256 { limbs: [0; 4] };
+    pub const ONE: U256 = U256 { limbs: [1, 0, 0, 0] };
+    pub const MAX: U256 = U256 { limbs: [u64::MAX; 4] };
+
+    pub fn from_u64(n: u64) -> U256 {
+        U256 { limbs: [n, 0, 0, 0] }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs == [0; 4]
+    }
+
+    // The value as a u64, if it fits.
+    pub fn as_u64(&self) -> Option<u64> {
+        if self.limbs[1..] == [0; 3] {
+            Some(self.limbs[0])
+        } else {
+            None
+        }
+    }
+
+    // Number of significant bits.
+    pub fn bits(&self) -> u32 {
+        for i in (0..4).rev() {
+            if self.limbs[i] != 0 {
+                return 64 * i as u32 + (64 - self.limbs[i].leading_zeros());
+            }
+        }
+        0
+    }
+
+    fn bit(&self, n: u32) -> bool {
+        self.limbs[(n / 64) as usize] >> (n % 64) & 1 == 1
+    }
+
+    pub fn overflowing_add(self, other: U256) -> (U256, bool) {
+        let mut limbs = [0u64; 4];
+        let mut carry = false;
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let (sum, c1) = self.limbs[i].overflowing_add(other.limbs[i]);
+            let (sum, c2) = sum.overflowing_add(carry as u64);
+            *limb = sum;
+            carry = c1 || c2;
+        }
+        (U256 { limbs }, carry)
+    }
+
+    pub fn overflowing_sub(self, other: U256) -> (U256, bool) {
+        let mut limbs = [0u64; 4];
+        let mut borrow = false;
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let (diff, b1) = self.limbs[i].overflowing_sub(other.limbs[i]);
+            let (diff, b2) = diff.overflowing_sub(borrow as u64);
+            *limb = diff;
+            borrow = b1 || b2;
+        }
+        (U256 { limbs }, borrow)
+    }
+
+    pub fn overflowing_mul(self, other: U256) -> (U256, bool) {
+        // Schoolbook multiplication into eight limbs; anything above the
+        // lower four is overflow.
+        let mut wide = [0u64; 8];
+        for i in 0..4 {
+            let mut carry = 0u128;
+            for j in 0..4 {
+                let cell = wide[i + j] as u128 + self.limbs[i] as u128 * other.limbs[j] as u128 + carry;
+                wide[i + j] = cell as u64;
+                carry = cell >> 64;
+            }
+            wide[i + 4] = carry as u64;
+        }
+        let overflow = wide[4..] != [0; 4];
+        (U256 { limbs: [wide[0], wide[1], wide[2], wide[3]] }, overflow)
+    }
+
+    pub fn checked_add(self, other: U256) -> Option<U256> {
+        match self.overflowing_add(other) {
+            (sum, false) => Some(sum),
+            _ => None,
+        }
+    }
+
+    pub fn checked_sub(self, other: U256) -> Option<U256> {
+        match self.overflowing_sub(other) {
+            (diff, false) => Some(diff),
+            _ => None,
+        }
+    }
+
+    pub fn checked_mul(self, other: U256) -> Option<U256> {
+        match self.overflowing_mul(other) {
+            (product, false) => Some(product),
+            _ => None,
+        }
+    }
+
+    pub fn checked_div(self, other: U256) -> Option<U256> {
+        self.div_rem(other).map(|(q, _)| q)
+    }
+
+    pub fn checked_rem(self, other: U256) -> Option<U256> {
+        self.div_rem(other).map(|(_, r)| r)
+    }
+
+    pub fn checked_pow(self, mut exp: u32) -> Option<U256> {
+        let mut base = self;
+        let mut result = U256::ONE;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result.checked_mul(base)?;
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = base.checked_mul(base)?;
+            }
+        }
+        Some(result)
+    }
+
+    pub fn wrapping_add(self, other: U256) -> U256 {
+        self.overflowing_add(other).0
+    }
+
+    pub fn wrapping_sub(self, other: U256) -> U256 {
+        self.overflowing_sub(other).0
+    }
+
+    pub fn wrapping_mul(self, other: U256) -> U256 {
+        self.overflowing_mul(other).0
+    }
+
+    pub fn saturating_add(self, other: U256) -> U256 {
+        self.checked_add(other).unwrap_or(U256::MAX)
+    }
+
+    pub fn saturating_sub(self, other: U256) -> U256 {
+        self.checked_sub(other).unwrap_or(U256::ZERO)
+    }
+
+    pub fn saturating_mul(self, other: U256) -> U256 {
+        self.checked_mul(other).unwrap_or(U256::MAX)
+    }
+
+    // Quotient and remainder, or None when dividing by zero.
+    pub fn div_rem(self, divisor: U256) -> Option<(U256, U256)> {
+        if divisor.is_zero() {
+            return None;
+        }
+        if let (Some(a), Some(b)) = (self.as_u64(), divisor.as_u64()) {
+            return Some((U256::from_u64(a / b), U256::from_u64(a % b)));
+        }
+        // Shift-and-subtract long division, one bit at a time.
+        let mut quotient = U256::ZERO;
+        let mut remainder = U256::ZERO;
+        for n in (0..self.bits()).rev() {
+            remainder = remainder.shl(1);
+            if self.bit(n) {
+                remainder.limbs[0] |= 1;
+            }
+            if remainder >= divisor {
+                remainder = remainder.wrapping_sub(divisor);
+                quotient.limbs[(n / 64) as usize] |= 1 << (n % 64);
+            }
+        }
+        Some((quotient, remainder))
+    }
+
+    pub fn shl(self, shift: u32) -> U256 {
+        if shift >= 256 {
+            return U256::ZERO;
+        }
+        let (words, bits) = ((shift / 64) as usize, shift % 64);
+        let mut limbs = [0u64; 4];
+        for i in (words..4).rev() {
+            limbs[i] = self.limbs[i - words] << bits;
+            if bits > 0 && i > words {
+                limbs[i] |= self.limbs[i - words - 1] >> (64 - bits);
+            }
+        }
+        U256 { limbs }
+    }
+
+    pub fn shr(self, shift: u32) -> U256 {
+        if shift >= 256 {
+            return U256::ZERO;
+        }
+        let (words, bits) = ((shift / 64) as usize, shift % 64);
+        let mut limbs = [0u64; 4];
+        for (i, limb) in limbs.iter_mut().enumerate().take(4 - words) {
+            *limb = self.limbs[i + words] >> bits;
+            if bits > 0 && i + words + 1 < 4 {
+                *limb |= self.limbs[i + words + 1] << (64 - bits);
+            }
+        }
+        U256 { limbs }
+    }
+
+    pub fn bitand(self, other: U256) -> U256 {
+        self.zip(other, |a, b| a & b)
+    }
+
+    pub fn bitor(self, other: U256) -> U256 {
+        self.zip(other, |a, b| a | b)
+    }
+
+    pub fn bitxor(self, other: U256) -> U256 {
+        self.zip(other, |a, b| a ^ b)
+    }
+
+    pub fn not(self) -> U256 {
+        self.zip(U256::ZERO, |a, _| !a)
+    }
+
+    fn zip(self, other: U256, f: impl Fn(u64, u64) -> u64) -> U256 {
+        let mut limbs = [0u64; 4];
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            *limb = f(self.limbs[i], other.limbs[i]);
+        }
+        U256 { limbs }
+    }
+
+    // Parses digits in the given radix; `_` separators are allowed.
+    pub fn from_str_radix(digits: &str, radix: u32) -> Option<U256> {
+        let base = U256::from_u64(radix as u64);
+        let mut value = U256::ZERO;
+        let mut seen = false;
+        for c in digits.chars() {
+            if c == '_' {
+                continue;
+            }
+            let digit = c.to_digit(radix)?;
+            value = value.checked_mul(base)?.checked_add(U256::from_u64(digit as u64))?;
+            seen = true;
+        }
+        if seen {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    // Parses decimal text, or hex/binary/octal with a 0x/0b/0o prefix.
+    pub fn parse(text: &str) -> Option<U256> {
+        let text = text.trim();
+        match text.get(..2) {
+            Some("0x") | Some("0X") => U256::from_str_radix(&text[2..], 16),
+            Some("0b") => U256::from_str_radix(&text[2..], 2),
+            Some("0o") => U256::from_str_radix(&text[2..], 8),
+            _ => U256::from_str_radix(text, 10),
+        }
+    }
+
+    // Big-endian bytes, the way the EVM and the .gmc format store a word.
+    pub fn to_be_bytes(self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (i, limb) in self.limbs.iter().rev().enumerate() {
+            bytes[8 * i..8 * i + 8].copy_from_slice(&limb.to_be_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_be_bytes(bytes: [u8; 32]) -> U256 {
+        let mut limbs = [0u64; 4];
+        for (i, limb) in limbs.iter_mut().rev().enumerate() {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(&bytes[8 * i..8 * i + 8]);
+            *limb = u64::from_be_bytes(word);
+        }
+        U256 { limbs }
+    }
+
+    // Nearest float; large values lose precision the way any f64 does.
+    pub fn to_f64(self) -> f64 {
+        self.limbs
+            .iter()
+            .rev()
+            .fold(0.0, |acc, limb| acc * 18446744073709551616.0 + *limb as f64)
+    }
+
+    // Exact conversion from a float: it must be whole, non-negative and fit.
+    pub fn from_f64(n: f64) -> Option<U256> {
+        if !n.is_finite() || n < 0.0 || n.fract() != 0.0 || n >= 2f64.powi(256) {
+            return None;
+        }
+        if n < 2f64.powi(64) {
+            return Some(U256::from_u64(n as u64));
+        }
+        // Split into mantissa and exponent so nothing is rounded on the way.
+        let bits = n.to_bits();
+        let exponent = ((bits >> 52) & 0x7ff) as u32 - 1075;
+        let mantissa = (bits & ((1 << 52) - 1)) | (1 << 52);
+        Some(U256::from_u64(mantissa).shl(exponent))
+    }
+}
+
+impl Ord for U256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs.iter().rev().cmp(other.limbs.iter().rev())
+    }
+}
+
+impl PartialOrd for U256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        // Peel off 19 decimal digits at a time, the most a u64 chunk holds.
+        let chunk = U256::from_u64(10_000_000_000_000_000_000);
+        let mut parts = Vec::new();
+        let mut rest = *self;
+        while !rest.is_zero() {
+            let (q, r) = rest.div_rem(chunk).expect("chunk is non-zero");
+            parts.push(r.limbs[0]);
+            rest = q;
+        }
+        let mut text = parts.pop().unwrap_or(0).to_string();
+        for part in parts.iter().rev() {
+            text.push_str(&format!("{:019}", part));
+        }
+        f.pad(&text)
+    }
+}
+
+impl fmt::LowerHex for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut text = String::new();
+        for limb in self.limbs.iter().rev() {
+            if text.is_empty() {
+                if *limb != 0 {
+                    text = format!("{:x}", limb);
+                }
+            } else {
+                text.push_str(&format!("{:016x}", limb));
+            }
+        }
+        if text.is_empty() {
+            text.push('0');
+        }
+        f.pad(&text)
+    }
+}
+
+impl fmt::Debug for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX_DECIMAL: &str =
+        "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+
+    fn n(text: &str) -> U256 {
+        U256::parse(text).unwrap()
+    }
+
+    #[test]
+    fn max_plus_one_overflows() {
+        assert_eq!(U256::MAX.checked_add(U256::ONE), None);
+        assert_eq!(U256::MAX.wrapping_add(U256::ONE), U256::ZERO);
+        assert_eq!(U256::MAX.saturating_add(U256::ONE), U256::MAX);
+        assert_eq!(U256::MAX.overflowing_add(U256::ONE), (U256::ZERO, true));
+    }
+
+    #[test]
+    fn zero_minus_one_underflows() {
+        assert_eq!(U256::ZERO.checked_sub(U256::ONE), None);
+        assert_eq!(U256::ZERO.wrapping_sub(U256::ONE), U256::MAX);
+        assert_eq!(U256::ZERO.saturating_sub(U256::ONE), U256::ZERO);
+        assert_eq!(U256::ZERO.overflowing_sub(U256::ONE), (U256::MAX, true));
+    }
+
+    #[test]
+    fn multiplication_past_max() {
+        let two = U256::from_u64(2);
+        assert_eq!(U256::MAX.checked_mul(two), None);
+        assert_eq!(U256::MAX.wrapping_mul(two), U256::MAX.wrapping_sub(U256::ONE));
+        assert_eq!(U256::MAX.saturating_mul(two), U256::MAX);
+        let half = U256::ONE.shl(128);
+        assert_eq!(half.checked_mul(half), None);
+        assert_eq!(half.wrapping_mul(half), U256::ZERO);
+        assert_eq!(U256::MAX.checked_mul(U256::ONE), Some(U256::MAX));
+    }
+
+    #[test]
+    fn division_by_one_and_max() {
+        let x = n("123456789012345678901234567890");
+        assert_eq!(x.div_rem(U256::ONE), Some((x, U256::ZERO)));
+        assert_eq!(U256::MAX.div_rem(U256::ONE), Some((U256::MAX, U256::ZERO)));
+        assert_eq!(x.div_rem(U256::MAX), Some((U256::ZERO, x)));
+        assert_eq!(U256::MAX.div_rem(U256::MAX), Some((U256::ONE, U256::ZERO)));
+        assert_eq!(x.checked_div(U256::ZERO), None);
+        assert_eq!(x.checked_rem(U256::ZERO), None);
+    }
+
+    #[test]
+    fn parse_rejects_overflow() {
+        assert_eq!(U256::parse(MAX_DECIMAL), Some(U256::MAX));
+        assert_eq!(
+            U256::parse("115792089237316195423570985008687907853269984665640564039457584007913129639936"),
+            None
+        );
+        assert_eq!(U256::parse(&format!("0x{}", "f".repeat(64))), Some(U256::MAX));
+        assert_eq!(U256::parse(&format!("0x1{}", "0".repeat(64))), None);
+        assert_eq!(U256::parse(&format!("0b1{}", "0".repeat(256))), None);
+        assert_eq!(U256::parse(""), None);
+        assert_eq!(U256::parse("0x"), None);
+        assert_eq!(U256::parse("12a"), None);
+    }
+
+    #[test]
+    fn text_and_hex_round_trip() {
+        for text in ["0", "1", "18446744073709551616", "1000000000000000000000000", MAX_DECIMAL] {
+            assert_eq!(n(text).to_string(), text);
+        }
+        for hex in ["0", "ff", "10000000000000000", "deadbeef00000000cafebabe00000000"] {
+            assert_eq!(format!("{:x}", n(&format!("0x{}", hex))), hex);
+        }
+        assert_eq!(format!("{:x}", U256::MAX), "f".repeat(64));
+        assert_eq!(n("1_000_000"), U256::from_u64(1_000_000));
+        let x = n("0x0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef");
+        assert_eq!(U256::from_be_bytes(x.to_be_bytes()), x);
+    }
+}
@@ -0,0 +1,377 @@
+// The operators, with the tree walker's rules: decimals win over uints,
+// uints over plain numbers, and numbers only meet a uint when they convert
+// exactly. gm-language dispatches on the operator's symbol through
+// `binary` and `unary`; generated code calls the named functions.
+
+use std::cmp::Ordering;
+use crate::error::Rekt;
+use crate::numeric::{Decimal, Rounding, U256};
+use crate::shape::{same_number, GmValue, View};
+
+// Floats hold every whole number up to 2^53 exactly, so those can meet a
+// uint without anything being rounded.
+const EXACT_FLOAT: f64 = 9007199254740992.0;
+
+// Applies a binary operator, named by its symbol, to two evaluated operands.
+pub fn binary<V: GmValue>(l: V, symbol: &str, r: V) -> Result<V, Rekt> {
+    match symbol {
+        "+" | "-" | "*" | "/" => arithmetic(&l, symbol, &r),
+        "<" => Ok(V::from_signal(ordering(&l, symbol, &r)? == Ordering::Less)),
+        ">" => Ok(V::from_signal(ordering(&l, symbol, &r)? == Ordering::Greater)),
+        "<=" => Ok(V::from_signal(ordering(&l, symbol, &r)? != Ordering::Greater)),
+        ">=" => Ok(V::from_signal(ordering(&l, symbol, &r)? != Ordering::Less)),
+        "==" => Ok(V::from_signal(equal(&l, symbol, &r)?)),
+        "!=" => Ok(V::from_signal(!equal(&l, symbol, &r)?)),
+        "%" | "~/" | "&" | "|" | "^" | "<<" | ">>" => integer_arithmetic(&l, symbol, &r),
+        // `and` and `or` are parsed, but not yet given a meaning
+        _ => Err(Rekt::Lexer("Op not supported yet.".to_string())),
+    }
+}
+
+// Prefix operators: `not` on signals and `~` on integers.
+pub fn unary<V: GmValue>(symbol: &str, value: V) -> Result<V, Rekt> {
+    match (symbol, value.view()) {
+        ("not", View::Signal(b)) => Ok(V::from_signal(!b)),
+        ("not", _) => Err(Rekt::Type(format!("'not' needs a signal, but found {}", value))),
+        ("~", View::Uint(n)) => Ok(V::from_uint(n.not())),
+        ("~", _) => Ok(V::from_number(!whole(&value, symbol)? as f64)),
+        _ => Err(Rekt::Runtime(format!("'{}' is not a prefix operator", symbol))),
+    }
+}
+
+// The uint operands of a binary operation, if either side is a uint. The
+// other side has to be a uint too, or a number that converts exactly.
+fn uint_operands<V: GmValue>(l: &V, r: &V, symbol: &str) -> Result<Option<(U256, U256)>, Rekt> {
+    match (l.view(), r.view()) {
+        (View::Uint(a), View::Uint(b)) => Ok(Some((a, b))),
+        (View::Uint(a), _) => Ok(Some((a, promote(r, symbol)?))),
+        (_, View::Uint(b)) => Ok(Some((promote(l, symbol)?, b))),
+        _ => Ok(None),
+    }
+}
+
+fn promote<V: GmValue>(value: &V, symbol: &str) -> Result<U256, Rekt> {
+    let exact = match value.view() {
+        View::Vibe(n) if n <= EXACT_FLOAT => U256::from_f64(n),
+        _ => None,
+    };
+    exact.ok_or_else(|| {
+        Rekt::Type(format!(
+            "Cannot use '{}' on uint and {} {}, convert with uint() or float() first",
+            symbol,
+            value,
+            value.render()
+        ))
+    })
+}
+
+// The decimal operands of a binary operation, if either side is a decimal.
+fn decimal_operands<V: GmValue>(l: &V, r: &V, symbol: &str) -> Result<Option<(Decimal, Decimal)>, Rekt> {
+    match (l.view(), r.view()) {
+        (View::Decimal(a), View::Decimal(b)) => Ok(Some((a, b))),
+        (View::Decimal(a), _) => Ok(Some((a, to_decimal(r, symbol)?))),
+        (_, View::Decimal(b)) => Ok(Some((to_decimal(l, symbol)?, b))),
+        _ => Ok(None),
+    }
+}
+
+// Numbers and uints join a decimal at the exact value they print as.
+fn to_decimal<V: GmValue>(value: &V, symbol: &str) -> Result<Decimal, Rekt> {
+    let exact = match value.view() {
+        View::Vibe(_) | View::Uint(_) => Decimal::parse(&value.render()),
+        _ => None,
+    };
+    exact.ok_or_else(|| Rekt::Type(format!("Cannot use '{}' on decimal and {} {}", symbol, value, value.render())))
+}
+
+// Exact decimal arithmetic. Division keeps the wider of the two scales and
+// rounds half-even; `div()` picks both explicitly.
+fn decimal_arithmetic<V: GmValue>(a: Decimal, symbol: &str, b: Decimal) -> Result<V, Rekt> {
+    let result = match symbol {
+        "+" => a.checked_add(b),
+        "-" => a.checked_sub(b),
+        "*" => a.checked_mul(b),
+        _ if b.is_zero() => return Err(Rekt::Runtime("You just got rekt by zero!".to_string())),
+        _ => a.checked_div(b, a.scale().max(b.scale()), Rounding::HalfEven),
+    };
+    result
+        .map(V::from_decimal)
+        .ok_or_else(|| Rekt::Runtime(format!("decimal overflow: {} {} {} 📉", a, symbol, b)))
+}
+
+// Checked uint arithmetic: results that leave 0..2^256 are errors.
+fn uint_arithmetic<V: GmValue>(a: U256, symbol: &str, b: U256) -> Result<V, Rekt> {
+    let result = match symbol {
+        "+" => a.checked_add(b),
+        "-" => a.checked_sub(b),
+        "*" => a.checked_mul(b),
+        _ if b.is_zero() => return Err(Rekt::Runtime("You just got rekt by zero!".to_string())),
+        _ => a.checked_div(b),
+    };
+    result.map(V::from_uint).ok_or_else(|| {
+        let what = if symbol == "-" { "underflow" } else { "overflow" };
+        Rekt::Runtime(format!("uint {}: {} {} {} 📉", what, a, symbol, b))
+    })
+}
+
+fn arithmetic<V: GmValue>(l: &V, symbol: &str, r: &V) -> Result<V, Rekt> {
+    if let Some((a, b)) = decimal_operands(l, r, symbol)? {
+        return decimal_arithmetic(a, symbol, b);
+    }
+    if let Some((a, b)) = uint_operands(l, r, symbol)? {
+        return uint_arithmetic(a, symbol, b);
+    }
+    let (a, b) = (number(l)?, number(r)?);
+    Ok(V::from_number(match symbol {
+        "+" => a + b,
+        "-" => a - b,
+        "*" => a * b,
+        _ if b == 0.0 => return Err(Rekt::Lexer("You just got rekt by zero!".to_string())),
+        _ => a / b,
+    }))
+}
+
+fn number<V: GmValue>(value: &V) -> Result<f64, Rekt> {
+    match value.view() {
+        View::Vibe(n) => Ok(n),
+        _ => Err(Rekt::Type(format!("Expected vibe, but found {}", value))),
+    }
+}
+
+// The natural order shared by comparisons and `sort`. Compares by value, so
+// 10 sorts after 9.
+pub fn compare<V: GmValue>(a: &V, b: &V) -> Result<Ordering, Rekt> {
+    match (a.view(), b.view()) {
+        (View::Uint(x), View::Uint(y)) => return Ok(x.cmp(&y)),
+        (View::Decimal(x), View::Decimal(y)) => return Ok(x.cmp(&y)),
+        _ => {}
+    }
+    if let (Some(x), Some(y)) = (a.as_number(), b.as_number()) {
+        return Ok(x.partial_cmp(&y).unwrap_or(Ordering::Equal));
+    }
+    match (a.view(), b.view()) {
+        (View::Text(x), View::Text(y)) => Ok(x.cmp(y)),
+        (View::Signal(x), View::Signal(y)) => Ok(x.cmp(&y)),
+        _ => Err(Rekt::Type(format!("Cannot compare {} with {}", a, b))),
+    }
+}
+
+fn ordering<V: GmValue>(l: &V, symbol: &str, r: &V) -> Result<Ordering, Rekt> {
+    if let Some((a, b)) = decimal_operands(l, r, symbol)? {
+        return Ok(a.cmp(&b));
+    }
+    if let Some((a, b)) = uint_operands(l, r, symbol)? {
+        return Ok(a.cmp(&b));
+    }
+    number(l)?;
+    number(r)?;
+    compare(l, r)
+}
+
+// ==, where a side that won't convert just means the two aren't numbers.
+fn equal<V: GmValue>(l: &V, symbol: &str, r: &V) -> Result<bool, Rekt> {
+    if let Ok(Some((a, b))) = decimal_operands(l, r, symbol) {
+        return Ok(a == b);
+    }
+    if let Ok(Some((a, b))) = uint_operands(l, r, symbol) {
+        return Ok(a == b);
+    }
+    match (l.view(), r.view()) {
+        (View::Vibe(a), View::Vibe(b)) => Ok(same_number(a, b)),
+        // Signals, texts, arrays, mappings and structs compare structurally
+        _ if l.same_type(r) => Ok(l == r),
+        _ => Err(Rekt::Lexer("Cannot compare different types".to_string())),
+    }
+}
+
+// The integer-only operators: %, ~/, &, |, ^, << and >>. Uints stay uints;
+// whole numbers work as signed integers and come back as numbers.
+fn integer_arithmetic<V: GmValue>(l: &V, symbol: &str, r: &V) -> Result<V, Rekt> {
+    if let Some((a, b)) = uint_operands(l, r, symbol)? {
+        return uint_integer(a, symbol, b);
+    }
+    let (a, b) = (whole(l, symbol)?, whole(r, symbol)?);
+    let result = match symbol {
+        "%" | "~/" if b == 0 => return Err(Rekt::Runtime("You just got rekt by zero!".to_string())),
+        // Division rounds down, and the remainder takes the divisor's sign
+        "~/" => floor_div(a, b),
+        "%" => a - b * floor_div(a, b),
+        "&" => a & b,
+        "|" => a | b,
+        "^" => a ^ b,
+        _ if !(0..64).contains(&b) => {
+            return Err(Rekt::Runtime(format!("Cannot shift by {}, shifts go from 0 to 63", b)))
+        }
+        ">>" => a >> b,
+        _ => {
+            let shifted = (a as i128) << b;
+            if shifted.unsigned_abs() > EXACT_FLOAT as u128 {
+                return Err(Rekt::Runtime(format!("{} << {} is too big for a number, use a uint 📉", a, b)));
+            }
+            shifted as i64
+        }
+    };
+    Ok(V::from_number(result as f64))
+}
+
+fn uint_integer<V: GmValue>(a: U256, symbol: &str, b: U256) -> Result<V, Rekt> {
+    // Shifting past the width clears everything, like Solidity
+    let shift = b.as_u64().unwrap_or(u64::MAX).min(256) as u32;
+    let result = match symbol {
+        "%" | "~/" if b.is_zero() => return Err(Rekt::Runtime("You just got rekt by zero!".to_string())),
+        "%" => a.checked_rem(b),
+        "~/" => a.checked_div(b),
+        "&" => Some(a.bitand(b)),
+        "|" => Some(a.bitor(b)),
+        "^" => Some(a.bitxor(b)),
+        "<<" => Some(a.shl(shift)),
+        _ => Some(a.shr(shift)),
+    };
+    result
+        .map(V::from_uint)
+        .ok_or_else(|| Rekt::Runtime(format!("uint overflow: {} {} {} 📉", a, symbol, b)))
+}
+
+fn floor_div(a: i64, b: i64) -> i64 {
+    let q = a / b;
+    if a % b != 0 && (a < 0) != (b < 0) {
+        q - 1
+    } else {
+        q
+    }
+}
+
+// A number as an integer, if it holds a whole number floats keep exact.
+fn whole<V: GmValue>(value: &V, symbol: &str) -> Result<i64, Rekt> {
+    match value.view() {
+        View::Vibe(n) if n.fract() == 0.0 && n.abs() <= EXACT_FLOAT => Ok(n as i64),
+        _ => Err(Rekt::Type(format!(
+            "'{}' needs integers, but found {} {}",
+            symbol,
+            value,
+            value.render()
+        ))),
+    }
+}
+
+pub fn add<V: GmValue>(l: V, r: V) -> Result<V, Rekt> {
+    binary(l, "+", r)
+}
+
+pub fn sub<V: GmValue>(l: V, r: V) -> Result<V, Rekt> {
+    binary(l, "-", r)
+}
+
+pub fn mul<V: GmValue>(l: V, r: V) -> Result<V, Rekt> {
+    binary(l, "*", r)
+}
+
+pub fn div<V: GmValue>(l: V, r: V) -> Result<V, Rekt> {
+    binary(l, "/", r)
+}
+
+pub fn rem<V: GmValue>(l: V, r: V) -> Result<V, Rekt> {
+    binary(l, "%", r)
+}
+
+pub fn chop<V: GmValue>(l: V, r: V) -> Result<V, Rekt> {
+    binary(l, "~/", r)
+}
+
+pub fn bitand<V: GmValue>(l: V, r: V) -> Result<V, Rekt> {
+    binary(l, "&", r)
+}
+
+pub fn bitor<V: GmValue>(l: V, r: V) -> Result<V, Rekt> {
+    binary(l, "|", r)
+}
+
+pub fn bitxor<V: GmValue>(l: V, r: V) -> Result<V, Rekt> {
+    binary(l, "^", r)
+}
+
+pub fn shl<V: GmValue>(l: V, r: V) -> Result<V, Rekt> {
+    binary(l, "<<", r)
+}
+
+pub fn shr<V: GmValue>(l: V, r: V) -> Result<V, Rekt> {
+    binary(l, ">>", r)
+}
+
+pub fn lt<V: GmValue>(l: V, r: V) -> Result<V, Rekt> {
+    binary(l, "<", r)
+}
+
+pub fn gt<V: GmValue>(l: V, r: V) -> Result<V, Rekt> {
+    binary(l, ">", r)
+}
+
+pub fn le<V: GmValue>(l: V, r: V) -> Result<V, Rekt> {
+    binary(l, "<=", r)
+}
+
+pub fn ge<V: GmValue>(l: V, r: V) -> Result<V, Rekt> {
+    binary(l, ">=", r)
+}
+
+pub fn eq<V: GmValue>(l: V, r: V) -> Result<V, Rekt> {
+    binary(l, "==", r)
+}
+
+pub fn ne<V: GmValue>(l: V, r: V) -> Result<V, Rekt> {
+    binary(l, "!=", r)
+}
+
+pub fn and<V: GmValue>(l: V, r: V) -> Result<V, Rekt> {
+    binary(l, "and", r)
+}
+
+pub fn or<V: GmValue>(l: V, r: V) -> Result<V, Rekt> {
+    binary(l, "or", r)
+}
+
+pub fn not<V: GmValue>(value: V) -> Result<V, Rekt> {
+    unary("not", value)
+}
+
+pub fn bitnot<V: GmValue>(value: V) -> Result<V, Rekt> {
+    unary("~", value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::{dec, text, uint, Value};
+
+    #[test]
+    fn the_wider_kind_of_number_wins() {
+        assert_eq!(binary(Value::Number(1.5), "+", Value::Number(2.0)).unwrap(), Value::Number(3.5));
+        assert_eq!(binary(uint("2"), "*", Value::Number(3.0)).unwrap(), uint("6"));
+        assert_eq!(binary(dec("1.50"), "+", uint("1")).unwrap(), dec("2.50"));
+        let err = binary(uint("1"), "+", Value::Number(0.5)).unwrap_err();
+        assert!(err.to_string().contains("Cannot use '+' on uint and vibe 0.5"), "{}", err);
+    }
+
+    #[test]
+    fn integer_operators_round_down() {
+        assert_eq!(binary(Value::Number(-7.0), "~/", Value::Number(2.0)).unwrap(), Value::Number(-4.0));
+        assert_eq!(binary(Value::Number(-7.0), "%", Value::Number(2.0)).unwrap(), Value::Number(1.0));
+        assert_eq!(unary("~", Value::Number(0.0)).unwrap(), Value::Number(-1.0));
+        assert!(binary(Value::Number(1.0), "%", Value::Number(0.0)).is_err());
+    }
+
+    #[test]
+    fn equality_is_structural_within_a_type() {
+        let xs = || crate::value::array(vec![Value::Number(1.0), text("gm")]);
+        assert_eq!(binary(xs(), "==", xs()).unwrap(), Value::Signal(true));
+        assert_eq!(binary(Value::Number(f64::NAN), "==", Value::Number(f64::NAN)).unwrap(), Value::Signal(true));
+        assert!(binary(text("1"), "==", Value::Number(1.0)).is_err());
+        assert_eq!(compare(&text("a"), &text("b")).unwrap(), Ordering::Less);
+    }
+
+    #[test]
+    fn unknown_operators_are_errors() {
+        assert!(binary(Value::Signal(true), "and", Value::Signal(true)).is_err());
+        assert!(unary("not", Value::Number(1.0)).is_err());
+    }
+}
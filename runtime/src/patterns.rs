@@ -0,0 +1,166 @@
+// Vibecheck patterns. gm-language parses them into its syntax tree; the
+// generated code spells them out as data.
+
+use std::cmp::Ordering;
+use crate::ops::compare;
+use crate::shape::{GmValue, View};
+
+// Generated code names bindings with string literals, gm-language with the
+// names it parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern<V, N = &'static str> {
+    Wildcard,            // _
+    Literal(V),          // 42, "deposit", true
+    Range(V, V),         // 0..10, end exclusive
+    Binding(N),          // n
+    Struct {
+        name: N,
+        fields: Vec<(N, Pattern<V, N>)>, // unlisted fields are ignored
+    },
+    Array {
+        items: Vec<Pattern<V, N>>,
+        rest: Option<N>, // `..rest`, or `_` for a bare `..`
+    },
+    Or(Vec<Pattern<V, N>>), // "burn" | "rug"
+}
+
+impl<V, N: AsRef<str> + Clone> Pattern<V, N> {
+    // The names a match binds, in the order they appear
+    pub fn bindings(&self, names: &mut Vec<N>) {
+        match self {
+            Pattern::Binding(name) => names.push(name.clone()),
+            Pattern::Struct { fields, .. } => {
+                for (_, sub) in fields {
+                    sub.bindings(names);
+                }
+            }
+            Pattern::Array { items, rest } => {
+                for item in items {
+                    item.bindings(names);
+                }
+                names.extend(rest.iter().filter(|r| r.as_ref() != "_").cloned());
+            }
+            Pattern::Or(alternatives) => {
+                for alternative in alternatives {
+                    alternative.bindings(names);
+                }
+            }
+            Pattern::Wildcard | Pattern::Literal(_) | Pattern::Range(..) => {}
+        }
+    }
+
+    // Matches anything without looking at it
+    pub fn is_catch_all(&self) -> bool {
+        match self {
+            Pattern::Wildcard | Pattern::Binding(_) => true,
+            Pattern::Or(alternatives) => alternatives.iter().any(|p| p.is_catch_all()),
+            _ => false,
+        }
+    }
+}
+
+// The names a matching pattern bound, in the order it bound them.
+pub struct Bound<V>(Vec<(&'static str, V)>);
+
+impl<V: GmValue> Bound<V> {
+    // The last value bound to `name`, which is the one the arm sees. An
+    // alternative that doesn't bind it leaves it lonely.
+    pub fn take(&mut self, name: &str) -> V {
+        match self.0.iter().rposition(|(n, _)| *n == name) {
+            Some(i) => self.0.remove(i).1,
+            None => V::null(),
+        }
+    }
+}
+
+pub fn matches<V: GmValue>(value: &V, pattern: &Pattern<V>) -> Option<Bound<V>> {
+    let mut bound = Vec::new();
+    if bind(pattern, value, &mut bound) {
+        Some(Bound(bound))
+    } else {
+        None
+    }
+}
+
+// Tests `value` against `pattern`, collecting any names it binds along the way.
+pub fn bind<V: GmValue, N: AsRef<str> + Clone>(pattern: &Pattern<V, N>, value: &V, bound: &mut Vec<(N, V)>) -> bool {
+    match pattern {
+        Pattern::Wildcard => true,
+        Pattern::Binding(name) => {
+            bound.push((name.clone(), value.clone()));
+            true
+        }
+        Pattern::Literal(expected) => match (expected.view(), value.view()) {
+            (View::Uint(a), View::Uint(b)) => a == b,
+            _ => match (expected.as_number(), value.as_number()) {
+                (Some(a), Some(b)) => a == b,
+                _ => compare(expected, value).map(|o| o == Ordering::Equal).unwrap_or(false),
+            },
+        },
+        Pattern::Range(start, end) => match (start.as_number(), end.as_number(), value.as_number()) {
+            (Some(start), Some(end), Some(n)) => start <= n && n < end,
+            _ => false,
+        },
+        Pattern::Struct { name, fields } => match value.view() {
+            View::Struct(actual, values) if actual == name.as_ref() => fields.iter().all(|(field, sub)| {
+                values
+                    .iter()
+                    .find(|(f, _)| f == field.as_ref())
+                    .map(|(_, v)| bind(sub, v, bound))
+                    .unwrap_or(false)
+            }),
+            _ => false,
+        },
+        Pattern::Array { items, rest } => match value.view() {
+            View::Array(values) => {
+                let fits = match rest {
+                    Some(_) => values.len() >= items.len(),
+                    None => values.len() == items.len(),
+                };
+                if !fits || !items.iter().zip(values).all(|(p, v)| bind(p, v, bound)) {
+                    return false;
+                }
+                if let Some(rest) = rest {
+                    if rest.as_ref() != "_" {
+                        bound.push((rest.clone(), V::from_array(values[items.len()..].to_vec())));
+                    }
+                }
+                true
+            }
+            _ => false,
+        },
+        Pattern::Or(alternatives) => alternatives.iter().any(|p| {
+            // Drop whatever a failed alternative managed to bind
+            let mark = bound.len();
+            let hit = bind(p, value, bound);
+            if !hit {
+                bound.truncate(mark);
+            }
+            hit
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::{array, text, Value};
+
+    #[test]
+    fn arrays_bind_their_rest() {
+        let pattern = Pattern::Array { items: vec![Pattern::Binding("head")], rest: Some("tail") };
+        let mut bound = matches(&array(vec![Value::Number(1.0), Value::Number(2.0)]), &pattern).unwrap();
+        assert_eq!(bound.take("head"), Value::Number(1.0));
+        assert_eq!(bound.take("tail"), array(vec![Value::Number(2.0)]));
+        assert!(matches(&array(vec![]), &pattern).is_none());
+    }
+
+    #[test]
+    fn literals_ranges_and_alternatives() {
+        let pattern = Pattern::Or(vec![Pattern::Literal(text("burn")), Pattern::Range(Value::Number(0.0), Value::Number(10.0))]);
+        assert!(matches(&text("burn"), &pattern).is_some());
+        assert!(matches(&Value::Number(9.5), &pattern).is_some());
+        assert!(matches(&Value::Number(10.0), &pattern).is_none());
+        assert!(!pattern.is_catch_all() && Pattern::<Value>::Binding("x").is_catch_all());
+    }
+}
@@ -0,0 +1,145 @@
+// What the shared rules need to know about a value. gm-language's own
+// values and the ones transpiled Rust runs on are laid out differently, so
+// each side implements `GmValue` and the operators, builtins, slot paths
+// and patterns are written once against it.
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+use crate::error::Rekt;
+use crate::numeric::{Decimal, U256};
+
+// A value seen through the eyes of the shared rules.
+pub enum View<'a, V> {
+    Vibe(f64), // a plain number
+    Uint(U256),
+    Decimal(Decimal),
+    Text(&'a str),
+    Signal(bool),
+    Array(&'a [V]),
+    Map(&'a BTreeMap<Key, V>),
+    Struct(&'a str, &'a [(String, V)]),
+    Contract,
+    Other, // lonely, and anything only one side has
+}
+
+impl<V> View<'_, V> {
+    pub fn number(&self) -> Option<f64> {
+        match self {
+            View::Vibe(n) => Some(*n),
+            View::Uint(n) => Some(n.to_f64()),
+            View::Decimal(d) => Some(d.to_f64()),
+            _ => None,
+        }
+    }
+}
+
+// The collections a write can step into.
+pub enum ViewMut<'a, V> {
+    Array(&'a mut Vec<V>),
+    Map(&'a mut BTreeMap<Key, V>),
+    Struct(&'a mut Vec<(String, V)>),
+    Other,
+}
+
+// Display gives the type name error messages print.
+pub trait GmValue: Clone + PartialEq + fmt::Display {
+    fn view(&self) -> View<'_, Self>;
+    fn view_mut(&mut self) -> ViewMut<'_, Self>;
+
+    // How broadcasts print the value.
+    fn render(&self) -> String;
+
+    // Whether two values have the same type, which is what lets `==` fall
+    // back to comparing them structurally.
+    fn same_type(&self, other: &Self) -> bool;
+
+    fn from_number(n: f64) -> Self;
+    fn from_uint(n: U256) -> Self;
+    fn from_decimal(d: Decimal) -> Self;
+    fn from_text(s: String) -> Self;
+    fn from_signal(b: bool) -> Self;
+    fn from_array(items: Vec<Self>) -> Self;
+    fn from_map(entries: BTreeMap<Key, Self>) -> Self;
+    fn null() -> Self;
+
+    // Anything numeric, as a float.
+    fn as_number(&self) -> Option<f64> {
+        self.view().number()
+    }
+}
+
+// Numbers are equal when they print the same, so NaN equals NaN and -0
+// doesn't equal 0.
+pub fn same_number(a: f64, b: f64) -> bool {
+    (a.is_nan() && b.is_nan()) || (a == b && a.is_sign_negative() == b.is_sign_negative())
+}
+
+// Keys a mapping can be indexed by. Signals sort before numbers, numbers
+// before uints, uints before text; numbers compare by value.
+#[derive(Debug, Clone)]
+pub enum Key {
+    Signal(bool),
+    Number(f64),
+    Uint(U256),
+    Text(String),
+}
+
+impl Key {
+    pub fn from_value<V: GmValue>(value: &V) -> Result<Key, Rekt> {
+        match value.view() {
+            View::Text(s) => Ok(Key::Text(s.to_string())),
+            View::Signal(b) => Ok(Key::Signal(b)),
+            View::Vibe(n) => Ok(Key::Number(n)),
+            View::Uint(n) => Ok(Key::Uint(n)),
+            _ => Err(Rekt::Type(format!(
+                "Mapping keys must be text, number or signal, but found {}",
+                value
+            ))),
+        }
+    }
+
+    pub fn to_value<V: GmValue>(&self) -> V {
+        match self {
+            Key::Signal(b) => V::from_signal(*b),
+            Key::Number(n) => V::from_number(*n),
+            Key::Uint(n) => V::from_uint(*n),
+            Key::Text(s) => V::from_text(s.clone()),
+        }
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            Key::Signal(_) => 0,
+            Key::Number(_) => 1,
+            Key::Uint(_) => 2,
+            Key::Text(_) => 3,
+        }
+    }
+}
+
+impl Ord for Key {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Key::Signal(a), Key::Signal(b)) => a.cmp(b),
+            (Key::Number(a), Key::Number(b)) => a.total_cmp(b),
+            (Key::Uint(a), Key::Uint(b)) => a.cmp(b),
+            (Key::Text(a), Key::Text(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl PartialOrd for Key {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Key {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Key {}
@@ -0,0 +1,253 @@
+// Indexing, fields and the paths writes walk down. gm-runtime's
+// collections are behind `Rc`, so there a write copies only the levels
+// someone else is still holding.
+
+use std::collections::BTreeMap;
+use crate::error::Rekt;
+use crate::shape::{GmValue, Key, View, ViewMut};
+
+// One step on the way from a binding down to the slot being written.
+// Generated code names fields with string literals, gm-language with the
+// names it parsed.
+#[derive(Clone)]
+pub enum Step<V, F = &'static str> {
+    Key(V),   // m[key] / xs[i]
+    Field(F), // record.field
+}
+
+// Missing mapping slots read as zero, the same way Solidity mappings do.
+fn default_slot<V: GmValue>() -> V {
+    V::from_number(0.0)
+}
+
+fn array_index<V: GmValue>(index: &V, len: usize) -> Result<usize, Rekt> {
+    let n = index
+        .as_number()
+        .ok_or_else(|| Rekt::Type(format!("Array index must be a number, but found {}", index)))?;
+
+    if n < 0.0 || n.fract() != 0.0 || n as usize >= len {
+        return Err(Rekt::Runtime(format!("Index {} out of bounds for array of length {}", n, len)));
+    }
+    Ok(n as usize)
+}
+
+pub fn index<V: GmValue>(object: V, index: V) -> Result<V, Rekt> {
+    match object.view() {
+        View::Map(entries) => Ok(entries
+            .get(&Key::from_value(&index)?)
+            .cloned()
+            .unwrap_or_else(default_slot)),
+        View::Array(items) => Ok(items[array_index(&index, items.len())?].clone()),
+        _ => Err(Rekt::Type(format!("Cannot index into {}", object))),
+    }
+}
+
+pub fn field<V: GmValue>(object: V, field: &str) -> Result<V, Rekt> {
+    match object.view() {
+        View::Struct(name, fields) => fields
+            .iter()
+            .find(|(f, _)| f == field)
+            .map(|(_, v)| v.clone())
+            .ok_or_else(|| Rekt::Type(format!("'{}' has no field '{}'", name, field))),
+        _ => Err(Rekt::Type(format!("Cannot read field '{}' of {}", field, object))),
+    }
+}
+
+// Reads the value at the end of `path` without copying anything on the way.
+pub fn read<V: GmValue, F: AsRef<str>>(root: &V, path: &[Step<V, F>]) -> Result<V, Rekt> {
+    let (step, rest) = match path.split_first() {
+        Some(split) => split,
+        None => return Ok(root.clone()),
+    };
+    match (root.view(), step) {
+        (View::Map(entries), Step::Key(key)) => match entries.get(&Key::from_value(key)?) {
+            Some(value) => read(value, rest),
+            None => read(&default_slot(), rest),
+        },
+        (View::Array(items), Step::Key(key)) => read(&items[array_index(key, items.len())?], rest),
+        (View::Struct(name, fields), Step::Field(field)) => {
+            match fields.iter().find(|(f, _)| f == field.as_ref()) {
+                Some((_, value)) => read(value, rest),
+                None => Err(Rekt::Type(format!("'{}' has no field '{}'", name, field.as_ref()))),
+            }
+        }
+        (_, Step::Key(key)) => index(root.clone(), key.clone()),
+        (_, Step::Field(name)) => field(root.clone(), name.as_ref()),
+    }
+}
+
+// Steps one slot deeper. Missing intermediate mapping slots are created on
+// the fly, like nested mappings.
+fn step_mut<'a, V: GmValue, F: AsRef<str>>(slot: &'a mut V, step: &Step<V, F>) -> Result<&'a mut V, Rekt> {
+    // Checked up front, so a step that can't be taken copies nothing
+    match (slot.view(), step) {
+        (View::Map(_) | View::Array(_), Step::Key(_)) => {}
+        (View::Struct(name, fields), Step::Field(field)) => {
+            if !fields.iter().any(|(f, _)| f == field.as_ref()) {
+                return Err(Rekt::Type(format!("'{}' has no field '{}'", name, field.as_ref())));
+            }
+        }
+        (_, Step::Key(_)) => return Err(Rekt::Type(format!("Cannot index into {}", slot))),
+        (_, Step::Field(field)) => {
+            return Err(Rekt::Type(format!("Cannot write field '{}' of {}", field.as_ref(), slot)))
+        }
+    }
+    match (slot.view_mut(), step) {
+        (ViewMut::Map(entries), Step::Key(key)) => Ok(entries
+            .entry(Key::from_value(key)?)
+            .or_insert_with(|| V::from_map(BTreeMap::new()))),
+        (ViewMut::Array(items), Step::Key(key)) => {
+            let i = array_index(key, items.len())?;
+            Ok(&mut items[i])
+        }
+        (ViewMut::Struct(fields), Step::Field(field)) => {
+            Ok(fields.iter_mut().find(|(f, _)| f == field.as_ref()).map(|(_, v)| v).unwrap())
+        }
+        _ => unreachable!("the step was checked against the slot above"),
+    }
+}
+
+// Walks `path` down from `root` and stores `value` in the last slot.
+pub fn set<V: GmValue, F: AsRef<str>>(root: &mut V, path: &[Step<V, F>], value: V) -> Result<(), Rekt> {
+    let (last, path) = match path.split_last() {
+        Some(split) => split,
+        None => {
+            *root = value;
+            return Ok(());
+        }
+    };
+
+    let mut slot = root;
+    for step in path {
+        slot = step_mut(slot, step)?;
+    }
+
+    // The last key of a mapping is inserted, not stepped into
+    if let (View::Map(_), Step::Key(key)) = (slot.view(), last) {
+        let key = Key::from_value(key)?;
+        if let ViewMut::Map(entries) = slot.view_mut() {
+            entries.insert(key, value);
+        }
+        return Ok(());
+    }
+    *step_mut(slot, last)? = value;
+    Ok(())
+}
+
+pub fn delete<V: GmValue, F: AsRef<str>>(root: &mut V, path: &[Step<V, F>]) -> Result<(), Rekt> {
+    let (last, path) = match path.split_last() {
+        Some(split) => split,
+        None => return Err(Rekt::Runtime("Nothing to delete".to_string())),
+    };
+
+    let mut slot = root;
+    for step in path {
+        if let (View::Map(entries), Step::Key(key)) = (slot.view(), step) {
+            if !entries.contains_key(&Key::from_value(key)?) {
+                return Ok(()); // deleting from a slot that was never set
+            }
+        }
+        slot = step_mut(slot, step)?;
+    }
+
+    let key = match (slot.view(), last) {
+        (View::Map(_) | View::Array(_), Step::Key(key)) => key,
+        _ => return Err(Rekt::Type(format!("Cannot delete from {}", slot))),
+    };
+    match slot.view_mut() {
+        ViewMut::Map(entries) => {
+            entries.remove(&Key::from_value(key)?);
+        }
+        ViewMut::Array(items) => {
+            let i = array_index(key, items.len())?;
+            items[i] = default_slot();
+        }
+        _ => unreachable!("the slot was checked to be a collection above"),
+    }
+    Ok(())
+}
+
+// What `farm x in xs` walks: the items of an array, or the keys of a
+// mapping.
+pub fn items<V: GmValue>(iterable: V) -> Result<Vec<V>, Rekt> {
+    match iterable.view() {
+        View::Array(items) => Ok(items.to_vec()),
+        View::Map(entries) => Ok(entries.keys().map(Key::to_value).collect()),
+        _ => Err(Rekt::Type(format!("Cannot farm over {}", iterable))),
+    }
+}
+
+// What `farm k, v in xs` walks: (index, item) over an array, or (key,
+// value) over a mapping.
+pub fn pairs<V: GmValue>(iterable: V) -> Result<Vec<(V, V)>, Rekt> {
+    match iterable.view() {
+        View::Array(items) => Ok(items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (V::from_number(i as f64), item.clone()))
+            .collect()),
+        View::Map(entries) => Ok(entries.iter().map(|(k, v)| (k.to_value(), v.clone())).collect()),
+        _ => Err(Rekt::Type(format!("Cannot farm over {}", iterable))),
+    }
+}
+
+// One end, or the step, of a farm range.
+pub fn bound<V: GmValue>(value: &V, what: &str) -> Result<f64, Rekt> {
+    value
+        .as_number()
+        .ok_or_else(|| Rekt::Type(format!("Range {} must be a number, but found {}", what, value)))
+}
+
+// The numbers a farm range walks, all checked before the first one.
+pub fn steps<V: GmValue>(start: V, end: V, step: V) -> Result<impl Iterator<Item = V>, Rekt> {
+    let (mut current, end, step) = (bound(&start, "start")?, bound(&end, "end")?, bound(&step, "step")?);
+    if step == 0.0 {
+        return Err(Rekt::Runtime("Range step can't be zero".to_string()));
+    }
+    Ok(std::iter::from_fn(move || {
+        if (step > 0.0 && current < end) || (step < 0.0 && current > end) {
+            let n = current;
+            current += step;
+            Some(V::from_number(n))
+        } else {
+            None
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::{array, mapping, text, Value};
+
+    fn balances() -> Value {
+        mapping(vec![(text("alice"), array(vec![Value::Number(1.0), Value::Number(2.0)]))]).unwrap()
+    }
+
+    #[test]
+    fn writes_walk_down_the_path() {
+        let mut root = balances();
+        let path: [Step<Value>; 2] = [Step::Key(text("alice")), Step::Key(Value::Number(1.0))];
+        set(&mut root, &path, Value::Number(5.0)).unwrap();
+        assert_eq!(read(&root, &path).unwrap(), Value::Number(5.0));
+        assert_eq!(index(root, text("bob")).unwrap(), Value::Number(0.0));
+    }
+
+    #[test]
+    fn writes_copy_what_others_still_hold() {
+        let before = balances();
+        let mut after = before.clone();
+        let path: [Step<Value>; 1] = [Step::Key(text("alice"))];
+        delete(&mut after, &path).unwrap();
+        assert_eq!(items(after).unwrap().len(), 0);
+        assert_eq!(pairs(before).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn bad_indexes_are_errors() {
+        let err = index(array(vec![]), Value::Number(0.0)).unwrap_err();
+        assert!(err.to_string().contains("Index 0 out of bounds for array of length 0"), "{}", err);
+        assert!(mapping(vec![(array(vec![]), Value::Null)]).is_err());
+        assert!(field(Value::Number(1.0), "owner").is_err());
+    }
+}
@@ -0,0 +1,291 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::rc::Rc;
+use crate::chain::Chain;
+use crate::error::Rekt;
+use crate::numeric::{Decimal, U256};
+use crate::shape::{same_number, GmValue, Key, View, ViewMut};
+use crate::{patterns, slots};
+
+// The slot steps and patterns generated code spells out.
+pub type Step = slots::Step<Value>;
+pub type Pattern = patterns::Pattern<Value>;
+
+#[derive(Clone)]
+pub enum Value {
+    Number(f64), // a vibe
+    Text(String),
+    Signal(bool),
+    Uint(U256),
+    Decimal(Decimal),
+    Array(Rc<Vec<Value>>),
+    Map(Rc<BTreeMap<Key, Value>>),
+    Struct(Rc<Record>),
+    Contract(Contract),
+    Null,
+}
+
+// A struct value, fields in declaration order.
+#[derive(Clone, PartialEq)]
+pub struct Record {
+    pub name: String,
+    pub fields: Vec<(String, Value)>,
+}
+
+// A contract value: a closure over the cells it captured, called with the
+// chain it runs on and its already-checked arguments.
+#[derive(Clone)]
+pub struct Contract {
+    pub name: String,
+    pub arity: usize,
+    pub body: Rc<Body>,
+}
+
+pub type Body = dyn Fn(&Chain, Vec<Value>) -> Result<Value, Rekt>;
+
+// Field types a struct declares, with the names GM prints them by.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Number,
+    Text,
+    Boolean,
+    Function,
+    Token,
+    Signal,
+    Uint,
+    Decimal,
+    Array,
+    Map,
+    Struct(String),
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Number => write!(f, "number"),
+            Type::Text => write!(f, "text"),
+            Type::Boolean => write!(f, "feeling"),
+            Type::Function => write!(f, "devotion"),
+            Type::Token => write!(f, "vibe"),
+            Type::Signal => write!(f, "signal"),
+            Type::Uint => write!(f, "uint"),
+            Type::Decimal => write!(f, "decimal"),
+            Type::Array => write!(f, "array"),
+            Type::Map => write!(f, "mapping"),
+            Type::Struct(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl Type {
+    // Whether a value fits a declared field type. Uints and decimals count
+    // as numbers, and so does lonely.
+    pub fn accepts(&self, value: &Value) -> bool {
+        match (self, value) {
+            (Type::Number, Value::Number(_) | Value::Uint(_) | Value::Decimal(_)) => true,
+            (Type::Boolean, Value::Signal(_)) => true,
+            (Type::Struct(expected), Value::Struct(record)) => *expected == record.name,
+            (expected, value) => *expected == value.get_type(),
+        }
+    }
+}
+
+// A value's type name, the way error messages print it.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Number(_) => write!(f, "vibe"),
+            Value::Text(_) => write!(f, "text"),
+            Value::Signal(_) => write!(f, "signal"),
+            Value::Uint(_) => write!(f, "uint"),
+            Value::Decimal(_) => write!(f, "decimal"),
+            Value::Array(_) => write!(f, "array"),
+            Value::Map(_) => write!(f, "mapping"),
+            Value::Struct(record) => write!(f, "{}", record.name),
+            Value::Contract(_) => write!(f, "smart contract"),
+            Value::Null => write!(f, "lonely"),
+        }
+    }
+}
+
+impl fmt::Debug for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render_nested())
+    }
+}
+
+impl Value {
+    // Lonely counts as a number, and each struct is its own type.
+    pub fn get_type(&self) -> Type {
+        match self {
+            Value::Number(_) => Type::Token,
+            Value::Text(_) => Type::Text,
+            Value::Signal(_) => Type::Signal,
+            Value::Uint(_) => Type::Uint,
+            Value::Decimal(_) => Type::Decimal,
+            Value::Array(_) => Type::Array,
+            Value::Map(_) => Type::Map,
+            Value::Struct(record) => Type::Struct(record.name.clone()),
+            Value::Contract(_) => Type::Function,
+            Value::Null => Type::Number,
+        }
+    }
+
+    // How broadcasts print a value. Text is shown bare at the top level and
+    // quoted once it's nested inside a collection.
+    pub fn render(&self) -> String {
+        match self {
+            Value::Text(s) => s.clone(),
+            other => other.render_nested(),
+        }
+    }
+
+    fn render_nested(&self) -> String {
+        match self {
+            Value::Number(n) => n.to_string(),
+            Value::Uint(n) => n.to_string(),
+            Value::Decimal(d) => d.to_string(),
+            Value::Text(s) => format!("{:?}", s),
+            Value::Signal(b) => b.to_string(),
+            Value::Contract(contract) => format!("<contract {}>", contract.name),
+            Value::Array(items) => {
+                let items: Vec<String> = items.iter().map(|v| v.render_nested()).collect();
+                format!("[{}]", items.join(", "))
+            }
+            Value::Map(entries) => {
+                let entries: Vec<String> = entries
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}", k.to_value::<Value>().render_nested(), v.render_nested()))
+                    .collect();
+                format!("{{{}}}", entries.join(", "))
+            }
+            Value::Struct(record) => {
+                let fields: Vec<String> = record
+                    .fields
+                    .iter()
+                    .map(|(f, v)| format!("{}: {}", f, v.render_nested()))
+                    .collect();
+                if fields.is_empty() {
+                    format!("{} {{}}", record.name)
+                } else {
+                    format!("{} {{ {} }}", record.name, fields.join(", "))
+                }
+            }
+            Value::Null => "null".to_string(),
+        }
+    }
+}
+
+// Same kind, same contents.
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Number(a), Value::Number(b)) => same_number(*a, *b),
+            (Value::Text(a), Value::Text(b)) => a == b,
+            (Value::Signal(a), Value::Signal(b)) => a == b,
+            (Value::Uint(a), Value::Uint(b)) => a == b,
+            (Value::Decimal(a), Value::Decimal(b)) => a == b,
+            (Value::Array(a), Value::Array(b)) => a == b,
+            (Value::Map(a), Value::Map(b)) => a == b,
+            (Value::Struct(a), Value::Struct(b)) => a == b,
+            (Value::Contract(a), Value::Contract(b)) => Rc::ptr_eq(&a.body, &b.body),
+            (Value::Null, Value::Null) => true,
+            _ => false,
+        }
+    }
+}
+
+impl GmValue for Value {
+    fn view(&self) -> View<'_, Value> {
+        match self {
+            Value::Number(n) => View::Vibe(*n),
+            Value::Text(s) => View::Text(s),
+            Value::Signal(b) => View::Signal(*b),
+            Value::Uint(n) => View::Uint(*n),
+            Value::Decimal(d) => View::Decimal(*d),
+            Value::Array(items) => View::Array(items),
+            Value::Map(entries) => View::Map(entries),
+            Value::Struct(record) => View::Struct(&record.name, &record.fields),
+            Value::Contract(_) => View::Contract,
+            Value::Null => View::Other,
+        }
+    }
+
+    // Copies the collection first if anything else still holds it.
+    fn view_mut(&mut self) -> ViewMut<'_, Value> {
+        match self {
+            Value::Array(items) => ViewMut::Array(Rc::make_mut(items)),
+            Value::Map(entries) => ViewMut::Map(Rc::make_mut(entries)),
+            Value::Struct(record) => ViewMut::Struct(&mut Rc::make_mut(record).fields),
+            _ => ViewMut::Other,
+        }
+    }
+
+    fn render(&self) -> String {
+        Value::render(self)
+    }
+
+    fn same_type(&self, other: &Value) -> bool {
+        self.get_type() == other.get_type()
+    }
+
+    fn from_number(n: f64) -> Value {
+        Value::Number(n)
+    }
+
+    fn from_uint(n: U256) -> Value {
+        Value::Uint(n)
+    }
+
+    fn from_decimal(d: Decimal) -> Value {
+        Value::Decimal(d)
+    }
+
+    fn from_text(s: String) -> Value {
+        Value::Text(s)
+    }
+
+    fn from_signal(b: bool) -> Value {
+        Value::Signal(b)
+    }
+
+    fn from_array(items: Vec<Value>) -> Value {
+        Value::Array(Rc::new(items))
+    }
+
+    fn from_map(entries: BTreeMap<Key, Value>) -> Value {
+        Value::Map(Rc::new(entries))
+    }
+
+    fn null() -> Value {
+        Value::Null
+    }
+}
+
+// Literal constructors generated code uses.
+
+pub fn array(items: Vec<Value>) -> Value {
+    Value::Array(Rc::new(items))
+}
+
+// A mapping literal. Later entries overwrite earlier ones with the same key.
+// It takes `Value`s rather than any `GmValue` so `{}` needs no annotation.
+pub fn mapping(entries: Vec<(Value, Value)>) -> Result<Value, Rekt> {
+    let mut map = BTreeMap::new();
+    for (key, value) in entries {
+        map.insert(Key::from_value(&key)?, value);
+    }
+    Ok(Value::Map(Rc::new(map)))
+}
+
+pub fn text(s: &str) -> Value {
+    Value::Text(s.to_string())
+}
+
+pub fn uint(digits: &str) -> Value {
+    Value::Uint(U256::parse(digits).unwrap_or_default())
+}
+
+pub fn dec(digits: &str) -> Value {
+    Decimal::parse(digits).map(Value::Decimal).unwrap_or(Value::Null)
+}
@@ -0,0 +1,620 @@
+// src/checker/mod.rs
+//
+// A light static pass that runs between parsing and deployment. It only
+// reasons about types it can see for sure (struct literals, annotated
+// parameters and return types) and stays quiet about everything else.
+
+use std::collections::HashMap;
+use crate::error::Rekt;
+use crate::parser::ast::{Ast, Pattern};
+use crate::shared_types::{BinaryOp, Type, Value};
+
+pub struct Checker {
+    structs: HashMap<String, Vec<(String, Option<Type>)>>,
+    returns: HashMap<String, Option<Type>>,
+    scopes: Vec<HashMap<String, Option<Type>>>,
+}
+
+impl Default for Checker {
+    fn default() -> Self {
+        Checker::new()
+    }
+}
+
+impl Checker {
+    pub fn new() -> Self {
+        Checker {
+            structs: HashMap::new(),
+            returns: HashMap::new(),
+            scopes: vec![HashMap::new()],
+        }
+    }
+
+    pub fn check(&mut self, ast: &Ast) -> Result<(), Rekt> {
+        // Declarations are visible before the line they appear on
+        if let Ast::Program(stmts) = ast {
+            for stmt in stmts {
+                self.hoist(stmt.unlocated());
+            }
+        }
+        self.stmt(ast)
+    }
+
+    fn hoist(&mut self, stmt: &Ast) {
+        match stmt {
+            Ast::StructDecl { name, fields, .. } => {
+                self.structs.insert(name.clone(), fields.clone());
+            }
+            Ast::FunctionDecl { name, return_type, .. } => {
+                self.returns.insert(name.clone(), return_type.clone());
+            }
+            _ => {}
+        }
+    }
+
+    fn declare(&mut self, name: &str, known: Option<Type>) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), known);
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<Type> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .cloned()
+            .flatten()
+    }
+
+    fn scoped(&mut self, stmts: &[Ast]) -> Result<(), Rekt> {
+        self.scopes.push(HashMap::new());
+        let result = stmts.iter().try_for_each(|stmt| self.stmt(stmt));
+        self.scopes.pop();
+        result
+    }
+
+    fn contract_body(&mut self, params: &[(String, Type)], body: &[Ast]) -> Result<(), Rekt> {
+        self.scopes.push(HashMap::new());
+        for (param, param_type) in params {
+            // Unannotated params default to number, so only trust structs
+            let known = match param_type {
+                Type::Struct(_) => Some(param_type.clone()),
+                _ => None,
+            };
+            self.declare(param, known);
+        }
+        let result = body.iter().try_for_each(|stmt| self.stmt(stmt));
+        self.scopes.pop();
+        result
+    }
+
+    // Struct patterns must name real fields of the struct being matched
+    fn check_pattern(&self, pattern: &Pattern, expected: &Type) -> Result<(), Rekt> {
+        match (pattern, expected) {
+            (Pattern::Struct { name, fields }, Type::Struct(record)) => {
+                if name != record {
+                    return Err(Rekt::Type(format!(
+                        "Pattern '{}' can never match a value of type {}",
+                        name, record
+                    )));
+                }
+                for (field, sub) in fields {
+                    if let Some(field_type) = self.field_type(record, field)? {
+                        self.check_pattern(sub, &field_type)?;
+                    }
+                }
+                Ok(())
+            }
+            (Pattern::Struct { name, .. }, other) => Err(Rekt::Type(format!(
+                "Pattern '{}' can never match a value of type {}",
+                name, other
+            ))),
+            (Pattern::Or(alternatives), _) => alternatives
+                .iter()
+                .try_for_each(|p| self.check_pattern(p, expected)),
+            _ => Ok(()),
+        }
+    }
+
+    // When the scrutinee's type is known, every possible value needs an arm.
+    // Only an arm set the checker can prove incomplete is an error.
+    fn check_exhaustive(&self, arms: &[(Pattern, Ast)], scrutinee: &Type) -> Result<(), Rekt> {
+        let rows: Vec<Vec<&Pattern>> = arms.iter().map(|(p, _)| vec![p]).collect();
+        if self.missed(rows, &[Some(scrutinee.clone())]) == Some(true) {
+            Err(Rekt::Type(format!(
+                "vibecheck on {} is not exhaustive; add a '_ =>' arm",
+                scrutinee
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    // Whether some value of the column types gets past every row of
+    // patterns, one column at a time: booleans split into `true` and `false`
+    // and struct columns open up into their fields. `None` when it can't tell.
+    fn missed(&self, rows: Vec<Vec<&Pattern>>, columns: &[Option<Type>]) -> Option<bool> {
+        let Some((column, rest)) = columns.split_first() else {
+            return Some(rows.is_empty());
+        };
+        if rows.is_empty() {
+            return Some(true);
+        }
+        let rows: Vec<Vec<&Pattern>> = rows
+            .iter()
+            .flat_map(|row| {
+                alternatives(row[0]).into_iter().map(move |p| {
+                    let mut row = row.clone();
+                    row[0] = p;
+                    row
+                })
+            })
+            .collect();
+        let catch_alls = || -> Vec<Vec<&Pattern>> {
+            rows.iter().filter(|row| row[0].is_catch_all()).map(|row| row[1..].to_vec()).collect()
+        };
+        if rows.iter().all(|row| row[0].is_catch_all()) {
+            return self.missed(catch_alls(), rest);
+        }
+
+        match column {
+            Some(Type::Signal | Type::Boolean) => {
+                for b in [true, false] {
+                    let matching = rows
+                        .iter()
+                        .filter(|row| covers_signal(row[0], b))
+                        .map(|row| row[1..].to_vec())
+                        .collect();
+                    match self.missed(matching, rest) {
+                        Some(false) => {}
+                        other => return other,
+                    }
+                }
+                Some(false)
+            }
+            Some(Type::Struct(name)) => {
+                let fields = self.structs.get(name)?;
+                let opened = rows
+                    .iter()
+                    .filter_map(|row| {
+                        let subs: Vec<&Pattern> = match row[0] {
+                            Pattern::Struct { name: pattern_name, fields: listed } if pattern_name == name => fields
+                                .iter()
+                                .map(|(field, _)| {
+                                    listed.iter().find(|(f, _)| f == field).map_or(WILDCARD, |(_, sub)| sub)
+                                })
+                                .collect(),
+                            other if other.is_catch_all() => vec![WILDCARD; fields.len()],
+                            _ => return None,
+                        };
+                        Some(subs.into_iter().chain(row[1..].iter().copied()).collect())
+                    })
+                    .collect();
+                let columns: Vec<Option<Type>> =
+                    fields.iter().map(|(_, t)| t.clone()).chain(rest.iter().cloned()).collect();
+                self.missed(opened, &columns)
+            }
+            Some(Type::Array) if rest.is_empty() => {
+                let patterns: Vec<&Pattern> = rows.iter().map(|row| row[0]).collect();
+                Some(!patterns.iter().any(|p| p.is_catch_all()) && !covers_arrays(&patterns))
+            }
+            Some(Type::Array) => match self.missed(catch_alls(), rest) {
+                Some(false) => Some(false),
+                _ => None,
+            },
+            // Numbers, text and untyped fields have more values than any
+            // set of literals and ranges, so only catch-alls cover them.
+            _ => self.missed(catch_alls(), rest),
+        }
+    }
+
+    fn field_type(&self, record: &str, field: &str) -> Result<Option<Type>, Rekt> {
+        match self.structs.get(record) {
+            Some(fields) => fields
+                .iter()
+                .find(|(f, _)| f == field)
+                .map(|(_, t)| t.clone())
+                .ok_or_else(|| Rekt::Type(format!("'{}' has no field '{}'", record, field))),
+            None => Ok(None),
+        }
+    }
+
+    fn expect_fits(&self, what: &str, expected: &Type, actual: &Option<Type>) -> Result<(), Rekt> {
+        let fits = match (expected, actual) {
+            (_, None) => true,
+            (Type::Number, Some(Type::Number | Type::Token | Type::Uint | Type::Decimal)) => true,
+            (Type::Boolean, Some(Type::Boolean | Type::Signal)) => true,
+            (expected, Some(actual)) => expected == actual,
+        };
+        if fits {
+            Ok(())
+        } else {
+            Err(Rekt::Type(format!(
+                "{} expects {}, but found {}",
+                what,
+                expected,
+                actual.as_ref().map(|t| t.to_string()).unwrap_or_default()
+            )))
+        }
+    }
+
+    fn stmt(&mut self, ast: &Ast) -> Result<(), Rekt> {
+        match ast {
+            Ast::Program(stmts) => stmts.iter().try_for_each(|stmt| self.stmt(stmt)),
+            Ast::Block(stmts) => self.scoped(stmts),
+            Ast::Located { line, statement } => self.stmt(statement).map_err(|e| e.at_line(*line)),
+            Ast::StructDecl { .. } => {
+                self.hoist(ast);
+                Ok(())
+            }
+            Ast::FunctionDecl { name, params, body, .. } => {
+                self.hoist(ast);
+                self.declare(name, Some(Type::Function));
+                self.contract_body(params, body)
+            }
+            Ast::VariableDecl { name, initializer, .. } => {
+                let known = self.expr(initializer)?;
+                self.declare(name, known);
+                Ok(())
+            }
+            Ast::If { condition, then_branch, else_branch } => {
+                self.expr(condition)?;
+                self.scoped(then_branch)?;
+                match else_branch {
+                    Some(stmts) => self.scoped(stmts),
+                    None => Ok(()),
+                }
+            }
+            Ast::While { condition, body } => {
+                self.expr(condition)?;
+                self.scoped(body)
+            }
+            Ast::ForEach { key, variable, iterable, body } => {
+                self.expr(iterable)?;
+                self.scopes.push(HashMap::new());
+                if let Some(key) = key {
+                    self.declare(key, None);
+                }
+                self.declare(variable, None);
+                let result = self.scoped(body);
+                self.scopes.pop();
+                result
+            }
+            Ast::ForRange { variable, start, end, step, body } => {
+                self.expr(start)?;
+                self.expr(end)?;
+                if let Some(step) = step {
+                    self.expr(step)?;
+                }
+                self.scopes.push(HashMap::new());
+                self.declare(variable, None);
+                let result = self.scoped(body);
+                self.scopes.pop();
+                result
+            }
+            Ast::ReturnStmt(Some(expr)) => self.expr(expr).map(|_| ()),
+            Ast::ExpressionStmt(expr) | Ast::PrintStmt(expr) | Ast::Delete(expr) => {
+                self.expr(expr).map(|_| ())
+            }
+            other => self.expr(other).map(|_| ()),
+        }
+    }
+
+    // Returns the type of `ast` when it is known for sure.
+    fn expr(&mut self, ast: &Ast) -> Result<Option<Type>, Rekt> {
+        match ast {
+            Ast::Literal(value) => Ok(Some(value.get_type())),
+            Ast::Variable(name) => Ok(self.lookup(name)),
+            Ast::Grouping(inner) => self.expr(inner),
+            Ast::Interpolation(parts) => {
+                for part in parts {
+                    self.expr(part)?;
+                }
+                Ok(Some(Type::Text))
+            }
+            Ast::Call { callee, arguments } => {
+                for arg in arguments {
+                    self.expr(arg)?;
+                }
+                match callee.as_ref() {
+                    Ast::Variable(name) => Ok(self.returns.get(name).cloned().flatten()),
+                    other => {
+                        self.expr(other)?;
+                        Ok(None)
+                    }
+                }
+            }
+            Ast::Lambda { params, body, .. } => {
+                self.contract_body(params, body)?;
+                Ok(Some(Type::Function))
+            }
+            Ast::Binary { left, operator, right } => {
+                let (l, r) = (self.expr(left)?, self.expr(right)?);
+                if is_integer_op(operator) {
+                    for side in [&l, &r] {
+                        expect_integer(operator, side)?;
+                    }
+                    if l == Some(Type::Uint) || r == Some(Type::Uint) {
+                        return Ok(Some(Type::Uint));
+                    }
+                }
+                match operator {
+                    BinaryOp::Add | BinaryOp::Subtract | BinaryOp::Multiply | BinaryOp::Divide
+                        if l == Some(Type::Uint) && r == Some(Type::Uint) => Ok(Some(Type::Uint)),
+                    BinaryOp::Add | BinaryOp::Subtract | BinaryOp::Multiply | BinaryOp::Divide
+                        if l == Some(Type::Decimal) || r == Some(Type::Decimal) => Ok(Some(Type::Decimal)),
+                    BinaryOp::Equal
+                    | BinaryOp::NotEqual
+                    | BinaryOp::Less
+                    | BinaryOp::Greater
+                    | BinaryOp::LessEqual
+                    | BinaryOp::GreaterEqual => Ok(Some(Type::Signal)),
+                    _ => Ok(None),
+                }
+            }
+            Ast::Unary { operator, operand } => {
+                let known = self.expr(operand)?;
+                match operator {
+                    BinaryOp::Not => Ok(Some(Type::Signal)),
+                    BinaryOp::BitNot => {
+                        expect_integer(operator, &known)?;
+                        Ok(known)
+                    }
+                    _ => Ok(None),
+                }
+            }
+            Ast::Match { scrutinee, arms } => {
+                let known = self.expr(scrutinee)?;
+                for (pattern, body) in arms {
+                    if let Some(scrutinee_type) = &known {
+                        self.check_pattern(pattern, scrutinee_type)?;
+                    }
+                    self.scopes.push(HashMap::new());
+                    let mut names = Vec::new();
+                    pattern.bindings(&mut names);
+                    for name in names {
+                        self.declare(&name, None);
+                    }
+                    let result = self.expr(body);
+                    self.scopes.pop();
+                    result?;
+                }
+                if let Some(scrutinee_type) = &known {
+                    self.check_exhaustive(arms, scrutinee_type)?;
+                }
+                Ok(None)
+            }
+            Ast::Block(stmts) => {
+                self.scoped(stmts)?;
+                Ok(None)
+            }
+            Ast::Assign { name, value } => {
+                let known = self.expr(value)?;
+                // Reassignment can change a token's type, so take the new one
+                if let Some(scope) = self.scopes.iter_mut().rev().find(|s| s.contains_key(name)) {
+                    scope.insert(name.clone(), known.clone());
+                }
+                Ok(known)
+            }
+            Ast::ArrayLiteral(elements) => {
+                for element in elements {
+                    self.expr(element)?;
+                }
+                Ok(Some(Type::Array))
+            }
+            Ast::MapLiteral(entries) => {
+                for (key, value) in entries {
+                    self.expr(key)?;
+                    self.expr(value)?;
+                }
+                Ok(Some(Type::Map))
+            }
+            Ast::Index { object, index } => {
+                self.expr(object)?;
+                self.expr(index)?;
+                Ok(None)
+            }
+            Ast::Ternary { condition, then_branch, else_branch } => {
+                self.expr(condition)?;
+                let then_type = self.expr(then_branch)?;
+                let else_type = self.expr(else_branch)?;
+                Ok(if then_type == else_type { then_type } else { None })
+            }
+            Ast::CompoundAssign { target, value, .. } => {
+                self.expr(target)?;
+                self.expr(value)?;
+                Ok(None)
+            }
+            Ast::SetIndex { object, index, value } => {
+                self.expr(object)?;
+                self.expr(index)?;
+                self.expr(value)
+            }
+            Ast::StructLiteral { name, fields } => {
+                let declared = self.structs
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| Rekt::Type(format!("Unknown struct '{}'", name)))?;
+                for (i, (field, init)) in fields.iter().enumerate() {
+                    if fields[..i].iter().any(|(f, _)| f == field) {
+                        return Err(Rekt::Type(format!("Duplicate field '{}' in '{}'", field, name)));
+                    }
+                    let actual = self.expr(init)?;
+                    if let Some(expected) = self.field_type(name, field)? {
+                        self.expect_fits(&format!("Field '{}.{}'", name, field), &expected, &actual)?;
+                    }
+                }
+                for (field, _) in &declared {
+                    if !fields.iter().any(|(f, _)| f == field) {
+                        return Err(Rekt::Type(format!("Missing field '{}' in '{}'", field, name)));
+                    }
+                }
+                Ok(Some(Type::Struct(name.clone())))
+            }
+            Ast::Field { object, name } => match self.expr(object)? {
+                Some(Type::Struct(record)) => self.field_type(&record, name),
+                Some(other) => Err(Rekt::Type(format!("Cannot read field '{}' of {}", name, other))),
+                None => Ok(None),
+            },
+            Ast::SetField { object, name, value } => {
+                let actual = self.expr(value)?;
+                match self.expr(object)? {
+                    Some(Type::Struct(record)) => {
+                        if let Some(expected) = self.field_type(&record, name)? {
+                            self.expect_fits(&format!("Field '{}.{}'", record, name), &expected, &actual)?;
+                        }
+                    }
+                    Some(other) => {
+                        return Err(Rekt::Type(format!("Cannot write field '{}' of {}", name, other)));
+                    }
+                    None => {}
+                }
+                Ok(actual)
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+fn is_integer_op(op: &BinaryOp) -> bool {
+    matches!(
+        op,
+        BinaryOp::Modulo
+            | BinaryOp::IntDivide
+            | BinaryOp::BitAnd
+            | BinaryOp::BitOr
+            | BinaryOp::BitXor
+            | BinaryOp::ShiftLeft
+            | BinaryOp::ShiftRight
+            | BinaryOp::BitNot
+    )
+}
+
+// Integer operators take uints or number tokens; whether a token is whole
+// is only known at runtime.
+fn expect_integer(op: &BinaryOp, operand: &Option<Type>) -> Result<(), Rekt> {
+    match operand {
+        None | Some(Type::Uint | Type::Token | Type::Number) => Ok(()),
+        Some(other) => Err(Rekt::Type(format!("'{}' needs integers, but found {}", op.symbol(), other))),
+    }
+}
+
+fn covers_signal(pattern: &Pattern, expected: bool) -> bool {
+    match pattern {
+        Pattern::Literal(Value::Signal(b) | Value::Boolean(b)) => *b == expected,
+        Pattern::Or(alternatives) => alternatives.iter().any(|p| covers_signal(p, expected)),
+        other => other.is_catch_all(),
+    }
+}
+
+const WILDCARD: &Pattern = &Pattern::Wildcard;
+
+fn alternatives(pattern: &Pattern) -> Vec<&Pattern> {
+    match pattern {
+        Pattern::Or(options) => options.iter().flat_map(alternatives).collect(),
+        other => vec![other],
+    }
+}
+
+// Arrays are covered once some `[a, b, ..rest]` arm catches every long array
+// and catch-all arms exist for each shorter length.
+fn covers_arrays(patterns: &[&Pattern]) -> bool {
+    let mut exact = Vec::new();
+    let mut open_from = None;
+    for pattern in patterns {
+        if let Pattern::Array { items, rest } = pattern {
+            if items.iter().all(|p| p.is_catch_all()) {
+                match rest {
+                    Some(_) => open_from = Some(open_from.map_or(items.len(), |n: usize| n.min(items.len()))),
+                    None => exact.push(items.len()),
+                }
+            }
+        }
+    }
+    match open_from {
+        Some(n) => (0..n).all(|len| exact.contains(&len)),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn check(source: &str) -> Result<(), String> {
+        let (tokens, lines) = Lexer::new(source).tokenize_with_lines().unwrap();
+        let ast = Parser::with_lines(tokens, lines).parse().unwrap();
+        Checker::new().check(&ast).map_err(|e| e.to_string())
+    }
+
+    const POINT: &str = "struct P { x: int, y: int }\n";
+
+    #[test]
+    fn struct_literals_name_each_field_once() {
+        assert_eq!(check(&format!("{}token p = P {{ x: 1, y: 2 }};", POINT)), Ok(()));
+        assert_eq!(
+            check(&format!("{}token p = P {{ x: 1, y: 2, z: 3 }};", POINT)),
+            Err("Type error: 'P' has no field 'z'\n  ↳ at line 2".to_string())
+        );
+        assert_eq!(
+            check(&format!("{}token p = P {{ x: 1 }};", POINT)),
+            Err("Type error: Missing field 'y' in 'P'\n  ↳ at line 2".to_string())
+        );
+        assert_eq!(
+            check(&format!("{}token p = P {{ x: 1, x: 2, y: 3 }};", POINT)),
+            Err("Type error: Duplicate field 'x' in 'P'\n  ↳ at line 2".to_string())
+        );
+    }
+
+    #[test]
+    fn struct_fields_keep_their_types() {
+        assert_eq!(
+            check(&format!("{}token p = P {{ x: \"one\", y: 2 }};", POINT)),
+            Err("Type error: Field 'P.x' expects number, but found text\n  ↳ at line 2".to_string())
+        );
+        assert_eq!(
+            check(&format!("{}token p = P {{ x: 1, y: 2 }};\np.y = \"two\";", POINT)),
+            Err("Type error: Field 'P.y' expects number, but found text\n  ↳ at line 3".to_string())
+        );
+    }
+
+    #[test]
+    fn struct_matches_are_covered_field_by_field() {
+        let flag = "struct T { k: bool }\ntoken t = T { k: true };\n";
+        let both = "ping vibecheck (t) { T { k: true } => 1, T { k: false } => 0 };";
+        assert_eq!(check(&format!("{}{}", flag, both)), Ok(()));
+
+        let nested = "struct W { t: T, on: bool }\ntoken w = W { t: t, on: false };\n\
+            ping vibecheck (w) { W { t: T { k: true }, on: _ } => 1, W { on: true } => 2, \
+            W { t: T { k: false } | T { k: true }, on: false } => 3 };";
+        assert_eq!(check(&format!("{}{}", flag, nested)), Ok(()));
+
+        let head = "ping vibecheck ([7, 8]) { [a, ..rest] => a + len(rest), _ => 0 };";
+        assert_eq!(check(head), Ok(()));
+    }
+
+    #[test]
+    fn struct_matches_that_miss_a_value_are_errors() {
+        let flag = "struct T { k: bool }\ntoken t = T { k: true };\n";
+        assert_eq!(
+            check(&format!("{}ping vibecheck (t) {{ T {{ k: true }} => 1 }};", flag)),
+            Err("Type error: vibecheck on T is not exhaustive; add a '_ =>' arm\n  ↳ at line 3".to_string())
+        );
+
+        let nested = "struct W { t: T, on: bool }\ntoken w = W { t: t, on: false };\n\
+            ping vibecheck (w) { W { t: T { k: true } } => 1, W { on: true } => 2 };";
+        assert_eq!(
+            check(&format!("{}{}", flag, nested)),
+            Err("Type error: vibecheck on W is not exhaustive; add a '_ =>' arm\n  ↳ at line 5".to_string())
+        );
+
+        let amount = "struct A { n: int }\ntoken a = A { n: 1 };\nping vibecheck (a) { A { n: 0 } => 1, A { n: 1..9 } => 2 };";
+        assert!(check(amount).is_err());
+        // An untyped field might hold a number, so array arms can't cover it
+        let loose = "struct L { k }\ntoken l = L { k: 1 };\nping vibecheck (l) { L { k: [] } => 1, L { k: [_, ..] } => 2 };";
+        assert!(check(loose).is_err());
+    }
+}
@@ -1,17 +1,5 @@
 // src/error.rs
-use thiserror::Error;
-
-#[derive(Error, Debug)]
-pub enum Rekt {
-    #[error("Lexer error: {0}")]
-    Lexer(String),
-    
-    #[error("Parser error: {0}")]
-    Parser(String),
-    
-    #[error("Runtime error: {0}")]
-    Runtime(String),
-
-    #[error("Type error: {0}")]
-    Type(String),  // Changed from Syntax to Type to match usage
-}
\ No newline at end of file
+//
+// gm-language fails the same four ways transpiled code does, so both
+// report through gm-runtime's `Rekt`.
+pub use gm_runtime::Rekt;
@@ -0,0 +1,62 @@
+use tiny_keccak::{Hasher, Keccak};
+use crate::numeric::U256;
+
+// What goes in and out of the contract the way Solidity tooling expects:
+// functions are picked by the first four bytes of the hash of their
+// signature, events by the hash of theirs, and a revert says why as an
+// `Error(string)`. The events are the ones the Solidity backend emits.
+pub const INT_EVENT: &str = "BroadcastInt(int256)";
+pub const BOOL_EVENT: &str = "BroadcastBool(bool)";
+pub const TEXT_EVENT: &str = "Broadcast(string)";
+const ERROR: &str = "Error(string)";
+
+pub fn keccak(bytes: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak::v256();
+    hasher.update(bytes);
+    let mut out = [0u8; 32];
+    hasher.finalize(&mut out);
+    out
+}
+
+pub fn selector(signature: &str) -> u32 {
+    let hash = keccak(signature.as_bytes());
+    u32::from_be_bytes([hash[0], hash[1], hash[2], hash[3]])
+}
+
+pub fn topic(signature: &str) -> U256 {
+    U256::from_be_bytes(keccak(signature.as_bytes()))
+}
+
+pub fn error_selector() -> u32 {
+    selector(ERROR)
+}
+
+// The text in ABI-encoded string data: its offset, length, then bytes.
+pub fn decode_string(data: &[u8]) -> Option<String> {
+    let word = |at: usize| -> Option<usize> {
+        let bytes: [u8; 32] = data.get(at..at + 32)?.try_into().ok()?;
+        U256::from_be_bytes(bytes).as_u64().map(|n| n as usize)
+    };
+    let start = word(0)?;
+    let len = word(start)?;
+    let bytes = data.get(start + 32..start + 32 + len)?;
+    Some(String::from_utf8_lossy(bytes).into_owned())
+}
+
+// Why a call reverted, if it said.
+pub fn decode_error(data: &[u8]) -> Option<String> {
+    let (head, rest) = (data.get(..4)?, data.get(4..)?);
+    if u32::from_be_bytes(head.try_into().ok()?) != error_selector() {
+        return None;
+    }
+    decode_string(rest)
+}
+
+// A word read as the int256 it holds, in decimal.
+pub fn signed(word: U256) -> String {
+    let negative = word.to_be_bytes()[0] & 0x80 != 0;
+    match negative {
+        true => format!("-{}", word.not().wrapping_add(U256::ONE)),
+        false => word.to_string(),
+    }
+}
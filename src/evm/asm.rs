@@ -0,0 +1,258 @@
+use std::fmt::Write;
+use crate::numeric::U256;
+
+// The opcodes the compiler writes and the machine runs. Pushes, dups,
+// swaps and logs are ranges, so they're worked out from their first opcode.
+pub const STOP: u8 = 0x00;
+pub const ADD: u8 = 0x01;
+pub const MUL: u8 = 0x02;
+pub const SUB: u8 = 0x03;
+pub const DIV: u8 = 0x04;
+pub const SDIV: u8 = 0x05;
+pub const MOD: u8 = 0x06;
+pub const SMOD: u8 = 0x07;
+pub const LT: u8 = 0x10;
+pub const GT: u8 = 0x11;
+pub const SLT: u8 = 0x12;
+pub const SGT: u8 = 0x13;
+pub const EQ: u8 = 0x14;
+pub const ISZERO: u8 = 0x15;
+pub const AND: u8 = 0x16;
+pub const OR: u8 = 0x17;
+pub const XOR: u8 = 0x18;
+pub const NOT: u8 = 0x19;
+pub const SHL: u8 = 0x1b;
+pub const SHR: u8 = 0x1c;
+pub const SAR: u8 = 0x1d;
+pub const CALLVALUE: u8 = 0x34;
+pub const CALLDATALOAD: u8 = 0x35;
+pub const CALLDATASIZE: u8 = 0x36;
+pub const CODESIZE: u8 = 0x38;
+pub const CODECOPY: u8 = 0x39;
+pub const POP: u8 = 0x50;
+pub const MLOAD: u8 = 0x51;
+pub const MSTORE: u8 = 0x52;
+pub const MSTORE8: u8 = 0x53;
+pub const SLOAD: u8 = 0x54;
+pub const SSTORE: u8 = 0x55;
+pub const JUMP: u8 = 0x56;
+pub const JUMPI: u8 = 0x57;
+pub const PC: u8 = 0x58;
+pub const MSIZE: u8 = 0x59;
+pub const JUMPDEST: u8 = 0x5b;
+pub const PUSH0: u8 = 0x5f;
+pub const PUSH1: u8 = 0x60;
+pub const PUSH32: u8 = 0x7f;
+pub const DUP1: u8 = 0x80;
+pub const DUP2: u8 = 0x81;
+pub const DUP3: u8 = 0x82;
+pub const DUP16: u8 = 0x8f;
+pub const SWAP1: u8 = 0x90;
+pub const SWAP16: u8 = 0x9f;
+pub const LOG0: u8 = 0xa0;
+pub const LOG4: u8 = 0xa4;
+pub const RETURN: u8 = 0xf3;
+pub const REVERT: u8 = 0xfd;
+pub const INVALID: u8 = 0xfe;
+
+pub fn mnemonic(op: u8) -> String {
+    let name = match op {
+        STOP => "STOP",
+        ADD => "ADD",
+        MUL => "MUL",
+        SUB => "SUB",
+        DIV => "DIV",
+        SDIV => "SDIV",
+        MOD => "MOD",
+        SMOD => "SMOD",
+        LT => "LT",
+        GT => "GT",
+        SLT => "SLT",
+        SGT => "SGT",
+        EQ => "EQ",
+        ISZERO => "ISZERO",
+        AND => "AND",
+        OR => "OR",
+        XOR => "XOR",
+        NOT => "NOT",
+        SHL => "SHL",
+        SHR => "SHR",
+        SAR => "SAR",
+        CALLVALUE => "CALLVALUE",
+        CALLDATALOAD => "CALLDATALOAD",
+        CALLDATASIZE => "CALLDATASIZE",
+        CODESIZE => "CODESIZE",
+        CODECOPY => "CODECOPY",
+        POP => "POP",
+        MLOAD => "MLOAD",
+        MSTORE => "MSTORE",
+        MSTORE8 => "MSTORE8",
+        SLOAD => "SLOAD",
+        SSTORE => "SSTORE",
+        JUMP => "JUMP",
+        JUMPI => "JUMPI",
+        PC => "PC",
+        MSIZE => "MSIZE",
+        JUMPDEST => "JUMPDEST",
+        PUSH0 => "PUSH0",
+        RETURN => "RETURN",
+        REVERT => "REVERT",
+        INVALID => "INVALID",
+        PUSH1..=PUSH32 => return format!("PUSH{}", op - PUSH1 + 1),
+        DUP1..=DUP16 => return format!("DUP{}", op - DUP1 + 1),
+        SWAP1..=SWAP16 => return format!("SWAP{}", op - SWAP1 + 1),
+        LOG0..=LOG4 => return format!("LOG{}", op - LOG0),
+        _ => return format!("UNKNOWN(0x{:02x})", op),
+    };
+    name.to_string()
+}
+
+// How many bytes of data follow an opcode.
+pub fn immediate(op: u8) -> usize {
+    match op {
+        PUSH1..=PUSH32 => (op - PUSH1 + 1) as usize,
+        _ => 0,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Label(pub usize);
+
+#[derive(Debug, Clone, PartialEq)]
+enum Item {
+    Op(u8),
+    Push(U256),
+    PushLabel(Label),
+    Mark(Label), // a JUMPDEST the label points at
+}
+
+// Code being written, with jumps to labels that are only placed once the
+// whole of it is known. Every label is pushed as two bytes, which is
+// plenty for the 24KB a contract may hold. Labels are numbered by whoever
+// writes the code, so pieces written apart can be joined.
+#[derive(Default)]
+pub struct Asm {
+    items: Vec<Item>,
+}
+
+impl Asm {
+    pub fn op(&mut self, op: u8) -> &mut Self {
+        self.items.push(Item::Op(op));
+        self
+    }
+
+    pub fn push(&mut self, value: U256) -> &mut Self {
+        self.items.push(Item::Push(value));
+        self
+    }
+
+    pub fn num(&mut self, n: u64) -> &mut Self {
+        self.push(U256::from_u64(n))
+    }
+
+    pub fn push_label(&mut self, label: Label) -> &mut Self {
+        self.items.push(Item::PushLabel(label));
+        self
+    }
+
+    pub fn mark(&mut self, label: Label) -> &mut Self {
+        self.items.push(Item::Mark(label));
+        self
+    }
+
+    pub fn jump(&mut self, label: Label) -> &mut Self {
+        self.push_label(label).op(JUMP)
+    }
+
+    // Jumps if the word on top of the stack isn't zero.
+    pub fn jump_if(&mut self, label: Label) -> &mut Self {
+        self.push_label(label).op(JUMPI)
+    }
+
+    // A push whose value is only known later, to be set with `fill`.
+    pub fn placeholder(&mut self) -> usize {
+        self.items.push(Item::Push(U256::ZERO));
+        self.items.len() - 1
+    }
+
+    pub fn fill(&mut self, at: usize, value: U256) {
+        self.items[at] = Item::Push(value);
+    }
+
+    pub fn extend(&mut self, other: Asm) {
+        self.items.extend(other.items);
+    }
+
+    pub fn assemble(&self) -> Vec<u8> {
+        let labels = self.items.iter().filter_map(|item| match item {
+            Item::Mark(label) | Item::PushLabel(label) => Some(label.0 + 1),
+            _ => None,
+        });
+        let mut at = vec![0usize; labels.max().unwrap_or(0)];
+        let mut offset = 0;
+        for item in &self.items {
+            if let Item::Mark(label) = item {
+                at[label.0] = offset;
+            }
+            offset += size(item);
+        }
+        let mut code = Vec::with_capacity(offset);
+        for item in &self.items {
+            match item {
+                Item::Op(op) => code.push(*op),
+                Item::Push(value) => push_bytes(&mut code, *value),
+                Item::PushLabel(label) => {
+                    code.push(PUSH1 + 1);
+                    code.extend_from_slice(&(at[label.0] as u16).to_be_bytes());
+                }
+                Item::Mark(_) => code.push(JUMPDEST),
+            }
+        }
+        code
+    }
+}
+
+fn size(item: &Item) -> usize {
+    match item {
+        Item::Op(_) | Item::Mark(_) => 1,
+        Item::Push(value) => 1 + significant(*value).len(),
+        Item::PushLabel(_) => 3,
+    }
+}
+
+// A value's big-endian bytes without the leading zeros.
+fn significant(value: U256) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(32);
+    bytes[start..].to_vec()
+}
+
+fn push_bytes(code: &mut Vec<u8>, value: U256) {
+    let bytes = significant(value);
+    match bytes.len() {
+        0 => code.push(PUSH0),
+        n => {
+            code.push(PUSH1 + n as u8 - 1);
+            code.extend_from_slice(&bytes);
+        }
+    }
+}
+
+// One line per instruction, with its offset, as the goldens hold it.
+pub fn disassemble(code: &[u8]) -> String {
+    let mut out = String::new();
+    let mut pc = 0;
+    while pc < code.len() {
+        let op = code[pc];
+        let _ = write!(out, "{:04x}  {}", pc, mnemonic(op));
+        let n = immediate(op);
+        if n > 0 {
+            let data = &code[(pc + 1).min(code.len())..(pc + 1 + n).min(code.len())];
+            let hex: String = data.iter().map(|b| format!("{:02x}", b)).collect();
+            let _ = write!(out, " 0x{}", hex);
+        }
+        out.push('\n');
+        pc += 1 + n;
+    }
+    out
+}
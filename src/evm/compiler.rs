@@ -0,0 +1,1319 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use crate::error::Rekt;
+use gm_runtime::BUILTINS;
+use crate::numeric::U256;
+use crate::parser::ast::Ast;
+use crate::shared_types::{BinaryOp, Type, Value};
+use crate::transpile::{declarations, ends_run, target_path, DeclKind, NOT_A_SIGNAL};
+use super::abi::{self, BOOL_EVENT, INT_EVENT, TEXT_EVENT};
+use super::asm::*;
+
+// Lowers the numeric part of GM to EVM bytecode: one contract whose
+// top-level tokens and blocks are storage slots, numbered in the order
+// they're declared, and whose `mine` contracts are functions picked by
+// their selector. The rest of the script is the function `run()`, so
+// deploying sets nothing up and calling `run()` does what running the
+// script does. Tokens are int256 words and signals bools, as in the
+// Solidity backend; a broadcast emits the same events it does.
+//
+// Inside the contract, GM's contracts call each other by jumping, with
+// their params and tokens in a frame of memory words. Memory starts with
+// the top of the frame stack and the current frame, then two scratch
+// words; frames follow from FRAMES on. Errors GM only hits at runtime
+// revert with an `Error(string)` holding the message the tree walker
+// gives. What this can't express (fractions, text other than broadcasting
+// a literal, collections, structs, uints and decimals, contracts as
+// values) is a Type error naming the line, as with WebAssembly, and types
+// are worked out in the same number of passes.
+//
+// Words are 256 bits where GM numbers are f64, so past 2^53 the two stop
+// agreeing: GM rounds where the EVM stays exact and wraps at 2^255.
+pub fn compile(ast: &Ast) -> Result<Compiled, Rekt> {
+    let stmts = match ast {
+        Ast::Program(stmts) => stmts.as_slice(),
+        other => std::slice::from_ref(other),
+    };
+    let mut known = Known::default();
+    for _ in 0..MAX_PASSES {
+        let mut gen = Gen::new(&known);
+        let compiled = gen.program(stmts);
+        if gen.learned == known {
+            return compiled;
+        }
+        known = gen.learned;
+    }
+    Gen::new(&known).program(stmts)
+}
+
+const MAX_PASSES: usize = 16;
+
+const SP: u64 = 0x00; // the first free byte above every frame
+const FP: u64 = 0x20; // the frame of the function running
+const LEFT: u64 = 0x40; // scratch for operators that need their operands twice
+const RIGHT: u64 = 0x60;
+const FRAMES: u64 = 0x80;
+const WORD: u64 = 32;
+
+// The contract: the code that deploys it, the code it runs, and the
+// functions it can be called with.
+pub struct Compiled {
+    pub creation: Vec<u8>,
+    pub runtime: Vec<u8>,
+    pub functions: Vec<(String, u32)>, // signature and selector
+}
+
+impl Compiled {
+    // What `--evm` writes and the goldens hold: the selectors, then both
+    // pieces of code disassembled. Runtime offsets start at zero, as its
+    // jumps count them.
+    pub fn listing(&self) -> String {
+        let mut out = String::new();
+        for (signature, selector) in &self.functions {
+            let _ = writeln!(out, "// {:08x}  {}", selector, signature);
+        }
+        out.push_str("\ncreation:\n");
+        out.push_str(&disassemble(&self.creation));
+        out.push_str("\nruntime:\n");
+        out.push_str(&disassemble(&self.runtime));
+        out
+    }
+
+    // The creation code with the runtime after it, which is what a deploy sends.
+    pub fn initcode(&self) -> Vec<u8> {
+        let mut code = self.creation.clone();
+        code.extend_from_slice(&self.runtime);
+        code
+    }
+}
+
+fn unsupported(what: &str) -> Rekt {
+    Rekt::Type(format!("{} can't be compiled to EVM bytecode", what))
+}
+
+fn unknown(what: &str) -> Rekt {
+    Rekt::Type(format!("Can't tell the type of {}, which EVM bytecode needs to know", what))
+}
+
+fn fractions() -> Rekt {
+    unsupported("'/' on numbers, which gives fractions in GM (use '~/' to divide whole numbers),")
+}
+
+// The types GM values have in the contract. Everything but Never is one
+// word on the stack.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Et {
+    Num,
+    Sig,
+    Null,    // what a contract that returns nothing gives back, as a zero
+    Never,   // after a revert, where nothing runs
+    Unknown, // not worked out yet in this pass
+}
+
+impl Et {
+    fn from_type(t: &Type) -> Option<Et> {
+        match t {
+            Type::Number | Type::Token => Some(Et::Num),
+            Type::Boolean | Type::Signal => Some(Et::Sig),
+            _ => None,
+        }
+    }
+
+    fn abi(self) -> &'static str {
+        match self {
+            Et::Sig => "bool",
+            _ => "int256",
+        }
+    }
+
+    // As GM names the type in its messages
+    fn name(self) -> &'static str {
+        match self {
+            Et::Num | Et::Unknown => "vibe",
+            Et::Sig => "signal",
+            Et::Null => "lonely",
+            Et::Never => "nothing",
+        }
+    }
+}
+
+#[derive(Default, Clone, PartialEq)]
+struct Known {
+    types: HashMap<usize, Et>,
+    returns: HashMap<String, Option<Et>>, // None for a contract that returns nothing
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Place {
+    Storage(u64),
+    Frame(u64), // a word of the running function's frame
+    Mine(usize),
+}
+
+#[derive(Clone)]
+struct Binding {
+    id: usize,
+    place: Place,
+    kind: Option<DeclKind>,
+    hoisted: bool,
+    live: bool,
+    flag: Option<Place>, // for a hoisted token: whether it's been minted
+}
+
+struct Mine {
+    name: String,
+    params: Vec<(String, Et)>,
+    entry: Label,
+    code: Option<Asm>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Sink {
+    Discard,
+    Return,
+}
+
+enum Returned {
+    Value(Et),
+    Nothing,
+    Unknown,
+}
+
+// The function being written. Word 0 of its frame is the caller's frame.
+struct Code {
+    asm: Asm,
+    words: u64,
+    size_at: usize, // the prologue's push of the frame size
+    loops: Vec<(Label, Label)>, // where a crash and a skip go
+}
+
+struct Gen<'a> {
+    known: &'a Known,
+    learned: Known,
+    errors: Vec<Rekt>,
+    unknown: Option<String>,
+    labels: usize,
+    slots: u64,
+    mines: Vec<Mine>,
+
+    code: Code,
+    scopes: Vec<HashMap<String, Binding>>,
+    floor: usize,
+    mine: Option<usize>,
+    returned: Vec<Returned>,
+    ids: usize,
+}
+
+impl<'a> Gen<'a> {
+    fn new(known: &'a Known) -> Self {
+        Gen {
+            known,
+            learned: known.clone(),
+            errors: Vec::new(),
+            unknown: None,
+            labels: 0,
+            slots: 0,
+            mines: Vec::new(),
+            code: Code { asm: Asm::default(), words: 1, size_at: 0, loops: Vec::new() },
+            scopes: Vec::new(),
+            floor: 0,
+            mine: None,
+            returned: Vec::new(),
+            ids: 0,
+        }
+    }
+
+    fn label(&mut self) -> Label {
+        self.labels += 1;
+        Label(self.labels - 1)
+    }
+
+    fn asm(&mut self) -> &mut Asm {
+        &mut self.code.asm
+    }
+
+    fn program(&mut self, stmts: &[Ast]) -> Result<Compiled, Rekt> {
+        self.top_level(stmts)?;
+        let run = self.label();
+        self.asm().mark(run);
+        self.prologue(0);
+        for stmt in stmts {
+            let (line, unlocated) = match stmt {
+                Ast::Located { line, statement } => (Some(*line), statement.as_ref()),
+                other => (None, other),
+            };
+            let result = match unlocated {
+                Ast::FunctionDecl { name, body, .. } => self.function(name, body),
+                other => self.statement(other, Sink::Discard),
+            };
+            if let Err(e) = result {
+                self.errors.push(match line {
+                    Some(line) => e.at_line(line),
+                    None => e,
+                });
+            }
+        }
+        self.asm().num(0);
+        self.epilogue();
+        if !self.errors.is_empty() {
+            return Err(self.errors.remove(0));
+        }
+        if let Some(what) = &self.unknown {
+            return Err(unknown(what));
+        }
+        Ok(self.assemble(run))
+    }
+
+    // A storage slot for every top-level token, and the signature of
+    // every contract, which can be called from any other.
+    fn top_level(&mut self, stmts: &[Ast]) -> Result<(), Rekt> {
+        let decls = declarations(&[], stmts);
+        for stmt in stmts {
+            let Ast::FunctionDecl { name, params, .. } = stmt.unlocated() else { continue };
+            let decl = decls.iter().find(|d| d.name == *name);
+            match decl.map(|d| (d.kind, d.hoisted)) {
+                Some((DeclKind::Contract(_), false)) => {}
+                Some((DeclKind::Contract(_), true)) => {
+                    return Err(unsupported(&format!("Contract '{}', mined more than once,", name)))
+                }
+                _ => return Err(unsupported(&format!("'{}', both a token and a contract,", name))),
+            }
+            if name == "run" {
+                return Err(unsupported("A contract named 'run', which is what the contract runs the script as,"));
+            }
+            let mut typed = Vec::new();
+            for (param, t) in params {
+                let ty = Et::from_type(t)
+                    .ok_or_else(|| unsupported(&format!("Param '{}' of '{}', of type {},", param, name, t)))?;
+                typed.push((param.clone(), ty));
+            }
+            let entry = self.label();
+            self.mines.push(Mine { name: name.clone(), params: typed, entry, code: None });
+        }
+
+        let mut scope = HashMap::new();
+        for decl in decls {
+            let id = self.next_id();
+            let binding = match decl.kind {
+                DeclKind::Contract(_) => match self.mines.iter().position(|m| m.name == decl.name) {
+                    Some(index) => Binding {
+                        id,
+                        place: Place::Mine(index),
+                        kind: Some(decl.kind),
+                        hoisted: false,
+                        live: false,
+                        flag: None,
+                    },
+                    None => {
+                        let what = format!("Contract '{}', mined inside a sus or grind body,", decl.name);
+                        return Err(unsupported(&what));
+                    }
+                },
+                kind => {
+                    let place = self.slot();
+                    let flag = decl.hoisted.then(|| self.slot());
+                    Binding { id, place, kind: Some(kind), hoisted: decl.hoisted, live: decl.hoisted, flag }
+                }
+            };
+            scope.insert(decl.name, binding);
+        }
+        self.scopes.push(scope);
+        Ok(())
+    }
+
+    fn slot(&mut self) -> Place {
+        self.slots += 1;
+        Place::Storage(self.slots - 1)
+    }
+
+    // A contract's function, written where the script mines it.
+    fn function(&mut self, name: &str, body: &[Ast]) -> Result<(), Rekt> {
+        let Some((_, binding)) = self.lookup(name) else { return Ok(()) };
+        let Place::Mine(index) = binding.place else { return Ok(()) };
+        self.minted(name);
+
+        let entry = self.mines[index].entry;
+        let fresh = Code { asm: Asm::default(), words: 1, size_at: 0, loops: Vec::new() };
+        let code = std::mem::replace(&mut self.code, fresh);
+        let floor = std::mem::replace(&mut self.floor, self.scopes.len());
+        let mine = self.mine.replace(index);
+        self.returned.clear();
+        let params = self.mines[index].params.clone();
+        self.asm().mark(entry);
+        self.prologue(params.len());
+        // Params are the first words `enter` hands out
+        let result = self.enter(&params, body).and_then(|()| {
+            let result = self.statements(body, Sink::Return);
+            self.leave();
+            result
+        });
+        if !ends_in_return(body) {
+            self.asm().num(0);
+            self.epilogue();
+        }
+        let written = std::mem::replace(&mut self.code, code);
+        self.floor = floor;
+        self.mine = mine;
+        result?;
+
+        // A contract that only returns what isn't known yet (its own
+        // result, say) hasn't been shown to return nothing
+        let returns = self.returns_of(name)?;
+        let guessing = self.returned.iter().all(|r| matches!(r, Returned::Unknown));
+        if returns.is_some() || !guessing || self.returned.is_empty() {
+            self.learned.returns.insert(name.to_string(), returns);
+        }
+        self.mines[index].code = Some(written.finish());
+        Ok(())
+    }
+
+    fn returns_of(&self, name: &str) -> Result<Option<Et>, Rekt> {
+        let mut found: Option<Et> = None;
+        let mut nothing = false;
+        for returned in &self.returned {
+            match (returned, found) {
+                (Returned::Unknown, _) => {}
+                (Returned::Nothing, _) => nothing = true,
+                (Returned::Value(ty), None) => found = Some(*ty),
+                (Returned::Value(ty), Some(first)) if *ty != first => {
+                    return Err(unsupported(&format!(
+                        "Contract '{}', which returns both {} and {},",
+                        name,
+                        first.name(),
+                        ty.name()
+                    )));
+                }
+                _ => {}
+            }
+        }
+        match found {
+            Some(_) if nothing => Err(unsupported(&format!(
+                "Contract '{}', which returns a value in one place and nothing in another,",
+                name
+            ))),
+            found => Ok(found),
+        }
+    }
+
+    // The dispatcher, then each function it can reach, then what they
+    // call. Calldata too short for what it picks reverts like an unknown
+    // selector does.
+    fn assemble(&mut self, run: Label) -> Compiled {
+        let mut exports: Vec<(String, Vec<Et>, Label, Option<Et>)> = vec![("run".to_string(), Vec::new(), run, None)];
+        for mine in &self.mines {
+            let params = mine.params.iter().map(|(_, ty)| *ty).collect();
+            let returns = self.known.returns.get(&mine.name).copied().flatten();
+            exports.push((mine.name.clone(), params, mine.entry, returns));
+        }
+
+        let mut head = Asm::default();
+        let reject = self.label();
+        head.num(FRAMES).num(SP).op(MSTORE);
+        head.num(4).op(CALLDATASIZE).op(LT).jump_if(reject);
+        head.num(0).op(CALLDATALOAD).num(0xe0).op(SHR);
+        let mut functions = Vec::new();
+        let mut entries = Vec::new();
+        for (name, params, _, _) in &exports {
+            let types: Vec<&str> = params.iter().map(|ty| ty.abi()).collect();
+            let signature = format!("{}({})", name, types.join(","));
+            let selector = abi::selector(&signature);
+            let entry = self.label();
+            head.op(DUP1).num(selector as u64).op(EQ).jump_if(entry);
+            functions.push((signature, selector));
+            entries.push(entry);
+        }
+        head.mark(reject).num(0).num(0).op(REVERT);
+
+        for ((_, params, function, returns), entry) in exports.iter().zip(entries) {
+            let back = self.label();
+            head.mark(entry).op(POP);
+            head.op(CALLDATASIZE).num(4 + WORD * params.len() as u64).op(GT).jump_if(reject);
+            head.push_label(back);
+            for (i, ty) in params.iter().enumerate() {
+                head.num(4 + WORD * i as u64).op(CALLDATALOAD);
+                if *ty == Et::Sig {
+                    head.op(ISZERO).op(ISZERO);
+                }
+            }
+            head.jump(*function).mark(back);
+            match returns {
+                Some(Et::Num | Et::Sig) => head.num(LEFT).op(MSTORE).num(WORD).num(LEFT).op(RETURN),
+                _ => head.op(POP).num(0).num(0).op(RETURN),
+            };
+        }
+
+        let run_code = std::mem::replace(&mut self.code, Code { asm: Asm::default(), words: 1, size_at: 0, loops: Vec::new() });
+        head.extend(run_code.finish());
+        for mine in &mut self.mines {
+            if let Some(code) = mine.code.take() {
+                head.extend(code);
+            }
+        }
+        let runtime = head.assemble();
+
+        // Copies the runtime out of the code being run and returns it
+        let mut creation = Vec::new();
+        creation.push(PUSH1 + 1);
+        creation.extend_from_slice(&(runtime.len() as u16).to_be_bytes());
+        creation.extend_from_slice(&[DUP1, PUSH1, 10, PUSH0, CODECOPY, PUSH0, RETURN]);
+        Compiled { creation, runtime, functions }
+    }
+
+    // ------------------------------------------------------------ frames
+
+    // With the return address and `params` args on the stack: the frame
+    // goes on top of the frame stack, holding the caller's frame and the args.
+    fn prologue(&mut self, params: usize) {
+        self.asm().num(SP).op(MLOAD);
+        self.asm().num(FP).op(MLOAD).op(DUP2).op(MSTORE);
+        self.asm().op(DUP1).num(FP).op(MSTORE);
+        let size_at = self.asm().placeholder();
+        self.code.size_at = size_at;
+        self.asm().op(ADD).num(SP).op(MSTORE);
+        for i in (0..params).rev() {
+            self.set(Place::Frame(1 + i as u64));
+        }
+    }
+
+    // With the return address and the result on the stack: drops the
+    // frame and jumps back with the result.
+    fn epilogue(&mut self) {
+        self.asm().num(FP).op(MLOAD).num(SP).op(MSTORE);
+        self.asm().num(FP).op(MLOAD).op(MLOAD).num(FP).op(MSTORE);
+        self.asm().op(SWAP1).op(JUMP);
+    }
+
+    fn word(&mut self) -> Place {
+        self.code.words += 1;
+        Place::Frame(self.code.words - 1)
+    }
+
+    fn get(&mut self, place: Place) {
+        match place {
+            Place::Storage(slot) => {
+                self.asm().num(slot).op(SLOAD);
+            }
+            Place::Frame(word) => {
+                self.asm().num(FP).op(MLOAD).num(WORD * word).op(ADD).op(MLOAD);
+            }
+            Place::Mine(_) => {}
+        }
+    }
+
+    fn set(&mut self, place: Place) {
+        match place {
+            Place::Storage(slot) => {
+                self.asm().num(slot).op(SSTORE);
+            }
+            Place::Frame(word) => {
+                self.asm().num(FP).op(MLOAD).num(WORD * word).op(ADD).op(MSTORE);
+            }
+            Place::Mine(_) => {}
+        }
+    }
+
+    // ------------------------------------------------------------ scopes
+
+    fn next_id(&mut self) -> usize {
+        self.ids += 1;
+        self.ids
+    }
+
+    fn declared_type(&mut self, id: usize, name: &str) -> Et {
+        match self.learned.types.get(&id) {
+            Some(ty) => *ty,
+            None => {
+                self.unknown.get_or_insert_with(|| format!("'{}'", name));
+                Et::Unknown
+            }
+        }
+    }
+
+    // Opens a scope holding `bound`, then whatever `stmts` declare, each
+    // in a word of the frame.
+    fn enter(&mut self, bound: &[(String, Et)], stmts: &[Ast]) -> Result<(), Rekt> {
+        let mut scope = HashMap::new();
+        for (name, ty) in bound {
+            let id = self.next_id();
+            self.learned.types.insert(id, *ty);
+            let place = self.word();
+            scope.insert(name.clone(), Binding { id, place, kind: None, hoisted: false, live: true, flag: None });
+        }
+        let names: Vec<String> = bound.iter().map(|(name, _)| name.clone()).collect();
+        for decl in declarations(&names, stmts) {
+            if names.contains(&decl.name) {
+                continue;
+            }
+            if let DeclKind::Contract(_) = decl.kind {
+                let what = format!("Contract '{}', mined inside a block or another contract,", decl.name);
+                return Err(unsupported(&what));
+            }
+            let id = self.next_id();
+            let place = self.word();
+            let flag = decl.hoisted.then(|| self.word());
+            let binding = Binding { id, place, kind: Some(decl.kind), hoisted: decl.hoisted, live: decl.hoisted, flag };
+            scope.insert(decl.name, binding);
+        }
+        self.scopes.push(scope);
+        Ok(())
+    }
+
+    fn leave(&mut self) {
+        self.scopes.pop();
+    }
+
+    // The binding a name refers to here, searching the scopes below
+    // `upto`. Inside the function being written, a token whose
+    // declaration hasn't been reached isn't minted, so an outer one is
+    // found instead, like at runtime.
+    fn lookup_below(&self, name: &str, upto: usize) -> Option<(usize, Binding)> {
+        let mut unminted = None;
+        for (index, scope) in self.scopes[..upto].iter().enumerate().rev() {
+            if let Some(binding) = scope.get(name) {
+                if binding.live || index < self.floor {
+                    return Some((index, binding.clone()));
+                }
+                unminted.get_or_insert((index, binding.clone()));
+            }
+        }
+        unminted
+    }
+
+    fn lookup(&self, name: &str) -> Option<(usize, Binding)> {
+        self.lookup_below(name, self.scopes.len())
+    }
+
+    fn minted_binding(&self, name: &str) -> Option<(usize, Binding)> {
+        self.lookup(name).filter(|(index, binding)| binding.live || *index < self.floor)
+    }
+
+    fn minted(&mut self, name: &str) {
+        if let Some(binding) = self.scopes.last_mut().and_then(|scope| scope.get_mut(name)) {
+            binding.live = true;
+        }
+    }
+
+    fn learn(&mut self, binding: &Binding, name: &str, ty: Et) -> Result<(), Rekt> {
+        match ty {
+            Et::Unknown => return Ok(()),
+            Et::Never => {
+                self.learned.types.entry(binding.id).or_insert(Et::Never);
+                return Ok(());
+            }
+            _ => {}
+        }
+        match self.learned.types.get(&binding.id) {
+            Some(held) if *held != ty && *held != Et::Never => Err(unsupported(&format!(
+                "'{}', which holds {} in one place and {} in another,",
+                name,
+                held.name(),
+                ty.name()
+            ))),
+            _ => {
+                self.learned.types.insert(binding.id, ty);
+                Ok(())
+            }
+        }
+    }
+
+    // ------------------------------------------------------------ emitting
+
+    fn drop_value(&mut self, ty: Et) {
+        if ty != Et::Never {
+            self.asm().op(POP);
+        }
+    }
+
+    // Writes `text` as ABI string data at the top of memory, after
+    // `head` (a selector, for a revert), leaving where it starts on the
+    // stack. Returns how many bytes it takes.
+    fn string_data(&mut self, head: Option<u32>, text: &str) -> u64 {
+        let bytes = text.as_bytes();
+        let start = if head.is_some() { 4 } else { 0 };
+        self.asm().num(SP).op(MLOAD);
+        if let Some(selector) = head {
+            self.asm().num(selector as u64).num(0xe0).op(SHL).op(DUP2).op(MSTORE);
+        }
+        self.asm().num(WORD).op(DUP2).num(start).op(ADD).op(MSTORE);
+        self.asm().num(bytes.len() as u64).op(DUP2).num(start + WORD).op(ADD).op(MSTORE);
+        for (i, chunk) in bytes.chunks(32).enumerate() {
+            let mut word = [0u8; 32];
+            word[..chunk.len()].copy_from_slice(chunk);
+            let at = start + WORD * (2 + i as u64);
+            self.asm().push(U256::from_be_bytes(word)).op(DUP2).num(at).op(ADD).op(MSTORE);
+        }
+        start + WORD * (2 + bytes.len().div_ceil(32) as u64)
+    }
+
+    // Reverts the way the tree walker fails at this point.
+    fn fail(&mut self, error: Rekt) -> Result<Et, Rekt> {
+        let reason = error.to_string();
+        let size = self.string_data(Some(abi::error_selector()), &reason);
+        self.asm().num(size).op(SWAP1).op(REVERT);
+        Ok(Et::Never)
+    }
+
+    // Fails with the message `error` makes of the signal on top of the stack.
+    fn fail_signal(&mut self, error: impl Fn(String) -> Rekt) -> Result<Et, Rekt> {
+        let yes = self.label();
+        self.asm().jump_if(yes);
+        self.fail(error("false".to_string()))?;
+        self.asm().mark(yes);
+        self.fail(error("true".to_string()))
+    }
+
+    fn broadcast_word(&mut self, event: &str) {
+        self.asm().num(SP).op(MLOAD).op(MSTORE);
+        self.asm().push(abi::topic(event)).num(WORD).num(SP).op(MLOAD).op(LOG0 + 1);
+    }
+
+    // ------------------------------------------------------------ statements
+
+    fn statements(&mut self, stmts: &[Ast], sink: Sink) -> Result<(), Rekt> {
+        if stmts.is_empty() && sink == Sink::Return {
+            self.give_back(Et::Null);
+        }
+        for (index, stmt) in stmts.iter().enumerate() {
+            let last = index + 1 == stmts.len();
+            self.statement(stmt, if last { sink } else { Sink::Discard })?;
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self, stmt: &Ast, sink: Sink) -> Result<(), Rekt> {
+        match stmt {
+            Ast::Located { line, statement } => self.statement(statement, sink).map_err(|e| e.at_line(*line)),
+
+            Ast::VariableDecl { name, initializer, .. } => {
+                let ty = self.value(initializer)?;
+                self.mint(name, ty)?;
+                if sink == Sink::Return {
+                    let ty = self.read(name)?;
+                    self.give_back(ty);
+                }
+                Ok(())
+            }
+            Ast::FunctionDecl { .. } => Err(unsupported("A contract mined here")),
+            Ast::StructDecl { .. } => Err(unsupported("A struct")),
+
+            Ast::If { condition, then_branch, else_branch } => {
+                let (otherwise, end) = (self.label(), self.label());
+                self.condition(condition)?;
+                self.asm().op(ISZERO).jump_if(otherwise);
+                self.statements(then_branch, sink)?;
+                self.asm().jump(end).mark(otherwise);
+                match else_branch {
+                    Some(else_branch) => self.statements(else_branch, sink)?,
+                    None => self.give_back_null(sink),
+                }
+                self.asm().mark(end);
+                Ok(())
+            }
+            Ast::While { condition, body } => {
+                let (top, end) = (self.label(), self.label());
+                self.asm().mark(top);
+                self.condition(condition)?;
+                self.asm().op(ISZERO).jump_if(end);
+                self.loop_body(body, (end, top))?;
+                self.asm().jump(top).mark(end);
+                self.give_back_null(sink);
+                Ok(())
+            }
+            Ast::ForRange { variable, start, end, step, body } => {
+                self.for_range(variable, start, end, step.as_deref(), body)?;
+                self.give_back_null(sink);
+                Ok(())
+            }
+            Ast::ForEach { .. } => Err(unsupported("Farming an array or mapping")),
+            Ast::Block(stmts) => {
+                self.enter(&[], stmts)?;
+                let result = self.statements(stmts, sink);
+                self.leave();
+                result
+            }
+
+            Ast::PrintStmt(value) => {
+                match value.unlocated() {
+                    Ast::Literal(Value::Text(text)) => {
+                        let size = self.string_data(None, text);
+                        self.asm().push(abi::topic(TEXT_EVENT)).op(SWAP1).num(size).op(SWAP1).op(LOG0 + 1);
+                    }
+                    _ => match self.expr(value)? {
+                        Et::Num | Et::Unknown => self.broadcast_word(INT_EVENT),
+                        Et::Sig => self.broadcast_word(BOOL_EVENT),
+                        Et::Null => return Err(unsupported("Broadcasting what a contract that returns nothing gives back")),
+                        Et::Never => {}
+                    },
+                }
+                self.give_back_null(sink);
+                Ok(())
+            }
+            Ast::ReturnStmt(value) => {
+                let ty = match value {
+                    Some(value) => self.value(value)?,
+                    None => {
+                        self.asm().num(0);
+                        Et::Null
+                    }
+                };
+                self.give_back(ty);
+                Ok(())
+            }
+            stmt if ends_run(stmt, self.code.loops.len()) => {
+                self.asm().num(0);
+                self.give_back(Et::Null);
+                Ok(())
+            }
+            Ast::Break | Ast::Continue => {
+                let (end, next) = self.code.loops[self.code.loops.len() - 1];
+                let target = if matches!(stmt, Ast::Break) { end } else { next };
+                self.asm().jump(target);
+                Ok(())
+            }
+            Ast::ExpressionStmt(value) => self.expression_statement(value, sink),
+            other => self.expression_statement(other, sink),
+        }
+    }
+
+    fn loop_body(&mut self, body: &[Ast], targets: (Label, Label)) -> Result<(), Rekt> {
+        self.code.loops.push(targets);
+        let result = self.statements(body, Sink::Discard);
+        self.code.loops.pop();
+        result
+    }
+
+    fn expression_statement(&mut self, value: &Ast, sink: Sink) -> Result<(), Rekt> {
+        let ty = self.expr(value)?;
+        match sink {
+            Sink::Discard => self.drop_value(ty),
+            Sink::Return => self.give_back(ty),
+        }
+        Ok(())
+    }
+
+    // Returns from the running function with the word on top of the stack.
+    fn give_back(&mut self, ty: Et) {
+        match ty {
+            Et::Never => return,
+            Et::Unknown => self.returned.push(Returned::Unknown),
+            Et::Null => self.returned.push(Returned::Nothing),
+            ty => self.returned.push(Returned::Value(ty)),
+        }
+        self.epilogue();
+    }
+
+    fn give_back_null(&mut self, sink: Sink) {
+        if sink == Sink::Return {
+            self.asm().num(0);
+            self.give_back(Et::Null);
+        }
+    }
+
+    fn for_range(&mut self, variable: &str, start: &Ast, end: &Ast, step: Option<&Ast>, body: &[Ast]) -> Result<(), Rekt> {
+        let mut bounds = Vec::new();
+        for (what, bound) in [("start", Some(start)), ("end", Some(end)), ("step", step)] {
+            match bound {
+                Some(bound) => match self.value(bound)? {
+                    Et::Num | Et::Unknown => {}
+                    Et::Never => return Ok(()),
+                    other => {
+                        self.asm().op(POP);
+                        self.fail(Rekt::Type(format!("Range {} must be a number, but found {}", what, other.name())))?;
+                        return Ok(());
+                    }
+                },
+                None => {
+                    self.asm().num(1);
+                }
+            }
+            let place = self.word();
+            self.set(place);
+            bounds.push(place);
+        }
+        let (current, end, step) = (bounds[0], bounds[1], bounds[2]);
+
+        let stepping = self.label();
+        self.get(step);
+        self.asm().jump_if(stepping);
+        self.fail(Rekt::Runtime("Range step can't be zero".to_string()))?;
+        self.asm().mark(stepping);
+
+        let (top, next, done) = (self.label(), self.label(), self.label());
+        self.asm().mark(top);
+        // (step > 0 and current < end) or (step < 0 and current > end)
+        for (before_end, stepping_up) in [(SLT, SGT), (SGT, SLT)] {
+            self.get(end);
+            self.get(current);
+            self.asm().op(before_end);
+            self.asm().num(0);
+            self.get(step);
+            self.asm().op(stepping_up);
+            self.asm().op(AND);
+        }
+        self.asm().op(OR).op(ISZERO).jump_if(done);
+
+        self.enter(&[(variable.to_string(), Et::Num)], body)?;
+        if let Some((_, binding)) = self.lookup(variable) {
+            self.get(current);
+            self.set(binding.place);
+        }
+        let result = self.loop_body(body, (done, next));
+        self.leave();
+        result?;
+
+        self.asm().mark(next);
+        self.get(step);
+        self.get(current);
+        self.asm().op(ADD);
+        self.set(current);
+        self.asm().jump(top).mark(done);
+        Ok(())
+    }
+
+    fn condition(&mut self, ast: &Ast) -> Result<(), Rekt> {
+        match self.value(ast)? {
+            Et::Sig | Et::Never | Et::Unknown => {}
+            _ => {
+                self.asm().op(POP);
+                self.fail(Rekt::Lexer(NOT_A_SIGNAL.to_string()))?;
+            }
+        }
+        Ok(())
+    }
+
+    // ------------------------------------------------------------ tokens
+
+    fn mint(&mut self, name: &str, ty: Et) -> Result<(), Rekt> {
+        let Some(binding) = self.scopes.last().and_then(|scope| scope.get(name)).cloned() else {
+            return Ok(());
+        };
+        self.learn(&binding, name, ty)?;
+        if ty != Et::Never {
+            self.set(binding.place);
+            if let Some(flag) = binding.flag {
+                self.asm().num(1);
+                self.set(flag);
+            }
+        }
+        self.minted(name);
+        Ok(())
+    }
+
+    fn read(&mut self, name: &str) -> Result<Et, Rekt> {
+        self.read_below(name, self.scopes.len())
+    }
+
+    fn read_below(&mut self, name: &str, upto: usize) -> Result<Et, Rekt> {
+        let found = self.lookup_below(name, upto).filter(|(index, binding)| binding.live || *index < self.floor);
+        let Some((index, binding)) = found else {
+            return self.fail(Rekt::Lexer(format!("Token '{}' not found.", name)));
+        };
+        if let Place::Mine(_) = binding.place {
+            return Err(unsupported(&format!("Contract '{}' used as a value", name)));
+        }
+        let ty = self.declared_type(binding.id, name);
+        if ty == Et::Never {
+            self.asm().op(INVALID);
+            return Ok(Et::Never);
+        }
+        match binding.flag {
+            // Until a hoisted token is minted, GM looks further out
+            Some(flag) if index >= self.floor => {
+                let (minted, end) = (self.label(), self.label());
+                self.get(flag);
+                self.asm().jump_if(minted);
+                let outer = self.read_below(name, index)?;
+                self.asm().jump(end).mark(minted);
+                self.get(binding.place);
+                self.asm().mark(end);
+                if join(Some(ty), outer) != Some(ty) {
+                    let what = format!("'{}', read where it may be minted as {} or {},", name, ty.name(), outer.name());
+                    return Err(unsupported(&what));
+                }
+            }
+            _ => self.get(binding.place),
+        }
+        Ok(ty)
+    }
+
+    // Stores the word on top of the stack in the token `name` already
+    // holds, keeping a copy on the stack if `keep`.
+    fn store(&mut self, name: &str, ty: Et, keep: bool) -> Result<Et, Rekt> {
+        let Some((_, binding)) = self.minted_binding(name) else {
+            self.drop_value(ty);
+            return self.fail(Rekt::Runtime(format!("Wallet address '{}' not found.", name)));
+        };
+        if let Place::Mine(_) = binding.place {
+            return Err(unsupported(&format!("Contract '{}' replaced by an assignment", name)));
+        }
+        if binding.kind == Some(DeclKind::Block) && !binding.hoisted {
+            self.drop_value(ty);
+            return self.fail(Rekt::Runtime(format!("'{}' is a block constant and can't be changed.", name)));
+        }
+        self.learn(&binding, name, ty)?;
+        if ty == Et::Never {
+            return Ok(Et::Never);
+        }
+        if keep {
+            self.asm().op(DUP1);
+        }
+        self.set(binding.place);
+        Ok(if keep { ty } else { Et::Null })
+    }
+
+    fn assign(&mut self, name: &str, value: &Ast, keep: bool) -> Result<Et, Rekt> {
+        let ty = self.value(value)?;
+        if ty == Et::Never {
+            return Ok(Et::Never);
+        }
+        self.store(name, ty, keep)
+    }
+
+    fn compound(&mut self, target: &Ast, operator: &BinaryOp, value: &Ast, keep: bool) -> Result<Et, Rekt> {
+        let (name, steps) = target_path(target)?;
+        if !steps.is_empty() {
+            return Err(unsupported("Writing into a collection or struct"));
+        }
+        let current = self.read(name)?;
+        let operand = self.value(value)?;
+        let ty = self.binary(operator, current, operand)?;
+        if ty == Et::Never {
+            return Ok(Et::Never);
+        }
+        self.store(name, ty, keep)
+    }
+
+    // ------------------------------------------------------------ expressions
+
+    fn value(&mut self, ast: &Ast) -> Result<Et, Rekt> {
+        match self.expr(ast)? {
+            Et::Null => Err(unsupported("Using what a contract that returns nothing gives back")),
+            ty => Ok(ty),
+        }
+    }
+
+    fn expr(&mut self, ast: &Ast) -> Result<Et, Rekt> {
+        match ast {
+            Ast::Located { line, statement } => self.expr(statement).map_err(|e| e.at_line(*line)),
+            Ast::Literal(value) => self.literal(value),
+            Ast::Variable(name) | Ast::ResolvedVariable { name, .. } => self.read(name),
+            Ast::Grouping(inner) => self.expr(inner),
+
+            Ast::Binary { left, operator, right } => {
+                let left = self.value(left)?;
+                let right = self.value(right)?;
+                self.binary(operator, left, right)
+            }
+            Ast::Unary { operator, operand } => {
+                let operand = self.value(operand)?;
+                self.unary(operator, operand)
+            }
+            Ast::Ternary { condition, then_branch, else_branch } => {
+                let (otherwise, end) = (self.label(), self.label());
+                self.condition(condition)?;
+                self.asm().op(ISZERO).jump_if(otherwise);
+                let then_ty = self.value(then_branch)?;
+                self.asm().jump(end).mark(otherwise);
+                let else_ty = self.value(else_branch)?;
+                self.asm().mark(end);
+                join(Some(then_ty), else_ty)
+                    .ok_or_else(|| unsupported(&format!("A ternary giving {} or {}", then_ty.name(), else_ty.name())))
+            }
+            Ast::Call { callee, arguments } => self.call_expr(callee, arguments),
+            Ast::Assign { name, value } => self.assign(name, value, true),
+            Ast::CompoundAssign { target, operator, value } => self.compound(target, operator, value, true),
+
+            Ast::Interpolation(_) => Err(unsupported("Text other than a broadcast literal")),
+            Ast::Lambda { .. } => Err(unsupported("An anonymous contract")),
+            Ast::Match { .. } => Err(unsupported("A vibecheck")),
+            Ast::ArrayLiteral(_) => Err(unsupported("An array")),
+            Ast::MapLiteral(_) => Err(unsupported("A mapping")),
+            Ast::StructLiteral { .. } => Err(unsupported("A struct")),
+            Ast::Index { .. } => Err(unsupported("Indexing")),
+            Ast::Field { .. } => Err(unsupported("A field")),
+            Ast::SetIndex { .. } | Ast::SetField { .. } | Ast::Delete(_) => {
+                Err(unsupported("Writing into a collection or struct"))
+            }
+
+            Ast::Program(_)
+            | Ast::Block(_)
+            | Ast::VariableDecl { .. }
+            | Ast::FunctionDecl { .. }
+            | Ast::StructDecl { .. }
+            | Ast::If { .. }
+            | Ast::While { .. }
+            | Ast::ForEach { .. }
+            | Ast::ForRange { .. }
+            | Ast::Break
+            | Ast::Continue
+            | Ast::ExpressionStmt(_)
+            | Ast::PrintStmt(_)
+            | Ast::ReturnStmt(_) => Err(unsupported("A statement used as a value")),
+        }
+    }
+
+    fn literal(&mut self, value: &Value) -> Result<Et, Rekt> {
+        let n = match value {
+            Value::Token(t) => t.parse::<f64>().unwrap_or(f64::NAN),
+            Value::Number(n) => *n,
+            Value::Signal(b) | Value::Boolean(b) => {
+                self.asm().num(*b as u64);
+                return Ok(Et::Sig);
+            }
+            Value::Text(_) => return Err(unsupported("Text other than a broadcast literal")),
+            Value::Null => {
+                self.asm().num(0);
+                return Ok(Et::Null);
+            }
+            other => return Err(unsupported(&format!("A {} literal", other))),
+        };
+        let word = U256::from_f64(n.abs())
+            .filter(|w| w.bits() < 256)
+            .ok_or_else(|| unsupported(&format!("Number {}, which isn't a whole int256,", n)))?;
+        self.asm().push(if n < 0.0 { word.not().wrapping_add(U256::ONE) } else { word });
+        Ok(Et::Num)
+    }
+
+    // Applies an operator to the two words on top of the stack, the right
+    // one on top.
+    fn binary(&mut self, operator: &BinaryOp, left: Et, right: Et) -> Result<Et, Rekt> {
+        if left == Et::Never || right == Et::Never {
+            return Ok(Et::Never);
+        }
+        if left == Et::Unknown || right == Et::Unknown {
+            self.asm().op(POP).op(POP).num(0);
+            return Ok(Et::Unknown);
+        }
+        let numbers = |gen: &mut Self| -> Result<bool, Rekt> {
+            match [left, right].into_iter().find(|ty| *ty != Et::Num) {
+                Some(other) => {
+                    gen.asm().op(POP).op(POP);
+                    gen.fail(Rekt::Type(format!("Expected vibe, but found {}", other.name()))).map(|_| false)
+                }
+                None => Ok(true),
+            }
+        };
+        match operator {
+            BinaryOp::Divide => Err(fractions()),
+            BinaryOp::Add | BinaryOp::Subtract | BinaryOp::Multiply => {
+                if !numbers(self)? {
+                    return Ok(Et::Never);
+                }
+                match operator {
+                    BinaryOp::Add => self.asm().op(ADD),
+                    BinaryOp::Subtract => self.asm().op(SWAP1).op(SUB),
+                    _ => self.asm().op(MUL),
+                };
+                Ok(Et::Num)
+            }
+            BinaryOp::Less | BinaryOp::Greater | BinaryOp::LessEqual | BinaryOp::GreaterEqual => {
+                if !numbers(self)? {
+                    return Ok(Et::Never);
+                }
+                // With the right operand on top, SGT asks whether left < right
+                match operator {
+                    BinaryOp::Less => self.asm().op(SGT),
+                    BinaryOp::Greater => self.asm().op(SLT),
+                    BinaryOp::LessEqual => self.asm().op(SLT).op(ISZERO),
+                    _ => self.asm().op(SGT).op(ISZERO),
+                };
+                Ok(Et::Sig)
+            }
+            BinaryOp::Equal | BinaryOp::NotEqual => {
+                if left != right {
+                    self.asm().op(POP).op(POP);
+                    return self.fail(Rekt::Lexer("Cannot compare different types".to_string()));
+                }
+                self.asm().op(EQ);
+                if *operator == BinaryOp::NotEqual {
+                    self.asm().op(ISZERO);
+                }
+                Ok(Et::Sig)
+            }
+            BinaryOp::Modulo | BinaryOp::IntDivide | BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::BitXor => {
+                self.integer(operator, left, right)
+            }
+            BinaryOp::ShiftLeft | BinaryOp::ShiftRight => {
+                Err(unsupported(&format!("'{}', which GM bounds to 64 bits,", operator.symbol())))
+            }
+            _ => {
+                self.asm().op(POP).op(POP);
+                self.fail(Rekt::Lexer("Op not supported yet.".to_string()))
+            }
+        }
+    }
+
+    // The integer operators. Every int256 is whole, so only signals fail.
+    fn integer(&mut self, operator: &BinaryOp, left: Et, right: Et) -> Result<Et, Rekt> {
+        let symbol = operator.symbol();
+        let needs = |ty: Et| move |value: String| {
+            Rekt::Type(format!("'{}' needs integers, but found {} {}", symbol, ty.name(), value))
+        };
+        if left != Et::Num {
+            self.asm().op(POP);
+            return self.fail_signal(needs(left));
+        }
+        if right != Et::Num {
+            return self.fail_signal(needs(right));
+        }
+        match operator {
+            BinaryOp::BitAnd => self.asm().op(AND),
+            BinaryOp::BitOr => self.asm().op(OR),
+            BinaryOp::BitXor => self.asm().op(XOR),
+            _ => return self.floor_division(operator == &BinaryOp::Modulo),
+        };
+        Ok(Et::Num)
+    }
+
+    // `~/` rounds down and `%` takes the divisor's sign, where SDIV and
+    // SMOD round toward zero, so both are adjusted when the signs differ.
+    fn floor_division(&mut self, modulo: bool) -> Result<Et, Rekt> {
+        let divisor = self.label();
+        self.asm().op(DUP1).jump_if(divisor);
+        self.fail(Rekt::Runtime("You just got rekt by zero!".to_string()))?;
+        self.asm().mark(divisor);
+        self.asm().num(RIGHT).op(MSTORE).num(LEFT).op(MSTORE);
+        let negative = |gen: &mut Self, at: u64| {
+            gen.asm().num(0).num(at).op(MLOAD).op(SLT);
+        };
+        if modulo {
+            self.asm().num(RIGHT).op(MLOAD).num(LEFT).op(MLOAD).op(SMOD);
+            self.asm().op(DUP1).op(ISZERO).op(ISZERO);
+            self.asm().num(0).op(DUP3).op(SLT);
+            negative(self, RIGHT);
+            self.asm().op(XOR).op(AND);
+            self.asm().num(RIGHT).op(MLOAD).op(MUL).op(ADD);
+        } else {
+            self.asm().num(RIGHT).op(MLOAD).num(LEFT).op(MLOAD).op(SDIV);
+            self.asm().num(RIGHT).op(MLOAD).num(LEFT).op(MLOAD).op(SMOD).op(ISZERO).op(ISZERO);
+            negative(self, LEFT);
+            negative(self, RIGHT);
+            self.asm().op(XOR).op(AND);
+            self.asm().op(SWAP1).op(SUB);
+        }
+        Ok(Et::Num)
+    }
+
+    fn unary(&mut self, operator: &BinaryOp, operand: Et) -> Result<Et, Rekt> {
+        match (operator, operand) {
+            (_, Et::Never) => Ok(Et::Never),
+            (_, Et::Unknown) => Ok(Et::Unknown),
+            (BinaryOp::Not, Et::Sig) => {
+                self.asm().op(ISZERO);
+                Ok(Et::Sig)
+            }
+            (BinaryOp::Not, other) => {
+                self.asm().op(POP);
+                self.fail(Rekt::Type(format!("'not' needs a signal, but found {}", other.name())))
+            }
+            (BinaryOp::BitNot, Et::Num) => {
+                self.asm().op(NOT);
+                Ok(Et::Num)
+            }
+            (BinaryOp::BitNot, other) => self.fail_signal(move |value| {
+                Rekt::Type(format!("'~' needs integers, but found {} {}", other.name(), value))
+            }),
+            (other, _) => {
+                self.asm().op(POP);
+                self.fail(Rekt::Runtime(format!("'{}' is not a prefix operator", other.symbol())))
+            }
+        }
+    }
+
+    // ------------------------------------------------------------ calls
+
+    fn call_expr(&mut self, callee: &Ast, arguments: &[Ast]) -> Result<Et, Rekt> {
+        let name = match callee.unlocated() {
+            Ast::Variable(name) | Ast::ResolvedVariable { name, .. } => name,
+            _ => return Err(unsupported("Calling anything but a contract by name")),
+        };
+        match self.minted_binding(name) {
+            None if BUILTINS.contains(&name.as_str()) => Err(unsupported(&format!("Builtin '{}'", name))),
+            None => {
+                self.discard_args(arguments)?;
+                self.fail(Rekt::Lexer(format!("Unknown smart contract '{}'", name)))
+            }
+            Some((_, binding)) => match binding.place {
+                Place::Mine(index) => self.call_mine(index, arguments),
+                _ => {
+                    let ty = self.read(name)?;
+                    self.drop_value(ty);
+                    self.discard_args(arguments)?;
+                    self.fail(Rekt::Lexer(format!("{} is not a contract", ty.name())))
+                }
+            },
+        }
+    }
+
+    fn discard_args(&mut self, arguments: &[Ast]) -> Result<(), Rekt> {
+        for argument in arguments {
+            let ty = self.expr(argument)?;
+            self.drop_value(ty);
+        }
+        Ok(())
+    }
+
+    // Pushes where to come back to, then the args, then jumps.
+    fn call_mine(&mut self, index: usize, arguments: &[Ast]) -> Result<Et, Rekt> {
+        let (name, params, entry) = {
+            let mine = &self.mines[index];
+            (mine.name.clone(), mine.params.clone(), mine.entry)
+        };
+        let back = self.label();
+        self.asm().push_label(back);
+        let mut types = Vec::new();
+        for argument in arguments {
+            types.push(self.value(argument)?);
+        }
+        if types.contains(&Et::Never) {
+            return Ok(Et::Never);
+        }
+        if types.len() != params.len() {
+            return self.fail(Rekt::Lexer(format!("Expected {} args, got {}.", params.len(), types.len())));
+        }
+        for (ty, (param, expected)) in types.iter().zip(&params) {
+            if ty != expected && *ty != Et::Unknown {
+                return Err(unsupported(&format!(
+                    "Passing {} to '{}', whose param '{}' is {},",
+                    ty.name(),
+                    name,
+                    param,
+                    expected.name()
+                )));
+            }
+        }
+        self.asm().jump(entry).mark(back);
+        match self.known.returns.get(&name) {
+            Some(Some(ty)) => Ok(*ty),
+            Some(None) => Ok(Et::Null),
+            None => {
+                self.unknown.get_or_insert_with(|| format!("what '{}' returns", name));
+                Ok(Et::Unknown)
+            }
+        }
+    }
+}
+
+impl Code {
+    // The function's code, with its frame size now known.
+    fn finish(mut self) -> Asm {
+        self.asm.fill(self.size_at, U256::from_u64(WORD * self.words));
+        self.asm
+    }
+}
+
+// Whether a body's last statement returns, so nothing runs after it.
+fn ends_in_return(body: &[Ast]) -> bool {
+    matches!(body.last().map(Ast::unlocated), Some(Ast::ReturnStmt(_)))
+}
+
+// The type two ways of giving a value agree on, if they do.
+fn join(known: Option<Et>, ty: Et) -> Option<Et> {
+    match (known, ty) {
+        (None, ty) | (Some(Et::Never), ty) => Some(ty),
+        (Some(known), Et::Never) => Some(known),
+        (Some(Et::Unknown), _) | (_, Et::Unknown) => Some(Et::Unknown),
+        (Some(known), ty) if known == ty => Some(known),
+        _ => None,
+    }
+}
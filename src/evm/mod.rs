@@ -0,0 +1,13 @@
+// src/evm/mod.rs
+//
+// The EVM backend: the numeric part of GM becomes bytecode for a single
+// contract, with top-level tokens in storage, a selector dispatcher for
+// `run()` and each `mine` contract, and broadcasts as the same events the
+// Solidity backend emits. The tests deploy and call it on a small machine
+// of their own to check it against the tree walker.
+
+pub mod abi;
+pub mod asm;
+mod compiler;
+
+pub use compiler::{compile, Compiled};
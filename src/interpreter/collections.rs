@@ -0,0 +1,19 @@
+// Indexing, fields and slot paths follow gm-runtime's rules; only the
+// shape of a farm loop is gm-language's own.
+
+use crate::error::Rekt;
+use crate::shared_types::Value;
+
+// One step on the way from a token down to the slot being written, with
+// fields named the way the parser read them.
+pub type Slot = gm_runtime::slots::Step<Value, String>;
+
+// What a farm loop walks, as (key, item) pairs. A lone loop variable gets
+// the items of an array or the keys of a mapping, and no key.
+pub fn farm_pairs(iterable: Value, keyed: bool) -> Result<Vec<(Value, Value)>, Rekt> {
+    if keyed {
+        gm_runtime::pairs(iterable)
+    } else {
+        Ok(gm_runtime::items(iterable)?.into_iter().map(|k| (Value::Null, k)).collect())
+    }
+}
@@ -1,7 +1,14 @@
-use std::collections::HashMap;
-use crate::shared_types::{ BinaryOp, Type, Value };
+use std::cell::RefCell;
+use std::collections::{ BTreeMap, HashMap, HashSet };
+use std::fmt;
+use std::rc::Rc;
+use crate::shared_types::{ Type, Value };
 use crate::parser::ast::Ast;
 use crate::error::Rekt;
+use gm_runtime::{ bind, binary, bound, delete, field, read, set, unary, Key, NativeContracts };
+use collections::{ farm_pairs, Slot };
+
+pub(crate) mod collections;
 
 // number => token
 // boolean => signal
@@ -13,47 +20,247 @@ use crate::error::Rekt;
 // Error:Runtime => Rekt
 
 // environment
+// A wallet is a handle onto one scope of tokens. Cloning the handle shares
+// the scope, which is how smart contracts hold on to where they were mined.
+#[derive(Clone)]
 pub struct Wallet {
-    tokens: HashMap<String, Value>,
+    vault: Rc<RefCell<Vault>>,
+}
+
+struct Vault {
+    slots: HashMap<String, usize>, // where each token sits in `tokens`
+    tokens: Vec<(String, Value)>,  // in the order they were first minted
+    blocks: HashSet<String>,       // names minted with `block`, which can't change
+    parent: Option<Wallet>,
+}
+
+impl Vault {
+    fn put(&mut self, address: String, token: Value) {
+        match self.slots.get(&address) {
+            Some(slot) => self.tokens[*slot].1 = token,
+            None => {
+                self.slots.insert(address.clone(), self.tokens.len());
+                self.tokens.push((address, token));
+            }
+        }
+    }
+}
+
+impl Default for Wallet {
+    fn default() -> Self {
+        Wallet::new()
+    }
 }
 
 impl Wallet {
     pub fn new() -> Self {
         Wallet {
-            tokens: HashMap::new(),
+            vault: Rc::new(RefCell::new(Vault {
+                slots: HashMap::new(),
+                tokens: Vec::new(),
+                blocks: HashSet::new(),
+                parent: None,
+            })),
         }
     }
 
-    pub fn mint(&mut self, address: String, token: Value) {
-        self.tokens.insert(address, token);
+    // A fresh scope that can still see (and transfer to) everything in `self`.
+    pub fn child(&self) -> Self {
+        Wallet {
+            vault: Rc::new(RefCell::new(Vault {
+                slots: HashMap::new(),
+                tokens: Vec::new(),
+                blocks: HashSet::new(),
+                parent: Some(self.clone()),
+            })),
+        }
     }
 
-    pub fn fetch(&self, address: &str) -> Option<&Value> {
-        self.tokens.get(address)
+    pub fn mint(&self, address: String, token: Value) {
+        let mut vault = self.vault.borrow_mut();
+        vault.blocks.remove(&address);
+        vault.put(address, token);
     }
 
-    pub fn transfer(&mut self, address: &str, token: Value) -> Result<(), Rekt> {
-        if self.tokens.contains_key(address) {
-            self.tokens.insert(address.to_string(), token);
-            Ok(())
-        } else {
-            Err(Rekt::Runtime(format!("Wallet address '{}' not found.", address)))
+    pub fn mint_block(&self, address: String, token: Value) {
+        let mut vault = self.vault.borrow_mut();
+        vault.blocks.insert(address.clone());
+        vault.put(address, token);
+    }
+
+    pub fn fetch(&self, address: &str) -> Option<Value> {
+        let vault = self.vault.borrow();
+        match vault.slots.get(address) {
+            Some(slot) => Some(vault.tokens[*slot].1.clone()),
+            None => vault.parent.as_ref().and_then(|parent| parent.fetch(address)),
+        }
+    }
+
+    // Reads a token where the resolver placed it, `depth` scopes out at
+    // `slot`. Tokens minted out of order (a skipped branch, a name used
+    // before it's minted) miss, and are searched for by name instead.
+    pub fn fetch_resolved(&self, depth: usize, slot: usize, address: &str) -> Option<Value> {
+        self.placed(depth, slot, address).or_else(|| self.fetch(address))
+    }
+
+    fn placed(&self, depth: usize, slot: usize, address: &str) -> Option<Value> {
+        let vault = self.vault.borrow();
+        if depth > 0 {
+            return vault.parent.as_ref().and_then(|parent| parent.placed(depth - 1, slot, address));
+        }
+        match vault.tokens.get(slot) {
+            Some((name, token)) if name == address => Some(token.clone()),
+            _ => None,
         }
     }
+
+    // Runs `f` on the token in whichever scope owns it.
+    pub fn update<R>(
+        &self,
+        address: &str,
+        f: impl FnOnce(&mut Value) -> Result<R, Rekt>
+    ) -> Result<R, Rekt> {
+        let mut vault = self.vault.borrow_mut();
+        if vault.blocks.contains(address) {
+            return Err(Rekt::Runtime(format!("'{}' is a block constant and can't be changed.", address)));
+        }
+        if let Some(slot) = vault.slots.get(address).copied() {
+            return f(&mut vault.tokens[slot].1);
+        }
+        match &vault.parent {
+            Some(parent) => parent.update(address, f),
+            None => Err(Rekt::Runtime(format!("Wallet address '{}' not found.", address))),
+        }
+    }
+
+    pub fn transfer(&self, address: &str, token: Value) -> Result<(), Rekt> {
+        self.update(address, |slot| {
+            *slot = token;
+            Ok(())
+        })
+    }
+}
+
+impl PartialEq for Wallet {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.vault, &other.vault)
+    }
+}
+
+impl fmt::Debug for Wallet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Wallet")
+    }
+}
+
+// A declared struct shape: each field with its optional type.
+pub type StructFields = Vec<(String, Option<Type>)>;
+pub type Structs = HashMap<String, StructFields>;
+
+fn declared_fields<'a>(structs: &'a Structs, record: &str) -> Result<&'a StructFields, Rekt> {
+    structs
+        .get(record)
+        .ok_or_else(|| Rekt::Runtime(format!("Unknown struct '{}'", record)))
+}
+
+pub fn check_field_type(structs: &Structs, record: &str, field: &str, value: &Value) -> Result<(), Rekt> {
+    let declared = structs
+        .get(record)
+        .and_then(|fields| fields.iter().find(|(f, _)| f == field))
+        .and_then(|(_, t)| t.as_ref());
+    match declared {
+        Some(expected) if !expected.accepts(value) => Err(Rekt::Type(format!(
+            "Field '{}.{}' expects {}, but found {}",
+            record, field, expected, value
+        ))),
+        _ => Ok(()),
+    }
+}
+
+// Struct construction happens field by field: check the name is declared,
+// evaluate the initializer, then record it.
+pub fn expect_field(structs: &Structs, record: &str, field: &str) -> Result<(), Rekt> {
+    if !declared_fields(structs, record)?.iter().any(|(f, _)| f == field) {
+        return Err(Rekt::Type(format!("'{}' has no field '{}'", record, field)));
+    }
+    Ok(())
+}
+
+pub fn init_field(
+    structs: &Structs,
+    record: &str,
+    given: &mut HashMap<String, Value>,
+    field: String,
+    value: Value
+) -> Result<(), Rekt> {
+    check_field_type(structs, record, &field, &value)?;
+    if given.contains_key(&field) {
+        return Err(Rekt::Runtime(format!("Field '{}' set twice in '{}'", field, record)));
+    }
+    given.insert(field, value);
+    Ok(())
+}
+
+// Lays the given fields out in declaration order, failing on any left out.
+pub fn finish_struct(structs: &Structs, name: String, mut given: HashMap<String, Value>) -> Result<Value, Rekt> {
+    let mut fields = Vec::new();
+    for (field, _) in declared_fields(structs, &name)? {
+        let value = given
+            .remove(field)
+            .ok_or_else(|| Rekt::Runtime(format!("Missing field '{}' in '{}'", field, name)))?;
+        fields.push((field.clone(), value));
+    }
+    Ok(Value::Struct { name, fields })
+}
+
+pub fn broadcast(events: &mut Option<Vec<String>>, value: &Value) {
+    match events {
+        Some(log) => log.push(value.render()),
+        None => println!("📢 Event: {}", value.render()),
+    }
+}
+
+// Control flow on its way out of the statements currently running.
+enum Unwind {
+    Return(Value),
+    Break,
+    Continue,
 }
 
 // interpreter
 pub struct Interpreter {
     wallet: Wallet,
+    structs: Structs,
+    unwinding: Option<Unwind>, // set by return/crash/skip until something catches it
+    events: Option<Vec<String>>, // broadcasts land here instead of stdout while recording
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter::new()
+    }
 }
 
 impl Interpreter {
     pub fn new() -> Self {
         Interpreter {
             wallet: Wallet::new(),
+            structs: HashMap::new(),
+            unwinding: None,
+            events: None,
         }
     }
 
+    // Keeps broadcasts in memory instead of printing them, so two runs of the
+    // same script can be compared.
+    pub fn record_events(&mut self) {
+        self.events = Some(Vec::new());
+    }
+
+    pub fn take_events(&mut self) -> Vec<String> {
+        self.events.take().unwrap_or_default()
+    }
+
      // The interpret method
      pub fn interpret(&mut self, ast: Ast) -> Result<Value, Rekt> {
         self.deploy(ast)?;  // Assuming deploy returns Result<Value, Rekt>
@@ -62,35 +269,95 @@ impl Interpreter {
     
     
 
-    fn verify_type(&self, token: &Value, expected: Type) -> Result<(), Rekt> {
-        let actual = token.get_type();
-        if actual != expected {
-            Err(Rekt::Type(format!("Expected {}, but found {}", expected, token)))
-        } else {
-            Ok(())
+    // Breaks a target like `m[a].b` down into its root token name and the
+    // evaluated path leading to the slot.
+    fn resolve_slot(&mut self, target: Ast) -> Result<(String, Vec<Slot>), Rekt> {
+        match target {
+            Ast::Variable(name) => Ok((name, Vec::new())),
+            Ast::Grouping(inner) => self.resolve_slot(*inner),
+            Ast::Index { object, index } => {
+                let (name, mut path) = self.resolve_slot(*object)?;
+                path.push(Slot::Key(self.deploy(*index)?));
+                Ok((name, path))
+            }
+            Ast::Field { object, name: field } => {
+                let (name, mut path) = self.resolve_slot(*object)?;
+                path.push(Slot::Field(field));
+                Ok((name, path))
+            }
+            _ => Err(Rekt::Runtime("Invalid assignment target".to_string())),
         }
     }
 
-    fn validate_tx(
-        &self,
-        left: &Value,
-        right: &Value,
-        _op: &BinaryOp,
-        expected_type: Type
-    ) -> Result<(), Rekt> {
-        self.verify_type(left, expected_type.clone())?;
-        self.verify_type(right, expected_type.clone())?;
-        Ok(())
+    fn build_struct(&mut self, name: String, inits: Vec<(String, Ast)>) -> Result<Value, Rekt> {
+        let mut given = HashMap::new();
+        for (field, init) in inits {
+            expect_field(&self.structs, &name, &field)?;
+            let value = self.deploy(init)?;
+            init_field(&self.structs, &name, &mut given, field, value)?;
+        }
+        finish_struct(&self.structs, name, given)
+    }
+
+    fn fetch_token(&self, name: &str) -> Result<Value, Rekt> {
+        self.wallet
+            .fetch(name)
+            .ok_or_else(|| Rekt::Lexer(format!("Token '{}' not found.", name)))
+    }
+
+    // Runs statements in order, stopping early once a return is in flight.
+    fn execute_block(&mut self, stmts: Vec<Ast>) -> Result<Value, Rekt> {
+        let mut result = Value::Null;
+        for stmt in stmts {
+            result = self.deploy(stmt)?;
+            if self.unwinding.is_some() {
+                break;
+            }
+        }
+        Ok(result)
+    }
+
+    fn execute_scoped(&mut self, stmts: Vec<Ast>, wallet: Wallet) -> Result<Value, Rekt> {
+        let old_wallet = std::mem::replace(&mut self.wallet, wallet);
+        let result = self.execute_block(stmts);
+        self.wallet = old_wallet;
+        result
+    }
+
+    fn take_return(&mut self) -> Option<Value> {
+        match self.unwinding.take() {
+            Some(Unwind::Return(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    // Runs one pass of a loop body and reports whether the loop should go on.
+    fn iterate(&mut self, body: Vec<Ast>, wallet: Wallet) -> Result<bool, Rekt> {
+        self.execute_scoped(body, wallet)?;
+        match self.unwinding {
+            Some(Unwind::Break) => {
+                self.unwinding = None;
+                Ok(false)
+            }
+            Some(Unwind::Continue) => {
+                self.unwinding = None;
+                Ok(true)
+            }
+            Some(Unwind::Return(_)) => Ok(false),
+            None => Ok(true),
+        }
+    }
+
+    fn loop_number(&mut self, ast: Ast, what: &str) -> Result<f64, Rekt> {
+        let value = self.deploy(ast)?;
+        bound(&value, what)
     }
 
     pub fn deploy(&mut self, ast: Ast) -> Result<Value, Rekt> {
         match ast {
             Ast::Program(contracts) => {
-                let mut result = Value::Null;
-                for c in contracts {
-                    result = self.deploy(c)?;
-                }
-                Ok(result)
+                let result = self.execute_block(contracts)?;
+                Ok(self.take_return().unwrap_or(result))
             }
 
             Ast::FunctionDecl { name, params, body, .. } => {
@@ -102,80 +369,69 @@ impl Interpreter {
                     name: name.clone(),
                     params: arg_names,
                     body,
+                    scope: self.wallet.clone(),
                 };
                 self.wallet.mint(name, contract.clone());
                 Ok(contract)
             }
 
-            Ast::Call { callee, arguments } => {
-                let contract = self.wallet
-                    .fetch(&callee)
-                    .ok_or_else(|| Rekt::Lexer(format!("Unknown smart contract '{}'", callee)))?
-                    .clone();
-
-                match contract {
-                    Value::SmartContract { params, body, .. } => {
-                        let mut new_wallet = Wallet::new();
-
-                        if params.len() != arguments.len() {
-                            return Err(
-                                Rekt::Lexer(
-                                    format!(
-                                        "Expected {} args, got {}.",
-                                        params.len(),
-                                        arguments.len()
-                                    )
-                                )
-                            );
-                        }
-
-                        for (param, arg) in params.iter().zip(arguments) {
-                            let val = self.deploy(arg)?;
-                            new_wallet.mint(param.clone(), val);
-                        }
+            Ast::Lambda { params, body, .. } => {
+                Ok(Value::SmartContract {
+                    name: "anonymous".to_string(),
+                    params: params.into_iter().map(|(name, _)| name).collect(),
+                    body,
+                    scope: self.wallet.clone(),
+                })
+            }
 
-                        let old_wallet = std::mem::replace(&mut self.wallet, new_wallet);
-                        let mut result = Value::Null;
-                        for stmt in body {
-                            result = self.deploy(stmt)?;
+            Ast::Call { callee, arguments } => {
+                // Names that aren't in any wallet may still be native contracts
+                if let Ast::Variable(name) = callee.as_ref() {
+                    if self.wallet.fetch(name).is_none() {
+                        let mut args = Vec::new();
+                        for arg in arguments {
+                            args.push(self.deploy(arg)?);
                         }
-                        self.wallet = old_wallet;
-                        Ok(result)
+                        return self.call_builtin(name, args).unwrap_or_else(|| {
+                            Err(Rekt::Lexer(format!("Unknown smart contract '{}'", name)))
+                        });
                     }
-                    _ => Err(Rekt::Lexer(format!("'{}' is not a contract", callee))),
                 }
+
+                let contract = self.deploy(*callee)?;
+                let mut args = Vec::new();
+                for arg in arguments {
+                    args.push(self.deploy(arg)?);
+                }
+                self.invoke(contract, args)
             }
 
             Ast::ReturnStmt(val) => {
-                match val {
-                    Some(expr) => self.deploy(*expr),
-                    None => Ok(Value::Null),
-                }
+                let result = match val {
+                    Some(expr) => self.deploy(*expr)?,
+                    None => Value::Null,
+                };
+                self.unwinding = Some(Unwind::Return(result.clone()));
+                Ok(result)
             }
 
-            Ast::VariableDecl { name, initializer, .. } => {
+            Ast::VariableDecl { name, initializer, is_constant, .. } => {
                 let token = self.deploy(*initializer)?;
-                self.wallet.mint(name, token.clone());
+                if is_constant {
+                    self.wallet.mint_block(name, token.clone());
+                } else {
+                    self.wallet.mint(name, token.clone());
+                }
                 Ok(token)
             }
 
             Ast::If { condition, then_branch, else_branch } => {
                 let signal = self.deploy(*condition)?;
                 match signal {
-                    Value::Signal(true) => {
-                        let mut last = Value::Null;
-                        for stmt in then_branch {
-                            last = self.deploy(stmt)?;
-                        }
-                        Ok(last)
-                    }
+                    Value::Signal(true) => self.execute_block(then_branch),
                     Value::Signal(false) => {
                         if let Some(else_stmts) = else_branch {
-                            let mut last = Value::Null;
-                            for stmt in else_stmts {
-                                last = self.deploy(stmt)?;
-                            }
-                            Ok(last)
+                            self.execute_block(else_stmts)
                         } else {
                             Ok(Value::Null)
                         }
@@ -190,110 +446,218 @@ impl Interpreter {
             Ast::Binary { left, operator, right } => {
                 let l = self.deploy(*left)?;
                 let r = self.deploy(*right)?;
-            
-                match operator {
-                    BinaryOp::Add | BinaryOp::Subtract | BinaryOp::Multiply | BinaryOp::Divide => {
-                        self.validate_tx(&l, &r, &operator, Type::Token)?;
-            
-                        // Try parsing the values to f64 before applying operations
-                        if let (Value::Token(a), Value::Token(b)) = (l, r) {
-                            // Parse both a and b as f64
-                            if let Ok(a_val) = a.parse::<f64>() {
-                                if let Ok(b_val) = b.parse::<f64>() {
-                                    match operator {
-                                        BinaryOp::Add => Ok(Value::Token((a_val + b_val).to_string())),
-                                        BinaryOp::Subtract => Ok(Value::Token((a_val - b_val).to_string())),
-                                        BinaryOp::Multiply => Ok(Value::Token((a_val * b_val).to_string())),
-                                        BinaryOp::Divide => {
-                                            if b_val == 0.0 {
-                                                Err(Rekt::Lexer("You just got rekt by zero!".to_string()))
-                                            } else {
-                                                Ok(Value::Token((a_val / b_val).to_string()))
-                                            }
-                                        }
-                                        _ => Err(Rekt::Lexer("Invalid trade".to_string())),
-                                    }
-                                } else {
-                                    Err(Rekt::Lexer("Invalid number: b".to_string())) // Handle invalid `b` parsing
-                                }
-                            } else {
-                                Err(Rekt::Lexer("Invalid number: a".to_string())) // Handle invalid `a` parsing
-                            }
-                        } else {
-                            Err(Rekt::Lexer("Invalid trade".to_string())) // Handle invalid types in left and right values
-                        }
-                    }
-                    BinaryOp::Greater
-                    | BinaryOp::Less
-                    | BinaryOp::GreaterEqual
-                    | BinaryOp::LessEqual => {
-                        self.validate_tx(&l, &r, &operator, Type::Token)?;
-            
-                        match (l, operator, r) {
-                            (Value::Token(a), BinaryOp::Greater, Value::Token(b)) =>
-                                Ok(Value::Signal(a > b)),
-                            (Value::Token(a), BinaryOp::Less, Value::Token(b)) =>
-                                Ok(Value::Signal(a < b)),
-                            (Value::Token(a), BinaryOp::GreaterEqual, Value::Token(b)) =>
-                                Ok(Value::Signal(a >= b)),
-                            (Value::Token(a), BinaryOp::LessEqual, Value::Token(b)) =>
-                                Ok(Value::Signal(a <= b)),
-                            _ => Err(Rekt::Lexer("Invalid comparison".to_string())),
-                        }
-                    }
-                    BinaryOp::Equal => {
-                        match (l, r) {
-                            (Value::Token(a), Value::Token(b)) => Ok(Value::Signal(a == b)),
-                            (Value::Signal(a), Value::Signal(b)) => Ok(Value::Signal(a == b)),
-                            _ => Err(Rekt::Lexer("Cannot compare different types".to_string())),
-                        }
-                    }
-                    BinaryOp::NotEqual => {
-                        match (l, r) {
-                            (Value::Token(a), Value::Token(b)) => Ok(Value::Signal(a != b)),
-                            (Value::Signal(a), Value::Signal(b)) => Ok(Value::Signal(a != b)),
-                            _ => Err(Rekt::Lexer("Cannot compare different types".to_string())),
-                        }
-                    }
-                    _ => Err(Rekt::Lexer("Op not supported yet.".to_string())),
-                }
+                binary(l, operator.symbol(), r)
             }
-            
 
             Ast::PrintStmt(expr) => {
                 let val = self.deploy(*expr)?;
-                println!("📢 Event: {:?}", val);
+                broadcast(&mut self.events, &val);
                 Ok(Value::Null)
             }
 
             Ast::Literal(val) => Ok(val),
-            Ast::Variable(name) => {
-                self.wallet
-                    .fetch(&name)
-                    .cloned()
-                    .ok_or_else(|| Rekt::Lexer(format!("Token '{}' not found.", name)))
-            }
+            Ast::Variable(name) => self.fetch_token(&name),
+            Ast::ResolvedVariable { name, depth, slot } => self
+                .wallet
+                .fetch_resolved(depth, slot, &name)
+                .ok_or_else(|| Rekt::Lexer(format!("Token '{}' not found.", name))),
             Ast::Assign { name, value } => {
                 let eval = self.deploy(*value)?;
                 self.wallet.transfer(&name, eval.clone())?;
                 Ok(eval)
             }
+            Ast::ArrayLiteral(elements) => {
+                let mut items = Vec::new();
+                for element in elements {
+                    items.push(self.deploy(element)?);
+                }
+                Ok(Value::Array(items))
+            }
+            Ast::MapLiteral(entries) => {
+                let mut map = BTreeMap::new();
+                for (key, value) in entries {
+                    let key = Key::from_value(&self.deploy(key)?)?;
+                    map.insert(key, self.deploy(value)?);
+                }
+                Ok(Value::Map(map))
+            }
+            Ast::Index { object, index } => {
+                let object = self.deploy(*object)?;
+                let index = self.deploy(*index)?;
+                gm_runtime::index(object, index)
+            }
+            Ast::SetIndex { object, index, value } => {
+                let (name, mut path) = self.resolve_slot(*object)?;
+                path.push(Slot::Key(self.deploy(*index)?));
+                let eval = self.deploy(*value)?;
+                let stored = eval.clone();
+                self.wallet.update(&name, |root| set(root, &path, stored))?;
+                Ok(eval)
+            }
+            Ast::CompoundAssign { target, operator, value } => {
+                // The target's path is worked out once, so `m[next()] stake= 1`
+                // only calls next() a single time
+                let (name, path) = self.resolve_slot(*target)?;
+                let current = read(&self.fetch_token(&name)?, &path)?;
+                let operand = self.deploy(*value)?;
+                let eval = binary(current, operator.symbol(), operand)?;
+                let stored = eval.clone();
+                self.wallet.update(&name, |root| set(root, &path, stored))?;
+                Ok(eval)
+            }
+            Ast::Delete(target) => {
+                let (name, path) = self.resolve_slot(*target)?;
+                self.wallet.update(&name, |root| delete(root, &path))?;
+                Ok(Value::Null)
+            }
+            Ast::StructDecl { name, fields, .. } => {
+                self.structs.insert(name, fields);
+                Ok(Value::Null)
+            }
+            Ast::StructLiteral { name, fields } => self.build_struct(name, fields),
+            Ast::Field { object, name } => {
+                let object = self.deploy(*object)?;
+                field(object, &name)
+            }
+            Ast::SetField { object, name: field, value } => {
+                let (name, mut path) = self.resolve_slot(*object)?;
+                let eval = self.deploy(*value)?;
+                let root = self.fetch_token(&name)?;
+                if let Value::Struct { name, .. } = read(&root, &path)? {
+                    check_field_type(&self.structs, &name, &field, &eval)?;
+                }
+                path.push(Slot::Field(field));
+                let stored = eval.clone();
+                self.wallet.update(&name, |root| set(root, &path, stored))?;
+                Ok(eval)
+            }
             Ast::ExpressionStmt(expr) => self.deploy(*expr),
+            Ast::Interpolation(parts) => {
+                let mut text = String::new();
+                for part in parts {
+                    text.push_str(&self.deploy(part)?.render());
+                }
+                Ok(Value::Text(text))
+            }
+            Ast::Unary { operator, operand } => {
+                let value = self.deploy(*operand)?;
+                unary(operator.symbol(), value)
+            }
             Ast::Grouping(expr) => self.deploy(*expr),
-            Ast::Block(stmts) => {
-                let mut result = Value::Null;
-                let new_wallet = Wallet::new();
-                let old_wallet = std::mem::replace(&mut self.wallet, new_wallet);
+            Ast::Ternary { condition, then_branch, else_branch } => {
+                match self.deploy(*condition)? {
+                    Value::Signal(true) => self.deploy(*then_branch),
+                    Value::Signal(false) => self.deploy(*else_branch),
+                    _ => Err(
+                        Rekt::Lexer("Condition must be bullish or bearish (yes/no)".to_string())
+                    ),
+                }
+            }
+            Ast::While { condition, body } => {
+                loop {
+                    match self.deploy((*condition).clone())? {
+                        Value::Signal(true) => {}
+                        Value::Signal(false) => break,
+                        _ => return Err(
+                            Rekt::Lexer("Condition must be bullish or bearish (yes/no)".to_string())
+                        ),
+                    }
+                    let wallet = self.wallet.clone();
+                    if !self.iterate(body.clone(), wallet)? {
+                        break;
+                    }
+                }
+                Ok(Value::Null)
+            }
+            Ast::ForEach { key, variable, iterable, body } => {
+                let iterable = self.deploy(*iterable)?;
+                let pairs = farm_pairs(iterable, key.is_some())?;
 
-                for stmt in stmts {
-                    result = self.deploy(stmt)?;
+                for (k, v) in pairs {
+                    let wallet = self.wallet.child();
+                    if let Some(key) = &key {
+                        wallet.mint(key.clone(), k);
+                    }
+                    wallet.mint(variable.clone(), v);
+                    if !self.iterate(body.clone(), wallet)? {
+                        break;
+                    }
+                }
+                Ok(Value::Null)
+            }
+            Ast::ForRange { variable, start, end, step, body } => {
+                let mut current = self.loop_number(*start, "start")?;
+                let end = self.loop_number(*end, "end")?;
+                let step = match step {
+                    Some(step) => self.loop_number(*step, "step")?,
+                    None => 1.0,
+                };
+                if step == 0.0 {
+                    return Err(Rekt::Runtime("Range step can't be zero".to_string()));
                 }
 
-                self.wallet = old_wallet;
-                Ok(result)
+                while (step > 0.0 && current < end) || (step < 0.0 && current > end) {
+                    let wallet = self.wallet.child();
+                    wallet.mint(variable.clone(), Value::Token(current.to_string()));
+                    if !self.iterate(body.clone(), wallet)? {
+                        break;
+                    }
+                    current += step;
+                }
+                Ok(Value::Null)
+            }
+            Ast::Match { scrutinee, arms } => {
+                let value = self.deploy(*scrutinee)?;
+                for (pattern, body) in arms {
+                    let mut bindings = Vec::new();
+                    if !bind(&pattern, &value, &mut bindings) {
+                        continue;
+                    }
+                    let wallet = self.wallet.child();
+                    for (name, bound) in bindings {
+                        wallet.mint(name, bound);
+                    }
+                    return self.execute_scoped(vec![body], wallet);
+                }
+                Err(Rekt::Runtime(format!("No vibecheck arm matched {}", value.render())))
+            }
+            Ast::Break => {
+                self.unwinding = Some(Unwind::Break);
+                Ok(Value::Null)
+            }
+            Ast::Continue => {
+                self.unwinding = Some(Unwind::Continue);
+                Ok(Value::Null)
             }
+            Ast::Block(stmts) => {
+                let new_wallet = self.wallet.child();
+                self.execute_scoped(stmts, new_wallet)
+            }
+            Ast::Located { line, statement } => self.deploy(*statement).map_err(|e| e.at_line(line)),
+        }
+    }
+}
 
-            _ => Err(Rekt::Lexer("Unimplemented in GmScript.".to_string())),
+impl NativeContracts<Value> for Interpreter {
+    fn invoke(&mut self, contract: Value, args: Vec<Value>) -> Result<Value, Rekt> {
+        match contract {
+            Value::SmartContract { name, params, body, scope } => {
+                if params.len() != args.len() {
+                    return Err(
+                        Rekt::Lexer(format!("Expected {} args, got {}.", params.len(), args.len()))
+                    );
+                }
+
+                let new_wallet = scope.child();
+                for (param, val) in params.into_iter().zip(args) {
+                    new_wallet.mint(param, val);
+                }
+
+                let result = self.execute_scoped(body, new_wallet)
+                    .map_err(|e| e.traced(&format!("contract '{}'", name)))?;
+                Ok(self.take_return().unwrap_or(result))
+            }
+            other => Err(Rekt::Lexer(format!("{} is not a contract", other))),
         }
     }
 }
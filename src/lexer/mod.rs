@@ -1,12 +1,17 @@
 use logos::Logos;
 use crate::error::Rekt;
-pub use token::Token;
+pub use token::{Segment, Token};
+use token::number_problem;
 
 mod token;
 
 #[derive(Debug)]
 pub struct Lexer<'a> {
     source: &'a str,
+    // The file `source` came from and where it starts there; they differ
+    // only for the code inside a string's `{}`.
+    file: &'a str,
+    start: usize,
     line: usize,
     column: usize,
 }
@@ -15,17 +20,158 @@ impl<'a> Lexer<'a> {
     pub fn new(source: &'a str) -> Self {
         Lexer {
             source,
+            file: source,
+            start: 0,
             line: 1,
             column: 1,
         }
     }
 
+    // A lexer over `source[from..to]` that still reports file positions.
+    fn inner(&self, from: usize, to: usize) -> Lexer<'a> {
+        Lexer {
+            source: &self.source[from..to],
+            start: self.start + from,
+            ..Lexer::new(self.file)
+        }
+    }
+
+    // Points line/column at the byte `offset` into the source.
+    fn locate(&mut self, offset: usize) {
+        let before = &self.file[..self.start + offset];
+        self.line = before.matches('\n').count() + 1;
+        self.column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+    }
+
+    // Decodes escapes in a string body and splits out `{}` holes. `offset` is
+    // where the body starts in the source, for error locations.
+    fn string(&mut self, raw: &str, offset: usize) -> Result<Token, Rekt> {
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut chars = raw.char_indices().peekable();
+        while let Some((at, c)) = chars.next() {
+            match c {
+                '\\' => {
+                    let decoded = match chars.next() {
+                        Some((_, 'n')) => '\n',
+                        Some((_, 't')) => '\t',
+                        Some((_, 'r')) => '\r',
+                        Some((_, '0')) => '\0',
+                        Some((_, c @ ('\\' | '"' | '{' | '}'))) => c,
+                        Some((_, 'u')) => {
+                            let mut hex = String::new();
+                            let mut closed = false;
+                            if let Some((_, '{')) = chars.peek() {
+                                chars.next();
+                                for (_, h) in chars.by_ref() {
+                                    if h == '}' {
+                                        closed = true;
+                                        break;
+                                    }
+                                    hex.push(h);
+                                }
+                            }
+                            let escape = format!("\\u{{{}}}", hex);
+                            match u32::from_str_radix(&hex, 16) {
+                                Ok(code) if closed && hex.len() <= 6 => match char::from_u32(code) {
+                                    Some(c) => c,
+                                    None => {
+                                        self.locate(offset + at);
+                                        return Err(Rekt::Lexer(format!(
+                                            "yo, escape '{}' at line {}, column {} is not a unicode code point 🤕",
+                                            escape, self.line, self.column
+                                        )));
+                                    }
+                                },
+                                _ => return Err(self.bad_escape(&escape, offset + at)),
+                            }
+                        }
+                        Some((_, other)) => return Err(self.bad_escape(&format!("\\{}", other), offset + at)),
+                        None => return Err(self.bad_escape("\\", offset + at)),
+                    };
+                    text.push(decoded);
+                }
+                '{' => {
+                    // Find the matching `}`, stepping over nested strings.
+                    let mut depth = 1;
+                    let mut in_string = false;
+                    let mut end = None;
+                    let mut escaped = false;
+                    for (i, c) in chars.by_ref() {
+                        match c {
+                            _ if escaped => escaped = false,
+                            '\\' if in_string => escaped = true,
+                            '"' => in_string = !in_string,
+                            '{' if !in_string => depth += 1,
+                            '}' if !in_string => {
+                                depth -= 1;
+                                if depth == 0 {
+                                    end = Some(i);
+                                    break;
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                    let end = match end {
+                        Some(end) => end,
+                        None => {
+                            self.locate(offset + at);
+                            return Err(Rekt::Lexer(format!(
+                                "yo, '{{' at line {}, column {} is never closed, use \\{{ for a literal brace 🤕",
+                                self.line, self.column
+                            )));
+                        }
+                    };
+                    let code = self.inner(offset + at + 1, offset + end).tokenize()?;
+                    if code.is_empty() {
+                        self.locate(offset + at);
+                        return Err(Rekt::Lexer(format!(
+                            "yo, empty '{{}}' in string at line {}, column {} 🤕",
+                            self.line, self.column
+                        )));
+                    }
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    segments.push(Segment::Code(code));
+                }
+                c => text.push(c),
+            }
+        }
+
+        if segments.is_empty() {
+            return Ok(Token::Text(text));
+        }
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        Ok(Token::Interpolation(segments))
+    }
+
+    fn bad_escape(&mut self, escape: &str, offset: usize) -> Rekt {
+        self.locate(offset);
+        Rekt::Lexer(format!(
+            "yo, unknown escape '{}' at line {}, column {} 🤕",
+            escape, self.line, self.column
+        ))
+    }
+
     pub fn tokenize(&mut self) -> Result<Vec<Token>, Rekt> {
+        self.tokenize_with_lines().map(|(tokens, _)| tokens)
+    }
+
+    // Tokens along with the line each one starts on.
+    pub fn tokenize_with_lines(&mut self) -> Result<(Vec<Token>, Vec<usize>), Rekt> {
         let mut tokens = Vec::new();
+        let mut lines = Vec::new();
         let mut lexer = Token::lexer(self.source);
+        let (mut line, mut counted) = (1, 0);
 
         while let Some(token) = lexer.next() {
             let span = lexer.span();
+            line += self.source[counted..span.start].matches('\n').count();
+            counted = span.start;
             // println!(
             //     "🔍 Token: {:?} | slice: '{}' | span: {:?}",
             //     token,
@@ -34,17 +180,212 @@ impl<'a> Lexer<'a> {
             // );
         
             match token {
-                Token::Rekt => {
+                Token::Error if self.source[span.start..].starts_with('"') => {
+                    self.locate(span.start);
+                    return Err(Rekt::Lexer(format!(
+                        "yo, string opened at line {}, column {} never closes 🤕",
+                        self.line, self.column
+                    )));
+                }
+                Token::Error if number_problem(&self.source[span.start..span.end]).is_some() => {
+                    let slice = &self.source[span.start..span.end];
+                    self.locate(span.start);
+                    return Err(Rekt::Lexer(format!(
+                        "yo, number '{}' at line {}, column {} {} 🤕",
+                        slice, self.line, self.column, number_problem(slice).unwrap_or_default()
+                    )));
+                }
+                Token::Error => {
+                    self.locate(span.start);
                     return Err(Rekt::Lexer(format!(
                         "yo, invalid token '{}' at line {}, column {} 🤕",
                         &self.source[span.start..span.end], self.line, self.column
                     )));
                 }
-                token => tokens.push(token),
+                Token::Text(raw) => {
+                    let quote = if self.source[span.start..].starts_with("\"\"\"") { 3 } else { 1 };
+                    tokens.push(self.string(&raw, span.start + quote)?);
+                    lines.push(line);
+                }
+                Token::UnterminatedComment => {
+                    self.locate(span.start);
+                    return Err(Rekt::Lexer(format!(
+                        "yo, block comment opened at line {}, column {} never closes 🤕",
+                        self.line, self.column
+                    )));
+                }
+                token => {
+                    tokens.push(token);
+                    lines.push(line);
+                }
             }
                 
         }
 
-        Ok(tokens)
+        Ok((tokens, lines))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numeric::U256;
+
+    fn number(source: &str) -> f64 {
+        match Lexer::new(source).tokenize() {
+            Ok(tokens) => match tokens.as_slice() {
+                [Token::Number(n)] => *n,
+                other => panic!("{} lexed as {:?}", source, other),
+            },
+            Err(e) => panic!("{} failed: {}", source, e),
+        }
+    }
+
+    fn rejected(source: &str, why: &str) {
+        match Lexer::new(source).tokenize() {
+            Err(Rekt::Lexer(message)) => assert!(message.contains(why), "{}: {}", source, message),
+            other => panic!("{} should not lex, got {:?}", source, other),
+        }
+    }
+
+    #[test]
+    fn number_literal_forms() {
+        assert_eq!(number("21_000_000"), 21_000_000.0);
+        assert_eq!(number("0xff"), 255.0);
+        assert_eq!(number("0b1010"), 10.0);
+        assert_eq!(number("0o17"), 15.0);
+        assert_eq!(number("-0x10"), -16.0);
+        assert_eq!(number("1e18"), 1e18);
+        assert_eq!(number("2.5e-3"), 0.0025);
+        assert_eq!(number("007"), 7.0);
+        assert_eq!(number("9007199254740992"), 9007199254740992.0);
+        assert_eq!(number("0x20000000000000000000"), 2f64.powi(77));
+    }
+
+    #[test]
+    fn unit_suffixes_scale_to_whole_base_units() {
+        assert_eq!(number("1 ether"), 1e18);
+        assert_eq!(number("5 gwei"), 5e9);
+        assert_eq!(number("0.3 gwei"), 3e8);
+        assert_eq!(number("1.5 gwei"), 1.5e9);
+        assert_eq!(number("2.5e3 wei"), 2500.0);
+        assert_eq!(number("0.5 btc"), 5e7);
+        rejected("0.1 wei", "fraction of the smallest unit");
+        rejected("1.5 wei", "fraction of the smallest unit");
+        rejected("0.0000000001 gwei", "fraction of the smallest unit");
+        rejected("1.5 sats", "fraction of the smallest unit");
+    }
+
+    #[test]
+    fn numbers_must_be_finite_and_exact() {
+        rejected("1e400", "too big");
+        rejected("-1e400", "too big");
+        rejected("0xffffffffffffffffffffffff", "exactly");
+        rejected("9007199254740993", "exactly");
+        rejected("0x1ffffffffffffffffffffffffffffffff", "too big");
+    }
+
+    #[test]
+    fn uint_literals_stay_exact() {
+        let tokens = Lexer::new("0xffffffffffffffffffffffffn 2n ether").tokenize().unwrap();
+        assert_eq!(tokens[0], Token::Uint(U256::parse("0xffffffffffffffffffffffff").unwrap()));
+        assert_eq!(tokens[1], Token::Uint(U256::parse("2000000000000000000").unwrap()));
+        rejected(&format!("{}n", "9".repeat(80)), "256-bit uint");
+        rejected("1.5n", "number '1.5n' at line 1, column 1 has a fraction, but uints are whole numbers");
+    }
+
+    #[test]
+    fn underscores_sit_between_digits() {
+        assert_eq!(number("1_0"), 10.0);
+        assert_eq!(number("0xf_f"), 255.0);
+        rejected("1_", "number '1_' at line 1, column 1 needs a digit on both sides of each '_'");
+        rejected("1__0", "needs a digit on both sides");
+        rejected("1_.5", "needs a digit on both sides");
+        rejected("0x_ff", "needs a digit on both sides");
+        rejected("1__0n", "needs a digit on both sides");
+        rejected("1__0d", "needs a digit on both sides");
+    }
+
+    #[test]
+    fn other_bad_tokens_are_not_numbers() {
+        rejected("ping `;", "invalid token '`' at line 1, column 6");
+    }
+
+    #[test]
+    fn block_comments_nest() {
+        let tokens = Lexer::new("1 /* outer /* inner */ still outer */ 2").tokenize().unwrap();
+        assert_eq!(tokens, vec![Token::Number(1.0), Token::Number(2.0)]);
+        let (_, lines) = Lexer::new("/* one\n/* two\n*/\n*/ 3").tokenize_with_lines().unwrap();
+        assert_eq!(lines, vec![4]);
+    }
+
+    #[test]
+    fn unterminated_block_comments_point_at_their_opening() {
+        rejected("1;\n  /* open /* shut */", "block comment opened at line 2, column 3 never closes");
+        rejected("/* /* */", "block comment opened at line 1, column 1 never closes");
+    }
+
+    #[test]
+    fn doc_comments_attach_to_the_next_declaration() {
+        use crate::parser::ast::Ast;
+        use crate::parser::Parser;
+
+        let source = "/// The total\n/// so far\ntoken total = 1;\n/// not a declaration\nping total;";
+        let ast = Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+        let Ast::Program(stmts) = ast else { panic!("not a program") };
+        assert_eq!(stmts.len(), 2);
+        match stmts[0].unlocated() {
+            Ast::VariableDecl { doc, .. } => assert_eq!(doc.as_deref(), Some("The total\nso far")),
+            other => panic!("expected a declaration, got {:?}", other),
+        }
+    }
+
+    fn text(source: &str) -> String {
+        match Lexer::new(source).tokenize() {
+            Ok(tokens) => match tokens.as_slice() {
+                [Token::Text(text)] => text.clone(),
+                other => panic!("{} lexed as {:?}", source, other),
+            },
+            Err(e) => panic!("{} failed: {}", source, e),
+        }
+    }
+
+    #[test]
+    fn string_escapes() {
+        assert_eq!(text(r#""a\nb""#), "a\nb");
+        assert_eq!(text(r#""a\tb""#), "a\tb");
+        assert_eq!(text(r#""a\rb""#), "a\rb");
+        assert_eq!(text(r#""a\0b""#), "a\0b");
+        assert_eq!(text(r#""\\ \" \{ \}""#), "\\ \" { }");
+        assert_eq!(text(r#""\u{1F49A}""#), "💚");
+        assert_eq!(text(r#""\u{e9}""#), "é");
+        assert_eq!(text("\"\"\"say \"gm\"\nback\"\"\""), "say \"gm\"\nback");
+    }
+
+    #[test]
+    fn bad_escapes_say_where_they_are() {
+        rejected(r#""ok\q""#, "unknown escape '\\q' at line 1, column 4");
+        rejected(r#""\u{zz}""#, "unknown escape '\\u{zz}' at line 1, column 2");
+        rejected(r#""\u{1234567}""#, "unknown escape '\\u{1234567}'");
+        rejected(r#""\u{110000}""#, "escape '\\u{110000}' at line 1, column 2 is not a unicode code point");
+        rejected(r#""\u{d800}""#, "is not a unicode code point");
+    }
+
+    #[test]
+    fn unterminated_strings_point_at_their_opening() {
+        rejected("ping 1;\nping \"\"\"never\nends\";", "string opened at line 2, column 6 never closes");
+        rejected("\"open", "string opened at line 1, column 1 never closes");
+    }
+
+    #[test]
+    fn interpolation_errors() {
+        rejected(r#""a {1 + 2 b""#, "'{' at line 1, column 4 is never closed");
+        rejected(r#""a {} b""#, "empty '{}' in string at line 1, column 4");
+        rejected("ping 1;\nping \"a {\"x\\q\"} b\";", "unknown escape '\\q' at line 2, column 12");
+
+        let source = "ping 1;\nping \"a{1 + }b\";";
+        let (tokens, lines) = Lexer::new(source).tokenize_with_lines().unwrap();
+        let error = crate::parser::Parser::with_lines(tokens, lines).parse().unwrap_err();
+        assert_eq!(error.to_string(), "Parser error: Unexpected end of input in '{1 +}'\n  ↳ at line 2");
     }
 }
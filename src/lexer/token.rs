@@ -1,5 +1,6 @@
 use logos::Logos;
 use std::fmt;
+use crate::numeric::{Decimal, U256};
 
 #[derive(Logos, Debug, PartialEq, Clone)]
 pub enum Token {
@@ -12,6 +13,14 @@ pub enum Token {
     #[regex(r".", priority = 0)]
     Error,
 
+    // Comments worth keeping
+    #[regex(r"///([^/\n][^\n]*)?\n?", doc_comment, priority = 10)]
+    DocComment(String),
+    // Block comments (/* ... */, nesting allowed) are skipped by their callback;
+    // this is only ever emitted when one never closes.
+    #[token("/*", block_comment)]
+    UnterminatedComment,
+
     // Keywords for GM Language
     #[token("token")]
     Launch,      // 'Launch' maps to 'token' in the GM language
@@ -20,21 +29,41 @@ pub enum Token {
     #[token("mine")]
     BossFight,   // 'BossFight' maps to 'mine' in the GM language
     #[token("ping")]
+    #[token("broadcast")]
     Ping,        // 'Ping' maps to 'broadcast' in the GM language
     
     // Control flow
     #[token("if")]
+    #[token("sus")]
     Sus,         // 'Sus' maps to 'if' in the GM language
     #[token("else")]
+    #[token("rekt")]
     Rekt,        // 'Rekt' maps to 'else' in the GM language
     #[token("while")]
+    #[token("grind")]
     Grind,       // 'Grind' maps to 'while' in the GM language
     #[token("return")]
     GG,          // 'GG' maps to 'return' in the GM language
     #[token("loop")]
     Loop,        // 'Loop' keeps the name
     #[token("break")]
+    #[token("crash")]
     Crash,       // 'Crash' maps to 'break' in the GM language
+    #[token("continue")]
+    #[token("skip")]
+    Skip,        // 'Skip' maps to 'continue' in the GM language
+    #[token("for")]
+    #[token("farm")]
+    Farm,        // 'Farm' maps to 'for' in the GM language
+    #[token("match")]
+    #[token("vibecheck")]
+    Vibecheck,   // 'Vibecheck' maps to 'match' in the GM language
+    #[token("in")]
+    In,
+    #[token("delete")]
+    Delete,      // 'Delete' clears a mapping slot, like Solidity
+    #[token("struct")]
+    Struct,      // 'Struct' declares a record type, like Solidity
     
     // Types
     #[token("int")]
@@ -43,12 +72,32 @@ pub enum Token {
     TypeStr,
     #[token("bool")]
     TypeBool,
+    #[token("uint")]
+    #[token("uint256")]
+    TypeUint,
+    #[token("decimal")]
+    TypeDecimal,
 
     // Literals
-    #[regex(r"-?[0-9]+(\.[0-9]+)?", |lex| lex.slice().parse().ok())] 
+    #[regex(r"-?[0-9][0-9_]*(\.[0-9][0-9_]*)?([eE][+-]?[0-9][0-9_]*)?", number)]
+    #[regex(r"-?0x[0-9a-fA-F_]+", number)]
+    #[regex(r"-?0b[01_]+", number)]
+    #[regex(r"-?0o[0-7_]+", number)]
     Number(f64),
-    #[regex(r#""[^"]*""#, |lex| Some(String::from(&lex.slice()[1..lex.slice().len() - 1])))] 
+    #[regex(r"[0-9][0-9_]*n", uint)]
+    #[regex(r"0x[0-9a-fA-F_]+n", uint)]
+    #[regex(r"0b[01_]+n", uint)]
+    #[regex(r"0o[0-7_]+n", uint)]
+    #[regex(r"[0-9][0-9_]*\.[0-9][0-9_]*n", |_| None::<U256>)]
+    Uint(U256), // 42n, exact
+    #[regex(r"-?[0-9][0-9_]*(\.[0-9][0-9_]*)?d", decimal)]
+    Decimal(Decimal), // 1.50d, exact with two places
+    // The callback only finds the closing quote; escapes and `{}` holes are
+    // worked out by `Lexer::tokenize`, which knows where errors are.
+    #[token("\"", string_body)]
+    #[token("\"\"\"", string_body)]
     Text(String),
+    Interpolation(Vec<Segment>), // "gm {name}", built by the lexer from a Text
     #[token("true")]
     True,
     #[token("false")]
@@ -73,6 +122,47 @@ pub enum Token {
     Burn,      // Alternative for '-'
     #[token("swap")]
     Swap,      // Alternative for '/'
+
+    // Integer operators. `//` is taken by comments, so integer division is `~/`
+    #[token("%")]
+    #[token("rake")]
+    Percent,     // remainder, the house's rake
+    #[token("~/")]
+    #[token("chop")]
+    TildeSlash,  // integer division, rounding down
+    #[token("&")]
+    #[token("mask")]
+    Amp,
+    #[token("^")]
+    #[token("flip")]
+    Caret,
+    #[token("<<")]
+    #[token("pump")]
+    ShiftLeft,
+    #[token(">>")]
+    #[token("dump")]
+    ShiftRight,
+    #[token("~")]
+    #[token("invert")]
+    Tilde,
+
+    // Compound assignment
+    #[token("+=")]
+    #[token("stake=")]
+    PlusMatch,
+    #[token("-=")]
+    #[token("burn=")]
+    MinusMatch,
+    #[token("*=")]
+    #[token("yield=")]
+    StarMatch,
+    #[token("/=")]
+    #[token("swap=")]
+    SlashMatch,
+    #[token("++")]
+    PlusPlus,
+    #[token("--")]
+    MinusMinus,
     
     // Comparison & Assignment
     #[token("==")]
@@ -107,6 +197,10 @@ pub enum Token {
     LBrace,
     #[token("}")]
     RBrace,
+    #[token("[")]
+    LBracket,
+    #[token("]")]
+    RBracket,
     #[token(";")]
     Semicolon,
     #[token(",")]
@@ -115,16 +209,267 @@ pub enum Token {
     Colon,
     #[token("->")]
     Arrow,
+    #[token(".")]
+    Dot,
+    #[token("..")]
+    DotDot,
+    #[token("=>")]
+    FatArrow,
+    #[token("|")]
+    #[token("merge")]
+    Pipe,        // or-patterns, and bitwise or
+    #[token("?")]
+    Question,
 
     // Identifier
     #[regex("[a-zA-Z_][a-zA-Z0-9_]*", |lex| Some(String::from(lex.slice())))] 
     Identifier(String),
 }
 
+// A piece of an interpolated string: literal text, or the tokens of a `{}` hole.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Segment {
+    Text(String),
+    Code(Vec<Token>),
+}
+
+// Finds the end of a string literal and returns its raw body. Quotes inside
+// `{}` holes belong to nested strings, so they don't close this one, unless
+// that leaves the string open; then the hole is the problem and the lexer
+// reports it.
+fn string_body(lex: &mut logos::Lexer<Token>) -> Option<String> {
+    let quote = lex.slice().len();
+    let rest = lex.remainder();
+    let end = closing_quote(rest, quote == 3, true).or_else(|| closing_quote(rest, quote == 3, false))?;
+    lex.bump(end + quote);
+    Some(rest[..end].to_string())
+}
+
+fn closing_quote(rest: &str, triple: bool, holes: bool) -> Option<usize> {
+    let bytes = rest.as_bytes();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'{' if holes => depth += 1,
+            b'}' if depth > 0 => depth -= 1,
+            b'"' if depth > 0 => i += skip_nested(&bytes[i + 1..]),
+            b'"' if !triple || rest[i..].starts_with("\"\"\"") => return Some(i),
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+// Length of a nested `"..."` up to and including its closing quote.
+fn skip_nested(bytes: &[u8]) -> usize {
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'"' => return i + 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+// Units a number literal can be written in, as powers of ten of the chain's
+// base unit.
+const UNITS: &[(&str, u32)] = &[
+    ("wei", 0),
+    ("gwei", 9),
+    ("ether", 18),
+    ("sats", 0),
+    ("btc", 8),
+];
+
+// Consumes a unit word after a number literal, returning its power of ten.
+fn unit_suffix(lex: &mut logos::Lexer<Token>) -> Option<u32> {
+    let rest = lex.remainder();
+    let gap = rest.len() - rest.trim_start_matches([' ', '\t']).len();
+    let word: String = rest[gap..]
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .collect();
+    let (_, power) = UNITS.iter().find(|(unit, _)| *unit == word)?;
+    lex.bump(gap + word.len());
+    Some(*power)
+}
+
+const TOO_BIG: &str = "is too big to be a number";
+const INEXACT: &str = "can't be held exactly by a number; write it as a uint like `42n`";
+const FRACTION: &str = "leaves a fraction of the smallest unit behind";
+const UINT_TOO_BIG: &str = "doesn't fit in a 256-bit uint";
+const UINT_FRACTION: &str = "has a fraction, but uints are whole numbers";
+const UNDERSCORE: &str = "needs a digit on both sides of each '_'";
+
+// `_` only separates digits: `21_000` but not `1_`, `1__0` or `0x_ff`.
+fn underscores_fit(literal: &str) -> bool {
+    let digits = literal.strip_prefix('-').unwrap_or(literal);
+    let radix = match digits.get(..2) {
+        Some("0x") => 16,
+        Some("0b") => 2,
+        Some("0o") => 8,
+        _ => 10,
+    };
+    let chars: Vec<char> = digits.chars().collect();
+    chars.iter().enumerate().all(|(i, c)| {
+        *c != '_'
+            || (i > 0
+                && chars[i - 1].is_digit(radix)
+                && chars.get(i + 1).is_some_and(|next| next.is_digit(radix)))
+    })
+}
+
+// Parses a number literal (`21_000_000`, `0xff`, `1e18`) and applies a unit
+// suffix like `1 ether` or `5 gwei` if one follows.
+fn number(lex: &mut logos::Lexer<Token>) -> Option<f64> {
+    let literal = lex.slice().to_string();
+    let unit = unit_suffix(lex);
+    number_value(&literal, unit).ok()
+}
+
+// The value of a number literal scaled by its unit, or why it can't be one.
+// Integers must survive the trip through f64 unchanged, and an amount in
+// units must come out as a whole number of the base unit.
+fn number_value(literal: &str, unit: Option<u32>) -> Result<f64, &'static str> {
+    if !underscores_fit(literal) {
+        return Err(UNDERSCORE);
+    }
+    let literal = literal.replace('_', "");
+    let (negative, digits) = match literal.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, literal.as_str()),
+    };
+    let power = unit.unwrap_or(0) as i32;
+    let radix = match digits.get(..2) {
+        Some("0x") => 16,
+        Some("0b") => 2,
+        Some("0o") => 8,
+        _ => 10,
+    };
+    let mut value = if radix == 10 {
+        let (mantissa, exponent) = match digits.find(['e', 'E']) {
+            Some(at) => (&digits[..at], digits[at + 1..].parse::<i32>().map_err(|_| TOO_BIG)?),
+            None => (digits, 0),
+        };
+        // Shifting the exponent instead of multiplying keeps `0.3 gwei` exact.
+        let value: f64 = format!("{}e{}", mantissa, exponent.saturating_add(power))
+            .parse()
+            .map_err(|_| TOO_BIG)?;
+        let whole = !mantissa.contains('.') && exponent == 0 && power == 0;
+        let shown = format!("{:.0}", value);
+        if whole && value.is_finite() && shown.trim_start_matches('0') != mantissa.trim_start_matches('0') {
+            return Err(INEXACT);
+        }
+        value
+    } else {
+        let n = u128::from_str_radix(&digits[2..], radix).map_err(|_| TOO_BIG)?;
+        if n != 0 && 128 - n.leading_zeros() - n.trailing_zeros() > f64::MANTISSA_DIGITS {
+            return Err(INEXACT);
+        }
+        n as f64 * 10f64.powi(power)
+    };
+    if !value.is_finite() {
+        return Err(TOO_BIG);
+    }
+    if unit.is_some() && value.fract() != 0.0 {
+        return Err(FRACTION);
+    }
+    if negative {
+        value = -value;
+    }
+    Ok(value)
+}
+
+// Why a number literal the lexer rejected is wrong, given its whole slice
+// including any unit. `None` when it wasn't a number after all.
+pub fn number_problem(slice: &str) -> Option<&'static str> {
+    if !slice.strip_prefix('-').unwrap_or(slice).starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let unit = UNITS
+        .iter()
+        .filter(|(unit, _)| slice.ends_with(unit))
+        .max_by_key(|(unit, _)| unit.len());
+    let (literal, power) = match unit {
+        Some((unit, power)) => (slice[..slice.len() - unit.len()].trim_end(), Some(*power)),
+        None => (slice, None),
+    };
+    if let Some(digits) = literal.strip_suffix('n') {
+        return Some(if !underscores_fit(digits) {
+            UNDERSCORE
+        } else if digits.contains('.') {
+            UINT_FRACTION
+        } else {
+            UINT_TOO_BIG
+        });
+    }
+    number_value(literal, power).err()
+}
+
+// Parses a uint literal like `42n` or `0xffn`; units scale it exactly.
+fn uint(lex: &mut logos::Lexer<Token>) -> Option<U256> {
+    let slice = lex.slice();
+    if !underscores_fit(&slice[..slice.len() - 1]) {
+        return None;
+    }
+    let value = U256::parse(&slice[..slice.len() - 1])?;
+    let scale = U256::from_u64(10).checked_pow(unit_suffix(lex).unwrap_or(0))?;
+    value.checked_mul(scale)
+}
+
+// Parses a decimal literal like `1.50d`.
+fn decimal(lex: &mut logos::Lexer<Token>) -> Option<Decimal> {
+    let slice = lex.slice();
+    let digits = &slice[..slice.len() - 1];
+    if !underscores_fit(digits) {
+        return None;
+    }
+    Decimal::parse(digits)
+}
+
+fn doc_comment(lex: &mut logos::Lexer<Token>) -> String {
+    lex.slice()[3..].trim().to_string()
+}
+
+// Skips a block comment, keeping track of nested `/* */` pairs.
+fn block_comment(lex: &mut logos::Lexer<Token>) -> logos::Filter<()> {
+    let rest = lex.remainder();
+    let bytes = rest.as_bytes();
+    let mut depth = 1;
+    let mut i = 0;
+    while i + 1 < bytes.len() {
+        match (bytes[i], bytes[i + 1]) {
+            (b'/', b'*') => {
+                depth += 1;
+                i += 2;
+            }
+            (b'*', b'/') => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    lex.bump(i);
+                    return logos::Filter::Skip;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    lex.bump(rest.len());
+    logos::Filter::Emit(())
+}
+
 impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Token::Error => write!(f, "error"),
+            Token::DocComment(text) => write!(f, "/// {}", text),
+            Token::UnterminatedComment => write!(f, "/*"),
             Token::Launch => write!(f, "token"),
             Token::Debug => write!(f, "block"),
             Token::BossFight => write!(f, "mine"),
@@ -135,17 +480,53 @@ impl fmt::Display for Token {
             Token::GG => write!(f, "return"),
             Token::Loop => write!(f, "loop"),
             Token::Crash => write!(f, "break"),
+            Token::Skip => write!(f, "continue"),
+            Token::Farm => write!(f, "for"),
+            Token::Vibecheck => write!(f, "match"),
+            Token::In => write!(f, "in"),
+            Token::Delete => write!(f, "delete"),
+            Token::Struct => write!(f, "struct"),
             Token::TypeInt => write!(f, "int"),
             Token::TypeStr => write!(f, "str"),
             Token::TypeBool => write!(f, "bool"),
+            Token::TypeUint => write!(f, "uint"),
+            Token::TypeDecimal => write!(f, "decimal"),
             Token::Number(n) => write!(f, "{}", n),
+            Token::Uint(n) => write!(f, "{}n", n),
+            Token::Decimal(d) => write!(f, "{}d", d),
             Token::Text(s) => write!(f, "\"{}\"", s),
+            Token::Interpolation(segments) => {
+                write!(f, "\"")?;
+                for segment in segments {
+                    match segment {
+                        Segment::Text(text) => write!(f, "{}", text)?,
+                        Segment::Code(tokens) => {
+                            let code: Vec<String> = tokens.iter().map(|t| t.to_string()).collect();
+                            write!(f, "{{{}}}", code.join(" "))?;
+                        }
+                    }
+                }
+                write!(f, "\"")
+            }
             Token::True => write!(f, "true"),
             Token::False => write!(f, "false"),
             Token::Stake => write!(f, "stake"),
             Token::Yield => write!(f, "yield"),
             Token::Burn => write!(f, "burn"),
             Token::Swap => write!(f, "swap"),
+            Token::Percent => write!(f, "%"),
+            Token::TildeSlash => write!(f, "~/"),
+            Token::Amp => write!(f, "&"),
+            Token::Caret => write!(f, "^"),
+            Token::ShiftLeft => write!(f, "<<"),
+            Token::ShiftRight => write!(f, ">>"),
+            Token::Tilde => write!(f, "~"),
+            Token::PlusMatch => write!(f, "+="),
+            Token::MinusMatch => write!(f, "-="),
+            Token::StarMatch => write!(f, "*="),
+            Token::SlashMatch => write!(f, "/="),
+            Token::PlusPlus => write!(f, "++"),
+            Token::MinusMinus => write!(f, "--"),
             Token::Not => write!(f, "not"),
             Token::And => write!(f, "and"),
             Token::Or => write!(f, "or"),
@@ -157,10 +538,17 @@ impl fmt::Display for Token {
             Token::RParen => write!(f, ")"),
             Token::LBrace => write!(f, "{{"),
             Token::RBrace => write!(f, "}}"),
+            Token::LBracket => write!(f, "["),
+            Token::RBracket => write!(f, "]"),
             Token::Semicolon => write!(f, ";"),
             Token::Comma => write!(f, ","),
             Token::Colon => write!(f, ":"),
             Token::Arrow => write!(f, "->"),
+            Token::Dot => write!(f, "."),
+            Token::DotDot => write!(f, ".."),
+            Token::FatArrow => write!(f, "=>"),
+            Token::Pipe => write!(f, "|"),
+            Token::Question => write!(f, "?"),
             Token::Identifier(name) => write!(f, "{}", name),
             Token::Plus => write!(f, "+"),
             Token::Minus => write!(f, "-"),
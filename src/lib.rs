@@ -0,0 +1,21 @@
+// src/lib.rs
+//
+// gm-language as a library: the lexer, parser and checks, the tree walker
+// and the VM, and every backend. The `gm-language` binary is a thin CLI
+// over `runner`, and the tests in `tests/` drive the same entry points.
+
+pub mod shared_types;
+pub mod numeric;
+pub mod lexer;
+pub mod parser;
+pub mod checker;
+pub mod resolver;
+pub mod optimizer;
+pub mod interpreter;
+pub mod vm;
+pub mod error;
+pub mod fun;
+pub mod runner;
+pub mod transpile;
+pub mod wasm;
+pub mod evm;
@@ -5,21 +5,15 @@ use std::env;
 #[allow(unused_imports)]
 use std::fs;
 
-mod shared_types;
-mod lexer;
-mod parser;
-mod interpreter;
-mod error;
-mod fun;
-mod runner;
-
-use crate::runner::Runner;
-use crate::shared_types::Value;
-use crate::error::Rekt;
-use crate::lexer::Lexer;
-use crate::parser::Parser;
+use gm_language::{interpreter, runner};
+use gm_language::checker::Checker;
+use gm_language::runner::{Comparison, Engine, Runner};
+use gm_language::shared_types::Value;
+use gm_language::error::Rekt;
+use gm_language::lexer::Lexer;
+use gm_language::parser::Parser;
 #[allow(unused_imports)]
-use crate::fun::*;
+use gm_language::fun::*;
 
 fn main() -> io::Result<()> {
     // Clear the terminal screen as the first action
@@ -27,14 +21,38 @@ fn main() -> io::Result<()> {
     
     let args: Vec<String> = env::args().collect();
     
-    match args.len() {
-        1 => run_repl(),
-        
-        2 => run_file(&args[1]),
+    match args.as_slice() {
+        [_] => run_repl(),
+
+        [_, flag, path] if flag == "--tree" => run_file(path, Engine::TreeWalker),
+
+        [_, flag, paths @ ..] if flag == "--diff" && !paths.is_empty() => run_diffs(paths, runner::compare_file, "agree"),
+
+        [_, flag, path, runs @ ..] if flag == "--bench" && runs.len() <= 1 => run_bench(path, runs.first()),
+
+        [_, flag, path, out @ ..] if flag == "--compile" && out.len() <= 1 => run_compile(path, out.first()),
+
+        [_, flag, path] if flag == "--disasm" => run_disasm(path),
+
+        [_, flag, path] if flag == "--dump-optimized" => run_dump_optimized(path),
+
+        [_, flag, path] if flag == "--check" => run_check(path),
+        [_, flag, path, out @ ..] if flag == "--ast" && out.len() <= 1 => run_ast(path, out.first()),
+        [_, flag, paths @ ..] if flag == "--diff-ast" && !paths.is_empty() => run_diffs(paths, runner::compare_ast_file, "agree"),
+        [_, flag, path, out @ ..] if flag == "--js" && out.len() <= 1 => run_js(path, out.first()),
+        [_, flag, paths @ ..] if flag == "--diff-js" && !paths.is_empty() => run_diffs(paths, runner::compare_js_file, "agree"),
+        [_, flag, path, out @ ..] if flag == "--sol" && out.len() <= 1 => run_sol(path, out.first()),
+        [_, flag, paths @ ..] if flag == "--diff-sol" && !paths.is_empty() => run_diffs(paths, runner::compare_sol_file, "match their goldens"),
+        [_, flag, path, out @ ..] if flag == "--rs" && out.len() <= 1 => run_rs(path, out.first()),
+        [_, flag, paths @ ..] if flag == "--diff-rs" && !paths.is_empty() => run_diffs(paths, runner::compare_rs_file, "agree"),
+        [_, flag, path, out @ ..] if flag == "--wasm" && out.len() <= 1 => run_wasm(path, out.first()),
+        [_, flag, path, out @ ..] if flag == "--evm" && out.len() <= 1 => run_evm(path, out.first()),
+
+        [_, path] if !path.starts_with("--") => run_file(path, Engine::Vm),
 
         _ => {
             println!("{}", create_crypto_border(
-                "Usage: gm-language [script.gm]"
+                "Usage: gm-language [script.gm | script.gmc | tree.json] | --tree script.gm | --diff scripts... | --bench script.gm [runs] | --compile script.gm [out.gmc] | --disasm script.gm|script.gmc | --dump-optimized script.gm | --check script.gm | --ast script.gm [out.json] | --diff-ast scripts... | --js script.gm [out.js] | --diff-js scripts... | --sol script.gm [out.sol] | --diff-sol scripts... | --rs script.gm [out.rs] | --diff-rs scripts... | --wasm script.gm [out.wasm] | --evm script.gm [out.bin]"
             ).bright_red());
             Ok(())
         }
@@ -45,6 +63,7 @@ fn run_repl() -> io::Result<()> {
     print_welcome_message();
 
     let mut rl = Editor::<()>::new();
+    let mut checker = Checker::new();
     let mut interpreter = interpreter::Interpreter::new();
     let mut current_line = String::new();
     let mut brace_count = 0;
@@ -87,13 +106,13 @@ fn run_repl() -> io::Result<()> {
                         Ok(_) => {
                             rl.add_history_entry(current_line.as_str());
 
-                            match execute_line(&current_line, &mut interpreter) {
+                            match execute_line(&current_line, &mut checker, &mut interpreter) {
                                 Ok(value) => {
                                     match value {
                                         Value::Null => (),
                                         _ => {
                                             println!("{} {}", get_random_emoji(), get_random_success_message().bright_green());
-                                            println!("{} {:?}", get_random_emoji(), value)
+                                            println!("{} {}", get_random_emoji(), value.render())
                                         },
                                     }
                                 },
@@ -111,7 +130,7 @@ fn run_repl() -> io::Result<()> {
                 }
             }
             Err(err) => {
-                println!("{}", format!("💥 Error: {}", format!("{}\n{}", get_random_error_message(), err.to_string())).bright_red());
+                println!("{}", format!("💥 Error: {}\n{}", get_random_error_message(), err).bright_red());
                 break;
             }
         }
@@ -120,8 +139,8 @@ fn run_repl() -> io::Result<()> {
     Ok(())
 }
 
-fn run_file(path: &str) -> io::Result<()> {
-    let mut runner = Runner::new();
+fn run_file(path: &str, engine: Engine) -> io::Result<()> {
+    let mut runner = Runner::with_engine(engine);
 
     println!("{}", format!("Reading GM script from: {}", path).bright_blue());
     
@@ -134,6 +153,144 @@ fn run_file(path: &str) -> io::Result<()> {
     Ok(())
 }
 
+// Runs `compare` on each script, printing a line for each and what
+// disagreed for any that didn't agree, and exits non-zero if one didn't.
+fn run_diffs<'a, C: Comparison>(
+    paths: &'a [String],
+    compare: impl Fn(&'a str) -> Result<C, Rekt>,
+    agreeing: &str,
+) -> io::Result<()> {
+    let mut failed = 0;
+    for path in paths {
+        match compare(path) {
+            Ok(c) if c.mismatches().is_empty() => match c.events() {
+                Some(events) => println!("{}", format!("✅ {} ({} events)", path, events).bright_green()),
+                None => println!("{}", format!("✅ {}", path).bright_green()),
+            },
+            Ok(c) => {
+                failed += 1;
+                println!("{}", format!("❌ {}", path).bright_red());
+                for line in c.mismatches() {
+                    println!("   {}", line);
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                println!("{}", format!("💥 {}: {}", path, format_error(&e)).bright_red());
+            }
+        }
+    }
+    println!("{}", create_crypto_border(
+        &format!("{} of {} scripts {}", paths.len() - failed, paths.len(), agreeing)
+    ).bright_cyan());
+    if failed > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn run_bench(path: &str, runs: Option<&String>) -> io::Result<()> {
+    let runs = runs.and_then(|r| r.parse().ok()).unwrap_or(10);
+    match runner::bench_file(path, runs) {
+        Ok(t) => {
+            let speedup = t.tree_walker.as_secs_f64() / t.vm.as_secs_f64().max(f64::EPSILON);
+            println!("{}", create_crypto_border(&format!("⏱️  {} ({} runs)", path, runs)).bright_cyan());
+            println!("   tree walker: {:>10.3} ms/run", t.tree_walker.as_secs_f64() * 1000.0);
+            println!("   vm:          {:>10.3} ms/run (+ {:.3} ms to compile)",
+                t.vm.as_secs_f64() * 1000.0, t.compile.as_secs_f64() * 1000.0);
+            println!("   speedup:     {:>10.1}x", speedup);
+        }
+        Err(e) => println!("{}", format!("💥 Error: {}", format_error(&e)).bright_red()),
+    }
+    Ok(())
+}
+
+fn run_compile(path: &str, out: Option<&String>) -> io::Result<()> {
+    match runner::compile_file(path, out.map(|o| o.as_str())) {
+        Ok(out) => println!("{}", create_crypto_border(&format!("📦 Compiled {} to {}", path, out.display())).bright_green()),
+        Err(e) => {
+            println!("{}", format!("💥 Error: {}", format_error(&e)).bright_red());
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+fn run_disasm(path: &str) -> io::Result<()> {
+    match runner::disassemble_file(path) {
+        Ok(listing) => print!("{}", listing),
+        Err(e) => {
+            println!("{}", format!("💥 Error: {}", format_error(&e)).bright_red());
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+fn run_dump_optimized(path: &str) -> io::Result<()> {
+    match runner::optimized_tree(path) {
+        Ok(ast) => println!("{:#?}", ast),
+        Err(e) => {
+            println!("{}", format!("💥 Error: {}", format_error(&e)).bright_red());
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+fn run_ast(path: &str, out: Option<&String>) -> io::Result<()> {
+    match runner::dump_ast_file(path, out.map(|o| o.as_str())) {
+        Ok(out) => println!("{}", create_crypto_border(&format!("🌳 Wrote the tree of {} to {}", path, out.display())).bright_green()),
+        Err(e) => {
+            println!("{}", format!("💥 Error: {}", format_error(&e)).bright_red());
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+
+// Reports undefined names, unused tokens and shadowing without running.
+fn run_check(path: &str) -> io::Result<()> {
+    match runner::check_file(path) {
+        Ok(warnings) if warnings.is_empty() => println!("{}", format!("✅ {}: no warnings", path).bright_green()),
+        Ok(warnings) => {
+            for warning in &warnings {
+                println!("{}", format!("⚠️  {} {}", path, warning).bright_yellow());
+            }
+        }
+        Err(e) => {
+            println!("{}", format!("💥 Error: {}", format_error(&e)).bright_red());
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+fn run_js(path: &str, out: Option<&String>) -> io::Result<()> {
+    match runner::transpile_js_file(path, out.map(|o| o.as_str())) {
+        Ok(out) => println!("{}", create_crypto_border(&format!("📜 Transpiled {} to {}", path, out.display())).bright_green()),
+        Err(e) => {
+            println!("{}", format!("💥 Error: {}", format_error(&e)).bright_red());
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+
+fn run_sol(path: &str, out: Option<&String>) -> io::Result<()> {
+    match runner::transpile_sol_file(path, out.map(|o| o.as_str())) {
+        Ok(out) => println!("{}", create_crypto_border(&format!("📜 Transpiled {} to {}", path, out.display())).bright_green()),
+        Err(e) => {
+            println!("{}", format!("💥 Error: {}", format_error(&e)).bright_red());
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+
 // Cross-platform function to clear the terminal screen
 fn clear_screen() {
     // For Windows
@@ -170,7 +327,7 @@ fn print_welcome_message() {
     println!("   x burn y;                // Subtraction operation");
     println!("   x swap y;                // Division operation");
     println!("\n   mine greet(name) {{         // Define a function"); 
-    println!("     ping \"GM \" + name;");
+    println!("     ping \"GM {{name}}\";");
     println!("     return name;");
     println!("   }}");  // Double curly braces to escape
     
@@ -178,15 +335,20 @@ fn print_welcome_message() {
     println!("💡 Type 'crypto help' for more info\n");
 }
 
-fn execute_line(line: &str, interpreter: &mut interpreter::Interpreter) -> Result<Value, Rekt> {
+fn execute_line(
+    line: &str,
+    checker: &mut Checker,
+    interpreter: &mut interpreter::Interpreter,
+) -> Result<Value, Rekt> {
     if line.trim().is_empty() {
         return Ok(Value::Null);
     }
 
-    let mut lexer = lexer::Lexer::new(line);
+    let mut lexer = Lexer::new(line);
     let tokens = lexer.tokenize()?;
     let mut parser = Parser::new(tokens);
     let ast = parser.parse()?;
+    checker.check(&ast)?;
 
     let result = interpreter.interpret(ast)?;
     
@@ -263,11 +425,11 @@ fn print_examples() {
     println!("// Basic variable declaration");
     println!("token balance = 100;");
     println!("token name = \"Satoshi\";");
-    println!("");
+    println!();
     
     println!("// Constants");
     println!("block MAX_SUPPLY = 21000000;");
-    println!("");
+    println!();
     
     println!("// Simple operations");
     println!("token a = 5;");
@@ -276,13 +438,13 @@ fn print_examples() {
     println!("token diff = b burn a;  // Subtraction: 5");
     println!("token product = a yield b;  // Multiplication: 50");
     println!("token quotient = b swap a;  // Division: 2");
-    println!("");
+    println!();
     
     println!("// Function definition");
     println!("mine calculate_gas(amount, rate) {{");
     println!("    return amount yield rate;");
     println!("}}");
-    println!("");
+    println!();
     
     println!("// Conditional statements");
     println!("token eth = 10;");
@@ -291,7 +453,7 @@ fn print_examples() {
     println!("}} else {{");
     println!("    ping \"Low ETH balance.\";");
     println!("}}");
-    println!("");
+    println!();
     
     println!("// Loops");
     println!("token i = 0;");
@@ -362,4 +524,40 @@ fn format_error(err: &Rekt) -> String {
         Rekt::Runtime(msg) => format!("Runtime Error: {}", msg),
         Rekt::Type(msg) => format!("Type Error: {}", msg),
     }
-}
\ No newline at end of file
+}
+
+fn run_rs(path: &str, out: Option<&String>) -> io::Result<()> {
+    match runner::transpile_rs_file(path, out.map(|o| o.as_str())) {
+        Ok(out) => println!("{}", create_crypto_border(&format!("📜 Transpiled {} to {}", path, out.display())).bright_green()),
+        Err(e) => {
+            println!("{}", format!("💥 Error: {}", format_error(&e)).bright_red());
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+
+fn run_wasm(path: &str, out: Option<&String>) -> io::Result<()> {
+    match runner::compile_wasm_file(path, out.map(|o| o.as_str())) {
+        Ok(out) => println!("{}", create_crypto_border(&format!("📜 Compiled {} to {}", path, out.display())).bright_green()),
+        Err(e) => {
+            println!("{}", format!("💥 Error: {}", format_error(&e)).bright_red());
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+
+fn run_evm(path: &str, out: Option<&String>) -> io::Result<()> {
+    match runner::compile_evm_file(path, out.map(|o| o.as_str())) {
+        Ok(out) => println!("{}", create_crypto_border(&format!("📜 Compiled {} to {}", path, out.display())).bright_green()),
+        Err(e) => {
+            println!("{}", format!("💥 Error: {}", format_error(&e)).bright_red());
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
+
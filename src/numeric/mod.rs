@@ -0,0 +1,4 @@
+// Exact number types that sit alongside the float-backed tokens. They live
+// in the runtime crate so transpiled Rust programs share them.
+
+pub use gm_runtime::numeric::{Decimal, U256};
@@ -0,0 +1,284 @@
+// src/optimizer/mod.rs
+//
+// Rewrites a checked tree into one that does less work at runtime. It folds
+// operators whose operands are all literals, substitutes `block` constants
+// whose value is known, drops `sus` branches and `grind` loops whose
+// condition is a literal, and drops statements after a return, crash or
+// skip. Folding goes through the same arithmetic the engines use, and
+// anything that would fail (divide by zero, overflow) is left in place so
+// it still fails at runtime, on the same line.
+
+use std::collections::HashMap;
+use gm_runtime::{binary, unary};
+use crate::parser::ast::Ast;
+use crate::shared_types::Value;
+
+pub fn optimize(ast: Ast) -> Ast {
+    let mut declarations = HashMap::new();
+    count_declarations(&ast, &mut declarations);
+    let mut optimizer = Optimizer { declarations, known: HashMap::new() };
+    match ast {
+        Ast::Program(stmts) => Ast::Program(optimizer.block(stmts)),
+        other => optimizer.node(other),
+    }
+}
+
+struct Optimizer {
+    declarations: HashMap<String, usize>, // how many times each name is declared anywhere
+    known: HashMap<String, Value>,        // block constants in scope with a literal value
+}
+
+// What a statement turns into inside its list.
+enum Rewrite {
+    Keep(Ast),
+    Inline(Vec<Ast>), // a branch that always runs, spliced into the list
+}
+
+impl Optimizer {
+    // Optimizes a statement list. The value of a list is its last statement,
+    // so anything removed from the end leaves a null behind.
+    fn block(&mut self, stmts: Vec<Ast>) -> Vec<Ast> {
+        let mut out = Vec::new();
+        let mut declared = Vec::new();
+        for stmt in stmts {
+            let (line, stmt) = match stmt {
+                Ast::Located { line, statement } => (Some(line), *statement),
+                other => (None, other),
+            };
+            match self.statement(stmt, &mut declared) {
+                Rewrite::Keep(stmt) => out.push(match line {
+                    Some(line) => Ast::Located { line, statement: Box::new(stmt) },
+                    None => stmt,
+                }),
+                Rewrite::Inline(stmts) if stmts.is_empty() => out.push(Ast::Literal(Value::Null)),
+                Rewrite::Inline(stmts) => out.extend(stmts),
+            }
+            let ends = out
+                .last()
+                .is_some_and(|last| matches!(last.unlocated(), Ast::ReturnStmt(_) | Ast::Break | Ast::Continue));
+            if ends {
+                break;
+            }
+        }
+        for name in declared {
+            self.known.remove(&name);
+        }
+
+        // Nulls left by removed statements only matter at the end
+        let last = out.len().saturating_sub(1);
+        let mut index = 0;
+        out.retain(|stmt| {
+            index += 1;
+            index - 1 == last || *stmt != Ast::Literal(Value::Null)
+        });
+        out
+    }
+
+    fn statement(&mut self, stmt: Ast, declared: &mut Vec<String>) -> Rewrite {
+        match stmt {
+            Ast::VariableDecl { name, initializer, is_constant, doc } => {
+                let initializer = self.node(*initializer);
+                if let Ast::Literal(value) = &initializer {
+                    if is_constant && self.declarations.get(&name) == Some(&1) {
+                        self.known.insert(name.clone(), value.clone());
+                        declared.push(name.clone());
+                    }
+                }
+                Rewrite::Keep(Ast::VariableDecl { name, initializer: Box::new(initializer), is_constant, doc })
+            }
+            // If bodies share the surrounding scope, so a branch that always
+            // runs can stand in for the whole statement.
+            Ast::If { condition, then_branch, else_branch } => match self.node(*condition) {
+                Ast::Literal(Value::Signal(true)) => Rewrite::Inline(self.block(then_branch)),
+                Ast::Literal(Value::Signal(false)) => {
+                    Rewrite::Inline(else_branch.map(|stmts| self.block(stmts)).unwrap_or_default())
+                }
+                condition => Rewrite::Keep(Ast::If {
+                    condition: Box::new(condition),
+                    then_branch: self.block(then_branch),
+                    else_branch: else_branch.map(|stmts| self.block(stmts)),
+                }),
+            },
+            Ast::While { condition, body } => match self.node(*condition) {
+                Ast::Literal(Value::Signal(false)) => Rewrite::Inline(Vec::new()),
+                condition => Rewrite::Keep(Ast::While { condition: Box::new(condition), body: self.block(body) }),
+            },
+            other => Rewrite::Keep(self.node(other)),
+        }
+    }
+
+    fn node(&mut self, ast: Ast) -> Ast {
+        match ast {
+            Ast::Program(stmts) => Ast::Program(self.block(stmts)),
+            Ast::Block(stmts) => Ast::Block(self.block(stmts)),
+            Ast::Located { line, statement } => match self.statement(*statement, &mut Vec::new()) {
+                Rewrite::Keep(statement) => Ast::Located { line, statement: Box::new(statement) },
+                Rewrite::Inline(stmts) => Ast::Located { line, statement: Box::new(Ast::Block(stmts)) },
+            },
+
+            Ast::Variable(ref name) | Ast::ResolvedVariable { ref name, .. } => match self.known.get(name) {
+                Some(value) => Ast::Literal(value.clone()),
+                None => ast,
+            },
+            Ast::Grouping(inner) => match self.node(*inner) {
+                literal @ Ast::Literal(_) => literal,
+                inner => Ast::Grouping(Box::new(inner)),
+            },
+            Ast::Binary { left, operator, right } => {
+                let (left, right) = (self.node(*left), self.node(*right));
+                if let (Ast::Literal(l), Ast::Literal(r)) = (&left, &right) {
+                    if let Ok(value) = binary(l.clone(), operator.symbol(), r.clone()) {
+                        return Ast::Literal(value);
+                    }
+                }
+                Ast::Binary { left: Box::new(left), operator, right: Box::new(right) }
+            }
+            Ast::Unary { operator, operand } => {
+                let operand = self.node(*operand);
+                if let Ast::Literal(value) = &operand {
+                    if let Ok(value) = unary(operator.symbol(), value.clone()) {
+                        return Ast::Literal(value);
+                    }
+                }
+                Ast::Unary { operator, operand: Box::new(operand) }
+            }
+            Ast::Interpolation(parts) => {
+                let parts: Vec<Ast> = parts.into_iter().map(|part| self.node(part)).collect();
+                let mut text = String::new();
+                for part in &parts {
+                    match part {
+                        Ast::Literal(value) => text.push_str(&value.render()),
+                        _ => return Ast::Interpolation(parts),
+                    }
+                }
+                Ast::Literal(Value::Text(text))
+            }
+            Ast::Ternary { condition, then_branch, else_branch } => match self.node(*condition) {
+                Ast::Literal(Value::Signal(true)) => self.node(*then_branch),
+                Ast::Literal(Value::Signal(false)) => self.node(*else_branch),
+                condition => Ast::Ternary {
+                    condition: Box::new(condition),
+                    then_branch: Box::new(self.node(*then_branch)),
+                    else_branch: Box::new(self.node(*else_branch)),
+                },
+            },
+
+            Ast::FunctionDecl { name, params, return_type, body, doc } => {
+                Ast::FunctionDecl { name, params, return_type, body: self.block(body), doc }
+            }
+            Ast::Lambda { params, return_type, body } => Ast::Lambda { params, return_type, body: self.block(body) },
+            // A named callee stays a name, so a missing contract still falls
+            // back to the builtin of that name
+            Ast::Call { callee, arguments } => Ast::Call {
+                callee: match *callee {
+                    Ast::Variable(name) => Box::new(Ast::Variable(name)),
+                    other => Box::new(self.node(other)),
+                },
+                arguments: arguments.into_iter().map(|arg| self.node(arg)).collect(),
+            },
+            Ast::ForEach { key, variable, iterable, body } => Ast::ForEach {
+                key,
+                variable,
+                iterable: Box::new(self.node(*iterable)),
+                body: self.block(body),
+            },
+            Ast::ForRange { variable, start, end, step, body } => Ast::ForRange {
+                variable,
+                start: Box::new(self.node(*start)),
+                end: Box::new(self.node(*end)),
+                step: step.map(|step| Box::new(self.node(*step))),
+                body: self.block(body),
+            },
+            Ast::If { .. } | Ast::While { .. } | Ast::VariableDecl { .. } => {
+                match self.statement(ast, &mut Vec::new()) {
+                    Rewrite::Keep(stmt) => stmt,
+                    Rewrite::Inline(stmts) => Ast::Block(stmts),
+                }
+            }
+            Ast::ExpressionStmt(inner) => Ast::ExpressionStmt(Box::new(self.node(*inner))),
+            Ast::PrintStmt(inner) => Ast::PrintStmt(Box::new(self.node(*inner))),
+            Ast::ReturnStmt(inner) => Ast::ReturnStmt(inner.map(|inner| Box::new(self.node(*inner)))),
+
+            // Assignment targets keep their root name; it's being written, not read
+            Ast::Assign { name, value } => Ast::Assign { name, value: Box::new(self.node(*value)) },
+            Ast::SetIndex { object, index, value } => Ast::SetIndex {
+                object: Box::new(self.target(*object)),
+                index: Box::new(self.node(*index)),
+                value: Box::new(self.node(*value)),
+            },
+            Ast::SetField { object, name, value } => Ast::SetField {
+                object: Box::new(self.target(*object)),
+                name,
+                value: Box::new(self.node(*value)),
+            },
+            Ast::CompoundAssign { target, operator, value } => Ast::CompoundAssign {
+                target: Box::new(self.target(*target)),
+                operator,
+                value: Box::new(self.node(*value)),
+            },
+            Ast::Delete(target) => Ast::Delete(Box::new(self.target(*target))),
+
+            Ast::Index { object, index } => Ast::Index {
+                object: Box::new(self.node(*object)),
+                index: Box::new(self.node(*index)),
+            },
+            Ast::Field { object, name } => Ast::Field { object: Box::new(self.node(*object)), name },
+            Ast::StructLiteral { name, fields } => Ast::StructLiteral {
+                name,
+                fields: fields.into_iter().map(|(field, value)| (field, self.node(value))).collect(),
+            },
+            Ast::ArrayLiteral(items) => Ast::ArrayLiteral(items.into_iter().map(|item| self.node(item)).collect()),
+            Ast::MapLiteral(entries) => Ast::MapLiteral(
+                entries.into_iter().map(|(key, value)| (self.node(key), self.node(value))).collect(),
+            ),
+            Ast::Match { scrutinee, arms } => Ast::Match {
+                scrutinee: Box::new(self.node(*scrutinee)),
+                arms: arms.into_iter().map(|(pattern, body)| (pattern, self.node(body))).collect(),
+            },
+
+            leaf @ (Ast::Literal(_) | Ast::StructDecl { .. } | Ast::Break | Ast::Continue) => leaf,
+        }
+    }
+
+    fn target(&mut self, ast: Ast) -> Ast {
+        match ast {
+            Ast::Variable(name) => Ast::Variable(name),
+            Ast::Grouping(inner) => Ast::Grouping(Box::new(self.target(*inner))),
+            Ast::Index { object, index } => Ast::Index {
+                object: Box::new(self.target(*object)),
+                index: Box::new(self.node(*index)),
+            },
+            Ast::Field { object, name } => Ast::Field { object: Box::new(self.target(*object)), name },
+            other => self.node(other),
+        }
+    }
+}
+
+// A block constant is only substituted when its name is declared once in
+// the whole program, so no other token, parameter, loop variable or match
+// binding can be the one a later use refers to.
+fn count_declarations(ast: &Ast, counts: &mut HashMap<String, usize>) {
+    let mut names = Vec::new();
+    match ast {
+        Ast::VariableDecl { name, .. } => names.push(name.clone()),
+        Ast::FunctionDecl { name, params, .. } => {
+            names.push(name.clone());
+            names.extend(params.iter().map(|(param, _)| param.clone()));
+        }
+        Ast::Lambda { params, .. } => names.extend(params.iter().map(|(param, _)| param.clone())),
+        Ast::ForEach { key, variable, .. } => names.extend(key.iter().chain([variable]).cloned()),
+        Ast::ForRange { variable, .. } => names.push(variable.clone()),
+        Ast::Match { arms, .. } => {
+            for (pattern, _) in arms {
+                pattern.bindings(&mut names);
+            }
+        }
+        _ => {}
+    }
+    for name in names {
+        *counts.entry(name).or_insert(0) += 1;
+    }
+    for child in ast.children() {
+        count_declarations(child, counts);
+    }
+}
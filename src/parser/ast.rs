@@ -9,6 +9,7 @@ pub enum Ast {
         name: String,
         initializer: Box<Ast>,
         is_constant: bool, // Should this meme be locked? 👀
+        doc: Option<String>, // from `///` lines right above
     },
 
     // Functions (Epic Functions like boss fights)
@@ -17,14 +18,29 @@ pub enum Ast {
         params: Vec<(String, Type)>,
         return_type: Option<Type>,
         body: Vec<Ast>,
+        doc: Option<String>,
+    },
+
+    // Struct declarations (the shape of a transaction)
+    StructDecl {
+        name: String,
+        fields: Vec<(String, Option<Type>)>,
+        doc: Option<String>,
     },
 
     // Function Calls (let's ping that function)
     Call {
-        callee: String, // The meme machine
+        callee: Box<Ast>, // The meme machine (any expression that yields a contract)
         arguments: Vec<Ast>,
     },
 
+    // Anonymous functions (contracts mined on the spot)
+    Lambda {
+        params: Vec<(String, Type)>,
+        return_type: Option<Type>,
+        body: Vec<Ast>,
+    },
+
     // Conditional checks (Are we sus?)
     If {
         condition: Box<Ast>, // Condition to check
@@ -32,12 +48,40 @@ pub enum Ast {
         else_branch: Option<Vec<Ast>>, // Action if it’s sus
     },
 
+    // Conditional expression (sus cond ? a : b), only the chosen side runs
+    Ternary {
+        condition: Box<Ast>,
+        then_branch: Box<Ast>,
+        else_branch: Box<Ast>,
+    },
+
     // While loop (Grind till it’s done)
     While {
         condition: Box<Ast>, // Loop condition
         body: Vec<Ast>, // Keep grinding
     },
 
+    // For-each loop (farming every item, or every key of a mapping)
+    ForEach {
+        key: Option<String>, // index or key when two names are given
+        variable: String,
+        iterable: Box<Ast>,
+        body: Vec<Ast>,
+    },
+
+    // Counted loop over start..end, end exclusive
+    ForRange {
+        variable: String,
+        start: Box<Ast>,
+        end: Box<Ast>,
+        step: Option<Box<Ast>>,
+        body: Vec<Ast>,
+    },
+
+    // Loop control (crash out or skip ahead)
+    Break,
+    Continue,
+
     // Code blocks (meme sequences)
     Block(Vec<Ast>),
 
@@ -69,12 +113,196 @@ pub enum Ast {
         value: Box<Ast>,
     },
 
+    // Indexed assignment (topping up a mapping slot)
+    SetIndex {
+        object: Box<Ast>,
+        index: Box<Ast>,
+        value: Box<Ast>,
+    },
+
+    // Field assignment (updating a record in place)
+    SetField {
+        object: Box<Ast>,
+        name: String,
+        value: Box<Ast>,
+    },
+
+    // Compound assignment (`x stake= 1`, `m[k] -= 2`, `x++`)
+    CompoundAssign {
+        target: Box<Ast>, // a variable, index or field
+        operator: BinaryOp,
+        value: Box<Ast>,
+    },
+
+    // Delete (clearing a mapping slot back to zero)
+    Delete(Box<Ast>),
+
     // Variables (with epic memes)
     Variable(String),
 
+    // A variable the resolver has bound: its declaration is `depth` scopes
+    // out, in the `slot`-th token minted there
+    ResolvedVariable {
+        name: String,
+        depth: usize,
+        slot: usize,
+    },
+
+    // Indexing (peeking into a mapping or array)
+    Index {
+        object: Box<Ast>,
+        index: Box<Ast>,
+    },
+
+    // Field access (reading a record)
+    Field {
+        object: Box<Ast>,
+        name: String,
+    },
+
+    // Struct construction (minting a record)
+    StructLiteral {
+        name: String,
+        fields: Vec<(String, Ast)>,
+    },
+
+    // Array literals (a bag of tokens)
+    ArrayLiteral(Vec<Ast>),
+
+    // Mapping literals (the on-chain ledger)
+    MapLiteral(Vec<(Ast, Ast)>),
+
     // Literals (simple and straight to the point)
     Literal(Value), // Literal values like numbers or strings
 
+    // Interpolated strings ("gm {name}"), literal text and expressions in order
+    Interpolation(Vec<Ast>),
+
     // Grouping (wrapping memes in parentheses)
     Grouping(Box<Ast>), // Group those sus operations
+
+    // Vibecheck (multi-way match that yields the chosen arm's value)
+    Match {
+        scrutinee: Box<Ast>,
+        arms: Vec<(Pattern, Ast)>,
+    },
+
+    // A statement and the source line it starts on (where the receipt was signed)
+    Located {
+        line: usize,
+        statement: Box<Ast>,
+    },
+}
+
+impl Ast {
+    // The statement itself, without the line it came from
+    pub fn unlocated(&self) -> &Ast {
+        match self {
+            Ast::Located { statement, .. } => statement.unlocated(),
+            other => other,
+        }
+    }
+
+    // Every node directly under this one, in source order
+    pub fn children(&self) -> Vec<&Ast> {
+        match self {
+            Ast::Program(stmts) | Ast::Block(stmts) | Ast::ArrayLiteral(stmts) | Ast::Interpolation(stmts) => {
+                stmts.iter().collect()
+            }
+            Ast::FunctionDecl { body, .. } | Ast::Lambda { body, .. } => body.iter().collect(),
+            Ast::VariableDecl { initializer: inner, .. }
+            | Ast::ExpressionStmt(inner)
+            | Ast::PrintStmt(inner)
+            | Ast::ReturnStmt(Some(inner))
+            | Ast::Unary { operand: inner, .. }
+            | Ast::Assign { value: inner, .. }
+            | Ast::Delete(inner)
+            | Ast::Field { object: inner, .. }
+            | Ast::Grouping(inner)
+            | Ast::Located { statement: inner, .. } => vec![inner],
+            Ast::Call { callee, arguments } => std::iter::once(&**callee).chain(arguments).collect(),
+            Ast::If { condition, then_branch, else_branch } => std::iter::once(&**condition)
+                .chain(then_branch)
+                .chain(else_branch.iter().flatten())
+                .collect(),
+            Ast::Ternary { condition, then_branch, else_branch } => vec![condition, then_branch, else_branch],
+            Ast::While { condition, body } => std::iter::once(&**condition).chain(body).collect(),
+            Ast::ForEach { iterable, body, .. } => std::iter::once(&**iterable).chain(body).collect(),
+            Ast::ForRange { start, end, step, body, .. } => [&**start, &**end]
+                .into_iter()
+                .chain(step.as_deref())
+                .chain(body)
+                .collect(),
+            Ast::Binary { left, right, .. } => vec![left, right],
+            Ast::SetIndex { object, index, value } => vec![object, index, value],
+            Ast::SetField { object, value, .. } => vec![object, value],
+            Ast::CompoundAssign { target, value, .. } => vec![target, value],
+            Ast::Index { object, index } => vec![object, index],
+            Ast::StructLiteral { fields, .. } => fields.iter().map(|(_, value)| value).collect(),
+            Ast::MapLiteral(entries) => entries.iter().flat_map(|(key, value)| [key, value]).collect(),
+            Ast::Match { scrutinee, arms } => std::iter::once(&**scrutinee)
+                .chain(arms.iter().map(|(_, body)| body))
+                .collect(),
+            Ast::StructDecl { .. }
+            | Ast::ReturnStmt(None)
+            | Ast::Break
+            | Ast::Continue
+            | Ast::Variable(_)
+            | Ast::ResolvedVariable { .. }
+            | Ast::Literal(_) => Vec::new(),
+        }
+    }
+    // The same nodes as `children`, for passes that rewrite in place
+    pub fn children_mut(&mut self) -> Vec<&mut Ast> {
+        match self {
+            Ast::Program(stmts) | Ast::Block(stmts) | Ast::ArrayLiteral(stmts) | Ast::Interpolation(stmts) => {
+                stmts.iter_mut().collect()
+            }
+            Ast::FunctionDecl { body, .. } | Ast::Lambda { body, .. } => body.iter_mut().collect(),
+            Ast::VariableDecl { initializer: inner, .. }
+            | Ast::ExpressionStmt(inner)
+            | Ast::PrintStmt(inner)
+            | Ast::ReturnStmt(Some(inner))
+            | Ast::Unary { operand: inner, .. }
+            | Ast::Assign { value: inner, .. }
+            | Ast::Delete(inner)
+            | Ast::Field { object: inner, .. }
+            | Ast::Grouping(inner)
+            | Ast::Located { statement: inner, .. } => vec![inner],
+            Ast::Call { callee, arguments } => std::iter::once(&mut **callee).chain(arguments).collect(),
+            Ast::If { condition, then_branch, else_branch } => std::iter::once(&mut **condition)
+                .chain(then_branch)
+                .chain(else_branch.iter_mut().flatten())
+                .collect(),
+            Ast::Ternary { condition, then_branch, else_branch } => vec![condition, then_branch, else_branch],
+            Ast::While { condition, body } => std::iter::once(&mut **condition).chain(body).collect(),
+            Ast::ForEach { iterable, body, .. } => std::iter::once(&mut **iterable).chain(body).collect(),
+            Ast::ForRange { start, end, step, body, .. } => [&mut **start, &mut **end]
+                .into_iter()
+                .chain(step.as_deref_mut())
+                .chain(body)
+                .collect(),
+            Ast::Binary { left, right, .. } => vec![left, right],
+            Ast::SetIndex { object, index, value } => vec![object, index, value],
+            Ast::SetField { object, value, .. } => vec![object, value],
+            Ast::CompoundAssign { target, value, .. } => vec![target, value],
+            Ast::Index { object, index } => vec![object, index],
+            Ast::StructLiteral { fields, .. } => fields.iter_mut().map(|(_, value)| value).collect(),
+            Ast::MapLiteral(entries) => entries.iter_mut().flat_map(|(key, value)| [key, value]).collect(),
+            Ast::Match { scrutinee, arms } => std::iter::once(&mut **scrutinee)
+                .chain(arms.iter_mut().map(|(_, body)| body))
+                .collect(),
+            Ast::StructDecl { .. }
+            | Ast::ReturnStmt(None)
+            | Ast::Break
+            | Ast::Continue
+            | Ast::Variable(_)
+            | Ast::ResolvedVariable { .. }
+            | Ast::Literal(_) => Vec::new(),
+        }
+    }
 }
+
+// Patterns a vibecheck arm can test a value against, shared with
+// transpiled Rust
+pub type Pattern = gm_runtime::patterns::Pattern<Value, String>;
@@ -0,0 +1,813 @@
+use serde_json::{json, Map, Value as Json};
+use crate::error::Rekt;
+use crate::lexer::{Lexer, Token};
+use crate::numeric::{Decimal, U256};
+use crate::shared_types::{BinaryOp, Type, Value};
+use super::ast::{Ast, Pattern};
+
+// The JSON form of a syntax tree, for tools that read GM without parsing
+// it and for trees built outside the parser. A document is
+//
+//   { "format": "gm-ast", "version": 1, "program": <node> }
+//
+// and every node is an object whose "kind" is the snake_case name of its
+// Ast variant, with the variant's fields under their own names. A
+// statement that knows where it came from carries "span": { "line": n }.
+// Spans have no column, since the lexer only keeps track of lines.
+// Values, types and operators are tagged the same way: a literal is
+// { "type": "token", "value": "42" }, a type is a name like "uint" or
+// { "struct": "Tx" }, and an operator is its variant's snake_case name.
+//
+// VERSION is bumped whenever a reader of the old form would misread the
+// new one. Adding an optional field doesn't count; readers ignore fields
+// they don't know.
+//
+// A tree is the parser's output, so a loaded one must be something the
+// parser could have written: no resolved variables, and none of the
+// shapes the parser refuses, like a `crash` outside a loop.
+pub const FORMAT: &str = "gm-ast";
+pub const VERSION: u64 = 1;
+
+pub fn to_json(ast: &Ast) -> Result<String, Rekt> {
+    let document = json!({ "format": FORMAT, "version": VERSION, "program": node(ast)? });
+    let mut text = serde_json::to_string_pretty(&document)
+        .map_err(|e| Rekt::Runtime(format!("Failed to write the tree as JSON: {}", e)))?;
+    text.push('\n');
+    Ok(text)
+}
+
+// Checks the format and version before reading the tree, and says where
+// anything it can't read is, as a path from the document's root.
+pub fn from_json(text: &str) -> Result<Ast, Rekt> {
+    let document: Json = serde_json::from_str(text)
+        .map_err(|e| Rekt::Parser(format!("Invalid AST JSON: {}", e)))?;
+    let root = Object::of(&document, "$".to_string())?;
+    if root.get("format")?.as_str() != Some(FORMAT) {
+        return Err(invalid("$.format", &format!("expected \"{}\"", FORMAT)));
+    }
+    match root.get("version")?.as_u64() {
+        Some(VERSION) => {}
+        Some(version) => {
+            return Err(Rekt::Parser(format!(
+                "This tree was written in AST format version {}, but this build reads version {}. Dump it again 🔁",
+                version, VERSION
+            )));
+        }
+        None => return Err(invalid("$.version", "expected a whole number")),
+    }
+    let program = root.node("program")?;
+    structure(&program, false)?;
+    Ok(program)
+}
+
+fn invalid(at: &str, why: &str) -> Rekt {
+    Rekt::Parser(format!("Invalid AST JSON at {}: {}", at, why))
+}
+
+// ------------------------------------------------------------ writing
+
+fn node(ast: &Ast) -> Result<Json, Rekt> {
+    let mut fields = Map::new();
+    let kind = match ast {
+        // The line goes on the statement itself, unless that's already
+        // located and there'd be two
+        Ast::Located { line, statement } => {
+            let inner = node(statement)?;
+            let span = json!({ "line": line });
+            return Ok(match inner {
+                Json::Object(mut rest) if !rest.contains_key("span") => {
+                    let mut object = Map::new();
+                    object.extend(rest.shift_remove("kind").map(|kind| ("kind".to_string(), kind)));
+                    object.insert("span".to_string(), span);
+                    object.extend(rest);
+                    Json::Object(object)
+                }
+                inner => json!({ "kind": "located", "span": span, "statement": inner }),
+            });
+        }
+        Ast::Program(stmts) => {
+            fields.insert("body".into(), nodes(stmts)?);
+            "program"
+        }
+        Ast::VariableDecl { name, initializer, is_constant, doc } => {
+            fields.insert("name".into(), json!(name));
+            fields.insert("initializer".into(), node(initializer)?);
+            fields.insert("is_constant".into(), json!(is_constant));
+            fields.insert("doc".into(), json!(doc));
+            "variable_decl"
+        }
+        Ast::FunctionDecl { name, params, return_type, body, doc } => {
+            fields.insert("name".into(), json!(name));
+            fields.insert("params".into(), params_json(params));
+            fields.insert("return_type".into(), optional_type(return_type));
+            fields.insert("body".into(), nodes(body)?);
+            fields.insert("doc".into(), json!(doc));
+            "function_decl"
+        }
+        Ast::StructDecl { name, fields: declared, doc } => {
+            let declared: Vec<Json> = declared
+                .iter()
+                .map(|(field, known)| json!({ "name": field, "type": optional_type(known) }))
+                .collect();
+            fields.insert("name".into(), json!(name));
+            fields.insert("fields".into(), Json::Array(declared));
+            fields.insert("doc".into(), json!(doc));
+            "struct_decl"
+        }
+        Ast::Call { callee, arguments } => {
+            fields.insert("callee".into(), node(callee)?);
+            fields.insert("arguments".into(), nodes(arguments)?);
+            "call"
+        }
+        Ast::Lambda { params, return_type, body } => {
+            fields.insert("params".into(), params_json(params));
+            fields.insert("return_type".into(), optional_type(return_type));
+            fields.insert("body".into(), nodes(body)?);
+            "lambda"
+        }
+        Ast::If { condition, then_branch, else_branch } => {
+            fields.insert("condition".into(), node(condition)?);
+            fields.insert("then_branch".into(), nodes(then_branch)?);
+            let else_branch = match else_branch {
+                Some(stmts) => nodes(stmts)?,
+                None => Json::Null,
+            };
+            fields.insert("else_branch".into(), else_branch);
+            "if"
+        }
+        Ast::Ternary { condition, then_branch, else_branch } => {
+            fields.insert("condition".into(), node(condition)?);
+            fields.insert("then_branch".into(), node(then_branch)?);
+            fields.insert("else_branch".into(), node(else_branch)?);
+            "ternary"
+        }
+        Ast::While { condition, body } => {
+            fields.insert("condition".into(), node(condition)?);
+            fields.insert("body".into(), nodes(body)?);
+            "while"
+        }
+        Ast::ForEach { key, variable, iterable, body } => {
+            fields.insert("key".into(), json!(key));
+            fields.insert("variable".into(), json!(variable));
+            fields.insert("iterable".into(), node(iterable)?);
+            fields.insert("body".into(), nodes(body)?);
+            "for_each"
+        }
+        Ast::ForRange { variable, start, end, step, body } => {
+            fields.insert("variable".into(), json!(variable));
+            fields.insert("start".into(), node(start)?);
+            fields.insert("end".into(), node(end)?);
+            fields.insert("step".into(), optional_node(step.as_deref())?);
+            fields.insert("body".into(), nodes(body)?);
+            "for_range"
+        }
+        Ast::Break => "break",
+        Ast::Continue => "continue",
+        Ast::Block(stmts) => {
+            fields.insert("body".into(), nodes(stmts)?);
+            "block"
+        }
+        Ast::ExpressionStmt(inner) => {
+            fields.insert("expression".into(), node(inner)?);
+            "expression_stmt"
+        }
+        Ast::PrintStmt(inner) => {
+            fields.insert("value".into(), node(inner)?);
+            "print_stmt"
+        }
+        Ast::ReturnStmt(inner) => {
+            fields.insert("value".into(), optional_node(inner.as_deref())?);
+            "return_stmt"
+        }
+        Ast::Binary { left, operator, right } => {
+            fields.insert("left".into(), node(left)?);
+            fields.insert("operator".into(), json!(operator_name(operator)));
+            fields.insert("right".into(), node(right)?);
+            "binary"
+        }
+        Ast::Unary { operator, operand } => {
+            fields.insert("operator".into(), json!(operator_name(operator)));
+            fields.insert("operand".into(), node(operand)?);
+            "unary"
+        }
+        Ast::Assign { name, value } => {
+            fields.insert("name".into(), json!(name));
+            fields.insert("value".into(), node(value)?);
+            "assign"
+        }
+        Ast::SetIndex { object, index, value } => {
+            fields.insert("object".into(), node(object)?);
+            fields.insert("index".into(), node(index)?);
+            fields.insert("value".into(), node(value)?);
+            "set_index"
+        }
+        Ast::SetField { object, name, value } => {
+            fields.insert("object".into(), node(object)?);
+            fields.insert("name".into(), json!(name));
+            fields.insert("value".into(), node(value)?);
+            "set_field"
+        }
+        Ast::CompoundAssign { target, operator, value } => {
+            fields.insert("target".into(), node(target)?);
+            fields.insert("operator".into(), json!(operator_name(operator)));
+            fields.insert("value".into(), node(value)?);
+            "compound_assign"
+        }
+        Ast::Delete(target) => {
+            fields.insert("target".into(), node(target)?);
+            "delete"
+        }
+        Ast::Variable(name) => {
+            fields.insert("name".into(), json!(name));
+            "variable"
+        }
+        Ast::ResolvedVariable { name, .. } => {
+            return Err(Rekt::Runtime(format!(
+                "Can't write '{}' as JSON once it's resolved; write the tree before the resolver runs",
+                name
+            )));
+        }
+        Ast::Index { object, index } => {
+            fields.insert("object".into(), node(object)?);
+            fields.insert("index".into(), node(index)?);
+            "index"
+        }
+        Ast::Field { object, name } => {
+            fields.insert("object".into(), node(object)?);
+            fields.insert("name".into(), json!(name));
+            "field"
+        }
+        Ast::StructLiteral { name, fields: given } => {
+            let given = given
+                .iter()
+                .map(|(field, value)| Ok(json!({ "name": field, "value": node(value)? })))
+                .collect::<Result<Vec<_>, Rekt>>()?;
+            fields.insert("name".into(), json!(name));
+            fields.insert("fields".into(), Json::Array(given));
+            "struct_literal"
+        }
+        Ast::ArrayLiteral(items) => {
+            fields.insert("items".into(), nodes(items)?);
+            "array_literal"
+        }
+        Ast::MapLiteral(entries) => {
+            let entries = entries
+                .iter()
+                .map(|(key, value)| Ok(json!({ "key": node(key)?, "value": node(value)? })))
+                .collect::<Result<Vec<_>, Rekt>>()?;
+            fields.insert("entries".into(), Json::Array(entries));
+            "map_literal"
+        }
+        Ast::Literal(literal) => {
+            fields.insert("value".into(), value(literal)?);
+            "literal"
+        }
+        Ast::Interpolation(parts) => {
+            fields.insert("parts".into(), nodes(parts)?);
+            "interpolation"
+        }
+        Ast::Grouping(inner) => {
+            fields.insert("expression".into(), node(inner)?);
+            "grouping"
+        }
+        Ast::Match { scrutinee, arms } => {
+            let arms = arms
+                .iter()
+                .map(|(arm, body)| Ok(json!({ "pattern": pattern(arm)?, "body": node(body)? })))
+                .collect::<Result<Vec<_>, Rekt>>()?;
+            fields.insert("scrutinee".into(), node(scrutinee)?);
+            fields.insert("arms".into(), Json::Array(arms));
+            "match"
+        }
+    };
+    let mut object = Map::new();
+    object.insert("kind".into(), json!(kind));
+    object.extend(fields);
+    Ok(Json::Object(object))
+}
+
+fn nodes(asts: &[Ast]) -> Result<Json, Rekt> {
+    Ok(Json::Array(asts.iter().map(node).collect::<Result<_, _>>()?))
+}
+
+fn optional_node(ast: Option<&Ast>) -> Result<Json, Rekt> {
+    ast.map(node).unwrap_or(Ok(Json::Null))
+}
+
+fn params_json(params: &[(String, Type)]) -> Json {
+    let params = params.iter().map(|(name, t)| json!({ "name": name, "type": type_json(t) })).collect();
+    Json::Array(params)
+}
+
+// Only values a literal can hold have a JSON form; the rest only exist
+// while a script runs.
+fn value(literal: &Value) -> Result<Json, Rekt> {
+    Ok(match literal {
+        Value::Null => json!({ "type": "null" }),
+        Value::Number(n) if n.is_finite() => json!({ "type": "number", "value": n }),
+        Value::Number(n) => json!({ "type": "number", "value": n.to_string() }),
+        Value::Text(s) => json!({ "type": "text", "value": s }),
+        Value::Boolean(b) => json!({ "type": "boolean", "value": b }),
+        Value::Token(t) => json!({ "type": "token", "value": t }),
+        Value::Signal(b) => json!({ "type": "signal", "value": b }),
+        Value::Int(n) => json!({ "type": "uint", "value": n.to_string() }),
+        Value::Decimal(d) => json!({ "type": "decimal", "units": d.units().to_string(), "scale": d.scale() }),
+        Value::Array(items) => {
+            let items = items.iter().map(value).collect::<Result<Vec<_>, Rekt>>()?;
+            json!({ "type": "array", "items": items })
+        }
+        other => return Err(Rekt::Runtime(format!("A {} can't be written as a JSON literal", other))),
+    })
+}
+
+fn type_json(t: &Type) -> Json {
+    match t {
+        Type::Struct(name) => json!({ "struct": name }),
+        simple => json!(type_name(simple)),
+    }
+}
+
+fn optional_type(known: &Option<Type>) -> Json {
+    known.as_ref().map(type_json).unwrap_or(Json::Null)
+}
+
+const TYPES: [(&str, Type); 10] = [
+    ("number", Type::Number),
+    ("text", Type::Text),
+    ("boolean", Type::Boolean),
+    ("function", Type::Function),
+    ("token", Type::Token),
+    ("signal", Type::Signal),
+    ("uint", Type::Uint),
+    ("decimal", Type::Decimal),
+    ("array", Type::Array),
+    ("map", Type::Map),
+];
+
+fn type_name(t: &Type) -> &'static str {
+    TYPES.iter().find(|(_, known)| known == t).map(|(name, _)| *name).unwrap_or("struct")
+}
+
+const OPERATORS: [(&str, BinaryOp); 21] = [
+    ("add", BinaryOp::Add),
+    ("subtract", BinaryOp::Subtract),
+    ("multiply", BinaryOp::Multiply),
+    ("divide", BinaryOp::Divide),
+    ("equal", BinaryOp::Equal),
+    ("not_equal", BinaryOp::NotEqual),
+    ("less", BinaryOp::Less),
+    ("greater", BinaryOp::Greater),
+    ("less_equal", BinaryOp::LessEqual),
+    ("greater_equal", BinaryOp::GreaterEqual),
+    ("and", BinaryOp::And),
+    ("or", BinaryOp::Or),
+    ("not", BinaryOp::Not),
+    ("modulo", BinaryOp::Modulo),
+    ("int_divide", BinaryOp::IntDivide),
+    ("bit_and", BinaryOp::BitAnd),
+    ("bit_or", BinaryOp::BitOr),
+    ("bit_xor", BinaryOp::BitXor),
+    ("shift_left", BinaryOp::ShiftLeft),
+    ("shift_right", BinaryOp::ShiftRight),
+    ("bit_not", BinaryOp::BitNot),
+];
+
+fn operator_name(operator: &BinaryOp) -> &'static str {
+    OPERATORS.iter().find(|(_, known)| known == operator).map(|(name, _)| *name).unwrap_or_default()
+}
+
+fn pattern(arm: &Pattern) -> Result<Json, Rekt> {
+    Ok(match arm {
+        Pattern::Wildcard => json!({ "kind": "wildcard" }),
+        Pattern::Literal(literal) => json!({ "kind": "literal", "value": value(literal)? }),
+        Pattern::Range(start, end) => json!({ "kind": "range", "start": value(start)?, "end": value(end)? }),
+        Pattern::Binding(name) => json!({ "kind": "binding", "name": name }),
+        Pattern::Struct { name, fields } => {
+            let fields = fields
+                .iter()
+                .map(|(field, sub)| Ok(json!({ "name": field, "pattern": pattern(sub)? })))
+                .collect::<Result<Vec<_>, Rekt>>()?;
+            json!({ "kind": "struct", "name": name, "fields": fields })
+        }
+        Pattern::Array { items, rest } => {
+            let items = items.iter().map(pattern).collect::<Result<Vec<_>, Rekt>>()?;
+            json!({ "kind": "array", "items": items, "rest": rest })
+        }
+        Pattern::Or(alternatives) => {
+            let alternatives = alternatives.iter().map(pattern).collect::<Result<Vec<_>, Rekt>>()?;
+            json!({ "kind": "or", "alternatives": alternatives })
+        }
+    })
+}
+
+// ------------------------------------------------------------ reading
+
+// A JSON object being read, and where it is in the document.
+struct Object<'a> {
+    fields: &'a Map<String, Json>,
+    at: String,
+}
+
+impl<'a> Object<'a> {
+    fn of(json: &'a Json, at: String) -> Result<Self, Rekt> {
+        match json.as_object() {
+            Some(fields) => Ok(Object { fields, at }),
+            None => Err(invalid(&at, "expected an object")),
+        }
+    }
+
+    fn path(&self, name: &str) -> String {
+        format!("{}.{}", self.at, name)
+    }
+
+    fn get(&self, name: &str) -> Result<&'a Json, Rekt> {
+        self.fields.get(name).ok_or_else(|| invalid(&self.at, &format!("missing \"{}\"", name)))
+    }
+
+    // A field that may be missing or null
+    fn maybe(&self, name: &str) -> Option<&'a Json> {
+        self.fields.get(name).filter(|json| !json.is_null())
+    }
+
+    fn object(&self, name: &str) -> Result<Object<'a>, Rekt> {
+        Object::of(self.get(name)?, self.path(name))
+    }
+
+    fn text(&self, name: &str) -> Result<String, Rekt> {
+        string(self.get(name)?, &self.path(name))
+    }
+
+    fn optional_text(&self, name: &str) -> Result<Option<String>, Rekt> {
+        self.maybe(name).map(|json| string(json, &self.path(name))).transpose()
+    }
+
+    fn flag(&self, name: &str) -> Result<bool, Rekt> {
+        self.get(name)?.as_bool().ok_or_else(|| invalid(&self.path(name), "expected true or false"))
+    }
+
+    fn whole(&self, name: &str) -> Result<usize, Rekt> {
+        let n = self.get(name)?.as_u64().ok_or_else(|| invalid(&self.path(name), "expected a whole number"))?;
+        Ok(n as usize)
+    }
+
+    // Each item of an array field, read by `f` with its own path
+    fn list<T>(&self, name: &str, mut f: impl FnMut(&'a Json, String) -> Result<T, Rekt>) -> Result<Vec<T>, Rekt> {
+        let at = self.path(name);
+        let items = self.get(name)?.as_array().ok_or_else(|| invalid(&at, "expected an array"))?;
+        items.iter().enumerate().map(|(i, item)| f(item, format!("{}[{}]", at, i))).collect()
+    }
+
+    fn node(&self, name: &str) -> Result<Ast, Rekt> {
+        read_node(self.get(name)?, self.path(name))
+    }
+
+    fn boxed(&self, name: &str) -> Result<Box<Ast>, Rekt> {
+        self.node(name).map(Box::new)
+    }
+
+    fn optional_node(&self, name: &str) -> Result<Option<Box<Ast>>, Rekt> {
+        self.maybe(name).map(|json| read_node(json, self.path(name)).map(Box::new)).transpose()
+    }
+
+    fn nodes(&self, name: &str) -> Result<Vec<Ast>, Rekt> {
+        self.list(name, read_node)
+    }
+
+    fn operator(&self, name: &str) -> Result<BinaryOp, Rekt> {
+        let given = self.text(name)?;
+        OPERATORS
+            .iter()
+            .find(|(known, _)| *known == given)
+            .map(|(_, operator)| *operator)
+            .ok_or_else(|| invalid(&self.path(name), &format!("unknown operator \"{}\"", given)))
+    }
+
+    fn params(&self, name: &str) -> Result<Vec<(String, Type)>, Rekt> {
+        self.list(name, |json, at| {
+            let param = Object::of(json, at)?;
+            Ok((param.text("name")?, read_type(param.get("type")?, param.path("type"))?))
+        })
+    }
+
+    fn optional_type(&self, name: &str) -> Result<Option<Type>, Rekt> {
+        self.maybe(name).map(|json| read_type(json, self.path(name))).transpose()
+    }
+}
+
+fn string(json: &Json, at: &str) -> Result<String, Rekt> {
+    json.as_str().map(str::to_string).ok_or_else(|| invalid(at, "expected a string"))
+}
+
+fn read_node(json: &Json, at: String) -> Result<Ast, Rekt> {
+    let o = Object::of(json, at)?;
+    let kind = o.text("kind")?;
+    let ast = match kind.as_str() {
+        "located" => *o.boxed("statement")?,
+        "program" => Ast::Program(o.nodes("body")?),
+        "variable_decl" => Ast::VariableDecl {
+            name: o.text("name")?,
+            initializer: o.boxed("initializer")?,
+            is_constant: o.flag("is_constant")?,
+            doc: o.optional_text("doc")?,
+        },
+        "function_decl" => Ast::FunctionDecl {
+            name: o.text("name")?,
+            params: o.params("params")?,
+            return_type: o.optional_type("return_type")?,
+            body: o.nodes("body")?,
+            doc: o.optional_text("doc")?,
+        },
+        "struct_decl" => Ast::StructDecl {
+            name: o.text("name")?,
+            fields: o.list("fields", |json, at| {
+                let field = Object::of(json, at)?;
+                Ok((field.text("name")?, field.optional_type("type")?))
+            })?,
+            doc: o.optional_text("doc")?,
+        },
+        "call" => Ast::Call { callee: o.boxed("callee")?, arguments: o.nodes("arguments")? },
+        "lambda" => Ast::Lambda {
+            params: o.params("params")?,
+            return_type: o.optional_type("return_type")?,
+            body: o.nodes("body")?,
+        },
+        "if" => Ast::If {
+            condition: o.boxed("condition")?,
+            then_branch: o.nodes("then_branch")?,
+            else_branch: match o.maybe("else_branch") {
+                Some(_) => Some(o.nodes("else_branch")?),
+                None => None,
+            },
+        },
+        "ternary" => Ast::Ternary {
+            condition: o.boxed("condition")?,
+            then_branch: o.boxed("then_branch")?,
+            else_branch: o.boxed("else_branch")?,
+        },
+        "while" => Ast::While { condition: o.boxed("condition")?, body: o.nodes("body")? },
+        "for_each" => Ast::ForEach {
+            key: o.optional_text("key")?,
+            variable: o.text("variable")?,
+            iterable: o.boxed("iterable")?,
+            body: o.nodes("body")?,
+        },
+        "for_range" => Ast::ForRange {
+            variable: o.text("variable")?,
+            start: o.boxed("start")?,
+            end: o.boxed("end")?,
+            step: o.optional_node("step")?,
+            body: o.nodes("body")?,
+        },
+        "break" => Ast::Break,
+        "continue" => Ast::Continue,
+        "block" => Ast::Block(o.nodes("body")?),
+        "expression_stmt" => Ast::ExpressionStmt(o.boxed("expression")?),
+        "print_stmt" => Ast::PrintStmt(o.boxed("value")?),
+        "return_stmt" => Ast::ReturnStmt(o.optional_node("value")?),
+        "binary" => Ast::Binary {
+            left: o.boxed("left")?,
+            operator: o.operator("operator")?,
+            right: o.boxed("right")?,
+        },
+        "unary" => Ast::Unary { operator: o.operator("operator")?, operand: o.boxed("operand")? },
+        "assign" => Ast::Assign { name: o.text("name")?, value: o.boxed("value")? },
+        "set_index" => Ast::SetIndex { object: o.boxed("object")?, index: o.boxed("index")?, value: o.boxed("value")? },
+        "set_field" => Ast::SetField { object: o.boxed("object")?, name: o.text("name")?, value: o.boxed("value")? },
+        "compound_assign" => Ast::CompoundAssign {
+            target: o.boxed("target")?,
+            operator: o.operator("operator")?,
+            value: o.boxed("value")?,
+        },
+        "delete" => Ast::Delete(o.boxed("target")?),
+        "variable" => Ast::Variable(o.text("name")?),
+        "resolved_variable" => {
+            return Err(invalid(&o.path("kind"), "resolved variables are the resolver's, so write a \"variable\""));
+        }
+        "index" => Ast::Index { object: o.boxed("object")?, index: o.boxed("index")? },
+        "field" => Ast::Field { object: o.boxed("object")?, name: o.text("name")? },
+        "struct_literal" => Ast::StructLiteral {
+            name: o.text("name")?,
+            fields: o.list("fields", |json, at| {
+                let field = Object::of(json, at)?;
+                Ok((field.text("name")?, field.node("value")?))
+            })?,
+        },
+        "array_literal" => Ast::ArrayLiteral(o.nodes("items")?),
+        "map_literal" => Ast::MapLiteral(o.list("entries", |json, at| {
+            let entry = Object::of(json, at)?;
+            Ok((entry.node("key")?, entry.node("value")?))
+        })?),
+        "literal" => Ast::Literal(read_value(o.get("value")?, o.path("value"))?),
+        "interpolation" => Ast::Interpolation(o.nodes("parts")?),
+        "grouping" => Ast::Grouping(o.boxed("expression")?),
+        "match" => Ast::Match {
+            scrutinee: o.boxed("scrutinee")?,
+            arms: o.list("arms", |json, at| {
+                let arm = Object::of(json, at)?;
+                Ok((read_pattern(arm.get("pattern")?, arm.path("pattern"))?, arm.node("body")?))
+            })?,
+        },
+        other => return Err(invalid(&o.path("kind"), &format!("unknown node kind \"{}\"", other))),
+    };
+    match o.maybe("span") {
+        Some(_) => Ok(Ast::Located { line: o.object("span")?.whole("line")?, statement: Box::new(ast) }),
+        None => Ok(ast),
+    }
+}
+
+// A token holds the number a literal in a script lexed to, so its text goes
+// through the lexer: `0xff` reads as "255", and `42n` or `abc` are refused.
+fn token_text(o: &Object) -> Result<String, Rekt> {
+    let text = o.text("value")?;
+    let at = o.path("value");
+    match Lexer::new(&text).tokenize() {
+        Ok(tokens) => match tokens.as_slice() {
+            [Token::Number(n)] => Ok(n.to_string()),
+            [Token::Uint(_)] => Err(invalid(&at, "expected a number, but this is a uint; use type \"uint\"")),
+            [Token::Decimal(_)] => Err(invalid(&at, "expected a number, but this is a decimal; use type \"decimal\"")),
+            _ => Err(invalid(&at, "expected a number")),
+        },
+        Err(e) => Err(invalid(&at, &format!("expected a number ({})", e))),
+    }
+}
+
+fn read_value(json: &Json, at: String) -> Result<Value, Rekt> {
+    let o = Object::of(json, at)?;
+    let tag = o.text("type")?;
+    Ok(match tag.as_str() {
+        "null" => Value::Null,
+        "number" => {
+            let given = o.get("value")?;
+            // Infinities and NaN have no JSON number, so they're written as text
+            let n = given.as_f64().or_else(|| given.as_str().and_then(|s| s.parse::<f64>().ok()));
+            Value::Number(n.ok_or_else(|| invalid(&o.path("value"), "expected a number"))?)
+        }
+        "text" => Value::Text(o.text("value")?),
+        "boolean" => Value::Boolean(o.flag("value")?),
+        "token" => Value::Token(token_text(&o)?),
+        "signal" => Value::Signal(o.flag("value")?),
+        "uint" => Value::Int(U256::parse(&o.text("value")?).ok_or_else(|| invalid(&o.path("value"), "expected a uint"))?),
+        "decimal" => {
+            let units = o.text("units")?.parse::<i128>().map_err(|_| invalid(&o.path("units"), "expected an integer"))?;
+            let scale = o.whole("scale")? as u32;
+            Value::Decimal(Decimal::new(units, scale).ok_or_else(|| invalid(&o.at, "the decimal has too many digits"))?)
+        }
+        "array" => Value::Array(o.list("items", read_value)?),
+        other => return Err(invalid(&o.path("type"), &format!("unknown value type \"{}\"", other))),
+    })
+}
+
+fn read_type(json: &Json, at: String) -> Result<Type, Rekt> {
+    if let Some(name) = json.as_str() {
+        return TYPES
+            .iter()
+            .find(|(known, _)| *known == name)
+            .map(|(_, t)| t.clone())
+            .ok_or_else(|| invalid(&at, &format!("unknown type \"{}\"", name)));
+    }
+    Ok(Type::Struct(Object::of(json, at)?.text("struct")?))
+}
+
+fn read_pattern(json: &Json, at: String) -> Result<Pattern, Rekt> {
+    let o = Object::of(json, at)?;
+    let kind = o.text("kind")?;
+    Ok(match kind.as_str() {
+        "wildcard" => Pattern::Wildcard,
+        "literal" => Pattern::Literal(read_value(o.get("value")?, o.path("value"))?),
+        "range" => Pattern::Range(read_value(o.get("start")?, o.path("start"))?, read_value(o.get("end")?, o.path("end"))?),
+        "binding" => Pattern::Binding(o.text("name")?),
+        "struct" => Pattern::Struct {
+            name: o.text("name")?,
+            fields: o.list("fields", |json, at| {
+                let field = Object::of(json, at)?;
+                Ok((field.text("name")?, read_pattern(field.get("pattern")?, field.path("pattern"))?))
+            })?,
+        },
+        "array" => Pattern::Array { items: o.list("items", read_pattern)?, rest: o.optional_text("rest")? },
+        "or" => Pattern::Or(o.list("alternatives", read_pattern)?),
+        other => return Err(invalid(&o.path("kind"), &format!("unknown pattern kind \"{}\"", other))),
+    })
+}
+
+// ------------------------------------------------------------ checking
+
+// The checks the parser makes as it goes, with its messages, for a tree
+// that didn't come from it.
+fn structure(ast: &Ast, in_loop: bool) -> Result<(), Rekt> {
+    let refused = |why: &str| Err(Rekt::Parser(why.to_string()));
+    match ast {
+        Ast::Located { line, statement } => return structure(statement, in_loop).map_err(|e| e.at_line(*line)),
+        Ast::Break if !in_loop => return refused("'crash' can only be used inside a loop"),
+        Ast::Continue if !in_loop => return refused("'skip' can only be used inside a loop"),
+        Ast::Delete(target) if !matches!(**target, Ast::Index { .. }) => {
+            return refused("Can only delete a mapping slot like 'm[key]'");
+        }
+        Ast::CompoundAssign { target, .. }
+            if !matches!(**target, Ast::Variable(_) | Ast::Index { .. } | Ast::Field { .. }) =>
+        {
+            return refused("Invalid assignment target");
+        }
+        Ast::StructDecl { name, fields, .. } => {
+            for (i, (field, _)) in fields.iter().enumerate() {
+                if fields[..i].iter().any(|(earlier, _)| earlier == field) {
+                    return Err(Rekt::Parser(format!("Duplicate field '{}' in struct '{}'", field, name)));
+                }
+            }
+        }
+        Ast::Match { arms, .. } => {
+            if arms.is_empty() {
+                return refused("A vibecheck needs at least one arm");
+            }
+            for (arm, _) in arms {
+                pattern_structure(arm)?;
+            }
+        }
+        _ => {}
+    }
+    // Only a loop's body is inside it, and contract bodies start outside
+    // any loop, even when mined inside one.
+    let body = match ast {
+        Ast::While { body, .. } | Ast::ForEach { body, .. } | Ast::ForRange { body, .. } => Some((body, true)),
+        Ast::FunctionDecl { body, .. } | Ast::Lambda { body, .. } => Some((body, false)),
+        _ => None,
+    };
+    for child in ast.children() {
+        let in_loop = match body {
+            Some((body, inside)) if body.iter().any(|stmt| std::ptr::eq(stmt, child)) => inside,
+            _ => in_loop,
+        };
+        structure(child, in_loop)?;
+    }
+    Ok(())
+}
+
+fn pattern_structure(arm: &Pattern) -> Result<(), Rekt> {
+    match arm {
+        Pattern::Or(alternatives) if alternatives.iter().any(|p| matches!(p, Pattern::Binding(_))) => {
+            Err(Rekt::Parser("Can't bind a name inside '|' alternatives".to_string()))
+        }
+        Pattern::Or(inner) | Pattern::Array { items: inner, .. } => inner.iter().try_for_each(pattern_structure),
+        Pattern::Struct { fields, .. } => fields.iter().try_for_each(|(_, sub)| pattern_structure(sub)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(program: Json) -> Result<Ast, Rekt> {
+        from_json(&json!({ "format": FORMAT, "version": VERSION, "program": program }).to_string())
+    }
+
+    fn program(body: Json) -> Json {
+        json!({ "kind": "program", "body": body })
+    }
+
+    #[test]
+    fn resolved_variables_are_refused() {
+        let resolved = json!({ "kind": "resolved_variable", "name": "x", "depth": 0, "slot": 0 });
+        let error = load(program(json!([{ "kind": "print_stmt", "value": resolved }]))).unwrap_err();
+        assert!(error.to_string().contains("$.program.body[0].value.kind"), "{}", error);
+    }
+
+    #[test]
+    fn loop_control_needs_a_loop() {
+        let crash = json!({ "kind": "break", "span": { "line": 3 } });
+        let error = load(program(json!([crash]))).unwrap_err().to_string();
+        assert!(error.contains("'crash' can only be used inside a loop") && error.contains("at line 3"), "{}", error);
+
+        let forever = |body: Json| {
+            let condition = json!({ "kind": "literal", "value": { "type": "signal", "value": true } });
+            program(json!([{ "kind": "while", "condition": condition, "body": body }]))
+        };
+        assert!(load(forever(json!([{ "kind": "break" }]))).is_ok());
+
+        let contract = json!({ "kind": "lambda", "params": [], "body": [{ "kind": "continue" }] });
+        let error = load(forever(json!([{ "kind": "expression_stmt", "expression": contract }]))).unwrap_err().to_string();
+        assert!(error.contains("'skip' can only be used inside a loop"), "{}", error);
+    }
+
+    #[test]
+    fn token_literals_must_lex_as_numbers() {
+        let ping = |value: &str| {
+            let literal = json!({ "kind": "literal", "value": { "type": "token", "value": value } });
+            load(program(json!([{ "kind": "print_stmt", "value": literal }])))
+        };
+        let Ast::Program(stmts) = ping("0xff").unwrap() else { panic!("not a program") };
+        assert!(matches!(&stmts[0], Ast::PrintStmt(value) if **value == Ast::Literal(Value::Token("255".to_string()))), "{:?}", stmts);
+        assert!(ping("-2.5").is_ok());
+
+        let refused = |value: &str, why: &str| {
+            let error = ping(value).unwrap_err().to_string();
+            assert!(error.contains("$.program.body[0].value.value.value") && error.contains(why), "{}: {}", value, error);
+        };
+        refused("abc", "expected a number");
+        refused("", "expected a number");
+        refused("1 2", "expected a number");
+        refused("42n", "use type \"uint\"");
+        refused("1.50d", "use type \"decimal\"");
+        refused("1__0", "needs a digit on both sides");
+        refused("9007199254740993", "can't be held exactly");
+    }
+}
@@ -1,20 +1,57 @@
 // src/parser/mod.rs
 
 use crate::error::Rekt;
-use crate::lexer::Token;
+use crate::lexer::{Segment, Token};
 use crate::shared_types::{BinaryOp, Value, Type};
-use ast::Ast;
+use ast::{Ast, Pattern};
 
 pub mod ast;
+pub mod json;
+
+type Params = Vec<(String, Type)>;
 
 pub struct Parser {
     tokens: Vec<Token>,
+    lines: Option<Vec<usize>>, // the line each token starts on, when known
     current: usize,
+    loop_depth: usize, // how many loops enclose the current statement
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Parser { tokens, current: 0 }
+        Parser::build(tokens, None)
+    }
+
+    // A parser that wraps each statement in the line it starts on.
+    pub fn with_lines(tokens: Vec<Token>, lines: Vec<usize>) -> Self {
+        Parser::build(tokens, Some(lines))
+    }
+
+    fn build(tokens: Vec<Token>, lines: Option<Vec<usize>>) -> Self {
+        // Doc comments only mean something right before a declaration; anywhere
+        // else they're plain comments, so drop them before parsing.
+        let mut kept: Vec<Token> = Vec::with_capacity(tokens.len());
+        let mut kept_lines = Vec::with_capacity(tokens.len());
+        for (i, token) in tokens.iter().enumerate() {
+            if let Token::DocComment(_) = token {
+                let next = tokens[i..].iter().position(|t| !matches!(t, Token::DocComment(_)));
+                let documents = match next.map(|n| i + n) {
+                    Some(n) => match &tokens[n] {
+                        Token::Launch | Token::Debug | Token::Struct => true,
+                        Token::BossFight => matches!(tokens.get(n + 1), Some(Token::Identifier(_))),
+                        _ => false,
+                    },
+                    None => false,
+                };
+                if !documents {
+                    continue;
+                }
+            }
+            kept.push(token.clone());
+            kept_lines.extend(lines.as_ref().map(|lines| lines[i]));
+        }
+        let lines = lines.map(|_| kept_lines);
+        Parser { tokens: kept, lines, current: 0, loop_depth: 0 }
     }
 
     pub fn parse(&mut self) -> Result<Ast, Rekt> {
@@ -26,15 +63,43 @@ impl Parser {
     }
 
     fn declaration(&mut self) -> Result<Ast, Rekt> {
+        let line = self.lines.as_ref().and_then(|lines| lines.get(self.current).copied());
+        let statement = self.statement_or_declaration()?;
+        Ok(match line {
+            Some(line) => Ast::Located { line, statement: Box::new(statement) },
+            None => statement,
+        })
+    }
+
+    fn statement_or_declaration(&mut self) -> Result<Ast, Rekt> {
+        let doc = self.doc_comment();
         match self.peek() {
-            Some(Token::Launch) => self.var_declaration(false),
-            Some(Token::BossFight) => self.function_declaration(),
-            Some(Token::Debug) => self.var_declaration(true), // Debug is used for constant declarations
+            Some(Token::Launch) => self.var_declaration(false, doc),
+            // `mine name(...)` declares; a bare `mine (...)` is an anonymous contract
+            Some(Token::BossFight) if matches!(self.tokens.get(self.current + 1), Some(Token::Identifier(_))) => {
+                self.function_declaration(doc)
+            }
+            Some(Token::Debug) => self.var_declaration(true, doc), // Debug is used for constant declarations
+            Some(Token::Struct) => self.struct_declaration(doc),
             _ => self.statement(),
         }
     }
 
-    fn var_declaration(&mut self, is_constant: bool) -> Result<Ast, Rekt> {
+    // Joins a run of `///` lines into the doc for the declaration that follows.
+    fn doc_comment(&mut self) -> Option<String> {
+        let mut lines = Vec::new();
+        while let Some(Token::DocComment(line)) = self.peek() {
+            lines.push(line.clone());
+            self.advance();
+        }
+        if lines.is_empty() {
+            None
+        } else {
+            Some(lines.join("\n"))
+        }
+    }
+
+    fn var_declaration(&mut self, is_constant: bool, doc: Option<String>) -> Result<Ast, Rekt> {
         self.advance(); // consume 'launch' or 'debug'
 
         // Get variable name
@@ -52,10 +117,11 @@ impl Parser {
             name,
             is_constant,
             initializer: Box::new(initializer),
+            doc,
         })
     }
 
-    fn function_declaration(&mut self) -> Result<Ast, Rekt> {
+    fn function_declaration(&mut self, doc: Option<String>) -> Result<Ast, Rekt> {
         self.advance(); // consume 'bossfight'
         
         // Get function name
@@ -64,6 +130,22 @@ impl Parser {
             _ => return Err(Rekt::Parser("Expected function name".to_string())),
         };
         
+        let (params, return_type) = self.signature()?;
+        
+        // Parse function body
+        let body = self.contract_body()?;
+        
+        Ok(Ast::FunctionDecl {
+            name,
+            params,
+            return_type,
+            body,
+            doc,
+        })
+    }
+
+    // Parses `(a, b: int) -> type`, shared by named and anonymous contracts.
+    fn signature(&mut self) -> Result<(Params, Option<Type>), Rekt> {
         // Parse parameters
         self.consume(&Token::LParen, "Expected '(' after function name")?;
         let mut params = Vec::new();
@@ -77,12 +159,7 @@ impl Parser {
                 // Optional type annotation
                 let param_type = if self.check(&Token::Colon) {
                     self.advance(); // consume ':'
-                    match self.advance() {
-                        Some(Token::TypeInt) => Type::Number,
-                        Some(Token::TypeStr) => Type::Text,
-                        Some(Token::TypeBool) => Type::Boolean,
-                        _ => return Err(Rekt::Parser("Expected type annotation".to_string())),
-                    }
+                    self.type_annotation("Expected type annotation")?
                 } else {
                     Type::Number // Default to Number type
                 };
@@ -100,25 +177,63 @@ impl Parser {
         // Parse return type
         let return_type = if self.check(&Token::Arrow) {
             self.advance(); // consume '->'
-            match self.advance() {
-                Some(Token::TypeInt) => Some(Type::Number),
-                Some(Token::TypeStr) => Some(Type::Text),
-                Some(Token::TypeBool) => Some(Type::Boolean),
-                _ => return Err(Rekt::Parser("Expected return type".to_string())),
-            }
+            Some(self.type_annotation("Expected return type")?)
         } else {
             None
         };
-        
-        // Parse function body
-        let body = self.block()?;
-        
-        Ok(Ast::FunctionDecl {
-            name,
-            params,
-            return_type,
-            body,
-        })
+
+        Ok((params, return_type))
+    }
+
+    fn struct_declaration(&mut self, doc: Option<String>) -> Result<Ast, Rekt> {
+        self.advance(); // consume 'struct'
+
+        let name = match self.advance() {
+            Some(Token::Identifier(name)) => name.clone(),
+            _ => return Err(Rekt::Parser("Expected struct name".to_string())),
+        };
+
+        self.consume(&Token::LBrace, "Expected '{' after struct name")?;
+        let mut fields: Vec<(String, Option<Type>)> = Vec::new();
+        while !self.check(&Token::RBrace) {
+            let field = match self.advance() {
+                Some(Token::Identifier(field)) => field.clone(),
+                _ => return Err(Rekt::Parser("Expected field name".to_string())),
+            };
+            if fields.iter().any(|(f, _)| *f == field) {
+                return Err(Rekt::Parser(format!("Duplicate field '{}' in struct '{}'", field, name)));
+            }
+
+            // Optional type annotation
+            let field_type = if self.check(&Token::Colon) {
+                self.advance(); // consume ':'
+                Some(self.type_annotation("Expected field type")?)
+            } else {
+                None
+            };
+            fields.push((field, field_type));
+
+            if !self.check(&Token::Comma) {
+                break;
+            }
+            self.advance(); // consume ','
+        }
+        self.consume(&Token::RBrace, "Expected '}' after struct fields")?;
+
+        Ok(Ast::StructDecl { name, fields, doc })
+    }
+
+    fn type_annotation(&mut self, message: &str) -> Result<Type, Rekt> {
+        match self.advance() {
+            Some(Token::TypeInt) => Ok(Type::Number),
+            Some(Token::TypeStr) => Ok(Type::Text),
+            Some(Token::TypeBool) => Ok(Type::Boolean),
+            Some(Token::TypeUint) => Ok(Type::Uint),
+            Some(Token::TypeDecimal) => Ok(Type::Decimal),
+            Some(Token::BossFight) => Ok(Type::Function),
+            Some(Token::Identifier(name)) => Ok(Type::Struct(name.clone())),
+            _ => Err(Rekt::Parser(message.to_string())),
+        }
     }
 
     fn statement(&mut self) -> Result<Ast, Rekt> {
@@ -126,7 +241,19 @@ impl Parser {
             Some(Token::Ping) => self.print_statement(),
             Some(Token::Sus) => self.if_statement(),
             Some(Token::Grind) => self.while_statement(),
+            Some(Token::Farm) => self.for_statement(),
+            Some(Token::Crash) => self.loop_control(Ast::Break, "crash"),
+            Some(Token::Skip) => self.loop_control(Ast::Continue, "skip"),
             Some(Token::GG) => self.return_statement(),
+            Some(Token::Delete) => self.delete_statement(),
+            Some(Token::Vibecheck) => {
+                let expr = self.vibecheck()?;
+                // The trailing ';' is optional when used as a statement
+                if self.check(&Token::Semicolon) {
+                    self.advance();
+                }
+                Ok(Ast::ExpressionStmt(Box::new(expr)))
+            }
             Some(Token::LBrace) => Ok(Ast::Block(self.block()?)),
             _ => self.expression_statement(),
         }
@@ -149,7 +276,12 @@ impl Parser {
 
         let else_branch = if matches!(self.peek(), Some(Token::Rekt)) {
             self.advance(); // consume 'rekt'
-            Some(self.block()?)
+            if matches!(self.peek(), Some(Token::Sus)) {
+                // `rekt sus (...)` chains nest as an if inside the else branch
+                Some(vec![self.if_statement()?])
+            } else {
+                Some(self.block()?)
+            }
         } else {
             None
         };
@@ -167,7 +299,7 @@ impl Parser {
         let condition = self.expression()?;
         self.consume(&Token::RParen, "Expected ')' after condition")?;
 
-        let body = self.block()?;
+        let body = self.loop_body()?;
 
         Ok(Ast::While {
             condition: Box::new(condition),
@@ -175,6 +307,87 @@ impl Parser {
         })
     }
 
+    // farm (x in xs) {}, farm (k, v in m) {} or farm (i in 0..10 step 2) {}
+    fn for_statement(&mut self) -> Result<Ast, Rekt> {
+        self.advance(); // consume 'farm'
+        self.consume(&Token::LParen, "Expected '(' after 'farm'")?;
+
+        let mut variable = self.loop_variable()?;
+        let mut key = None;
+        if self.check(&Token::Comma) {
+            self.advance(); // consume ','
+            key = Some(variable);
+            variable = self.loop_variable()?;
+        }
+        self.consume(&Token::In, "Expected 'in' after loop variable")?;
+
+        let iterable = self.expression()?;
+        if !self.check(&Token::DotDot) {
+            self.consume(&Token::RParen, "Expected ')' after loop source")?;
+            let body = self.loop_body()?;
+            return Ok(Ast::ForEach {
+                key,
+                variable,
+                iterable: Box::new(iterable),
+                body,
+            });
+        }
+
+        if key.is_some() {
+            return Err(Rekt::Parser("A range loop takes a single variable".to_string()));
+        }
+        self.advance(); // consume '..'
+        let end = self.expression()?;
+        // `step` is only special right here, so it stays a valid token name elsewhere
+        let step = if matches!(self.peek(), Some(Token::Identifier(word)) if word == "step") {
+            self.advance(); // consume 'step'
+            Some(Box::new(self.expression()?))
+        } else {
+            None
+        };
+        self.consume(&Token::RParen, "Expected ')' after range")?;
+        let body = self.loop_body()?;
+
+        Ok(Ast::ForRange {
+            variable,
+            start: Box::new(iterable),
+            end: Box::new(end),
+            step,
+            body,
+        })
+    }
+
+    fn loop_variable(&mut self) -> Result<String, Rekt> {
+        match self.advance() {
+            Some(Token::Identifier(name)) => Ok(name.clone()),
+            _ => Err(Rekt::Parser("Expected loop variable name".to_string())),
+        }
+    }
+
+    fn loop_body(&mut self) -> Result<Vec<Ast>, Rekt> {
+        self.loop_depth += 1;
+        let body = self.block();
+        self.loop_depth -= 1;
+        body
+    }
+
+    // Contract bodies start outside any loop, even when mined inside one.
+    fn contract_body(&mut self) -> Result<Vec<Ast>, Rekt> {
+        let depth = std::mem::replace(&mut self.loop_depth, 0);
+        let body = self.block();
+        self.loop_depth = depth;
+        body
+    }
+
+    fn loop_control(&mut self, control: Ast, keyword: &str) -> Result<Ast, Rekt> {
+        self.advance(); // consume 'crash' or 'skip'
+        if self.loop_depth == 0 {
+            return Err(Rekt::Parser(format!("'{}' can only be used inside a loop", keyword)));
+        }
+        self.consume(&Token::Semicolon, &format!("Expected ';' after '{}'", keyword))?;
+        Ok(control)
+    }
+
     fn return_statement(&mut self) -> Result<Ast, Rekt> {
         self.advance(); // consume 'gg'
         let value = if !self.check(&Token::Semicolon) {
@@ -186,6 +399,16 @@ impl Parser {
         Ok(Ast::ReturnStmt(value))
     }
 
+    fn delete_statement(&mut self) -> Result<Ast, Rekt> {
+        self.advance(); // consume 'delete'
+        let target = self.expression()?;
+        if !matches!(target, Ast::Index { .. }) {
+            return Err(Rekt::Parser("Can only delete a mapping slot like 'm[key]'".to_string()));
+        }
+        self.consume(&Token::Semicolon, "Expected ';' after delete")?;
+        Ok(Ast::Delete(Box::new(target)))
+    }
+
     fn expression_statement(&mut self) -> Result<Ast, Rekt> {
         let expr = self.expression()?;
         self.consume(&Token::Semicolon, "Expected ';' after expression")?;
@@ -207,7 +430,7 @@ impl Parser {
     }
 
     fn assignment(&mut self) -> Result<Ast, Rekt> {
-        let expr = self.equality()?;
+        let expr = self.conditional()?;
 
         if matches!(self.peek(), Some(Token::Match)) {
             self.advance(); // consume 'match'
@@ -218,13 +441,83 @@ impl Parser {
                     name,
                     value: Box::new(value),
                 }),
+                Ast::Index { object, index } => Ok(Ast::SetIndex {
+                    object,
+                    index,
+                    value: Box::new(value),
+                }),
+                Ast::Field { object, name } => Ok(Ast::SetField {
+                    object,
+                    name,
+                    value: Box::new(value),
+                }),
                 _ => Err(Rekt::Parser("Invalid assignment target".to_string())),
             }
+        } else if let Some(operator) = self.match_compound_operator() {
+            let value = self.assignment()?;
+            self.compound_assign(expr, operator, value)
+        } else if matches!(self.peek(), Some(Token::PlusPlus | Token::MinusMinus)) {
+            // `x++` / `x--` are shorthand for `x += 1` / `x -= 1`
+            let operator = match self.advance() {
+                Some(Token::PlusPlus) => BinaryOp::Add,
+                _ => BinaryOp::Subtract,
+            };
+            self.compound_assign(expr, operator, Ast::Literal(Value::Token("1".to_string())))
         } else {
             Ok(expr)
         }
     }
 
+    fn match_compound_operator(&mut self) -> Option<BinaryOp> {
+        let op = match self.peek() {
+            Some(Token::PlusMatch) => BinaryOp::Add,
+            Some(Token::MinusMatch) => BinaryOp::Subtract,
+            Some(Token::StarMatch) => BinaryOp::Multiply,
+            Some(Token::SlashMatch) => BinaryOp::Divide,
+            _ => return None,
+        };
+        self.advance();
+        Some(op)
+    }
+
+    fn compound_assign(&self, target: Ast, operator: BinaryOp, value: Ast) -> Result<Ast, Rekt> {
+        match target {
+            Ast::Variable(_) | Ast::Index { .. } | Ast::Field { .. } => Ok(Ast::CompoundAssign {
+                target: Box::new(target),
+                operator,
+                value: Box::new(value),
+            }),
+            _ => Err(Rekt::Parser("Invalid assignment target".to_string())),
+        }
+    }
+
+    // `sus cond ? a : b`, or just `cond ? a : b`; nests to the right
+    fn conditional(&mut self) -> Result<Ast, Rekt> {
+        let prefixed = self.check(&Token::Sus);
+        if prefixed {
+            self.advance(); // consume 'sus'
+        }
+
+        let condition = self.equality()?;
+        if !self.check(&Token::Question) {
+            if prefixed {
+                return Err(Rekt::Parser("Expected '?' after 'sus' condition".to_string()));
+            }
+            return Ok(condition);
+        }
+        self.advance(); // consume '?'
+
+        let then_branch = self.expression()?;
+        self.consume(&Token::Colon, "Expected ':' between the two sides of 'sus ?'")?;
+        let else_branch = self.conditional()?;
+
+        Ok(Ast::Ternary {
+            condition: Box::new(condition),
+            then_branch: Box::new(then_branch),
+            else_branch: Box::new(else_branch),
+        })
+    }
+
     fn equality(&mut self) -> Result<Ast, Rekt> {
         let mut expr = self.comparison()?;
 
@@ -253,10 +546,10 @@ impl Parser {
     }
 
     fn comparison(&mut self) -> Result<Ast, Rekt> {
-        let mut expr = self.term()?;
+        let mut expr = self.bit_or()?;
 
         while let Some(op) = self.match_comparison_operator() {
-            let right = self.term()?;
+            let right = self.bit_or()?;
             expr = Ast::Binary {
                 left: Box::new(expr),
                 operator: op,
@@ -285,6 +578,46 @@ impl Parser {
         }
     }
 
+    // Bitwise operators bind tighter than comparisons, so `flags & MASK == 0`
+    // tests the masked value. Loosest first: |, ^, &, then shifts.
+    fn bit_or(&mut self) -> Result<Ast, Rekt> {
+        self.binary_level(&[(Token::Pipe, BinaryOp::BitOr)], Parser::bit_xor)
+    }
+
+    fn bit_xor(&mut self) -> Result<Ast, Rekt> {
+        self.binary_level(&[(Token::Caret, BinaryOp::BitXor)], Parser::bit_and)
+    }
+
+    fn bit_and(&mut self) -> Result<Ast, Rekt> {
+        self.binary_level(&[(Token::Amp, BinaryOp::BitAnd)], Parser::shift)
+    }
+
+    fn shift(&mut self) -> Result<Ast, Rekt> {
+        self.binary_level(
+            &[(Token::ShiftLeft, BinaryOp::ShiftLeft), (Token::ShiftRight, BinaryOp::ShiftRight)],
+            Parser::term,
+        )
+    }
+
+    // One left-associative precedence level: `next (op next)*`.
+    fn binary_level(
+        &mut self,
+        operators: &[(Token, BinaryOp)],
+        next: fn(&mut Parser) -> Result<Ast, Rekt>,
+    ) -> Result<Ast, Rekt> {
+        let mut expr = next(self)?;
+        while let Some((_, op)) = operators.iter().find(|(token, _)| self.check(token)) {
+            self.advance();
+            let right = next(self)?;
+            expr = Ast::Binary {
+                left: Box::new(expr),
+                operator: *op,
+                right: Box::new(right),
+            };
+        }
+        Ok(expr)
+    }
+
     fn term(&mut self) -> Result<Ast, Rekt> {
         let mut expr = self.factor()?;
 
@@ -334,17 +667,26 @@ impl Parser {
         } else if matches!(self.peek(), Some(Token::Slash | Token::Swap)) {
             self.advance();
             Some(BinaryOp::Divide)
+        } else if matches!(self.peek(), Some(Token::Percent)) {
+            self.advance();
+            Some(BinaryOp::Modulo)
+        } else if matches!(self.peek(), Some(Token::TildeSlash)) {
+            self.advance();
+            Some(BinaryOp::IntDivide)
         } else {
             None
         }
     }
 
     fn unary(&mut self) -> Result<Ast, Rekt> {
-        if matches!(self.peek(), Some(Token::Not)) {
-            self.advance();
+        if matches!(self.peek(), Some(Token::Not | Token::Tilde)) {
+            let operator = match self.advance() {
+                Some(Token::Not) => BinaryOp::Not,
+                _ => BinaryOp::BitNot,
+            };
             let expr = self.unary()?;
             Ok(Ast::Unary {
-                operator: BinaryOp::Not,
+                operator,
                 operand: Box::new(expr),
             })
         } else {
@@ -359,6 +701,24 @@ impl Parser {
             if matches!(self.peek(), Some(Token::LParen)) {
                 self.advance();
                 expr = self.finish_call(expr)?;
+            } else if matches!(self.peek(), Some(Token::LBracket)) {
+                self.advance();
+                let index = self.expression()?;
+                self.consume(&Token::RBracket, "Expected ']' after index")?;
+                expr = Ast::Index {
+                    object: Box::new(expr),
+                    index: Box::new(index),
+                };
+            } else if matches!(self.peek(), Some(Token::Dot)) {
+                self.advance();
+                let name = match self.advance() {
+                    Some(Token::Identifier(name)) => name.clone(),
+                    _ => return Err(Rekt::Parser("Expected field name after '.'".to_string())),
+                };
+                expr = Ast::Field {
+                    object: Box::new(expr),
+                    name,
+                };
             } else {
                 break;
             }
@@ -382,13 +742,10 @@ impl Parser {
 
         self.consume(&Token::RParen, "Expected ')' after arguments")?;
 
-        match callee {
-            Ast::Variable(name) => Ok(Ast::Call {
-                callee: name,
-                arguments,
-            }),
-            _ => Err(Rekt::Parser("Can only call functions".to_string())),
-        }
+        Ok(Ast::Call {
+            callee: Box::new(callee),
+            arguments,
+        })
     }
 
     fn primary(&mut self) -> Result<Ast, Rekt> {
@@ -400,10 +757,30 @@ impl Parser {
                     self.advance();
                     Ok(Ast::Literal(Value::Token(value.to_string())))
                 },
+                Token::Uint(value) => {
+                    self.advance();
+                    Ok(Ast::Literal(Value::Int(value)))
+                },
+                Token::Decimal(value) => {
+                    self.advance();
+                    Ok(Ast::Literal(Value::Decimal(value)))
+                },
                 Token::Text(text) => {
                     self.advance();
                     Ok(Ast::Literal(Value::Text(text.clone())))
                 },
+                Token::Interpolation(segments) => {
+                    let line = self.lines.as_ref().and_then(|lines| lines.get(self.current).copied());
+                    self.advance();
+                    let mut parts = Vec::new();
+                    for segment in segments {
+                        match segment {
+                            Segment::Text(text) => parts.push(Ast::Literal(Value::Text(text))),
+                            Segment::Code(tokens) => parts.push(interpolated(tokens, line)?),
+                        }
+                    }
+                    Ok(Ast::Interpolation(parts))
+                },
                 Token::True => {
                     self.advance();
                     Ok(Ast::Literal(Value::Signal(true)))
@@ -412,8 +789,19 @@ impl Parser {
                     self.advance();
                     Ok(Ast::Literal(Value::Signal(false)))
                 },
+                // `uint(x)` reads like a Solidity cast; it calls the builtin
+                Token::TypeUint | Token::TypeDecimal
+                    if matches!(self.tokens.get(self.current + 1), Some(Token::LParen)) =>
+                {
+                    self.advance();
+                    Ok(Ast::Variable(token.to_string()))
+                },
                 Token::Identifier(name) => {
                     self.advance();
+                    if self.at_struct_literal() {
+                        self.advance(); // consume '{'
+                        return self.struct_literal(name);
+                    }
                     Ok(Ast::Variable(name.clone()))
                 },
                 Token::LParen => {
@@ -422,6 +810,19 @@ impl Parser {
                     self.consume(&Token::RParen, "Expected ')' after expression")?;
                     Ok(Ast::Grouping(Box::new(expr)))
                 },
+                Token::LBracket => {
+                    self.advance();
+                    self.array_literal()
+                },
+                Token::BossFight => {
+                    self.advance();
+                    self.lambda()
+                },
+                Token::Vibecheck => self.vibecheck(),
+                Token::LBrace => {
+                    self.advance();
+                    self.map_literal()
+                },
                 _ => Err(Rekt::Parser(format!("Unexpected token: {}", token))),
             }
         } else {
@@ -429,6 +830,205 @@ impl Parser {
         }
     }
 
+    // vibecheck (value) { pattern => expr, pattern => { ... }, _ => expr }
+    fn vibecheck(&mut self) -> Result<Ast, Rekt> {
+        self.advance(); // consume 'vibecheck'
+        self.consume(&Token::LParen, "Expected '(' after 'vibecheck'")?;
+        let scrutinee = self.expression()?;
+        self.consume(&Token::RParen, "Expected ')' after vibecheck value")?;
+        self.consume(&Token::LBrace, "Expected '{' to start vibecheck arms")?;
+
+        let mut arms = Vec::new();
+        while !self.check(&Token::RBrace) && !self.is_at_end() {
+            let pattern = self.pattern()?;
+            self.consume(&Token::FatArrow, "Expected '=>' after pattern")?;
+            let body = if self.check(&Token::LBrace) {
+                Ast::Block(self.block()?)
+            } else {
+                self.expression()?
+            };
+            let block_arm = matches!(body, Ast::Block(_));
+            arms.push((pattern, body));
+
+            if self.check(&Token::Comma) {
+                self.advance(); // consume ','
+            } else if !block_arm {
+                break;
+            }
+        }
+        self.consume(&Token::RBrace, "Expected '}' after vibecheck arms")?;
+
+        if arms.is_empty() {
+            return Err(Rekt::Parser("A vibecheck needs at least one arm".to_string()));
+        }
+        Ok(Ast::Match {
+            scrutinee: Box::new(scrutinee),
+            arms,
+        })
+    }
+
+    fn pattern(&mut self) -> Result<Pattern, Rekt> {
+        let first = self.single_pattern()?;
+        if !self.check(&Token::Pipe) {
+            return Ok(first);
+        }
+
+        let mut alternatives = vec![first];
+        while self.check(&Token::Pipe) {
+            self.advance(); // consume '|'
+            alternatives.push(self.single_pattern()?);
+        }
+        if alternatives.iter().any(|p| matches!(p, Pattern::Binding(_))) {
+            return Err(Rekt::Parser("Can't bind a name inside '|' alternatives".to_string()));
+        }
+        Ok(Pattern::Or(alternatives))
+    }
+
+    fn single_pattern(&mut self) -> Result<Pattern, Rekt> {
+        let token = match self.advance() {
+            Some(token) => token.clone(),
+            None => return Err(Rekt::Parser("Expected pattern".to_string())),
+        };
+
+        match token {
+            Token::Number(_) | Token::Uint(_) | Token::Decimal(_) => {
+                let start = pattern_number(&token);
+                if !self.check(&Token::DotDot) {
+                    return Ok(Pattern::Literal(start));
+                }
+                self.advance(); // consume '..'
+                match self.advance() {
+                    Some(end @ (Token::Number(_) | Token::Uint(_) | Token::Decimal(_))) => Ok(Pattern::Range(start, pattern_number(end))),
+                    _ => Err(Rekt::Parser("Expected number after '..' in range pattern".to_string())),
+                }
+            }
+            Token::Text(text) => Ok(Pattern::Literal(Value::Text(text))),
+            Token::True => Ok(Pattern::Literal(Value::Signal(true))),
+            Token::False => Ok(Pattern::Literal(Value::Signal(false))),
+            Token::Identifier(name) if name == "_" => Ok(Pattern::Wildcard),
+            Token::Identifier(name) if self.check(&Token::LBrace) => {
+                self.advance(); // consume '{'
+                let mut fields = Vec::new();
+                while !self.check(&Token::RBrace) {
+                    let field = match self.advance() {
+                        Some(Token::Identifier(field)) => field.clone(),
+                        _ => return Err(Rekt::Parser("Expected field name in pattern".to_string())),
+                    };
+                    // `field` alone is shorthand for `field: field`
+                    let sub = if self.check(&Token::Colon) {
+                        self.advance(); // consume ':'
+                        self.pattern()?
+                    } else {
+                        Pattern::Binding(field.clone())
+                    };
+                    fields.push((field, sub));
+                    if !self.check(&Token::Comma) {
+                        break;
+                    }
+                    self.advance(); // consume ','
+                }
+                self.consume(&Token::RBrace, "Expected '}' after struct pattern")?;
+                Ok(Pattern::Struct { name, fields })
+            }
+            Token::Identifier(name) => Ok(Pattern::Binding(name)),
+            Token::LBracket => {
+                let mut items = Vec::new();
+                let mut rest = None;
+                while !self.check(&Token::RBracket) {
+                    if self.check(&Token::DotDot) {
+                        self.advance(); // consume '..'
+                        rest = Some(match self.peek() {
+                            Some(Token::Identifier(name)) => {
+                                let name = name.clone();
+                                self.advance();
+                                name
+                            }
+                            _ => "_".to_string(),
+                        });
+                        break;
+                    }
+                    items.push(self.pattern()?);
+                    if !self.check(&Token::Comma) {
+                        break;
+                    }
+                    self.advance(); // consume ','
+                }
+                self.consume(&Token::RBracket, "Expected ']' after array pattern")?;
+                Ok(Pattern::Array { items, rest })
+            }
+            other => Err(Rekt::Parser(format!("Unexpected token in pattern: {}", other))),
+        }
+    }
+
+    fn lambda(&mut self) -> Result<Ast, Rekt> {
+        let (params, return_type) = self.signature()?;
+        let body = self.contract_body()?;
+        Ok(Ast::Lambda {
+            params,
+            return_type,
+            body,
+        })
+    }
+
+    fn array_literal(&mut self) -> Result<Ast, Rekt> {
+        let mut elements = Vec::new();
+        while !self.check(&Token::RBracket) {
+            elements.push(self.expression()?);
+            if !self.check(&Token::Comma) {
+                break;
+            }
+            self.advance(); // consume ','
+        }
+        self.consume(&Token::RBracket, "Expected ']' after array elements")?;
+        Ok(Ast::ArrayLiteral(elements))
+    }
+
+    fn map_literal(&mut self) -> Result<Ast, Rekt> {
+        let mut entries = Vec::new();
+        while !self.check(&Token::RBrace) {
+            let key = self.expression()?;
+            self.consume(&Token::Colon, "Expected ':' after mapping key")?;
+            let value = self.expression()?;
+            entries.push((key, value));
+            if !self.check(&Token::Comma) {
+                break;
+            }
+            self.advance(); // consume ','
+        }
+        self.consume(&Token::RBrace, "Expected '}' after mapping entries")?;
+        Ok(Ast::MapLiteral(entries))
+    }
+
+    // `Name {` only starts a struct literal when followed by `}` or `field:`,
+    // so a variable right before a block body is never mistaken for one.
+    fn at_struct_literal(&self) -> bool {
+        if !self.check(&Token::LBrace) {
+            return false;
+        }
+        matches!(
+            (self.tokens.get(self.current + 1), self.tokens.get(self.current + 2)),
+            (Some(Token::RBrace), _) | (Some(Token::Identifier(_)), Some(Token::Colon))
+        )
+    }
+
+    fn struct_literal(&mut self, name: String) -> Result<Ast, Rekt> {
+        let mut fields: Vec<(String, Ast)> = Vec::new();
+        while !self.check(&Token::RBrace) {
+            let field = match self.advance() {
+                Some(Token::Identifier(field)) => field.clone(),
+                _ => return Err(Rekt::Parser("Expected field name".to_string())),
+            };
+            self.consume(&Token::Colon, "Expected ':' after field name")?;
+            fields.push((field, self.expression()?));
+            if !self.check(&Token::Comma) {
+                break;
+            }
+            self.advance(); // consume ','
+        }
+        self.consume(&Token::RBrace, "Expected '}' after struct fields")?;
+        Ok(Ast::StructLiteral { name, fields })
+    }
+
     fn advance(&mut self) -> Option<&Token> {
         if !self.is_at_end() {
             self.current += 1;
@@ -445,7 +1045,7 @@ impl Parser {
     }
 
     fn is_at_end(&self) -> bool {
-        matches!(self.peek(), None)
+        self.peek().is_none()
     }
 
     fn consume(&mut self, expected: &Token, message: &str) -> Result<(), Rekt> {
@@ -456,47 +1056,36 @@ impl Parser {
             Err(Rekt::Parser(message.to_string()))
         }
     }
-    #[allow(dead_code)]
-    fn match_token(&mut self, token: Token) -> bool {
-        if let Some(current_token) = self.tokens.get(self.current) {
-            if *current_token == token {
-                self.current += 1;
-                return true;
-            }
-        }
-        false
-    }
+}
 
-    fn binary(&mut self) -> Result<Ast, Rekt> {
-        let mut expr = self.unary()?;
-    
-        while let Some(token) = self.peek() {
-            let op = match token {
-                Token::Plus | Token::Stake => BinaryOp::Add,
-                Token::Minus | Token::Burn => BinaryOp::Subtract,
-                Token::Star | Token::Yield => BinaryOp::Multiply,
-                Token::Slash | Token::Swap => BinaryOp::Divide,
-                Token::Equal => BinaryOp::Equal,
-                Token::NotEqual => BinaryOp::NotEqual,
-                Token::GreaterThan => BinaryOp::Greater,
-                Token::LessThan => BinaryOp::Less,
-                Token::GreaterThanEqual => BinaryOp::GreaterEqual,
-                Token::LessThanEqual => BinaryOp::LessEqual,
-                Token::And => BinaryOp::And,
-                Token::Or => BinaryOp::Or,
-                _ => break,
-            };
-    
-            self.advance();
-            let right = self.unary()?;
-            expr = Ast::Binary {
-                left: Box::new(expr),
-                operator: op,
-                right: Box::new(right),
-            };
-        }
-    
-        Ok(expr)
+fn pattern_number(token: &Token) -> Value {
+    match token {
+        Token::Uint(n) => Value::Int(*n),
+        Token::Decimal(d) => Value::Decimal(*d),
+        other => Value::Token(other.to_string()),
     }
 }
 
+// Parses the code inside a string's `{...}`. Its tokens have no lines of
+// their own, so errors name the braces and the string's line instead.
+fn interpolated(tokens: Vec<Token>, line: Option<usize>) -> Result<Ast, Rekt> {
+    let code: Vec<String> = tokens.iter().map(|t| t.to_string()).collect();
+    let mut inner = Parser::new(tokens);
+    let parsed = inner.expression().and_then(|part| {
+        if inner.is_at_end() {
+            Ok(part)
+        } else {
+            Err(Rekt::Parser("Expected '}' after interpolated expression".to_string()))
+        }
+    });
+    parsed.map_err(|e| {
+        let e = match e {
+            Rekt::Parser(msg) => Rekt::Parser(format!("{} in '{{{}}}'", msg, code.join(" "))),
+            other => other,
+        };
+        match line {
+            Some(line) => e.at_line(line),
+            None => e,
+        }
+    })
+}
@@ -0,0 +1,363 @@
+// src/resolver/mod.rs
+//
+// Binds every name to the declaration it refers to before anything runs.
+// Scopes follow the tree walker's wallets exactly: the program, each
+// contract call (params, then its body), each farm pass (key, then item),
+// each vibecheck arm (its bindings) and each `{ }` block. `sus` and `grind`
+// bodies share the scope around them. A token's slot is the order it's
+// first declared in within its scope, which is the order it gets minted in
+// when the scope runs straight through.
+//
+// Names no scope declares are errors. Tokens that are never read, tokens
+// minted again in the same scope before the first one is read, and
+// declarations that hide an earlier one from an outer scope come back as
+// warnings.
+
+use std::collections::HashMap;
+use std::fmt;
+use crate::error::Rekt;
+use gm_runtime::BUILTINS;
+use crate::parser::ast::Ast;
+
+// Something worth a look that doesn't stop the script from running.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+// Rewrites every variable read into a `ResolvedVariable`, and returns the
+// warnings in line order.
+pub fn resolve(mut ast: Ast) -> Result<(Ast, Vec<Warning>), Rekt> {
+    let mut resolver = Resolver { scopes: Vec::new(), line: None, warnings: Vec::new() };
+    match &mut ast {
+        Ast::Program(stmts) => resolver.scoped(Vec::new(), stmts)?,
+        other => resolver.visit(other)?,
+    }
+    let mut warnings = resolver.warnings;
+    warnings.sort_by_key(|warning| warning.line);
+    Ok((ast, warnings))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Token,
+    Block,
+    Contract,
+    Param,
+    Binding, // loop variables and vibecheck bindings
+}
+
+impl Kind {
+    fn describe(self) -> &'static str {
+        match self {
+            Kind::Token => "token",
+            Kind::Block => "block",
+            Kind::Contract => "contract",
+            Kind::Param => "param",
+            Kind::Binding => "binding",
+        }
+    }
+}
+
+struct Declared {
+    name: String,
+    kind: Kind,
+    line: Option<usize>,
+    reads: usize,
+    // The line of the last declaration run straight through, and the reads
+    // seen by then
+    minted: Option<(Option<usize>, usize)>,
+}
+
+#[derive(Default)]
+struct Scope {
+    slots: HashMap<String, usize>,
+    declared: Vec<Declared>, // by slot
+}
+
+struct Resolver {
+    scopes: Vec<Scope>,
+    line: Option<usize>,
+    warnings: Vec<Warning>,
+}
+
+impl Resolver {
+    // Resolves a statement list in a new scope that starts out holding
+    // `bound` (params, loop variables, match bindings).
+    fn scoped(&mut self, bound: Vec<(String, Kind)>, stmts: &mut [Ast]) -> Result<(), Rekt> {
+        let mut declared: Vec<_> = bound.into_iter().map(|(name, kind)| (name, kind, self.line)).collect();
+        collect(stmts, self.line, &mut declared);
+        self.enter(declared);
+        let result = stmts.iter_mut().try_for_each(|stmt| self.statement(stmt));
+        self.leave();
+        result
+    }
+
+    // A statement of the list a scope runs straight through. A token minted
+    // here again before anything read the last one threw that one away.
+    fn statement(&mut self, stmt: &mut Ast) -> Result<(), Rekt> {
+        let (line, name) = match stmt {
+            Ast::Located { line, statement } => match statement.as_ref() {
+                Ast::VariableDecl { name, .. } => (Some(*line), name.clone()),
+                _ => return self.visit(stmt),
+            },
+            Ast::VariableDecl { name, .. } => (self.line, name.clone()),
+            _ => return self.visit(stmt),
+        };
+        let before = self.declared(&name).and_then(|declared| declared.minted);
+        self.visit(stmt)?;
+        let Some(declared) = self.declared(&name) else { return Ok(()) };
+        let reads = declared.reads;
+        declared.minted = Some((line, reads));
+        if let Some((earlier, seen)) = before {
+            if seen == reads && !name.starts_with('_') {
+                let kind = declared.kind.describe();
+                let message = match line {
+                    Some(line) => format!("{} '{}' is minted again at line {} before it is read", kind, name, line),
+                    None => format!("{} '{}' is minted again before it is read", kind, name),
+                };
+                self.warn(earlier, message);
+            }
+        }
+        Ok(())
+    }
+
+    // The declaration `name` has in the innermost scope.
+    fn declared(&mut self, name: &str) -> Option<&mut Declared> {
+        let scope = self.scopes.last_mut()?;
+        let slot = *scope.slots.get(name)?;
+        scope.declared.get_mut(slot)
+    }
+
+    fn enter(&mut self, declared: Vec<(String, Kind, Option<usize>)>) {
+        let mut scope = Scope::default();
+        for (name, kind, line) in declared {
+            if scope.slots.contains_key(&name) {
+                continue;
+            }
+            // Only a declaration that comes first can be hidden; a top-level
+            // name minted further down isn't around yet when this one is.
+            let hides = |outer: &Scope| match outer.slots.get(&name) {
+                Some(&slot) => match (outer.declared[slot].line, line) {
+                    (Some(before), Some(here)) => before <= here,
+                    _ => true,
+                },
+                None => false,
+            };
+            if self.scopes.iter().any(hides) {
+                self.warn(line, format!("{} '{}' shadows one from an outer scope", kind.describe(), name));
+            }
+            scope.slots.insert(name.clone(), scope.declared.len());
+            scope.declared.push(Declared { name, kind, line, reads: 0, minted: None });
+        }
+        self.scopes.push(scope);
+    }
+
+    fn leave(&mut self) {
+        let Some(scope) = self.scopes.pop() else { return };
+        for declared in scope.declared {
+            let token = matches!(declared.kind, Kind::Token | Kind::Block);
+            if token && declared.reads == 0 && !declared.name.starts_with('_') {
+                let message = format!("{} '{}' is never read", declared.kind.describe(), declared.name);
+                self.warn(declared.line, message);
+            }
+        }
+    }
+
+    fn warn(&mut self, line: Option<usize>, message: String) {
+        self.warnings.push(Warning { line, message });
+    }
+
+    // The scope depth (0 is the innermost) and slot a name resolves to.
+    fn lookup(&mut self, name: &str, read: bool) -> Option<(usize, usize)> {
+        for (depth, scope) in self.scopes.iter_mut().rev().enumerate() {
+            if let Some(&slot) = scope.slots.get(name) {
+                if read {
+                    scope.declared[slot].reads += 1;
+                }
+                return Some((depth, slot));
+            }
+        }
+        None
+    }
+
+    fn undefined(name: &str) -> Rekt {
+        Rekt::Type(format!("Token '{}' is never minted in any scope it could come from", name))
+    }
+
+    fn visit(&mut self, ast: &mut Ast) -> Result<(), Rekt> {
+        match ast {
+            Ast::Located { line, statement } => {
+                let outer = self.line.replace(*line);
+                let result = self.visit(statement).map_err(|e| e.at_line(*line));
+                self.line = outer;
+                result
+            }
+            Ast::Program(stmts) | Ast::Block(stmts) => self.scoped(Vec::new(), stmts),
+
+            Ast::Variable(name) => {
+                let (depth, slot) = self.lookup(name, true).ok_or_else(|| Self::undefined(name))?;
+                *ast = Ast::ResolvedVariable { name: std::mem::take(name), depth, slot };
+                Ok(())
+            }
+            // A named callee stays a name so the engines can still fall back
+            // to the builtin of that name
+            Ast::Call { callee, arguments } => {
+                match callee.as_mut() {
+                    Ast::Variable(name) => {
+                        if self.lookup(name, true).is_none() && !BUILTINS.contains(&name.as_str()) {
+                            return Err(Rekt::Type(format!("Unknown smart contract '{}'", name)));
+                        }
+                    }
+                    other => self.visit(other)?,
+                }
+                arguments.iter_mut().try_for_each(|arg| self.visit(arg))
+            }
+
+            Ast::FunctionDecl { params, body, .. } | Ast::Lambda { params, body, .. } => {
+                let bound = params.iter().map(|(param, _)| (param.clone(), Kind::Param)).collect();
+                self.scoped(bound, body)
+            }
+            Ast::ForEach { key, variable, iterable, body } => {
+                self.visit(iterable)?;
+                let bound = key.iter().chain([&*variable]).map(|name| (name.clone(), Kind::Binding)).collect();
+                self.scoped(bound, body)
+            }
+            Ast::ForRange { variable, start, end, step, body } => {
+                self.visit(start)?;
+                self.visit(end)?;
+                if let Some(step) = step {
+                    self.visit(step)?;
+                }
+                self.scoped(vec![(variable.clone(), Kind::Binding)], body)
+            }
+            Ast::Match { scrutinee, arms } => {
+                self.visit(scrutinee)?;
+                for (pattern, body) in arms {
+                    let mut names = Vec::new();
+                    pattern.bindings(&mut names);
+                    let bound = names.into_iter().map(|name| (name, Kind::Binding)).collect();
+                    self.scoped(bound, std::slice::from_mut(body))?;
+                }
+                Ok(())
+            }
+
+            // Assignment targets keep their root name; writing to a token
+            // doesn't count as reading it
+            Ast::Assign { name, value } => {
+                self.lookup(name, false).ok_or_else(|| Self::undefined(name))?;
+                self.visit(value)
+            }
+            Ast::SetIndex { object, index, value } => {
+                self.target(object)?;
+                self.visit(index)?;
+                self.visit(value)
+            }
+            Ast::SetField { object: target, value, .. } | Ast::CompoundAssign { target, value, .. } => {
+                self.target(target)?;
+                self.visit(value)
+            }
+            Ast::Delete(target) => self.target(target),
+
+            // Minted inside a `sus` or `grind` body, which might not run, so
+            // an earlier declaration may still be read
+            Ast::VariableDecl { name, initializer, .. } => {
+                self.visit(initializer)?;
+                if let Some(declared) = self.declared(name) {
+                    declared.minted = None;
+                }
+                Ok(())
+            }
+
+            other => other.children_mut().into_iter().try_for_each(|child| self.visit(child)),
+        }
+    }
+
+    fn target(&mut self, ast: &mut Ast) -> Result<(), Rekt> {
+        match ast {
+            Ast::Variable(name) => self.lookup(name, false).map(|_| ()).ok_or_else(|| Self::undefined(name)),
+            Ast::Grouping(inner) => self.target(inner),
+            Ast::Index { object, index } => {
+                self.target(object)?;
+                self.visit(index)
+            }
+            Ast::Field { object, .. } => self.target(object),
+            other => self.visit(other),
+        }
+    }
+}
+
+// The names a statement list declares into its own scope, in order. `sus`
+// and `grind` bodies run in the same scope, so their declarations count.
+fn collect(stmts: &[Ast], line: Option<usize>, out: &mut Vec<(String, Kind, Option<usize>)>) {
+    for stmt in stmts {
+        collect_one(stmt, line, out);
+    }
+}
+
+fn collect_one(stmt: &Ast, line: Option<usize>, out: &mut Vec<(String, Kind, Option<usize>)>) {
+    match stmt {
+        Ast::Located { line, statement } => collect_one(statement, Some(*line), out),
+        Ast::VariableDecl { name, is_constant, .. } => {
+            let kind = if *is_constant { Kind::Block } else { Kind::Token };
+            out.push((name.clone(), kind, line));
+        }
+        Ast::FunctionDecl { name, .. } => out.push((name.clone(), Kind::Contract, line)),
+        Ast::If { then_branch, else_branch, .. } => {
+            collect(then_branch, line, out);
+            if let Some(else_branch) = else_branch {
+                collect(else_branch, line, out);
+            }
+        }
+        Ast::While { body, .. } => collect(body, line, out),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn warnings(source: &str) -> Vec<String> {
+        let (tokens, lines) = Lexer::new(source).tokenize_with_lines().unwrap();
+        let ast = Parser::with_lines(tokens, lines).parse().unwrap();
+        resolve(ast).unwrap().1.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn shadowing_needs_an_earlier_declaration() {
+        let later = "mine f(x) { return x; }\ntoken x = 1;\nping f(x);";
+        assert!(warnings(later).is_empty(), "{:?}", warnings(later));
+        let earlier = "token y = 2;\nmine g(y) { return y; }\nping g(y);";
+        assert_eq!(warnings(earlier), vec!["line 2: param 'y' shadows one from an outer scope"]);
+    }
+
+    #[test]
+    fn unread_tokens_are_reported() {
+        assert_eq!(warnings("token a = 1;\ntoken _b = 2;"), vec!["line 1: token 'a' is never read"]);
+    }
+
+    #[test]
+    fn tokens_minted_again_unread_are_reported() {
+        assert_eq!(
+            warnings("token x = 1;\ntoken x = 2;\nping x;"),
+            vec!["line 1: token 'x' is minted again at line 2 before it is read"]
+        );
+        // Reading it in the new value, or only maybe replacing it, is fine
+        assert!(warnings("token x = 1;\ntoken x = x + 1;\nping x;").is_empty());
+        assert!(warnings("token x = 1;\nsus (true) { token x = 2; }\nping x;").is_empty());
+        assert!(warnings("sus (true) { token y = 1; } rekt { token y = 2; }\nping y;").is_empty());
+    }
+}
@@ -1,22 +1,165 @@
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use colored::*;
 
 // Import the new Rekt instead of LoveError
 use crate::error::Rekt;  
+use crate::checker::Checker;
 use crate::interpreter::Interpreter;
 use crate::lexer::Lexer;
+use crate::optimizer::optimize;
 use crate::parser::Parser;
+use crate::parser::ast::Ast;
+use crate::parser::json;
+use crate::resolver::{resolve, Warning};
+use crate::shared_types::Value;
+use crate::transpile::{javascript, rust, solidity};
+use crate::vm::{compile, decode, disassemble, encode, header, Program, Vm};
+use crate::wasm;
+use crate::evm;
 use crate::fun::*;  // Assuming the fun module is still relevant
 
+// Which engine runs a checked script. Both behave the same; the tree walker
+// stays around as the reference the VM is checked against.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Engine {
+    TreeWalker,
+    Vm,
+}
+
+// What one engine made of a script: everything it broadcast, and the error
+// it stopped on, if any.
+#[derive(Debug, PartialEq)]
+pub struct Outcome {
+    pub events: Vec<String>,
+    pub error: Option<String>,
+}
+
+impl Outcome {
+    // Keeps only the first line of the error, for backends that can't say
+    // which contracts it passed through.
+    pub fn headline(self) -> Outcome {
+        Outcome { error: self.error.map(|e| first_line(&e)), ..self }
+    }
+}
+
+// A script run or written two ways, which can say what didn't line up.
+pub trait Comparison {
+    // What disagreed, a line each; empty when everything agrees.
+    fn mismatches(&self) -> Vec<String>;
+    // How many events the script broadcast, if the comparison ran it.
+    fn events(&self) -> Option<usize>;
+}
+
+// Lines for two outcomes that should have been the same. Labels are
+// padded so the two line up.
+pub fn outcome_mismatch(left: (&str, &Outcome), right: (&str, &Outcome)) -> Vec<String> {
+    if left.1 == right.1 {
+        return Vec::new();
+    }
+    let width = left.0.len().max(right.0.len()) + 1;
+    vec![
+        format!("{:<width$} {:?}", format!("{}:", left.0), left.1, width = width),
+        format!("{:<width$} {:?}", format!("{}:", right.0), right.1, width = width),
+    ]
+}
+
+// A comparison that didn't apply to a script, such as one a backend can't
+// compile, has nothing to disagree about.
+impl<C: Comparison> Comparison for Option<C> {
+    fn mismatches(&self) -> Vec<String> {
+        self.as_ref().map(C::mismatches).unwrap_or_default()
+    }
+
+    fn events(&self) -> Option<usize> {
+        self.as_ref().and_then(C::events)
+    }
+}
+
+// How what a backend wrote compares with the golden checked in next to the
+// script. Every script in a backend's own examples directory needs one.
+#[derive(Debug, PartialEq)]
+pub enum Golden {
+    Agrees,
+    OutOfDate { extension: &'static str, flag: &'static str },
+    Missing { extension: &'static str, flag: &'static str },
+    Unchecked, // the scripts in examples/diff, which every backend shares
+}
+
+impl Golden {
+    // Checks `written` against the golden .`extension` next to `script`;
+    // `flag` is the one that rewrites it.
+    pub fn check(script: &Path, extension: &'static str, flag: &'static str, written: &str) -> Golden {
+        match fs::read_to_string(script.with_extension(extension)) {
+            Ok(golden) if golden == written => Golden::Agrees,
+            Ok(_) => Golden::OutOfDate { extension, flag },
+            Err(_) => Golden::Missing { extension, flag },
+        }
+    }
+
+    pub fn mismatch(&self) -> Option<String> {
+        match self {
+            Golden::OutOfDate { extension, flag } => {
+                Some(format!("golden .{} is out of date (rewrite it with {})", extension, flag))
+            }
+            Golden::Missing { extension, flag } => Some(format!("no golden .{} next to it (write one with {})", extension, flag)),
+            Golden::Agrees | Golden::Unchecked => None,
+        }
+    }
+}
+
+// What the tree walker and a backend made of the same script, and how the
+// backend's output compares with its golden.
+pub struct BackendComparison {
+    pub backend: &'static str, // what ran the output: node, native, wasm or evm
+    pub tree_walker: Outcome,
+    pub outcome: Outcome,
+    pub golden: Golden,
+    pub notes: Vec<String>, // anything else the backend's own checks turned up
+}
+
+impl BackendComparison {
+    // The same comparison without the golden, for a script in examples/diff.
+    pub fn shared(self) -> BackendComparison {
+        BackendComparison { golden: Golden::Unchecked, ..self }
+    }
+}
+
+impl Comparison for BackendComparison {
+    fn mismatches(&self) -> Vec<String> {
+        let mut lines = outcome_mismatch(("tree walker", &self.tree_walker), (self.backend, &self.outcome));
+        lines.extend(self.notes.iter().cloned());
+        lines.extend(self.golden.mismatch());
+        lines
+    }
+
+    fn events(&self) -> Option<usize> {
+        Some(self.tree_walker.events.len())
+    }
+}
+
+// Average time per run of a script on each engine.
+pub struct Timings {
+    pub tree_walker: Duration,
+    pub compile: Duration,
+    pub vm: Duration,
+}
+
 pub struct Runner {
+    checker: Checker,
     interpreter: Interpreter,
+    vm: Vm,
+    engine: Engine,
 }
 
 impl Runner {
-    pub fn new() -> Self {
+    pub fn with_engine(engine: Engine) -> Self {
         Runner {
+            checker: Checker::new(),
             interpreter: Interpreter::new(),
+            vm: Vm::new(),
+            engine,
         }
     }
 
@@ -24,16 +167,20 @@ impl Runner {
         let path = path.as_ref();
         
         // Check file extension
-        if let Some(extension) = path.extension() {
-            if extension != "gm" {  // Changed to .gm extension for "Gm"
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("gm") => {}  // Changed to .gm extension for "Gm"
+            Some("gmc") => return self.run_compiled(path),
+            Some("json") => return self.run_tree(path),
+            Some(_) => {
                 return Err(Rekt::Runtime(
-                    "Only .gm files can contain our gm story! 💚".to_string()
+                    "Only .gm files (or compiled .gmc ones, or .json trees) can contain our gm story! 💚".to_string()
+                ));
+            }
+            None => {
+                return Err(Rekt::Runtime(
+                    "File must have a .gm, .gmc or .json extension! 💚".to_string()
                 ));
             }
-        } else {
-            return Err(Rekt::Runtime(
-                "File must have a .gm extension! 💚".to_string()
-            ));
         }
 
         // Read file content
@@ -50,7 +197,7 @@ impl Runner {
 
         // Execute the code
         let mut lexer = Lexer::new(&content);
-        let tokens = match lexer.tokenize() {
+        let (tokens, lines) = match lexer.tokenize_with_lines() {
             Ok(t) => t,
             Err(e) => {
                 println!("{}", create_gm_border(
@@ -60,7 +207,7 @@ impl Runner {
             }
         };
 
-        let mut parser = Parser::new(tokens);
+        let mut parser = Parser::with_lines(tokens, lines);
         let ast = match parser.parse() {
             Ok(a) => a,
             Err(e) => {
@@ -71,23 +218,558 @@ impl Runner {
             }
         };
 
-        match self.interpreter.interpret(ast) {
-            Ok(_) => {
+        self.run_checked(ast)
+    }
+
+    // Checks, resolves and runs a tree from a .json file, as if it had just
+    // been parsed.
+    fn run_tree(&mut self, path: &Path) -> Result<(), Rekt> {
+        let ast = match read_tree(path) {
+            Ok(ast) => ast,
+            Err(e) => {
                 println!("{}", create_gm_border(
-                    &format!("{} Gm story executed successfully!", get_random_emoji())
-                ).bright_green());
-                Ok(())
-            },
+                    &format!("💔 Load error:\n{}", format_error(&e))
+                ).bright_red());
+                return Err(e);
+            }
+        };
+
+        println!("{}", create_gm_border(
+            &format!("💌 Reading gm story tree from: {}", path.display())
+        ).bright_cyan());
+        println!("{}", "💚 Gm story output:".bright_yellow());
+        println!();
+
+        self.run_checked(ast)
+    }
+
+    fn run_checked(&mut self, ast: Ast) -> Result<(), Rekt> {
+        if let Err(e) = self.checker.check(&ast) {
+            println!("{}", create_gm_border(
+                &format!("💔 Type error:\n{}", format_error(&e))
+            ).bright_red());
+            return Err(e);
+        }
+
+        // Warnings are for --check; a run only needs the bindings
+        let ast = match resolve(ast) {
+            Ok((ast, _)) => ast,
+            Err(e) => {
+                println!("{}", create_gm_border(
+                    &format!("💔 Resolve error:\n{}", format_error(&e))
+                ).bright_red());
+                return Err(e);
+            }
+        };
+
+        let ast = optimize(ast);
+        let result = match self.engine {
+            Engine::TreeWalker => self.interpreter.interpret(ast),
+            Engine::Vm => self.vm.interpret(&ast),
+        };
+        report(result)
+    }
+
+    // Runs a .gmc file. There's no tree in it, so only the VM can.
+    fn run_compiled(&mut self, path: &Path) -> Result<(), Rekt> {
+        if self.engine == Engine::TreeWalker {
+            return Err(Rekt::Runtime(
+                "Compiled .gmc files only run on the VM; run the .gm script with --tree instead 💚".to_string()
+            ));
+        }
+        let program = match read_compiled(path) {
+            Ok(program) => program,
             Err(e) => {
                 println!("{}", create_gm_border(
-                    &format!("💔 Runtime error:\n{}", format_error(&e))
+                    &format!("💔 Load error:\n{}", format_error(&e))
                 ).bright_red());
-                Err(e)
+                return Err(e);
             }
+        };
+
+        println!("{}", create_gm_border(
+            &format!("💌 Reading compiled gm story from: {}", path.display())
+        ).bright_cyan());
+        println!("{}", "💚 Gm story output:".bright_yellow());
+        println!();
+
+        report(self.vm.run(program))
+    }
+}
+
+fn report(result: Result<Value, Rekt>) -> Result<(), Rekt> {
+    match result {
+        Ok(_) => {
+            println!("{}", create_gm_border(
+                &format!("{} Gm story executed successfully!", get_random_emoji())
+            ).bright_green());
+            Ok(())
+        },
+        Err(e) => {
+            println!("{}", create_gm_border(
+                &format!("💔 Runtime error:\n{}", format_error(&e))
+            ).bright_red());
+            Err(e)
+        }
+    }
+}
+
+fn read_compiled(path: &Path) -> Result<Program, Rekt> {
+    let bytes = fs::read(path)
+        .map_err(|e| Rekt::Runtime(format!("Failed to read compiled gm letter: {}", e)))?;
+    decode(&bytes)
+}
+
+// Compiles a script to a .gmc file, next to the script unless `out` says
+// where, and returns where it went.
+pub fn compile_file<P: AsRef<Path>>(path: P, out: Option<&str>) -> Result<PathBuf, Rekt> {
+    let path = path.as_ref();
+    let program = compile(&optimize(load(path)?))?;
+    let bytes = encode(&program)?;
+    let out = out.map(PathBuf::from).unwrap_or_else(|| path.with_extension("gmc"));
+    fs::write(&out, bytes)
+        .map_err(|e| Rekt::Runtime(format!("Failed to write {}: {}", out.display(), e)))?;
+    Ok(out)
+}
+
+// A bytecode listing of a script, or of a .gmc file along with its header.
+pub fn disassemble_file<P: AsRef<Path>>(path: P) -> Result<String, Rekt> {
+    let path = path.as_ref();
+    if path.extension().and_then(|e| e.to_str()) != Some("gmc") {
+        return Ok(disassemble(&compile(&optimize(load(path)?))?));
+    }
+    let bytes = fs::read(path)
+        .map_err(|e| Rekt::Runtime(format!("Failed to read compiled gm letter: {}", e)))?;
+    let header = header(&bytes)?;
+    Ok(format!(
+        "; .gmc format v{}, {} byte body, checksum {:08x}\n{}",
+        header.version,
+        header.length,
+        header.checksum,
+        disassemble(&decode(&bytes)?)
+    ))
+}
+
+// Reads, parses, checks and resolves a script without running it.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Ast, Rekt> {
+    Ok(load_with_warnings(path.as_ref())?.0)
+}
+
+fn load_with_warnings(path: &Path) -> Result<(Ast, Vec<Warning>), Rekt> {
+    let ast = parse_file(path)?;
+    Checker::new().check(&ast)?;
+    resolve(ast)
+}
+
+// The tree a script parses to, or the one a .json file holds.
+fn parse_file(path: &Path) -> Result<Ast, Rekt> {
+    if path.extension().and_then(|e| e.to_str()) == Some("json") {
+        return read_tree(path);
+    }
+    let content = fs::read_to_string(path)
+        .map_err(|e| Rekt::Runtime(format!("Failed to read gm letter: {}", e)))?;
+    let (tokens, lines) = Lexer::new(&content).tokenize_with_lines()?;
+    Parser::with_lines(tokens, lines).parse()
+}
+
+fn read_tree(path: &Path) -> Result<Ast, Rekt> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| Rekt::Runtime(format!("Failed to read gm story tree: {}", e)))?;
+    json::from_json(&text)
+}
+
+// Writes the tree a script parses to as JSON, next to the script unless
+// `out` says where, and returns where it went. The tree is checked but
+// not resolved, so it holds what the source says and nothing worked out
+// from it.
+pub fn dump_ast_file<P: AsRef<Path>>(path: P, out: Option<&str>) -> Result<PathBuf, Rekt> {
+    let path = path.as_ref();
+    let ast = parse_file(path)?;
+    Checker::new().check(&ast)?;
+    let out = out.map(PathBuf::from).unwrap_or_else(|| path.with_extension("json"));
+    fs::write(&out, json::to_json(&ast)?)
+        .map_err(|e| Rekt::Runtime(format!("Failed to write {}: {}", out.display(), e)))?;
+    Ok(out)
+}
+
+// What a script did, and what its tree did once written as JSON and read back
+pub struct AstComparison {
+    pub round_trips: bool, // whether the tree read back is the tree written
+    pub script: Outcome,
+    pub tree: Outcome,
+    pub golden: Golden,
+}
+
+// Writes a script's tree as JSON, reads it back and runs both on the tree
+// walker.
+pub fn compare_ast_file<P: AsRef<Path>>(path: P) -> Result<AstComparison, Rekt> {
+    let path = path.as_ref();
+    let ast = parse_file(path)?;
+    let text = json::to_json(&ast)?;
+    let golden = Golden::check(path, "json", "--ast", &text);
+    let read_back = json::from_json(&text)?;
+    let round_trips = read_back == ast;
+    Ok(AstComparison { round_trips, script: run_tree_walker(ast), tree: run_tree_walker(read_back), golden })
+}
+
+impl Comparison for AstComparison {
+    fn mismatches(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if !self.round_trips {
+            lines.push("the tree read back from JSON isn't the tree written".to_string());
         }
+        lines.extend(outcome_mismatch(("script", &self.script), ("tree", &self.tree)));
+        lines.extend(self.golden.mismatch());
+        lines
+    }
+
+    fn events(&self) -> Option<usize> {
+        Some(self.script.events.len())
+    }
+}
+
+// Checks, resolves and walks a tree that hasn't been loaded yet, so a
+// check error counts as the outcome.
+fn run_tree_walker(ast: Ast) -> Outcome {
+    let checked = Checker::new().check(&ast).and_then(|()| resolve(ast));
+    match checked {
+        Ok((ast, _)) => walk(ast).headline(),
+        Err(e) => Outcome { events: Vec::new(), error: Some(first_line(&e.to_string())) },
     }
 }
 
+// Runs a loaded tree on the tree walker, quietly.
+pub fn walk(ast: Ast) -> Outcome {
+    let mut interpreter = Interpreter::new();
+    interpreter.record_events();
+    let error = interpreter.interpret(ast).err().map(|e| e.to_string());
+    Outcome { events: interpreter.take_events(), error }
+}
+
+// Everything the static passes have to say about a script.
+pub fn check_file<P: AsRef<Path>>(path: P) -> Result<Vec<Warning>, Rekt> {
+    Ok(load_with_warnings(path.as_ref())?.1)
+}
+
+// The tree a script runs as, once the optimizer is done with it.
+pub fn optimized_tree<P: AsRef<Path>>(path: P) -> Result<Ast, Rekt> {
+    Ok(optimize(load(path.as_ref())?))
+}
+
+// What the tree walker and what the VM made of the same script
+pub struct VmComparison {
+    pub tree_walker: Outcome,
+    pub vm: Outcome,
+}
+
+impl Comparison for VmComparison {
+    fn mismatches(&self) -> Vec<String> {
+        outcome_mismatch(("tree walker", &self.tree_walker), ("vm", &self.vm))
+    }
+
+    fn events(&self) -> Option<usize> {
+        Some(self.tree_walker.events.len())
+    }
+}
+
+// Runs a script on the tree walker and on the VM, quietly, so the two
+// outcomes can be compared. The tree walker gets the tree as resolved and
+// the VM gets it optimized, so this checks the optimizer too.
+pub fn compare_file<P: AsRef<Path>>(path: P) -> Result<VmComparison, Rekt> {
+    let ast = load(path.as_ref())?;
+    let tree_walker = walk(ast.clone());
+
+    let mut vm = Vm::new();
+    vm.record_events();
+    let error = vm.interpret(&optimize(ast)).err().map(|e| e.to_string());
+    let compiled = Outcome { events: vm.take_events(), error };
+
+    Ok(VmComparison { tree_walker, vm: compiled })
+}
+
+// Transpiles a script to an ES module, next to the script unless `out`
+// says where, with the runtime it imports beside it. Returns where the
+// module went.
+pub fn transpile_js_file<P: AsRef<Path>>(path: P, out: Option<&str>) -> Result<PathBuf, Rekt> {
+    let path = path.as_ref();
+    let module = javascript::transpile(&load(path)?, &source_name(path))?;
+    let out = out.map(PathBuf::from).unwrap_or_else(|| path.with_extension("js"));
+    write_module(&out, &module)?;
+    Ok(out)
+}
+
+// Runs a script on the tree walker and its transpiled module on node. Only
+// the first line of an error is compared, since the module can't say
+// which contracts it was inside.
+pub fn compare_js_file<P: AsRef<Path>>(path: P) -> Result<BackendComparison, Rekt> {
+    let path = path.as_ref();
+    let ast = load(path)?;
+    let module = javascript::transpile(&ast, &source_name(path))?;
+    let golden = Golden::check(path, "js", "--js", &module);
+
+    Ok(BackendComparison {
+        backend: "node",
+        tree_walker: walk(ast).headline(),
+        outcome: run_node(&module)?,
+        golden,
+        notes: Vec::new(),
+    })
+}
+
+// Transpiles a script to a Solidity contract, next to the script unless
+// `out` says where. Returns where the contract went.
+pub fn transpile_sol_file<P: AsRef<Path>>(path: P, out: Option<&str>) -> Result<PathBuf, Rekt> {
+    let path = path.as_ref();
+    let contract = solidity::transpile(&load(path)?, &source_name(path))?;
+    let out = out.map(PathBuf::from).unwrap_or_else(|| path.with_extension("sol"));
+    fs::write(&out, contract).map_err(|e| Rekt::Runtime(format!("Failed to write {}: {}", out.display(), e)))?;
+    Ok(out)
+}
+
+// What the Solidity transpiler made of a script (the contract, or the
+// first line of why not) and what its golden file says it should.
+pub struct SolComparison {
+    pub written: Result<String, String>,
+    pub golden: Option<Result<String, String>>,
+}
+
+// Goldens sit next to the script: a .sol for a contract, a .err holding
+// the diagnostic for a script Solidity can't express. Nothing here needs
+// a Solidity compiler.
+pub fn compare_sol_file<P: AsRef<Path>>(path: P) -> Result<SolComparison, Rekt> {
+    let path = path.as_ref();
+    let written = solidity::transpile(&load(path)?, &source_name(path)).map_err(|e| first_line(&e.to_string()));
+    let golden = match fs::read_to_string(path.with_extension("sol")) {
+        Ok(contract) => Some(Ok(contract)),
+        Err(_) => fs::read_to_string(path.with_extension("err")).ok().map(|e| Err(first_line(&e))),
+    };
+    Ok(SolComparison { written, golden })
+}
+
+impl Comparison for SolComparison {
+    fn mismatches(&self) -> Vec<String> {
+        let line = match (&self.golden, &self.written) {
+            (Some(golden), written) if golden == written => return Vec::new(),
+            (None, _) => "no golden .sol or .err next to it".to_string(),
+            (Some(Ok(_)), Ok(_)) => "golden .sol is out of date (rewrite it with --sol)".to_string(),
+            (Some(Ok(_)), Err(e)) => format!("expected a contract, but: {}", e),
+            (Some(Err(expected)), Ok(_)) => format!("expected \"{}\", but it transpiled", expected),
+            (Some(Err(expected)), Err(e)) => format!("expected \"{}\", but got \"{}\"", expected, e),
+        };
+        vec![line]
+    }
+
+    fn events(&self) -> Option<usize> {
+        None
+    }
+}
+
+// Transpiles a script to a Rust module for the gm-runtime crate, next to
+// the script unless `out` says where. Returns where the module went.
+pub fn transpile_rs_file<P: AsRef<Path>>(path: P, out: Option<&str>) -> Result<PathBuf, Rekt> {
+    let path = path.as_ref();
+    let module = rust::transpile(&load(path)?, &source_name(path))?;
+    let out = out.map(PathBuf::from).unwrap_or_else(|| path.with_extension("rs"));
+    fs::write(&out, module).map_err(|e| Rekt::Runtime(format!("Failed to write {}: {}", out.display(), e)))?;
+    Ok(out)
+}
+
+// Runs a script on the tree walker and its transpiled module built with
+// cargo. Only the first line of an error is compared, as with node.
+pub fn compare_rs_file<P: AsRef<Path>>(path: P) -> Result<BackendComparison, Rekt> {
+    let path = path.as_ref();
+    let ast = load(path)?;
+    let module = rust::transpile(&ast, &source_name(path))?;
+    let golden = Golden::check(path, "rs", "--rs", &module);
+
+    Ok(BackendComparison {
+        backend: "native",
+        tree_walker: walk(ast).headline(),
+        outcome: run_native(&module)?,
+        golden,
+        notes: Vec::new(),
+    })
+}
+
+// Compiles a script to a WebAssembly module, written as binary to `out`
+// (next to the script by default) with its text beside it as .wat.
+// Returns where the binary went.
+pub fn compile_wasm_file<P: AsRef<Path>>(path: P, out: Option<&str>) -> Result<PathBuf, Rekt> {
+    let path = path.as_ref();
+    let module = wasm::compile(&load(path)?)?;
+    let out = out.map(PathBuf::from).unwrap_or_else(|| path.with_extension("wasm"));
+    let text = out.with_extension("wat");
+    fs::write(&out, wasm::encode(&module))
+        .and_then(|_| fs::write(&text, module.to_wat()))
+        .map_err(|e| Rekt::Runtime(format!("Failed to write {}: {}", out.display(), e)))?;
+    Ok(out)
+}
+
+// Compiles a script to EVM bytecode, written as hex initcode to `out`
+// (next to the script by default) with its listing beside it as .asm.
+// Returns where the bytecode went.
+pub fn compile_evm_file<P: AsRef<Path>>(path: P, out: Option<&str>) -> Result<PathBuf, Rekt> {
+    let path = path.as_ref();
+    let compiled = evm::compile(&load(path)?)?;
+    let out = out.map(PathBuf::from).unwrap_or_else(|| path.with_extension("bin"));
+    let listing = out.with_extension("asm");
+    let hex: String = compiled.initcode().iter().map(|b| format!("{:02x}", b)).collect();
+    fs::write(&out, hex + "\n")
+        .and_then(|_| fs::write(&listing, compiled.listing()))
+        .map_err(|e| Rekt::Runtime(format!("Failed to write {}: {}", out.display(), e)))?;
+    Ok(out)
+}
+
+fn source_name(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+fn first_line(text: &str) -> String {
+    text.lines().next().unwrap_or_default().to_string()
+}
+
+fn write_module(out: &Path, module: &str) -> Result<(), Rekt> {
+    let runtime = out.with_file_name(javascript::RUNTIME_FILE);
+    fs::write(out, module)
+        .and_then(|_| fs::write(&runtime, javascript::RUNTIME))
+        .map_err(|e| Rekt::Runtime(format!("Failed to write {}: {}", out.display(), e)))
+}
+
+// Loads the module in node and hands back what it broadcast and what it
+// threw. Both come back hex-encoded a line each, so no text the script
+// prints can be mistaken for the next line.
+const NODE_DRIVER: &str = r#"
+const hex = (text) => Buffer.from(text, "utf8").toString("hex");
+const { default: run } = await import(process.argv[1]);
+try {
+  run((text) => console.log("E " + hex(text)));
+} catch (e) {
+  console.log("X " + hex(e && e.name === "GmError" ? String(e) : `JavaScript error: ${e}`));
+}
+"#;
+
+fn run_node(module: &str) -> Result<Outcome, Rekt> {
+    let dir = std::env::temp_dir().join(format!("gm-js-{}", std::process::id()));
+    fs::create_dir_all(&dir)
+        .map_err(|e| Rekt::Runtime(format!("Failed to create {}: {}", dir.display(), e)))?;
+    let file = dir.join("script.js");
+    write_module(&file, module)?;
+    let url = format!("file://{}", file.display());
+    let output = std::process::Command::new("node")
+        .args(["--input-type=module", "-e", NODE_DRIVER, &url])
+        .output()
+        .map_err(|e| Rekt::Runtime(format!("Couldn't run node, which --diff-js needs: {}", e)));
+    let _ = fs::remove_dir_all(&dir);
+    let output = output?;
+    if !output.status.success() {
+        return Err(Rekt::Runtime(format!(
+            "node failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(read_outcome(&output.stdout))
+}
+
+// Builds a module as a binary of a scratch cargo package that depends on
+// the runtime in this tree, and reads back what it printed the same way
+// as from node. The build directory is shared between runs, so only the
+// first one pays for compiling the runtime.
+const NATIVE_DRIVER: &str = r#"
+fn hex(text: &str) -> String {
+    text.bytes().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn main() {
+    if let Err(e) = run(|text, _| println!("E {}", hex(text))) {
+        println!("X {}", hex(&e.to_string()));
+    }
+}
+"#;
+
+fn run_native(module: &str) -> Result<Outcome, Rekt> {
+    let scratch = std::env::temp_dir().join("gm-rs");
+    let dir = scratch.join(format!("run-{}", std::process::id()));
+    let manifest = format!(
+        "[package]\nname = \"gm-script\"\nversion = \"0.0.0\"\nedition = \"2021\"\n\n\
+         [dependencies]\ngm-runtime = {{ path = {:?} }}\n\n[workspace]\n",
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("runtime").display().to_string()
+    );
+    let written = fs::create_dir_all(dir.join("src"))
+        .and_then(|_| fs::write(dir.join("Cargo.toml"), manifest))
+        .and_then(|_| fs::write(dir.join("src").join("main.rs"), format!("{}{}", module, NATIVE_DRIVER)));
+    if let Err(e) = written {
+        let _ = fs::remove_dir_all(&dir);
+        return Err(Rekt::Runtime(format!("Failed to write {}: {}", dir.display(), e)));
+    }
+    let output = std::process::Command::new("cargo")
+        .args(["run", "--offline", "--quiet"])
+        .env("CARGO_TARGET_DIR", scratch.join("target"))
+        .current_dir(&dir)
+        .output()
+        .map_err(|e| Rekt::Runtime(format!("Couldn't run cargo, which --diff-rs needs: {}", e)));
+    let _ = fs::remove_dir_all(&dir);
+    let output = output?;
+    if !output.status.success() {
+        return Err(Rekt::Runtime(format!(
+            "cargo failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(read_outcome(&output.stdout))
+}
+
+// Events and the error, hex-encoded a line each by one of the drivers.
+fn read_outcome(stdout: &[u8]) -> Outcome {
+    let mut outcome = Outcome { events: Vec::new(), error: None };
+    for line in String::from_utf8_lossy(stdout).lines() {
+        let (kind, text) = line.split_once(' ').unwrap_or((line, ""));
+        let text = unhex(text);
+        match kind {
+            "E" => outcome.events.push(text),
+            "X" => outcome.error = Some(first_line(&text)),
+            _ => {}
+        }
+    }
+    outcome
+}
+
+fn unhex(text: &str) -> String {
+    let bytes: Vec<u8> = (0..text.len() / 2)
+        .filter_map(|i| u8::from_str_radix(&text[i * 2..i * 2 + 2], 16).ok())
+        .collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+// Times `runs` quiet runs of a script on each engine. Compiling is timed
+// on its own so it's clear what the VM pays up front.
+pub fn bench_file<P: AsRef<Path>>(path: P, runs: u32) -> Result<Timings, Rekt> {
+    let ast = optimize(load(path.as_ref())?);
+    let runs = runs.max(1);
+
+    let start = Instant::now();
+    for _ in 0..runs {
+        let mut interpreter = Interpreter::new();
+        interpreter.record_events();
+        interpreter.interpret(ast.clone())?;
+    }
+    let tree_walker = start.elapsed() / runs;
+
+    let start = Instant::now();
+    let program = compile(&ast)?;
+    let compile_time = start.elapsed();
+
+    let start = Instant::now();
+    for _ in 0..runs {
+        let mut vm = Vm::new();
+        vm.record_events();
+        vm.run(program.clone())?;
+    }
+    let vm_time = start.elapsed() / runs;
+
+    Ok(Timings { tree_walker, compile: compile_time, vm: vm_time })
+}
+
 // Updated to Rekt handling
 fn format_error(error: &Rekt) -> String {
     match error {
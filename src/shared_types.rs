@@ -1,7 +1,11 @@
-#[allow(dead_code)]
-
+use std::collections::BTreeMap;
 use std::fmt;
+use std::rc::Rc;
 use crate::parser::ast::Ast;
+use crate::interpreter::Wallet;
+use crate::vm::Closure;
+use crate::numeric::{Decimal, U256};
+use gm_runtime::{GmValue, Key, View, ViewMut};
 
 #[derive(Debug, Clone, PartialEq)]
 #[allow(dead_code)]
@@ -16,15 +20,25 @@ pub enum Value {
     },
     Token(String),   // Add this
     Signal(bool),    // Add this
+    Int(U256),       // exact uint, for token amounts
+    Decimal(Decimal), // exact fixed-point, for prices and fees
+    Array(Vec<Value>),
+    Map(BTreeMap<Key, Value>), // Solidity-style mapping, ordered by key
+    Struct {
+        name: String,
+        fields: Vec<(String, Value)>, // in declaration order
+    },
     SmartContract {
         name: String,
         params: Vec<String>,
         body: Vec<Ast>,
+        scope: Wallet, // where it was mined; closures see this scope
     },
+    Closure(Rc<Closure>), // a contract compiled for the VM, with its captured tokens
     Null,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 
 #[allow(dead_code)]
 pub enum BinaryOp {
@@ -41,6 +55,42 @@ pub enum BinaryOp {
     And,           // and
     Or,            // or
     Not,           // not
+    Modulo,        // rake
+    IntDivide,     // chop
+    BitAnd,        // mask
+    BitOr,         // merge
+    BitXor,        // flip
+    ShiftLeft,     // pump
+    ShiftRight,    // dump
+    BitNot,        // invert
+}
+
+impl BinaryOp {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Subtract => "-",
+            BinaryOp::Multiply => "*",
+            BinaryOp::Divide => "/",
+            BinaryOp::Equal => "==",
+            BinaryOp::NotEqual => "!=",
+            BinaryOp::Less => "<",
+            BinaryOp::Greater => ">",
+            BinaryOp::LessEqual => "<=",
+            BinaryOp::GreaterEqual => ">=",
+            BinaryOp::And => "and",
+            BinaryOp::Or => "or",
+            BinaryOp::Not => "not",
+            BinaryOp::Modulo => "%",
+            BinaryOp::IntDivide => "~/",
+            BinaryOp::BitAnd => "&",
+            BinaryOp::BitOr => "|",
+            BinaryOp::BitXor => "^",
+            BinaryOp::ShiftLeft => "<<",
+            BinaryOp::ShiftRight => ">>",
+            BinaryOp::BitNot => "~",
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -51,6 +101,11 @@ pub enum Type {
     Function,
     Token,
     Signal,
+    Uint,
+    Decimal,
+    Array,
+    Map,
+    Struct(String),
 }
 
 impl fmt::Display for Type {
@@ -62,6 +117,11 @@ impl fmt::Display for Type {
             Type::Function => write!(f, "devotion"),
             Type::Token => write!(f, "vibe"),
             Type::Signal => write!(f, "signal"),
+            Type::Uint => write!(f, "uint"),
+            Type::Decimal => write!(f, "decimal"),
+            Type::Array => write!(f, "array"),
+            Type::Map => write!(f, "mapping"),
+            Type::Struct(name) => write!(f, "{}", name),
         }
     }
 }
@@ -78,7 +138,13 @@ impl fmt::Display for Value {
             Value::Null => write!(f, "lonely"),
             Value::Token(_) => write!(f, "vibe"),
             Value::Signal(_) => write!(f, "signal"),
+            Value::Int(_) => write!(f, "uint"),
+            Value::Decimal(_) => write!(f, "decimal"),
+            Value::Array(_) => write!(f, "array"),
+            Value::Map(_) => write!(f, "mapping"),
+            Value::Struct { name, .. } => write!(f, "{}", name),
             Value::SmartContract { .. } => write!(f, "smart contract"), // Handle SmartContract case
+            Value::Closure(_) => write!(f, "smart contract"),
         }
     }
 }
@@ -96,7 +162,170 @@ impl Value {
             Value::Null => Type::Number, // Maybe use Type::Null in the future?
             Value::Token(_) => Type::Token,
             Value::Signal(_) => Type::Signal,
+            Value::Int(_) => Type::Uint,
+            Value::Decimal(_) => Type::Decimal,
+            Value::Array(_) => Type::Array,
+            Value::Map(_) => Type::Map,
             Value::SmartContract { .. } => Type::Function, // Assuming SmartContract maps to Type::Function, adjust as needed
+            Value::Closure(_) => Type::Function,
+            Value::Struct { name, .. } => Type::Struct(name.clone()),
+        }
+    }
+
+    // Human-readable form used by broadcasts and the REPL. Text is shown bare
+    // at the top level and quoted once it's nested inside a collection.
+    pub fn render(&self) -> String {
+        match self {
+            Value::Text(s) => s.clone(),
+            other => other.render_nested(),
+        }
+    }
+
+    fn render_nested(&self) -> String {
+        match self {
+            Value::Number(n) => n.to_string(),
+            Value::Token(t) => t.clone(),
+            Value::Int(n) => n.to_string(),
+            Value::Decimal(d) => d.to_string(),
+            Value::Text(s) => format!("{:?}", s),
+            Value::Boolean(b) | Value::Signal(b) => b.to_string(),
+            Value::Function { name, .. } | Value::SmartContract { name, .. } => {
+                format!("<contract {}>", name)
+            }
+            Value::Closure(closure) => format!("<contract {}>", closure.name),
+            Value::Array(items) => {
+                let items: Vec<String> = items.iter().map(|v| v.render_nested()).collect();
+                format!("[{}]", items.join(", "))
+            }
+            Value::Map(entries) => {
+                let entries: Vec<String> = entries
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}", k.to_value::<Value>().render_nested(), v.render_nested()))
+                    .collect();
+                format!("{{{}}}", entries.join(", "))
+            }
+            Value::Struct { name, fields } => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(f, v)| format!("{}: {}", f, v.render_nested()))
+                    .collect();
+                if fields.is_empty() {
+                    format!("{} {{}}", name)
+                } else {
+                    format!("{} {{ {} }}", name, fields.join(", "))
+                }
+            }
+            Value::Null => "null".to_string(),
+        }
+    }
+}
+
+impl Type {
+    // Whether a runtime value fits a declared type. Number literals are
+    // carried as tokens, so both count as numbers, and so do uints and decimals.
+    pub fn accepts(&self, value: &Value) -> bool {
+        match (self, value) {
+            (Type::Number, Value::Number(_) | Value::Token(_) | Value::Int(_) | Value::Decimal(_)) => true,
+            (Type::Boolean, Value::Boolean(_) | Value::Signal(_)) => true,
+            (Type::Struct(expected), Value::Struct { name, .. }) => expected == name,
+            (expected, value) => *expected == value.get_type(),
+        }
+    }
+}
+
+// Tokens are the numbers arithmetic works on; a bare float only counts
+// where anything numeric does.
+impl GmValue for Value {
+    fn view(&self) -> View<'_, Value> {
+        match self {
+            Value::Token(t) => t.parse::<f64>().map(View::Vibe).unwrap_or(View::Other),
+            Value::Int(n) => View::Uint(*n),
+            Value::Decimal(d) => View::Decimal(*d),
+            Value::Text(s) => View::Text(s),
+            Value::Signal(b) | Value::Boolean(b) => View::Signal(*b),
+            Value::Array(items) => View::Array(items),
+            Value::Map(entries) => View::Map(entries),
+            Value::Struct { name, fields } => View::Struct(name, fields),
+            Value::SmartContract { .. } | Value::Closure(_) => View::Contract,
+            Value::Number(_) | Value::Function { .. } | Value::Null => View::Other,
+        }
+    }
+
+    fn view_mut(&mut self) -> ViewMut<'_, Value> {
+        match self {
+            Value::Array(items) => ViewMut::Array(items),
+            Value::Map(entries) => ViewMut::Map(entries),
+            Value::Struct { fields, .. } => ViewMut::Struct(fields),
+            _ => ViewMut::Other,
+        }
+    }
+
+    fn render(&self) -> String {
+        Value::render(self)
+    }
+
+    fn same_type(&self, other: &Value) -> bool {
+        self.get_type() == other.get_type()
+    }
+
+    fn as_number(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            other => other.view().number(),
         }
     }
-}
\ No newline at end of file
+
+    fn from_number(n: f64) -> Value {
+        Value::Token(n.to_string())
+    }
+
+    fn from_uint(n: U256) -> Value {
+        Value::Int(n)
+    }
+
+    fn from_decimal(d: Decimal) -> Value {
+        Value::Decimal(d)
+    }
+
+    fn from_text(s: String) -> Value {
+        Value::Text(s)
+    }
+
+    fn from_signal(b: bool) -> Value {
+        Value::Signal(b)
+    }
+
+    fn from_array(items: Vec<Value>) -> Value {
+        Value::Array(items)
+    }
+
+    fn from_map(entries: BTreeMap<Key, Value>) -> Value {
+        Value::Map(entries)
+    }
+
+    fn null() -> Value {
+        Value::Null
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(t: &str) -> Value {
+        Value::Token(t.to_string())
+    }
+
+    #[test]
+    fn tokens_are_numbers_to_the_shared_rules() {
+        assert_eq!(gm_runtime::binary(token("1.5"), "+", token("2")).unwrap(), token("3.5"));
+        assert_eq!(gm_runtime::binary(token("7"), "~/", Value::Int(U256::from_u64(2))).unwrap(), Value::Int(U256::from_u64(3)));
+        assert_eq!(Key::from_value(&token("1.0")).unwrap(), Key::Number(1.0));
+        assert!(gm_runtime::binary(token("1"), "+", Value::Null).is_err());
+    }
+
+    #[test]
+    fn booleans_read_as_signals() {
+        assert_eq!(gm_runtime::unary("not", Value::Boolean(true)).unwrap(), Value::Signal(false));
+    }
+}
@@ -0,0 +1,1290 @@
+// gm_runtime.js
+//
+// What transpiled GM scripts run on. Values map onto JavaScript like this:
+//
+//   number (vibe)   -> number        uint     -> bigint
+//   text            -> string        decimal  -> Decimal
+//   signal          -> boolean       array    -> Array
+//   lonely          -> null          mapping  -> Mapping
+//   struct          -> Record        contract -> function
+//
+// Every operator goes through here so it behaves exactly like the Rust
+// engines: uints are checked, decimals are exact and rounded half-even,
+// numbers print the way Rust prints an f64, and errors carry the same
+// messages. Collections are never changed in place; an assignment builds a
+// new value and stores it back, which is what gives GM its copy semantics.
+
+const EXACT_FLOAT = 9007199254740992; // 2^53
+const UINT_MAX = (1n << 256n) - 1n;
+const I128_MIN = -(1n << 127n);
+const I128_MAX = (1n << 127n) - 1n;
+const MAX_SCALE = 30;
+
+export class GmError extends Error {
+  constructor(kind, message) {
+    super(message);
+    this.name = "GmError";
+    this.kind = kind; // "Lexer", "Runtime" or "Type", like Rekt
+  }
+
+  toString() {
+    return `${this.kind} error: ${this.message}`;
+  }
+}
+
+const fail = (kind, message) => {
+  throw new GmError(kind, message);
+};
+
+// ---------------------------------------------------------------- numbers
+
+// An f64 the way Rust's Display writes it: shortest round-trip digits,
+// never in exponent form.
+export function showFloat(n) {
+  if (Number.isNaN(n)) return "NaN";
+  if (n === Infinity) return "inf";
+  if (n === -Infinity) return "-inf";
+  const sign = n < 0 || Object.is(n, -0) ? "-" : "";
+  const text = String(Math.abs(n));
+  const e = text.indexOf("e");
+  if (e < 0) return sign + text;
+  const exponent = Number(text.slice(e + 1));
+  const digits = text.slice(0, e).replace(".", "");
+  const point = text.slice(0, e).split(".")[0].length + exponent;
+  if (point <= 0) return `${sign}0.${"0".repeat(-point)}${digits}`;
+  if (point >= digits.length) return sign + digits + "0".repeat(point - digits.length);
+  return `${sign}${digits.slice(0, point)}.${digits.slice(point)}`;
+}
+
+// f64::total_cmp on two numbers.
+function totalCmp(a, b) {
+  if (Number.isNaN(a) || Number.isNaN(b)) {
+    return Number.isNaN(a) - Number.isNaN(b);
+  }
+  if (a !== b) return a < b ? -1 : 1;
+  return Object.is(a, -0) === Object.is(b, -0) ? 0 : Object.is(a, -0) ? -1 : 1;
+}
+
+// U256::to_f64, which rounds one 64-bit limb at a time.
+function uintToFloat(n) {
+  let acc = 0;
+  for (let shift = 192n; shift >= 0n; shift -= 64n) {
+    acc = acc * 18446744073709551616 + Number((n >> shift) & 0xffffffffffffffffn);
+  }
+  return acc;
+}
+
+// U256::from_f64: whole, non-negative and in range, or null.
+function uintFromFloat(n) {
+  if (!Number.isFinite(n) || n < 0 || n % 1 !== 0 || n >= 2 ** 256) return null;
+  return BigInt(n);
+}
+
+// U256::parse: decimal, or 0x/0b/0o digits, with `_` separators.
+function parseUint(text) {
+  text = text.trim();
+  let radix = 10;
+  const prefix = text.slice(0, 2);
+  if (prefix === "0x" || prefix === "0X") radix = 16;
+  else if (prefix === "0b") radix = 2;
+  else if (prefix === "0o") radix = 8;
+  if (radix !== 10) text = text.slice(2);
+  let value = 0n;
+  let seen = false;
+  for (const c of text) {
+    if (c === "_") continue;
+    const digit = parseInt(c, radix);
+    if (Number.isNaN(digit) || !/[0-9a-zA-Z]/.test(c)) return null;
+    value = value * BigInt(radix) + BigInt(digit);
+    if (value > UINT_MAX) return null;
+    seen = true;
+  }
+  return seen ? value : null;
+}
+
+// ---------------------------------------------------------------- decimals
+
+const ROUNDING = ["down", "up", "half_up", "half_even", "floor", "ceil"];
+
+const pow10 = (exp) => (exp <= 38 ? 10n ** BigInt(exp) : null);
+const fitsI128 = (n) => n >= I128_MIN && n <= I128_MAX;
+
+// Fixed-point `units / 10^scale` with an i128 for units. The scale shows
+// when printed but doesn't affect equality.
+export class Decimal {
+  constructor(units, scale) {
+    this.units = units;
+    this.scale = scale;
+  }
+
+  static make(units, scale) {
+    return units !== null && scale <= MAX_SCALE && fitsI128(units) ? new Decimal(units, scale) : null;
+  }
+
+  static parse(text) {
+    text = text.trim().replaceAll("_", "");
+    const negative = text.startsWith("-");
+    const digits = negative ? text.slice(1) : text;
+    const dot = digits.indexOf(".");
+    const whole = dot < 0 ? digits : digits.slice(0, dot);
+    const fraction = dot < 0 ? "" : digits.slice(dot + 1);
+    if (whole === "" || !/^[0-9]*$/.test(whole + fraction)) return null;
+    const units = BigInt(whole + fraction);
+    return Decimal.make(negative ? -units : units, fraction.length);
+  }
+
+  widen(scale) {
+    if (scale < this.scale) return null;
+    const factor = pow10(scale - this.scale);
+    return factor === null ? null : Decimal.make(this.units * factor, scale);
+  }
+
+  align(other) {
+    const scale = Math.max(this.scale, other.scale);
+    const a = this.widen(scale);
+    const b = other.widen(scale);
+    return a && b ? [a.units, b.units, scale] : null;
+  }
+
+  add(other) {
+    const aligned = this.align(other);
+    return aligned && Decimal.make(aligned[0] + aligned[1], aligned[2]);
+  }
+
+  sub(other) {
+    const aligned = this.align(other);
+    return aligned && Decimal.make(aligned[0] - aligned[1], aligned[2]);
+  }
+
+  mul(other) {
+    return Decimal.make(this.units * other.units, this.scale + other.scale);
+  }
+
+  div(other, scale, rounding) {
+    if (other.units === 0n) return null;
+    const up = pow10(other.scale + scale);
+    const down = pow10(this.scale);
+    if (up === null || down === null) return null;
+    const numerator = this.units * up;
+    const denominator = other.units * down;
+    if (!fitsI128(numerator) || !fitsI128(denominator)) return null;
+    return Decimal.make(divide(numerator, denominator, rounding), scale);
+  }
+
+  round(scale, rounding) {
+    if (scale >= this.scale) return this.widen(scale);
+    return Decimal.make(divide(this.units, pow10(this.scale - scale), rounding), scale);
+  }
+
+  whole() {
+    const factor = pow10(this.scale);
+    return factor !== null && this.units % factor === 0n ? this.units / factor : null;
+  }
+
+  cmp(other) {
+    const aligned = this.align(other);
+    if (aligned) return aligned[0] < aligned[1] ? -1 : aligned[0] > aligned[1] ? 1 : 0;
+    return totalCmp(this.toFloat(), other.toFloat());
+  }
+
+  toFloat() {
+    return Number(this.toString());
+  }
+
+  toString() {
+    const digits = (this.units < 0n ? -this.units : this.units).toString().padStart(this.scale + 1, "0");
+    const whole = digits.slice(0, digits.length - this.scale);
+    const fraction = digits.slice(digits.length - this.scale);
+    const sign = this.units < 0n ? "-" : "";
+    return fraction ? `${sign}${whole}.${fraction}` : sign + whole;
+  }
+}
+
+// Integer division rounded the way it's told to instead of truncated.
+function divide(n, d, rounding) {
+  const q = n / d;
+  const r = n % d;
+  if (r === 0n) return q;
+  const negative = n < 0n !== d < 0n;
+  const away = negative ? q - 1n : q + 1n;
+  const twice = (r < 0n ? -r : r) * 2n;
+  const half = d < 0n ? -d : d;
+  const roundAway = {
+    down: false,
+    up: true,
+    floor: negative,
+    ceil: !negative,
+    half_up: twice >= half,
+    half_even: twice > half || (twice === half && q % 2n !== 0n),
+  }[rounding];
+  return roundAway ? away : q;
+}
+
+// ---------------------------------------------------------------- collections
+
+// A mapping, kept sorted by key the way GM orders keys: signals, then
+// numbers, then uints, then text.
+export class Mapping {
+  constructor(entries = []) {
+    this.entries = entries; // [key, value] pairs in key order
+  }
+
+  find(key) {
+    let low = 0;
+    let high = this.entries.length;
+    while (low < high) {
+      const mid = (low + high) >> 1;
+      const order = compareKeys(this.entries[mid][0], key);
+      if (order === 0) return { at: mid, found: true };
+      if (order < 0) low = mid + 1;
+      else high = mid;
+    }
+    return { at: low, found: false };
+  }
+
+  get(key) {
+    const { at, found } = this.find(key);
+    return found ? this.entries[at][1] : undefined;
+  }
+
+  has(key) {
+    return this.find(key).found;
+  }
+
+  with(key, value) {
+    const { at, found } = this.find(key);
+    const entries = this.entries.slice();
+    entries.splice(at, found ? 1 : 0, [key, value]);
+    return new Mapping(entries);
+  }
+
+  without(key) {
+    const { at, found } = this.find(key);
+    if (!found) return this;
+    const entries = this.entries.slice();
+    entries.splice(at, 1);
+    return new Mapping(entries);
+  }
+
+  get size() {
+    return this.entries.length;
+  }
+}
+
+const keyRank = (key) => ({ boolean: 0, number: 1, bigint: 2, string: 3 })[typeof key];
+
+function compareKeys(a, b) {
+  const rank = keyRank(a) - keyRank(b);
+  if (rank !== 0) return rank;
+  if (typeof a === "number") return totalCmp(a, b);
+  if (typeof a === "string") return compareText(a, b);
+  return a < b ? -1 : a > b ? 1 : 0;
+}
+
+// Rust orders strings by their UTF-8 bytes, which is code point order.
+function compareText(a, b) {
+  const x = Array.from(a, (c) => c.codePointAt(0));
+  const y = Array.from(b, (c) => c.codePointAt(0));
+  for (let i = 0; i < Math.min(x.length, y.length); i++) {
+    if (x[i] !== y[i]) return x[i] < y[i] ? -1 : 1;
+  }
+  return x.length - y.length;
+}
+
+// A struct value, fields in declaration order.
+export class Record {
+  constructor(name, fields) {
+    this.name = name;
+    this.fields = fields; // [field, value] pairs
+  }
+
+  with(field, value) {
+    return new Record(this.name, this.fields.map(([f, v]) => [f, f === field ? value : v]));
+  }
+}
+
+// One `.field` step of an assignment target; every other step is a key.
+class Dot {
+  constructor(name) {
+    this.name = name;
+  }
+}
+
+// ---------------------------------------------------------------- values
+
+function typeName(value) {
+  if (value === null || value === undefined) return "lonely";
+  if (value instanceof Decimal) return "decimal";
+  if (value instanceof Mapping) return "mapping";
+  if (value instanceof Record) return value.name;
+  if (Array.isArray(value)) return "array";
+  return {
+    number: "vibe",
+    string: "text",
+    boolean: "signal",
+    bigint: "uint",
+    function: "smart contract",
+  }[typeof value];
+}
+
+// Value::get_type, as a string: lonely counts as a number, and each
+// struct is its own type.
+function typeOf(value) {
+  if (value === null || value === undefined) return "number";
+  if (value instanceof Record) return `struct ${value.name}`;
+  return typeof value === "function" ? "devotion" : typeName(value);
+}
+
+// Value::render: text is bare at the top level and quoted inside
+// collections.
+export function show(value) {
+  return typeof value === "string" ? value : showNested(value);
+}
+
+function showNested(value) {
+  if (value === null || value === undefined) return "null";
+  switch (typeof value) {
+    case "number":
+      return showFloat(value);
+    case "bigint":
+      return value.toString();
+    case "boolean":
+      return String(value);
+    case "string":
+      return debugText(value);
+    case "function":
+      return `<contract ${value.gmName ?? value.name}>`;
+  }
+  if (value instanceof Decimal) return value.toString();
+  if (Array.isArray(value)) return `[${value.map(showNested).join(", ")}]`;
+  if (value instanceof Mapping) {
+    return `{${value.entries.map(([k, v]) => `${showNested(k)}: ${showNested(v)}`).join(", ")}}`;
+  }
+  if (value instanceof Record) {
+    if (value.fields.length === 0) return `${value.name} {}`;
+    return `${value.name} { ${value.fields.map(([f, v]) => `${f}: ${showNested(v)}`).join(", ")} }`;
+  }
+  return String(value);
+}
+
+// Rust's `{:?}` for a str.
+function debugText(text) {
+  let out = '"';
+  for (const c of text) {
+    const code = c.codePointAt(0);
+    if (c === '"') out += '\\"';
+    else if (c === "\\") out += "\\\\";
+    else if (c === "\n") out += "\\n";
+    else if (c === "\r") out += "\\r";
+    else if (c === "\t") out += "\\t";
+    else if (c === "\0") out += "\\0";
+    else if (code < 0x20 || (code >= 0x7f && code <= 0x9f)) out += `\\u{${code.toString(16)}}`;
+    else out += c;
+  }
+  return out + '"';
+}
+
+// Derived PartialEq on Value: same kind, same contents.
+function same(a, b) {
+  if (a === null || a === undefined) return b === null || b === undefined;
+  if (typeof a !== typeof b) return false;
+  if (typeof a === "number") return Object.is(a, b);
+  if (typeof a !== "object") return a === b;
+  if (a instanceof Decimal) return b instanceof Decimal && a.cmp(b) === 0;
+  if (Array.isArray(a)) {
+    return Array.isArray(b) && a.length === b.length && a.every((item, i) => same(item, b[i]));
+  }
+  if (a instanceof Mapping) {
+    return (
+      b instanceof Mapping &&
+      a.size === b.size &&
+      a.entries.every(([k, v], i) => compareKeys(k, b.entries[i][0]) === 0 && same(v, b.entries[i][1]))
+    );
+  }
+  if (a instanceof Record) {
+    return (
+      b instanceof Record &&
+      a.name === b.name &&
+      a.fields.length === b.fields.length &&
+      a.fields.every(([f, v], i) => f === b.fields[i][0] && same(v, b.fields[i][1]))
+    );
+  }
+  return a === b;
+}
+
+// as_number: anything numeric, as a float.
+function asNumber(value) {
+  if (typeof value === "number") return value;
+  if (typeof value === "bigint") return uintToFloat(value);
+  if (value instanceof Decimal) return value.toFloat();
+  return null;
+}
+
+// The natural order shared by comparisons and sort().
+function compare(a, b) {
+  if (typeof a === "bigint" && typeof b === "bigint") return a < b ? -1 : a > b ? 1 : 0;
+  if (a instanceof Decimal && b instanceof Decimal) return a.cmp(b);
+  const x = asNumber(a);
+  const y = asNumber(b);
+  if (x !== null && y !== null) return x < y ? -1 : x > y ? 1 : 0;
+  if (typeof a === "string" && typeof b === "string") return compareText(a, b);
+  if (typeof a === "boolean" && typeof b === "boolean") return a - b;
+  return fail("Type", `Cannot compare ${typeName(a)} with ${typeName(b)}`);
+}
+
+function mapKey(key) {
+  if (["string", "boolean", "number", "bigint"].includes(typeof key)) return key;
+  return fail("Type", `Mapping keys must be text, number or signal, but found ${typeName(key)}`);
+}
+
+// ---------------------------------------------------------------- operators
+
+function promote(value, symbol) {
+  const exact = typeof value === "number" && value <= EXACT_FLOAT ? uintFromFloat(value) : null;
+  if (exact !== null) return exact;
+  return fail(
+    "Type",
+    `Cannot use '${symbol}' on uint and ${typeName(value)} ${show(value)}, convert with uint() or float() first`,
+  );
+}
+
+function uintOperands(l, r, symbol) {
+  if (typeof l === "bigint" && typeof r === "bigint") return [l, r];
+  if (typeof l === "bigint") return [l, promote(r, symbol)];
+  if (typeof r === "bigint") return [promote(l, symbol), r];
+  return null;
+}
+
+function toDecimal(value, symbol) {
+  const exact = typeof value === "number" || typeof value === "bigint" ? Decimal.parse(show(value)) : null;
+  if (exact !== null) return exact;
+  return fail("Type", `Cannot use '${symbol}' on decimal and ${typeName(value)} ${show(value)}`);
+}
+
+function decimalOperands(l, r, symbol) {
+  if (l instanceof Decimal && r instanceof Decimal) return [l, r];
+  if (l instanceof Decimal) return [l, toDecimal(r, symbol)];
+  if (r instanceof Decimal) return [toDecimal(l, symbol), r];
+  return null;
+}
+
+// Operands for ==/!=, where a failed promotion just means "not numbers".
+function quietly(operands, l, r, symbol) {
+  try {
+    return operands(l, r, symbol);
+  } catch (e) {
+    if (e instanceof GmError) return null;
+    throw e;
+  }
+}
+
+function decimalArithmetic(a, symbol, b) {
+  let result;
+  if (symbol === "+") result = a.add(b);
+  else if (symbol === "-") result = a.sub(b);
+  else if (symbol === "*") result = a.mul(b);
+  else if (b.units === 0n) return fail("Runtime", "You just got rekt by zero!");
+  else result = a.div(b, Math.max(a.scale, b.scale), "half_even");
+  return result ?? fail("Runtime", `decimal overflow: ${a} ${symbol} ${b} 📉`);
+}
+
+function uintArithmetic(a, symbol, b) {
+  let result;
+  if (symbol === "+") result = a + b;
+  else if (symbol === "-") result = a - b;
+  else if (symbol === "*") result = a * b;
+  else if (b === 0n) return fail("Runtime", "You just got rekt by zero!");
+  else result = a / b;
+  if (result >= 0n && result <= UINT_MAX) return result;
+  const what = symbol === "-" ? "underflow" : "overflow";
+  return fail("Runtime", `uint ${what}: ${a} ${symbol} ${b} 📉`);
+}
+
+function arithmetic(l, symbol, r) {
+  const decimals = decimalOperands(l, r, symbol);
+  if (decimals) return decimalArithmetic(decimals[0], symbol, decimals[1]);
+  const uints = uintOperands(l, r, symbol);
+  if (uints) return uintArithmetic(uints[0], symbol, uints[1]);
+  expectNumber(l);
+  expectNumber(r);
+  if (symbol === "+") return l + r;
+  if (symbol === "-") return l - r;
+  if (symbol === "*") return l * r;
+  if (r === 0) return fail("Lexer", "You just got rekt by zero!");
+  return l / r;
+}
+
+function expectNumber(value) {
+  if (typeof value !== "number") fail("Type", `Expected vibe, but found ${typeName(value)}`);
+}
+
+function ordering(l, symbol, r) {
+  const decimals = decimalOperands(l, r, symbol);
+  if (decimals) return decimals[0].cmp(decimals[1]);
+  const uints = uintOperands(l, r, symbol);
+  if (uints) return uints[0] < uints[1] ? -1 : uints[0] > uints[1] ? 1 : 0;
+  expectNumber(l);
+  expectNumber(r);
+  return compare(l, r);
+}
+
+function equal(l, symbol, r) {
+  const decimals = quietly(decimalOperands, l, r, symbol);
+  if (decimals) return decimals[0].cmp(decimals[1]) === 0;
+  const uints = quietly(uintOperands, l, r, symbol);
+  if (uints) return uints[0] === uints[1];
+  if (typeOf(l) !== typeOf(r)) return fail("Lexer", "Cannot compare different types");
+  return same(l, r);
+}
+
+// A number as a whole i64, for the integer-only operators.
+function whole(value, symbol) {
+  if (typeof value === "number" && value % 1 === 0 && Math.abs(value) <= EXACT_FLOAT) return BigInt(value);
+  return fail("Type", `'${symbol}' needs integers, but found ${typeName(value)} ${show(value)}`);
+}
+
+function floorDiv(a, b) {
+  const q = a / b;
+  return a % b !== 0n && a < 0n !== b < 0n ? q - 1n : q;
+}
+
+function integerArithmetic(l, symbol, r) {
+  const uints = uintOperands(l, r, symbol);
+  if (uints) return uintInteger(uints[0], symbol, uints[1]);
+  const a = whole(l, symbol);
+  const b = whole(r, symbol);
+  let result;
+  switch (symbol) {
+    case "%":
+    case "~/":
+      if (b === 0n) return fail("Runtime", "You just got rekt by zero!");
+      result = symbol === "~/" ? floorDiv(a, b) : a - b * floorDiv(a, b);
+      break;
+    case "&":
+      result = a & b;
+      break;
+    case "|":
+      result = a | b;
+      break;
+    case "^":
+      result = a ^ b;
+      break;
+    default:
+      if (b < 0n || b > 63n) return fail("Runtime", `Cannot shift by ${b}, shifts go from 0 to 63`);
+      if (symbol === ">>") {
+        result = a >> b;
+        break;
+      }
+      result = a << b;
+      if ((result < 0n ? -result : result) > BigInt(EXACT_FLOAT)) {
+        return fail("Runtime", `${a} << ${b} is too big for a number, use a uint 📉`);
+      }
+  }
+  return Number(result);
+}
+
+function uintInteger(a, symbol, b) {
+  const shift = b > 256n ? 256n : b;
+  switch (symbol) {
+    case "%":
+    case "~/":
+      if (b === 0n) return fail("Runtime", "You just got rekt by zero!");
+      return symbol === "%" ? a % b : a / b;
+    case "&":
+      return a & b;
+    case "|":
+      return a | b;
+    case "^":
+      return a ^ b;
+    case "<<":
+      return (a << shift) & UINT_MAX;
+    default:
+      return a >> shift;
+  }
+}
+
+// ---------------------------------------------------------------- slots
+
+function arrayIndex(index, length) {
+  const n = asNumber(index);
+  if (n === null) return fail("Type", `Array index must be a number, but found ${typeName(index)}`);
+  if (n < 0 || n % 1 !== 0 || n >= length) {
+    return fail("Runtime", `Index ${showFloat(n)} out of bounds for array of length ${length}`);
+  }
+  return n;
+}
+
+function index(object, key) {
+  if (object instanceof Mapping) return object.has(mapKey(key)) ? object.get(key) : 0;
+  if (Array.isArray(object)) return object[arrayIndex(key, object.length)];
+  return fail("Type", `Cannot index into ${typeName(object)}`);
+}
+
+function field(object, name) {
+  if (object instanceof Record) {
+    const found = object.fields.find(([f]) => f === name);
+    return found ? found[1] : fail("Type", `'${object.name}' has no field '${name}'`);
+  }
+  return fail("Type", `Cannot read field '${name}' of ${typeName(object)}`);
+}
+
+function readSlot(root, steps) {
+  let slot = root;
+  for (const step of steps) {
+    slot = step instanceof Dot ? field(slot, step.name) : index(slot, step);
+  }
+  return slot;
+}
+
+// The slot one step down, for writing: missing mapping slots start out as
+// empty mappings, like nested Solidity mappings.
+function child(slot, step) {
+  if (step instanceof Dot) {
+    if (!(slot instanceof Record)) {
+      return fail("Type", `Cannot write field '${step.name}' of ${typeName(slot)}`);
+    }
+    return field(slot, step.name);
+  }
+  if (slot instanceof Mapping) return slot.has(mapKey(step)) ? slot.get(step) : new Mapping();
+  if (Array.isArray(slot)) return slot[arrayIndex(step, slot.length)];
+  return fail("Type", `Cannot index into ${typeName(slot)}`);
+}
+
+function replace(slot, step, value) {
+  if (step instanceof Dot) return slot.with(step.name, value);
+  if (slot instanceof Mapping) return slot.with(mapKey(step), value);
+  const items = slot.slice();
+  items[arrayIndex(step, slot.length)] = value;
+  return items;
+}
+
+function setSlot(slot, steps, at, value) {
+  if (steps.length === 0) return value;
+  const step = steps[at];
+  if (at === steps.length - 1) {
+    if (!(slot instanceof Mapping) || step instanceof Dot) child(slot, step);
+    return replace(slot, step, value);
+  }
+  return replace(slot, step, setSlot(child(slot, step), steps, at + 1, value));
+}
+
+function deleteSlot(slot, steps, at) {
+  const step = steps[at];
+  if (at === steps.length - 1) {
+    if (slot instanceof Mapping && !(step instanceof Dot)) return slot.without(mapKey(step));
+    if (Array.isArray(slot) && !(step instanceof Dot)) return replace(slot, step, 0);
+    return fail("Type", `Cannot delete from ${typeName(slot)}`);
+  }
+  if (slot instanceof Mapping && !(step instanceof Dot) && !slot.has(mapKey(step))) {
+    return slot; // deleting from a slot that was never set
+  }
+  return replace(slot, step, deleteSlot(child(slot, step), steps, at + 1));
+}
+
+// ---------------------------------------------------------------- patterns
+
+// Vibecheck patterns, built by the generated code.
+export const P = {
+  wild: { kind: "wild" },
+  lit: (value) => ({ kind: "lit", value }),
+  range: (start, end) => ({ kind: "range", start, end }),
+  bind: (name) => ({ kind: "bind", name }),
+  struct: (name, fields) => ({ kind: "struct", name, fields }),
+  array: (items, rest = null) => ({ kind: "array", items, rest }),
+  or: (...alternatives) => ({ kind: "or", alternatives }),
+};
+
+function matches(pattern, value, bound) {
+  switch (pattern.kind) {
+    case "wild":
+      return true;
+    case "bind":
+      bound.push([pattern.name, value]);
+      return true;
+    case "lit": {
+      const expected = pattern.value;
+      if (typeof expected === "bigint" && typeof value === "bigint") return expected === value;
+      const a = asNumber(expected);
+      const b = asNumber(value);
+      if (a !== null && b !== null) return a === b;
+      try {
+        return compare(expected, value) === 0;
+      } catch (e) {
+        if (e instanceof GmError) return false;
+        throw e;
+      }
+    }
+    case "range": {
+      const [start, end, n] = [asNumber(pattern.start), asNumber(pattern.end), asNumber(value)];
+      return start !== null && end !== null && n !== null && start <= n && n < end;
+    }
+    case "struct":
+      return (
+        value instanceof Record &&
+        value.name === pattern.name &&
+        Object.entries(pattern.fields).every(([f, sub]) => {
+          const found = value.fields.find(([name]) => name === f);
+          return found !== undefined && matches(sub, found[1], bound);
+        })
+      );
+    case "array": {
+      if (!Array.isArray(value)) return false;
+      const { items, rest } = pattern;
+      const fits = rest === null ? value.length === items.length : value.length >= items.length;
+      if (!fits || !items.every((p, i) => matches(p, value[i], bound))) return false;
+      if (rest !== null && rest !== "_") bound.push([rest, value.slice(items.length)]);
+      return true;
+    }
+    case "or":
+      return pattern.alternatives.some((p) => {
+        const mark = bound.length;
+        const hit = matches(p, value, bound);
+        if (!hit) bound.length = mark;
+        return hit;
+      });
+  }
+  return false;
+}
+
+// ---------------------------------------------------------------- builtins
+
+function expectArgs(name, args, count) {
+  if (args.length !== count) fail("Runtime", `'${name}' expected ${count} args, got ${args.length}.`);
+}
+
+function expectMapping(value) {
+  return value instanceof Mapping ? value : fail("Type", `Expected mapping, but found ${typeName(value)}`);
+}
+
+function arrayAndContract(args) {
+  const [items, contract] = args;
+  if (!Array.isArray(items)) fail("Type", `Expected array, but found ${typeName(items)}`);
+  if (typeof contract !== "function") fail("Type", `Expected smart contract, but found ${typeName(contract)}`);
+  return [items, contract];
+}
+
+// A stable merge sort, split the same way as the Rust one so a comparator
+// contract sees the same calls in the same order.
+function mergeSort(items, cmp) {
+  if (items.length <= 1) return items;
+  const middle = items.length >> 1;
+  const left = mergeSort(items.slice(0, middle), cmp);
+  const right = mergeSort(items.slice(middle), cmp);
+  const merged = [];
+  let i = 0;
+  let j = 0;
+  while (i < left.length && j < right.length) {
+    merged.push(cmp(left[i], right[j]) > 0 ? right[j++] : left[i++]);
+  }
+  return merged.concat(left.slice(i), right.slice(j));
+}
+
+function scaleArg(value) {
+  const n = asNumber(value);
+  if (n !== null && n % 1 === 0 && n >= 0 && n <= MAX_SCALE) return n;
+  return fail("Type", `Scale must be a whole number from 0 to ${MAX_SCALE}, but found ${show(value)}`);
+}
+
+function roundingArg(value) {
+  if (value === undefined) return "half_even";
+  if (typeof value !== "string") return fail("Type", `Rounding mode must be text, but found ${typeName(value)}`);
+  return ROUNDING.includes(value) ? value : fail("Type", `Unknown rounding mode "${value}"`);
+}
+
+function decimalArg(value) {
+  if (value instanceof Decimal) return value;
+  const exact = typeof value === "number" || typeof value === "bigint" ? Decimal.parse(show(value)) : null;
+  return exact ?? fail("Type", `Expected decimal, but found ${typeName(value)}`);
+}
+
+function roundTo(value, scale, rounding) {
+  return value.round(scale, rounding) ?? fail("Runtime", `decimal overflow rounding ${value} to ${scale} places 📉`);
+}
+
+function uintOp(name, args) {
+  expectArgs(name, args, 2);
+  const [a, b] = args;
+  if (typeof a !== "bigint" || typeof b !== "bigint") {
+    fail("Type", `${name} expects two uints, but found ${typeName(a)} and ${typeName(b)}`);
+  }
+  const [kind, op] = name.split("_");
+  let exact;
+  if (op === "add") exact = a + b;
+  else if (op === "sub") exact = a - b;
+  else if (op === "mul") exact = a * b;
+  else exact = b === 0n ? null : a / b;
+  const fits = exact !== null && exact >= 0n && exact <= UINT_MAX;
+  if (kind === "checked") return fits ? exact : null;
+  if (kind === "wrapping") return ((exact % (UINT_MAX + 1n)) + UINT_MAX + 1n) % (UINT_MAX + 1n);
+  return fits ? exact : exact < 0n ? 0n : UINT_MAX;
+}
+
+// ---------------------------------------------------------------- the chain
+
+// Everything a running script needs: the operators, the builtins, its
+// struct declarations and where broadcasts go.
+class Chain {
+  constructor(emit) {
+    this.emit = emit;
+    this.structs = new Map();
+  }
+
+  broadcast(value) {
+    this.emit(show(value), value);
+  }
+
+  // How a value reads inside an interpolated string
+  show(value) {
+    return show(value);
+  }
+
+  // `sus`, `grind` and `?` only take signals
+  cond(value) {
+    if (typeof value !== "boolean") fail("Lexer", "Condition must be bullish or bearish (yes/no)");
+    return value;
+  }
+
+  // A token read before it's been minted
+  minted(value, name) {
+    return value === undefined ? fail("Lexer", `Token '${name}' not found.`) : value;
+  }
+
+  locked(name) {
+    fail("Runtime", `'${name}' is a block constant and can't be changed.`);
+  }
+
+  plus(l, r) {
+    return arithmetic(l, "+", r);
+  }
+
+  minus(l, r) {
+    return arithmetic(l, "-", r);
+  }
+
+  times(l, r) {
+    return arithmetic(l, "*", r);
+  }
+
+  divide(l, r) {
+    return arithmetic(l, "/", r);
+  }
+
+  mod(l, r) {
+    return integerArithmetic(l, "%", r);
+  }
+
+  chop(l, r) {
+    return integerArithmetic(l, "~/", r);
+  }
+
+  band(l, r) {
+    return integerArithmetic(l, "&", r);
+  }
+
+  bor(l, r) {
+    return integerArithmetic(l, "|", r);
+  }
+
+  bxor(l, r) {
+    return integerArithmetic(l, "^", r);
+  }
+
+  shl(l, r) {
+    return integerArithmetic(l, "<<", r);
+  }
+
+  shr(l, r) {
+    return integerArithmetic(l, ">>", r);
+  }
+
+  lt(l, r) {
+    return ordering(l, "<", r) < 0;
+  }
+
+  gt(l, r) {
+    return ordering(l, ">", r) > 0;
+  }
+
+  le(l, r) {
+    return ordering(l, "<=", r) <= 0;
+  }
+
+  ge(l, r) {
+    return ordering(l, ">=", r) >= 0;
+  }
+
+  eq(l, r) {
+    return equal(l, "==", r);
+  }
+
+  ne(l, r) {
+    return !equal(l, "!=", r);
+  }
+
+  // Parsed, but not yet given a meaning
+  and(l, r) {
+    fail("Lexer", "Op not supported yet.");
+  }
+
+  or(l, r) {
+    fail("Lexer", "Op not supported yet.");
+  }
+
+  not(value) {
+    if (typeof value !== "boolean") fail("Type", `'not' needs a signal, but found ${typeName(value)}`);
+    return !value;
+  }
+
+  bnot(value) {
+    if (typeof value === "bigint") return UINT_MAX ^ value;
+    return Number(~whole(value, "~"));
+  }
+
+  dec(text) {
+    return Decimal.parse(text);
+  }
+
+  mapping(entries) {
+    let map = new Mapping();
+    for (const [key, value] of entries) map = map.with(mapKey(key), value);
+    return map;
+  }
+
+  declare(name, fields) {
+    this.structs.set(name, fields);
+  }
+
+  fieldType(record, name, value) {
+    const expected = this.structs.get(record)?.[name];
+    if (expected === null || expected === undefined || accepts(expected, value)) return;
+    fail("Type", `Field '${record}.${name}' expects ${expected}, but found ${typeName(value)}`);
+  }
+
+  record(name, given) {
+    const declared = this.structs.get(name) ?? fail("Runtime", `Unknown struct '${name}'`);
+    for (const [f, value] of Object.entries(given)) {
+      if (!(f in declared)) fail("Type", `'${name}' has no field '${f}'`);
+      this.fieldType(name, f, value);
+    }
+    const fields = Object.keys(declared).map((f) =>
+      f in given ? [f, given[f]] : fail("Runtime", `Missing field '${f}' in '${name}'`),
+    );
+    return new Record(name, fields);
+  }
+
+  index(object, key) {
+    return index(object, key);
+  }
+
+  field(object, name) {
+    return field(object, name);
+  }
+
+  dot(name) {
+    return new Dot(name);
+  }
+
+  // `root[k]... = value`, returning the new root
+  set(root, steps, value) {
+    return setSlot(root, steps, 0, value);
+  }
+
+  setField(root, steps, name, value) {
+    const target = readSlot(root, steps);
+    if (target instanceof Record) this.fieldType(target.name, name, value);
+    return setSlot(root, [...steps, new Dot(name)], 0, value);
+  }
+
+  // `root[k]... op= value`, with op one of the operator methods
+  update(root, steps, op, operand) {
+    return setSlot(root, steps, 0, op.call(this, readSlot(root, steps), operand));
+  }
+
+  remove(root, steps) {
+    if (steps.length === 0) fail("Runtime", "Nothing to delete");
+    return deleteSlot(root, steps, 0);
+  }
+
+  // What `farm (x in xs)` walks: array items, or mapping keys
+  items(iterable) {
+    if (Array.isArray(iterable)) return iterable;
+    if (iterable instanceof Mapping) return iterable.entries.map(([k]) => k);
+    return fail("Type", `Cannot farm over ${typeName(iterable)}`);
+  }
+
+  // What `farm (k, v in xs)` walks: index and item, or key and value
+  pairs(iterable) {
+    if (Array.isArray(iterable)) return iterable.map((item, i) => [i, item]);
+    if (iterable instanceof Mapping) return iterable.entries;
+    return fail("Type", `Cannot farm over ${typeName(iterable)}`);
+  }
+
+  // `farm (i in start..end step s)`
+  steps(start, end, step = 1) {
+    const bound = (value, what) =>
+      asNumber(value) ?? fail("Type", `Range ${what} must be a number, but found ${typeName(value)}`);
+    let current = bound(start, "start");
+    end = bound(end, "end");
+    step = bound(step, "step");
+    if (step === 0) fail("Runtime", "Range step can't be zero");
+    const values = [];
+    while ((step > 0 && current < end) || (step < 0 && current > end)) {
+      values.push(current);
+      current += step;
+    }
+    return values;
+  }
+
+  // vibecheck: the bindings of a matching pattern, or null
+  match(value, pattern) {
+    const bound = [];
+    return matches(pattern, value, bound) ? Object.fromEntries(bound) : null;
+  }
+
+  unmatched(value) {
+    fail("Runtime", `No vibecheck arm matched ${show(value)}`);
+  }
+
+  lambda(f) {
+    f.gmName = "anonymous";
+    return f;
+  }
+
+  named(name, f) {
+    f.gmName = name;
+    return f;
+  }
+
+  call(contract, ...args) {
+    if (typeof contract !== "function") fail("Lexer", `${typeName(contract)} is not a contract`);
+    if (contract.length !== args.length) fail("Lexer", `Expected ${contract.length} args, got ${args.length}.`);
+    return contract(...args);
+  }
+
+  // Builtins, by their GM names
+
+  len(...args) {
+    expectArgs("len", args, 1);
+    const [value] = args;
+    if (Array.isArray(value)) return value.length;
+    if (value instanceof Mapping) return value.size;
+    if (typeof value === "string") return Array.from(value).length;
+    return fail("Type", `Cannot take the length of ${typeName(value)}`);
+  }
+
+  keys(...args) {
+    expectArgs("keys", args, 1);
+    return expectMapping(args[0]).entries.map(([k]) => k);
+  }
+
+  values(...args) {
+    expectArgs("values", args, 1);
+    return expectMapping(args[0]).entries.map(([, v]) => v);
+  }
+
+  entries(...args) {
+    expectArgs("entries", args, 1);
+    return expectMapping(args[0]).entries.map(([k, v]) => [k, v]);
+  }
+
+  has(...args) {
+    expectArgs("has", args, 2);
+    return expectMapping(args[0]).has(mapKey(args[1]));
+  }
+
+  get(...args) {
+    expectArgs("get", args, 3);
+    const map = expectMapping(args[0]);
+    return map.has(mapKey(args[1])) ? map.get(args[1]) : args[2];
+  }
+
+  zip(...args) {
+    expectArgs("zip", args, 2);
+    const [a, b] = args;
+    if (!Array.isArray(a) || !Array.isArray(b)) {
+      fail("Type", `zip expects two arrays, but found ${typeName(a)} and ${typeName(b)}`);
+    }
+    return a.slice(0, Math.min(a.length, b.length)).map((x, i) => [x, b[i]]);
+  }
+
+  enumerate(...args) {
+    expectArgs("enumerate", args, 1);
+    if (!Array.isArray(args[0])) fail("Type", `Expected array, but found ${typeName(args[0])}`);
+    return args[0].map((item, i) => [i, item]);
+  }
+
+  range(...args) {
+    const bounds = args.map((arg) => asNumber(arg) ?? fail("Type", `range expects numbers, but found ${typeName(arg)}`));
+    let start = 0;
+    let end;
+    let step = 1;
+    if (bounds.length === 1) [end] = bounds;
+    else if (bounds.length === 2) [start, end] = bounds;
+    else if (bounds.length === 3) [start, end, step] = bounds;
+    else fail("Runtime", `'range' expected 1 to 3 args, got ${args.length}.`);
+    if (step === 0) fail("Runtime", "range step can't be zero");
+    const items = [];
+    for (let current = start; (step > 0 && current < end) || (step < 0 && current > end); current += step) {
+      items.push(current);
+    }
+    return items;
+  }
+
+  sort(...args) {
+    expectArgs("sort", args, 1);
+    if (!Array.isArray(args[0])) fail("Type", `Expected array, but found ${typeName(args[0])}`);
+    return mergeSort(args[0], compare);
+  }
+
+  map(...args) {
+    expectArgs("map", args, 2);
+    const [items, f] = arrayAndContract(args);
+    return items.map((item) => this.call(f, item));
+  }
+
+  filter(...args) {
+    expectArgs("filter", args, 2);
+    const [items, pred] = arrayAndContract(args);
+    return items.filter((item) => this.test(pred, item, "filter"));
+  }
+
+  reduce(...args) {
+    expectArgs("reduce", args, 3);
+    const [items, f] = arrayAndContract(args);
+    return items.reduce((acc, item) => this.call(f, acc, item), args[2]);
+  }
+
+  sort_by(...args) {
+    expectArgs("sort_by", args, 2);
+    const [items, cmp] = arrayAndContract(args);
+    return mergeSort(items, (a, b) => {
+      const verdict = this.call(cmp, a, b);
+      const n = asNumber(verdict);
+      if (n === null) fail("Type", `sort_by comparator must return a number, but returned ${typeName(verdict)}`);
+      return n < 0 ? -1 : n > 0 ? 1 : 0;
+    });
+  }
+
+  any(...args) {
+    expectArgs("any", args, 2);
+    const [items, pred] = arrayAndContract(args);
+    return items.some((item) => this.test(pred, item, "any"));
+  }
+
+  all(...args) {
+    expectArgs("all", args, 2);
+    const [items, pred] = arrayAndContract(args);
+    return items.every((item) => this.test(pred, item, "all"));
+  }
+
+  test(pred, item, name) {
+    const verdict = this.call(pred, item);
+    if (typeof verdict !== "boolean") {
+      fail("Type", `${name} predicate must return a signal, but returned ${typeName(verdict)}`);
+    }
+    return verdict;
+  }
+
+  uint(...args) {
+    expectArgs("uint", args, 1);
+    const [value] = args;
+    let converted = null;
+    if (typeof value === "bigint") converted = value;
+    else if (typeof value === "string") converted = parseUint(value);
+    else if (value instanceof Decimal) {
+      const n = value.whole();
+      converted = n === null || n < 0n || n > UINT_MAX ? null : n;
+    } else {
+      const n = asNumber(value);
+      converted = n === null ? null : uintFromFloat(n);
+    }
+    if (converted !== null) return converted;
+    return fail("Type", `${typeName(value)} ${show(value)} is not a whole number in uint range`);
+  }
+
+  float(...args) {
+    expectArgs("float", args, 1);
+    const [value] = args;
+    const n = typeof value === "string" ? parseFloatText(value) : asNumber(value);
+    return n ?? fail("Type", `Cannot turn ${typeName(value)} into a float`);
+  }
+
+  text(...args) {
+    expectArgs("text", args, 1);
+    return show(args[0]);
+  }
+
+  hex(...args) {
+    expectArgs("hex", args, 1);
+    if (typeof args[0] !== "bigint") fail("Type", `hex expects a uint, but found ${typeName(args[0])}`);
+    return `0x${args[0].toString(16)}`;
+  }
+
+  decimal(...args) {
+    if (args.length < 1 || args.length > 2) fail("Runtime", `'decimal' expected 1 or 2 args, got ${args.length}.`);
+    const [value, scale] = args;
+    let converted = null;
+    if (value instanceof Decimal) converted = value;
+    else if (typeof value === "string") converted = Decimal.parse(value);
+    else if (typeof value === "number" || typeof value === "bigint") converted = Decimal.parse(show(value));
+    if (converted === null) fail("Type", `Cannot turn ${typeName(value)} ${show(value)} into a decimal`);
+    return args.length === 2 ? roundTo(converted, scaleArg(scale), "half_even") : converted;
+  }
+
+  round(...args) {
+    if (args.length < 2 || args.length > 3) fail("Runtime", `'round' expected 2 or 3 args, got ${args.length}.`);
+    const value = decimalArg(args[0]);
+    return roundTo(value, scaleArg(args[1]), roundingArg(args[2]));
+  }
+
+  div(...args) {
+    if (args.length < 3 || args.length > 4) fail("Runtime", `'div' expected 3 or 4 args, got ${args.length}.`);
+    const a = decimalArg(args[0]);
+    const b = decimalArg(args[1]);
+    if (b.units === 0n) fail("Runtime", "You just got rekt by zero!");
+    return a.div(b, scaleArg(args[2]), roundingArg(args[3])) ?? fail("Runtime", `decimal overflow: ${a} / ${b} 📉`);
+  }
+}
+
+for (const name of ["checked", "wrapping", "saturating"].flatMap((kind) =>
+  ["add", "sub", "mul", "div"].map((op) => `${kind}_${op}`),
+)) {
+  if (name === "wrapping_div" || name === "saturating_div") continue;
+  Chain.prototype[name] = (...args) => uintOp(name, args);
+}
+
+// Rust's str::parse::<f64> on trimmed text: plain decimal or exponent
+// notation, inf, infinity and NaN.
+function parseFloatText(text) {
+  text = text.trim();
+  if (/^[+-]?(inf|infinity)$/i.test(text)) return text.startsWith("-") ? -Infinity : Infinity;
+  if (/^[+-]?nan$/i.test(text)) return NaN;
+  return /^[+-]?(\d+\.?\d*|\.\d+)([eE][+-]?\d+)?$/.test(text) ? Number(text) : null;
+}
+
+// Type::accepts, with types named the way GM prints them
+function accepts(expected, value) {
+  switch (expected) {
+    case "number":
+      return value === null || ["number", "bigint"].includes(typeof value) || value instanceof Decimal;
+    case "feeling":
+      return typeof value === "boolean";
+    default:
+      return value instanceof Record ? expected === value.name : expected === typeOf(value);
+  }
+}
+
+// Wraps a transpiled script: call the result with an emit function to run
+// it. Reading a token before it's minted trips JavaScript's own check; it's
+// reported the way GM reports it.
+export function chain(body) {
+  return function run(emit = (text) => console.log(text)) {
+    try {
+      body(new Chain(emit));
+    } catch (e) {
+      const unminted = e instanceof ReferenceError && /^Cannot access '(.+)' before initialization$/.exec(e.message);
+      if (unminted) throw new GmError("Lexer", `Token '${unminted[1]}' not found.`);
+      throw e;
+    }
+  };
+}
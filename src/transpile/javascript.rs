@@ -0,0 +1,1027 @@
+// src/transpile/javascript.rs
+//
+// Turns a resolved tree into an ES module that reads like it was written by
+// hand: tokens become `let`, blocks `const`, contracts functions, loops
+// `for`/`while` and vibechecks if/else chains. Everything whose meaning
+// differs between the two languages (every operator, indexing, printing,
+// copying collections on assignment) goes through the runtime the module
+// imports, so the module does exactly what the tree walker does.
+//
+// The module's default export runs the script. It takes the function each
+// broadcast is handed to, and logs them by default:
+//
+//   import run from "./script.js";
+//   run((text, value) => events.push(text));
+
+use std::collections::{HashMap, HashSet};
+use crate::error::Rekt;
+use gm_runtime::BUILTINS;
+use crate::parser::ast::{Ast, Pattern};
+use crate::shared_types::{BinaryOp, Type, Value};
+use super::{assigned_names, declarations, escapes, mentions, read_first, target_path, DeclKind, PathStep, Write};
+
+// The runtime generated modules import, to be written out next to them.
+pub const RUNTIME: &str = include_str!("gm_runtime.js");
+pub const RUNTIME_FILE: &str = "gm_runtime.js";
+
+// Names GM allows that JavaScript doesn't, which get a `$` in front.
+const RESERVED: &[&str] = &[
+    "arguments", "await", "break", "case", "catch", "class", "const", "continue", "debugger",
+    "default", "delete", "do", "else", "enum", "eval", "export", "extends", "false", "finally",
+    "for", "function", "if", "implements", "import", "in", "instanceof", "interface", "let",
+    "new", "null", "package", "private", "protected", "public", "return", "static", "super",
+    "switch", "this", "throw", "true", "try", "typeof", "undefined", "var", "void", "while",
+    "with", "yield", "NaN", "Infinity", "P",
+];
+
+// `source` is only used to say where the module came from.
+pub fn transpile(ast: &Ast, source: &str) -> Result<String, Rekt> {
+    let mut assigned = HashSet::new();
+    assigned_names(ast, &mut assigned);
+    let mut js = Js {
+        out: String::new(),
+        depth: 1,
+        scopes: Vec::new(),
+        function_floor: 0,
+        assigned,
+        patterns: false,
+        temps: 0,
+    };
+    let stmts = match ast {
+        Ast::Program(stmts) => stmts.as_slice(),
+        other => std::slice::from_ref(other),
+    };
+    js.enter(&[], stmts, None);
+    js.statements(stmts, false)?;
+    js.leave();
+
+    let imports = if js.patterns { "chain, P" } else { "chain" };
+    Ok(format!(
+        "// Generated by gm-language from {}.\n\
+         // Broadcasts go to the function passed to the default export.\n\
+         import {{ {} }} from \"./{}\";\n\
+         \n\
+         export default chain(($) => {{\n{}}});\n",
+        source, imports, RUNTIME_FILE, js.out
+    ))
+}
+
+fn unsupported(what: &str) -> Rekt {
+    Rekt::Type(format!("{} can't be transpiled to JavaScript", what))
+}
+
+#[derive(Clone)]
+struct Binding {
+    js: String,              // the identifier it's written as
+    kind: Option<DeclKind>,  // None for params, loop variables and match bindings
+    hoisted: bool,           // declared up front, so possibly still unset
+    live: bool,              // its declaration has been written out
+}
+
+// One step of an assignment target below its root token.
+enum Step {
+    Key(String),
+    Dot(String),
+}
+
+struct Js {
+    out: String,
+    depth: usize,
+    scopes: Vec<HashMap<String, Binding>>,
+    function_floor: usize, // scopes below this belong to enclosing contracts
+    assigned: HashSet<String>, // names that are ever reassigned, so can't be called directly
+    patterns: bool,            // whether `P` needs importing
+    temps: usize,
+}
+
+impl Js {
+    fn line(&mut self, text: &str) {
+        self.out.push_str(&"  ".repeat(self.depth));
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn indented(&mut self, f: impl FnOnce(&mut Self) -> Result<(), Rekt>) -> Result<(), Rekt> {
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
+    // Writes `f`'s lines one level in and returns them as a `{ }` block
+    // that can sit inside an expression.
+    fn block_text(&mut self, f: impl FnOnce(&mut Self) -> Result<(), Rekt>) -> Result<String, Rekt> {
+        let outer = std::mem::take(&mut self.out);
+        let result = self.indented(f);
+        let inner = std::mem::replace(&mut self.out, outer);
+        result?;
+        Ok(format!("{{\n{}{}}}", inner, "  ".repeat(self.depth)))
+    }
+
+    fn temp(&mut self) -> String {
+        self.temps += 1;
+        format!("$key{}", self.temps)
+    }
+
+    // Opens a GM scope holding `bound`, then whatever `stmts` declare.
+    // Hoisted names are declared on the spot. A declaration that hides an
+    // outer token the scope reads first gets its own identifier, since
+    // `let` would hide the outer one from the top of the block.
+    fn enter(&mut self, bound: &[String], stmts: &[Ast], head: Option<&Ast>) {
+        let mut scope = HashMap::new();
+        for name in bound {
+            let shadows = head.is_some_and(|head| mentions(head, name)) && self.lookup(name).is_some();
+            let js = if shadows { self.renamed(name) } else { ident(name) };
+            scope.insert(name.clone(), Binding { js, kind: None, hoisted: false, live: true });
+        }
+        let mut hoisted = Vec::new();
+        for decl in declarations(bound, stmts) {
+            if bound.contains(&decl.name) {
+                continue;
+            }
+            let shadows = self.lookup(&decl.name).is_some() && (decl.hoisted || read_first(stmts, &decl.name));
+            let js = if shadows { self.renamed(&decl.name) } else { ident(&decl.name) };
+            if decl.hoisted {
+                hoisted.push(js.clone());
+            }
+            let binding = Binding { js, kind: Some(decl.kind), hoisted: decl.hoisted, live: decl.hoisted };
+            scope.insert(decl.name, binding);
+        }
+        self.scopes.push(scope);
+        if !hoisted.is_empty() {
+            self.line(&format!("let {};", hoisted.join(", ")));
+        }
+    }
+
+    fn leave(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn renamed(&self, name: &str) -> String {
+        format!("{}${}", ident(name), self.scopes.len())
+    }
+
+    // The binding a name refers to at this point of the script. Inside the
+    // contract being written, a token whose declaration hasn't been reached
+    // yet isn't minted, so an outer one is found instead, like at runtime.
+    fn lookup(&self, name: &str) -> Option<&Binding> {
+        let mut unminted = None;
+        for (index, scope) in self.scopes.iter().enumerate().rev() {
+            if let Some(binding) = scope.get(name) {
+                if binding.live || index < self.function_floor {
+                    return Some(binding);
+                }
+                unminted.get_or_insert(binding);
+            }
+        }
+        unminted
+    }
+
+    // The identifier the current scope declares `name` as, and whether it
+    // already exists (hoisted, or a param or loop variable) so minting it
+    // is an assignment.
+    fn declared_here(&self, name: &str) -> (String, bool) {
+        match self.scopes.last().and_then(|scope| scope.get(name)) {
+            Some(binding) => (binding.js.clone(), binding.hoisted || binding.kind.is_none()),
+            None => (ident(name), false),
+        }
+    }
+
+    fn minted(&mut self, name: &str) {
+        if let Some(binding) = self.scopes.last_mut().and_then(|scope| scope.get_mut(name)) {
+            binding.live = true;
+        }
+    }
+
+    fn read(&self, name: &str) -> String {
+        match self.lookup(name) {
+            Some(binding) if binding.hoisted => format!("$.minted({}, {})", binding.js, js_string(name)),
+            Some(binding) => binding.js.clone(),
+            None => ident(name),
+        }
+    }
+
+    // The identifier a token is written to through, or None when it's a
+    // block constant that can't be.
+    fn writable(&self, name: &str) -> Option<String> {
+        match self.lookup(name) {
+            Some(Binding { kind: Some(DeclKind::Block), hoisted: false, .. }) => None,
+            Some(binding) => Some(binding.js.clone()),
+            None => Some(ident(name)),
+        }
+    }
+
+    // ------------------------------------------------------------ statements
+
+    // `tail` statements are the last thing a contract runs, so their value
+    // is its return value.
+    fn statements(&mut self, stmts: &[Ast], tail: bool) -> Result<(), Rekt> {
+        if stmts.is_empty() && tail {
+            self.line("return null;");
+        }
+        for (index, stmt) in stmts.iter().enumerate() {
+            self.statement(stmt, tail && index + 1 == stmts.len())?;
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self, stmt: &Ast, tail: bool) -> Result<(), Rekt> {
+        match stmt {
+            Ast::Located { line, statement } => self.statement(statement, tail).map_err(|e| e.at_line(*line)),
+
+            Ast::VariableDecl { name, initializer, is_constant, .. } => {
+                let value = self.expr(initializer)?;
+                let (js, declared) = self.declared_here(name);
+                if declared {
+                    self.line(&format!("{} = {};", js, value));
+                } else {
+                    let keyword = if *is_constant { "const" } else { "let" };
+                    self.line(&format!("{} {} = {};", keyword, js, value));
+                    self.minted(name);
+                }
+                if tail {
+                    let value = self.read(name);
+                    self.line(&format!("return {};", value));
+                }
+                Ok(())
+            }
+            Ast::FunctionDecl { name, params, body, .. } => {
+                let (js, hoisted) = self.declared_here(name);
+                let names: Vec<String> = params.iter().map(|(param, _)| param.clone()).collect();
+                let params = names.iter().map(|param| ident(param)).collect::<Vec<_>>().join(", ");
+                if !hoisted {
+                    self.minted(name);
+                }
+                let body = self.contract_body(&names, body)?;
+                match (hoisted, js == *name) {
+                    (false, true) => {
+                        let header = format!("function {}({}) ", js, params);
+                        self.line(&(header + &body));
+                    }
+                    (false, false) => {
+                        let f = format!("$.named({}, function ({}) {})", js_string(name), params, body);
+                        self.line(&format!("const {} = {};", js, f));
+                    }
+                    (true, _) => {
+                        let f = format!("$.named({}, function ({}) {})", js_string(name), params, body);
+                        self.line(&format!("{} = {};", js, f));
+                    }
+                }
+                if tail {
+                    self.line(&format!("return {};", js));
+                }
+                Ok(())
+            }
+            Ast::StructDecl { name, fields, .. } => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(field, t)| {
+                        let t = t.as_ref().map(|t| js_string(&type_name(t))).unwrap_or_else(|| "null".to_string());
+                        format!("{}: {}", field, t)
+                    })
+                    .collect();
+                let fields = if fields.is_empty() { "{}".to_string() } else { format!("{{ {} }}", fields.join(", ")) };
+                self.line(&format!("$.declare({}, {});", js_string(name), fields));
+                self.return_null(tail);
+                Ok(())
+            }
+
+            Ast::If { condition, then_branch, else_branch } => {
+                let condition = self.condition(condition)?;
+                self.line(&format!("if ({}) {{", condition));
+                self.indented(|js| js.statements(then_branch, tail))?;
+                let mut branch = else_branch.as_deref();
+                loop {
+                    match branch {
+                        None => {
+                            self.line("}");
+                            self.return_null(tail);
+                            return Ok(());
+                        }
+                        Some([only]) if matches!(only.unlocated(), Ast::If { .. }) => {
+                            let Ast::If { condition, then_branch, else_branch } = only.unlocated() else {
+                                unreachable!()
+                            };
+                            let condition = self.condition(condition)?;
+                            self.line(&format!("}} else if ({}) {{", condition));
+                            self.indented(|js| js.statements(then_branch, tail))?;
+                            branch = else_branch.as_deref();
+                        }
+                        Some(stmts) => {
+                            self.line("} else {");
+                            self.indented(|js| js.statements(stmts, tail))?;
+                            self.line("}");
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+            Ast::While { condition, body } => {
+                let condition = self.condition(condition)?;
+                self.line(&format!("while ({}) {{", condition));
+                self.indented(|js| js.statements(body, false))?;
+                self.line("}");
+                self.return_null(tail);
+                Ok(())
+            }
+            Ast::ForEach { key, variable, iterable, body } => {
+                let items = self.expr(iterable)?;
+                let bound: Vec<String> = key.iter().chain([variable]).cloned().collect();
+                self.enter(&bound, body, Some(iterable));
+                let names: Vec<String> = bound.iter().map(|name| self.read(name)).collect();
+                let head = match key {
+                    Some(_) => format!("for (let [{}] of $.pairs({})) {{", names.join(", "), items),
+                    None => format!("for (let {} of $.items({})) {{", names[0], items),
+                };
+                self.loop_body(head, body)?;
+                self.return_null(tail);
+                Ok(())
+            }
+            Ast::ForRange { variable, start, end, step, body } => {
+                let mut bounds = vec![self.expr(start)?, self.expr(end)?];
+                if let Some(step) = step {
+                    bounds.push(self.expr(step)?);
+                }
+                let head = Ast::ArrayLiteral(
+                    [&**start, &**end].into_iter().chain(step.as_deref()).cloned().collect(),
+                );
+                self.enter(std::slice::from_ref(variable), body, Some(&head));
+                let head = format!("for (let {} of $.steps({})) {{", self.read(variable), bounds.join(", "));
+                self.loop_body(head, body)?;
+                self.return_null(tail);
+                Ok(())
+            }
+            Ast::Block(stmts) => {
+                self.line("{");
+                self.indented(|js| {
+                    js.enter(&[], stmts, None);
+                    let result = js.statements(stmts, tail);
+                    js.leave();
+                    result
+                })?;
+                self.line("}");
+                Ok(())
+            }
+
+            Ast::PrintStmt(value) => {
+                let value = self.expr(value)?;
+                self.line(&format!("$.broadcast({});", value));
+                self.return_null(tail);
+                Ok(())
+            }
+            Ast::ReturnStmt(None) => {
+                self.line("return null;");
+                Ok(())
+            }
+            Ast::ReturnStmt(Some(value)) => self.expression_statement(value, true),
+            Ast::Break => {
+                self.line("break;");
+                Ok(())
+            }
+            Ast::Continue => {
+                self.line("continue;");
+                Ok(())
+            }
+            Ast::ExpressionStmt(value) => self.expression_statement(value, tail),
+            other => self.expression_statement(other, tail),
+        }
+    }
+
+    fn return_null(&mut self, tail: bool) {
+        if tail {
+            self.line("return null;");
+        }
+    }
+
+    // The body of a farm loop, whose scope `enter` has already opened.
+    fn loop_body(&mut self, head: String, body: &[Ast]) -> Result<(), Rekt> {
+        // The hoisted `let` belongs inside the loop, after its head
+        let hoisted = self.take_last_line_if(|line| line.starts_with("let ") && !line.contains('='));
+        self.line(&head);
+        let result = self.indented(|js| {
+            if let Some(hoisted) = hoisted {
+                js.line(&hoisted);
+            }
+            js.statements(body, false)
+        });
+        self.leave();
+        result?;
+        self.line("}");
+        Ok(())
+    }
+
+    fn take_last_line_if(&mut self, keep: impl Fn(&str) -> bool) -> Option<String> {
+        let trimmed = self.out.trim_end_matches('\n');
+        let start = trimmed.rfind('\n').map(|at| at + 1).unwrap_or(0);
+        let last = trimmed[start..].trim_start().to_string();
+        if !keep(&last) {
+            return None;
+        }
+        self.out.truncate(start);
+        Some(last)
+    }
+
+    fn expression_statement(&mut self, value: &Ast, tail: bool) -> Result<(), Rekt> {
+        match value.unlocated() {
+            Ast::Match { scrutinee, arms } => self.match_statement(scrutinee, arms, tail, true),
+            Ast::Assign { .. } | Ast::SetIndex { .. } | Ast::SetField { .. } | Ast::CompoundAssign { .. } | Ast::Delete(_) => {
+                let write = self.write(value, tail)?;
+                for setup in &write.setup {
+                    self.line(setup);
+                }
+                self.line(&format!("{};", write.store));
+                if tail {
+                    self.line(&format!("return {};", write.value));
+                }
+                Ok(())
+            }
+            _ => {
+                let value = self.expr(value)?;
+                match tail {
+                    true => self.line(&format!("return {};", value)),
+                    false => self.line(&format!("{};", value)),
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // A contract's body, where the last statement's value is returned.
+    fn contract_body(&mut self, params: &[String], body: &[Ast]) -> Result<String, Rekt> {
+        let floor = std::mem::replace(&mut self.function_floor, self.scopes.len());
+        let text = self.block_text(|js| {
+            js.enter(params, body, None);
+            let result = js.statements(body, true);
+            js.leave();
+            result
+        });
+        self.function_floor = floor;
+        text
+    }
+
+    // ------------------------------------------------------------ vibecheck
+
+    // An if/else chain over the arms, which run as statements. Its
+    // temporaries get a block of their own unless `braced` is false because
+    // it's the whole of one already.
+    fn match_statement(&mut self, scrutinee: &Ast, arms: &[(Pattern, Ast)], tail: bool, braced: bool) -> Result<(), Rekt> {
+        let mut bound = Vec::new();
+        for (pattern, _) in arms {
+            pattern.bindings(&mut bound);
+        }
+        let value = self.expr(scrutinee)?;
+        let plain = is_identifier(&value) && !bound.iter().any(|name| ident(name) == value);
+        let needs_bound = arms.iter().any(|(pattern, _)| !pattern_is_catch_all(pattern) && binds(pattern));
+        let wrapped = braced && (!plain || needs_bound);
+        let subject = if plain { value.clone() } else { "$subject".to_string() };
+
+        if wrapped {
+            self.line("{");
+            self.depth += 1;
+        }
+        if !plain {
+            self.line(&format!("const $subject = {};", value));
+        }
+        if needs_bound {
+            self.line("let $bound;");
+        }
+        let result = self.arms(&subject, arms, tail);
+        if wrapped {
+            self.depth -= 1;
+            self.line("}");
+        }
+        result
+    }
+
+    fn arms(&mut self, subject: &str, arms: &[(Pattern, Ast)], tail: bool) -> Result<(), Rekt> {
+        for (index, (pattern, body)) in arms.iter().enumerate() {
+            let mut names = Vec::new();
+            pattern.bindings(&mut names);
+            let mut unique: Vec<String> = Vec::new();
+            for name in names {
+                if !unique.contains(&name) {
+                    unique.push(name);
+                }
+            }
+            let catch_all = pattern_is_catch_all(pattern);
+            let opening = match (catch_all, index) {
+                (true, 0) => "{".to_string(),
+                (true, _) => "} else {".to_string(),
+                (false, _) => {
+                    let pattern = self.pattern(pattern);
+                    let test = match unique.is_empty() {
+                        true => format!("$.match({}, {})", subject, pattern),
+                        false => format!("($bound = $.match({}, {}))", subject, pattern),
+                    };
+                    let keyword = if index == 0 { "if" } else { "} else if" };
+                    format!("{} ({}) {{", keyword, test)
+                }
+            };
+            self.line(&opening);
+            self.indented(|js| js.arm(subject, pattern, &unique, body, tail))?;
+            if catch_all {
+                self.line("}");
+                return Ok(());
+            }
+        }
+        self.line("} else {");
+        self.indented(|js| {
+            js.line(&format!("$.unmatched({});", subject));
+            Ok(())
+        })?;
+        self.line("}");
+        Ok(())
+    }
+
+    fn arm(&mut self, subject: &str, pattern: &Pattern, names: &[String], body: &Ast, tail: bool) -> Result<(), Rekt> {
+        let stmts = match body.unlocated() {
+            Ast::Block(stmts) => stmts.as_slice(),
+            _ => &[],
+        };
+        self.enter(names, stmts, None);
+        match pattern {
+            Pattern::Binding(name) => self.line(&format!("let {} = {};", ident(name), subject)),
+            _ if names.is_empty() => {}
+            _ => {
+                let fields: Vec<String> = names
+                    .iter()
+                    .map(|name| match ident(name) {
+                        js if js == *name => js,
+                        js => format!("{}: {}", name, js),
+                    })
+                    .collect();
+                self.line(&format!("let {{ {} }} = $bound;", fields.join(", ")));
+            }
+        }
+        let result = match body.unlocated() {
+            Ast::Block(stmts) => self.statements(stmts, tail),
+            other => self.expression_statement(other, tail),
+        };
+        self.leave();
+        result
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) -> String {
+        self.patterns = true;
+        match pattern {
+            Pattern::Wildcard => "P.wild".to_string(),
+            Pattern::Literal(value) => format!("P.lit({})", literal(value).unwrap_or_else(|_| "null".to_string())),
+            Pattern::Range(start, end) => format!(
+                "P.range({}, {})",
+                literal(start).unwrap_or_else(|_| "null".to_string()),
+                literal(end).unwrap_or_else(|_| "null".to_string())
+            ),
+            Pattern::Binding(name) => format!("P.bind({})", js_string(name)),
+            Pattern::Struct { name, fields } => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(field, sub)| format!("{}: {}", field, self.pattern(sub)))
+                    .collect();
+                let fields = if fields.is_empty() { "{}".to_string() } else { format!("{{ {} }}", fields.join(", ")) };
+                format!("P.struct({}, {})", js_string(name), fields)
+            }
+            Pattern::Array { items, rest } => {
+                let items: Vec<String> = items.iter().map(|item| self.pattern(item)).collect();
+                match rest {
+                    Some(rest) => format!("P.array([{}], {})", items.join(", "), js_string(rest)),
+                    None => format!("P.array([{}])", items.join(", ")),
+                }
+            }
+            Pattern::Or(alternatives) => {
+                let alternatives: Vec<String> = alternatives.iter().map(|p| self.pattern(p)).collect();
+                format!("P.or({})", alternatives.join(", "))
+            }
+        }
+    }
+
+    // ------------------------------------------------------------ expressions
+
+    fn expr(&mut self, ast: &Ast) -> Result<String, Rekt> {
+        Ok(match ast {
+            Ast::Located { line, statement } => return self.expr(statement).map_err(|e| e.at_line(*line)),
+            Ast::Literal(value) => literal(value)?,
+            Ast::Variable(name) | Ast::ResolvedVariable { name, .. } => self.read(name),
+            Ast::Grouping(inner) => self.expr(inner)?,
+
+            Ast::Binary { left, operator, right } => {
+                format!("$.{}({}, {})", operator_method(operator), self.expr(left)?, self.expr(right)?)
+            }
+            Ast::Unary { operator: BinaryOp::Not, operand } if is_signal(operand) => {
+                format!("!{}", self.wrapped(operand)?)
+            }
+            Ast::Unary { operator, operand } => format!("$.{}({})", operator_method(operator), self.expr(operand)?),
+            Ast::Ternary { condition, then_branch, else_branch } => format!(
+                "({} ? {} : {})",
+                self.condition(condition)?,
+                self.expr(then_branch)?,
+                self.expr(else_branch)?
+            ),
+            Ast::Interpolation(parts) => {
+                let mut text = String::from("`");
+                for part in parts {
+                    match part {
+                        Ast::Literal(Value::Text(s)) => text.push_str(&template_text(s)),
+                        other => text.push_str(&format!("${{$.show({})}}", self.expr(other)?)),
+                    }
+                }
+                text + "`"
+            }
+
+            Ast::Call { callee, arguments } => {
+                let args = arguments.iter().map(|arg| self.expr(arg)).collect::<Result<Vec<_>, _>>()?;
+                if let Ast::Variable(name) = callee.unlocated() {
+                    match self.lookup(name) {
+                        None if BUILTINS.contains(&name.as_str()) => {
+                            return Ok(format!("$.{}({})", name, args.join(", ")));
+                        }
+                        Some(Binding { js, kind: Some(DeclKind::Contract(arity)), hoisted: false, .. })
+                            if *arity == args.len() && !self.assigned.contains(name) =>
+                        {
+                            return Ok(format!("{}({})", js, args.join(", ")));
+                        }
+                        _ => {}
+                    }
+                }
+                let callee = self.expr(callee)?;
+                let args: String = args.iter().map(|arg| format!(", {}", arg)).collect();
+                format!("$.call({}{})", callee, args)
+            }
+            Ast::Lambda { params, body, .. } => {
+                let names: Vec<String> = params.iter().map(|(param, _)| param.clone()).collect();
+                let params = names.iter().map(|param| ident(param)).collect::<Vec<_>>().join(", ");
+                format!("$.lambda(({}) => {})", params, self.contract_body(&names, body)?)
+            }
+
+            Ast::ArrayLiteral(items) => {
+                let items = items.iter().map(|item| self.expr(item)).collect::<Result<Vec<_>, _>>()?;
+                format!("[{}]", items.join(", "))
+            }
+            Ast::MapLiteral(entries) => {
+                let mut pairs = Vec::new();
+                for (key, value) in entries {
+                    pairs.push(format!("[{}, {}]", self.expr(key)?, self.expr(value)?));
+                }
+                format!("$.mapping([{}])", pairs.join(", "))
+            }
+            Ast::StructLiteral { name, fields } => {
+                let mut seen = HashSet::new();
+                let mut inits = Vec::new();
+                for (field, value) in fields {
+                    if !seen.insert(field) {
+                        return Err(Rekt::Runtime(format!("Field '{}' set twice in '{}'", field, name)));
+                    }
+                    inits.push(format!("{}: {}", field, self.expr(value)?));
+                }
+                let inits = if inits.is_empty() { "{}".to_string() } else { format!("{{ {} }}", inits.join(", ")) };
+                format!("$.record({}, {})", js_string(name), inits)
+            }
+            Ast::Index { object, index } => format!("$.index({}, {})", self.expr(object)?, self.expr(index)?),
+            Ast::Field { object, name } => format!("$.field({}, {})", self.expr(object)?, js_string(name)),
+
+            Ast::Match { scrutinee, arms } => {
+                if arms.iter().any(|(_, body)| escapes(body, false)) {
+                    return Err(unsupported("A return, crash or skip inside a vibecheck used as a value"));
+                }
+                let body = self.block_text(|js| js.match_statement(scrutinee, arms, true, false))?;
+                format!("(() => {})()", body)
+            }
+
+            Ast::Assign { name, value } if self.writable(name).is_some() => {
+                let js = self.writable(name).unwrap_or_default();
+                format!("({} = {})", js, self.expr(value)?)
+            }
+            Ast::Assign { .. } | Ast::SetIndex { .. } | Ast::SetField { .. } | Ast::CompoundAssign { .. } | Ast::Delete(_) => {
+                let write = self.write(ast, true)?;
+                if write.setup.is_empty() {
+                    format!("({}, {})", write.store, write.value)
+                } else {
+                    let body = self.block_text(|js| {
+                        for setup in &write.setup {
+                            js.line(setup);
+                        }
+                        js.line(&format!("{};", write.store));
+                        js.line(&format!("return {};", write.value));
+                        Ok(())
+                    })?;
+                    format!("(() => {})()", body)
+                }
+            }
+
+            Ast::Program(_)
+            | Ast::Block(_)
+            | Ast::VariableDecl { .. }
+            | Ast::FunctionDecl { .. }
+            | Ast::StructDecl { .. }
+            | Ast::If { .. }
+            | Ast::While { .. }
+            | Ast::ForEach { .. }
+            | Ast::ForRange { .. }
+            | Ast::Break
+            | Ast::Continue
+            | Ast::ExpressionStmt(_)
+            | Ast::PrintStmt(_)
+            | Ast::ReturnStmt(_) => return Err(unsupported("A statement used as a value")),
+        })
+    }
+
+    // An operand that needs to stay one unit next to a prefix operator.
+    fn wrapped(&mut self, ast: &Ast) -> Result<String, Rekt> {
+        let js = self.expr(ast)?;
+        Ok(if js.starts_with('!') || js.starts_with('(') || !js.contains(' ') { js } else { format!("({})", js) })
+    }
+
+    // A `sus`/`grind`/`?` condition, checked to be a signal unless it
+    // can't be anything else.
+    fn condition(&mut self, ast: &Ast) -> Result<String, Rekt> {
+        let js = self.expr(ast)?;
+        Ok(if is_signal(ast) { js } else { format!("$.cond({})", js) })
+    }
+
+    // ------------------------------------------------------------ assignments
+
+    // `value` is only needed when the assigned value is used, which is
+    // when the path's keys get pinned down in temporaries if reading them
+    // twice could do something twice.
+    fn write(&mut self, ast: &Ast, value_used: bool) -> Result<Write, Rekt> {
+        let mut setup = Vec::new();
+        let (root, steps, stored) = match ast.unlocated() {
+            Ast::Assign { name, value } => {
+                let value = self.expr(value)?;
+                return Ok(match self.writable(name) {
+                    Some(js) => Write { setup, store: format!("{} = {}", js, value), value: js },
+                    None => self.locked(name, value),
+                });
+            }
+            Ast::CompoundAssign { target, operator, value } => {
+                let (root, steps) = self.target(target, value_used, &mut setup)?;
+                let operand = self.expr(value)?;
+                if steps.is_empty() {
+                    let current = self.read(&root);
+                    let store = format!("$.{}({}, {})", operator_method(operator), current, operand);
+                    return Ok(match self.writable(&root) {
+                        Some(js) => Write { setup, store: format!("{} = {}", js, store), value: js },
+                        None => self.locked(&root, store),
+                    });
+                }
+                let stored = format!("$.{}, {}", operator_method(operator), operand);
+                (root, steps, ("update", stored))
+            }
+            Ast::SetIndex { object, index, value } => {
+                let (root, mut steps) = self.target(object, value_used, &mut setup)?;
+                let key = self.expr(index)?;
+                steps.push(Step::Key(self.pinned(key, index, value_used, &mut setup)));
+                (root, steps, ("set", self.expr(value)?))
+            }
+            Ast::SetField { object, name, value } => {
+                let (root, steps) = self.target(object, value_used, &mut setup)?;
+                let value = self.expr(value)?;
+                let root_js = self.writable(&root);
+                let path = steps_list(&steps);
+                let mut read_steps = steps;
+                read_steps.push(Step::Dot(name.clone()));
+                return Ok(match root_js {
+                    Some(js) => Write {
+                        setup,
+                        store: format!("{} = $.setField({}, {}, {}, {})", js, js, path, js_string(name), value),
+                        value: read_back(&js, &read_steps),
+                    },
+                    None => self.locked(&root, value),
+                });
+            }
+            Ast::Delete(target) => {
+                let (root, steps) = self.target(target, false, &mut setup)?;
+                return Ok(match self.writable(&root) {
+                    Some(js) => Write {
+                        setup,
+                        store: format!("{} = $.remove({}, {})", js, js, steps_list(&steps)),
+                        value: "null".to_string(),
+                    },
+                    None => self.locked(&root, String::new()),
+                });
+            }
+            _ => return Err(Rekt::Runtime("Invalid assignment target".to_string())),
+        };
+        let (method, stored) = stored;
+        Ok(match self.writable(&root) {
+            Some(js) => Write {
+                setup,
+                store: format!("{} = $.{}({}, {}, {})", js, method, js, steps_list(&steps), stored),
+                value: read_back(&js, &steps),
+            },
+            None => self.locked(&root, stored),
+        })
+    }
+
+    fn locked(&self, name: &str, value: String) -> Write {
+        let args = if value.is_empty() { String::new() } else { format!(", {}", value) };
+        Write {
+            setup: Vec::new(),
+            store: format!("$.locked({}{})", js_string(name), args),
+            value: "null".to_string(),
+        }
+    }
+
+    fn target(&mut self, ast: &Ast, value_used: bool, setup: &mut Vec<String>) -> Result<(String, Vec<Step>), Rekt> {
+        let (root, path) = target_path(ast)?;
+        let mut steps = Vec::new();
+        for step in path {
+            steps.push(match step {
+                PathStep::Key(index) => {
+                    let key = self.expr(index)?;
+                    Step::Key(self.pinned(key, index, value_used, setup))
+                }
+                PathStep::Field(name) => Step::Dot(name.to_string()),
+            });
+        }
+        Ok((root.to_string(), steps))
+    }
+
+    fn pinned(&mut self, key: String, ast: &Ast, value_used: bool, setup: &mut Vec<String>) -> String {
+        if !value_used || is_pure(ast) {
+            return key;
+        }
+        let temp = self.temp();
+        setup.push(format!("const {} = {};", temp, key));
+        temp
+    }
+}
+
+fn steps_list(steps: &[Step]) -> String {
+    let steps: Vec<String> = steps
+        .iter()
+        .map(|step| match step {
+            Step::Key(key) => key.clone(),
+            Step::Dot(name) => format!("$.dot({})", js_string(name)),
+        })
+        .collect();
+    format!("[{}]", steps.join(", "))
+}
+
+fn read_back(root: &str, steps: &[Step]) -> String {
+    steps.iter().fold(root.to_string(), |object, step| match step {
+        Step::Key(key) => format!("$.index({}, {})", object, key),
+        Step::Dot(name) => format!("$.field({}, {})", object, js_string(name)),
+    })
+}
+
+fn operator_method(operator: &BinaryOp) -> &'static str {
+    match operator {
+        BinaryOp::Add => "plus",
+        BinaryOp::Subtract => "minus",
+        BinaryOp::Multiply => "times",
+        BinaryOp::Divide => "divide",
+        BinaryOp::Equal => "eq",
+        BinaryOp::NotEqual => "ne",
+        BinaryOp::Less => "lt",
+        BinaryOp::Greater => "gt",
+        BinaryOp::LessEqual => "le",
+        BinaryOp::GreaterEqual => "ge",
+        BinaryOp::And => "and",
+        BinaryOp::Or => "or",
+        BinaryOp::Not => "not",
+        BinaryOp::Modulo => "mod",
+        BinaryOp::IntDivide => "chop",
+        BinaryOp::BitAnd => "band",
+        BinaryOp::BitOr => "bor",
+        BinaryOp::BitXor => "bxor",
+        BinaryOp::ShiftLeft => "shl",
+        BinaryOp::ShiftRight => "shr",
+        BinaryOp::BitNot => "bnot",
+    }
+}
+
+// Whether an expression can only ever be a signal.
+fn is_signal(ast: &Ast) -> bool {
+    match ast.unlocated() {
+        Ast::Binary { operator, .. } => matches!(
+            operator,
+            BinaryOp::Equal
+                | BinaryOp::NotEqual
+                | BinaryOp::Less
+                | BinaryOp::Greater
+                | BinaryOp::LessEqual
+                | BinaryOp::GreaterEqual
+        ),
+        Ast::Unary { operator: BinaryOp::Not, .. } => true,
+        Ast::Literal(Value::Signal(_) | Value::Boolean(_)) => true,
+        Ast::Grouping(inner) => is_signal(inner),
+        _ => false,
+    }
+}
+
+// Whether evaluating an expression twice is the same as doing it once.
+fn is_pure(ast: &Ast) -> bool {
+    match ast.unlocated() {
+        Ast::Literal(_) | Ast::Variable(_) | Ast::ResolvedVariable { .. } => true,
+        Ast::Grouping(_)
+        | Ast::Binary { .. }
+        | Ast::Unary { .. }
+        | Ast::Index { .. }
+        | Ast::Field { .. }
+        | Ast::Interpolation(_) => ast.children().into_iter().all(is_pure),
+        _ => false,
+    }
+}
+
+fn pattern_is_catch_all(pattern: &Pattern) -> bool {
+    matches!(pattern, Pattern::Wildcard | Pattern::Binding(_))
+}
+
+fn binds(pattern: &Pattern) -> bool {
+    let mut names = Vec::new();
+    pattern.bindings(&mut names);
+    !names.is_empty()
+}
+
+fn ident(name: &str) -> String {
+    if RESERVED.contains(&name) {
+        format!("${}", name)
+    } else {
+        name.to_string()
+    }
+}
+
+fn is_identifier(js: &str) -> bool {
+    !js.is_empty() && js.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+// How the runtime names a declared field type, which is how GM prints it.
+fn type_name(t: &Type) -> String {
+    t.to_string()
+}
+
+fn literal(value: &Value) -> Result<String, Rekt> {
+    Ok(match value {
+        Value::Token(t) => number(t.parse::<f64>().unwrap_or(f64::NAN)),
+        Value::Number(n) => number(*n),
+        Value::Text(s) => js_string(s),
+        Value::Signal(b) | Value::Boolean(b) => b.to_string(),
+        Value::Int(n) => format!("{}n", n),
+        Value::Decimal(d) => format!("$.dec(\"{}\")", d),
+        Value::Null => "null".to_string(),
+        Value::Array(items) => {
+            let items = items.iter().map(literal).collect::<Result<Vec<_>, _>>()?;
+            format!("[{}]", items.join(", "))
+        }
+        Value::Map(entries) => {
+            let mut pairs = Vec::new();
+            for (key, value) in entries {
+                pairs.push(format!("[{}, {}]", literal(&key.to_value())?, literal(value)?));
+            }
+            format!("$.mapping([{}])", pairs.join(", "))
+        }
+        Value::Struct { .. } | Value::Function { .. } | Value::SmartContract { .. } | Value::Closure(_) => {
+            return Err(unsupported(&format!("A {} literal", value)))
+        }
+    })
+}
+
+fn number(n: f64) -> String {
+    if n.is_nan() {
+        "NaN".to_string()
+    } else if n.is_infinite() {
+        if n > 0.0 { "Infinity" } else { "(-Infinity)" }.to_string()
+    } else if n.is_sign_negative() {
+        format!("({})", n)
+    } else {
+        n.to_string()
+    }
+}
+
+fn js_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            other => push_escaped(&mut out, other),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn template_text(s: &str) -> String {
+    let mut out = String::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '`' => out.push_str("\\`"),
+            '$' if chars.peek() == Some(&'{') => out.push_str("\\$"),
+            other => push_escaped(&mut out, other),
+        }
+    }
+    out
+}
+
+fn push_escaped(out: &mut String, c: char) {
+    match c {
+        '\\' => out.push_str("\\\\"),
+        '\n' => out.push_str("\\n"),
+        '\r' => out.push_str("\\r"),
+        '\t' => out.push_str("\\t"),
+        c if (c as u32) < 0x20 || c == '\u{2028}' || c == '\u{2029}' => {
+            out.push_str(&format!("\\u{{{:x}}}", c as u32))
+        }
+        c => out.push(c),
+    }
+}
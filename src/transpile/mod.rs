@@ -0,0 +1,181 @@
+// src/transpile/mod.rs
+//
+// Source-to-source backends: each one turns a resolved tree into a program
+// in another language that behaves the same when run. What they share is
+// working out where GM's declarations can go in a language with block
+// scoping, which is the same question for all of them.
+
+pub mod javascript;
+pub mod rust;
+pub mod solidity;
+
+use std::collections::{HashMap, HashSet};
+use crate::error::Rekt;
+use crate::parser::ast::Ast;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeclKind {
+    Token,
+    Block,
+    Contract(usize), // with its arity
+}
+
+// One name a scope declares, and whether its declaration can stay where it
+// is written. A name declared once, straight in the scope's own list, maps
+// onto a block-scoped `let` in place. Anything declared twice, inside a
+// `sus` or `grind` body (which share the scope around them), or on top of
+// a param or loop variable is `hoisted`: declared once up front, then
+// assigned wherever GM mints it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declaration {
+    pub name: String,
+    pub kind: DeclKind,
+    pub hoisted: bool,
+}
+
+// The declarations of a scope that starts out holding `bound`, in the
+// order they're first made.
+pub fn declarations(bound: &[String], stmts: &[Ast]) -> Vec<Declaration> {
+    let mut found = Vec::new();
+    for stmt in stmts {
+        collect(stmt, true, &mut found);
+    }
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for (name, _, _) in &found {
+        *counts.entry(name.as_str()).or_insert(0) += 1;
+    }
+
+    let mut out: Vec<Declaration> = Vec::new();
+    for (name, kind, direct) in &found {
+        if out.iter().any(|d| &d.name == name) {
+            continue;
+        }
+        let hoisted = !direct || counts[name.as_str()] > 1 || bound.contains(name);
+        out.push(Declaration { name: name.clone(), kind: *kind, hoisted });
+    }
+    out
+}
+
+fn collect(stmt: &Ast, direct: bool, out: &mut Vec<(String, DeclKind, bool)>) {
+    match stmt {
+        Ast::Located { statement, .. } => collect(statement, direct, out),
+        Ast::VariableDecl { name, is_constant, .. } => {
+            let kind = if *is_constant { DeclKind::Block } else { DeclKind::Token };
+            out.push((name.clone(), kind, direct));
+        }
+        Ast::FunctionDecl { name, params, .. } => out.push((name.clone(), DeclKind::Contract(params.len()), direct)),
+        Ast::If { then_branch, else_branch, .. } => {
+            for stmt in then_branch.iter().chain(else_branch.iter().flatten()) {
+                collect(stmt, false, out);
+            }
+        }
+        Ast::While { body, .. } => {
+            for stmt in body {
+                collect(stmt, false, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Every name that's assigned to somewhere, as a whole or along a path.
+pub fn assigned_names(ast: &Ast, out: &mut HashSet<String>) {
+    let root = match ast {
+        Ast::Assign { name, .. } => Some(name.clone()),
+        Ast::SetIndex { object: target, .. }
+        | Ast::SetField { object: target, .. }
+        | Ast::CompoundAssign { target, .. }
+        | Ast::Delete(target) => target_path(target).ok().map(|(root, _)| root.to_string()),
+        _ => None,
+    };
+    out.extend(root);
+    for child in ast.children() {
+        assigned_names(child, out);
+    }
+}
+
+// One step of an assignment target below its root token.
+pub enum PathStep<'a> {
+    Key(&'a Ast),   // [index]
+    Field(&'a str), // .field
+}
+
+// Breaks `m[a].b` down into its root token and the steps below it, from
+// the root down.
+pub fn target_path(ast: &Ast) -> Result<(&str, Vec<PathStep<'_>>), Rekt> {
+    match ast.unlocated() {
+        Ast::Variable(name) | Ast::ResolvedVariable { name, .. } => Ok((name, Vec::new())),
+        Ast::Grouping(inner) => target_path(inner),
+        Ast::Index { object, index } => {
+            let (root, mut steps) = target_path(object)?;
+            steps.push(PathStep::Key(index));
+            Ok((root, steps))
+        }
+        Ast::Field { object, name } => {
+            let (root, mut steps) = target_path(object)?;
+            steps.push(PathStep::Field(name));
+            Ok((root, steps))
+        }
+        _ => Err(Rekt::Runtime("Invalid assignment target".to_string())),
+    }
+}
+
+// An assignment broken into what has to run first (temporaries), the
+// expression that stores the new value and one that reads it back.
+pub struct Write {
+    pub setup: Vec<String>,
+    pub store: String,
+    pub value: String,
+}
+
+// What a backend fails with when a `sus`/`grind`/`?` condition isn't a
+// signal. Backends that can see the condition's type check it themselves;
+// the others leave it to their runtime's `cond`.
+pub const NOT_A_SIGNAL: &str = "Condition must be bullish or bearish (yes/no)";
+
+// Whether `stmt` is a crash or skip that no loop catches, given how many
+// loops are around it inside its contract. Outside a loop, crash and skip
+// just end whatever is running: the contract, or the script.
+pub fn ends_run(stmt: &Ast, loops: usize) -> bool {
+    matches!(stmt, Ast::Break | Ast::Continue) && loops == 0
+}
+
+// Whether a return, crash or skip in `ast` would leave it. Contracts
+// inside don't count, and loops inside catch their own crash and skip.
+pub fn escapes(ast: &Ast, in_loop: bool) -> bool {
+    match ast {
+        Ast::ReturnStmt(_) => true,
+        Ast::Break | Ast::Continue => !in_loop,
+        Ast::FunctionDecl { .. } | Ast::Lambda { .. } => false,
+        Ast::While { body, .. } | Ast::ForEach { body, .. } | Ast::ForRange { body, .. } => {
+            ast.children().into_iter().filter(|child| !body.contains(child)).any(|child| escapes(child, in_loop))
+                || body.iter().any(|stmt| escapes(stmt, true))
+        }
+        other => other.children().into_iter().any(|child| escapes(child, in_loop)),
+    }
+}
+
+// Whether `name` comes up in `ast` outside any contract body, where it
+// would be looked up when `ast` runs.
+pub fn mentions(ast: &Ast, name: &str) -> bool {
+    match ast {
+        Ast::Variable(n) | Ast::ResolvedVariable { name: n, .. } | Ast::Assign { name: n, .. } if n == name => true,
+        Ast::FunctionDecl { .. } | Ast::Lambda { .. } => false,
+        other => other.children().into_iter().any(|child| mentions(child, name)),
+    }
+}
+
+// Whether a scope's statements use `name` before (or while) declaring it.
+pub fn read_first(stmts: &[Ast], name: &str) -> bool {
+    for stmt in stmts {
+        match stmt.unlocated() {
+            Ast::VariableDecl { name: declared, initializer, .. } if declared == name => {
+                return mentions(initializer, name);
+            }
+            Ast::FunctionDecl { name: declared, .. } if declared == name => return false,
+            other if mentions(other, name) => return true,
+            _ => {}
+        }
+    }
+    false
+}
@@ -0,0 +1,965 @@
+// src/transpile/rust.rs
+//
+// Turns a resolved tree into a Rust module that runs natively on the
+// gm-runtime crate. The module keeps the script's shape: tokens become
+// `let` bindings, contracts closures, loops `for`/`while` and vibechecks
+// if/else chains. What GM means by each operation (operators, indexing,
+// copying collections, struct checks, builtins) is the runtime's job, so
+// the module does exactly what the tree walker does.
+//
+// Rust won't let a closure share a local the way GM contracts share their
+// wallet, so bindings a contract captures, and bindings declared ahead of
+// where GM mints them, live in a `Cell` instead. Working out which those
+// are takes a first pass over the script before the real one.
+//
+// The module exports `run`, which takes the function each broadcast is
+// handed to:
+//
+//   mod script;
+//   script::run(|text, _| println!("{}", text))?;
+
+use std::collections::{HashMap, HashSet};
+use crate::error::Rekt;
+use crate::parser::ast::{Ast, Pattern};
+use crate::shared_types::{BinaryOp, Type, Value};
+use super::{declarations, ends_run, escapes, read_first, target_path, DeclKind, PathStep, Write};
+
+// Names GM allows that Rust doesn't, or that the module itself uses, which
+// get a `_` after them.
+const RESERVED: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true",
+    "type", "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final",
+    "gen", "macro", "override", "priv", "try", "typeof", "unsized", "virtual", "yield",
+    "ch", "args", "subject", "bound", "path", "value", "root", "gm", "emit", "run", "main",
+    "Value", "Cell", "Chain", "Step", "Ok", "Err", "Some", "None", "Result", "Option", "Vec",
+    "String", "Box",
+];
+
+// `source` is only used to say where the module came from.
+pub fn transpile(ast: &Ast, source: &str) -> Result<String, Rekt> {
+    let stmts = match ast {
+        Ast::Program(stmts) => stmts.as_slice(),
+        other => std::slice::from_ref(other),
+    };
+    // The first pass only finds out which bindings contracts capture
+    let mut scout = Rs::new(HashSet::new());
+    scout.program(stmts)?;
+    let mut rs = Rs::new(scout.captured);
+    rs.program(stmts)?;
+
+    Ok(format!(
+        "// Generated by gm-language from {}.\n\
+         // Broadcasts go to the function passed to `run`.\n\
+         #![allow(unused_mut, unused_variables, unused_assignments, unused_imports, unused_braces, unused_parens)]\n\
+         #![allow(unreachable_code, non_snake_case, clippy::all)]\n\
+         \n\
+         use gm_runtime::{{self as gm, Cell, Chain, Step, Value}};\n\
+         \n\
+         pub fn run(emit: impl FnMut(&str, &Value) + 'static) -> Result<(), gm::Rekt> {{\n\
+         \x20   let ch = &Chain::new(emit);\n\
+         {}\
+         \x20   Ok(())\n\
+         }}\n",
+        source, rs.out
+    ))
+}
+
+fn unsupported(what: &str) -> Rekt {
+    Rekt::Type(format!("{} can't be transpiled to Rust", what))
+}
+
+#[derive(Clone)]
+struct Binding {
+    rs: String,             // the identifier it's written as
+    kind: Option<DeclKind>, // None for params, loop variables and match bindings
+    hoisted: bool,          // declared up front, so possibly still unset
+    live: bool,             // its declaration has been written out
+    cell: bool,             // shared through a `Cell` rather than a plain local
+    id: usize,              // the same in both passes
+}
+
+// A contract being written, and the cells it has to take a handle to
+// because something in it reads a binding from outside.
+struct Frame {
+    floor: usize,
+    captures: Vec<String>,
+}
+
+struct Rs {
+    out: String,
+    depth: usize,
+    scopes: Vec<HashMap<String, Binding>>,
+    function_floor: usize, // scopes below this belong to enclosing contracts
+    frames: Vec<Frame>,
+    cells: HashSet<usize>,    // bindings the first pass saw captured
+    captured: HashSet<usize>, // bindings this pass saw captured
+    ids: usize,
+    loops: usize,  // loops around the statement being written, inside its contract
+    returns: bool, // whether it's inside something that returns a value
+}
+
+impl Rs {
+    fn new(cells: HashSet<usize>) -> Self {
+        Rs {
+            out: String::new(),
+            depth: 1,
+            scopes: Vec::new(),
+            function_floor: 0,
+            frames: Vec::new(),
+            cells,
+            captured: HashSet::new(),
+            ids: 0,
+            loops: 0,
+            returns: false,
+        }
+    }
+
+    fn program(&mut self, stmts: &[Ast]) -> Result<(), Rekt> {
+        for line in self.enter(&[], stmts) {
+            self.line(&line);
+        }
+        self.statements(stmts, false)?;
+        self.leave();
+        Ok(())
+    }
+
+    fn line(&mut self, text: &str) {
+        self.out.push_str(&"    ".repeat(self.depth));
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn indented(&mut self, f: impl FnOnce(&mut Self) -> Result<(), Rekt>) -> Result<(), Rekt> {
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
+    // Writes `f`'s lines one level in and returns them as a `{ }` block
+    // that can sit inside an expression.
+    fn block_text(&mut self, f: impl FnOnce(&mut Self) -> Result<(), Rekt>) -> Result<String, Rekt> {
+        let outer = std::mem::take(&mut self.out);
+        let result = self.indented(f);
+        let inner = std::mem::replace(&mut self.out, outer);
+        result?;
+        Ok(format!("{{\n{}{}}}", inner, "    ".repeat(self.depth)))
+    }
+
+    // Opens a GM scope holding `bound`, then whatever `stmts` declare, and
+    // returns the lines that set its cells up. Cells are declared at the
+    // top of the scope, so contracts can take a handle to one before GM
+    // mints it; one that would hide an outer binding the scope still reads
+    // gets its own identifier.
+    fn enter(&mut self, bound: &[String], stmts: &[Ast]) -> Vec<String> {
+        let mut scope = HashMap::new();
+        let mut lines = Vec::new();
+        for name in bound {
+            let id = self.next_id();
+            let cell = self.cells.contains(&id);
+            let rs = ident(name);
+            if cell {
+                lines.push(format!("let {} = Cell::with({});", rs, rs));
+            }
+            scope.insert(name.clone(), Binding { rs, kind: None, hoisted: false, live: true, cell, id });
+        }
+        for decl in declarations(bound, stmts) {
+            if bound.contains(&decl.name) {
+                continue;
+            }
+            let id = self.next_id();
+            let cell = decl.hoisted || self.cells.contains(&id);
+            let shadows = cell
+                && self.lookup(&decl.name).is_some()
+                && (decl.hoisted || read_first(stmts, &decl.name));
+            let rs = if shadows { self.renamed(&decl.name) } else { ident(&decl.name) };
+            if cell {
+                lines.push(format!("let {} = Cell::new();", rs));
+            }
+            let binding = Binding { rs, kind: Some(decl.kind), hoisted: decl.hoisted, live: decl.hoisted, cell, id };
+            scope.insert(decl.name, binding);
+        }
+        self.scopes.push(scope);
+        lines
+    }
+
+    fn leave(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn next_id(&mut self) -> usize {
+        self.ids += 1;
+        self.ids
+    }
+
+    fn renamed(&self, name: &str) -> String {
+        format!("{}_{}_", ident(name), self.scopes.len())
+    }
+
+    // The binding a name refers to at this point of the script, and the
+    // scope it's in. Inside the contract being written, a token whose
+    // declaration hasn't been reached yet isn't minted, so an outer one is
+    // found instead, like at runtime.
+    fn lookup(&self, name: &str) -> Option<(usize, &Binding)> {
+        let mut unminted = None;
+        for (index, scope) in self.scopes.iter().enumerate().rev() {
+            if let Some(binding) = scope.get(name) {
+                if binding.live || index < self.function_floor {
+                    return Some((index, binding));
+                }
+                unminted.get_or_insert((index, binding));
+            }
+        }
+        unminted
+    }
+
+    // `lookup` for a use of the name, which captures it if it belongs to an
+    // enclosing contract.
+    fn resolve(&mut self, name: &str) -> Option<Binding> {
+        let (index, binding) = self.lookup(name)?;
+        let binding = binding.clone();
+        if index < self.function_floor {
+            self.captured.insert(binding.id);
+            for frame in self.frames.iter_mut().filter(|frame| index < frame.floor) {
+                if !frame.captures.contains(&binding.rs) {
+                    frame.captures.push(binding.rs.clone());
+                }
+            }
+        }
+        Some(binding)
+    }
+
+    fn minted(&mut self, name: &str) {
+        if let Some(binding) = self.scopes.last_mut().and_then(|scope| scope.get_mut(name)) {
+            binding.live = true;
+        }
+    }
+
+    fn read(&mut self, name: &str) -> String {
+        match self.resolve(name) {
+            Some(binding) if binding.cell => format!("{}.get({:?})?", binding.rs, name),
+            Some(binding) => format!("{}.clone()", binding.rs),
+            None => format!("gm::unminted({:?})?", name),
+        }
+    }
+
+    // Writes the line that mints `name` in the current scope to `value`.
+    fn mint(&mut self, name: &str, value: &str, constant: bool) {
+        let binding = self.scopes.last().and_then(|scope| scope.get(name)).cloned();
+        let line = match binding {
+            Some(binding) if binding.cell => format!("{}.set({});", binding.rs, value),
+            Some(binding) if binding.hoisted || binding.kind.is_none() => format!("{} = {};", binding.rs, value),
+            Some(binding) if constant => format!("let {} = {};", binding.rs, value),
+            Some(binding) => format!("let mut {} = {};", binding.rs, value),
+            None => format!("let mut {} = {};", ident(name), value),
+        };
+        self.line(&line);
+        self.minted(name);
+    }
+
+    // ------------------------------------------------------------ statements
+
+    // `tail` statements are the last thing a contract runs, so their value
+    // is its return value.
+    fn statements(&mut self, stmts: &[Ast], tail: bool) -> Result<(), Rekt> {
+        if stmts.is_empty() && tail {
+            self.line("return Ok(Value::Null);");
+        }
+        for (index, stmt) in stmts.iter().enumerate() {
+            self.statement(stmt, tail && index + 1 == stmts.len())?;
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self, stmt: &Ast, tail: bool) -> Result<(), Rekt> {
+        match stmt {
+            Ast::Located { line, statement } => self.statement(statement, tail).map_err(|e| e.at_line(*line)),
+
+            Ast::VariableDecl { name, initializer, is_constant, .. } => {
+                let value = self.expr(initializer)?;
+                self.mint(name, &value, *is_constant);
+                if tail {
+                    let value = self.read(name);
+                    self.line(&format!("return Ok({});", value));
+                }
+                Ok(())
+            }
+            Ast::FunctionDecl { name, params, body, .. } => {
+                let hoisted = self.scopes.last().and_then(|scope| scope.get(name)).is_some_and(|b| b.hoisted);
+                if !hoisted {
+                    self.minted(name);
+                }
+                let contract = self.contract(name, params, body)?;
+                self.mint(name, &contract, false);
+                if tail {
+                    let value = self.read(name);
+                    self.line(&format!("return Ok({});", value));
+                }
+                Ok(())
+            }
+            Ast::StructDecl { name, fields, .. } => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(field, t)| {
+                        let t = t.as_ref().map(|t| format!("Some({})", type_expr(t))).unwrap_or_else(|| "None".to_string());
+                        format!("({:?}, {})", field, t)
+                    })
+                    .collect();
+                self.line(&format!("ch.declare({:?}, vec![{}]);", name, fields.join(", ")));
+                self.return_null(tail);
+                Ok(())
+            }
+
+            Ast::If { condition, then_branch, else_branch } => {
+                let condition = self.condition(condition)?;
+                self.line(&format!("if {} {{", condition));
+                self.indented(|rs| rs.statements(then_branch, tail))?;
+                let mut branch = else_branch.as_deref();
+                loop {
+                    match branch {
+                        None => {
+                            self.line("}");
+                            self.return_null(tail);
+                            return Ok(());
+                        }
+                        Some([only]) if matches!(only.unlocated(), Ast::If { .. }) => {
+                            let Ast::If { condition, then_branch, else_branch } = only.unlocated() else {
+                                unreachable!()
+                            };
+                            let condition = self.condition(condition)?;
+                            self.line(&format!("}} else if {} {{", condition));
+                            self.indented(|rs| rs.statements(then_branch, tail))?;
+                            branch = else_branch.as_deref();
+                        }
+                        Some(stmts) => {
+                            self.line("} else {");
+                            self.indented(|rs| rs.statements(stmts, tail))?;
+                            self.line("}");
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+            Ast::While { condition, body } => {
+                let condition = self.condition(condition)?;
+                self.line(&format!("while {} {{", condition));
+                self.indented(|rs| rs.loop_statements(body))?;
+                self.line("}");
+                self.return_null(tail);
+                Ok(())
+            }
+            Ast::ForEach { key, variable, iterable, body } => {
+                let items = self.expr(iterable)?;
+                let bound: Vec<String> = key.iter().chain([variable]).cloned().collect();
+                let setup = self.enter(&bound, body);
+                let head = match key {
+                    Some(key) if key == variable => format!("for (_, mut {}) in gm::pairs({})? {{", ident(variable), items),
+                    Some(key) => format!("for (mut {}, mut {}) in gm::pairs({})? {{", ident(key), ident(variable), items),
+                    None => format!("for mut {} in gm::items({})? {{", ident(variable), items),
+                };
+                self.loop_body(head, setup, body)?;
+                self.return_null(tail);
+                Ok(())
+            }
+            Ast::ForRange { variable, start, end, step, body } => {
+                let mut bounds = vec![self.expr(start)?, self.expr(end)?];
+                match step {
+                    Some(step) => bounds.push(self.expr(step)?),
+                    None => bounds.push("Value::Number(1.0)".to_string()),
+                }
+                let setup = self.enter(std::slice::from_ref(variable), body);
+                let head = format!("for mut {} in gm::steps({})? {{", ident(variable), bounds.join(", "));
+                self.loop_body(head, setup, body)?;
+                self.return_null(tail);
+                Ok(())
+            }
+            Ast::Block(stmts) => {
+                self.line("{");
+                self.indented(|rs| {
+                    for line in rs.enter(&[], stmts) {
+                        rs.line(&line);
+                    }
+                    let result = rs.statements(stmts, tail);
+                    rs.leave();
+                    result
+                })?;
+                self.line("}");
+                Ok(())
+            }
+
+            Ast::PrintStmt(value) => {
+                let value = self.expr(value)?;
+                self.line(&format!("ch.broadcast(&{});", value));
+                self.return_null(tail);
+                Ok(())
+            }
+            Ast::ReturnStmt(value) => {
+                match (value, self.returns) {
+                    (Some(value), true) => return self.expression_statement(value, true),
+                    (None, true) => self.line("return Ok(Value::Null);"),
+                    // Returning from the script itself just stops it
+                    (Some(value), false) => {
+                        self.expression_statement(value, false)?;
+                        self.line("return Ok(());");
+                    }
+                    (None, false) => self.line("return Ok(());"),
+                }
+                Ok(())
+            }
+            stmt if ends_run(stmt, self.loops) => {
+                match self.returns {
+                    true => self.line("return Ok(Value::Null);"),
+                    false => self.line("return Ok(());"),
+                }
+                Ok(())
+            }
+            Ast::Break => {
+                self.line("break;");
+                Ok(())
+            }
+            Ast::Continue => {
+                self.line("continue;");
+                Ok(())
+            }
+            Ast::ExpressionStmt(value) => self.expression_statement(value, tail),
+            other => self.expression_statement(other, tail),
+        }
+    }
+
+    fn return_null(&mut self, tail: bool) {
+        if tail {
+            self.line("return Ok(Value::Null);");
+        }
+    }
+
+    fn loop_statements(&mut self, body: &[Ast]) -> Result<(), Rekt> {
+        self.loops += 1;
+        let result = self.statements(body, false);
+        self.loops -= 1;
+        result
+    }
+
+    // The body of a farm loop, whose scope `enter` has already opened.
+    fn loop_body(&mut self, head: String, setup: Vec<String>, body: &[Ast]) -> Result<(), Rekt> {
+        self.line(&head);
+        let result = self.indented(|rs| {
+            for line in &setup {
+                rs.line(line);
+            }
+            rs.loop_statements(body)
+        });
+        self.leave();
+        result?;
+        self.line("}");
+        Ok(())
+    }
+
+    fn expression_statement(&mut self, value: &Ast, tail: bool) -> Result<(), Rekt> {
+        match value.unlocated() {
+            Ast::Match { scrutinee, arms } => self.match_statement(scrutinee, arms, tail),
+            Ast::Assign { .. } | Ast::SetIndex { .. } | Ast::SetField { .. } | Ast::CompoundAssign { .. } | Ast::Delete(_) => {
+                let write = self.write(value, tail)?;
+                for setup in &write.setup {
+                    self.line(setup);
+                }
+                self.line(&format!("{};", write.store));
+                if tail {
+                    self.line(&format!("return Ok({});", write.value));
+                }
+                Ok(())
+            }
+            _ => {
+                let value = self.expr(value)?;
+                match tail {
+                    true => self.line(&format!("return Ok({});", value)),
+                    false => self.line(&format!("{};", value)),
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // A contract value: a closure taking the chain and its arguments, with
+    // a handle on every outside cell its body uses.
+    fn contract(&mut self, name: &str, params: &[(String, Type)], body: &[Ast]) -> Result<String, Rekt> {
+        let names: Vec<String> = params.iter().map(|(param, _)| param.clone()).collect();
+        let floor = std::mem::replace(&mut self.function_floor, self.scopes.len());
+        let loops = std::mem::replace(&mut self.loops, 0);
+        let returns = std::mem::replace(&mut self.returns, true);
+        self.frames.push(Frame { floor: self.scopes.len(), captures: Vec::new() });
+        let text = self.block_text(|rs| {
+            if !names.is_empty() {
+                // A repeated param name ends up with the last argument
+                let pattern: Vec<String> = names
+                    .iter()
+                    .enumerate()
+                    .map(|(i, param)| match names[i + 1..].contains(param) {
+                        true => "_".to_string(),
+                        false => format!("mut {}", ident(param)),
+                    })
+                    .collect();
+                rs.line(&format!("let [{}] = gm::params::<{}>(args);", pattern.join(", "), names.len()));
+            }
+            for line in rs.enter(&names, body) {
+                rs.line(&line);
+            }
+            let result = rs.statements(body, true);
+            rs.leave();
+            result
+        });
+        let frame = self.frames.pop().unwrap_or(Frame { floor, captures: Vec::new() });
+        self.function_floor = floor;
+        self.loops = loops;
+        self.returns = returns;
+
+        let closure = format!("|ch: &Chain, args: Vec<Value>| {}", text?);
+        if frame.captures.is_empty() {
+            return Ok(format!("gm::contract({:?}, {}, {})", name, names.len(), closure));
+        }
+        let pad = "    ".repeat(self.depth + 1);
+        let mut wrapped = String::from("{\n");
+        for capture in &frame.captures {
+            wrapped.push_str(&format!("{}let {} = {}.clone();\n", pad, capture, capture));
+        }
+        wrapped.push_str(&format!("{}move {}\n{}}}", pad, closure.replace('\n', "\n    "), "    ".repeat(self.depth)));
+        Ok(format!("gm::contract({:?}, {}, {})", name, names.len(), wrapped))
+    }
+
+    // ------------------------------------------------------------ vibecheck
+
+    // An if/else chain over the arms, which run as statements.
+    fn match_statement(&mut self, scrutinee: &Ast, arms: &[(Pattern, Ast)], tail: bool) -> Result<(), Rekt> {
+        let value = self.expr(scrutinee)?;
+        let subject = match value.strip_suffix(".clone()") {
+            Some(local) if is_identifier(local) => local.to_string(),
+            _ => {
+                self.line(&format!("let subject = {};", value));
+                "subject".to_string()
+            }
+        };
+
+        for (index, (pattern, body)) in arms.iter().enumerate() {
+            let mut names = Vec::new();
+            pattern.bindings(&mut names);
+            let mut unique: Vec<String> = Vec::new();
+            for name in names {
+                if !unique.contains(&name) {
+                    unique.push(name);
+                }
+            }
+            let catch_all = matches!(pattern, Pattern::Wildcard | Pattern::Binding(_));
+            let opening = match (catch_all, index) {
+                (true, 0) => "{".to_string(),
+                (true, _) => "} else {".to_string(),
+                (false, _) => {
+                    let pattern = self.pattern(pattern);
+                    let keyword = if index == 0 { "if" } else { "} else if" };
+                    match unique.is_empty() {
+                        true => format!("{} gm::matches(&{}, &{}).is_some() {{", keyword, subject, pattern),
+                        false => format!("{} let Some(mut bound) = gm::matches(&{}, &{}) {{", keyword, subject, pattern),
+                    }
+                }
+            };
+            self.line(&opening);
+            self.indented(|rs| rs.arm(&subject, pattern, &unique, body, tail))?;
+            if catch_all {
+                self.line("}");
+                return Ok(());
+            }
+        }
+        let unmatched = match tail {
+            true => format!("return gm::unmatched(&{});", subject),
+            false => format!("gm::unmatched(&{})?;", subject),
+        };
+        self.line("} else {");
+        self.depth += 1;
+        self.line(&unmatched);
+        self.depth -= 1;
+        self.line("}");
+        Ok(())
+    }
+
+    fn arm(&mut self, subject: &str, pattern: &Pattern, names: &[String], body: &Ast, tail: bool) -> Result<(), Rekt> {
+        let stmts = match body.unlocated() {
+            Ast::Block(stmts) => stmts.as_slice(),
+            _ => &[],
+        };
+        let setup = self.enter(names, stmts);
+        match pattern {
+            Pattern::Binding(name) => self.line(&format!("let mut {} = {}.clone();", ident(name), subject)),
+            _ => {
+                for name in names {
+                    self.line(&format!("let mut {} = bound.take({:?});", ident(name), name));
+                }
+            }
+        }
+        for line in &setup {
+            self.line(line);
+        }
+        let result = match body.unlocated() {
+            Ast::Block(stmts) => self.statements(stmts, tail),
+            other => self.expression_statement(other, tail),
+        };
+        self.leave();
+        result
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) -> String {
+        let value = |value: &Value| literal(value).unwrap_or_else(|_| "Value::Null".to_string());
+        match pattern {
+            Pattern::Wildcard => "gm::Pattern::Wildcard".to_string(),
+            Pattern::Literal(literal) => format!("gm::Pattern::Literal({})", value(literal)),
+            Pattern::Range(start, end) => format!("gm::Pattern::Range({}, {})", value(start), value(end)),
+            Pattern::Binding(name) => format!("gm::Pattern::Binding({:?})", name),
+            Pattern::Struct { name, fields } => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(field, sub)| format!("({:?}, {})", field, self.pattern(sub)))
+                    .collect();
+                format!("gm::Pattern::Struct {{ name: {:?}, fields: vec![{}] }}", name, fields.join(", "))
+            }
+            Pattern::Array { items, rest } => {
+                let items: Vec<String> = items.iter().map(|item| self.pattern(item)).collect();
+                let rest = rest.as_ref().map(|rest| format!("Some({:?})", rest)).unwrap_or_else(|| "None".to_string());
+                format!("gm::Pattern::Array {{ items: vec![{}], rest: {} }}", items.join(", "), rest)
+            }
+            Pattern::Or(alternatives) => {
+                let alternatives: Vec<String> = alternatives.iter().map(|p| self.pattern(p)).collect();
+                format!("gm::Pattern::Or(vec![{}])", alternatives.join(", "))
+            }
+        }
+    }
+
+    // ------------------------------------------------------------ expressions
+
+    fn expr(&mut self, ast: &Ast) -> Result<String, Rekt> {
+        Ok(match ast {
+            Ast::Located { line, statement } => return self.expr(statement).map_err(|e| e.at_line(*line)),
+            Ast::Literal(value) => literal(value)?,
+            Ast::Variable(name) | Ast::ResolvedVariable { name, .. } => self.read(name),
+            Ast::Grouping(inner) => self.expr(inner)?,
+
+            Ast::Binary { left, operator, right } => {
+                format!("gm::{}({}, {})?", operator_fn(operator), self.expr(left)?, self.expr(right)?)
+            }
+            Ast::Unary { operator, operand } => format!("gm::{}({})?", operator_fn(operator), self.expr(operand)?),
+            Ast::Ternary { condition, then_branch, else_branch } => format!(
+                "if {} {{ {} }} else {{ {} }}",
+                self.condition(condition)?,
+                self.expr(then_branch)?,
+                self.expr(else_branch)?
+            ),
+            Ast::Interpolation(parts) => {
+                let mut template = String::new();
+                let mut args = Vec::new();
+                for part in parts {
+                    match part {
+                        Ast::Literal(Value::Text(s)) => template.push_str(&s.replace('{', "{{").replace('}', "}}")),
+                        other => {
+                            template.push_str("{}");
+                            args.push(format!("{}.render()", receiver(self.expr(other)?)));
+                        }
+                    }
+                }
+                match args.is_empty() {
+                    true => format!("gm::text({:?})", template.replace("{{", "{").replace("}}", "}")),
+                    false => format!("Value::Text(format!({:?}, {}))", template, args.join(", ")),
+                }
+            }
+
+            Ast::Call { callee, arguments } => {
+                let args = arguments.iter().map(|arg| self.expr(arg)).collect::<Result<Vec<_>, _>>()?;
+                // A name no wallet holds may still be a builtin
+                if let Ast::Variable(name) | Ast::ResolvedVariable { name, .. } = callee.unlocated() {
+                    if self.lookup(name).is_none() {
+                        return Ok(format!("gm::builtin(ch, {:?}, vec![{}])?", name, args.join(", ")));
+                    }
+                }
+                format!("ch.call({}, vec![{}])?", self.expr(callee)?, args.join(", "))
+            }
+            Ast::Lambda { params, body, .. } => self.contract("anonymous", params, body)?,
+
+            Ast::ArrayLiteral(items) => {
+                let items = items.iter().map(|item| self.expr(item)).collect::<Result<Vec<_>, _>>()?;
+                format!("gm::array(vec![{}])", items.join(", "))
+            }
+            Ast::MapLiteral(entries) => {
+                let mut pairs = Vec::new();
+                for (key, value) in entries {
+                    pairs.push(format!("({}, {})", self.expr(key)?, self.expr(value)?));
+                }
+                format!("gm::mapping(vec![{}])?", pairs.join(", "))
+            }
+            Ast::StructLiteral { name, fields } => {
+                let mut seen = HashSet::new();
+                let mut inits = Vec::new();
+                for (field, value) in fields {
+                    if !seen.insert(field) {
+                        return Err(Rekt::Runtime(format!("Field '{}' set twice in '{}'", field, name)));
+                    }
+                    inits.push(format!("({:?}, {})", field, self.expr(value)?));
+                }
+                format!("ch.record({:?}, vec![{}])?", name, inits.join(", "))
+            }
+            Ast::Index { object, index } => format!("gm::index({}, {})?", self.expr(object)?, self.expr(index)?),
+            Ast::Field { object, name } => format!("gm::field({}, {:?})?", self.expr(object)?, name),
+
+            Ast::Match { scrutinee, arms } => {
+                if arms.iter().any(|(_, body)| escapes(body, false)) {
+                    return Err(unsupported("A return, crash or skip inside a vibecheck used as a value"));
+                }
+                let loops = std::mem::replace(&mut self.loops, 0);
+                let returns = std::mem::replace(&mut self.returns, true);
+                let body = self.block_text(|rs| rs.match_statement(scrutinee, arms, true));
+                self.loops = loops;
+                self.returns = returns;
+                format!("(|| -> Result<Value, gm::Rekt> {})()?", body?)
+            }
+
+            Ast::Assign { .. } | Ast::SetIndex { .. } | Ast::SetField { .. } | Ast::CompoundAssign { .. } | Ast::Delete(_) => {
+                let write = self.write(ast, true)?;
+                if write.setup.is_empty() {
+                    format!("{{ {}; {} }}", write.store, write.value)
+                } else {
+                    self.block_text(|rs| {
+                        for setup in &write.setup {
+                            rs.line(setup);
+                        }
+                        rs.line(&format!("{};", write.store));
+                        rs.line(&write.value);
+                        Ok(())
+                    })?
+                }
+            }
+
+            Ast::Program(_)
+            | Ast::Block(_)
+            | Ast::VariableDecl { .. }
+            | Ast::FunctionDecl { .. }
+            | Ast::StructDecl { .. }
+            | Ast::If { .. }
+            | Ast::While { .. }
+            | Ast::ForEach { .. }
+            | Ast::ForRange { .. }
+            | Ast::Break
+            | Ast::Continue
+            | Ast::ExpressionStmt(_)
+            | Ast::PrintStmt(_)
+            | Ast::ReturnStmt(_) => return Err(unsupported("A statement used as a value")),
+        })
+    }
+
+    fn condition(&mut self, ast: &Ast) -> Result<String, Rekt> {
+        Ok(format!("gm::cond({})?", self.expr(ast)?))
+    }
+
+    // ------------------------------------------------------------ assignments
+
+    // `value` is only needed when the assigned value is used, which is
+    // when the stored value is kept back to be read.
+    fn write(&mut self, ast: &Ast, value_used: bool) -> Result<Write, Rekt> {
+        let stored = if value_used { "value.clone()" } else { "value" };
+        match ast.unlocated() {
+            Ast::Assign { name, value } => {
+                let value = self.expr(value)?;
+                Ok(self.assign(name, value))
+            }
+            Ast::CompoundAssign { target, operator, value } => {
+                let (root, steps) = self.target(target)?;
+                let operator = operator_fn(operator);
+                if steps.is_empty() {
+                    let current = self.read(&root);
+                    let operand = self.expr(value)?;
+                    return Ok(self.assign(&root, format!("gm::{}({}, {})?", operator, current, operand)));
+                }
+                let current = match self.resolve(&root) {
+                    Some(binding) if binding.cell => format!("gm::read(&{}.get({:?})?, &path)?", binding.rs, root),
+                    Some(binding) => format!("gm::read(&{}, &path)?", binding.rs),
+                    None => format!("gm::read(&gm::unminted({:?})?, &path)?", root),
+                };
+                let operand = self.expr(value)?;
+                let setup = vec![
+                    format!("let path = [{}];", steps.join(", ")),
+                    format!("let value = gm::{}({}, {})?;", operator, current, operand),
+                ];
+                Ok(self.slot_write(&root, setup, |root| format!("gm::set({}, &path, {})", root, stored), stored, false))
+            }
+            Ast::SetIndex { object, index, value } => {
+                let (root, mut steps) = self.target(object)?;
+                steps.push(format!("Step::Key({})", self.expr(index)?));
+                let setup = vec![
+                    format!("let path = [{}];", steps.join(", ")),
+                    format!("let value = {};", self.expr(value)?),
+                ];
+                Ok(self.slot_write(&root, setup, |root| format!("gm::set({}, &path, {})", root, stored), stored, false))
+            }
+            Ast::SetField { object, name, value } => {
+                let (root, steps) = self.target(object)?;
+                let mut setup = Vec::new();
+                if !steps.is_empty() {
+                    setup.push(format!("let path = [{}];", steps.join(", ")));
+                }
+                setup.push(format!("let value = {};", self.expr(value)?));
+                let path = if steps.is_empty() { "&[]" } else { "&path" };
+                let store = |root: &str| format!("ch.set_field({}, {}, {:?}, {})", root, path, name, stored);
+                Ok(self.slot_write(&root, setup, store, stored, true))
+            }
+            Ast::Delete(target) => {
+                let (root, steps) = self.target(target)?;
+                let mut setup = Vec::new();
+                if !steps.is_empty() {
+                    setup.push(format!("let path = [{}];", steps.join(", ")));
+                }
+                let path = if steps.is_empty() { "&[]" } else { "&path" };
+                let mut write = self.slot_write(&root, setup, |root| format!("gm::delete({}, {})", root, path), "Value::Null", false);
+                write.value = "Value::Null".to_string();
+                Ok(write)
+            }
+            _ => Err(Rekt::Runtime("Invalid assignment target".to_string())),
+        }
+    }
+
+    // Stores a whole new value in a token.
+    fn assign(&mut self, name: &str, value: String) -> Write {
+        let (store, read_back) = match self.resolve(name) {
+            Some(binding) if is_locked(&binding) => (format!("gm::locked({:?}, {})?", name, value), "Value::Null".to_string()),
+            Some(binding) if binding.cell => (
+                format!("{}.put({:?}, {})?", binding.rs, name, value),
+                format!("{}.get({:?})?", binding.rs, name),
+            ),
+            Some(binding) => (format!("{} = {}", binding.rs, value), format!("{}.clone()", binding.rs)),
+            None => (format!("gm::no_wallet({:?}, {})?", name, value), "Value::Null".to_string()),
+        };
+        Write { setup: Vec::new(), store, value: read_back }
+    }
+
+    // Stores into a slot under a token, through `store`, which is given the
+    // token's value to change. `minted` says the token is read first, so a
+    // missing one is reported the way a read would report it.
+    fn slot_write(
+        &mut self,
+        name: &str,
+        setup: Vec<String>,
+        store: impl Fn(&str) -> String,
+        stored: &str,
+        minted: bool,
+    ) -> Write {
+        let update = if minted { "update_minted" } else { "update" };
+        let store = match self.resolve(name) {
+            Some(binding) if is_locked(&binding) => format!("gm::locked({:?}, {})?", name, stored),
+            Some(binding) if binding.cell => format!("{}.{}({:?}, |root| {})?", binding.rs, update, name, store("root")),
+            Some(binding) => format!("{}?", store(&format!("&mut {}", binding.rs))),
+            None if minted => format!("gm::unminted({:?})?", name),
+            None => format!("gm::no_wallet({:?}, {})?", name, stored),
+        };
+        Write { setup, store, value: "value".to_string() }
+    }
+
+    fn target(&mut self, ast: &Ast) -> Result<(String, Vec<String>), Rekt> {
+        let (root, path) = target_path(ast)?;
+        let mut steps = Vec::new();
+        for step in path {
+            steps.push(match step {
+                PathStep::Key(index) => format!("Step::Key({})", self.expr(index)?),
+                PathStep::Field(name) => format!("Step::Field({:?})", name),
+            });
+        }
+        Ok((root.to_string(), steps))
+    }
+}
+
+fn is_locked(binding: &Binding) -> bool {
+    binding.kind == Some(DeclKind::Block) && !binding.hoisted
+}
+
+fn operator_fn(operator: &BinaryOp) -> &'static str {
+    match operator {
+        BinaryOp::Add => "add",
+        BinaryOp::Subtract => "sub",
+        BinaryOp::Multiply => "mul",
+        BinaryOp::Divide => "div",
+        BinaryOp::Equal => "eq",
+        BinaryOp::NotEqual => "ne",
+        BinaryOp::Less => "lt",
+        BinaryOp::Greater => "gt",
+        BinaryOp::LessEqual => "le",
+        BinaryOp::GreaterEqual => "ge",
+        BinaryOp::And => "and",
+        BinaryOp::Or => "or",
+        BinaryOp::Not => "not",
+        BinaryOp::Modulo => "rem",
+        BinaryOp::IntDivide => "chop",
+        BinaryOp::BitAnd => "bitand",
+        BinaryOp::BitOr => "bitor",
+        BinaryOp::BitXor => "bitxor",
+        BinaryOp::ShiftLeft => "shl",
+        BinaryOp::ShiftRight => "shr",
+        BinaryOp::BitNot => "bitnot",
+    }
+}
+
+// An expression that a method can be called on as it is.
+fn receiver(rs: String) -> String {
+    if rs.starts_with("if ") || rs.starts_with('{') {
+        format!("({})", rs)
+    } else {
+        rs
+    }
+}
+
+fn ident(name: &str) -> String {
+    if RESERVED.contains(&name) || name.ends_with('_') {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
+
+fn is_identifier(rs: &str) -> bool {
+    !rs.is_empty() && rs.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn type_expr(t: &Type) -> String {
+    match t {
+        Type::Struct(name) => format!("gm::Type::Struct({:?}.to_string())", name),
+        other => format!("gm::Type::{:?}", other),
+    }
+}
+
+fn literal(value: &Value) -> Result<String, Rekt> {
+    Ok(match value {
+        Value::Token(t) => number(t.parse::<f64>().unwrap_or(f64::NAN)),
+        Value::Number(n) => number(*n),
+        Value::Text(s) => format!("gm::text({:?})", s),
+        Value::Signal(b) | Value::Boolean(b) => format!("Value::Signal({})", b),
+        Value::Int(n) => format!("gm::uint(\"{}\")", n),
+        Value::Decimal(d) => format!("gm::dec(\"{}\")", d),
+        Value::Null => "Value::Null".to_string(),
+        Value::Array(items) => {
+            let items = items.iter().map(literal).collect::<Result<Vec<_>, _>>()?;
+            format!("gm::array(vec![{}])", items.join(", "))
+        }
+        Value::Map(entries) => {
+            let mut pairs = Vec::new();
+            for (key, value) in entries {
+                pairs.push(format!("({}, {})", literal(&key.to_value())?, literal(value)?));
+            }
+            format!("gm::mapping(vec![{}])?", pairs.join(", "))
+        }
+        Value::Struct { .. } | Value::Function { .. } | Value::SmartContract { .. } | Value::Closure(_) => {
+            return Err(unsupported(&format!("A {} literal", value)))
+        }
+    })
+}
+
+fn number(n: f64) -> String {
+    if n.is_nan() {
+        "Value::Number(f64::NAN)".to_string()
+    } else if n.is_infinite() {
+        if n > 0.0 { "Value::Number(f64::INFINITY)" } else { "Value::Number(f64::NEG_INFINITY)" }.to_string()
+    } else {
+        format!("Value::Number({:?})", n)
+    }
+}
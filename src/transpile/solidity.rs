@@ -0,0 +1,2008 @@
+// src/transpile/solidity.rs
+//
+// Turns a resolved tree into one Solidity contract. The script's top level
+// is the contract: its tokens and blocks become public storage variables,
+// its `mine` contracts become functions, and the rest of what it does runs
+// in the constructor. `broadcast` emits an event, one per type broadcast.
+//
+// Solidity needs a type for everything, so one is worked out for every
+// token from what it's minted with (or, for an empty mapping, from what's
+// written into it) and for every function from its params' annotations and
+// what it returns. Knowing one thing can tell us another declared further
+// down, so the contract is written again until nothing new is learned.
+//
+// Tokens become int256 and uints uint256, so only whole numbers exist.
+// What has no faithful counterpart (fractions, decimals, contracts as
+// values, farming a mapping) is refused with a Type error saying what and
+// on which line, rather than turned into something that behaves differently.
+// The one difference left is when storage exists: for the contract's whole
+// life, so a function that reads a token before the constructor mints it
+// sees its literal (or zero) where GM would fail.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use crate::error::Rekt;
+use gm_runtime::BUILTINS;
+use crate::parser::ast::{Ast, Pattern};
+use crate::shared_types::{BinaryOp, Type, Value};
+use super::{declarations, DeclKind};
+
+// Names GM allows that Solidity doesn't, which get a `_` after them.
+const RESERVED: &[&str] = &[
+    "abi", "abstract", "address", "after", "alias", "apply", "assert", "auto", "block", "bool",
+    "break", "byte", "bytes", "calldata", "case", "catch", "constant", "constructor", "continue",
+    "contract", "copyof", "days", "default", "define", "delete", "do", "else", "emit", "enum",
+    "ether", "event", "external", "fallback", "false", "final", "for", "function", "gwei",
+    "hours", "if", "immutable", "implements", "import", "in", "indexed", "inline", "int",
+    "int256", "interface", "internal", "is", "keccak256", "let", "library", "macro", "mapping",
+    "match", "memory", "minutes", "modifier", "msg", "mutable", "new", "null", "of", "override",
+    "partial", "payable", "pragma", "private", "promise", "public", "pure", "receive",
+    "reference", "relocatable", "require", "return", "returns", "revert", "sealed", "seconds",
+    "selfdestruct", "sizeof", "static", "storage", "string", "struct", "super", "supports",
+    "switch", "this", "true", "try", "tx", "type", "typedef", "typeof", "uint", "uint256",
+    "unchecked", "using", "var", "view", "virtual", "weeks", "wei", "while", "years",
+];
+
+// `source` names the file the script came from, which also names the contract.
+pub fn transpile(ast: &Ast, source: &str) -> Result<String, Rekt> {
+    let stmts = match ast {
+        Ast::Program(stmts) => stmts.as_slice(),
+        other => std::slice::from_ref(other),
+    };
+    // Each pass can use what the last one learned: the type of a mapping
+    // from writes further down, what a later contract returns. Knowledge
+    // only grows, but the number of passes is capped all the same.
+    let mut known = Known::default();
+    for _ in 0..MAX_PASSES {
+        let mut contract = Contract::new(&known);
+        let written = contract.write(stmts, source);
+        if contract.learned == known {
+            return written;
+        }
+        known = contract.learned;
+    }
+    Contract::new(&known).write(stmts, source)
+}
+
+const MAX_PASSES: usize = 16;
+
+fn unsupported(what: &str) -> Rekt {
+    Rekt::Type(format!("{} can't be transpiled to Solidity", what))
+}
+
+fn unknown(what: &str) -> Rekt {
+    Rekt::Type(format!("Can't tell what {} holds, which Solidity needs to know", what))
+}
+
+fn mismatch(what: &str, expected: &Sol, found: &Sol) -> Rekt {
+    Rekt::Type(format!("{} is {} in Solidity, but this is {}", what, expected, found))
+}
+
+// The Solidity types GM values map onto.
+#[derive(Debug, Clone, PartialEq)]
+enum Sol {
+    Int,
+    Uint,
+    Bool,
+    Text,
+    Struct(String),
+    Array(Box<Sol>),
+    Mapping(Box<Sol>, Box<Sol>),
+}
+
+impl fmt::Display for Sol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Sol::Int => write!(f, "int256"),
+            Sol::Uint => write!(f, "uint256"),
+            Sol::Bool => write!(f, "bool"),
+            Sol::Text => write!(f, "string"),
+            Sol::Struct(name) => write!(f, "{}", name),
+            Sol::Array(item) => write!(f, "{}[]", item),
+            Sol::Mapping(key, value) => write!(f, "mapping({} => {})", key, value),
+        }
+    }
+}
+
+impl Sol {
+    fn from_type(t: &Type, what: &str) -> Result<Sol, Rekt> {
+        match t {
+            Type::Number | Type::Token => Ok(Sol::Int),
+            Type::Uint => Ok(Sol::Uint),
+            Type::Boolean | Type::Signal => Ok(Sol::Bool),
+            Type::Text => Ok(Sol::Text),
+            Type::Struct(name) => Ok(Sol::Struct(name.clone())),
+            Type::Function => Err(unsupported(&format!("{}, which holds a contract,", what))),
+            other => Err(unsupported(&format!("{} of type {}", what, other))),
+        }
+    }
+
+    // How a local or param of this type is declared
+    fn local(&self) -> String {
+        match self {
+            Sol::Text | Sol::Struct(_) | Sol::Array(_) => format!("{} memory", self),
+            _ => self.to_string(),
+        }
+    }
+
+    fn is_number(&self) -> bool {
+        matches!(self, Sol::Int | Sol::Uint)
+    }
+
+    // The event a value of this type is broadcast with
+    fn event(&self) -> String {
+        match self {
+            Sol::Text => "Broadcast".to_string(),
+            Sol::Int => "BroadcastInt".to_string(),
+            Sol::Uint => "BroadcastUint".to_string(),
+            Sol::Bool => "BroadcastBool".to_string(),
+            Sol::Struct(name) => format!("Broadcast{}", name),
+            Sol::Array(item) => format!("{}Array", item.event()),
+            Sol::Mapping(..) => "BroadcastMapping".to_string(),
+        }
+    }
+}
+
+// What one pass over the contract works out, for the next one to use.
+#[derive(Default, Clone, PartialEq)]
+struct Known {
+    storage: HashMap<String, Sol>,
+    returns: HashMap<String, Option<Sol>>, // None for a function that returns nothing
+}
+
+// Private functions the contract gets when something needs one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Helper {
+    FloorDiv,
+    FloorMod,
+    ToUint,
+    ToInt,
+    Same,
+    IntText,
+    UintText,
+    BoolText,
+}
+
+impl Helper {
+    fn needs(self) -> &'static [Helper] {
+        match self {
+            Helper::FloorMod => &[Helper::FloorDiv],
+            Helper::IntText => &[Helper::UintText],
+            _ => &[],
+        }
+    }
+
+    fn source(self) -> &'static str {
+        match self {
+            Helper::FloorDiv => r#"    // `~/` rounds down, where Solidity's `/` rounds toward zero
+    function _floorDiv(int256 a, int256 b) private pure returns (int256) {
+        int256 q = a / b;
+        if (a % b != 0 && (a < 0) != (b < 0)) {
+            q -= 1;
+        }
+        return q;
+    }
+"#,
+            Helper::FloorMod => r#"    // `%` takes the divisor's sign
+    function _floorMod(int256 a, int256 b) private pure returns (int256) {
+        return a - b * _floorDiv(a, b);
+    }
+"#,
+            Helper::ToUint => r#"    function _toUint(int256 x) private pure returns (uint256) {
+        require(x >= 0, "not a whole number in uint range");
+        return uint256(x);
+    }
+"#,
+            Helper::ToInt => r#"    function _toInt(uint256 x) private pure returns (int256) {
+        require(x <= uint256(type(int256).max), "too big for a number");
+        return int256(x);
+    }
+"#,
+            Helper::Same => r#"    function _same(string memory a, string memory b) private pure returns (bool) {
+        return keccak256(bytes(a)) == keccak256(bytes(b));
+    }
+"#,
+            Helper::IntText => r#"    function _intText(int256 x) private pure returns (string memory) {
+        if (x >= 0) {
+            return _uintText(uint256(x));
+        }
+        return string.concat("-", _uintText(uint256(-(x + 1)) + 1));
+    }
+"#,
+            Helper::UintText => r#"    function _uintText(uint256 x) private pure returns (string memory) {
+        if (x == 0) {
+            return "0";
+        }
+        uint256 digits;
+        for (uint256 rest = x; rest != 0; rest /= 10) {
+            digits++;
+        }
+        bytes memory text = new bytes(digits);
+        for (; x != 0; x /= 10) {
+            text[--digits] = bytes1(uint8(48 + x % 10));
+        }
+        return string(text);
+    }
+"#,
+            Helper::BoolText => r#"    function _boolText(bool x) private pure returns (string memory) {
+        return x ? "true" : "false";
+    }
+"#,
+        }
+    }
+}
+
+// Where a statement's value goes when it's the last one run.
+enum Sink {
+    Discard,
+    Return,                      // it's what the function returns
+    Assign(String, Option<Sol>), // it's assigned to a place of that type, once known
+}
+
+// An expression written out, with its type and how tightly it binds.
+struct Typed {
+    code: String,
+    ty: Sol,
+    prec: u8,
+}
+
+const TERNARY: u8 = 1;
+const EQUALITY: u8 = 2;
+const COMPARISON: u8 = 3;
+const BIT_OR: u8 = 4;
+const BIT_XOR: u8 = 5;
+const BIT_AND: u8 = 6;
+const SHIFT: u8 = 7;
+const ADDITIVE: u8 = 8;
+const MULTIPLICATIVE: u8 = 9;
+const UNARY: u8 = 10;
+const POSTFIX: u8 = 11;
+const ATOM: u8 = 12;
+
+impl Typed {
+    fn new(code: String, ty: Sol, prec: u8) -> Self {
+        Typed { code, ty, prec }
+    }
+
+    // The code, in parentheses unless it binds at least as tightly as `min`
+    fn at(&self, min: u8) -> String {
+        if self.prec < min {
+            format!("({})", self.code)
+        } else {
+            self.code.clone()
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Binding {
+    sol: String,     // the identifier it's written as
+    ty: Option<Sol>, // for storage, looked up in `learned` instead
+    storage: bool,
+    constant: bool,  // a block, which can't be written
+    declared: bool,  // exists before GM mints it, so minting it is an assignment
+}
+
+// A place being written: its code, its type if known yet, and for a
+// storage root whose type isn't known, the key types along the way.
+struct Place {
+    code: String,
+    ty: Option<Sol>,
+    root: String,
+    storage: bool,
+    keys: Vec<Sol>,
+}
+
+struct Storage {
+    name: String,
+    sol: String,
+    constant: bool,
+    once: bool,           // minted once, straight in the constructor
+    literal: bool,        // and with a literal, so declared with it
+    init: Option<String>, // that literal
+}
+
+struct Signature {
+    params: Vec<(String, Sol)>,
+    returns: Option<Sol>, // as annotated
+}
+
+enum Returned {
+    Value(Sol, bool), // and whether it was a bare literal
+    Nothing,
+}
+
+// What a function does to the chain, which decides `pure` or `view`
+#[derive(Default)]
+struct Effects {
+    reads: bool,
+    writes: bool,
+    calls: BTreeSet<String>,
+}
+
+struct Function {
+    name: String,
+    params: String,
+    returns: Option<Sol>,
+    body: String,
+    effects: Effects,
+}
+
+struct Contract<'a> {
+    known: &'a Known,
+    learned: Known,
+    structs: Vec<(String, Vec<(String, Sol)>)>,
+    signatures: HashMap<String, Signature>,
+    storage: Vec<Storage>,
+    events: Vec<Sol>,
+    helpers: BTreeSet<Helper>,
+    functions: Vec<Function>,
+    errors: Vec<Rekt>,
+
+    // The function (or constructor) being written
+    out: String,
+    depth: usize,
+    scopes: Vec<HashMap<String, Binding>>,
+    function: Option<String>,
+    effects: Effects,
+    returned: Vec<Returned>,
+    loops: usize,
+}
+
+impl<'a> Contract<'a> {
+    fn new(known: &'a Known) -> Self {
+        Contract {
+            known,
+            learned: known.clone(),
+            structs: Vec::new(),
+            signatures: HashMap::new(),
+            storage: Vec::new(),
+            events: Vec::new(),
+            helpers: BTreeSet::new(),
+            functions: Vec::new(),
+            errors: Vec::new(),
+            out: String::new(),
+            depth: 2,
+            scopes: Vec::new(),
+            function: None,
+            effects: Effects::default(),
+            returned: Vec::new(),
+            loops: 0,
+        }
+    }
+
+    fn write(&mut self, stmts: &[Ast], source: &str) -> Result<String, Rekt> {
+        self.declare_all(stmts)?;
+        for stmt in stmts {
+            let (line, unlocated) = match stmt {
+                Ast::Located { line, statement } => (Some(*line), statement.as_ref()),
+                other => (None, other),
+            };
+            let result = match unlocated {
+                Ast::StructDecl { .. } => Ok(()),
+                Ast::FunctionDecl { name, params, body, .. } => self.function(name, params, body),
+                other => self.statement(other, &mut Sink::Discard),
+            };
+            if let Err(e) = result {
+                self.errors.push(match line {
+                    Some(line) => e.at_line(line),
+                    None => e,
+                });
+            }
+        }
+        let constructor = std::mem::take(&mut self.out);
+        if !self.errors.is_empty() {
+            return Err(self.errors.remove(0));
+        }
+        self.assemble(source, constructor)
+    }
+
+    // Structs, storage and function signatures, which are all visible
+    // from anywhere in the contract.
+    fn declare_all(&mut self, stmts: &[Ast]) -> Result<(), Rekt> {
+        for stmt in stmts {
+            if let Ast::StructDecl { name, fields, .. } = stmt.unlocated() {
+                let mut typed = Vec::new();
+                for (field, t) in fields {
+                    let what = format!("Field '{}.{}'", name, field);
+                    let t = t.as_ref().ok_or_else(|| unknown(&format!("field '{}.{}'", name, field)))?;
+                    typed.push((field.clone(), Sol::from_type(t, &what)?));
+                }
+                self.structs.push((name.clone(), typed));
+            }
+        }
+        for stmt in stmts {
+            if let Ast::FunctionDecl { name, params, return_type, .. } = stmt.unlocated() {
+                let mut typed = Vec::new();
+                for (param, t) in params {
+                    typed.push((param.clone(), Sol::from_type(t, &format!("Param '{}' of '{}'", param, name))?));
+                }
+                let returns = match return_type {
+                    Some(t) => Some(Sol::from_type(t, &format!("What '{}' returns", name))?),
+                    None => None,
+                };
+                let signature = Signature { params: typed, returns };
+                if self.signatures.insert(name.clone(), signature).is_some() {
+                    return Err(unsupported(&format!("Contract '{}', mined twice,", name)));
+                }
+            }
+        }
+
+        let mut scope = HashMap::new();
+        for decl in declarations(&[], stmts) {
+            if matches!(decl.kind, DeclKind::Contract(_)) {
+                continue;
+            }
+            if self.signatures.contains_key(&decl.name) {
+                return Err(unsupported(&format!("'{}', both a token and a contract,", decl.name)));
+            }
+            let sol = ident(&decl.name);
+            let constant = decl.kind == DeclKind::Block;
+            let literal = !decl.hoisted && stmts.iter().any(|stmt| match stmt.unlocated() {
+                Ast::VariableDecl { name, initializer, .. } => *name == decl.name && is_literal(initializer),
+                _ => false,
+            });
+            let binding = Binding { sol: sol.clone(), ty: None, storage: true, constant, declared: true };
+            scope.insert(decl.name.clone(), binding);
+            self.storage.push(Storage { name: decl.name, sol, constant, once: !decl.hoisted, literal, init: None });
+        }
+        self.scopes.push(scope);
+        Ok(())
+    }
+
+    fn assemble(&mut self, source: &str, constructor: String) -> Result<String, Rekt> {
+        let name = contract_name(source);
+        let mut sections = Vec::new();
+
+        for (struct_name, fields) in &self.structs {
+            let mut text = format!("    struct {} {{\n", struct_name);
+            for (field, ty) in fields {
+                text.push_str(&format!("        {} {};\n", ty, ident(field)));
+            }
+            text.push_str("    }\n");
+            sections.push(text);
+        }
+
+        if !self.events.is_empty() {
+            let events: String = self
+                .events
+                .iter()
+                .map(|ty| format!("    event {}({} value);\n", ty.event(), ty))
+                .collect();
+            sections.push(events);
+        }
+
+        let mut storage = String::new();
+        for slot in &self.storage {
+            let ty = self.learned.storage.get(&slot.name).ok_or_else(|| unknown(&format!("'{}'", slot.name)))?;
+            let line = match (&slot.init, slot.constant) {
+                (Some(init), true) => format!("    {} public constant {} = {};\n", ty, slot.sol, init),
+                (Some(init), false) => format!("    {} public {} = {};\n", ty, slot.sol, init),
+                (None, _) => format!("    {} public {};\n", ty, slot.sol),
+            };
+            storage.push_str(&line);
+        }
+        if !storage.is_empty() {
+            sections.push(storage);
+        }
+
+        if !constructor.is_empty() {
+            sections.push(format!("    constructor() {{\n{}    }}\n", constructor));
+        }
+
+        let levels = self.mutability();
+        for function in &self.functions {
+            let mutability = match levels[&function.name] {
+                0 => " pure",
+                1 => " view",
+                _ => "",
+            };
+            let returns = match &function.returns {
+                Some(ty) => format!(" returns ({})", ty.local()),
+                None => String::new(),
+            };
+            sections.push(format!(
+                "    function {}({}) public{}{} {{\n{}    }}\n",
+                ident(&function.name),
+                function.params,
+                mutability,
+                returns,
+                function.body
+            ));
+        }
+
+        let mut helpers: BTreeSet<Helper> = self.helpers.clone();
+        for helper in &self.helpers {
+            helpers.extend(helper.needs());
+        }
+        for helper in helpers {
+            sections.push(helper.source().to_string());
+        }
+
+        Ok(format!(
+            "// SPDX-License-Identifier: MIT\n\
+             // Generated by gm-language from {}.\n\
+             pragma solidity ^0.8.20;\n\
+             \n\
+             contract {} {{\n{}}}\n",
+            source,
+            name,
+            sections.join("\n")
+        ))
+    }
+
+    // 0 for pure, 1 for view, 2 for a function that changes the chain,
+    // counting what the functions it calls do.
+    fn mutability(&self) -> HashMap<String, u8> {
+        let mut levels: HashMap<String, u8> = self
+            .functions
+            .iter()
+            .map(|f| (f.name.clone(), if f.effects.writes { 2 } else { f.effects.reads as u8 }))
+            .collect();
+        loop {
+            let mut changed = false;
+            for function in &self.functions {
+                let called = function.effects.calls.iter().filter_map(|c| levels.get(c)).max().copied();
+                let level = levels[&function.name].max(called.unwrap_or(0));
+                if level != levels[&function.name] {
+                    levels.insert(function.name.clone(), level);
+                    changed = true;
+                }
+            }
+            if !changed {
+                return levels;
+            }
+        }
+    }
+
+    fn function(&mut self, name: &str, params: &[(String, Type)], body: &[Ast]) -> Result<(), Rekt> {
+        let signature = &self.signatures[name];
+        let typed = signature.params.clone();
+        let annotated = signature.returns.clone();
+        let header = typed
+            .iter()
+            .map(|(param, ty)| format!("{} {}", ty.local(), ident(param)))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        self.function = Some(name.to_string());
+        let errors = self.errors.len();
+        self.effects = Effects::default();
+        self.returned.clear();
+        let outer = std::mem::take(&mut self.out);
+        let bound: Vec<(String, Sol)> = typed;
+        let names: Vec<String> = params.iter().map(|(param, _)| param.clone()).collect();
+        let result = self.scoped(&names, body, |c| {
+            for (param, ty) in &bound {
+                c.bind(param, Some(ty.clone()));
+            }
+            c.statements(body, &mut Sink::Return)
+        });
+        let text = std::mem::replace(&mut self.out, outer);
+        self.function = None;
+        result?;
+
+        let returns = match annotated {
+            Some(ty) => Some(ty),
+            None => self.returns_of(name)?,
+        };
+        // Returning nothing is only certain if the whole body was written
+        if returns.is_some() || self.errors.len() == errors {
+            self.learned.returns.insert(name.to_string(), returns.clone());
+        }
+        let effects = std::mem::take(&mut self.effects);
+        self.functions.push(Function { name: name.to_string(), params: header, returns, body: text, effects });
+        Ok(())
+    }
+
+    // What a function returns, going by its `return`s and its last statement.
+    fn returns_of(&self, name: &str) -> Result<Option<Sol>, Rekt> {
+        let mut found: Option<(Sol, bool)> = None;
+        let mut bare = false;
+        for returned in &self.returned {
+            match (returned, &found) {
+                (Returned::Nothing, _) => bare = true,
+                (Returned::Value(ty, literal), None) => found = Some((ty.clone(), *literal)),
+                (Returned::Value(ty, true), Some((first, _))) if ty.is_number() && first.is_number() => {}
+                (Returned::Value(ty, false), Some((_, true))) if ty.is_number() => found = Some((ty.clone(), false)),
+                (Returned::Value(ty, _), Some((first, _))) if ty != first => {
+                    return Err(unsupported(&format!(
+                        "Contract '{}', which returns both {} and {},",
+                        name, first, ty
+                    )));
+                }
+                _ => {}
+            }
+        }
+        match found {
+            Some(_) if bare => Err(unsupported(&format!(
+                "Contract '{}', which returns a value in one place and nothing in another,",
+                name
+            ))),
+            found => Ok(found.map(|(ty, _)| ty)),
+        }
+    }
+
+    // What the function being written is known to return, if anything
+    fn expected_return(&self) -> Option<Sol> {
+        let name = self.function.as_ref()?;
+        match &self.signatures[name].returns {
+            Some(ty) => Some(ty.clone()),
+            None => self.known.returns.get(name).cloned().flatten(),
+        }
+    }
+
+    // ------------------------------------------------------------ scopes
+
+    fn line(&mut self, text: &str) {
+        self.out.push_str(&"    ".repeat(self.depth));
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn indented(&mut self, f: impl FnOnce(&mut Self) -> Result<(), Rekt>) -> Result<(), Rekt> {
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
+    // Runs `f` in a new GM scope holding `bound` and whatever `stmts`
+    // declare. Names declared more than once, or inside a `sus` or `grind`
+    // body, are declared at the top of the scope once their type is known.
+    fn scoped(&mut self, bound: &[String], stmts: &[Ast], f: impl FnOnce(&mut Self) -> Result<(), Rekt>) -> Result<(), Rekt> {
+        let mut scope = HashMap::new();
+        let mut hoisted = Vec::new();
+        for decl in declarations(bound, stmts) {
+            if bound.contains(&decl.name) {
+                continue;
+            }
+            if let DeclKind::Contract(_) = decl.kind {
+                continue; // diagnosed where it's mined
+            }
+            let sol = if decl.hoisted && self.lookup(&decl.name).is_some() {
+                format!("{}${}", ident(&decl.name), self.scopes.len())
+            } else {
+                ident(&decl.name)
+            };
+            if decl.hoisted {
+                hoisted.push(decl.name.clone());
+            }
+            let constant = decl.kind == DeclKind::Block;
+            let binding = Binding { sol, ty: None, storage: false, constant, declared: decl.hoisted };
+            scope.insert(decl.name, binding);
+        }
+        self.scopes.push(scope);
+
+        let outer = std::mem::take(&mut self.out);
+        let result = f(self);
+        let body = std::mem::replace(&mut self.out, outer);
+        let scope = self.scopes.pop().unwrap_or_default();
+        result?;
+
+        for name in hoisted {
+            let binding = &scope[&name];
+            let ty = binding.ty.clone().ok_or_else(|| unknown(&format!("'{}'", name)))?;
+            self.line(&format!("{} {};", ty.local(), binding.sol));
+        }
+        self.out.push_str(&body);
+        Ok(())
+    }
+
+    // Adds a param, loop variable or match binding to the innermost scope.
+    fn bind(&mut self, name: &str, ty: Option<Sol>) -> String {
+        let sol = ident(name);
+        if let Some(scope) = self.scopes.last_mut() {
+            let binding = Binding { sol: sol.clone(), ty, storage: false, constant: false, declared: true };
+            scope.insert(name.to_string(), binding);
+        }
+        sol
+    }
+
+    // A local whose declaration hasn't been written yet isn't visible, so
+    // a block can read an outer token before minting its own.
+    fn lookup(&self, name: &str) -> Option<&Binding> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name).filter(|b| b.declared))
+    }
+
+    fn binding(&self, name: &str) -> Result<Binding, Rekt> {
+        self.lookup(name)
+            .cloned()
+            .ok_or_else(|| unsupported(&format!("'{}', which isn't a token the contract has,", name)))
+    }
+
+    fn binding_type(&self, name: &str, binding: &Binding) -> Option<Sol> {
+        if binding.storage {
+            self.learned.storage.get(name).cloned()
+        } else {
+            binding.ty.clone()
+        }
+    }
+
+    // Records the type a token turns out to hold.
+    fn learn(&mut self, name: &str, ty: &Sol) -> Result<(), Rekt> {
+        let scope = self.scopes.iter_mut().rev().find(|scope| scope.get(name).is_some_and(|b| b.declared));
+        let Some(binding) = scope.and_then(|scope| scope.get_mut(name)) else {
+            return Ok(());
+        };
+        let held = if binding.storage { self.learned.storage.get(name) } else { binding.ty.as_ref() };
+        match held {
+            Some(held) if held != ty => Err(unsupported(&format!(
+                "'{}', which holds {} in one place and {} in another,",
+                name, held, ty
+            ))),
+            Some(_) => Ok(()),
+            None if binding.storage => {
+                self.learned.storage.insert(name.to_string(), ty.clone());
+                Ok(())
+            }
+            None => {
+                binding.ty = Some(ty.clone());
+                Ok(())
+            }
+        }
+    }
+
+    fn in_constructor(&self) -> bool {
+        self.function.is_none()
+    }
+
+    fn temp(&self, what: &str) -> String {
+        match self.loops {
+            0 => format!("${}", what),
+            n => format!("${}{}", what, n + 1),
+        }
+    }
+
+    // ------------------------------------------------------------ statements
+
+    // Only the last statement's value goes to `sink`. A statement that
+    // can't be written is recorded and skipped, so the rest of the body
+    // still teaches us types (a recursive contract's return type comes
+    // from its base case).
+    fn statements(&mut self, stmts: &[Ast], sink: &mut Sink) -> Result<(), Rekt> {
+        for (index, stmt) in stmts.iter().enumerate() {
+            let result = if index + 1 == stmts.len() {
+                self.statement(stmt, sink)
+            } else {
+                self.statement(stmt, &mut Sink::Discard)
+            };
+            if let Err(e) = result {
+                self.errors.push(e);
+            }
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self, stmt: &Ast, sink: &mut Sink) -> Result<(), Rekt> {
+        match stmt {
+            Ast::Located { line, statement } => self.statement(statement, sink).map_err(|e| e.at_line(*line)),
+
+            Ast::VariableDecl { name, initializer, .. } => self.declare(name, initializer),
+            Ast::FunctionDecl { name, .. } => Err(unsupported(&format!(
+                "Contract '{}', mined anywhere but the top level,",
+                name
+            ))),
+            Ast::StructDecl { name, .. } => Err(unsupported(&format!(
+                "Struct '{}', declared anywhere but the top level,",
+                name
+            ))),
+
+            Ast::If { condition, then_branch, else_branch } => {
+                let condition = self.condition(condition)?;
+                self.line(&format!("if ({}) {{", condition));
+                self.indented(|c| c.statements(then_branch, sink))?;
+                let mut branch = else_branch.as_deref();
+                loop {
+                    match branch {
+                        None => break,
+                        Some([only]) if matches!(only.unlocated(), Ast::If { .. }) => {
+                            let Ast::If { condition, then_branch, else_branch } = only.unlocated() else {
+                                unreachable!()
+                            };
+                            let condition = self.condition(condition)?;
+                            self.line(&format!("}} else if ({}) {{", condition));
+                            self.indented(|c| c.statements(then_branch, sink))?;
+                            branch = else_branch.as_deref();
+                        }
+                        Some(stmts) => {
+                            self.line("} else {");
+                            self.indented(|c| c.statements(stmts, sink))?;
+                            break;
+                        }
+                    }
+                }
+                self.line("}");
+                Ok(())
+            }
+            Ast::While { condition, body } => {
+                let condition = self.condition(condition)?;
+                self.line(&format!("while ({}) {{", condition));
+                self.loops += 1;
+                let result = self.indented(|c| c.statements(body, &mut Sink::Discard));
+                self.loops -= 1;
+                result?;
+                self.line("}");
+                Ok(())
+            }
+            Ast::ForRange { variable, start, end, step, body } => self.for_range(variable, start, end, step.as_deref(), body),
+            Ast::ForEach { key, variable, iterable, body } => self.for_each(key.as_deref(), variable, iterable, body),
+            Ast::Block(stmts) => {
+                self.line("{");
+                self.indented(|c| c.scoped(&[], stmts, |c| c.statements(stmts, sink)))?;
+                self.line("}");
+                Ok(())
+            }
+
+            Ast::PrintStmt(value) => {
+                let value = self.expr(value, None)?;
+                if let Sol::Mapping(..) = value.ty {
+                    return Err(unsupported("Broadcasting a mapping"));
+                }
+                if !self.events.contains(&value.ty) {
+                    self.events.push(value.ty.clone());
+                }
+                self.effects.writes = true;
+                self.line(&format!("emit {}({});", value.ty.event(), value.code));
+                Ok(())
+            }
+            Ast::ReturnStmt(None) => {
+                self.returned.push(Returned::Nothing);
+                self.line("return;");
+                Ok(())
+            }
+            Ast::ReturnStmt(Some(value)) if self.in_constructor() => {
+                let _ = value;
+                Err(unsupported("Returning a value from the top level"))
+            }
+            Ast::ReturnStmt(Some(value)) => self.deliver(value, &mut Sink::Return),
+            Ast::Break => {
+                self.line("break;");
+                Ok(())
+            }
+            Ast::Continue => {
+                self.line("continue;");
+                Ok(())
+            }
+            Ast::ExpressionStmt(value) => self.deliver(value, sink),
+            other => self.deliver(other, sink),
+        }
+    }
+
+    // Writes an expression statement whose value goes to `sink`.
+    fn deliver(&mut self, value: &Ast, sink: &mut Sink) -> Result<(), Rekt> {
+        match value.unlocated() {
+            Ast::Match { scrutinee, arms } => return self.match_statement(scrutinee, arms, sink),
+            Ast::Assign { .. } | Ast::SetIndex { .. } | Ast::SetField { .. } | Ast::CompoundAssign { .. } | Ast::Delete(_) => {
+                if let Sink::Assign(..) = sink {
+                    return Err(unsupported("An assignment as a vibecheck arm's value"));
+                }
+                return self.assignment(value.unlocated());
+            }
+            _ => {}
+        }
+        match sink {
+            Sink::Discard => {
+                if let Ast::Call { callee, arguments } = value.unlocated() {
+                    let (call, _) = self.call(callee, arguments)?;
+                    self.line(&format!("{};", call));
+                } else {
+                    // Nothing to do, but it still has to make sense
+                    self.expr(value, None)?;
+                }
+            }
+            Sink::Return => {
+                if let Ast::Call { callee, arguments } = value.unlocated() {
+                    if self.call_type(callee)?.is_none() {
+                        let (call, _) = self.call(callee, arguments)?;
+                        self.line(&format!("{};", call));
+                        return Ok(());
+                    }
+                }
+                let expected = self.expected_return();
+                let typed = self.expr(value, expected.as_ref())?;
+                if let Some(expected) = &expected {
+                    if *expected != typed.ty {
+                        let name = self.function.clone().unwrap_or_default();
+                        return Err(mismatch(&format!("What '{}' returns", name), expected, &typed.ty));
+                    }
+                }
+                self.returned.push(Returned::Value(typed.ty.clone(), is_literal(value)));
+                self.line(&format!("return {};", typed.code));
+            }
+            Sink::Assign(place, want) => {
+                let typed = self.expr(value, want.as_ref())?;
+                match want {
+                    Some(want) if *want != typed.ty => return Err(mismatch(&format!("'{}'", place), want, &typed.ty)),
+                    Some(_) => {}
+                    None => *want = Some(typed.ty.clone()),
+                }
+                let place = place.clone();
+                self.line(&format!("{} = {};", place, typed.code));
+            }
+        }
+        Ok(())
+    }
+
+    fn condition(&mut self, ast: &Ast) -> Result<String, Rekt> {
+        let typed = self.expr(ast, Some(&Sol::Bool))?;
+        if typed.ty != Sol::Bool {
+            return Err(mismatch("A condition", &Sol::Bool, &typed.ty));
+        }
+        Ok(typed.code)
+    }
+
+
+    // `token x = ...` and `block X = ...`
+    fn declare(&mut self, name: &str, initializer: &Ast) -> Result<(), Rekt> {
+        let binding = match self.scopes.last().and_then(|scope| scope.get(name)) {
+            Some(binding) => binding.clone(),
+            None => self.binding(name)?,
+        };
+        let want = self.binding_type(name, &binding);
+        let fresh = !binding.declared;
+
+        match initializer.unlocated() {
+            Ast::ArrayLiteral(items) => {
+                let first = match binding.storage {
+                    true => self.storage.iter().any(|slot| slot.name == name && slot.once),
+                    false => fresh,
+                };
+                let ty = self.fill_array(name, &binding, items, first)?;
+                self.minted(name);
+                self.learn(name, &ty)
+            }
+            Ast::MapLiteral(entries) if binding.storage => {
+                if let Some(ty) = self.fill_mapping(&binding.sol, entries, want)? {
+                    self.learn(name, &ty)?;
+                }
+                Ok(())
+            }
+            Ast::MapLiteral(_) => Err(unsupported(&format!("Mapping '{}', minted inside a contract or block,", name))),
+            Ast::Match { scrutinee, arms } => {
+                let outer = std::mem::take(&mut self.out);
+                let mut sink = Sink::Assign(binding.sol.clone(), want);
+                let result = self.match_statement(scrutinee, arms, &mut sink);
+                let arms_text = std::mem::replace(&mut self.out, outer);
+                result?;
+                let Sink::Assign(_, Some(ty)) = sink else {
+                    return Err(unknown(&format!("'{}'", name)));
+                };
+                if fresh {
+                    self.line(&format!("{} {};", ty.local(), binding.sol));
+                }
+                self.out.push_str(&arms_text);
+                self.minted(name);
+                self.learn(name, &ty)
+            }
+            _ => {
+                self.no_alias(initializer, name, &binding)?;
+                let typed = self.expr(initializer, want.as_ref())?;
+                if let Sol::Mapping(..) = typed.ty {
+                    return Err(unsupported(&format!("Copying a whole mapping into '{}'", name)));
+                }
+                self.minted(name);
+                self.learn(name, &typed.ty)?;
+                let literal = self.storage.iter_mut().find(|slot| slot.name == name && slot.literal);
+                if let (true, Some(slot)) = (binding.storage, literal) {
+                    slot.init = Some(typed.code);
+                } else if fresh {
+                    self.line(&format!("{} {} = {};", typed.ty.local(), binding.sol, typed.code));
+                } else {
+                    self.line(&format!("{} = {};", binding.sol, typed.code));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // A local's declaration has been written, so from here it's assigned
+    fn minted(&mut self, name: &str) {
+        if let Some(binding) = self.scopes.last_mut().and_then(|scope| scope.get_mut(name)) {
+            binding.declared = true;
+        }
+    }
+
+    // Solidity shares local arrays and structs between variables rather
+    // than copying them like GM does.
+    fn no_alias(&self, value: &Ast, target: &str, to: &Binding) -> Result<(), Rekt> {
+        let (Ast::Variable(source) | Ast::ResolvedVariable { name: source, .. }) = value.unlocated() else {
+            return Ok(());
+        };
+        let Some(from) = self.lookup(source) else {
+            return Ok(());
+        };
+        match &from.ty {
+            Some(ty @ (Sol::Array(_) | Sol::Struct(_))) if !from.storage && !to.storage => Err(unsupported(&format!(
+                "Copying local {} '{}' into '{}', which Solidity would share rather than copy,",
+                ty, source, target
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    // Writes an array literal into a token, which `first` says is still
+    // empty (a storage array being minted, or a local being declared).
+    fn fill_array(&mut self, name: &str, binding: &Binding, items: &[Ast], first: bool) -> Result<Sol, Rekt> {
+        let mut item_ty = match self.binding_type(name, binding) {
+            Some(Sol::Array(item)) => Some(*item),
+            _ => None,
+        };
+        let mut codes = Vec::new();
+        for item in items {
+            let typed = self.expr(item, item_ty.as_ref())?;
+            unify(&mut item_ty, &typed.ty, "An array's items")?;
+            codes.push(typed.code);
+        }
+        let item_ty = item_ty.ok_or_else(|| unknown(&format!("the empty array '{}'", name)))?;
+        if let Sol::Mapping(..) = item_ty {
+            return Err(unsupported("An array of mappings"));
+        }
+        let ty = Sol::Array(Box::new(item_ty.clone()));
+        if binding.storage {
+            self.effects.writes |= !self.in_constructor();
+            if !first {
+                self.line(&format!("delete {};", binding.sol));
+            }
+            for code in codes {
+                self.line(&format!("{}.push({});", binding.sol, code));
+            }
+        } else {
+            let array = format!("new {}[]({})", item_ty, codes.len());
+            match first {
+                true => self.line(&format!("{} {} = {};", ty.local(), binding.sol, array)),
+                false => self.line(&format!("{} = {};", binding.sol, array)),
+            }
+            for (index, code) in codes.iter().enumerate() {
+                self.line(&format!("{}[{}] = {};", binding.sol, index, code));
+            }
+        }
+        Ok(ty)
+    }
+
+    // Writes a mapping literal into storage slot by slot, and works out
+    // its type if it has any entries.
+    fn fill_mapping(&mut self, place: &str, entries: &[(Ast, Ast)], want: Option<Sol>) -> Result<Option<Sol>, Rekt> {
+        let (mut key_ty, mut value_ty) = match want {
+            Some(Sol::Mapping(key, value)) => (Some(*key), Some(*value)),
+            _ => (None, None),
+        };
+        for (key, value) in entries {
+            let key = self.expr(key, key_ty.as_ref())?;
+            if !matches!(key.ty, Sol::Int | Sol::Uint | Sol::Bool | Sol::Text) {
+                return Err(unsupported(&format!("A mapping keyed by {}", key.ty)));
+            }
+            unify(&mut key_ty, &key.ty, "A mapping's keys")?;
+            let slot = format!("{}[{}]", place, key.code);
+            match value.unlocated() {
+                Ast::MapLiteral(inner) => {
+                    if let Some(ty) = self.fill_mapping(&slot, inner, value_ty.clone())? {
+                        unify(&mut value_ty, &ty, "A mapping's values")?;
+                    }
+                }
+                Ast::ArrayLiteral(_) => return Err(unsupported("An array literal inside a mapping literal")),
+                _ => {
+                    let typed = self.expr(value, value_ty.as_ref())?;
+                    unify(&mut value_ty, &typed.ty, "A mapping's values")?;
+                    self.line(&format!("{} = {};", slot, typed.code));
+                }
+            }
+        }
+        Ok(match (key_ty, value_ty) {
+            (Some(key), Some(value)) => Some(Sol::Mapping(Box::new(key), Box::new(value))),
+            _ => None,
+        })
+    }
+
+    // ------------------------------------------------------------ assignments
+
+    fn assignment(&mut self, ast: &Ast) -> Result<(), Rekt> {
+        match ast {
+            Ast::Assign { name, value } => {
+                let binding = self.binding(name)?;
+                if binding.constant {
+                    return Err(unsupported(&format!("Changing block constant '{}'", name)));
+                }
+                if binding.storage && !self.in_constructor() {
+                    self.effects.writes = true;
+                }
+                let want = self.binding_type(name, &binding);
+                match value.unlocated() {
+                    Ast::ArrayLiteral(items) => {
+                        let ty = self.fill_array(name, &binding, items, false)?;
+                        self.learn(name, &ty)
+                    }
+                    Ast::MapLiteral(_) => Err(unsupported(&format!("Replacing the whole of mapping '{}'", name))),
+                    Ast::Match { scrutinee, arms } => {
+                        let mut sink = Sink::Assign(binding.sol.clone(), want);
+                        self.match_statement(scrutinee, arms, &mut sink)?;
+                        match sink {
+                            Sink::Assign(_, Some(ty)) => self.learn(name, &ty),
+                            _ => Ok(()),
+                        }
+                    }
+                    _ => {
+                        self.no_alias(value, name, &binding)?;
+                        let typed = self.expr(value, want.as_ref())?;
+                        if let Sol::Mapping(..) = typed.ty {
+                            return Err(unsupported(&format!("Copying a whole mapping into '{}'", name)));
+                        }
+                        self.learn(name, &typed.ty)?;
+                        self.line(&format!("{} = {};", binding.sol, typed.code));
+                        Ok(())
+                    }
+                }
+            }
+            Ast::SetIndex { object, index, value } => {
+                let place = self.index_place(object, index)?;
+                self.store(place, value)
+            }
+            Ast::SetField { object, name, value } => {
+                let place = self.field_place(object, name)?;
+                self.store(place, value)
+            }
+            Ast::CompoundAssign { target, operator, value } => {
+                let place = self.place(target)?;
+                let ty = match place.ty.clone() {
+                    Some(ty) => ty,
+                    None => {
+                        let ty = self.expr(value, None)?.ty;
+                        self.settle(&place, &ty)?;
+                        ty
+                    }
+                };
+                let line = self.compound(&place.code, &ty, operator, value)?;
+                self.line(&line);
+                Ok(())
+            }
+            Ast::Delete(target) => {
+                let place = self.place(target)?;
+                match place.ty {
+                    Some(Sol::Mapping(..)) => Err(unsupported("Deleting a whole mapping")),
+                    Some(_) => {
+                        self.line(&format!("delete {};", place.code));
+                        Ok(())
+                    }
+                    None => Err(unknown(&format!("'{}'", place.root))),
+                }
+            }
+            _ => Err(unsupported("This assignment")),
+        }
+    }
+
+    // Writes `value` into a slot or field. A write into a storage mapping
+    // whose type isn't known yet is what tells us it.
+    fn store(&mut self, place: Place, value: &Ast) -> Result<(), Rekt> {
+        if matches!(value.unlocated(), Ast::ArrayLiteral(_) | Ast::MapLiteral(_)) {
+            return Err(unsupported("A collection literal written into a slot or field"));
+        }
+        let typed = self.expr(value, place.ty.as_ref())?;
+        match &place.ty {
+            Some(ty) if *ty != typed.ty => return Err(mismatch(&format!("A slot of '{}'", place.root), ty, &typed.ty)),
+            Some(_) => {}
+            None => self.settle(&place, &typed.ty)?,
+        }
+        self.line(&format!("{} = {};", place.code, typed.code));
+        Ok(())
+    }
+
+    // A storage mapping with no type yet holds whatever this slot is
+    // written with.
+    fn settle(&mut self, place: &Place, ty: &Sol) -> Result<(), Rekt> {
+        if !place.storage || place.keys.is_empty() {
+            return Err(unknown(&format!("'{}'", place.root)));
+        }
+        let ty = place
+            .keys
+            .iter()
+            .rev()
+            .fold(ty.clone(), |value, key| Sol::Mapping(Box::new(key.clone()), Box::new(value)));
+        self.learn(&place.root, &ty)
+    }
+
+    // `x op= v`, with GM's rounding for whole-number division
+    fn compound(&mut self, place: &str, ty: &Sol, operator: &BinaryOp, value: &Ast) -> Result<String, Rekt> {
+        if !ty.is_number() {
+            return Err(unsupported(&format!("'{}=' on {}", operator.symbol(), ty)));
+        }
+        let shift = matches!(operator, BinaryOp::ShiftLeft | BinaryOp::ShiftRight);
+        let typed = self.expr(value, Some(ty))?;
+        let amount = match (shift, &typed.ty) {
+            (true, Sol::Int) if !is_number_literal(value) => format!("uint256({})", typed.code),
+            (true, found) if found.is_number() => typed.code,
+            (_, found) if found == ty => typed.code,
+            (_, found) => return Err(mismatch(&format!("'{}'", place), ty, found)),
+        };
+        let symbol = match (operator, ty) {
+            (BinaryOp::Add, _) => "+",
+            (BinaryOp::Subtract, _) => "-",
+            (BinaryOp::Multiply, _) => "*",
+            (BinaryOp::Divide | BinaryOp::IntDivide, Sol::Uint) => "/",
+            (BinaryOp::Modulo, Sol::Uint) => "%",
+            (BinaryOp::Divide, _) => return Err(fractions()),
+            (BinaryOp::IntDivide, _) => {
+                self.helpers.insert(Helper::FloorDiv);
+                return Ok(format!("{} = _floorDiv({}, {});", place, place, amount));
+            }
+            (BinaryOp::Modulo, _) => {
+                self.helpers.insert(Helper::FloorMod);
+                return Ok(format!("{} = _floorMod({}, {});", place, place, amount));
+            }
+            (BinaryOp::BitAnd, _) => "&",
+            (BinaryOp::BitOr, _) => "|",
+            (BinaryOp::BitXor, _) => "^",
+            (BinaryOp::ShiftLeft, _) => "<<",
+            (BinaryOp::ShiftRight, _) => ">>",
+            (other, _) => return Err(unsupported(&format!("'{}='", other.symbol()))),
+        };
+        Ok(format!("{} {}= {};", place, symbol, amount))
+    }
+
+    // Where an assignment target lives.
+    fn place(&mut self, ast: &Ast) -> Result<Place, Rekt> {
+        match ast.unlocated() {
+            Ast::Variable(name) | Ast::ResolvedVariable { name, .. } => {
+                let binding = self.binding(name)?;
+                if binding.constant {
+                    return Err(unsupported(&format!("Changing block constant '{}'", name)));
+                }
+                if binding.storage && !self.in_constructor() {
+                    self.effects.writes = true;
+                }
+                let ty = self.binding_type(name, &binding);
+                Ok(Place { code: binding.sol, ty, root: name.clone(), storage: binding.storage, keys: Vec::new() })
+            }
+            Ast::Grouping(inner) => self.place(inner),
+            Ast::Index { object, index } => self.index_place(object, index),
+            Ast::Field { object, name } => self.field_place(object, name),
+            _ => Err(unsupported("Assigning to something other than a token, slot or field")),
+        }
+    }
+
+    fn index_place(&mut self, object: &Ast, index: &Ast) -> Result<Place, Rekt> {
+        let mut place = self.place(object)?;
+        match place.ty.clone() {
+            Some(Sol::Array(item)) => {
+                place.code = format!("{}[{}]", place.code, self.index_code(index)?);
+                place.ty = Some(*item);
+            }
+            Some(Sol::Mapping(key, value)) => {
+                let key = self.coerce(index, &key, &format!("A key of '{}'", place.root))?;
+                place.code = format!("{}[{}]", place.code, key);
+                place.ty = Some(*value);
+            }
+            Some(other) => return Err(unsupported(&format!("Indexing {}", other))),
+            None if place.storage => {
+                let key = self.expr(index, None)?;
+                place.code = format!("{}[{}]", place.code, key.code);
+                place.keys.push(key.ty);
+            }
+            None => return Err(unknown(&format!("'{}'", place.root))),
+        }
+        Ok(place)
+    }
+
+    fn field_place(&mut self, object: &Ast, name: &str) -> Result<Place, Rekt> {
+        let mut place = self.place(object)?;
+        match place.ty.clone() {
+            Some(Sol::Struct(record)) => {
+                place.ty = Some(self.field_type(&record, name)?);
+                place.code = format!("{}.{}", place.code, ident(name));
+                Ok(place)
+            }
+            Some(other) => Err(unsupported(&format!("Writing field '{}' of {}", name, other))),
+            None => Err(unknown(&format!("'{}'", place.root))),
+        }
+    }
+
+    fn field_type(&self, record: &str, field: &str) -> Result<Sol, Rekt> {
+        let (_, fields) = self
+            .structs
+            .iter()
+            .find(|(name, _)| name == record)
+            .ok_or_else(|| Rekt::Type(format!("Unknown struct '{}'", record)))?;
+        fields
+            .iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, ty)| ty.clone())
+            .ok_or_else(|| Rekt::Type(format!("'{}' has no field '{}'", record, field)))
+    }
+
+    // An array index, which Solidity wants as a uint256
+    fn index_code(&mut self, index: &Ast) -> Result<String, Rekt> {
+        let typed = self.expr(index, Some(&Sol::Uint))?;
+        match typed.ty {
+            Sol::Uint => Ok(typed.code),
+            Sol::Int => Ok(format!("uint256({})", typed.code)),
+            other => Err(mismatch("An array index", &Sol::Uint, &other)),
+        }
+    }
+
+    fn coerce(&mut self, ast: &Ast, want: &Sol, what: &str) -> Result<String, Rekt> {
+        let typed = self.expr(ast, Some(want))?;
+        if typed.ty != *want {
+            return Err(mismatch(what, want, &typed.ty));
+        }
+        Ok(typed.code)
+    }
+
+    // ------------------------------------------------------------ loops
+
+    fn for_range(&mut self, variable: &str, start: &Ast, end: &Ast, step: Option<&Ast>, body: &[Ast]) -> Result<(), Rekt> {
+        let step = match step {
+            None => 1.0,
+            Some(step) => literal_number(step)
+                .ok_or_else(|| unsupported("A farm step that isn't a number literal"))?,
+        };
+        if step == 0.0 {
+            return Err(Rekt::Runtime("Range step can't be zero".to_string()));
+        }
+        if step.fract() != 0.0 {
+            return Err(unsupported(&format!("Fractional farm step {}", step)));
+        }
+        let start = self.coerce(start, &Sol::Int, "A farm range's start")?;
+        let end_code = self.coerce(end, &Sol::Int, "A farm range's end")?;
+
+        let mut written = std::collections::HashSet::new();
+        for stmt in body {
+            super::assigned_names(stmt, &mut written);
+        }
+        // The end is worked out once, and the loop variable is a fresh
+        // copy each time round
+        let stable = match end.unlocated() {
+            Ast::Variable(name) | Ast::ResolvedVariable { name, .. } => {
+                !written.contains(name) && self.lookup(name).is_some_and(|b| !b.storage)
+            }
+            other => is_literal(other),
+        };
+        let counter = if written.contains(variable) { self.temp("i") } else { ident(variable) };
+        let end_code = if stable {
+            end_code
+        } else {
+            let temp = self.temp("end");
+            self.line("{");
+            self.depth += 1;
+            self.line(&format!("int256 {} = {};", temp, end_code));
+            temp
+        };
+        let (compare, advance) = match step {
+            1.0 => ("<", "++".to_string()),
+            s if s > 0.0 => ("<", format!(" += {}", s)),
+            s => (">", format!(" -= {}", -s)),
+        };
+        self.line(&format!(
+            "for (int256 {c} = {}; {c} {} {}; {c}{}) {{",
+            start,
+            compare,
+            end_code,
+            advance,
+            c = counter
+        ));
+        self.loops += 1;
+        let bound = [variable.to_string()];
+        let result = self.indented(|c| {
+            c.scoped(&bound, body, |c| {
+                let sol = c.bind(variable, Some(Sol::Int));
+                if sol != counter {
+                    c.line(&format!("int256 {} = {};", sol, counter));
+                }
+                c.statements(body, &mut Sink::Discard)
+            })
+        });
+        self.loops -= 1;
+        result?;
+        self.line("}");
+        if !stable {
+            self.depth -= 1;
+            self.line("}");
+        }
+        Ok(())
+    }
+
+    fn for_each(&mut self, key: Option<&str>, variable: &str, iterable: &Ast, body: &[Ast]) -> Result<(), Rekt> {
+        let items = self.expr(iterable, None)?;
+        let item_ty = match &items.ty {
+            Sol::Array(item) => (**item).clone(),
+            Sol::Mapping(..) => return Err(unsupported("Farming a mapping, whose keys Solidity can't list,")),
+            other => return Err(unsupported(&format!("Farming {}", other))),
+        };
+        let mut written = std::collections::HashSet::new();
+        for stmt in body {
+            super::assigned_names(stmt, &mut written);
+        }
+        // GM farms a copy, so changes inside the loop don't move it
+        let snapshot = match iterable.unlocated() {
+            Ast::Variable(name) | Ast::ResolvedVariable { name, .. } => written.contains(name),
+            _ => true,
+        };
+        let counter = self.temp("i");
+        let list = if snapshot {
+            let temp = self.temp("items");
+            self.line("{");
+            self.depth += 1;
+            self.line(&format!("{} {} = {};", items.ty.local(), temp, items.code));
+            temp
+        } else {
+            items.at(POSTFIX)
+        };
+        self.line(&format!("for (uint256 {c} = 0; {c} < {}.length; {c}++) {{", list, c = counter));
+        self.loops += 1;
+        let bound: Vec<String> = key.iter().map(|k| k.to_string()).chain([variable.to_string()]).collect();
+        let result = self.indented(|c| {
+            c.scoped(&bound, body, |c| {
+                if let Some(key) = key {
+                    let sol = c.bind(key, Some(Sol::Int));
+                    c.line(&format!("int256 {} = int256({});", sol, counter));
+                }
+                let sol = c.bind(variable, Some(item_ty.clone()));
+                c.line(&format!("{} {} = {}[{}];", item_ty.local(), sol, list, counter));
+                c.statements(body, &mut Sink::Discard)
+            })
+        });
+        self.loops -= 1;
+        result?;
+        self.line("}");
+        if snapshot {
+            self.depth -= 1;
+            self.line("}");
+        }
+        Ok(())
+    }
+
+    // ------------------------------------------------------------ vibecheck
+
+    // An if/else chain over the arms. Without a catch-all arm, running off
+    // the end reverts the way GM fails.
+    fn match_statement(&mut self, scrutinee: &Ast, arms: &[(Pattern, Ast)], sink: &mut Sink) -> Result<(), Rekt> {
+        let subject = self.expr(scrutinee, None)?;
+        let plain = matches!(scrutinee.unlocated(), Ast::Variable(_) | Ast::ResolvedVariable { .. });
+        let code = if plain { subject.code.clone() } else { self.temp("subject") };
+        if !plain {
+            self.line("{");
+            self.depth += 1;
+            self.line(&format!("{} {} = {};", subject.ty.local(), code, subject.code));
+        }
+
+        let mut exhaustive = false;
+        for (index, (pattern, body)) in arms.iter().enumerate() {
+            let mut binds = Vec::new();
+            let test = self.pattern_test(pattern, &code, &subject.ty, &mut binds)?;
+            let opening = match (&test, index) {
+                (None, 0) => "{".to_string(),
+                (None, _) => "} else {".to_string(),
+                (Some(test), 0) => format!("if ({}) {{", test),
+                (Some(test), _) => format!("}} else if ({}) {{", test),
+            };
+            self.line(&opening);
+            let names: Vec<String> = binds.iter().map(|(name, _, _)| name.clone()).collect();
+            let stmts: &[Ast] = match body.unlocated() {
+                Ast::Block(stmts) => stmts,
+                _ => &[],
+            };
+            self.indented(|c| {
+                c.scoped(&names, stmts, |c| {
+                    for (name, code, ty) in &binds {
+                        let sol = c.bind(name, Some(ty.clone()));
+                        c.line(&format!("{} {} = {};", ty.local(), sol, code));
+                    }
+                    match body.unlocated() {
+                        Ast::Block(stmts) => c.statements(stmts, sink),
+                        other => c.deliver(other, sink),
+                    }
+                })
+            })?;
+            if test.is_none() {
+                exhaustive = true;
+                break;
+            }
+        }
+        if !exhaustive {
+            self.line("} else {");
+            self.indented(|c| {
+                c.line("revert(\"No vibecheck arm matched\");");
+                Ok(())
+            })?;
+        }
+        self.line("}");
+        if !plain {
+            self.depth -= 1;
+            self.line("}");
+        }
+        Ok(())
+    }
+
+    // The condition under which `pattern` matches the value in `code`, or
+    // None when it always does. What it binds is added to `binds`.
+    fn pattern_test(&mut self, pattern: &Pattern, code: &str, ty: &Sol, binds: &mut Vec<(String, String, Sol)>) -> Result<Option<String>, Rekt> {
+        Ok(match pattern {
+            Pattern::Wildcard => None,
+            Pattern::Binding(name) => {
+                binds.push((name.clone(), code.to_string(), ty.clone()));
+                None
+            }
+            Pattern::Literal(value) => {
+                let literal = self.literal(value, Some(ty))?;
+                if literal.ty != *ty {
+                    return Err(never_matches(&value.render(), ty));
+                }
+                Some(if *ty == Sol::Text {
+                    self.helpers.insert(Helper::Same);
+                    format!("_same({}, {})", code, literal.code)
+                } else {
+                    format!("{} == {}", code, literal.code)
+                })
+            }
+            Pattern::Range(start, end) => {
+                let (start, end) = (self.literal(start, Some(ty))?, self.literal(end, Some(ty))?);
+                if !ty.is_number() || start.ty != *ty || end.ty != *ty {
+                    return Err(never_matches(&format!("{}..{}", start.code, end.code), ty));
+                }
+                Some(format!("{} >= {} && {} < {}", code, start.code, code, end.code))
+            }
+            Pattern::Or(alternatives) => {
+                let mut tests = Vec::new();
+                for alternative in alternatives {
+                    let mut inner = Vec::new();
+                    let test = self.pattern_test(alternative, code, ty, &mut inner)?;
+                    if !inner.is_empty() {
+                        return Err(unsupported("Binding a name inside an '|' pattern"));
+                    }
+                    match test {
+                        None => return Ok(None),
+                        Some(test) if test.contains("&&") => tests.push(format!("({})", test)),
+                        Some(test) => tests.push(test),
+                    }
+                }
+                Some(tests.join(" || "))
+            }
+            Pattern::Struct { name, fields } => {
+                if *ty != Sol::Struct(name.clone()) {
+                    return Err(never_matches(name, ty));
+                }
+                let mut tests = Vec::new();
+                for (field, sub) in fields {
+                    let field_ty = self.field_type(name, field)?;
+                    let field_code = format!("{}.{}", code, ident(field));
+                    match self.pattern_test(sub, &field_code, &field_ty, binds)? {
+                        Some(test) if test.contains("||") => tests.push(format!("({})", test)),
+                        Some(test) => tests.push(test),
+                        None => {}
+                    }
+                }
+                if tests.is_empty() {
+                    None
+                } else {
+                    Some(tests.join(" && "))
+                }
+            }
+            Pattern::Array { .. } => return Err(unsupported("An array pattern")),
+        })
+    }
+
+    // ------------------------------------------------------------ expressions
+
+    // `want` is the type the value is headed for, which only decides what
+    // a bare number literal becomes.
+    fn expr(&mut self, ast: &Ast, want: Option<&Sol>) -> Result<Typed, Rekt> {
+        match ast {
+            Ast::Located { line, statement } => self.expr(statement, want).map_err(|e| e.at_line(*line)),
+            Ast::Literal(value) => self.literal(value, want),
+            Ast::Grouping(inner) => self.expr(inner, want),
+            Ast::Variable(name) | Ast::ResolvedVariable { name, .. } => {
+                let binding = self.binding(name)?;
+                let ty = self.binding_type(name, &binding).ok_or_else(|| unknown(&format!("'{}'", name)))?;
+                let folded = self.storage.iter().any(|slot| slot.name == *name && slot.constant && slot.literal);
+                if binding.storage && !folded && !self.in_constructor() {
+                    self.effects.reads = true;
+                }
+                Ok(Typed::new(binding.sol, ty, ATOM))
+            }
+
+            Ast::Binary { left, operator, right } => self.binary(left, operator, right, want),
+            Ast::Unary { operator, operand } => {
+                let typed = self.expr(operand, want)?;
+                match (operator, &typed.ty) {
+                    (BinaryOp::Not, Sol::Bool) => Ok(Typed::new(format!("!{}", typed.at(UNARY)), Sol::Bool, UNARY)),
+                    (BinaryOp::BitNot, ty) if ty.is_number() => {
+                        Ok(Typed::new(format!("~{}", typed.at(UNARY)), typed.ty.clone(), UNARY))
+                    }
+                    (BinaryOp::Subtract, Sol::Int) => Ok(Typed::new(format!("-{}", typed.at(UNARY)), Sol::Int, UNARY)),
+                    (BinaryOp::Subtract, Sol::Uint) if is_number_literal(operand) => {
+                        let typed = self.expr(operand, None)?;
+                        Ok(Typed::new(format!("-{}", typed.at(UNARY)), Sol::Int, UNARY))
+                    }
+                    (operator, ty) => Err(unsupported(&format!("'{}' on {}", operator.symbol(), ty))),
+                }
+            }
+            Ast::Ternary { condition, then_branch, else_branch } => {
+                let condition = self.expr(condition, Some(&Sol::Bool))?;
+                if condition.ty != Sol::Bool {
+                    return Err(mismatch("A condition", &Sol::Bool, &condition.ty));
+                }
+                let (then_branch, else_branch) = self.operands(then_branch, else_branch, want)?;
+                if then_branch.ty != else_branch.ty {
+                    return Err(mismatch("The other side of '?'", &then_branch.ty, &else_branch.ty));
+                }
+                Ok(Typed::new(
+                    format!(
+                        "{} ? {} : {}",
+                        condition.at(TERNARY + 1),
+                        then_branch.at(TERNARY + 1),
+                        else_branch.at(TERNARY)
+                    ),
+                    then_branch.ty,
+                    TERNARY,
+                ))
+            }
+            Ast::Interpolation(parts) => {
+                let mut pieces = Vec::new();
+                for part in parts {
+                    pieces.push(match part {
+                        Ast::Literal(Value::Text(text)) => sol_string(text),
+                        other => {
+                            let typed = self.expr(other, None)?;
+                            self.text_of(typed)?
+                        }
+                    });
+                }
+                Ok(match pieces.len() {
+                    1 if matches!(parts[0], Ast::Literal(Value::Text(_))) => Typed::new(pieces.remove(0), Sol::Text, ATOM),
+                    _ => Typed::new(format!("string.concat({})", pieces.join(", ")), Sol::Text, POSTFIX),
+                })
+            }
+
+            Ast::Call { callee, arguments } => {
+                let (code, ty) = self.call(callee, arguments)?;
+                let ty = ty.ok_or_else(|| unsupported(&format!("Using the nothing '{}' returns", code)))?;
+                Ok(Typed::new(code, ty, POSTFIX))
+            }
+            Ast::Index { object, index } => {
+                let object = self.expr(object, None)?;
+                match object.ty.clone() {
+                    Sol::Array(item) => {
+                        let index = self.index_code(index)?;
+                        Ok(Typed::new(format!("{}[{}]", object.at(POSTFIX), index), *item, POSTFIX))
+                    }
+                    Sol::Mapping(key, value) => {
+                        let key = self.coerce(index, &key, "A mapping key")?;
+                        Ok(Typed::new(format!("{}[{}]", object.at(POSTFIX), key), *value, POSTFIX))
+                    }
+                    other => Err(unsupported(&format!("Indexing {}", other))),
+                }
+            }
+            Ast::Field { object, name } => {
+                let object = self.expr(object, None)?;
+                let Sol::Struct(record) = &object.ty else {
+                    return Err(Rekt::Type(format!("Cannot read field '{}' of {}", name, object.ty)));
+                };
+                let ty = self.field_type(record, name)?;
+                Ok(Typed::new(format!("{}.{}", object.at(POSTFIX), ident(name)), ty, POSTFIX))
+            }
+            Ast::StructLiteral { name, fields } => {
+                let mut inits = Vec::new();
+                let mut seen = Vec::new();
+                for (field, value) in fields {
+                    if seen.contains(&field) {
+                        return Err(Rekt::Runtime(format!("Field '{}' set twice in '{}'", field, name)));
+                    }
+                    seen.push(field);
+                    let ty = self.field_type(name, field)?;
+                    let code = self.coerce(value, &ty, &format!("Field '{}.{}'", name, field))?;
+                    inits.push(format!("{}: {}", ident(field), code));
+                }
+                Ok(Typed::new(format!("{}({{{}}})", name, inits.join(", ")), Sol::Struct(name.clone()), POSTFIX))
+            }
+
+            Ast::Lambda { .. } => Err(unsupported("A contract used as a value")),
+            Ast::ArrayLiteral(_) => Err(unsupported("An array literal anywhere but a token's initializer or assignment")),
+            Ast::MapLiteral(_) => Err(unsupported("A mapping literal anywhere but a top-level token's initializer")),
+            Ast::Match { .. } => Err(unsupported(
+                "A vibecheck used as a value anywhere but a return, a token's initializer or an assignment",
+            )),
+            Ast::Assign { .. } | Ast::SetIndex { .. } | Ast::SetField { .. } | Ast::CompoundAssign { .. } | Ast::Delete(_) => {
+                Err(unsupported("An assignment used as a value"))
+            }
+            Ast::Program(_)
+            | Ast::Block(_)
+            | Ast::VariableDecl { .. }
+            | Ast::FunctionDecl { .. }
+            | Ast::StructDecl { .. }
+            | Ast::If { .. }
+            | Ast::While { .. }
+            | Ast::ForEach { .. }
+            | Ast::ForRange { .. }
+            | Ast::Break
+            | Ast::Continue
+            | Ast::ExpressionStmt(_)
+            | Ast::PrintStmt(_)
+            | Ast::ReturnStmt(_) => Err(unsupported("A statement used as a value")),
+        }
+    }
+
+    fn literal(&mut self, value: &Value, want: Option<&Sol>) -> Result<Typed, Rekt> {
+        Ok(match value {
+            Value::Token(token) => {
+                let n = token.parse::<f64>().unwrap_or(f64::NAN);
+                if !n.is_finite() || n.fract() != 0.0 {
+                    return Err(unsupported(&format!("Number {}, which isn't whole,", token)));
+                }
+                let ty = if want == Some(&Sol::Uint) && n >= 0.0 { Sol::Uint } else { Sol::Int };
+                Typed::new(format!("{}", n), ty, if n < 0.0 { UNARY } else { ATOM })
+            }
+            Value::Number(n) => return self.literal(&Value::Token(n.to_string()), want),
+            Value::Int(n) => Typed::new(n.to_string(), Sol::Uint, ATOM),
+            Value::Text(text) => Typed::new(sol_string(text), Sol::Text, ATOM),
+            Value::Boolean(b) | Value::Signal(b) => Typed::new(b.to_string(), Sol::Bool, ATOM),
+            Value::Decimal(d) => return Err(unsupported(&format!("Decimal {}", d))),
+            Value::Null => return Err(unsupported("null")),
+            other => return Err(unsupported(&format!("Literal {}", other.render()))),
+        })
+    }
+
+    // Both sides of an operator, with a bare number literal on one side
+    // taking the other side's type.
+    fn operands(&mut self, left: &Ast, right: &Ast, want: Option<&Sol>) -> Result<(Typed, Typed), Rekt> {
+        let mut l = self.expr(left, want)?;
+        let mut r = self.expr(right, want)?;
+        if l.ty != r.ty {
+            if is_number_literal(left) && r.ty.is_number() {
+                l = self.expr(left, Some(&r.ty))?;
+            } else if is_number_literal(right) && l.ty.is_number() {
+                r = self.expr(right, Some(&l.ty))?;
+            }
+        }
+        Ok((l, r))
+    }
+
+    fn binary(&mut self, left: &Ast, operator: &BinaryOp, right: &Ast, want: Option<&Sol>) -> Result<Typed, Rekt> {
+        let comparison = matches!(
+            operator,
+            BinaryOp::Equal | BinaryOp::NotEqual | BinaryOp::Less | BinaryOp::Greater | BinaryOp::LessEqual | BinaryOp::GreaterEqual
+        );
+        let shift = matches!(operator, BinaryOp::ShiftLeft | BinaryOp::ShiftRight);
+        if matches!(operator, BinaryOp::And | BinaryOp::Or) {
+            return Err(unsupported(&format!("'{}', which GM doesn't run yet either,", operator.symbol())));
+        }
+        let want = if comparison { None } else { want };
+        let (l, r) = if shift {
+            let l = self.expr(left, want)?;
+            let r = self.expr(right, Some(&Sol::Uint))?;
+            (l, r)
+        } else {
+            self.operands(left, right, want)?
+        };
+        let symbol = operator.symbol();
+        if !shift && l.ty != r.ty {
+            if l.ty.is_number() && r.ty.is_number() {
+                return Err(unsupported(&format!(
+                    "Mixing a number and a uint in '{}' without uint() or float()",
+                    symbol
+                )));
+            }
+            return Err(unsupported(&format!("'{}' on {} and {}", symbol, l.ty, r.ty)));
+        }
+        let ty = l.ty.clone();
+        let infix = |prec: u8, ty: Sol| Typed::new(format!("{} {} {}", l.at(prec), symbol, r.at(prec + 1)), ty, prec);
+        match operator {
+            BinaryOp::Equal | BinaryOp::NotEqual if ty == Sol::Text => {
+                self.helpers.insert(Helper::Same);
+                let same = format!("_same({}, {})", l.code, r.code);
+                Ok(match operator {
+                    BinaryOp::Equal => Typed::new(same, Sol::Bool, POSTFIX),
+                    _ => Typed::new(format!("!{}", same), Sol::Bool, UNARY),
+                })
+            }
+            BinaryOp::Equal | BinaryOp::NotEqual if ty.is_number() || ty == Sol::Bool => Ok(infix(EQUALITY, Sol::Bool)),
+            _ if comparison && ty.is_number() && !matches!(operator, BinaryOp::Equal | BinaryOp::NotEqual) => {
+                Ok(infix(COMPARISON, Sol::Bool))
+            }
+            _ if !ty.is_number() => Err(unsupported(&format!("'{}' on {}", symbol, ty))),
+            BinaryOp::Add | BinaryOp::Subtract => Ok(infix(ADDITIVE, ty)),
+            BinaryOp::Multiply => Ok(infix(MULTIPLICATIVE, ty)),
+            BinaryOp::Divide | BinaryOp::IntDivide | BinaryOp::Modulo if ty == Sol::Uint => {
+                let symbol = if *operator == BinaryOp::Modulo { "%" } else { "/" };
+                Ok(Typed::new(format!("{} {} {}", l.at(MULTIPLICATIVE), symbol, r.at(MULTIPLICATIVE + 1)), ty, MULTIPLICATIVE))
+            }
+            BinaryOp::Divide => Err(fractions()),
+            BinaryOp::IntDivide => {
+                self.helpers.insert(Helper::FloorDiv);
+                Ok(Typed::new(format!("_floorDiv({}, {})", l.code, r.code), ty, POSTFIX))
+            }
+            BinaryOp::Modulo => {
+                self.helpers.insert(Helper::FloorMod);
+                Ok(Typed::new(format!("_floorMod({}, {})", l.code, r.code), ty, POSTFIX))
+            }
+            BinaryOp::BitAnd => Ok(infix(BIT_AND, ty)),
+            BinaryOp::BitOr => Ok(infix(BIT_OR, ty)),
+            BinaryOp::BitXor => Ok(infix(BIT_XOR, ty)),
+            BinaryOp::ShiftLeft | BinaryOp::ShiftRight => {
+                let amount = match r.ty {
+                    Sol::Uint => r.at(SHIFT + 1),
+                    Sol::Int => format!("uint256({})", r.code),
+                    other => return Err(unsupported(&format!("Shifting by {}", other))),
+                };
+                Ok(Typed::new(format!("{} {} {}", l.at(SHIFT), symbol, amount), ty, SHIFT))
+            }
+            _ => Err(unsupported(&format!("'{}' on {}", symbol, ty))),
+        }
+    }
+
+    fn text_of(&mut self, typed: Typed) -> Result<String, Rekt> {
+        let helper = match typed.ty {
+            Sol::Text => return Ok(typed.code),
+            Sol::Int => Helper::IntText,
+            Sol::Uint => Helper::UintText,
+            Sol::Bool => Helper::BoolText,
+            other => return Err(unsupported(&format!("Putting {} into text", other))),
+        };
+        self.helpers.insert(helper);
+        let name = match helper {
+            Helper::IntText => "_intText",
+            Helper::UintText => "_uintText",
+            _ => "_boolText",
+        };
+        Ok(format!("{}({})", name, typed.code))
+    }
+
+    // A call, and the type of what it returns (None for nothing)
+    fn call(&mut self, callee: &Ast, arguments: &[Ast]) -> Result<(String, Option<Sol>), Rekt> {
+        let (Ast::Variable(name) | Ast::ResolvedVariable { name, .. }) = callee.unlocated() else {
+            return Err(unsupported("Calling a contract held in a slot or returned by a call"));
+        };
+        if self.lookup(name).is_some() {
+            return Err(unsupported(&format!("Calling '{}', a contract held in a token,", name)));
+        }
+        let Some(signature) = self.signatures.get(name) else {
+            return self.builtin(name, arguments);
+        };
+        let params = signature.params.clone();
+        if params.len() != arguments.len() {
+            return Err(Rekt::Type(format!(
+                "'{}' takes {} args, but gets {}",
+                name,
+                params.len(),
+                arguments.len()
+            )));
+        }
+        let mut args = Vec::new();
+        for ((param, ty), arg) in params.iter().zip(arguments) {
+            args.push(self.coerce(arg, ty, &format!("Param '{}' of '{}'", param, name))?);
+        }
+        self.effects.calls.insert(name.clone());
+        let returns = self.call_type(callee)?;
+        Ok((format!("{}({})", ident(name), args.join(", ")), returns))
+    }
+
+    // What a contract the script mines returns: None for nothing
+    fn call_type(&self, callee: &Ast) -> Result<Option<Sol>, Rekt> {
+        let (Ast::Variable(name) | Ast::ResolvedVariable { name, .. }) = callee.unlocated() else {
+            return Ok(None);
+        };
+        if self.lookup(name).is_some() {
+            return Ok(None);
+        }
+        match self.signatures.get(name) {
+            None => Ok(Some(Sol::Int)), // a builtin, which always gives something back
+            Some(Signature { returns: Some(ty), .. }) => Ok(Some(ty.clone())),
+            Some(_) => self.known.returns.get(name).cloned().ok_or_else(|| {
+                Rekt::Type(format!("Can't tell what '{}' returns, which Solidity needs to know", name))
+            }),
+        }
+    }
+
+    fn builtin(&mut self, name: &str, arguments: &[Ast]) -> Result<(String, Option<Sol>), Rekt> {
+        if !BUILTINS.contains(&name) {
+            return Err(Rekt::Type(format!("Unknown smart contract '{}'", name)));
+        }
+        let [argument] = arguments else {
+            return Err(unsupported(&format!("Builtin '{}' with {} args", name, arguments.len())));
+        };
+        let want = if name == "uint" { Some(&Sol::Uint) } else { None };
+        let arg = self.expr(argument, want)?;
+        let (code, ty) = match (name, &arg.ty) {
+            ("len", Sol::Array(_)) => (format!("int256({}.length)", arg.at(POSTFIX)), Sol::Int),
+            ("len", Sol::Text) => {
+                return Err(unsupported("len() of text, which counts characters where Solidity sees bytes,"))
+            }
+            ("uint", Sol::Uint) | ("float", Sol::Int) => (arg.code, arg.ty),
+            ("uint", Sol::Int) => {
+                self.helpers.insert(Helper::ToUint);
+                (format!("_toUint({})", arg.code), Sol::Uint)
+            }
+            ("float", Sol::Uint) => {
+                self.helpers.insert(Helper::ToInt);
+                (format!("_toInt({})", arg.code), Sol::Int)
+            }
+            ("text", _) => (self.text_of(arg)?, Sol::Text),
+            (_, ty) => return Err(unsupported(&format!("Builtin '{}' on {}", name, ty))),
+        };
+        Ok((code, Some(ty)))
+    }
+}
+
+fn fractions() -> Rekt {
+    unsupported("'/' on numbers, which gives fractions in GM (use '~/' to divide whole numbers),")
+}
+
+fn never_matches(pattern: &str, ty: &Sol) -> Rekt {
+    Rekt::Type(format!("Pattern {} can never match {}", pattern, ty))
+}
+
+// Settles what a group of values (an array's items, a mapping's keys) holds.
+fn unify(slot: &mut Option<Sol>, ty: &Sol, what: &str) -> Result<(), Rekt> {
+    match slot {
+        Some(held) if held != ty => Err(mismatch(what, held, ty)),
+        Some(_) => Ok(()),
+        None => {
+            *slot = Some(ty.clone());
+            Ok(())
+        }
+    }
+}
+
+fn is_literal(ast: &Ast) -> bool {
+    match ast.unlocated() {
+        Ast::Literal(Value::Token(_) | Value::Int(_) | Value::Text(_) | Value::Boolean(_) | Value::Signal(_)) => true,
+        Ast::Grouping(inner) => is_literal(inner),
+        other => is_number_literal(other),
+    }
+}
+
+fn is_number_literal(ast: &Ast) -> bool {
+    literal_number(ast).is_some() || matches!(ast.unlocated(), Ast::Literal(Value::Int(_)))
+}
+
+fn literal_number(ast: &Ast) -> Option<f64> {
+    match ast.unlocated() {
+        Ast::Literal(Value::Token(token)) => token.parse().ok(),
+        Ast::Unary { operator: BinaryOp::Subtract, operand } => literal_number(operand).map(|n| -n),
+        Ast::Grouping(inner) => literal_number(inner),
+        _ => None,
+    }
+}
+
+fn ident(name: &str) -> String {
+    if RESERVED.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
+
+// `token_sale.gm` is contract TokenSale
+fn contract_name(source: &str) -> String {
+    let stem = source.rsplit_once('.').map(|(stem, _)| stem).unwrap_or(source);
+    let name: String = stem
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars.next().map(|c| c.to_ascii_uppercase().to_string() + chars.as_str()).unwrap_or_default()
+        })
+        .collect();
+    match name.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => name,
+        _ => format!("Gm{}", name),
+    }
+}
+
+// A string literal; anything outside ASCII needs Solidity's unicode"..."
+fn sol_string(text: &str) -> String {
+    let mut out = String::new();
+    let mut unicode = false;
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_ascii_control() => out.push_str(&format!("\\x{:02x}", c as u32)),
+            c => {
+                unicode |= !c.is_ascii();
+                out.push(c);
+            }
+        }
+    }
+    format!("{}\"{}\"", if unicode { "unicode" } else { "" }, out)
+}
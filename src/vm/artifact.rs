@@ -0,0 +1,741 @@
+use crate::error::Rekt;
+use crate::numeric::{Decimal, U256};
+use crate::parser::ast::Pattern;
+use crate::shared_types::{BinaryOp, Type, Value};
+use super::chunk::{Capture, Function, MatchArm, Op, Path, Program, Var, MAX_PATH};
+
+// A .gmc file is a compiled program that can be shipped and run without the
+// source. The layout, all little-endian:
+//
+//   magic     "GMC\0"
+//   version   u16, bumped whenever the layout or the op set changes
+//   flags     u16, always 0 for now
+//   length    u32, bytes in the body
+//   checksum  u32, CRC-32 of the body
+//   body      constant pool, globals, structs, patterns, function table
+//
+// Each function carries its code and a debug line table, so runtime errors
+// from a .gmc point at the same lines as errors from the script.
+pub const MAGIC: [u8; 4] = *b"GMC\0";
+pub const VERSION: u16 = 1;
+const HEADER_LEN: usize = 16;
+
+// Everything the header says, for listings.
+pub struct Header {
+    pub version: u16,
+    pub length: u32,
+    pub checksum: u32,
+}
+
+pub fn encode(program: &Program) -> Result<Vec<u8>, Rekt> {
+    let mut body = Writer { bytes: Vec::new() };
+    body.list(&program.constants, |w, value| w.value(value))?;
+    body.list(&program.globals, |w, name| {
+        w.text(name);
+        Ok(())
+    })?;
+    body.list(&program.structs, |w, (name, fields)| {
+        w.text(name);
+        w.list(fields, |w, (field, known)| {
+            w.text(field);
+            w.optional_type(known);
+            Ok(())
+        })
+    })?;
+    body.list(&program.patterns, |w, arm| {
+        w.pattern(&arm.pattern)?;
+        w.list(&arm.bindings, |w, (name, slot)| {
+            w.text(name);
+            w.u16(*slot);
+            Ok(())
+        })
+    })?;
+    body.list(&program.functions, |w, function| w.function(function))?;
+
+    let mut bytes = Vec::with_capacity(HEADER_LEN + body.bytes.len());
+    bytes.extend_from_slice(&MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&0u16.to_le_bytes());
+    bytes.extend_from_slice(&(body.bytes.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&crc32(&body.bytes).to_le_bytes());
+    bytes.extend_from_slice(&body.bytes);
+    Ok(bytes)
+}
+
+// Checks the magic, version, length and checksum before anything is trusted.
+pub fn header(bytes: &[u8]) -> Result<Header, Rekt> {
+    if bytes.len() < HEADER_LEN || bytes[..4] != MAGIC {
+        return Err(corrupt("not a .gmc file"));
+    }
+    let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+    if version != VERSION {
+        return Err(Rekt::Runtime(format!(
+            "This .gmc was compiled for format version {}, but this build reads version {}. Recompile the script 🔁",
+            version, VERSION
+        )));
+    }
+    let length = u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]);
+    let checksum = u32::from_le_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]);
+    if bytes.len() - HEADER_LEN != length as usize {
+        return Err(corrupt("the file is truncated"));
+    }
+    if crc32(&bytes[HEADER_LEN..]) != checksum {
+        return Err(corrupt("the checksum doesn't match"));
+    }
+    Ok(Header { version, length, checksum })
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Program, Rekt> {
+    header(bytes)?;
+    let mut r = Reader { bytes: &bytes[HEADER_LEN..], at: 0 };
+    let constants = r.list(|r| r.value())?;
+    let globals = r.list(|r| r.text())?;
+    let structs = r.list(|r| {
+        let name = r.text()?;
+        let fields = r.list(|r| Ok((r.text()?, r.optional_type()?)))?;
+        Ok((name, fields))
+    })?;
+    let patterns = r.list(|r| {
+        let pattern = r.pattern()?;
+        let bindings = r.list(|r| Ok((r.text()?, r.u16()?)))?;
+        Ok(MatchArm { pattern, bindings })
+    })?;
+    let functions = r.list(|r| r.function())?;
+    if r.at != r.bytes.len() {
+        return Err(corrupt("there are bytes after the function table"));
+    }
+
+    let program = Program { constants, globals, functions, structs, patterns };
+    validate(&program)?;
+    Ok(program)
+}
+
+pub(super) fn corrupt(why: &str) -> Rekt {
+    Rekt::Runtime(format!("Corrupt .gmc file: {} 💔", why))
+}
+
+// The checksum only catches accidents, so the VM also makes sure every index
+// in the code points at something before running it.
+fn validate(program: &Program) -> Result<(), Rekt> {
+    if program.functions.is_empty() {
+        return Err(corrupt("there is no top-level code"));
+    }
+    let (constants, globals, functions) =
+        (program.constants.len(), program.globals.len(), program.functions.len());
+    for function in &program.functions {
+        let slots = function.slot_names.len();
+        let within = |index: u32, len: usize| (index as usize) < len;
+        let var_ok = |var: Var| match var {
+            Var::Local(slot) => (slot as usize) < slots,
+            Var::Upvalue(index) => (index as usize) < function.upvalues.len(),
+            Var::Global(index) => within(index, globals),
+        };
+        let code_ok = |target: u32| (target as usize) < function.code.len();
+        let ok = function.code.iter().all(|op| match *op {
+            Op::Constant(i) | Op::Field(i) | Op::Fail(i) | Op::Struct(i, _) => within(i, constants),
+            Op::DeclareStruct(i) => within(i, program.structs.len()),
+            Op::Get(var) | Op::Set(var) | Op::Define(var) | Op::DefineBlock(var) | Op::CallVar(var, _) => var_ok(var),
+            Op::SetPath(var, path) | Op::CompoundPath(var, path, _) | Op::DeletePath(var, path) => {
+                var_ok(var) && path.len as usize <= MAX_PATH
+            }
+            Op::SetField(var, path) => var_ok(var) && (1..=MAX_PATH).contains(&(path.len as usize)),
+            Op::Jump(target) | Op::JumpIfFalse(target) | Op::Next(target) | Op::Unwind(target) => code_ok(target),
+            Op::Match(i, target) => {
+                within(i, program.patterns.len())
+                    && code_ok(target)
+                    && program.patterns[i as usize].bindings.iter().all(|(_, slot)| (*slot as usize) < slots)
+            }
+            // Captures are read from the frame that creates the closure
+            Op::Closure(i) => {
+                within(i, functions)
+                    && program.functions[i as usize].upvalues.iter().all(|capture| match *capture {
+                        Capture::Local(slot) => (slot as usize) < slots,
+                        Capture::Upvalue(index) => (index as usize) < function.upvalues.len(),
+                    })
+            }
+            Op::EndScope(i) => within(i, function.scopes.len()),
+            _ => true,
+        });
+        let slots_ok = function.params.iter().chain(function.scopes.iter().flatten()).all(|s| (*s as usize) < slots);
+        let names_ok = function.upvalue_names.len() == function.upvalues.len();
+        if !ok || !slots_ok || !names_ok || function.code.last() != Some(&Op::Return) {
+            return Err(corrupt(&format!("contract '{}' has an out-of-range operand", function.name)));
+        }
+    }
+    Ok(())
+}
+
+// CRC-32 (the zlib polynomial), bit by bit; .gmc files are small.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+const OPERATORS: [BinaryOp; 21] = [
+    BinaryOp::Add,
+    BinaryOp::Subtract,
+    BinaryOp::Multiply,
+    BinaryOp::Divide,
+    BinaryOp::Equal,
+    BinaryOp::NotEqual,
+    BinaryOp::Less,
+    BinaryOp::Greater,
+    BinaryOp::LessEqual,
+    BinaryOp::GreaterEqual,
+    BinaryOp::And,
+    BinaryOp::Or,
+    BinaryOp::Not,
+    BinaryOp::Modulo,
+    BinaryOp::IntDivide,
+    BinaryOp::BitAnd,
+    BinaryOp::BitOr,
+    BinaryOp::BitXor,
+    BinaryOp::ShiftLeft,
+    BinaryOp::ShiftRight,
+    BinaryOp::BitNot,
+];
+
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, n: u8) {
+        self.bytes.push(n);
+    }
+
+    fn u16(&mut self, n: u16) {
+        self.bytes.extend_from_slice(&n.to_le_bytes());
+    }
+
+    fn u32(&mut self, n: u32) {
+        self.bytes.extend_from_slice(&n.to_le_bytes());
+    }
+
+    fn text(&mut self, text: &str) {
+        self.u32(text.len() as u32);
+        self.bytes.extend_from_slice(text.as_bytes());
+    }
+
+    fn list<T>(&mut self, items: &[T], mut f: impl FnMut(&mut Self, &T) -> Result<(), Rekt>) -> Result<(), Rekt> {
+        self.u32(items.len() as u32);
+        items.iter().try_for_each(|item| f(self, item))
+    }
+
+    fn value(&mut self, value: &Value) -> Result<(), Rekt> {
+        match value {
+            Value::Null => self.u8(0),
+            Value::Number(n) => {
+                self.u8(1);
+                self.bytes.extend_from_slice(&n.to_le_bytes());
+            }
+            Value::Text(s) => {
+                self.u8(2);
+                self.text(s);
+            }
+            Value::Boolean(b) => {
+                self.u8(3);
+                self.u8(*b as u8);
+            }
+            Value::Token(t) => {
+                self.u8(4);
+                self.text(t);
+            }
+            Value::Signal(b) => {
+                self.u8(5);
+                self.u8(*b as u8);
+            }
+            Value::Int(n) => {
+                self.u8(6);
+                self.bytes.extend_from_slice(&n.to_be_bytes());
+            }
+            Value::Decimal(d) => {
+                self.u8(7);
+                self.bytes.extend_from_slice(&d.units().to_le_bytes());
+                self.u32(d.scale());
+            }
+            Value::Array(items) => {
+                self.u8(8);
+                self.list(items, |w, item| w.value(item))?;
+            }
+            other => {
+                return Err(Rekt::Runtime(format!("A {} can't be stored in a .gmc constant pool", other)));
+            }
+        }
+        Ok(())
+    }
+
+    fn optional_type(&mut self, known: &Option<Type>) {
+        let tag = match known {
+            None => 0,
+            Some(Type::Number) => 1,
+            Some(Type::Text) => 2,
+            Some(Type::Boolean) => 3,
+            Some(Type::Function) => 4,
+            Some(Type::Token) => 5,
+            Some(Type::Signal) => 6,
+            Some(Type::Uint) => 7,
+            Some(Type::Decimal) => 8,
+            Some(Type::Array) => 9,
+            Some(Type::Map) => 10,
+            Some(Type::Struct(_)) => 11,
+        };
+        self.u8(tag);
+        if let Some(Type::Struct(name)) = known {
+            self.text(name);
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) -> Result<(), Rekt> {
+        match pattern {
+            Pattern::Wildcard => self.u8(0),
+            Pattern::Literal(value) => {
+                self.u8(1);
+                self.value(value)?;
+            }
+            Pattern::Range(start, end) => {
+                self.u8(2);
+                self.value(start)?;
+                self.value(end)?;
+            }
+            Pattern::Binding(name) => {
+                self.u8(3);
+                self.text(name);
+            }
+            Pattern::Struct { name, fields } => {
+                self.u8(4);
+                self.text(name);
+                self.list(fields, |w, (field, sub)| {
+                    w.text(field);
+                    w.pattern(sub)
+                })?;
+            }
+            Pattern::Array { items, rest } => {
+                self.u8(5);
+                self.list(items, |w, item| w.pattern(item))?;
+                match rest {
+                    Some(rest) => {
+                        self.u8(1);
+                        self.text(rest);
+                    }
+                    None => self.u8(0),
+                }
+            }
+            Pattern::Or(alternatives) => {
+                self.u8(6);
+                self.list(alternatives, |w, alternative| w.pattern(alternative))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn function(&mut self, function: &Function) -> Result<(), Rekt> {
+        self.text(&function.name);
+        self.list(&function.params, |w, slot| {
+            w.u16(*slot);
+            Ok(())
+        })?;
+        self.list(&function.slot_names, |w, name| {
+            w.text(name);
+            Ok(())
+        })?;
+        self.list(&function.upvalues, |w, capture| {
+            match capture {
+                Capture::Local(slot) => {
+                    w.u8(0);
+                    w.u16(*slot);
+                }
+                Capture::Upvalue(index) => {
+                    w.u8(1);
+                    w.u16(*index);
+                }
+            }
+            Ok(())
+        })?;
+        self.list(&function.upvalue_names, |w, name| {
+            w.text(name);
+            Ok(())
+        })?;
+        self.list(&function.scopes, |w, slots| {
+            w.list(slots, |w, slot| {
+                w.u16(*slot);
+                Ok(())
+            })
+        })?;
+        self.list(&function.code, |w, op| {
+            w.op(op);
+            Ok(())
+        })?;
+        self.list(&function.lines, |w, (start, line)| {
+            w.u32(*start);
+            w.u32(*line);
+            Ok(())
+        })
+    }
+
+    fn var(&mut self, var: Var) {
+        match var {
+            Var::Local(slot) => {
+                self.u8(0);
+                self.u16(slot);
+            }
+            Var::Upvalue(index) => {
+                self.u8(1);
+                self.u16(index);
+            }
+            Var::Global(index) => {
+                self.u8(2);
+                self.u32(index);
+            }
+        }
+    }
+
+    fn path(&mut self, path: Path) {
+        self.u8(path.len);
+        self.u32(path.fields);
+    }
+
+    fn operator(&mut self, operator: BinaryOp) {
+        self.u8(operator as u8);
+    }
+
+    fn op(&mut self, op: &Op) {
+        match *op {
+            Op::Constant(i) => {
+                self.u8(0);
+                self.u32(i);
+            }
+            Op::Null => self.u8(1),
+            Op::Pop => self.u8(2),
+            Op::Nip => self.u8(3),
+            Op::Get(var) => {
+                self.u8(4);
+                self.var(var);
+            }
+            Op::Set(var) => {
+                self.u8(5);
+                self.var(var);
+            }
+            Op::Define(var) => {
+                self.u8(6);
+                self.var(var);
+            }
+            Op::DefineBlock(var) => {
+                self.u8(7);
+                self.var(var);
+            }
+            Op::Binary(operator) => {
+                self.u8(8);
+                self.operator(operator);
+            }
+            Op::Unary(operator) => {
+                self.u8(9);
+                self.operator(operator);
+            }
+            Op::Concat(n) => {
+                self.u8(10);
+                self.u32(n);
+            }
+            Op::Array(n) => {
+                self.u8(11);
+                self.u32(n);
+            }
+            Op::Map(n) => {
+                self.u8(12);
+                self.u32(n);
+            }
+            Op::Struct(name, n) => {
+                self.u8(13);
+                self.u32(name);
+                self.u32(n);
+            }
+            Op::DeclareStruct(i) => {
+                self.u8(14);
+                self.u32(i);
+            }
+            Op::Index => self.u8(15),
+            Op::Field(name) => {
+                self.u8(16);
+                self.u32(name);
+            }
+            Op::SetPath(var, path) => {
+                self.u8(17);
+                self.var(var);
+                self.path(path);
+            }
+            Op::SetField(var, path) => {
+                self.u8(18);
+                self.var(var);
+                self.path(path);
+            }
+            Op::CompoundPath(var, path, operator) => {
+                self.u8(19);
+                self.var(var);
+                self.path(path);
+                self.operator(operator);
+            }
+            Op::DeletePath(var, path) => {
+                self.u8(20);
+                self.var(var);
+                self.path(path);
+            }
+            Op::Jump(target) => {
+                self.u8(21);
+                self.u32(target);
+            }
+            Op::JumpIfFalse(target) => {
+                self.u8(22);
+                self.u32(target);
+            }
+            Op::Closure(i) => {
+                self.u8(23);
+                self.u32(i);
+            }
+            Op::Call(argc) => {
+                self.u8(24);
+                self.u8(argc);
+            }
+            Op::CallVar(var, argc) => {
+                self.u8(25);
+                self.var(var);
+                self.u8(argc);
+            }
+            Op::Return => self.u8(26),
+            Op::Print => self.u8(27),
+            Op::Loop => self.u8(28),
+            Op::IterPairs(keyed) => {
+                self.u8(29);
+                self.u8(keyed as u8);
+            }
+            Op::IterRange(stepped) => {
+                self.u8(30);
+                self.u8(stepped as u8);
+            }
+            Op::Next(exit) => {
+                self.u8(31);
+                self.u32(exit);
+            }
+            Op::EndLoop => self.u8(32),
+            Op::Unwind(target) => {
+                self.u8(33);
+                self.u32(target);
+            }
+            Op::Match(i, miss) => {
+                self.u8(34);
+                self.u32(i);
+                self.u32(miss);
+            }
+            Op::NoMatch => self.u8(35),
+            Op::EndScope(i) => {
+                self.u8(36);
+                self.u32(i);
+            }
+            Op::Fail(message) => {
+                self.u8(37);
+                self.u32(message);
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Rekt> {
+        let end = self.at.checked_add(n).filter(|end| *end <= self.bytes.len());
+        match end {
+            Some(end) => {
+                let bytes = &self.bytes[self.at..end];
+                self.at = end;
+                Ok(bytes)
+            }
+            None => Err(corrupt("a section runs past the end")),
+        }
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], Rekt> {
+        let mut bytes = [0u8; N];
+        bytes.copy_from_slice(self.take(N)?);
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, Rekt> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn flag(&mut self) -> Result<bool, Rekt> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(corrupt("a flag is neither 0 nor 1")),
+        }
+    }
+
+    fn u16(&mut self) -> Result<u16, Rekt> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, Rekt> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn text(&mut self) -> Result<String, Rekt> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| corrupt("a name isn't valid UTF-8"))
+    }
+
+    fn list<T>(&mut self, mut f: impl FnMut(&mut Self) -> Result<T, Rekt>) -> Result<Vec<T>, Rekt> {
+        let count = self.u32()? as usize;
+        // Every item takes at least a byte, which caps what a bad count can allocate
+        let mut items = Vec::with_capacity(count.min(self.bytes.len() - self.at));
+        for _ in 0..count {
+            items.push(f(self)?);
+        }
+        Ok(items)
+    }
+
+    fn value(&mut self) -> Result<Value, Rekt> {
+        Ok(match self.u8()? {
+            0 => Value::Null,
+            1 => Value::Number(f64::from_le_bytes(self.array()?)),
+            2 => Value::Text(self.text()?),
+            3 => Value::Boolean(self.flag()?),
+            4 => Value::Token(self.text()?),
+            5 => Value::Signal(self.flag()?),
+            6 => Value::Int(U256::from_be_bytes(self.array()?)),
+            7 => {
+                let units = i128::from_le_bytes(self.array()?);
+                let scale = self.u32()?;
+                Value::Decimal(Decimal::new(units, scale).ok_or_else(|| corrupt("a decimal has too many digits"))?)
+            }
+            8 => Value::Array(self.list(|r| r.value())?),
+            tag => return Err(corrupt(&format!("unknown value tag {}", tag))),
+        })
+    }
+
+    fn optional_type(&mut self) -> Result<Option<Type>, Rekt> {
+        Ok(Some(match self.u8()? {
+            0 => return Ok(None),
+            1 => Type::Number,
+            2 => Type::Text,
+            3 => Type::Boolean,
+            4 => Type::Function,
+            5 => Type::Token,
+            6 => Type::Signal,
+            7 => Type::Uint,
+            8 => Type::Decimal,
+            9 => Type::Array,
+            10 => Type::Map,
+            11 => Type::Struct(self.text()?),
+            tag => return Err(corrupt(&format!("unknown type tag {}", tag))),
+        }))
+    }
+
+    fn pattern(&mut self) -> Result<Pattern, Rekt> {
+        Ok(match self.u8()? {
+            0 => Pattern::Wildcard,
+            1 => Pattern::Literal(self.value()?),
+            2 => Pattern::Range(self.value()?, self.value()?),
+            3 => Pattern::Binding(self.text()?),
+            4 => Pattern::Struct {
+                name: self.text()?,
+                fields: self.list(|r| Ok((r.text()?, r.pattern()?)))?,
+            },
+            5 => Pattern::Array {
+                items: self.list(|r| r.pattern())?,
+                rest: if self.flag()? { Some(self.text()?) } else { None },
+            },
+            6 => Pattern::Or(self.list(|r| r.pattern())?),
+            tag => return Err(corrupt(&format!("unknown pattern tag {}", tag))),
+        })
+    }
+
+    fn function(&mut self) -> Result<Function, Rekt> {
+        Ok(Function {
+            name: self.text()?,
+            params: self.list(|r| r.u16())?,
+            slot_names: self.list(|r| r.text())?,
+            upvalues: self.list(|r| match r.u8()? {
+                0 => Ok(Capture::Local(r.u16()?)),
+                1 => Ok(Capture::Upvalue(r.u16()?)),
+                tag => Err(corrupt(&format!("unknown capture tag {}", tag))),
+            })?,
+            upvalue_names: self.list(|r| r.text())?,
+            scopes: self.list(|r| r.list(|r| r.u16()))?,
+            code: self.list(|r| r.op())?,
+            lines: self.list(|r| Ok((r.u32()?, r.u32()?)))?,
+        })
+    }
+
+    fn var(&mut self) -> Result<Var, Rekt> {
+        match self.u8()? {
+            0 => Ok(Var::Local(self.u16()?)),
+            1 => Ok(Var::Upvalue(self.u16()?)),
+            2 => Ok(Var::Global(self.u32()?)),
+            tag => Err(corrupt(&format!("unknown variable tag {}", tag))),
+        }
+    }
+
+    fn path(&mut self) -> Result<Path, Rekt> {
+        Ok(Path { len: self.u8()?, fields: self.u32()? })
+    }
+
+    fn operator(&mut self) -> Result<BinaryOp, Rekt> {
+        let index = self.u8()? as usize;
+        OPERATORS.get(index).copied().ok_or_else(|| corrupt(&format!("unknown operator {}", index)))
+    }
+
+    fn op(&mut self) -> Result<Op, Rekt> {
+        Ok(match self.u8()? {
+            0 => Op::Constant(self.u32()?),
+            1 => Op::Null,
+            2 => Op::Pop,
+            3 => Op::Nip,
+            4 => Op::Get(self.var()?),
+            5 => Op::Set(self.var()?),
+            6 => Op::Define(self.var()?),
+            7 => Op::DefineBlock(self.var()?),
+            8 => Op::Binary(self.operator()?),
+            9 => Op::Unary(self.operator()?),
+            10 => Op::Concat(self.u32()?),
+            11 => Op::Array(self.u32()?),
+            12 => Op::Map(self.u32()?),
+            13 => Op::Struct(self.u32()?, self.u32()?),
+            14 => Op::DeclareStruct(self.u32()?),
+            15 => Op::Index,
+            16 => Op::Field(self.u32()?),
+            17 => Op::SetPath(self.var()?, self.path()?),
+            18 => Op::SetField(self.var()?, self.path()?),
+            19 => Op::CompoundPath(self.var()?, self.path()?, self.operator()?),
+            20 => Op::DeletePath(self.var()?, self.path()?),
+            21 => Op::Jump(self.u32()?),
+            22 => Op::JumpIfFalse(self.u32()?),
+            23 => Op::Closure(self.u32()?),
+            24 => Op::Call(self.u8()?),
+            25 => Op::CallVar(self.var()?, self.u8()?),
+            26 => Op::Return,
+            27 => Op::Print,
+            28 => Op::Loop,
+            29 => Op::IterPairs(self.flag()?),
+            30 => Op::IterRange(self.flag()?),
+            31 => Op::Next(self.u32()?),
+            32 => Op::EndLoop,
+            33 => Op::Unwind(self.u32()?),
+            34 => Op::Match(self.u32()?, self.u32()?),
+            35 => Op::NoMatch,
+            36 => Op::EndScope(self.u32()?),
+            37 => Op::Fail(self.u32()?),
+            code => return Err(corrupt(&format!("unknown opcode {}", code))),
+        })
+    }
+}
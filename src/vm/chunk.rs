@@ -0,0 +1,149 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use crate::parser::ast::Pattern;
+use crate::interpreter::StructFields;
+use crate::shared_types::{BinaryOp, Value};
+
+// Where a name lives once the compiler has looked it up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Var {
+    Local(u16),   // a slot in the running frame
+    Upvalue(u16), // captured from an enclosing contract
+    Global(u32),  // a top-level token, by index into Program::globals
+}
+
+// The steps of an assignment target like `m[k].balance`. The steps are
+// pushed onto the stack before the op runs; bit i of `fields` says whether
+// step i is a field name rather than an evaluated key.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Path {
+    pub len: u8,
+    pub fields: u32,
+}
+
+pub const MAX_PATH: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Constant(u32),            // pushes Program::constants[i]
+    Null,
+    Pop,
+    Nip,                      // drops the value under the top one
+    Get(Var),
+    Set(Var),                 // transfers the top value, leaving it there
+    Define(Var),              // mints the top value, leaving it there
+    DefineBlock(Var),         // same, but the name becomes a block constant
+    Binary(BinaryOp),
+    Unary(BinaryOp),
+    Concat(u32),              // joins the rendered top n values into text
+    Array(u32),
+    Map(u32),                 // n key/value pairs
+    Struct(u32, u32),         // name constant, n field-name/value pairs
+    DeclareStruct(u32),       // index into Program::structs
+    Index,
+    Field(u32),               // field name constant
+    SetPath(Var, Path),       // steps, then the value
+    SetField(Var, Path),      // steps ending in the field, then the value
+    CompoundPath(Var, Path, BinaryOp),
+    DeletePath(Var, Path),
+    Jump(u32),
+    JumpIfFalse(u32),         // pops a signal
+    Closure(u32),             // index into Program::functions
+    Call(u8),                 // the callee sits under its n arguments
+    CallVar(Var, u8),         // calls a named contract, or the builtin of that name
+    Return,
+    Print,
+    Loop,                     // a grind loop starts; remembers the stack height
+    IterPairs(bool),          // pops the iterable of a farm loop; true when it binds a key
+    IterRange(bool),          // pops start, end and, when true, step
+    Next(u32),                // pushes the next key/value or number, or jumps when done
+    EndLoop,
+    Unwind(u32),              // crash/skip: drops loop temporaries and jumps
+    Match(u32, u32),          // index into Program::patterns, jump when it doesn't match
+    NoMatch,
+    EndScope(u32),            // index into Function::scopes
+    Fail(u32),                // runtime error with a text constant
+}
+
+// A compiled contract body. Slots are never shared between two names, so
+// a slot number is enough to name a local in an error.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub params: Vec<u16>,          // the slot each argument lands in
+    pub slot_names: Vec<String>,
+    pub upvalues: Vec<Capture>,
+    pub upvalue_names: Vec<String>,
+    pub scopes: Vec<Vec<u16>>,     // slots that go out of scope together
+    pub code: Vec<Op>,
+    pub lines: Vec<(u32, u32)>,    // (first op, source line), in code order
+}
+
+impl Function {
+    // The source line the op at `at` was compiled from, when it's known.
+    pub fn line_at(&self, at: usize) -> Option<u32> {
+        let after = self.lines.partition_point(|(start, _)| *start as usize <= at);
+        after.checked_sub(1).map(|i| self.lines[i].1).filter(|line| *line != 0)
+    }
+}
+
+// How a closure picks up an upvalue when it's created: from a local of the
+// enclosing frame, or from one of the enclosing closure's own upvalues.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Capture {
+    Local(u16),
+    Upvalue(u16),
+}
+
+// A vibecheck pattern and the slot each of its bindings lands in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub bindings: Vec<(String, u16)>,
+}
+
+// Everything the VM needs to run a script. functions[0] is the top level.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    pub constants: Vec<Value>,
+    pub globals: Vec<String>,
+    pub functions: Vec<Function>,
+    pub structs: Vec<(String, StructFields)>,
+    pub patterns: Vec<MatchArm>,
+}
+
+// A token slot: its value once minted, and whether it was minted as a block.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Local {
+    pub value: Option<Value>,
+    pub block: bool,
+}
+
+// A captured token. It points into the frame while that frame's scope is
+// alive and takes the value along once the scope ends.
+#[derive(Debug)]
+pub enum Upvalue {
+    Open(usize),
+    Closed(Local),
+}
+
+// A contract value built by the VM.
+pub struct Closure {
+    pub function: u32,
+    pub name: String,
+    pub upvalues: Vec<Rc<RefCell<Upvalue>>>,
+}
+
+// Contracts are equal only to themselves, like a deployed address.
+impl PartialEq for Closure {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Closure({})", self.name)
+    }
+}
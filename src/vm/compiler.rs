@@ -0,0 +1,713 @@
+use std::collections::{HashMap, HashSet};
+use crate::error::Rekt;
+use crate::parser::ast::Ast;
+use crate::shared_types::Value;
+use super::chunk::{Capture, Function, MatchArm, Op, Path, Program, Var, MAX_PATH};
+
+// Lowers a parsed program to bytecode. Names are resolved here, once:
+// top-level tokens become globals, everything declared inside a block,
+// loop or contract gets a numbered slot in its frame, and tokens a contract
+// borrows from around it become upvalues.
+pub fn compile(ast: &Ast) -> Result<Program, Rekt> {
+    let stmts = match ast {
+        Ast::Program(stmts) => stmts.as_slice(),
+        other => std::slice::from_ref(other),
+    };
+    let main = FunctionState::new("main");
+    let mut compiler = Compiler {
+        program: Program {
+            constants: Vec::new(),
+            globals: Vec::new(),
+            // Held for the top level until it's done compiling
+            functions: vec![main.function.clone()],
+            structs: Vec::new(),
+            patterns: Vec::new(),
+        },
+        globals: HashMap::new(),
+        constants: HashMap::new(),
+        functions: vec![main],
+    };
+    compiler.block_value(stmts)?;
+    compiler.emit(Op::Return);
+
+    let main = compiler.functions.pop().expect("the top level is always compiled");
+    let mut program = compiler.program;
+    program.functions[0] = main.function;
+    Ok(program)
+}
+
+struct Scope {
+    id: u32,                      // index into Function::scopes
+    names: HashMap<String, u16>,
+    pending: HashSet<String>,     // declared further down in this scope
+}
+
+struct LoopContext {
+    depth: usize,      // scopes open around the loop
+    body: usize,       // scopes open inside one pass of the body
+    breaks: Vec<usize>,
+    continues: Vec<usize>,
+}
+
+struct FunctionState {
+    function: Function,
+    scopes: Vec<Scope>, // empty only at the top level, where names are globals
+    loops: Vec<LoopContext>,
+    line: u32,          // source line of the statement being compiled, 0 if unknown
+}
+
+impl FunctionState {
+    fn new(name: &str) -> Self {
+        FunctionState {
+            function: Function {
+                name: name.to_string(),
+                params: Vec::new(),
+                slot_names: Vec::new(),
+                upvalues: Vec::new(),
+                upvalue_names: Vec::new(),
+                scopes: Vec::new(),
+                code: Vec::new(),
+                lines: Vec::new(),
+            },
+            scopes: Vec::new(),
+            loops: Vec::new(),
+            line: 0,
+        }
+    }
+}
+
+// One step of an assignment target, before it's compiled.
+enum Step<'a> {
+    Key(&'a Ast),
+    Field(&'a str),
+}
+
+struct Compiler {
+    program: Program,
+    globals: HashMap<String, u32>,
+    constants: HashMap<String, u32>, // text and token constants, deduplicated
+    functions: Vec<FunctionState>,   // the contract being compiled is last
+}
+
+impl Compiler {
+    fn current(&mut self) -> &mut FunctionState {
+        self.functions.last_mut().expect("always inside a function")
+    }
+
+    fn emit(&mut self, op: Op) -> usize {
+        let code = &mut self.current().function.code;
+        code.push(op);
+        code.len() - 1
+    }
+
+    fn here(&mut self) -> u32 {
+        self.current().function.code.len() as u32
+    }
+
+    // Points the jump at `at` to `target`.
+    fn patch(&mut self, at: usize, target: u32) {
+        let op = &mut self.current().function.code[at];
+        *op = match *op {
+            Op::Jump(_) => Op::Jump(target),
+            Op::JumpIfFalse(_) => Op::JumpIfFalse(target),
+            Op::Next(_) => Op::Next(target),
+            Op::Unwind(_) => Op::Unwind(target),
+            Op::Match(pattern, _) => Op::Match(pattern, target),
+            other => other,
+        };
+    }
+
+    // Notes that the code from here on comes from `line`.
+    fn mark_line(&mut self, line: u32) {
+        let at = self.here();
+        let state = self.current();
+        state.line = line;
+        let lines = &mut state.function.lines;
+        if matches!(lines.last(), Some((start, _)) if *start == at) {
+            lines.pop();
+        }
+        if lines.last().map_or(line != 0, |(_, last)| *last != line) {
+            lines.push((at, line));
+        }
+    }
+
+    fn constant(&mut self, value: Value) -> u32 {
+        let key = match &value {
+            Value::Text(s) => Some(format!("t{}", s)),
+            Value::Token(t) => Some(format!("n{}", t)),
+            _ => None,
+        };
+        if let Some(index) = key.as_ref().and_then(|k| self.constants.get(k)) {
+            return *index;
+        }
+        let index = self.program.constants.len() as u32;
+        self.program.constants.push(value);
+        if let Some(key) = key {
+            self.constants.insert(key, index);
+        }
+        index
+    }
+
+    fn text(&mut self, text: &str) -> u32 {
+        self.constant(Value::Text(text.to_string()))
+    }
+
+    fn global(&mut self, name: &str) -> u32 {
+        if let Some(index) = self.globals.get(name) {
+            return *index;
+        }
+        let index = self.program.globals.len() as u32;
+        self.program.globals.push(name.to_string());
+        self.globals.insert(name.to_string(), index);
+        index
+    }
+
+    fn begin_scope(&mut self, stmts: &[Ast]) {
+        let mut pending = HashSet::new();
+        declared_names(stmts, &mut pending);
+        let state = self.current();
+        let id = state.function.scopes.len() as u32;
+        state.function.scopes.push(Vec::new());
+        state.scopes.push(Scope { id, names: HashMap::new(), pending });
+    }
+
+    fn end_scope(&mut self) {
+        let state = self.current();
+        let scope = state.scopes.pop().expect("scopes are balanced");
+        if !state.function.scopes[scope.id as usize].is_empty() {
+            self.emit(Op::EndScope(scope.id));
+        }
+    }
+
+    // Closes every scope deeper than `depth` without leaving it, ahead of a
+    // crash or skip jumping out.
+    fn close_scopes(&mut self, depth: usize) {
+        let ids: Vec<u32> = self.current().scopes[depth..].iter().rev().map(|s| s.id).collect();
+        for id in ids {
+            self.emit(Op::EndScope(id));
+        }
+    }
+
+    // Gives `name` a slot in scope `scope` of function `f`.
+    fn allocate(&mut self, f: usize, scope: usize, name: &str) -> Result<u16, Rekt> {
+        let state = &mut self.functions[f];
+        let slot = u16::try_from(state.function.slot_names.len())
+            .map_err(|_| Rekt::Runtime(format!("Contract '{}' has too many tokens", state.function.name)))?;
+        state.function.slot_names.push(name.to_string());
+        let scope = &mut state.scopes[scope];
+        scope.names.insert(name.to_string(), slot);
+        scope.pending.remove(name);
+        state.function.scopes[scope.id as usize].push(slot);
+        Ok(slot)
+    }
+
+    // Where a declaration in the current scope lands. Declaring a name twice
+    // in one scope reuses its slot, the same way minting overwrites.
+    fn declare(&mut self, name: &str) -> Result<Var, Rekt> {
+        let f = self.functions.len() - 1;
+        let depth = match self.functions[f].scopes.last() {
+            None => return Ok(Var::Global(self.global(name))),
+            Some(scope) => match scope.names.get(name) {
+                Some(slot) => return Ok(Var::Local(*slot)),
+                None => self.functions[f].scopes.len() - 1,
+            },
+        };
+        Ok(Var::Local(self.allocate(f, depth, name)?))
+    }
+
+    fn resolve(&mut self, name: &str) -> Result<Var, Rekt> {
+        let f = self.functions.len() - 1;
+        if let Some(slot) = self.local(f, name, false)? {
+            return Ok(Var::Local(slot));
+        }
+        if let Some(index) = self.upvalue(f, name)? {
+            return Ok(Var::Upvalue(index));
+        }
+        Ok(Var::Global(self.global(name)))
+    }
+
+    // A name's slot among function f's open scopes. Contracts run later
+    // than they're mined, so when `hoist` is set a name declared further
+    // down an enclosing scope counts too, and gets its slot right away.
+    fn local(&mut self, f: usize, name: &str, hoist: bool) -> Result<Option<u16>, Rekt> {
+        for depth in (0..self.functions[f].scopes.len()).rev() {
+            let scope = &self.functions[f].scopes[depth];
+            if let Some(slot) = scope.names.get(name) {
+                return Ok(Some(*slot));
+            }
+            if hoist && scope.pending.contains(name) {
+                return self.allocate(f, depth, name).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    fn upvalue(&mut self, f: usize, name: &str) -> Result<Option<u16>, Rekt> {
+        if f == 0 {
+            return Ok(None);
+        }
+        let capture = match self.local(f - 1, name, true)? {
+            Some(slot) => Capture::Local(slot),
+            None => match self.upvalue(f - 1, name)? {
+                Some(index) => Capture::Upvalue(index),
+                None => return Ok(None),
+            },
+        };
+        let function = &mut self.functions[f].function;
+        if let Some(index) = function.upvalues.iter().position(|c| *c == capture) {
+            return Ok(Some(index as u16));
+        }
+        function.upvalues.push(capture);
+        function.upvalue_names.push(name.to_string());
+        Ok(Some((function.upvalues.len() - 1) as u16))
+    }
+
+    // Compiles statements that leave only the last one's value behind, or
+    // null when there are none.
+    fn block_value(&mut self, stmts: &[Ast]) -> Result<(), Rekt> {
+        if stmts.is_empty() {
+            self.emit(Op::Null);
+        }
+        for (i, stmt) in stmts.iter().enumerate() {
+            self.node(stmt)?;
+            if i + 1 < stmts.len() {
+                self.emit(Op::Pop);
+            }
+        }
+        Ok(())
+    }
+
+    fn statements(&mut self, stmts: &[Ast]) -> Result<(), Rekt> {
+        for stmt in stmts {
+            self.node(stmt)?;
+            self.emit(Op::Pop);
+        }
+        Ok(())
+    }
+
+    // Compiles a contract body and returns its index in the program.
+    fn function(&mut self, name: &str, params: &[String], body: &[Ast]) -> Result<u32, Rekt> {
+        self.functions.push(FunctionState::new(name));
+        self.begin_scope(body);
+        for param in params {
+            let slot = match self.declare(param)? {
+                Var::Local(slot) => slot,
+                _ => unreachable!("parameters are always local"),
+            };
+            self.current().function.params.push(slot);
+        }
+        self.block_value(body)?;
+        self.emit(Op::Return);
+        let state = self.functions.pop().expect("pushed above");
+        self.program.functions.push(state.function);
+        Ok(self.program.functions.len() as u32 - 1)
+    }
+
+    // Compiles the steps of an assignment target and returns its root token
+    // and path, or None when it isn't something that can be assigned to.
+    fn target(&mut self, ast: &Ast, last: Option<Step>) -> Result<Option<(Var, Path)>, Rekt> {
+        let mut steps = Vec::new();
+        let root = match target_steps(ast, &mut steps) {
+            Some(root) => root,
+            None => {
+                let message = self.text("Invalid assignment target");
+                self.emit(Op::Fail(message));
+                return Ok(None);
+            }
+        };
+        steps.extend(last);
+        if steps.len() > MAX_PATH {
+            return Err(Rekt::Runtime(format!("Assignment to '{}' goes more than {} levels deep", root, MAX_PATH)));
+        }
+        let mut path = Path { len: steps.len() as u8, fields: 0 };
+        for (i, step) in steps.iter().enumerate() {
+            match step {
+                Step::Key(index) => self.node(index)?,
+                Step::Field(field) => {
+                    let name = self.text(field);
+                    self.emit(Op::Constant(name));
+                    path.fields |= 1 << i;
+                }
+            }
+        }
+        Ok(Some((self.resolve(root)?, path)))
+    }
+
+    fn condition(&mut self, condition: &Ast) -> Result<usize, Rekt> {
+        self.node(condition)?;
+        Ok(self.emit(Op::JumpIfFalse(0)))
+    }
+
+    fn begin_loop(&mut self, depth: usize) {
+        let body = self.current().scopes.len();
+        self.current().loops.push(LoopContext { depth, body, breaks: Vec::new(), continues: Vec::new() });
+    }
+
+    fn end_loop(&mut self, next: u32, exit: u32) {
+        let context = self.current().loops.pop().expect("loops are balanced");
+        for at in context.breaks {
+            self.patch(at, exit);
+        }
+        for at in context.continues {
+            self.patch(at, next);
+        }
+        self.emit(Op::EndLoop);
+        self.emit(Op::Null);
+    }
+
+    // The shared tail of both farm loops: bind the loop variables in a fresh
+    // scope for each pass and run the body.
+    fn farm_body(&mut self, key: Option<&str>, variable: &str, body: &[Ast]) -> Result<(), Rekt> {
+        let depth = self.current().scopes.len();
+        let next = self.here();
+        let exhausted = self.emit(Op::Next(0));
+        self.begin_scope(body);
+        // Next leaves the key on top of the value
+        if let Some(key) = key {
+            let var = self.declare(key)?;
+            self.emit(Op::Define(var));
+            self.emit(Op::Pop);
+        }
+        let var = self.declare(variable)?;
+        self.emit(Op::Define(var));
+        self.emit(Op::Pop);
+
+        self.begin_loop(depth);
+        self.statements(body)?;
+        let skip = self.here();
+        self.end_scope();
+        self.emit(Op::Jump(next));
+        let exit = self.here();
+        self.patch(exhausted, exit);
+        self.end_loop(skip, exit);
+        Ok(())
+    }
+
+    fn node(&mut self, ast: &Ast) -> Result<(), Rekt> {
+        match ast {
+            Ast::Program(stmts) => self.block_value(stmts)?,
+
+            Ast::Literal(value) => {
+                let index = self.constant(value.clone());
+                self.emit(Op::Constant(index));
+            }
+
+            Ast::Variable(name) | Ast::ResolvedVariable { name, .. } => {
+                let var = self.resolve(name)?;
+                self.emit(Op::Get(var));
+            }
+
+            Ast::Grouping(inner) | Ast::ExpressionStmt(inner) => self.node(inner)?,
+
+            Ast::Located { line, statement } => {
+                let outer = self.current().line;
+                self.mark_line(*line as u32);
+                self.node(statement).map_err(|e| e.at_line(*line))?;
+                self.mark_line(outer);
+            }
+
+            Ast::Binary { left, operator, right } => {
+                self.node(left)?;
+                self.node(right)?;
+                self.emit(Op::Binary(*operator));
+            }
+
+            Ast::Unary { operator, operand } => {
+                self.node(operand)?;
+                self.emit(Op::Unary(*operator));
+            }
+
+            Ast::Interpolation(parts) => {
+                for part in parts {
+                    self.node(part)?;
+                }
+                self.emit(Op::Concat(parts.len() as u32));
+            }
+
+            Ast::ArrayLiteral(items) => {
+                for item in items {
+                    self.node(item)?;
+                }
+                self.emit(Op::Array(items.len() as u32));
+            }
+
+            Ast::MapLiteral(entries) => {
+                for (key, value) in entries {
+                    self.node(key)?;
+                    self.node(value)?;
+                }
+                self.emit(Op::Map(entries.len() as u32));
+            }
+
+            Ast::Index { object, index } => {
+                self.node(object)?;
+                self.node(index)?;
+                self.emit(Op::Index);
+            }
+
+            Ast::Field { object, name } => {
+                self.node(object)?;
+                let name = self.text(name);
+                self.emit(Op::Field(name));
+            }
+
+            Ast::StructDecl { name, fields, .. } => {
+                self.program.structs.push((name.clone(), fields.clone()));
+                self.emit(Op::DeclareStruct(self.program.structs.len() as u32 - 1));
+            }
+
+            Ast::StructLiteral { name, fields } => {
+                for (field, init) in fields {
+                    let field = self.text(field);
+                    self.emit(Op::Constant(field));
+                    self.node(init)?;
+                }
+                let name = self.text(name);
+                self.emit(Op::Struct(name, fields.len() as u32));
+            }
+
+            Ast::VariableDecl { name, initializer, is_constant, .. } => {
+                self.node(initializer)?;
+                let var = self.declare(name)?;
+                self.emit(if *is_constant { Op::DefineBlock(var) } else { Op::Define(var) });
+            }
+
+            Ast::Assign { name, value } => {
+                self.node(value)?;
+                let var = self.resolve(name)?;
+                self.emit(Op::Set(var));
+            }
+
+            Ast::SetIndex { object, index, value } => {
+                if let Some((var, path)) = self.target(object, Some(Step::Key(index)))? {
+                    self.node(value)?;
+                    self.emit(Op::SetPath(var, path));
+                }
+            }
+
+            Ast::SetField { object, name, value } => {
+                if let Some((var, path)) = self.target(object, Some(Step::Field(name)))? {
+                    self.node(value)?;
+                    self.emit(Op::SetField(var, path));
+                }
+            }
+
+            Ast::CompoundAssign { target, operator, value } => {
+                if let Some((var, path)) = self.target(target, None)? {
+                    self.node(value)?;
+                    self.emit(Op::CompoundPath(var, path, *operator));
+                }
+            }
+
+            Ast::Delete(target) => {
+                if let Some((var, path)) = self.target(target, None)? {
+                    self.emit(Op::DeletePath(var, path));
+                }
+            }
+
+            Ast::FunctionDecl { name, params, body, .. } => {
+                // Declared first so the body can call itself
+                let var = self.declare(name)?;
+                let params: Vec<String> = params.iter().map(|(p, _)| p.clone()).collect();
+                let index = self.function(name, &params, body)?;
+                self.emit(Op::Closure(index));
+                self.emit(Op::Define(var));
+            }
+
+            Ast::Lambda { params, body, .. } => {
+                let params: Vec<String> = params.iter().map(|(p, _)| p.clone()).collect();
+                let index = self.function("anonymous", &params, body)?;
+                self.emit(Op::Closure(index));
+            }
+
+            Ast::Call { callee, arguments } => {
+                let argc = u8::try_from(arguments.len())
+                    .map_err(|_| Rekt::Runtime("A contract call can take at most 255 args".to_string()))?;
+                match callee.as_ref() {
+                    Ast::Variable(name) => {
+                        let var = self.resolve(name)?;
+                        for arg in arguments {
+                            self.node(arg)?;
+                        }
+                        self.emit(Op::CallVar(var, argc));
+                    }
+                    other => {
+                        self.node(other)?;
+                        for arg in arguments {
+                            self.node(arg)?;
+                        }
+                        self.emit(Op::Call(argc));
+                    }
+                }
+            }
+
+            Ast::ReturnStmt(value) => {
+                match value {
+                    Some(value) => self.node(value)?,
+                    None => {
+                        self.emit(Op::Null);
+                    }
+                }
+                self.emit(Op::Return);
+            }
+
+            Ast::PrintStmt(value) => {
+                self.node(value)?;
+                self.emit(Op::Print);
+            }
+
+            // If and grind bodies share the surrounding scope
+            Ast::If { condition, then_branch, else_branch } => {
+                let skip = self.condition(condition)?;
+                self.block_value(then_branch)?;
+                let done = self.emit(Op::Jump(0));
+                let otherwise = self.here();
+                self.patch(skip, otherwise);
+                match else_branch {
+                    Some(stmts) => self.block_value(stmts)?,
+                    None => {
+                        self.emit(Op::Null);
+                    }
+                }
+                let end = self.here();
+                self.patch(done, end);
+            }
+
+            Ast::Ternary { condition, then_branch, else_branch } => {
+                let skip = self.condition(condition)?;
+                self.node(then_branch)?;
+                let done = self.emit(Op::Jump(0));
+                let otherwise = self.here();
+                self.patch(skip, otherwise);
+                self.node(else_branch)?;
+                let end = self.here();
+                self.patch(done, end);
+            }
+
+            Ast::While { condition, body } => {
+                self.emit(Op::Loop);
+                let start = self.here();
+                let exhausted = self.condition(condition)?;
+                let depth = self.current().scopes.len();
+                self.begin_loop(depth);
+                self.statements(body)?;
+                self.emit(Op::Jump(start));
+                let exit = self.here();
+                self.patch(exhausted, exit);
+                self.end_loop(start, exit);
+            }
+
+            Ast::ForEach { key, variable, iterable, body } => {
+                self.node(iterable)?;
+                self.emit(Op::IterPairs(key.is_some()));
+                self.farm_body(key.as_deref(), variable, body)?;
+            }
+
+            Ast::ForRange { variable, start, end, step, body } => {
+                self.node(start)?;
+                self.node(end)?;
+                if let Some(step) = step {
+                    self.node(step)?;
+                }
+                self.emit(Op::IterRange(step.is_some()));
+                self.farm_body(None, variable, body)?;
+            }
+
+            Ast::Break | Ast::Continue => {
+                let is_break = matches!(ast, Ast::Break);
+                let depth = match self.current().loops.last() {
+                    Some(context) if is_break => context.depth,
+                    Some(context) => context.body,
+                    // Outside any loop a crash or skip just stops the contract
+                    None => {
+                        self.emit(Op::Null);
+                        self.emit(Op::Return);
+                        return Ok(());
+                    }
+                };
+                self.close_scopes(depth);
+                let at = self.emit(Op::Unwind(0));
+                let context = self.current().loops.last_mut().expect("checked above");
+                if is_break {
+                    context.breaks.push(at);
+                } else {
+                    context.continues.push(at);
+                }
+            }
+
+            Ast::Block(stmts) => {
+                self.begin_scope(stmts);
+                self.block_value(stmts)?;
+                self.end_scope();
+            }
+
+            Ast::Match { scrutinee, arms } => {
+                self.node(scrutinee)?;
+                let mut done = Vec::new();
+                for (pattern, body) in arms {
+                    self.begin_scope(std::slice::from_ref(body));
+                    let mut names = Vec::new();
+                    pattern.bindings(&mut names);
+                    let mut bindings = Vec::new();
+                    for name in names {
+                        if let Var::Local(slot) = self.declare(&name)? {
+                            bindings.push((name, slot));
+                        }
+                    }
+                    self.program.patterns.push(MatchArm { pattern: pattern.clone(), bindings });
+                    let index = self.program.patterns.len() as u32 - 1;
+                    let miss = self.emit(Op::Match(index, 0));
+                    self.node(body)?;
+                    self.end_scope();
+                    self.emit(Op::Nip);
+                    done.push(self.emit(Op::Jump(0)));
+                    let next = self.here();
+                    self.patch(miss, next);
+                }
+                self.emit(Op::NoMatch);
+                let end = self.here();
+                for at in done {
+                    self.patch(at, end);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Walks an assignment target down to its root token, collecting the steps.
+fn target_steps<'a>(ast: &'a Ast, steps: &mut Vec<Step<'a>>) -> Option<&'a str> {
+    match ast {
+        Ast::Variable(name) => Some(name),
+        Ast::Grouping(inner) => target_steps(inner, steps),
+        Ast::Index { object, index } => {
+            let root = target_steps(object, steps)?;
+            steps.push(Step::Key(index));
+            Some(root)
+        }
+        Ast::Field { object, name } => {
+            let root = target_steps(object, steps)?;
+            steps.push(Step::Field(name));
+            Some(root)
+        }
+        _ => None,
+    }
+}
+
+// Names declared directly in a scope. If and grind bodies don't open a
+// scope of their own, so what they declare counts too.
+fn declared_names(stmts: &[Ast], names: &mut HashSet<String>) {
+    for stmt in stmts {
+        match stmt.unlocated() {
+            Ast::VariableDecl { name, .. } | Ast::FunctionDecl { name, .. } => {
+                names.insert(name.clone());
+            }
+            Ast::If { then_branch, else_branch, .. } => {
+                declared_names(then_branch, names);
+                declared_names(else_branch.as_deref().unwrap_or_default(), names);
+            }
+            Ast::While { body, .. } => declared_names(body, names),
+            _ => {}
+        }
+    }
+}
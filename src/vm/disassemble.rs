@@ -0,0 +1,96 @@
+use std::fmt::Write;
+use crate::shared_types::Value;
+use super::chunk::{Function, Op, Path, Program, Var};
+
+// A listing of every contract in a program, one op per line:
+//
+//   == contract 'add' (#1, 2 params, 2 slots, 0 upvalues) ==
+//    line  addr  op
+//       2  0000  Get             local a
+//       |  0001  Get             local b
+//
+// The line column comes from the debug line table and only changes when
+// the source line does.
+pub fn disassemble(program: &Program) -> String {
+    let mut out = String::new();
+    for (index, function) in program.functions.iter().enumerate() {
+        if index > 0 {
+            out.push('\n');
+        }
+        let title = if index == 0 { "top level".to_string() } else { format!("contract '{}'", function.name) };
+        let _ = writeln!(
+            out,
+            "== {} (#{}, {} params, {} slots, {} upvalues) ==",
+            title,
+            index,
+            function.params.len(),
+            function.slot_names.len(),
+            function.upvalues.len()
+        );
+        let _ = writeln!(out, " line  addr  op");
+        let mut last_line = None;
+        for (at, op) in function.code.iter().enumerate() {
+            let line = function.line_at(at);
+            let column = match line {
+                Some(line) if last_line != Some(line) => format!("{:>5}", line),
+                Some(_) => "    |".to_string(),
+                None => "     ".to_string(),
+            };
+            last_line = line;
+            let name = format!("{:?}", op);
+            let name = name.split('(').next().unwrap_or_default();
+            let operands = operands(program, function, op);
+            let _ = writeln!(out, "{}  {:04}  {:<15} {}", column, at, name, operands);
+        }
+    }
+    // Trailing spaces from ops without operands
+    out.lines().map(|line| line.trim_end()).collect::<Vec<_>>().join("\n") + "\n"
+}
+
+fn operands(program: &Program, function: &Function, op: &Op) -> String {
+    let constant = |index: u32| match &program.constants[index as usize] {
+        Value::Text(s) => format!("{:?}", s),
+        other => other.render(),
+    };
+    let var = |var: Var| match var {
+        Var::Local(slot) => format!("local {}", function.slot_names[slot as usize]),
+        Var::Upvalue(index) => format!("upvalue {}", function.upvalue_names[index as usize]),
+        Var::Global(index) => format!("global {}", program.globals[index as usize]),
+    };
+    let path = |var_text: String, path: Path| {
+        let steps: String = (0..path.len).map(|i| if path.fields & (1 << i) != 0 { ".field" } else { "[key]" }).collect();
+        format!("{}{}", var_text, steps)
+    };
+    match *op {
+        Op::Constant(index) => format!("#{:<4} {}", index, constant(index)),
+        Op::Get(v) | Op::Set(v) | Op::Define(v) | Op::DefineBlock(v) => var(v),
+        Op::Binary(operator) | Op::Unary(operator) => operator.symbol().to_string(),
+        Op::Concat(n) => format!("{} parts", n),
+        Op::Array(n) => format!("{} items", n),
+        Op::Map(n) => format!("{} entries", n),
+        Op::Struct(name, n) => format!("{} with {} fields", program.constants[name as usize].render(), n),
+        Op::DeclareStruct(index) => program.structs[index as usize].0.clone(),
+        Op::Field(name) => program.constants[name as usize].render(),
+        Op::SetPath(v, p) | Op::SetField(v, p) | Op::DeletePath(v, p) => path(var(v), p),
+        Op::CompoundPath(v, p, operator) => format!("{} {}=", path(var(v), p), operator.symbol()),
+        Op::Jump(target) | Op::JumpIfFalse(target) | Op::Unwind(target) => format!("-> {:04}", target),
+        Op::Next(exit) => format!("done -> {:04}", exit),
+        Op::Closure(index) => format!("'{}' (#{})", program.functions[index as usize].name, index),
+        Op::Call(argc) => format!("{} args", argc),
+        Op::CallVar(v, argc) => format!("{} with {} args", var(v), argc),
+        Op::IterPairs(keyed) => if keyed { "key and value" } else { "value" }.to_string(),
+        Op::IterRange(stepped) => if stepped { "start, end, step" } else { "start, end" }.to_string(),
+        Op::Match(index, miss) => format!("{:?} else -> {:04}", program.patterns[index as usize].pattern, miss),
+        Op::EndScope(index) => {
+            let names: Vec<&str> = function.scopes[index as usize]
+                .iter()
+                .map(|slot| function.slot_names[*slot as usize].as_str())
+                .collect();
+            names.join(", ")
+        }
+        Op::Fail(message) => constant(message),
+        Op::Null | Op::Pop | Op::Nip | Op::Index | Op::Return | Op::Print | Op::Loop | Op::EndLoop | Op::NoMatch => {
+            String::new()
+        }
+    }
+}
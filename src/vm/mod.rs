@@ -0,0 +1,695 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+use crate::error::Rekt;
+use crate::interpreter::collections::{farm_pairs, Slot};
+use crate::interpreter::{broadcast, check_field_type, expect_field, finish_struct, init_field, Structs};
+use crate::parser::ast::Ast;
+use crate::shared_types::Value;
+use gm_runtime::{bind, binary, bound, delete, read, set, unary, Key, NativeContracts};
+use chunk::{Capture, Local, Op, Path, Upvalue, Var};
+
+mod artifact;
+mod chunk;
+mod compiler;
+mod disassemble;
+
+pub use artifact::{decode, encode, header};
+use artifact::corrupt;
+pub use chunk::{Closure, Program};
+pub use compiler::compile;
+pub use disassemble::disassemble;
+
+// Deep enough for any sane recursion, shallow enough to fail before memory does.
+const MAX_FRAMES: usize = 10_000;
+
+// One running contract call.
+struct Frame {
+    function: usize,
+    closure: Option<Rc<Closure>>, // None for the top level
+    ip: usize,
+    base: usize,   // where its slots start
+    height: usize, // operand stack height to return to
+    loops: usize,  // loops already running when the call started
+}
+
+struct LoopState {
+    height: usize, // operand stack height when the loop started
+    iter: Iter,
+}
+
+enum Iter {
+    Grind,
+    Pairs(std::vec::IntoIter<(Value, Value)>, bool),
+    Range { current: f64, end: f64, step: f64 },
+}
+
+// Why a write to a token was turned down.
+enum Denied {
+    Block,
+    Missing,
+}
+
+// A stack machine for compiled programs. It keeps every behaviour of the
+// tree walker (same values, same errors, same traces), it just doesn't
+// re-walk and clone the tree on every call.
+pub struct Vm {
+    program: Rc<Program>,
+    globals: Vec<Local>,
+    structs: Structs,
+    stack: Vec<Value>,
+    slots: Vec<Local>,
+    frames: Vec<Frame>,
+    loops: Vec<LoopState>,
+    open: Vec<(usize, Rc<RefCell<Upvalue>>)>, // captured slots still in scope
+    events: Option<Vec<String>>,
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Vm::new()
+    }
+}
+
+impl Vm {
+    pub fn new() -> Self {
+        Vm {
+            program: Rc::new(Program {
+                constants: Vec::new(),
+                globals: Vec::new(),
+                functions: Vec::new(),
+                structs: Vec::new(),
+                patterns: Vec::new(),
+            }),
+            globals: Vec::new(),
+            structs: HashMap::new(),
+            stack: Vec::new(),
+            slots: Vec::new(),
+            frames: Vec::new(),
+            loops: Vec::new(),
+            open: Vec::new(),
+            events: None,
+        }
+    }
+
+    pub fn record_events(&mut self) {
+        self.events = Some(Vec::new());
+    }
+
+    pub fn take_events(&mut self) -> Vec<String> {
+        self.events.take().unwrap_or_default()
+    }
+
+    pub fn interpret(&mut self, ast: &Ast) -> Result<Value, Rekt> {
+        let program = compile(ast)?;
+        self.run(program)
+    }
+
+    pub fn run(&mut self, program: Program) -> Result<Value, Rekt> {
+        self.globals.resize(program.globals.len(), Local::default());
+        let base = self.slots.len();
+        self.slots.resize(base + program.functions[0].slot_names.len(), Local::default());
+        self.program = Rc::new(program);
+        self.frames.push(Frame {
+            function: 0,
+            closure: None,
+            ip: 0,
+            base,
+            height: self.stack.len(),
+            loops: self.loops.len(),
+        });
+        self.execute(0)
+    }
+
+    // Runs until the frame count drops back to `depth`, then hands back the
+    // value the last frame returned. On an error every frame above `depth`
+    // is unwound, adding its source line and contract to the trace.
+    fn execute(&mut self, depth: usize) -> Result<Value, Rekt> {
+        match self.dispatch(depth) {
+            Ok(value) => Ok(value),
+            Err(mut error) => {
+                while self.frames.len() > depth {
+                    let frame = self.leave();
+                    if let Some(line) = self.program.functions[frame.function].line_at(frame.ip - 1) {
+                        error = error.at_line(line as usize);
+                    }
+                    if let Some(closure) = frame.closure {
+                        error = error.traced(&format!("contract '{}'", closure.name));
+                    }
+                }
+                Err(error)
+            }
+        }
+    }
+
+    // Pops the running frame and everything it had going.
+    fn leave(&mut self) -> Frame {
+        let frame = self.frames.pop().expect("leaving a running frame");
+        self.close_upvalues(frame.base);
+        self.slots.truncate(frame.base);
+        self.stack.truncate(frame.height);
+        self.loops.truncate(frame.loops);
+        frame
+    }
+
+    fn dispatch(&mut self, depth: usize) -> Result<Value, Rekt> {
+        let program = Rc::clone(&self.program);
+        loop {
+            let frame = self.frames.last_mut().expect("running a frame");
+            let (function, base) = (frame.function, frame.base);
+            let op = program.functions[function].code[frame.ip];
+            frame.ip += 1;
+
+            match op {
+                Op::Constant(index) => self.stack.push(program.constants[index as usize].clone()),
+                Op::Null => self.stack.push(Value::Null),
+                Op::Pop => {
+                    self.pop()?;
+                }
+                Op::Nip => {
+                    let top = self.pop()?;
+                    self.pop()?;
+                    self.stack.push(top);
+                }
+                Op::Get(var) => {
+                    let value = self.read(var)?;
+                    self.stack.push(value);
+                }
+                Op::Set(var) => {
+                    let value = self.peek()?.clone();
+                    self.update(var, |slot| {
+                        *slot = value;
+                        Ok(())
+                    })?;
+                }
+                Op::Define(var) | Op::DefineBlock(var) => {
+                    let value = self.peek()?.clone();
+                    let block = matches!(op, Op::DefineBlock(_));
+                    self.with_local(var, |local| *local = Local { value: Some(value), block });
+                }
+                Op::Binary(operator) => {
+                    let r = self.pop()?;
+                    let l = self.pop()?;
+                    self.stack.push(binary(l, operator.symbol(), r)?);
+                }
+                Op::Unary(operator) => {
+                    let value = self.pop()?;
+                    self.stack.push(unary(operator.symbol(), value)?);
+                }
+                Op::Concat(count) => {
+                    let parts = self.take(count as usize)?;
+                    let text: String = parts.iter().map(|part| part.render()).collect();
+                    self.stack.push(Value::Text(text));
+                }
+                Op::Array(count) => {
+                    let items = self.take(count as usize)?;
+                    self.stack.push(Value::Array(items));
+                }
+                Op::Map(count) => {
+                    let mut entries = self.take(2 * count as usize)?.into_iter();
+                    let mut map = BTreeMap::new();
+                    while let (Some(key), Some(value)) = (entries.next(), entries.next()) {
+                        map.insert(Key::from_value(&key)?, value);
+                    }
+                    self.stack.push(Value::Map(map));
+                }
+                Op::Struct(name, count) => {
+                    let name = program.constants[name as usize].render();
+                    let mut inits = self.take(2 * count as usize)?.into_iter();
+                    let mut given = HashMap::new();
+                    while let (Some(field), Some(value)) = (inits.next(), inits.next()) {
+                        let field = field.render();
+                        expect_field(&self.structs, &name, &field)?;
+                        init_field(&self.structs, &name, &mut given, field, value)?;
+                    }
+                    self.stack.push(finish_struct(&self.structs, name, given)?);
+                }
+                Op::DeclareStruct(index) => {
+                    let (name, fields) = program.structs[index as usize].clone();
+                    self.structs.insert(name, fields);
+                    self.stack.push(Value::Null);
+                }
+                Op::Index => {
+                    let index = self.pop()?;
+                    let object = self.pop()?;
+                    self.stack.push(gm_runtime::index(object, index)?);
+                }
+                Op::Field(name) => {
+                    let object = self.pop()?;
+                    let field = program.constants[name as usize].render();
+                    self.stack.push(gm_runtime::field(object, &field)?);
+                }
+                Op::SetPath(var, path) => {
+                    let value = self.pop()?;
+                    let steps = self.steps(path)?;
+                    let stored = value.clone();
+                    self.update(var, |root| set(root, &steps, stored))?;
+                    self.stack.push(value);
+                }
+                Op::SetField(var, path) => {
+                    let value = self.pop()?;
+                    let steps = self.steps(path)?;
+                    let (field, record) = steps.split_last().ok_or_else(|| corrupt("a field path has no steps"))?;
+                    if let Value::Struct { name, .. } = self.read_path(var, record)? {
+                        if let Slot::Field(field) = field {
+                            check_field_type(&self.structs, &name, field, &value)?;
+                        }
+                    }
+                    let stored = value.clone();
+                    self.update(var, |root| set(root, &steps, stored))?;
+                    self.stack.push(value);
+                }
+                Op::CompoundPath(var, path, operator) => {
+                    let operand = self.pop()?;
+                    let steps = self.steps(path)?;
+                    let current = self.read_path(var, &steps)?;
+                    let value = binary(current, operator.symbol(), operand)?;
+                    let stored = value.clone();
+                    self.update(var, |root| set(root, &steps, stored))?;
+                    self.stack.push(value);
+                }
+                Op::DeletePath(var, path) => {
+                    let steps = self.steps(path)?;
+                    self.update(var, |root| delete(root, &steps))?;
+                    self.stack.push(Value::Null);
+                }
+                Op::Jump(target) => self.jump(target),
+                Op::JumpIfFalse(target) => match self.pop()? {
+                    Value::Signal(true) => {}
+                    Value::Signal(false) => self.jump(target),
+                    _ => {
+                        return Err(Rekt::Lexer("Condition must be bullish or bearish (yes/no)".to_string()))
+                    }
+                },
+                Op::Closure(index) => {
+                    let prototype = &program.functions[index as usize];
+                    let mut upvalues = Vec::new();
+                    for capture in &prototype.upvalues {
+                        upvalues.push(match *capture {
+                            Capture::Local(slot) => self.capture(base + slot as usize),
+                            Capture::Upvalue(index) => self.upvalue(index),
+                        });
+                    }
+                    let closure = Closure { function: index, name: prototype.name.clone(), upvalues };
+                    self.stack.push(Value::Closure(Rc::new(closure)));
+                }
+                Op::Call(argc) => {
+                    let at = self.stack.len().checked_sub(argc as usize + 1).ok_or_else(unbalanced)?;
+                    let callee = self.stack.remove(at);
+                    self.call(callee, argc as usize)?;
+                }
+                Op::CallVar(var, argc) => match self.with_local(var, |local| local.value.clone()) {
+                    Some(callee) => self.call(callee, argc as usize)?,
+                    // Names that aren't in any wallet may still be native contracts
+                    None => {
+                        let args = self.take(argc as usize)?;
+                        let name = var_name(&program, function, var);
+                        let result = self.call_builtin(name, args).unwrap_or_else(|| {
+                            Err(Rekt::Lexer(format!("Unknown smart contract '{}'", name)))
+                        })?;
+                        self.stack.push(result);
+                    }
+                },
+                Op::Return => {
+                    let value = self.pop()?;
+                    self.leave();
+                    if self.frames.len() == depth {
+                        return Ok(value);
+                    }
+                    self.stack.push(value);
+                }
+                Op::Print => {
+                    let value = self.pop()?;
+                    broadcast(&mut self.events, &value);
+                    self.stack.push(Value::Null);
+                }
+                Op::Loop => self.loops.push(LoopState { height: self.stack.len(), iter: Iter::Grind }),
+                Op::IterPairs(keyed) => {
+                    let iterable = self.pop()?;
+                    let pairs = farm_pairs(iterable, keyed)?;
+                    self.loops.push(LoopState {
+                        height: self.stack.len(),
+                        iter: Iter::Pairs(pairs.into_iter(), keyed),
+                    });
+                }
+                Op::IterRange(stepped) => {
+                    let step = if stepped { Some(self.pop()?) } else { None };
+                    let end = self.pop()?;
+                    let start = self.pop()?;
+                    let current = bound(&start, "start")?;
+                    let end = bound(&end, "end")?;
+                    let step = match step {
+                        Some(step) => bound(&step, "step")?,
+                        None => 1.0,
+                    };
+                    if step == 0.0 {
+                        return Err(Rekt::Runtime("Range step can't be zero".to_string()));
+                    }
+                    self.loops.push(LoopState {
+                        height: self.stack.len(),
+                        iter: Iter::Range { current, end, step },
+                    });
+                }
+                Op::Next(exit) => {
+                    let state = self.innermost_loop()?;
+                    let next = match &mut state.iter {
+                        Iter::Pairs(pairs, keyed) => pairs.next().map(|(key, value)| (value, keyed.then_some(key))),
+                        Iter::Range { current, end, step } => {
+                            if (*step > 0.0 && *current < *end) || (*step < 0.0 && *current > *end) {
+                                let value = Value::Token(current.to_string());
+                                *current += *step;
+                                Some((value, None))
+                            } else {
+                                None
+                            }
+                        }
+                        Iter::Grind => None,
+                    };
+                    match next {
+                        Some((value, key)) => {
+                            self.stack.push(value);
+                            self.stack.extend(key);
+                        }
+                        None => self.jump(exit),
+                    }
+                }
+                Op::EndLoop => {
+                    self.innermost_loop()?;
+                    let state = self.loops.pop().expect("checked just above");
+                    self.stack.truncate(state.height);
+                }
+                Op::Unwind(target) => {
+                    let height = self.innermost_loop()?.height;
+                    self.stack.truncate(height);
+                    self.jump(target);
+                }
+                Op::Match(index, miss) => {
+                    let arm = &program.patterns[index as usize];
+                    let mut bindings = Vec::new();
+                    if bind(&arm.pattern, self.peek()?, &mut bindings) {
+                        for (name, value) in bindings {
+                            if let Some((_, slot)) = arm.bindings.iter().find(|(n, _)| *n == name) {
+                                self.slots[base + *slot as usize] = Local { value: Some(value), block: false };
+                            }
+                        }
+                    } else {
+                        self.jump(miss);
+                    }
+                }
+                Op::NoMatch => {
+                    let value = self.pop()?;
+                    return Err(Rekt::Runtime(format!("No vibecheck arm matched {}", value.render())));
+                }
+                Op::EndScope(scope) => {
+                    for slot in &program.functions[function].scopes[scope as usize] {
+                        let at = base + *slot as usize;
+                        self.close_upvalue(at);
+                        self.slots[at] = Local::default();
+                    }
+                }
+                Op::Fail(message) => {
+                    return Err(Rekt::Runtime(program.constants[message as usize].render()));
+                }
+            }
+        }
+    }
+
+    // The compiler keeps the stack balanced, so running short only happens
+    // when a .gmc file was put together by hand.
+    fn pop(&mut self) -> Result<Value, Rekt> {
+        self.stack.pop().ok_or_else(unbalanced)
+    }
+
+    fn peek(&self) -> Result<&Value, Rekt> {
+        self.stack.last().ok_or_else(unbalanced)
+    }
+
+    // The top `count` values, oldest first.
+    fn take(&mut self, count: usize) -> Result<Vec<Value>, Rekt> {
+        let at = self.stack.len().checked_sub(count).ok_or_else(unbalanced)?;
+        Ok(self.stack.split_off(at))
+    }
+
+    // The loop the running frame is in; loops of the frames below don't count.
+    fn innermost_loop(&mut self) -> Result<&mut LoopState, Rekt> {
+        let floor = self.frames.last().expect("running a frame").loops;
+        if self.loops.len() <= floor {
+            return Err(corrupt("a loop instruction runs outside any loop"));
+        }
+        Ok(self.loops.last_mut().expect("checked just above"))
+    }
+
+    fn jump(&mut self, target: u32) {
+        self.frames.last_mut().expect("running a frame").ip = target as usize;
+    }
+
+    // Pops the steps of an assignment path.
+    fn steps(&mut self, path: Path) -> Result<Vec<Slot>, Rekt> {
+        Ok(self
+            .take(path.len as usize)?
+            .into_iter()
+            .enumerate()
+            .map(|(i, step)| {
+                if path.fields & (1 << i) != 0 {
+                    Slot::Field(step.render())
+                } else {
+                    Slot::Key(step)
+                }
+            })
+            .collect())
+    }
+
+    fn upvalue(&self, index: u16) -> Rc<RefCell<Upvalue>> {
+        let frame = self.frames.last().expect("running a frame");
+        let closure = frame.closure.as_ref().expect("only contracts have upvalues");
+        Rc::clone(&closure.upvalues[index as usize])
+    }
+
+    fn with_local<R>(&mut self, var: Var, f: impl FnOnce(&mut Local) -> R) -> R {
+        match var {
+            Var::Local(slot) => {
+                let at = self.frames.last().expect("running a frame").base + slot as usize;
+                f(&mut self.slots[at])
+            }
+            Var::Global(index) => f(&mut self.globals[index as usize]),
+            Var::Upvalue(index) => {
+                let cell = self.upvalue(index);
+                let open = match &*cell.borrow() {
+                    Upvalue::Open(at) => Some(*at),
+                    Upvalue::Closed(_) => None,
+                };
+                match open {
+                    Some(at) => f(&mut self.slots[at]),
+                    None => match &mut *cell.borrow_mut() {
+                        Upvalue::Closed(local) => f(local),
+                        Upvalue::Open(_) => unreachable!("checked above"),
+                    },
+                }
+            }
+        }
+    }
+
+    fn read(&mut self, var: Var) -> Result<Value, Rekt> {
+        match self.with_local(var, |local| local.value.clone()) {
+            Some(value) => Ok(value),
+            None => Err(Rekt::Lexer(format!("Token '{}' not found.", self.name(var)))),
+        }
+    }
+
+    // Reads down a path from a token without copying the whole token first.
+    fn read_path(&mut self, var: Var, steps: &[Slot]) -> Result<Value, Rekt> {
+        match self.with_local(var, |local| local.value.as_ref().map(|root| read(root, steps))) {
+            Some(result) => result,
+            None => Err(Rekt::Lexer(format!("Token '{}' not found.", self.name(var)))),
+        }
+    }
+
+    // Runs `f` on a token's value, refusing blocks and unminted names just
+    // like a wallet transfer does.
+    fn update<R>(&mut self, var: Var, f: impl FnOnce(&mut Value) -> Result<R, Rekt>) -> Result<R, Rekt> {
+        let outcome = self.with_local(var, |local| {
+            if local.block {
+                return Err(Denied::Block);
+            }
+            local.value.as_mut().map(f).ok_or(Denied::Missing)
+        });
+        match outcome {
+            Ok(result) => result,
+            Err(Denied::Block) => Err(Rekt::Runtime(format!(
+                "'{}' is a block constant and can't be changed.",
+                self.name(var)
+            ))),
+            Err(Denied::Missing) => Err(Rekt::Runtime(format!("Wallet address '{}' not found.", self.name(var)))),
+        }
+    }
+
+    fn name(&self, var: Var) -> String {
+        let function = self.frames.last().expect("running a frame").function;
+        var_name(&self.program, function, var).to_string()
+    }
+
+    fn capture(&mut self, at: usize) -> Rc<RefCell<Upvalue>> {
+        if let Some((_, cell)) = self.open.iter().find(|(slot, _)| *slot == at) {
+            return Rc::clone(cell);
+        }
+        let cell = Rc::new(RefCell::new(Upvalue::Open(at)));
+        self.open.push((at, Rc::clone(&cell)));
+        cell
+    }
+
+    // Moves a slot's value into the closures holding it, if any are.
+    fn close_upvalue(&mut self, at: usize) {
+        if let Some(position) = self.open.iter().position(|(slot, _)| *slot == at) {
+            let (_, cell) = self.open.swap_remove(position);
+            *cell.borrow_mut() = Upvalue::Closed(self.slots[at].clone());
+        }
+    }
+
+    fn close_upvalues(&mut self, from: usize) {
+        let (closing, open): (Vec<_>, Vec<_>) = self.open.drain(..).partition(|(slot, _)| *slot >= from);
+        self.open = open;
+        for (at, cell) in closing {
+            *cell.borrow_mut() = Upvalue::Closed(self.slots[at].clone());
+        }
+    }
+
+    // Starts a call to `callee` with the top `argc` values as its arguments.
+    fn call(&mut self, callee: Value, argc: usize) -> Result<(), Rekt> {
+        let closure = match callee {
+            Value::Closure(closure) => closure,
+            other => return Err(Rekt::Lexer(format!("{} is not a contract", other))),
+        };
+        let program = Rc::clone(&self.program);
+        let function = &program.functions[closure.function as usize];
+        if function.params.len() != argc {
+            return Err(Rekt::Lexer(format!("Expected {} args, got {}.", function.params.len(), argc)));
+        }
+        if self.frames.len() >= MAX_FRAMES {
+            return Err(Rekt::Runtime(format!(
+                "Contract '{}' went more than {} calls deep",
+                closure.name, MAX_FRAMES
+            )));
+        }
+
+        let base = self.slots.len();
+        self.slots.resize(base + function.slot_names.len(), Local::default());
+        let height = self.stack.len().checked_sub(argc).ok_or_else(unbalanced)?;
+        for (param, arg) in function.params.iter().zip(self.stack.drain(height..)) {
+            self.slots[base + *param as usize] = Local { value: Some(arg), block: false };
+        }
+        self.frames.push(Frame {
+            function: closure.function as usize,
+            closure: Some(closure),
+            ip: 0,
+            base,
+            height,
+            loops: self.loops.len(),
+        });
+        Ok(())
+    }
+}
+
+fn unbalanced() -> Rekt {
+    corrupt("the code takes more values off the stack than it put on")
+}
+
+fn var_name(program: &Program, function: usize, var: Var) -> &str {
+    match var {
+        Var::Local(slot) => &program.functions[function].slot_names[slot as usize],
+        Var::Upvalue(index) => &program.functions[function].upvalue_names[index as usize],
+        Var::Global(index) => &program.globals[index as usize],
+    }
+}
+
+impl NativeContracts<Value> for Vm {
+    fn invoke(&mut self, contract: Value, args: Vec<Value>) -> Result<Value, Rekt> {
+        let depth = self.frames.len();
+        let argc = args.len();
+        self.stack.extend(args);
+        if let Err(error) = self.call(contract, argc) {
+            self.stack.truncate(self.stack.len() - argc);
+            return Err(error);
+        }
+        self.execute(depth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+    use crate::shared_types::BinaryOp;
+
+    fn program(source: &str) -> Program {
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        compile(&Parser::new(tokens).parse().unwrap()).unwrap()
+    }
+
+    // Swaps one op of the top level, then writes and reads the file back so
+    // the checksum is right and only the VM can notice.
+    fn tampered(source: &str, at: impl Fn(&Op) -> bool, with: Op) -> Result<Value, Rekt> {
+        let mut program = program(source);
+        let code = &mut program.functions[0].code;
+        let index = code.iter().position(at).expect("op to tamper with");
+        code[index] = with;
+        let program = decode(&encode(&program)?)?;
+        Vm::new().run(program)
+    }
+
+    fn assert_corrupt(result: Result<Value, Rekt>) {
+        match result {
+            Err(Rekt::Runtime(message)) => assert!(message.starts_with("Corrupt .gmc file"), "{}", message),
+            other => panic!("expected a corrupt file error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn round_trip_runs() {
+        let program = program("token x = 2; farm (i in 0..3) { x = x stake i; } ping x;");
+        let decoded = decode(&encode(&program).unwrap()).unwrap();
+        assert_eq!(decoded, program);
+        let mut vm = Vm::new();
+        vm.record_events();
+        vm.run(decoded).unwrap();
+        assert_eq!(vm.take_events(), vec!["5"]);
+    }
+
+    #[test]
+    fn unbalanced_stack_is_an_error() {
+        let source = "token x = 1; ping x;";
+        assert_corrupt(tampered(source, |op| matches!(op, Op::Constant(_)), Op::Concat(5)));
+        assert_corrupt(tampered(source, |op| matches!(op, Op::Constant(_)), Op::Nip));
+        assert_corrupt(tampered(source, |op| matches!(op, Op::Constant(_)), Op::Call(3)));
+        assert_corrupt(tampered(source, |op| matches!(op, Op::Constant(_)), Op::Binary(BinaryOp::Add)));
+    }
+
+    #[test]
+    fn loop_ops_outside_a_loop_are_an_error() {
+        let source = "token x = 1; ping x;";
+        assert_corrupt(tampered(source, |op| matches!(op, Op::Print), Op::EndLoop));
+        assert_corrupt(tampered(source, |op| matches!(op, Op::Print), Op::Next(0)));
+        assert_corrupt(tampered(source, |op| matches!(op, Op::Print), Op::Unwind(0)));
+    }
+
+    #[test]
+    fn loops_of_the_caller_are_out_of_reach() {
+        // The contract's own `skip` becomes an Unwind; inside the call it
+        // must not reach the grind loop of the top level.
+        let source = "mine f() { grind (true) { skip; } } grind (true) { f(); }";
+        let mut program = program(source);
+        let code = &mut program.functions[1].code;
+        let index = code.iter().position(|op| matches!(op, Op::Loop)).unwrap();
+        code[index] = Op::Null;
+        let program = decode(&encode(&program).unwrap()).unwrap();
+        assert_corrupt(Vm::new().run(program));
+    }
+
+    #[test]
+    fn stray_binding_slots_are_rejected_on_load() {
+        let mut program = program("vibecheck (1) { x => { ping x; } }");
+        program.patterns[0].bindings[0].1 = 500;
+        let bytes = encode(&program).unwrap();
+        assert_corrupt(decode(&bytes).map(|_| Value::Null));
+    }
+}
@@ -0,0 +1,1581 @@
+use std::collections::HashMap;
+use crate::error::Rekt;
+use gm_runtime::BUILTINS;
+use crate::parser::ast::{Ast, Pattern};
+use crate::shared_types::{BinaryOp, Type, Value};
+use crate::transpile::{declarations, ends_run, escapes, target_path, DeclKind, NOT_A_SIGNAL};
+use super::module::{Function, Global, Instr, Module, ValType};
+use super::runtime::{self, Callee, Helper, Linker, LEXER, RUNTIME, TYPE};
+
+// Lowers a resolved tree to a WebAssembly module. Top-level tokens become
+// globals, top-level `mine` contracts become functions exported under
+// their own names, and the rest of the script runs in `run`. Numbers are
+// f64, signals i32, and texts i32 addresses in linear memory (see
+// runtime.rs); broadcasts are handed to the host's `gm.broadcast`.
+//
+// A wasm local has one type for good, so the type of every token is worked
+// out from what it's minted with, and what each contract returns from its
+// returns and last statements. Knowing one can tell us another declared
+// further down, so the module is written again until nothing new is
+// learned, like the Solidity backend does.
+//
+// Errors GM would only hit at runtime are raised at runtime too: an
+// operator on the wrong type, a condition that isn't a signal, a call with
+// the wrong number of args or to a contract nobody mined all go through
+// `gm.rekt` with the message the tree walker gives, at the point the tree
+// walker would give it. What the module can't do at all (collections,
+// structs, uints and decimals, contracts as values) is a Type error up
+// front, with the line. Two differences are left, both about top-level
+// tokens existing as globals from the start: a contract that reads one
+// before the script mints it sees zero, and one that calls a contract
+// mined further down finds it.
+pub fn compile(ast: &Ast) -> Result<Module, Rekt> {
+    let stmts = match ast {
+        Ast::Program(stmts) => stmts.as_slice(),
+        other => std::slice::from_ref(other),
+    };
+    let mut known = Known::default();
+    for _ in 0..MAX_PASSES {
+        let mut gen = Gen::new(&known);
+        let module = gen.program(stmts);
+        if gen.learned == known {
+            return module;
+        }
+        known = gen.learned;
+    }
+    Gen::new(&known).program(stmts)
+}
+
+const MAX_PASSES: usize = 16;
+
+fn unsupported(what: &str) -> Rekt {
+    Rekt::Type(format!("{} can't be compiled to WebAssembly", what))
+}
+
+fn unknown(what: &str) -> Rekt {
+    Rekt::Type(format!("Can't tell the type of {}, which WebAssembly needs to know", what))
+}
+
+// The types GM values have in the module.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Wt {
+    Num,
+    Sig,
+    Text,
+    Null,    // what a contract that returns nothing gives back
+    Never,   // after an error, where nothing runs
+    Unknown, // not worked out yet in this pass
+}
+
+impl Wt {
+    fn from_type(t: &Type) -> Option<Wt> {
+        match t {
+            Type::Number | Type::Token => Some(Wt::Num),
+            Type::Text => Some(Wt::Text),
+            Type::Boolean | Type::Signal => Some(Wt::Sig),
+            _ => None,
+        }
+    }
+
+    fn val(self) -> Option<ValType> {
+        match self {
+            Wt::Num | Wt::Unknown => Some(ValType::F64),
+            Wt::Sig | Wt::Text => Some(ValType::I32),
+            Wt::Null | Wt::Never => None,
+        }
+    }
+
+    // What a local or global holding it is declared as
+    fn storage(self) -> ValType {
+        self.val().unwrap_or(ValType::I32)
+    }
+
+    // As GM names the type in its messages
+    fn name(self) -> &'static str {
+        match self {
+            Wt::Num => "vibe",
+            Wt::Sig => "signal",
+            Wt::Text => "text",
+            Wt::Null => "lonely",
+            Wt::Never | Wt::Unknown => "nothing",
+        }
+    }
+}
+
+// What one pass works out, for the next one to use.
+#[derive(Default, Clone, PartialEq)]
+struct Known {
+    types: HashMap<usize, Wt>,               // by binding id, the same in every pass
+    returns: HashMap<String, Option<Wt>>, // None for a contract that returns nothing
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Place {
+    Local(u32),
+    Global(u32),
+    Mine(usize),
+}
+
+#[derive(Clone)]
+struct Binding {
+    id: usize,
+    place: Place,
+    kind: Option<DeclKind>, // None for params, loop variables and match bindings
+    hoisted: bool,          // declared up front, so possibly not minted yet
+    live: bool,             // GM has minted it by this point of the script
+    flag: Option<Place>,    // for a hoisted token: whether it's been minted
+}
+
+struct Mine {
+    name: String,
+    params: Vec<(String, Wt)>,
+    function: Option<Function>,
+}
+
+// Where the last statement's value goes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Sink {
+    Discard,
+    Return, // it's what the contract returns
+}
+
+// How a vibecheck is used.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Use {
+    Statement(Sink),
+    Value,
+}
+
+enum Returned {
+    Value(Wt),
+    Nothing,
+    Unknown, // a value not worked out yet, which tells us nothing
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Label {
+    Plain,
+    Break,    // where a crash goes
+    Continue, // where a skip goes
+}
+
+// The function being written.
+#[derive(Default)]
+struct Code {
+    locals: Vec<(String, ValType)>,
+    body: Vec<Instr>,
+    labels: Vec<Label>,
+}
+
+struct Gen<'a> {
+    known: &'a Known,
+    learned: Known,
+    link: Linker,
+    globals: Vec<Global>,
+    mines: Vec<Mine>,
+    errors: Vec<Rekt>,
+    unknown: Option<String>, // the first thing whose type this pass didn't know
+
+    code: Code,
+    scopes: Vec<HashMap<String, Binding>>,
+    floor: usize,         // scopes below this belong to the top level, outside the contract
+    mine: Option<usize>,  // the contract being written, None for `run`
+    returned: Vec<Returned>,
+    loops: usize,
+    ids: usize,
+}
+
+impl<'a> Gen<'a> {
+    fn new(known: &'a Known) -> Self {
+        Gen {
+            known,
+            learned: known.clone(),
+            link: Linker::default(),
+            globals: Vec::new(),
+            mines: Vec::new(),
+            errors: Vec::new(),
+            unknown: None,
+            code: Code::default(),
+            scopes: Vec::new(),
+            floor: 0,
+            mine: None,
+            returned: Vec::new(),
+            loops: 0,
+            ids: 0,
+        }
+    }
+
+    fn program(&mut self, stmts: &[Ast]) -> Result<Module, Rekt> {
+        self.top_level(stmts)?;
+        for stmt in stmts {
+            let (line, unlocated) = match stmt {
+                Ast::Located { line, statement } => (Some(*line), statement.as_ref()),
+                other => (None, other),
+            };
+            let result = match unlocated {
+                Ast::FunctionDecl { name, body, .. } => self.function(name, body),
+                other => self.statement(other, Sink::Discard),
+            };
+            if let Err(e) = result {
+                self.errors.push(match line {
+                    Some(line) => e.at_line(line),
+                    None => e,
+                });
+            }
+        }
+        if !self.errors.is_empty() {
+            return Err(self.errors.remove(0));
+        }
+        if let Some(what) = &self.unknown {
+            return Err(unknown(what));
+        }
+        Ok(self.assemble())
+    }
+
+    // The top-level scope: a global for every token, and the signature of
+    // every contract, which can be called from any other.
+    fn top_level(&mut self, stmts: &[Ast]) -> Result<(), Rekt> {
+        let decls = declarations(&[], stmts);
+        for stmt in stmts {
+            let Ast::FunctionDecl { name, params, .. } = stmt.unlocated() else { continue };
+            let decl = decls.iter().find(|d| d.name == *name);
+            match decl.map(|d| (d.kind, d.hoisted)) {
+                Some((DeclKind::Contract(_), false)) => {}
+                Some((DeclKind::Contract(_), true)) => {
+                    return Err(unsupported(&format!("Contract '{}', mined more than once,", name)))
+                }
+                _ => return Err(unsupported(&format!("'{}', both a token and a contract,", name))),
+            }
+            if name == "run" {
+                return Err(unsupported("A contract named 'run', which is what the module runs the script as,"));
+            }
+            let mut typed = Vec::new();
+            for (param, t) in params {
+                let ty = Wt::from_type(t)
+                    .ok_or_else(|| unsupported(&format!("Param '{}' of '{}', of type {},", param, name, t)))?;
+                typed.push((param.clone(), ty));
+            }
+            self.mines.push(Mine { name: name.clone(), params: typed, function: None });
+        }
+
+        let mut scope = HashMap::new();
+        for decl in decls {
+            let id = self.next_id();
+            let binding = match decl.kind {
+                DeclKind::Contract(_) => match self.mines.iter().position(|m| m.name == decl.name) {
+                    Some(index) => Binding {
+                        id,
+                        place: Place::Mine(index),
+                        kind: Some(decl.kind),
+                        hoisted: false,
+                        live: false,
+                        flag: None,
+                    },
+                    None => {
+                        let what = format!("Contract '{}', mined inside a sus or grind body,", decl.name);
+                        return Err(unsupported(&what));
+                    }
+                },
+                kind => {
+                    let ty = self.declared_type(id, &decl.name);
+                    let place = self.global(&decl.name, ty.storage());
+                    let flag = decl.hoisted.then(|| self.global(&format!("{}.set", decl.name), ValType::I32));
+                    Binding { id, place, kind: Some(kind), hoisted: decl.hoisted, live: decl.hoisted, flag }
+                }
+            };
+            scope.insert(decl.name, binding);
+        }
+        self.scopes.push(scope);
+        Ok(())
+    }
+
+    fn global(&mut self, name: &str, ty: ValType) -> Place {
+        self.globals.push(Global { name: name.to_string(), ty, init: zero(ty) });
+        Place::Global(self.globals.len() as u32 - 1)
+    }
+
+    // A contract's function, written where the script mines it.
+    fn function(&mut self, name: &str, body: &[Ast]) -> Result<(), Rekt> {
+        let Some((_, binding)) = self.lookup(name) else { return Ok(()) };
+        let Place::Mine(index) = binding.place else { return Ok(()) };
+        self.minted(name);
+
+        let code = std::mem::take(&mut self.code);
+        let floor = std::mem::replace(&mut self.floor, self.scopes.len());
+        let mine = self.mine.replace(index);
+        let loops = std::mem::replace(&mut self.loops, 0);
+        self.returned.clear();
+        let params = self.mines[index].params.clone();
+        // Params are the first locals `enter` makes
+        let result = self.enter(&params, body).and_then(|()| {
+            let result = self.statements(body, Sink::Return);
+            self.leave();
+            result
+        });
+        let mut written = std::mem::replace(&mut self.code, code);
+        self.floor = floor;
+        self.mine = mine;
+        self.loops = loops;
+        result?;
+
+        // A contract that only returns what isn't known yet (its own
+        // result, say) hasn't been shown to return nothing
+        let returns = self.returns_of(name)?;
+        let guessing = self.returned.iter().all(|r| matches!(r, Returned::Unknown));
+        if returns.is_some() || !guessing || self.returned.is_empty() {
+            self.learned.returns.insert(name.to_string(), returns);
+        }
+        let result = self.result_of(index);
+        if result.is_some() {
+            // Every way out returns, which wasm can't see for itself
+            written.body.push(Instr::Unreachable);
+        }
+        let locals = written.locals.split_off(params.len());
+        self.mines[index].function = Some(Function {
+            name: name.to_string(),
+            export: Some(name.to_string()),
+            params: written.locals,
+            result,
+            locals,
+            body: written.body,
+        });
+        Ok(())
+    }
+
+    // What a contract returns, going by its returns and last statements.
+    fn returns_of(&self, name: &str) -> Result<Option<Wt>, Rekt> {
+        let mut found: Option<Wt> = None;
+        let mut nothing = false;
+        for returned in &self.returned {
+            match (returned, found) {
+                (Returned::Unknown, _) => {}
+                (Returned::Nothing, _) => nothing = true,
+                (Returned::Value(ty), None) => found = Some(*ty),
+                (Returned::Value(ty), Some(first)) if *ty != first => {
+                    return Err(unsupported(&format!(
+                        "Contract '{}', which returns both {} and {},",
+                        name,
+                        first.name(),
+                        ty.name()
+                    )));
+                }
+                _ => {}
+            }
+        }
+        match found {
+            Some(_) if nothing => Err(unsupported(&format!(
+                "Contract '{}', which returns a value in one place and nothing in another,",
+                name
+            ))),
+            found => Ok(found),
+        }
+    }
+
+    // The result type a contract's function is written with this pass.
+    fn result_of(&self, index: usize) -> Option<ValType> {
+        self.known.returns.get(&self.mines[index].name).copied().flatten().and_then(Wt::val)
+    }
+
+    fn assemble(&mut self) -> Module {
+        let mut functions: Vec<Function> = self.mines.iter_mut().filter_map(|m| m.function.take()).collect();
+        let code = std::mem::take(&mut self.code);
+        functions.push(Function {
+            name: "gm.run".to_string(),
+            export: Some("run".to_string()),
+            params: Vec::new(),
+            result: None,
+            locals: code.locals,
+            body: code.body,
+        });
+
+        // Helpers call other helpers, so keep going until none are new
+        let heap = self.globals.len() as u32;
+        let mut helpers: Vec<(Helper, Function)> = Vec::new();
+        loop {
+            let missing = self.link.callees.iter().find_map(|callee| match callee {
+                Callee::Helper(helper) if !helpers.iter().any(|(h, _)| h == helper) => Some(*helper),
+                _ => None,
+            });
+            let Some(helper) = missing else { break };
+            helpers.push((helper, runtime::helper(helper, &mut self.link, heap)));
+        }
+        helpers.sort_by_key(|(helper, _)| *helper);
+        let heap_start = self.link.data_end();
+        self.globals.push(Global {
+            name: "gm.heap".to_string(),
+            ty: ValType::I32,
+            init: Instr::I32Const(heap_start as i32),
+        });
+
+        // Number every callee: imports, then contracts, `run` and helpers
+        let imports = self.link.imports();
+        let mines = functions.len() - 1;
+        let index_of = |callee: &Callee| -> u32 {
+            let at = match callee {
+                Callee::Host(host) => imports.iter().position(|(h, _)| h == host).unwrap_or(0),
+                Callee::Mine(index) => imports.len() + index,
+                Callee::Helper(helper) => {
+                    imports.len() + mines + 1 + helpers.iter().position(|(h, _)| h == helper).unwrap_or(0)
+                }
+            };
+            at as u32
+        };
+        let numbers: Vec<u32> = self.link.callees.iter().map(index_of).collect();
+        functions.extend(helpers.into_iter().map(|(_, function)| function));
+        for function in &mut functions {
+            for instr in &mut function.body {
+                if let Instr::Call(placeholder) = instr {
+                    *instr = Instr::Call(numbers[*placeholder as usize]);
+                }
+            }
+        }
+
+        let pages = heap_start.div_ceil(65_536).max(1);
+        let data = match self.link.data.is_empty() {
+            true => Vec::new(),
+            false => vec![(runtime::DATA_START, std::mem::take(&mut self.link.data))],
+        };
+        Module {
+            imports: imports.into_iter().map(|(_, import)| import).collect(),
+            functions,
+            globals: std::mem::take(&mut self.globals),
+            pages,
+            data,
+        }
+    }
+
+    // ------------------------------------------------------------ scopes
+
+    fn next_id(&mut self) -> usize {
+        self.ids += 1;
+        self.ids
+    }
+
+    // The type a binding is declared with, as far as is known.
+    fn declared_type(&mut self, id: usize, name: &str) -> Wt {
+        match self.learned.types.get(&id) {
+            Some(ty) => *ty,
+            None => {
+                self.unknown.get_or_insert_with(|| format!("'{}'", name));
+                Wt::Unknown
+            }
+        }
+    }
+
+    fn local(&mut self, name: &str, ty: ValType) -> u32 {
+        let mut unique = name.to_string();
+        let mut n = 1;
+        while self.code.locals.iter().any(|(local, _)| *local == unique) {
+            unique = format!("{}.{}", name, n);
+            n += 1;
+        }
+        self.code.locals.push((unique, ty));
+        self.code.locals.len() as u32 - 1
+    }
+
+    fn temp(&mut self, what: &str, ty: ValType) -> u32 {
+        self.local(&format!("gm.{}", what), ty)
+    }
+
+    // Opens a scope inside a contract or `run` holding `bound`, then
+    // whatever `stmts` declare, each in a local of its own.
+    fn enter(&mut self, bound: &[(String, Wt)], stmts: &[Ast]) -> Result<(), Rekt> {
+        let mut scope = HashMap::new();
+        for (name, ty) in bound {
+            let id = self.next_id();
+            self.learned.types.insert(id, *ty);
+            let place = Place::Local(self.local(name, ty.storage()));
+            scope.insert(name.clone(), Binding { id, place, kind: None, hoisted: false, live: true, flag: None });
+        }
+        let names: Vec<String> = bound.iter().map(|(name, _)| name.clone()).collect();
+        for decl in declarations(&names, stmts) {
+            if names.contains(&decl.name) {
+                continue;
+            }
+            if let DeclKind::Contract(_) = decl.kind {
+                let what = format!("Contract '{}', mined inside a block or another contract,", decl.name);
+                return Err(unsupported(&what));
+            }
+            let id = self.next_id();
+            let ty = self.declared_type(id, &decl.name);
+            let place = Place::Local(self.local(&decl.name, ty.storage()));
+            let flag = decl.hoisted.then(|| Place::Local(self.local(&format!("{}.set", decl.name), ValType::I32)));
+            let binding = Binding { id, place, kind: Some(decl.kind), hoisted: decl.hoisted, live: decl.hoisted, flag };
+            scope.insert(decl.name, binding);
+        }
+        self.scopes.push(scope);
+        Ok(())
+    }
+
+    fn leave(&mut self) {
+        self.scopes.pop();
+    }
+
+    // The binding a name refers to at this point, searching the scopes
+    // below `upto`. Inside the function being written, a token whose
+    // declaration hasn't been reached yet isn't minted, so an outer one is
+    // found instead, like at runtime.
+    fn lookup_below(&self, name: &str, upto: usize) -> Option<(usize, Binding)> {
+        let mut unminted = None;
+        for (index, scope) in self.scopes[..upto].iter().enumerate().rev() {
+            if let Some(binding) = scope.get(name) {
+                if binding.live || index < self.floor {
+                    return Some((index, binding.clone()));
+                }
+                unminted.get_or_insert((index, binding.clone()));
+            }
+        }
+        unminted
+    }
+
+    fn lookup(&self, name: &str) -> Option<(usize, Binding)> {
+        self.lookup_below(name, self.scopes.len())
+    }
+
+    // Only a binding GM has minted by now, as the wallet would find it.
+    fn minted_binding(&self, name: &str) -> Option<(usize, Binding)> {
+        self.lookup(name).filter(|(index, binding)| binding.live || *index < self.floor)
+    }
+
+    fn minted(&mut self, name: &str) {
+        if let Some(binding) = self.scopes.last_mut().and_then(|scope| scope.get_mut(name)) {
+            binding.live = true;
+        }
+    }
+
+    fn type_of(&mut self, binding: &Binding, name: &str) -> Wt {
+        match self.learned.types.get(&binding.id) {
+            Some(ty) => *ty,
+            None => {
+                self.unknown.get_or_insert_with(|| format!("'{}'", name));
+                Wt::Unknown
+            }
+        }
+    }
+
+    // Records the type a token turns out to hold.
+    fn learn(&mut self, binding: &Binding, name: &str, ty: Wt) -> Result<(), Rekt> {
+        match ty {
+            Wt::Unknown => return Ok(()),
+            // Never holds anything, unless it's given something elsewhere
+            Wt::Never => {
+                self.learned.types.entry(binding.id).or_insert(Wt::Never);
+                return Ok(());
+            }
+            _ => {}
+        }
+        match self.learned.types.get(&binding.id) {
+            Some(held) if *held != ty && *held != Wt::Never => Err(unsupported(&format!(
+                "'{}', which holds {} in one place and {} in another,",
+                name,
+                held.name(),
+                ty.name()
+            ))),
+            _ => {
+                self.learned.types.insert(binding.id, ty);
+                Ok(())
+            }
+        }
+    }
+
+    // ------------------------------------------------------------ emitting
+
+    fn emit(&mut self, instr: Instr) {
+        self.code.body.push(instr);
+    }
+
+    fn open(&mut self, instr: Instr, label: Label) {
+        self.emit(instr);
+        self.code.labels.push(label);
+    }
+
+    fn close(&mut self) {
+        self.emit(Instr::End);
+        self.code.labels.pop();
+    }
+
+    // How many labels out the innermost one of this kind is.
+    fn depth(&self, label: Label) -> u32 {
+        self.code.labels.iter().rev().position(|l| *l == label).unwrap_or(0) as u32
+    }
+
+    fn call(&mut self, callee: Callee) {
+        let call = self.link.call(callee);
+        self.emit(call);
+    }
+
+    fn helper(&mut self, helper: Helper) {
+        self.call(Callee::Helper(helper));
+    }
+
+    fn text(&mut self, text: &str) {
+        let address = self.link.text(text);
+        self.emit(Instr::I32Const(address as i32));
+    }
+
+    fn get(&mut self, place: Place) {
+        match place {
+            Place::Local(index) => self.emit(Instr::LocalGet(index)),
+            Place::Global(index) => self.emit(Instr::GlobalGet(index)),
+            Place::Mine(_) => {}
+        }
+    }
+
+    fn set(&mut self, place: Place) {
+        match place {
+            Place::Local(index) => self.emit(Instr::LocalSet(index)),
+            Place::Global(index) => self.emit(Instr::GlobalSet(index)),
+            Place::Mine(_) => {}
+        }
+    }
+
+    fn drop_value(&mut self, ty: Wt) {
+        if ty.val().is_some() {
+            self.emit(Instr::Drop);
+        }
+    }
+
+    // Fails the way the tree walker would at this point.
+    fn fail(&mut self, kind: i32, message: &str) -> Result<Wt, Rekt> {
+        self.emit(Instr::I32Const(kind));
+        self.text(message);
+        self.helper(Helper::Fail);
+        self.emit(Instr::Unreachable);
+        Ok(Wt::Never)
+    }
+
+    // Fails with `prefix` and the value on top of the stack.
+    fn fail_with(&mut self, kind: i32, prefix: &str, ty: Wt) -> Result<Wt, Rekt> {
+        let value = self.temp("value", ty.storage());
+        if ty.val().is_some() {
+            self.emit(Instr::LocalSet(value));
+        }
+        self.emit(Instr::I32Const(kind));
+        self.text(prefix);
+        if ty.val().is_some() {
+            self.emit(Instr::LocalGet(value));
+        }
+        self.render(ty);
+        self.helper(Helper::Concat);
+        self.helper(Helper::Fail);
+        self.emit(Instr::Unreachable);
+        Ok(Wt::Never)
+    }
+
+    // Turns the value on top of the stack into the text GM renders it as.
+    fn render(&mut self, ty: Wt) {
+        match ty {
+            Wt::Num => self.helper(Helper::TextOfNumber),
+            Wt::Sig => self.helper(Helper::TextOfSignal),
+            Wt::Null => self.text("null"),
+            Wt::Unknown => {
+                self.emit(Instr::Drop);
+                self.emit(Instr::I32Const(0));
+            }
+            Wt::Text | Wt::Never => {}
+        }
+    }
+
+    // ------------------------------------------------------------ statements
+
+    // Only the last statement's value goes to `sink`.
+    fn statements(&mut self, stmts: &[Ast], sink: Sink) -> Result<(), Rekt> {
+        if stmts.is_empty() && sink == Sink::Return {
+            self.give_back(Wt::Null);
+        }
+        for (index, stmt) in stmts.iter().enumerate() {
+            let last = index + 1 == stmts.len();
+            self.statement(stmt, if last { sink } else { Sink::Discard })?;
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self, stmt: &Ast, sink: Sink) -> Result<(), Rekt> {
+        match stmt {
+            Ast::Located { line, statement } => self.statement(statement, sink).map_err(|e| e.at_line(*line)),
+
+            Ast::VariableDecl { name, initializer, .. } => {
+                let ty = self.value(initializer)?;
+                self.mint(name, ty)?;
+                if sink == Sink::Return {
+                    let ty = self.read(name)?;
+                    self.give_back(ty);
+                }
+                Ok(())
+            }
+            Ast::FunctionDecl { .. } => Err(unsupported("A contract mined here")),
+            Ast::StructDecl { .. } => Err(unsupported("A struct")),
+
+            Ast::If { condition, then_branch, else_branch } => {
+                self.condition(condition)?;
+                self.open(Instr::If(None), Label::Plain);
+                self.statements(then_branch, sink)?;
+                if let Some(else_branch) = else_branch {
+                    self.emit(Instr::Else);
+                    self.statements(else_branch, sink)?;
+                }
+                self.close();
+                if else_branch.is_none() {
+                    self.give_back_null(sink);
+                }
+                Ok(())
+            }
+            Ast::While { condition, body } => {
+                self.open(Instr::Block(None), Label::Break);
+                self.open(Instr::Loop(None), Label::Continue);
+                self.condition(condition)?;
+                self.emit(Instr::I32Eqz);
+                self.emit(Instr::BrIf(1));
+                self.loop_statements(body)?;
+                self.emit(Instr::Br(0));
+                self.close();
+                self.close();
+                self.give_back_null(sink);
+                Ok(())
+            }
+            Ast::ForRange { variable, start, end, step, body } => {
+                self.for_range(variable, start, end, step.as_deref(), body)?;
+                self.give_back_null(sink);
+                Ok(())
+            }
+            Ast::ForEach { .. } => Err(unsupported("Farming an array or mapping")),
+            Ast::Block(stmts) => {
+                self.enter(&[], stmts)?;
+                let result = self.statements(stmts, sink);
+                self.leave();
+                result
+            }
+
+            Ast::PrintStmt(value) => {
+                let ty = self.expr(value)?;
+                self.render(ty);
+                if ty != Wt::Never {
+                    self.helper(Helper::Emit);
+                }
+                self.give_back_null(sink);
+                Ok(())
+            }
+            Ast::ReturnStmt(value) => {
+                let ty = match value {
+                    Some(value) => self.value(value)?,
+                    None => Wt::Null,
+                };
+                self.give_back(ty);
+                Ok(())
+            }
+            stmt if ends_run(stmt, self.loops) => {
+                self.give_back(Wt::Null);
+                Ok(())
+            }
+            Ast::Break => {
+                let depth = self.depth(Label::Break);
+                self.emit(Instr::Br(depth));
+                Ok(())
+            }
+            Ast::Continue => {
+                let depth = self.depth(Label::Continue);
+                self.emit(Instr::Br(depth));
+                Ok(())
+            }
+            Ast::ExpressionStmt(value) => self.expression_statement(value, sink),
+            other => self.expression_statement(other, sink),
+        }
+    }
+
+    fn loop_statements(&mut self, body: &[Ast]) -> Result<(), Rekt> {
+        self.loops += 1;
+        let result = self.statements(body, Sink::Discard);
+        self.loops -= 1;
+        result
+    }
+
+    fn expression_statement(&mut self, value: &Ast, sink: Sink) -> Result<(), Rekt> {
+        let ty = match (value.unlocated(), sink) {
+            (Ast::Match { scrutinee, arms }, _) => {
+                self.vibecheck(scrutinee, arms, Use::Statement(sink))?;
+                return Ok(());
+            }
+            (Ast::Assign { name, value }, Sink::Discard) => return self.assign(name, value, false).map(|_| ()),
+            (Ast::CompoundAssign { target, operator, value }, Sink::Discard) => {
+                return self.compound(target, operator, value, false).map(|_| ())
+            }
+            _ => self.expr(value)?,
+        };
+        match sink {
+            Sink::Discard => self.drop_value(ty),
+            Sink::Return => self.give_back(ty),
+        }
+        Ok(())
+    }
+
+    // Leaves the running contract with the value on top of the stack, or
+    // `run` with nothing.
+    fn give_back(&mut self, ty: Wt) {
+        match ty {
+            Wt::Never => return,
+            Wt::Unknown => self.returned.push(Returned::Unknown),
+            Wt::Null => self.returned.push(Returned::Nothing),
+            ty => self.returned.push(Returned::Value(ty)),
+        }
+        let result = self.mine.and_then(|index| self.result_of(index));
+        match (ty.val(), result) {
+            (Some(_), None) => self.emit(Instr::Drop),
+            (None, Some(_)) => self.emit(Instr::Unreachable), // a mismatch this pass reports
+            _ => {}
+        }
+        self.emit(Instr::Return);
+    }
+
+    fn give_back_null(&mut self, sink: Sink) {
+        if sink == Sink::Return {
+            self.give_back(Wt::Null);
+        }
+    }
+
+    fn for_range(&mut self, variable: &str, start: &Ast, end: &Ast, step: Option<&Ast>, body: &[Ast]) -> Result<(), Rekt> {
+        let mut bounds = Vec::new();
+        for (what, bound) in [("start", Some(start)), ("end", Some(end)), ("step", step)] {
+            let local = self.temp(what, ValType::F64);
+            match bound {
+                Some(bound) => {
+                    let ty = self.value(bound)?;
+                    match ty {
+                        Wt::Num | Wt::Unknown => {}
+                        Wt::Never => return Ok(()),
+                        other => {
+                            let message = format!("Range {} must be a number, but found {}", what, other.name());
+                            self.fail(TYPE, &message)?;
+                            return Ok(());
+                        }
+                    }
+                }
+                None => self.emit(Instr::F64Const(1.0)),
+            }
+            self.emit(Instr::LocalSet(local));
+            bounds.push(local);
+        }
+        let (current, end, step) = (bounds[0], bounds[1], bounds[2]);
+
+        self.emit(Instr::LocalGet(step));
+        self.emit(Instr::F64Const(0.0));
+        self.emit(Instr::F64Eq);
+        self.open(Instr::If(None), Label::Plain);
+        self.fail(RUNTIME, "Range step can't be zero")?;
+        self.close();
+
+        self.open(Instr::Block(None), Label::Break);
+        self.open(Instr::Loop(None), Label::Plain);
+        for (direction, past) in [(Instr::F64Gt, Instr::F64Lt), (Instr::F64Lt, Instr::F64Gt)] {
+            self.emit(Instr::LocalGet(step));
+            self.emit(Instr::F64Const(0.0));
+            self.emit(direction);
+            self.emit(Instr::LocalGet(current));
+            self.emit(Instr::LocalGet(end));
+            self.emit(past);
+            self.emit(Instr::I32And);
+        }
+        self.emit(Instr::I32Or);
+        self.emit(Instr::I32Eqz);
+        self.emit(Instr::BrIf(1));
+
+        // Each pass gets a fresh scope holding the loop variable
+        self.enter(&[(variable.to_string(), Wt::Num)], body)?;
+        if let Some((_, binding)) = self.lookup(variable) {
+            self.emit(Instr::LocalGet(current));
+            self.set(binding.place);
+        }
+        self.open(Instr::Block(None), Label::Continue);
+        let result = self.loop_statements(body);
+        self.close();
+        self.leave();
+        result?;
+
+        self.emit(Instr::LocalGet(current));
+        self.emit(Instr::LocalGet(step));
+        self.emit(Instr::F64Add);
+        self.emit(Instr::LocalSet(current));
+        self.emit(Instr::Br(0));
+        self.close();
+        self.close();
+        Ok(())
+    }
+
+    fn condition(&mut self, ast: &Ast) -> Result<(), Rekt> {
+        match self.value(ast)? {
+            Wt::Sig | Wt::Never => {}
+            Wt::Unknown => {
+                self.emit(Instr::Drop);
+                self.emit(Instr::I32Const(0));
+            }
+            _ => {
+                self.fail(LEXER, NOT_A_SIGNAL)?;
+            }
+        }
+        Ok(())
+    }
+
+    // ------------------------------------------------------------ tokens
+
+    // Mints `name` in the innermost scope with the value on top of the stack.
+    fn mint(&mut self, name: &str, ty: Wt) -> Result<(), Rekt> {
+        let Some(binding) = self.scopes.last().and_then(|scope| scope.get(name)).cloned() else {
+            return Ok(());
+        };
+        self.learn(&binding, name, ty)?;
+        if ty != Wt::Never {
+            self.set(binding.place);
+            if let Some(flag) = binding.flag {
+                self.emit(Instr::I32Const(1));
+                self.set(flag);
+            }
+        }
+        self.minted(name);
+        Ok(())
+    }
+
+    fn read(&mut self, name: &str) -> Result<Wt, Rekt> {
+        self.read_below(name, self.scopes.len())
+    }
+
+    fn read_below(&mut self, name: &str, upto: usize) -> Result<Wt, Rekt> {
+        let found = self.lookup_below(name, upto).filter(|(index, binding)| binding.live || *index < self.floor);
+        let Some((index, binding)) = found else {
+            return self.fail(LEXER, &format!("Token '{}' not found.", name));
+        };
+        if let Place::Mine(_) = binding.place {
+            return Err(unsupported(&format!("Contract '{}' used as a value", name)));
+        }
+        let ty = self.type_of(&binding, name);
+        if ty == Wt::Never {
+            self.emit(Instr::Unreachable);
+            return Ok(Wt::Never);
+        }
+        match binding.flag {
+            // Until a hoisted token is minted, GM looks further out
+            Some(flag) if index >= self.floor => {
+                self.get(flag);
+                self.open(Instr::If(ty.val()), Label::Plain);
+                self.get(binding.place);
+                self.emit(Instr::Else);
+                let outer = self.read_below(name, index)?;
+                self.close();
+                if join(Some(ty), outer) != Some(ty) {
+                    let what = format!("'{}', read where it may be minted as {} or {},", name, ty.name(), outer.name());
+                    return Err(unsupported(&what));
+                }
+            }
+            _ => self.get(binding.place),
+        }
+        Ok(ty)
+    }
+
+    // Stores the value on top of the stack in the token `name` already
+    // holds, keeping a copy on the stack if `keep`.
+    fn store(&mut self, name: &str, ty: Wt, keep: bool) -> Result<Wt, Rekt> {
+        let Some((_, binding)) = self.minted_binding(name) else {
+            self.drop_value(ty);
+            return self.fail(RUNTIME, &format!("Wallet address '{}' not found.", name));
+        };
+        if let Place::Mine(_) = binding.place {
+            return Err(unsupported(&format!("Contract '{}' replaced by an assignment", name)));
+        }
+        if binding.kind == Some(DeclKind::Block) && !binding.hoisted {
+            self.drop_value(ty);
+            return self.fail(RUNTIME, &format!("'{}' is a block constant and can't be changed.", name));
+        }
+        self.learn(&binding, name, ty)?;
+        if ty == Wt::Never {
+            return Ok(Wt::Never);
+        }
+        match (binding.place, keep) {
+            (Place::Local(index), true) => self.emit(Instr::LocalTee(index)),
+            (place, true) => {
+                self.set(place);
+                self.get(place);
+            }
+            (place, false) => self.set(place),
+        }
+        Ok(if keep { ty } else { Wt::Null })
+    }
+
+    fn assign(&mut self, name: &str, value: &Ast, keep: bool) -> Result<Wt, Rekt> {
+        let ty = self.value(value)?;
+        if ty == Wt::Never {
+            return Ok(Wt::Never);
+        }
+        self.store(name, ty, keep)
+    }
+
+    fn compound(&mut self, target: &Ast, operator: &BinaryOp, value: &Ast, keep: bool) -> Result<Wt, Rekt> {
+        let (name, steps) = target_path(target)?;
+        if !steps.is_empty() {
+            return Err(unsupported("Writing into a collection or struct"));
+        }
+        let current = self.read(name)?;
+        let operand = self.value(value)?;
+        let ty = self.binary(operator, current, operand)?;
+        if ty == Wt::Never {
+            return Ok(Wt::Never);
+        }
+        self.store(name, ty, keep)
+    }
+
+    // ------------------------------------------------------------ expressions
+
+    // An expression whose value is used, which has to be something.
+    fn value(&mut self, ast: &Ast) -> Result<Wt, Rekt> {
+        match self.expr(ast)? {
+            Wt::Null => Err(unsupported("Using what a contract that returns nothing gives back")),
+            ty => Ok(ty),
+        }
+    }
+
+    fn expr(&mut self, ast: &Ast) -> Result<Wt, Rekt> {
+        match ast {
+            Ast::Located { line, statement } => self.expr(statement).map_err(|e| e.at_line(*line)),
+            Ast::Literal(value) => self.literal(value),
+            Ast::Variable(name) | Ast::ResolvedVariable { name, .. } => self.read(name),
+            Ast::Grouping(inner) => self.expr(inner),
+
+            Ast::Binary { left, operator, right } => {
+                let left = self.value(left)?;
+                let right = self.value(right)?;
+                self.binary(operator, left, right)
+            }
+            Ast::Unary { operator, operand } => {
+                let operand = self.value(operand)?;
+                self.unary(operator, operand)
+            }
+            Ast::Ternary { condition, then_branch, else_branch } => {
+                self.condition(condition)?;
+                let start = self.code.body.len();
+                self.open(Instr::If(None), Label::Plain);
+                let then_ty = self.value(then_branch)?;
+                self.emit(Instr::Else);
+                let else_ty = self.value(else_branch)?;
+                self.close();
+                let ty = join(Some(then_ty), else_ty)
+                    .ok_or_else(|| unsupported(&format!("A ternary giving {} or {}", then_ty.name(), else_ty.name())))?;
+                self.code.body[start] = Instr::If(ty.val());
+                Ok(ty)
+            }
+            Ast::Interpolation(parts) => {
+                let mut never = false;
+                for (index, part) in parts.iter().enumerate() {
+                    match part {
+                        Ast::Literal(Value::Text(text)) => self.text(text),
+                        other => {
+                            let ty = self.expr(other)?;
+                            never |= ty == Wt::Never;
+                            self.render(ty);
+                        }
+                    }
+                    if index > 0 {
+                        self.helper(Helper::Concat);
+                    }
+                }
+                if parts.is_empty() {
+                    self.text("");
+                }
+                Ok(if never { Wt::Never } else { Wt::Text })
+            }
+
+            Ast::Call { callee, arguments } => self.call_expr(callee, arguments),
+            Ast::Lambda { .. } => Err(unsupported("An anonymous contract")),
+            Ast::Match { scrutinee, arms } => self.vibecheck(scrutinee, arms, Use::Value),
+            Ast::Assign { name, value } => self.assign(name, value, true),
+            Ast::CompoundAssign { target, operator, value } => self.compound(target, operator, value, true),
+
+            Ast::ArrayLiteral(_) => Err(unsupported("An array")),
+            Ast::MapLiteral(_) => Err(unsupported("A mapping")),
+            Ast::StructLiteral { .. } => Err(unsupported("A struct")),
+            Ast::Index { .. } => Err(unsupported("Indexing")),
+            Ast::Field { .. } => Err(unsupported("A field")),
+            Ast::SetIndex { .. } | Ast::SetField { .. } | Ast::Delete(_) => {
+                Err(unsupported("Writing into a collection or struct"))
+            }
+
+            Ast::Program(_)
+            | Ast::Block(_)
+            | Ast::VariableDecl { .. }
+            | Ast::FunctionDecl { .. }
+            | Ast::StructDecl { .. }
+            | Ast::If { .. }
+            | Ast::While { .. }
+            | Ast::ForEach { .. }
+            | Ast::ForRange { .. }
+            | Ast::Break
+            | Ast::Continue
+            | Ast::ExpressionStmt(_)
+            | Ast::PrintStmt(_)
+            | Ast::ReturnStmt(_) => Err(unsupported("A statement used as a value")),
+        }
+    }
+
+    fn literal(&mut self, value: &Value) -> Result<Wt, Rekt> {
+        match value {
+            Value::Token(t) => self.emit(Instr::F64Const(t.parse::<f64>().unwrap_or(f64::NAN))),
+            Value::Number(n) => self.emit(Instr::F64Const(*n)),
+            Value::Text(s) => {
+                self.text(s);
+                return Ok(Wt::Text);
+            }
+            Value::Signal(b) | Value::Boolean(b) => {
+                self.emit(Instr::I32Const(*b as i32));
+                return Ok(Wt::Sig);
+            }
+            Value::Null => return Ok(Wt::Null),
+            other => return Err(unsupported(&format!("A {} literal", other))),
+        }
+        Ok(Wt::Num)
+    }
+
+    // Applies an operator to the two values on top of the stack.
+    fn binary(&mut self, operator: &BinaryOp, left: Wt, right: Wt) -> Result<Wt, Rekt> {
+        if left == Wt::Never || right == Wt::Never {
+            return Ok(Wt::Never);
+        }
+        if left == Wt::Unknown || right == Wt::Unknown {
+            self.drop_value(right);
+            self.drop_value(left);
+            self.emit(Instr::F64Const(0.0));
+            return Ok(Wt::Unknown);
+        }
+        let numbers = |gen: &mut Self| -> Result<bool, Rekt> {
+            match [left, right].into_iter().find(|ty| *ty != Wt::Num) {
+                Some(other) => gen.fail(TYPE, &format!("Expected vibe, but found {}", other.name())).map(|_| false),
+                None => Ok(true),
+            }
+        };
+        match operator {
+            BinaryOp::Add | BinaryOp::Subtract | BinaryOp::Multiply | BinaryOp::Divide => {
+                if !numbers(self)? {
+                    return Ok(Wt::Never);
+                }
+                match operator {
+                    BinaryOp::Add => self.emit(Instr::F64Add),
+                    BinaryOp::Subtract => self.emit(Instr::F64Sub),
+                    BinaryOp::Multiply => self.emit(Instr::F64Mul),
+                    _ => {
+                        let divisor = self.temp("divisor", ValType::F64);
+                        self.emit(Instr::LocalSet(divisor));
+                        self.emit(Instr::LocalGet(divisor));
+                        self.emit(Instr::F64Const(0.0));
+                        self.emit(Instr::F64Eq);
+                        self.open(Instr::If(None), Label::Plain);
+                        self.fail(LEXER, "You just got rekt by zero!")?;
+                        self.close();
+                        self.emit(Instr::LocalGet(divisor));
+                        self.emit(Instr::F64Div);
+                    }
+                }
+                Ok(Wt::Num)
+            }
+            BinaryOp::Less | BinaryOp::Greater | BinaryOp::LessEqual | BinaryOp::GreaterEqual => {
+                if !numbers(self)? {
+                    return Ok(Wt::Never);
+                }
+                // NaN compares as equal to everything, so `<=` is "not greater"
+                match operator {
+                    BinaryOp::Less => self.emit(Instr::F64Lt),
+                    BinaryOp::Greater => self.emit(Instr::F64Gt),
+                    BinaryOp::LessEqual => {
+                        self.emit(Instr::F64Gt);
+                        self.emit(Instr::I32Eqz);
+                    }
+                    _ => {
+                        self.emit(Instr::F64Lt);
+                        self.emit(Instr::I32Eqz);
+                    }
+                }
+                Ok(Wt::Sig)
+            }
+            BinaryOp::Equal | BinaryOp::NotEqual => {
+                match (left, right) {
+                    (Wt::Num, Wt::Num) => self.helper(Helper::NumEq),
+                    (Wt::Sig, Wt::Sig) => self.emit(Instr::I32Eq),
+                    (Wt::Text, Wt::Text) => self.helper(Helper::TextEq),
+                    _ => return self.fail(LEXER, "Cannot compare different types"),
+                }
+                if *operator == BinaryOp::NotEqual {
+                    self.emit(Instr::I32Eqz);
+                }
+                Ok(Wt::Sig)
+            }
+            BinaryOp::Modulo
+            | BinaryOp::IntDivide
+            | BinaryOp::BitAnd
+            | BinaryOp::BitOr
+            | BinaryOp::BitXor
+            | BinaryOp::ShiftLeft
+            | BinaryOp::ShiftRight => self.integer(operator, left, right),
+            _ => self.fail(LEXER, "Op not supported yet."),
+        }
+    }
+
+    // The integer operators, which work on whole numbers as i64.
+    fn integer(&mut self, operator: &BinaryOp, left: Wt, right: Wt) -> Result<Wt, Rekt> {
+        let prefix = |ty: Wt| format!("'{}' needs integers, but found {} ", operator.symbol(), ty.name());
+        if left != Wt::Num {
+            self.drop_value(right);
+            return self.fail_with(TYPE, &prefix(left), left);
+        }
+        let rhs = self.temp("rhs", right.storage());
+        self.emit(Instr::LocalSet(rhs));
+        let whole = self.link.text(&prefix(Wt::Num));
+        self.emit(Instr::I32Const(whole as i32));
+        self.helper(Helper::Whole);
+        self.emit(Instr::LocalGet(rhs));
+        if right != Wt::Num {
+            self.fail_with(TYPE, &prefix(right), right)?;
+            return Ok(Wt::Never);
+        }
+        self.emit(Instr::I32Const(whole as i32));
+        self.helper(Helper::Whole);
+
+        match operator {
+            BinaryOp::IntDivide => self.helper(Helper::FloorDiv),
+            BinaryOp::Modulo => {
+                // The remainder takes the divisor's sign
+                let (a, b) = (self.temp("a", ValType::I64), self.temp("b", ValType::I64));
+                self.emit(Instr::LocalSet(b));
+                self.emit(Instr::LocalSet(a));
+                self.emit(Instr::LocalGet(a));
+                self.emit(Instr::LocalGet(a));
+                self.emit(Instr::LocalGet(b));
+                self.helper(Helper::FloorDiv);
+                self.emit(Instr::LocalGet(b));
+                self.emit(Instr::I64Mul);
+                self.emit(Instr::I64Sub);
+            }
+            BinaryOp::BitAnd => self.emit(Instr::I64And),
+            BinaryOp::BitOr => self.emit(Instr::I64Or),
+            BinaryOp::BitXor => self.emit(Instr::I64Xor),
+            BinaryOp::ShiftLeft => self.helper(Helper::Shl),
+            _ => self.helper(Helper::Shr),
+        }
+        self.emit(Instr::F64ConvertI64S);
+        Ok(Wt::Num)
+    }
+
+    fn unary(&mut self, operator: &BinaryOp, operand: Wt) -> Result<Wt, Rekt> {
+        match (operator, operand) {
+            (_, Wt::Never) => Ok(Wt::Never),
+            (_, Wt::Unknown) => Ok(Wt::Unknown),
+            (BinaryOp::Not, Wt::Sig) => {
+                self.emit(Instr::I32Eqz);
+                Ok(Wt::Sig)
+            }
+            (BinaryOp::Not, other) => self.fail(TYPE, &format!("'not' needs a signal, but found {}", other.name())),
+            (BinaryOp::BitNot, Wt::Num) => {
+                self.text("'~' needs integers, but found vibe ");
+                self.helper(Helper::Whole);
+                self.emit(Instr::I64Const(-1));
+                self.emit(Instr::I64Xor);
+                self.emit(Instr::F64ConvertI64S);
+                Ok(Wt::Num)
+            }
+            (BinaryOp::BitNot, other) => {
+                self.fail_with(TYPE, &format!("'~' needs integers, but found {} ", other.name()), other)
+            }
+            (other, _) => self.fail(RUNTIME, &format!("'{}' is not a prefix operator", other.symbol())),
+        }
+    }
+
+    // ------------------------------------------------------------ calls
+
+    fn call_expr(&mut self, callee: &Ast, arguments: &[Ast]) -> Result<Wt, Rekt> {
+        let name = match callee.unlocated() {
+            Ast::Variable(name) | Ast::ResolvedVariable { name, .. } => name,
+            _ => return Err(unsupported("Calling anything but a contract by name")),
+        };
+        match self.minted_binding(name) {
+            // A name no wallet holds may still be a builtin
+            None => self.builtin(name, arguments),
+            Some((_, binding)) => match binding.place {
+                Place::Mine(index) => self.call_mine(index, arguments),
+                _ => {
+                    let ty = self.read(name)?;
+                    self.drop_value(ty);
+                    self.discard_args(arguments)?;
+                    self.fail(LEXER, &format!("{} is not a contract", ty.name()))
+                }
+            },
+        }
+    }
+
+    fn discard_args(&mut self, arguments: &[Ast]) -> Result<(), Rekt> {
+        for argument in arguments {
+            let ty = self.expr(argument)?;
+            self.drop_value(ty);
+        }
+        Ok(())
+    }
+
+    fn call_mine(&mut self, index: usize, arguments: &[Ast]) -> Result<Wt, Rekt> {
+        let (name, params) = (self.mines[index].name.clone(), self.mines[index].params.clone());
+        let mut types = Vec::new();
+        for argument in arguments {
+            types.push(self.value(argument)?);
+        }
+        if types.contains(&Wt::Never) {
+            return Ok(Wt::Never);
+        }
+        if types.len() != params.len() {
+            for ty in types.iter().rev() {
+                self.drop_value(*ty);
+            }
+            return self.fail(LEXER, &format!("Expected {} args, got {}.", params.len(), types.len()));
+        }
+        for (ty, (param, expected)) in types.iter().zip(&params) {
+            if ty != expected && *ty != Wt::Unknown {
+                return Err(unsupported(&format!(
+                    "Passing {} to '{}', whose param '{}' is {},",
+                    ty.name(),
+                    name,
+                    param,
+                    expected.name()
+                )));
+            }
+        }
+        self.call(Callee::Mine(index));
+        match self.known.returns.get(&name) {
+            Some(Some(ty)) => Ok(*ty),
+            Some(None) => Ok(Wt::Null),
+            None => {
+                self.unknown.get_or_insert_with(|| format!("what '{}' returns", name));
+                self.emit(Instr::F64Const(0.0));
+                Ok(Wt::Unknown)
+            }
+        }
+    }
+
+    fn builtin(&mut self, name: &str, arguments: &[Ast]) -> Result<Wt, Rekt> {
+        if !BUILTINS.contains(&name) {
+            self.discard_args(arguments)?;
+            return self.fail(LEXER, &format!("Unknown smart contract '{}'", name));
+        }
+        if name != "len" && name != "text" {
+            return Err(unsupported(&format!("Builtin '{}'", name)));
+        }
+        let mut types = Vec::new();
+        for argument in arguments {
+            types.push(self.expr(argument)?);
+        }
+        if types.contains(&Wt::Never) {
+            return Ok(Wt::Never);
+        }
+        if types.len() != 1 {
+            for ty in types.iter().rev() {
+                self.drop_value(*ty);
+            }
+            return self.fail(RUNTIME, &format!("'{}' expected 1 args, got {}.", name, types.len()));
+        }
+        match (name, types[0]) {
+            ("len", Wt::Text) => {
+                self.helper(Helper::TextLen);
+                Ok(Wt::Num)
+            }
+            ("len", Wt::Unknown) => Ok(Wt::Unknown),
+            ("len", other) => self.fail(TYPE, &format!("Cannot take the length of {}", other.name())),
+            (_, ty) => {
+                self.render(ty);
+                Ok(Wt::Text)
+            }
+        }
+    }
+
+    // ------------------------------------------------------------ vibecheck
+
+    // A block whose arms each test the subject and, on a hit, run and
+    // leave it. Used as a value, the block gives the arm's value.
+    fn vibecheck(&mut self, scrutinee: &Ast, arms: &[(Pattern, Ast)], usage: Use) -> Result<Wt, Rekt> {
+        if usage == Use::Value && arms.iter().any(|(_, body)| escapes(body, false)) {
+            return Err(unsupported("A return, crash or skip inside a vibecheck used as a value"));
+        }
+        let ty = self.value(scrutinee)?;
+        if ty == Wt::Never {
+            return Ok(Wt::Never);
+        }
+        let subject = self.temp("subject", ty.storage());
+        self.emit(Instr::LocalSet(subject));
+
+        let start = self.code.body.len();
+        self.open(Instr::Block(None), Label::Plain);
+        let exit = self.code.labels.len();
+        let mut result: Option<Wt> = None;
+        let mut exhaustive = false;
+        for (pattern, body) in arms {
+            let catch_all = matches!(pattern, Pattern::Wildcard | Pattern::Binding(_));
+            if !catch_all {
+                self.pattern(pattern, ty, subject)?;
+                self.open(Instr::If(None), Label::Plain);
+            }
+            let arm = self.arm(pattern, ty, subject, body, usage)?;
+            if usage == Use::Value {
+                result = join(result, arm).or_else(|| result.filter(|_| false));
+                if result.is_none() && arm != Wt::Never {
+                    return Err(unsupported("A vibecheck whose arms give different types"));
+                }
+            }
+            let depth = (self.code.labels.len() - exit) as u32;
+            self.emit(Instr::Br(depth));
+            if catch_all {
+                exhaustive = true;
+                break;
+            }
+            self.close();
+        }
+        if !exhaustive {
+            self.emit(Instr::LocalGet(subject));
+            self.fail_with(RUNTIME, "No vibecheck arm matched ", ty)?;
+        }
+        self.close();
+
+        match usage {
+            Use::Value => {
+                let ty = result.unwrap_or(Wt::Never);
+                self.code.body[start] = Instr::Block(ty.val());
+                Ok(ty)
+            }
+            Use::Statement(_) => Ok(Wt::Null),
+        }
+    }
+
+    // One arm's body, in a scope holding what its pattern binds.
+    fn arm(&mut self, pattern: &Pattern, ty: Wt, subject: u32, body: &Ast, usage: Use) -> Result<Wt, Rekt> {
+        let stmts = match body.unlocated() {
+            Ast::Block(stmts) => stmts.as_slice(),
+            _ => &[],
+        };
+        let bound: Vec<(String, Wt)> = match pattern {
+            Pattern::Binding(name) => vec![(name.clone(), ty)],
+            _ => Vec::new(),
+        };
+        self.enter(&bound, stmts)?;
+        if let Pattern::Binding(name) = pattern {
+            if let Some((_, binding)) = self.lookup(name) {
+                self.emit(Instr::LocalGet(subject));
+                self.set(binding.place);
+            }
+        }
+        let result = match (body.unlocated(), usage) {
+            (Ast::Block(stmts), Use::Statement(sink)) => self.statements(stmts, sink).map(|()| Wt::Null),
+            (other, Use::Statement(sink)) => self.expression_statement(other, sink).map(|()| Wt::Null),
+            (Ast::Block(stmts), Use::Value) => self.block_value(stmts),
+            (other, Use::Value) => self.expr(other),
+        };
+        self.leave();
+        result
+    }
+
+    // The value of a block's last statement, which is what GM gives back
+    // for a vibecheck arm.
+    fn block_value(&mut self, stmts: &[Ast]) -> Result<Wt, Rekt> {
+        let Some((last, rest)) = stmts.split_last() else { return Ok(Wt::Null) };
+        self.statements(rest, Sink::Discard)?;
+        let (line, last) = match last {
+            Ast::Located { line, statement } => (Some(*line), statement.as_ref()),
+            other => (None, other),
+        };
+        let result = match last {
+            Ast::VariableDecl { name, .. } => self.statement(last, Sink::Discard).and_then(|()| self.read(name)),
+            Ast::ExpressionStmt(value) => self.expr(value),
+            Ast::PrintStmt(_) | Ast::While { .. } | Ast::ForRange { .. } => {
+                self.statement(last, Sink::Discard).map(|()| Wt::Null)
+            }
+            Ast::If { .. } | Ast::Block(_) | Ast::ForEach { .. } | Ast::StructDecl { .. } | Ast::FunctionDecl { .. } => {
+                Err(unsupported("A vibecheck arm ending in a statement, used as a value,"))
+            }
+            other => self.expr(other),
+        };
+        match line {
+            Some(line) => result.map_err(|e| e.at_line(line)),
+            None => result,
+        }
+    }
+
+    // Pushes whether the subject matches `pattern`, as a signal.
+    fn pattern(&mut self, pattern: &Pattern, ty: Wt, subject: u32) -> Result<(), Rekt> {
+        match pattern {
+            Pattern::Wildcard | Pattern::Binding(_) => self.emit(Instr::I32Const(1)),
+            Pattern::Literal(literal) => match (literal, ty) {
+                (Value::Token(_) | Value::Number(_), Wt::Num) => {
+                    // Compared as numbers, so 0 matches -0
+                    self.emit(Instr::LocalGet(subject));
+                    self.literal(literal)?;
+                    self.emit(Instr::F64Eq);
+                }
+                (Value::Text(text), Wt::Text) => {
+                    self.emit(Instr::LocalGet(subject));
+                    self.text(text);
+                    self.helper(Helper::TextEq);
+                }
+                (Value::Signal(b) | Value::Boolean(b), Wt::Sig) => {
+                    self.emit(Instr::LocalGet(subject));
+                    self.emit(Instr::I32Const(*b as i32));
+                    self.emit(Instr::I32Eq);
+                }
+                (Value::Token(_) | Value::Number(_) | Value::Text(_) | Value::Signal(_) | Value::Boolean(_), _) => {
+                    self.emit(Instr::I32Const(0));
+                }
+                (other, _) => return Err(unsupported(&format!("A {} pattern", other))),
+            },
+            Pattern::Range(start, end) => {
+                let bound = |value: &Value| match value {
+                    Value::Token(t) => t.parse::<f64>().ok(),
+                    Value::Number(n) => Some(*n),
+                    _ => None,
+                };
+                match (bound(start), bound(end), ty) {
+                    (Some(start), Some(end), Wt::Num) => {
+                        self.emit(Instr::LocalGet(subject));
+                        self.emit(Instr::F64Const(start));
+                        self.emit(Instr::F64Ge);
+                        self.emit(Instr::LocalGet(subject));
+                        self.emit(Instr::F64Const(end));
+                        self.emit(Instr::F64Lt);
+                        self.emit(Instr::I32And);
+                    }
+                    (Some(_), Some(_), _) => self.emit(Instr::I32Const(0)),
+                    _ => return Err(unsupported("A range pattern over anything but numbers")),
+                }
+            }
+            Pattern::Or(alternatives) => {
+                let mut names = Vec::new();
+                pattern.bindings(&mut names);
+                if !names.is_empty() {
+                    return Err(unsupported("A pattern that binds inside '|'"));
+                }
+                for (index, alternative) in alternatives.iter().enumerate() {
+                    self.pattern(alternative, ty, subject)?;
+                    if index > 0 {
+                        self.emit(Instr::I32Or);
+                    }
+                }
+            }
+            Pattern::Struct { .. } | Pattern::Array { .. } => return Err(unsupported("A struct or array pattern")),
+        }
+        Ok(())
+    }
+}
+
+// The type two ways of giving a value agree on, if they do. An error
+// leaves no value to disagree with.
+fn join(known: Option<Wt>, ty: Wt) -> Option<Wt> {
+    match (known, ty) {
+        (None, ty) | (Some(Wt::Never), ty) => Some(ty),
+        (Some(known), Wt::Never) => Some(known),
+        (Some(Wt::Unknown), _) | (_, Wt::Unknown) => Some(Wt::Unknown),
+        (Some(known), ty) if known == ty => Some(known),
+        _ => None,
+    }
+}
+
+fn zero(ty: ValType) -> Instr {
+    match ty {
+        ValType::I32 => Instr::I32Const(0),
+        ValType::I64 => Instr::I64Const(0),
+        ValType::F64 => Instr::F64Const(0.0),
+    }
+}
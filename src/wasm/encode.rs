@@ -0,0 +1,310 @@
+use super::module::{FuncType, Instr, Module, ValType, HOST_MODULE};
+
+// The binary format. A module is the magic and version, then its sections
+// in the order the spec fixes, each one an id, its size and its contents.
+// Integers are LEB128 and floats little-endian.
+pub const MAGIC: [u8; 4] = *b"\0asm";
+pub const VERSION: u32 = 1;
+
+pub const TYPE_SECTION: u8 = 1;
+pub const IMPORT_SECTION: u8 = 2;
+pub const FUNCTION_SECTION: u8 = 3;
+pub const MEMORY_SECTION: u8 = 5;
+pub const GLOBAL_SECTION: u8 = 6;
+pub const EXPORT_SECTION: u8 = 7;
+pub const CODE_SECTION: u8 = 10;
+pub const DATA_SECTION: u8 = 11;
+
+pub const FUNC_TYPE: u8 = 0x60;
+pub const EMPTY_BLOCK: u8 = 0x40;
+pub const FUNC_KIND: u8 = 0x00;
+pub const MEMORY_KIND: u8 = 0x02;
+pub const END: u8 = 0x0b;
+pub const MISC_PREFIX: u8 = 0xfc;
+pub const MEMORY_COPY: u32 = 10;
+
+// Every instruction without an immediate, by opcode. The decoder reads
+// with the same table.
+pub const OPCODES: &[(u8, Instr)] = &[
+    (0x00, Instr::Unreachable),
+    (0x05, Instr::Else),
+    (0x0b, Instr::End),
+    (0x0f, Instr::Return),
+    (0x1a, Instr::Drop),
+    (0x1b, Instr::Select),
+    (0x45, Instr::I32Eqz),
+    (0x46, Instr::I32Eq),
+    (0x47, Instr::I32Ne),
+    (0x49, Instr::I32LtU),
+    (0x4b, Instr::I32GtU),
+    (0x4f, Instr::I32GeU),
+    (0x50, Instr::I64Eqz),
+    (0x51, Instr::I64Eq),
+    (0x52, Instr::I64Ne),
+    (0x53, Instr::I64LtS),
+    (0x55, Instr::I64GtS),
+    (0x5a, Instr::I64GeU),
+    (0x61, Instr::F64Eq),
+    (0x62, Instr::F64Ne),
+    (0x63, Instr::F64Lt),
+    (0x64, Instr::F64Gt),
+    (0x65, Instr::F64Le),
+    (0x66, Instr::F64Ge),
+    (0x6a, Instr::I32Add),
+    (0x6b, Instr::I32Sub),
+    (0x6c, Instr::I32Mul),
+    (0x71, Instr::I32And),
+    (0x72, Instr::I32Or),
+    (0x73, Instr::I32Xor),
+    (0x76, Instr::I32ShrU),
+    (0x7c, Instr::I64Add),
+    (0x7d, Instr::I64Sub),
+    (0x7e, Instr::I64Mul),
+    (0x7f, Instr::I64DivS),
+    (0x81, Instr::I64RemS),
+    (0x83, Instr::I64And),
+    (0x84, Instr::I64Or),
+    (0x85, Instr::I64Xor),
+    (0x86, Instr::I64Shl),
+    (0x87, Instr::I64ShrS),
+    (0x99, Instr::F64Abs),
+    (0x9d, Instr::F64Trunc),
+    (0xa0, Instr::F64Add),
+    (0xa1, Instr::F64Sub),
+    (0xa2, Instr::F64Mul),
+    (0xa3, Instr::F64Div),
+    (0xa7, Instr::I32WrapI64),
+    (0xb0, Instr::I64TruncF64S),
+    (0xb9, Instr::F64ConvertI64S),
+    (0xba, Instr::F64ConvertI64U),
+    (0xbd, Instr::I64ReinterpretF64),
+];
+
+pub fn encode(module: &Module) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&MAGIC);
+    out.extend_from_slice(&VERSION.to_le_bytes());
+    let types = module.types();
+    let type_index = |ty: &FuncType| types.iter().position(|t| t == ty).unwrap_or(0) as u32;
+
+    section(&mut out, TYPE_SECTION, types.len(), |w| {
+        for ty in &types {
+            w.push(FUNC_TYPE);
+            valtypes(w, &ty.params);
+            valtypes(w, &ty.results);
+        }
+    });
+    if !module.imports.is_empty() {
+        section(&mut out, IMPORT_SECTION, module.imports.len(), |w| {
+            for import in &module.imports {
+                name(w, HOST_MODULE);
+                name(w, import.name);
+                w.push(FUNC_KIND);
+                unsigned(w, type_index(&import.ty) as u64);
+            }
+        });
+    }
+    section(&mut out, FUNCTION_SECTION, module.functions.len(), |w| {
+        for function in &module.functions {
+            unsigned(w, type_index(&function.ty()) as u64);
+        }
+    });
+    section(&mut out, MEMORY_SECTION, 1, |w| {
+        w.push(0x00); // no maximum
+        unsigned(w, module.pages as u64);
+    });
+    if !module.globals.is_empty() {
+        section(&mut out, GLOBAL_SECTION, module.globals.len(), |w| {
+            for global in &module.globals {
+                w.push(global.ty.code());
+                w.push(0x01); // mutable
+                instruction(w, &global.init);
+                w.push(END);
+            }
+        });
+    }
+    let exports: Vec<(usize, &String)> = module
+        .functions
+        .iter()
+        .enumerate()
+        .filter_map(|(i, f)| f.export.as_ref().map(|name| (i, name)))
+        .collect();
+    section(&mut out, EXPORT_SECTION, exports.len() + 1, |w| {
+        name(w, "memory");
+        w.push(MEMORY_KIND);
+        unsigned(w, 0);
+        for (index, export) in &exports {
+            name(w, export);
+            w.push(FUNC_KIND);
+            unsigned(w, (module.imports.len() + index) as u64);
+        }
+    });
+    section(&mut out, CODE_SECTION, module.functions.len(), |w| {
+        for function in &module.functions {
+            let mut body = Vec::new();
+            // Locals are listed as runs of the same type
+            let mut runs: Vec<(u32, ValType)> = Vec::new();
+            for (_, ty) in &function.locals {
+                match runs.last_mut() {
+                    Some((count, last)) if last == ty => *count += 1,
+                    _ => runs.push((1, *ty)),
+                }
+            }
+            unsigned(&mut body, runs.len() as u64);
+            for (count, ty) in runs {
+                unsigned(&mut body, count as u64);
+                body.push(ty.code());
+            }
+            for instr in &function.body {
+                instruction(&mut body, instr);
+            }
+            body.push(END);
+            unsigned(w, body.len() as u64);
+            w.extend_from_slice(&body);
+        }
+    });
+    if !module.data.is_empty() {
+        section(&mut out, DATA_SECTION, module.data.len(), |w| {
+            for (offset, bytes) in &module.data {
+                w.push(0x00); // active, memory 0
+                instruction(w, &Instr::I32Const(*offset as i32));
+                w.push(END);
+                unsigned(w, bytes.len() as u64);
+                w.extend_from_slice(bytes);
+            }
+        });
+    }
+    out
+}
+
+fn section(out: &mut Vec<u8>, id: u8, count: usize, f: impl FnOnce(&mut Vec<u8>)) {
+    let mut contents = Vec::new();
+    unsigned(&mut contents, count as u64);
+    f(&mut contents);
+    out.push(id);
+    unsigned(out, contents.len() as u64);
+    out.extend_from_slice(&contents);
+}
+
+fn valtypes(w: &mut Vec<u8>, types: &[ValType]) {
+    unsigned(w, types.len() as u64);
+    w.extend(types.iter().map(|t| t.code()));
+}
+
+fn name(w: &mut Vec<u8>, text: &str) {
+    unsigned(w, text.len() as u64);
+    w.extend_from_slice(text.as_bytes());
+}
+
+fn block_type(w: &mut Vec<u8>, ty: &Option<ValType>) {
+    w.push(ty.map(ValType::code).unwrap_or(EMPTY_BLOCK));
+}
+
+// Loads and stores say how aligned their address is, as a power of two.
+fn memarg(w: &mut Vec<u8>, align: u64, offset: u32) {
+    unsigned(w, align);
+    unsigned(w, offset as u64);
+}
+
+fn instruction(w: &mut Vec<u8>, instr: &Instr) {
+    match instr {
+        Instr::Block(ty) => {
+            w.push(0x02);
+            block_type(w, ty);
+        }
+        Instr::Loop(ty) => {
+            w.push(0x03);
+            block_type(w, ty);
+        }
+        Instr::If(ty) => {
+            w.push(0x04);
+            block_type(w, ty);
+        }
+        Instr::Br(depth) => {
+            w.push(0x0c);
+            unsigned(w, *depth as u64);
+        }
+        Instr::BrIf(depth) => {
+            w.push(0x0d);
+            unsigned(w, *depth as u64);
+        }
+        Instr::Call(index) => {
+            w.push(0x10);
+            unsigned(w, *index as u64);
+        }
+        Instr::LocalGet(i) | Instr::LocalSet(i) | Instr::LocalTee(i) | Instr::GlobalGet(i) | Instr::GlobalSet(i) => {
+            w.push(match instr {
+                Instr::LocalGet(_) => 0x20,
+                Instr::LocalSet(_) => 0x21,
+                Instr::LocalTee(_) => 0x22,
+                Instr::GlobalGet(_) => 0x23,
+                _ => 0x24,
+            });
+            unsigned(w, *i as u64);
+        }
+        Instr::I32Load(offset) => {
+            w.push(0x28);
+            memarg(w, 2, *offset);
+        }
+        Instr::I32Load8U(offset) => {
+            w.push(0x2d);
+            memarg(w, 0, *offset);
+        }
+        Instr::I32Store(offset) => {
+            w.push(0x36);
+            memarg(w, 2, *offset);
+        }
+        Instr::I32Store8(offset) => {
+            w.push(0x3a);
+            memarg(w, 0, *offset);
+        }
+        Instr::MemorySize => w.extend_from_slice(&[0x3f, 0x00]),
+        Instr::MemoryGrow => w.extend_from_slice(&[0x40, 0x00]),
+        Instr::MemoryCopy => {
+            w.push(MISC_PREFIX);
+            unsigned(w, MEMORY_COPY as u64);
+            w.extend_from_slice(&[0x00, 0x00]);
+        }
+        Instr::I32Const(n) => {
+            w.push(0x41);
+            signed(w, *n as i64);
+        }
+        Instr::I64Const(n) => {
+            w.push(0x42);
+            signed(w, *n);
+        }
+        Instr::F64Const(n) => {
+            w.push(0x44);
+            w.extend_from_slice(&n.to_le_bytes());
+        }
+        other => match OPCODES.iter().find(|(_, i)| i == other) {
+            Some((opcode, _)) => w.push(*opcode),
+            None => unreachable!("{} has no opcode", other.mnemonic()),
+        },
+    }
+}
+
+pub fn unsigned(w: &mut Vec<u8>, mut n: u64) {
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            w.push(byte);
+            return;
+        }
+        w.push(byte | 0x80);
+    }
+}
+
+pub fn signed(w: &mut Vec<u8>, mut n: i64) {
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        let done = (n == 0 && byte & 0x40 == 0) || (n == -1 && byte & 0x40 != 0);
+        if done {
+            w.push(byte);
+            return;
+        }
+        w.push(byte | 0x80);
+    }
+}
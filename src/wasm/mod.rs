@@ -0,0 +1,17 @@
+// src/wasm/mod.rs
+//
+// The WebAssembly backend: a resolved tree becomes a module whose `mine`
+// contracts are exported functions and whose top level is the exported
+// `run`. Broadcasts, number formatting and errors go through a handful of
+// host functions imported from "gm"; everything else, texts included,
+// lives in the module's own linear memory. The module is written out both
+// as text (.wat) and binary (.wasm); the tests run the binary on a small
+// machine of their own to check it against the tree walker.
+
+mod compiler;
+pub mod encode;
+pub mod module;
+mod runtime;
+
+pub use compiler::compile;
+pub use encode::encode;
@@ -0,0 +1,409 @@
+use std::fmt::Write;
+
+// A WebAssembly module as the code generator builds it, and its text
+// format. Only what the generator needs is here: function imports, one
+// memory, globals, data segments, and functions taking i32, i64 and f64
+// values and returning at most one.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValType {
+    I32,
+    I64,
+    F64,
+}
+
+impl ValType {
+    pub fn name(self) -> &'static str {
+        match self {
+            ValType::I32 => "i32",
+            ValType::I64 => "i64",
+            ValType::F64 => "f64",
+        }
+    }
+
+    pub fn code(self) -> u8 {
+        match self {
+            ValType::I32 => 0x7f,
+            ValType::I64 => 0x7e,
+            ValType::F64 => 0x7c,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FuncType {
+    pub params: Vec<ValType>,
+    pub results: Vec<ValType>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Instr {
+    Unreachable,
+    Block(Option<ValType>),
+    Loop(Option<ValType>),
+    If(Option<ValType>),
+    Else,
+    End,
+    Br(u32),
+    BrIf(u32),
+    Return,
+    Call(u32),
+    Drop,
+    Select,
+
+    LocalGet(u32),
+    LocalSet(u32),
+    LocalTee(u32),
+    GlobalGet(u32),
+    GlobalSet(u32),
+
+    // Loads and stores carry their offset; alignment is always natural
+    I32Load(u32),
+    I32Load8U(u32),
+    I32Store(u32),
+    I32Store8(u32),
+    MemorySize,
+    MemoryGrow,
+    MemoryCopy,
+
+    I32Const(i32),
+    I64Const(i64),
+    F64Const(f64),
+
+    I32Eqz,
+    I32Eq,
+    I32Ne,
+    I32LtU,
+    I32GtU,
+    I32GeU,
+    I32Add,
+    I32Sub,
+    I32Mul,
+    I32And,
+    I32Or,
+    I32Xor,
+    I32ShrU,
+
+    I64Eqz,
+    I64Eq,
+    I64Ne,
+    I64LtS,
+    I64GtS,
+    I64GeU,
+    I64Add,
+    I64Sub,
+    I64Mul,
+    I64DivS,
+    I64RemS,
+    I64And,
+    I64Or,
+    I64Xor,
+    I64Shl,
+    I64ShrS,
+
+    F64Eq,
+    F64Ne,
+    F64Lt,
+    F64Gt,
+    F64Le,
+    F64Ge,
+    F64Abs,
+    F64Trunc,
+    F64Add,
+    F64Sub,
+    F64Mul,
+    F64Div,
+
+    I32WrapI64,
+    I64TruncF64S,
+    F64ConvertI64S,
+    F64ConvertI64U,
+    I64ReinterpretF64,
+}
+
+impl Instr {
+    // The mnemonic, and the opcode after the 0xfc prefix for the few that
+    // have one.
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Instr::Unreachable => "unreachable",
+            Instr::Block(_) => "block",
+            Instr::Loop(_) => "loop",
+            Instr::If(_) => "if",
+            Instr::Else => "else",
+            Instr::End => "end",
+            Instr::Br(_) => "br",
+            Instr::BrIf(_) => "br_if",
+            Instr::Return => "return",
+            Instr::Call(_) => "call",
+            Instr::Drop => "drop",
+            Instr::Select => "select",
+            Instr::LocalGet(_) => "local.get",
+            Instr::LocalSet(_) => "local.set",
+            Instr::LocalTee(_) => "local.tee",
+            Instr::GlobalGet(_) => "global.get",
+            Instr::GlobalSet(_) => "global.set",
+            Instr::I32Load(_) => "i32.load",
+            Instr::I32Load8U(_) => "i32.load8_u",
+            Instr::I32Store(_) => "i32.store",
+            Instr::I32Store8(_) => "i32.store8",
+            Instr::MemorySize => "memory.size",
+            Instr::MemoryGrow => "memory.grow",
+            Instr::MemoryCopy => "memory.copy",
+            Instr::I32Const(_) => "i32.const",
+            Instr::I64Const(_) => "i64.const",
+            Instr::F64Const(_) => "f64.const",
+            Instr::I32Eqz => "i32.eqz",
+            Instr::I32Eq => "i32.eq",
+            Instr::I32Ne => "i32.ne",
+            Instr::I32LtU => "i32.lt_u",
+            Instr::I32GtU => "i32.gt_u",
+            Instr::I32GeU => "i32.ge_u",
+            Instr::I32Add => "i32.add",
+            Instr::I32Sub => "i32.sub",
+            Instr::I32Mul => "i32.mul",
+            Instr::I32And => "i32.and",
+            Instr::I32Or => "i32.or",
+            Instr::I32Xor => "i32.xor",
+            Instr::I32ShrU => "i32.shr_u",
+            Instr::I64Eqz => "i64.eqz",
+            Instr::I64Eq => "i64.eq",
+            Instr::I64Ne => "i64.ne",
+            Instr::I64LtS => "i64.lt_s",
+            Instr::I64GtS => "i64.gt_s",
+            Instr::I64GeU => "i64.ge_u",
+            Instr::I64Add => "i64.add",
+            Instr::I64Sub => "i64.sub",
+            Instr::I64Mul => "i64.mul",
+            Instr::I64DivS => "i64.div_s",
+            Instr::I64RemS => "i64.rem_s",
+            Instr::I64And => "i64.and",
+            Instr::I64Or => "i64.or",
+            Instr::I64Xor => "i64.xor",
+            Instr::I64Shl => "i64.shl",
+            Instr::I64ShrS => "i64.shr_s",
+            Instr::F64Eq => "f64.eq",
+            Instr::F64Ne => "f64.ne",
+            Instr::F64Lt => "f64.lt",
+            Instr::F64Gt => "f64.gt",
+            Instr::F64Le => "f64.le",
+            Instr::F64Ge => "f64.ge",
+            Instr::F64Abs => "f64.abs",
+            Instr::F64Trunc => "f64.trunc",
+            Instr::F64Add => "f64.add",
+            Instr::F64Sub => "f64.sub",
+            Instr::F64Mul => "f64.mul",
+            Instr::F64Div => "f64.div",
+            Instr::I32WrapI64 => "i32.wrap_i64",
+            Instr::I64TruncF64S => "i64.trunc_f64_s",
+            Instr::F64ConvertI64S => "f64.convert_i64_s",
+            Instr::F64ConvertI64U => "f64.convert_i64_u",
+            Instr::I64ReinterpretF64 => "i64.reinterpret_f64",
+        }
+    }
+}
+
+// A host function the module calls, always from the "gm" namespace.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Import {
+    pub name: &'static str,
+    pub ty: FuncType,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub export: Option<String>,
+    pub params: Vec<(String, ValType)>,
+    pub result: Option<ValType>,
+    pub locals: Vec<(String, ValType)>,
+    pub body: Vec<Instr>,
+}
+
+impl Function {
+    pub fn ty(&self) -> FuncType {
+        FuncType {
+            params: self.params.iter().map(|(_, t)| *t).collect(),
+            results: self.result.into_iter().collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Global {
+    pub name: String,
+    pub ty: ValType,
+    pub init: Instr, // a constant
+}
+
+// Functions are numbered imports first, then `functions` in order. The
+// memory is exported as "memory", so hosts can read the texts they're
+// handed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module {
+    pub imports: Vec<Import>,
+    pub functions: Vec<Function>,
+    pub globals: Vec<Global>,
+    pub pages: u32,
+    pub data: Vec<(u32, Vec<u8>)>,
+}
+
+pub const HOST_MODULE: &str = "gm";
+
+impl Module {
+    // Every distinct function type, in the order first used by the
+    // imports and then the functions.
+    pub fn types(&self) -> Vec<FuncType> {
+        let mut types: Vec<FuncType> = Vec::new();
+        let all = self.imports.iter().map(|i| i.ty.clone()).chain(self.functions.iter().map(Function::ty));
+        for ty in all {
+            if !types.contains(&ty) {
+                types.push(ty);
+            }
+        }
+        types
+    }
+
+    fn function_name(&self, index: u32) -> String {
+        let index = index as usize;
+        match self.imports.get(index) {
+            Some(import) => format!("$gm.{}", import.name),
+            None => format!("${}", self.functions[index - self.imports.len()].name),
+        }
+    }
+
+    // The text format, using names rather than indices wherever the
+    // format allows.
+    pub fn to_wat(&self) -> String {
+        let mut out = String::from("(module\n");
+        for import in &self.imports {
+            let _ = writeln!(
+                out,
+                "  (import \"{}\" \"{}\" (func $gm.{}{}))",
+                HOST_MODULE,
+                import.name,
+                import.name,
+                signature(&import.ty.params.iter().map(|t| (None, *t)).collect::<Vec<_>>(), import.ty.results.first())
+            );
+        }
+        let _ = writeln!(out, "  (memory (export \"memory\") {})", self.pages);
+        for global in &self.globals {
+            let _ = writeln!(
+                out,
+                "  (global ${} (mut {}) ({}))",
+                global.name,
+                global.ty.name(),
+                instruction(&global.init, self, None)
+            );
+        }
+        for (offset, bytes) in &self.data {
+            let _ = writeln!(out, "  (data (i32.const {}) \"{}\")", offset, escape(bytes));
+        }
+        for function in &self.functions {
+            let export = match &function.export {
+                Some(name) => format!(" (export \"{}\")", name),
+                None => String::new(),
+            };
+            let params: Vec<(Option<&str>, ValType)> =
+                function.params.iter().map(|(name, t)| (Some(name.as_str()), *t)).collect();
+            let _ = writeln!(
+                out,
+                "  (func ${}{}{}",
+                function.name,
+                export,
+                signature(&params, function.result.as_ref())
+            );
+            for (name, ty) in &function.locals {
+                let _ = writeln!(out, "    (local ${} {})", name, ty.name());
+            }
+            let mut depth = 2;
+            for instr in &function.body {
+                if matches!(instr, Instr::End | Instr::Else) {
+                    depth -= 1;
+                }
+                let _ = writeln!(out, "{}{}", "  ".repeat(depth), instruction(instr, self, Some(function)));
+                if matches!(instr, Instr::Block(_) | Instr::Loop(_) | Instr::If(_) | Instr::Else) {
+                    depth += 1;
+                }
+            }
+            out.push_str("  )\n");
+        }
+        out.push_str(")\n");
+        out
+    }
+}
+
+fn signature(params: &[(Option<&str>, ValType)], result: Option<&ValType>) -> String {
+    let mut out = String::new();
+    let unnamed: Vec<&str> = params.iter().filter(|(name, _)| name.is_none()).map(|(_, t)| t.name()).collect();
+    if !unnamed.is_empty() {
+        let _ = write!(out, " (param {})", unnamed.join(" "));
+    }
+    for (name, ty) in params {
+        if let Some(name) = name {
+            let _ = write!(out, " (param ${} {})", name, ty.name());
+        }
+    }
+    if let Some(result) = result {
+        let _ = write!(out, " (result {})", result.name());
+    }
+    out
+}
+
+fn instruction(instr: &Instr, module: &Module, function: Option<&Function>) -> String {
+    let local = |index: u32| -> String {
+        let Some(function) = function else { return index.to_string() };
+        let index = index as usize;
+        match function.params.get(index) {
+            Some((name, _)) => format!("${}", name),
+            None => format!("${}", function.locals[index - function.params.len()].0),
+        }
+    };
+    let mnemonic = instr.mnemonic();
+    match instr {
+        Instr::Block(Some(t)) | Instr::Loop(Some(t)) | Instr::If(Some(t)) => {
+            format!("{} (result {})", mnemonic, t.name())
+        }
+        Instr::Br(depth) | Instr::BrIf(depth) => format!("{} {}", mnemonic, depth),
+        Instr::Call(index) => format!("{} {}", mnemonic, module.function_name(*index)),
+        Instr::LocalGet(i) | Instr::LocalSet(i) | Instr::LocalTee(i) => format!("{} {}", mnemonic, local(*i)),
+        Instr::GlobalGet(i) | Instr::GlobalSet(i) => format!("{} ${}", mnemonic, module.globals[*i as usize].name),
+        Instr::I32Load(offset) | Instr::I32Load8U(offset) | Instr::I32Store(offset) | Instr::I32Store8(offset)
+            if *offset != 0 =>
+        {
+            format!("{} offset={}", mnemonic, offset)
+        }
+        Instr::I32Const(n) => format!("{} {}", mnemonic, n),
+        Instr::I64Const(n) => format!("{} {}", mnemonic, n),
+        Instr::F64Const(n) => format!("{} {}", mnemonic, float(*n)),
+        _ => mnemonic.to_string(),
+    }
+}
+
+// Floats are written so the text format reads them back bit for bit.
+fn float(n: f64) -> String {
+    if n.is_nan() {
+        "nan".to_string()
+    } else if n.is_infinite() {
+        if n > 0.0 { "inf" } else { "-inf" }.to_string()
+    } else {
+        format!("{:?}", n)
+    }
+}
+
+fn escape(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for &b in bytes {
+        match b {
+            b'"' | b'\\' => {
+                out.push('\\');
+                out.push(b as char);
+            }
+            0x20..=0x7e => out.push(b as char),
+            _ => {
+                let _ = write!(out, "\\{:02x}", b);
+            }
+        }
+    }
+    out
+}
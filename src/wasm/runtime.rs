@@ -0,0 +1,342 @@
+use std::collections::HashMap;
+use super::module::{FuncType, Function, Import, Instr, ValType};
+
+// What generated code calls besides the script's own contracts: the host's
+// "gm" functions, and helpers the module carries for itself.
+//
+// Texts live in linear memory as a u32 length followed by their UTF-8
+// bytes, and are passed around as the address of the length. The empty
+// text sits at address 0, texts the module knows up front (literals,
+// messages) in one data segment from DATA_START, and everything worked out
+// while running comes from a bump allocator after that, never freed.
+
+pub const DATA_START: u32 = 8;
+
+// Room the host gets to write a number into: Rust prints no f64 longer.
+pub const NUMBER_ROOM: i32 = 340;
+
+// Floats hold every whole number up to 2^53 exactly, which is as far as
+// GM's integer operators go.
+const EXACT_FLOAT: f64 = 9007199254740992.0;
+
+// The kinds `gm.rekt` is told an error is, in the order of `Rekt`'s variants
+pub const LEXER: i32 = 0;
+pub const RUNTIME: i32 = 2;
+pub const TYPE: i32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Host {
+    Broadcast,  // (ptr, len): a broadcast, already rendered
+    NumberText, // (number, dest, room) -> len: writes the number as GM prints it
+    Rekt,       // (kind, ptr, len): the script failed with this error
+}
+
+impl Host {
+    fn import(self) -> Import {
+        let (name, params, results) = match self {
+            Host::Broadcast => ("broadcast", vec![ValType::I32, ValType::I32], vec![]),
+            Host::NumberText => ("number_text", vec![ValType::F64, ValType::I32, ValType::I32], vec![ValType::I32]),
+            Host::Rekt => ("rekt", vec![ValType::I32, ValType::I32, ValType::I32], vec![]),
+        };
+        Import { name, ty: FuncType { params, results } }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Helper {
+    Alloc,
+    Fail,
+    Emit,
+    TextOfNumber,
+    TextOfSignal,
+    Concat,
+    TextEq,
+    TextLen,
+    NumEq,
+    Whole,
+    FloorDiv,
+    Shl,
+    Shr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Callee {
+    Host(Host),
+    Mine(usize), // the script's contracts, in the order they're mined
+    Helper(Helper),
+}
+
+// Calls are written against `Callee`s and numbered once the module knows
+// which imports and helpers it ended up needing. Until then a `Call`
+// holds the callee's place in `callees`.
+#[derive(Default)]
+pub struct Linker {
+    pub callees: Vec<Callee>,
+    pub data: Vec<u8>,
+    texts: HashMap<String, u32>,
+}
+
+impl Linker {
+    pub fn call(&mut self, callee: Callee) -> Instr {
+        let index = match self.callees.iter().position(|c| *c == callee) {
+            Some(index) => index,
+            None => {
+                self.callees.push(callee);
+                self.callees.len() - 1
+            }
+        };
+        Instr::Call(index as u32)
+    }
+
+    // The address of `text`, laid out in the data segment the first time
+    // it's asked for.
+    pub fn text(&mut self, text: &str) -> u32 {
+        if text.is_empty() {
+            return 0;
+        }
+        if let Some(address) = self.texts.get(text) {
+            return *address;
+        }
+        let address = DATA_START + self.data.len() as u32;
+        self.data.extend_from_slice(&(text.len() as u32).to_le_bytes());
+        self.data.extend_from_slice(text.as_bytes());
+        while !self.data.len().is_multiple_of(4) {
+            self.data.push(0);
+        }
+        self.texts.insert(text.to_string(), address);
+        address
+    }
+
+    // Where the heap starts: just past the data.
+    pub fn data_end(&self) -> u32 {
+        DATA_START + self.data.len() as u32
+    }
+
+    pub fn imports(&self) -> Vec<(Host, Import)> {
+        let mut hosts: Vec<Host> = self
+            .callees
+            .iter()
+            .filter_map(|c| match c {
+                Callee::Host(host) => Some(*host),
+                _ => None,
+            })
+            .collect();
+        hosts.sort();
+        hosts.into_iter().map(|host| (host, host.import())).collect()
+    }
+}
+
+// Instructions with the locals of the function they're for, by name.
+struct Body<'a> {
+    link: &'a mut Linker,
+    names: Vec<&'static str>,
+    code: Vec<Instr>,
+}
+
+impl Body<'_> {
+    fn get(&mut self, name: &str) -> &mut Self {
+        let index = self.index(name);
+        self.code.push(Instr::LocalGet(index));
+        self
+    }
+
+    fn set(&mut self, name: &str) -> &mut Self {
+        let index = self.index(name);
+        self.code.push(Instr::LocalSet(index));
+        self
+    }
+
+    fn tee(&mut self, name: &str) -> &mut Self {
+        let index = self.index(name);
+        self.code.push(Instr::LocalTee(index));
+        self
+    }
+
+    fn index(&self, name: &str) -> u32 {
+        self.names.iter().position(|n| *n == name).unwrap_or(0) as u32
+    }
+
+    fn i32(&mut self, n: i32) -> &mut Self {
+        self.op(Instr::I32Const(n))
+    }
+
+    fn op(&mut self, instr: Instr) -> &mut Self {
+        self.code.push(instr);
+        self
+    }
+
+    fn call(&mut self, callee: Callee) -> &mut Self {
+        let call = self.link.call(callee);
+        self.op(call)
+    }
+
+    fn helper(&mut self, helper: Helper) -> &mut Self {
+        self.call(Callee::Helper(helper))
+    }
+
+    fn text(&mut self, text: &str) -> &mut Self {
+        let address = self.link.text(text);
+        self.i32(address as i32)
+    }
+
+    // Fails with the text on top of the stack, whose kind was pushed first.
+    fn fail(&mut self) -> &mut Self {
+        self.helper(Helper::Fail)
+    }
+
+    // Pushes `prefix`, then the text of the i64 in `local`, joined.
+    fn joined(&mut self, prefix: &str, local: &str) -> &mut Self {
+        self.text(prefix).get(local).op(Instr::F64ConvertI64S).helper(Helper::TextOfNumber).helper(Helper::Concat)
+    }
+
+    fn suffix(&mut self, suffix: &str) -> &mut Self {
+        self.text(suffix).helper(Helper::Concat)
+    }
+
+    // Shifts go from 0 to 63; `b` as unsigned catches negatives too
+    fn shift_range(&mut self) -> &mut Self {
+        self.get("b").op(Instr::I64Const(64)).op(Instr::I64GeU).op(Instr::If(None));
+        self.i32(RUNTIME).joined("Cannot shift by ", "b").suffix(", shifts go from 0 to 63").fail();
+        self.op(Instr::End)
+    }
+}
+
+type Locals = Vec<(&'static str, ValType)>;
+
+// The helper's function, named `gm.<helper>`. `heap` is the global
+// holding where the next allocation goes.
+pub fn helper(helper: Helper, link: &mut Linker, heap: u32) -> Function {
+    use Instr::*;
+    use ValType::{F64, I32, I64};
+
+    let (name, params, result, locals): (&str, Locals, Option<ValType>, Locals) =
+        match helper {
+            Helper::Alloc => ("alloc", vec![("size", I32)], Some(I32), vec![("ptr", I32), ("end", I32)]),
+            Helper::Fail => ("fail", vec![("kind", I32), ("text", I32)], None, vec![]),
+            Helper::Emit => ("emit", vec![("text", I32)], None, vec![]),
+            Helper::TextOfNumber => ("text_of_number", vec![("n", F64)], Some(I32), vec![("ptr", I32), ("len", I32)]),
+            Helper::TextOfSignal => ("text_of_signal", vec![("b", I32)], Some(I32), vec![]),
+            Helper::Concat => ("concat", vec![("a", I32), ("b", I32)], Some(I32), vec![("la", I32), ("lb", I32), ("ptr", I32)]),
+            Helper::TextEq => ("text_eq", vec![("a", I32), ("b", I32)], Some(I32), vec![("len", I32), ("i", I32)]),
+            Helper::TextLen => ("text_len", vec![("text", I32)], Some(F64), vec![("i", I32), ("count", F64)]),
+            Helper::NumEq => ("num_eq", vec![("a", F64), ("b", F64)], Some(I32), vec![]),
+            Helper::Whole => ("whole", vec![("n", F64), ("prefix", I32)], Some(I64), vec![]),
+            Helper::FloorDiv => ("floor_div", vec![("a", I64), ("b", I64)], Some(I64), vec![("q", I64)]),
+            Helper::Shl => ("shl", vec![("a", I64), ("b", I64)], Some(I64), vec![("abs", I64)]),
+            Helper::Shr => ("shr", vec![("a", I64), ("b", I64)], Some(I64), vec![]),
+        };
+    let names = params.iter().chain(&locals).map(|(n, _)| *n).collect();
+    let mut b = Body { link, names, code: Vec::new() };
+
+    match helper {
+        Helper::Alloc => {
+            b.op(GlobalGet(heap)).set("ptr");
+            // Rounded up so every text's length stays aligned
+            b.get("ptr").get("size").op(I32Add).i32(3).op(I32Add).i32(-4).op(I32And).set("end");
+            b.get("end").op(MemorySize).i32(65_536).op(I32Mul).op(I32GtU).op(If(None));
+            b.get("end").op(MemorySize).i32(65_536).op(I32Mul).op(I32Sub).i32(65_535).op(I32Add).i32(16).op(I32ShrU);
+            b.op(MemoryGrow).i32(-1).op(I32Eq).op(If(None)).op(Unreachable).op(End);
+            b.op(End);
+            b.get("end").op(GlobalSet(heap)).get("ptr");
+        }
+        Helper::Fail => {
+            b.get("kind").get("text").i32(4).op(I32Add).get("text").op(I32Load(0));
+            b.call(Callee::Host(Host::Rekt)).op(Unreachable);
+        }
+        Helper::Emit => {
+            b.get("text").i32(4).op(I32Add).get("text").op(I32Load(0));
+            b.call(Callee::Host(Host::Broadcast));
+        }
+        Helper::TextOfNumber => {
+            b.i32(NUMBER_ROOM + 4).helper(Helper::Alloc).set("ptr");
+            b.get("n").get("ptr").i32(4).op(I32Add).i32(NUMBER_ROOM).call(Callee::Host(Host::NumberText)).set("len");
+            b.get("ptr").get("len").op(I32Store(0));
+            // Hands back the room the number didn't use
+            b.get("ptr").get("len").op(I32Add).i32(7).op(I32Add).i32(-4).op(I32And).op(GlobalSet(heap));
+            b.get("ptr");
+        }
+        Helper::TextOfSignal => {
+            b.text("true").text("false").get("b").op(Select);
+        }
+        Helper::Concat => {
+            b.get("a").op(I32Load(0)).set("la");
+            b.get("b").op(I32Load(0)).set("lb");
+            b.get("la").get("lb").op(I32Add).i32(4).op(I32Add).helper(Helper::Alloc).set("ptr");
+            b.get("ptr").get("la").get("lb").op(I32Add).op(I32Store(0));
+            b.get("ptr").i32(4).op(I32Add).get("a").i32(4).op(I32Add).get("la").op(MemoryCopy);
+            b.get("ptr").i32(4).op(I32Add).get("la").op(I32Add).get("b").i32(4).op(I32Add).get("lb").op(MemoryCopy);
+            b.get("ptr");
+        }
+        Helper::TextEq => {
+            b.get("a").op(I32Load(0)).tee("len").get("b").op(I32Load(0)).op(I32Ne);
+            b.op(If(None)).i32(0).op(Return).op(End);
+            b.op(Block(None)).op(Loop(None));
+            b.get("i").get("len").op(I32GeU).op(BrIf(1));
+            b.get("a").get("i").op(I32Add).op(I32Load8U(4));
+            b.get("b").get("i").op(I32Add).op(I32Load8U(4));
+            b.op(I32Ne).op(If(None)).i32(0).op(Return).op(End);
+            b.get("i").i32(1).op(I32Add).set("i").op(Br(0));
+            b.op(End).op(End);
+            b.i32(1);
+        }
+        Helper::TextLen => {
+            // Counts characters, which is every byte but UTF-8's continuations
+            b.op(Block(None)).op(Loop(None));
+            b.get("i").get("text").op(I32Load(0)).op(I32GeU).op(BrIf(1));
+            b.get("text").get("i").op(I32Add).op(I32Load8U(4)).i32(0xc0).op(I32And).i32(0x80).op(I32Ne);
+            b.op(If(None)).get("count").op(F64Const(1.0)).op(F64Add).set("count").op(End);
+            b.get("i").i32(1).op(I32Add).set("i").op(Br(0));
+            b.op(End).op(End);
+            b.get("count");
+        }
+        Helper::NumEq => {
+            // Numbers are equal when they print the same: bit for bit, or both NaN
+            b.get("a").op(I64ReinterpretF64).get("b").op(I64ReinterpretF64).op(I64Eq);
+            b.get("a").get("a").op(F64Ne).get("b").get("b").op(F64Ne).op(I32And);
+            b.op(I32Or);
+        }
+        Helper::Whole => {
+            b.get("n").op(F64Trunc).get("n").op(F64Eq);
+            b.get("n").op(F64Abs).op(F64Const(EXACT_FLOAT)).op(F64Le);
+            b.op(I32And).op(I32Eqz).op(If(None));
+            b.i32(TYPE).get("prefix").get("n").helper(Helper::TextOfNumber).helper(Helper::Concat).fail();
+            b.op(End);
+            b.get("n").op(I64TruncF64S);
+        }
+        Helper::FloorDiv => {
+            b.get("b").op(I64Eqz).op(If(None));
+            b.i32(RUNTIME).text("You just got rekt by zero!").fail();
+            b.op(End);
+            b.get("a").get("b").op(I64DivS).set("q");
+            // Rounds down rather than toward zero
+            b.get("a").get("b").op(I64RemS).op(I64Const(0)).op(I64Ne);
+            b.get("a").op(I64Const(0)).op(I64LtS).get("b").op(I64Const(0)).op(I64LtS).op(I32Ne);
+            b.op(I32And).op(If(None)).get("q").op(I64Const(1)).op(I64Sub).set("q").op(End);
+            b.get("q");
+        }
+        Helper::Shl => {
+            b.shift_range();
+            // |a| * 2^b has to stay within 2^53
+            b.op(I64Const(0)).get("a").op(I64Sub).get("a").get("a").op(I64Const(0)).op(I64LtS).op(Select).set("abs");
+            b.get("abs").op(I64Const(EXACT_FLOAT as i64)).get("b").op(I64ShrS).op(I64GtS).op(If(None));
+            b.i32(RUNTIME).get("a").op(F64ConvertI64S).helper(Helper::TextOfNumber);
+            b.joined(" << ", "b").helper(Helper::Concat);
+            b.suffix(" is too big for a number, use a uint 📉").fail();
+            b.op(End);
+            b.get("a").get("b").op(I64Shl);
+        }
+        Helper::Shr => {
+            b.shift_range();
+            b.get("a").get("b").op(I64ShrS);
+        }
+    }
+
+    Function {
+        name: format!("gm.{}", name),
+        export: None,
+        params: params.into_iter().map(|(n, t)| (n.to_string(), t)).collect(),
+        result,
+        locals: locals.into_iter().map(|(n, t)| (n.to_string(), t)).collect(),
+        body: b.code,
+    }
+}
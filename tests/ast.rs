@@ -0,0 +1,11 @@
+// Syntax trees written as JSON and read back, against the scripts they
+// came from and the goldens, over every script in examples/ast.
+
+mod common;
+
+use gm_language::runner;
+
+#[test]
+fn trees_round_trip_through_json() {
+    common::assert_agree("ast", |script| runner::compare_ast_file(script));
+}
@@ -0,0 +1,75 @@
+// What the backend tests share: finding the example scripts and running
+// each one through a comparison.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use gm_language::error::Rekt;
+use gm_language::runner::{BackendComparison, Comparison};
+
+// The .gm scripts in one of the examples directories, in name order.
+pub fn scripts(dir: &str) -> Vec<PathBuf> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("examples").join(dir);
+    let mut scripts: Vec<PathBuf> = fs::read_dir(&dir)
+        .unwrap_or_else(|e| panic!("can't read {}: {}", dir.display(), e))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "gm"))
+        .collect();
+    scripts.sort();
+    assert!(!scripts.is_empty(), "no scripts in {}", dir.display());
+    scripts
+}
+
+// Whether `tool` is on the path. Tests that need one skip without it.
+#[allow(dead_code)]
+pub fn have(tool: &str) -> bool {
+    let found = Command::new(tool).arg("--version").output().is_ok();
+    if !found {
+        eprintln!("skipping: {} isn't on the path", tool);
+    }
+    found
+}
+
+// A backend's comparison over a script in examples/diff, which keeps no
+// goldens. Scripts the backend says it can't compile are skipped.
+#[allow(dead_code)]
+pub fn where_supported(compared: Result<BackendComparison, Rekt>) -> Result<Option<BackendComparison>, Rekt> {
+    match compared {
+        Err(Rekt::Type(why)) if why.contains("can't be compiled to") => Ok(None),
+        compared => compared.map(|c| Some(c.shared())),
+    }
+}
+
+// The tree walker recurses deeper than a test thread's default stack
+// allows, so the scripts run on a thread with a generous one.
+const STACK: usize = 64 * 1024 * 1024;
+
+// Compares every script in `dir` and fails with everything that disagreed.
+pub fn assert_agree<C: Comparison>(
+    dir: &'static str,
+    compare: impl Fn(&Path) -> Result<C, Rekt> + Send + 'static,
+) {
+    let failures = thread::Builder::new()
+        .stack_size(STACK)
+        .spawn(move || disagreements(dir, compare))
+        .unwrap()
+        .join()
+        .unwrap();
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+fn disagreements<C: Comparison>(dir: &str, compare: impl Fn(&Path) -> Result<C, Rekt>) -> Vec<String> {
+    let mut failures = Vec::new();
+    for script in scripts(dir) {
+        match compare(&script) {
+            Ok(comparison) => {
+                for mismatch in comparison.mismatches() {
+                    failures.push(format!("{}: {}", script.display(), mismatch));
+                }
+            }
+            Err(e) => failures.push(format!("{}: {}", script.display(), e)),
+        }
+    }
+    failures
+}
@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use gm_language::error::Rekt;
+use gm_language::evm::asm::*;
+use gm_language::numeric::U256;
+
+// Just enough of the EVM to deploy a contract and call it: the stack,
+// memory, storage, logs, jumps and returns, with no accounts, value or
+// calls between contracts. Gas is a plain budget with every instruction
+// costing one, so a script that loops forever still stops.
+const STACK_LIMIT: usize = 1024;
+const GAS: u64 = 100_000_000;
+const MEMORY_LIMIT: usize = 1 << 24;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Log {
+    pub topics: Vec<U256>,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum End {
+    Returned(Vec<u8>),
+    Reverted(Vec<u8>),
+    Failed(String), // an exceptional halt: bad jump, stack overflow, out of gas
+}
+
+// What a call did. Logs from a call that reverted are kept, the way a
+// trace shows them, though a chain would throw them away.
+#[derive(Debug)]
+pub struct Call {
+    pub logs: Vec<Log>,
+    pub end: End,
+}
+
+pub struct Contract {
+    pub code: Vec<u8>,
+    pub storage: HashMap<U256, U256>,
+}
+
+// Runs the code that creates a contract; what it returns is the contract's code.
+pub fn deploy(initcode: &[u8]) -> Result<Contract, Rekt> {
+    let mut storage = HashMap::new();
+    let call = execute(initcode, &[], &mut storage);
+    match call.end {
+        End::Returned(code) => Ok(Contract { code, storage }),
+        End::Reverted(_) => Err(Rekt::Runtime("EVM error: deployment reverted".to_string())),
+        End::Failed(why) => Err(Rekt::Runtime(format!("EVM error: deployment failed: {}", why))),
+    }
+}
+
+impl Contract {
+    // Calls the contract; storage only changes if the call returns.
+    pub fn call(&mut self, calldata: &[u8]) -> Call {
+        let mut storage = self.storage.clone();
+        let call = execute(&self.code, calldata, &mut storage);
+        if let End::Returned(_) = call.end {
+            self.storage = storage;
+        }
+        call
+    }
+}
+
+// Where a jump may land: a JUMPDEST that isn't push data.
+fn jump_dests(code: &[u8]) -> Vec<bool> {
+    let mut dests = vec![false; code.len()];
+    let mut pc = 0;
+    while pc < code.len() {
+        if code[pc] == JUMPDEST {
+            dests[pc] = true;
+        }
+        pc += 1 + immediate(code[pc]);
+    }
+    dests
+}
+
+fn is_negative(word: U256) -> bool {
+    word.to_be_bytes()[0] & 0x80 != 0
+}
+
+fn negate(word: U256) -> U256 {
+    word.not().wrapping_add(U256::ONE)
+}
+
+fn abs(word: U256) -> U256 {
+    if is_negative(word) { negate(word) } else { word }
+}
+
+fn flag(b: bool) -> U256 {
+    if b { U256::ONE } else { U256::ZERO }
+}
+
+fn signed_less(a: U256, b: U256) -> bool {
+    match (is_negative(a), is_negative(b)) {
+        (true, false) => true,
+        (false, true) => false,
+        _ => a < b,
+    }
+}
+
+// Quotient rounded toward zero; dividing by zero gives zero.
+fn sdiv(a: U256, b: U256) -> U256 {
+    let Some((q, _)) = abs(a).div_rem(abs(b)) else { return U256::ZERO };
+    if is_negative(a) != is_negative(b) { negate(q) } else { q }
+}
+
+// Remainder with the sign of the dividend.
+fn smod(a: U256, b: U256) -> U256 {
+    let Some((_, r)) = abs(a).div_rem(abs(b)) else { return U256::ZERO };
+    if is_negative(a) { negate(r) } else { r }
+}
+
+fn shift_amount(word: U256) -> u32 {
+    word.as_u64().map(|n| n.min(256) as u32).unwrap_or(256)
+}
+
+fn word_of(bytes: &[u8]) -> U256 {
+    let mut word = [0u8; 32];
+    word[..bytes.len()].copy_from_slice(bytes);
+    U256::from_be_bytes(word)
+}
+
+fn index(word: U256) -> Option<usize> {
+    word.as_u64().map(|n| n as usize).filter(|n| *n <= MEMORY_LIMIT)
+}
+
+struct Frame<'a> {
+    code: &'a [u8],
+    calldata: &'a [u8],
+    stack: Vec<U256>,
+    memory: Vec<u8>,
+    logs: Vec<Log>,
+}
+
+impl Frame<'_> {
+    fn pop(&mut self) -> Result<U256, String> {
+        self.stack.pop().ok_or_else(|| "stack underflow".to_string())
+    }
+
+    fn push(&mut self, word: U256) -> Result<(), String> {
+        if self.stack.len() == STACK_LIMIT {
+            return Err("stack overflow".to_string());
+        }
+        self.stack.push(word);
+        Ok(())
+    }
+
+    // Grows memory, in whole words, to cover `len` bytes from `offset`.
+    fn touch(&mut self, offset: U256, len: U256) -> Result<(usize, usize), String> {
+        let len = index(len).ok_or("out of memory")?;
+        if len == 0 {
+            return Ok((0, 0));
+        }
+        let offset = index(offset).ok_or("out of memory")?;
+        let end = offset + len;
+        if end > MEMORY_LIMIT {
+            return Err("out of memory".to_string());
+        }
+        if end > self.memory.len() {
+            self.memory.resize(end.div_ceil(32) * 32, 0);
+        }
+        Ok((offset, len))
+    }
+
+    fn read(&mut self, offset: U256, len: U256) -> Result<Vec<u8>, String> {
+        let (offset, len) = self.touch(offset, len)?;
+        Ok(self.memory[offset..offset + len].to_vec())
+    }
+}
+
+fn execute(code: &[u8], calldata: &[u8], storage: &mut HashMap<U256, U256>) -> Call {
+    let mut frame = Frame { code, calldata, stack: Vec::new(), memory: Vec::new(), logs: Vec::new() };
+    let end = run(&mut frame, storage).unwrap_or_else(End::Failed);
+    Call { logs: frame.logs, end }
+}
+
+fn run(f: &mut Frame, storage: &mut HashMap<U256, U256>) -> Result<End, String> {
+    let dests = jump_dests(f.code);
+    let mut pc = 0;
+    let mut gas = GAS;
+    loop {
+        if gas == 0 {
+            return Err("out of gas".to_string());
+        }
+        gas -= 1;
+        let Some(&op) = f.code.get(pc) else { return Ok(End::Returned(Vec::new())) };
+        pc += 1;
+        match op {
+            STOP => return Ok(End::Returned(Vec::new())),
+            ADD | MUL | SUB | DIV | SDIV | MOD | SMOD | LT | GT | SLT | SGT | EQ | AND | OR | XOR | SHL | SHR
+            | SAR => {
+                let (a, b) = (f.pop()?, f.pop()?);
+                let result = match op {
+                    ADD => a.wrapping_add(b),
+                    MUL => a.wrapping_mul(b),
+                    SUB => a.wrapping_sub(b),
+                    DIV => a.div_rem(b).map(|(q, _)| q).unwrap_or(U256::ZERO),
+                    SDIV => sdiv(a, b),
+                    MOD => a.div_rem(b).map(|(_, r)| r).unwrap_or(U256::ZERO),
+                    SMOD => smod(a, b),
+                    LT => flag(a < b),
+                    GT => flag(a > b),
+                    SLT => flag(signed_less(a, b)),
+                    SGT => flag(signed_less(b, a)),
+                    EQ => flag(a == b),
+                    AND => a.bitand(b),
+                    OR => a.bitor(b),
+                    XOR => a.bitxor(b),
+                    // The shift comes first, then the value shifted
+                    SHL => b.shl(shift_amount(a)),
+                    SHR => b.shr(shift_amount(a)),
+                    _ => match (is_negative(b), shift_amount(a)) {
+                        (false, n) => b.shr(n),
+                        (true, 256) => U256::MAX,
+                        (true, n) => b.shr(n).bitor(U256::MAX.shr(n).not()),
+                    },
+                };
+                f.push(result)?;
+            }
+            ISZERO => {
+                let a = f.pop()?;
+                f.push(flag(a.is_zero()))?;
+            }
+            NOT => {
+                let a = f.pop()?;
+                f.push(a.not())?;
+            }
+            CALLVALUE => f.push(U256::ZERO)?,
+            CALLDATALOAD => {
+                let at = f.pop()?;
+                let bytes: Vec<u8> = (0..32)
+                    .map(|i| index(at).and_then(|at| f.calldata.get(at + i)).copied().unwrap_or(0))
+                    .collect();
+                f.push(word_of(&bytes))?;
+            }
+            CALLDATASIZE => f.push(U256::from_u64(f.calldata.len() as u64))?,
+            CODESIZE => f.push(U256::from_u64(f.code.len() as u64))?,
+            CODECOPY => {
+                let (dest, from, len) = (f.pop()?, f.pop()?, f.pop()?);
+                let (dest, len) = f.touch(dest, len)?;
+                for i in 0..len {
+                    f.memory[dest + i] = index(from).and_then(|from| f.code.get(from + i)).copied().unwrap_or(0);
+                }
+            }
+            POP => {
+                f.pop()?;
+            }
+            MLOAD => {
+                let at = f.pop()?;
+                let bytes = f.read(at, U256::from_u64(32))?;
+                f.push(word_of(&bytes))?;
+            }
+            MSTORE => {
+                let (at, value) = (f.pop()?, f.pop()?);
+                let (at, _) = f.touch(at, U256::from_u64(32))?;
+                f.memory[at..at + 32].copy_from_slice(&value.to_be_bytes());
+            }
+            MSTORE8 => {
+                let (at, value) = (f.pop()?, f.pop()?);
+                let (at, _) = f.touch(at, U256::ONE)?;
+                f.memory[at] = value.to_be_bytes()[31];
+            }
+            SLOAD => {
+                let slot = f.pop()?;
+                f.push(storage.get(&slot).copied().unwrap_or(U256::ZERO))?;
+            }
+            SSTORE => {
+                let (slot, value) = (f.pop()?, f.pop()?);
+                storage.insert(slot, value);
+            }
+            JUMP | JUMPI => {
+                let dest = f.pop()?;
+                let taken = op == JUMP || !f.pop()?.is_zero();
+                if taken {
+                    match index(dest).filter(|d| dests.get(*d) == Some(&true)) {
+                        Some(dest) => pc = dest,
+                        None => return Err(format!("bad jump to {}", dest)),
+                    }
+                }
+            }
+            PC => f.push(U256::from_u64(pc as u64 - 1))?,
+            MSIZE => f.push(U256::from_u64(f.memory.len() as u64))?,
+            JUMPDEST => {}
+            PUSH0..=PUSH32 => {
+                let n = immediate(op);
+                let mut bytes = [0u8; 32];
+                for i in 0..n {
+                    bytes[32 - n + i] = f.code.get(pc + i).copied().unwrap_or(0);
+                }
+                pc += n;
+                f.push(U256::from_be_bytes(bytes))?;
+            }
+            DUP1..=DUP16 => {
+                let depth = (op - DUP1) as usize;
+                let word = *f.stack.iter().rev().nth(depth).ok_or("stack underflow")?;
+                f.push(word)?;
+            }
+            SWAP1..=SWAP16 => {
+                let depth = (op - SWAP1) as usize + 1;
+                let top = f.stack.len().checked_sub(1).ok_or("stack underflow")?;
+                let other = top.checked_sub(depth).ok_or("stack underflow")?;
+                f.stack.swap(top, other);
+            }
+            LOG0..=LOG4 => {
+                let (offset, len) = (f.pop()?, f.pop()?);
+                let mut topics = Vec::new();
+                for _ in 0..op - LOG0 {
+                    topics.push(f.pop()?);
+                }
+                let data = f.read(offset, len)?;
+                f.logs.push(Log { topics, data });
+            }
+            RETURN | REVERT => {
+                let (offset, len) = (f.pop()?, f.pop()?);
+                let data = f.read(offset, len)?;
+                return Ok(if op == RETURN { End::Returned(data) } else { End::Reverted(data) });
+            }
+            INVALID => return Err("invalid instruction".to_string()),
+            other => return Err(format!("unknown opcode 0x{:02x}", other)),
+        }
+    }
+}
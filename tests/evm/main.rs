@@ -0,0 +1,75 @@
+// Each script in examples/evm compiled to a contract, deployed on the
+// machine in `machine.rs` and called, against the tree walker and the
+// goldens, then the scripts in examples/diff the compiler supports.
+
+#[path = "../common/mod.rs"]
+mod common;
+mod machine;
+
+use std::path::Path;
+use gm_language::error::Rekt;
+use gm_language::evm::{self, abi};
+use gm_language::numeric::U256;
+use gm_language::runner::{self, BackendComparison, Golden, Outcome};
+use machine::End;
+
+// Runs a script on the tree walker, then deploys its contract on the
+// machine and calls `run()`. Events are read back by their topic, and a
+// revert by its `Error(string)`, of which only the first line is compared.
+fn compare_evm_file(path: &Path) -> Result<BackendComparison, Rekt> {
+    let ast = runner::load(path)?;
+    let compiled = evm::compile(&ast)?;
+    let golden = Golden::check(path, "asm", "--evm", &compiled.listing());
+    let machine = run_evm(&compiled)?;
+
+    Ok(BackendComparison {
+        backend: "evm",
+        tree_walker: runner::walk(ast).headline(),
+        outcome: machine,
+        golden,
+        notes: Vec::new(),
+    })
+}
+
+fn run_evm(compiled: &evm::Compiled) -> Result<Outcome, Rekt> {
+    let mut contract = machine::deploy(&compiled.initcode())?;
+    let run = compiled.functions[0].1;
+    let call = contract.call(&run.to_be_bytes());
+    let (int, boolean, text) = (
+        abi::topic(abi::INT_EVENT),
+        abi::topic(abi::BOOL_EVENT),
+        abi::topic(abi::TEXT_EVENT),
+    );
+    let mut events = Vec::new();
+    for log in &call.logs {
+        let word = || {
+            let mut bytes = [0u8; 32];
+            let data = &log.data[..log.data.len().min(32)];
+            bytes[..data.len()].copy_from_slice(data);
+            U256::from_be_bytes(bytes)
+        };
+        let event = match log.topics.first() {
+            Some(topic) if *topic == int => abi::signed(word()),
+            Some(topic) if *topic == boolean => (!word().is_zero()).to_string(),
+            Some(topic) if *topic == text => abi::decode_string(&log.data).unwrap_or_default(),
+            _ => return Err(Rekt::Runtime("EVM error: unknown event".to_string())),
+        };
+        events.push(event);
+    }
+    let error = match call.end {
+        End::Returned(_) => None,
+        End::Reverted(data) => Some(abi::decode_error(&data).unwrap_or_else(|| "EVM error: reverted".to_string())),
+        End::Failed(why) => Some(format!("EVM error: {}", why)),
+    };
+    Ok(Outcome { events, error }.headline())
+}
+
+#[test]
+fn contracts_agree_with_the_tree_walker() {
+    common::assert_agree("evm", compare_evm_file);
+}
+
+#[test]
+fn shared_scripts_agree_where_they_compile() {
+    common::assert_agree("diff", |script| common::where_supported(compare_evm_file(script)));
+}
@@ -0,0 +1,15 @@
+// The transpiled JavaScript on node against the tree walker and the goldens,
+// over every script in examples/js and examples/diff.
+
+mod common;
+
+use gm_language::runner::{self, BackendComparison};
+
+#[test]
+fn node_agrees_with_the_tree_walker() {
+    if !common::have("node") {
+        return;
+    }
+    common::assert_agree("js", |script| runner::compare_js_file(script));
+    common::assert_agree("diff", |script| runner::compare_js_file(script).map(BackendComparison::shared));
+}
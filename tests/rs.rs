@@ -0,0 +1,15 @@
+// The transpiled Rust, built with cargo, against the tree walker and the
+// goldens, over every script in examples/rs and examples/diff.
+
+mod common;
+
+use gm_language::runner::{self, BackendComparison};
+
+#[test]
+fn native_build_agrees_with_the_tree_walker() {
+    if !common::have("cargo") {
+        return;
+    }
+    common::assert_agree("rs", |script| runner::compare_rs_file(script));
+    common::assert_agree("diff", |script| runner::compare_rs_file(script).map(BackendComparison::shared));
+}
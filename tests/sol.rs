@@ -0,0 +1,11 @@
+// The Solidity transpiler against its goldens, over every script in
+// examples/sol.
+
+mod common;
+
+use gm_language::runner;
+
+#[test]
+fn solidity_matches_the_goldens() {
+    common::assert_agree("sol", |script| runner::compare_sol_file(script));
+}
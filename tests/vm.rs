@@ -0,0 +1,10 @@
+// The VM against the tree walker, over every script in examples/diff.
+
+mod common;
+
+use gm_language::runner;
+
+#[test]
+fn vm_agrees_with_the_tree_walker() {
+    common::assert_agree("diff", |script| runner::compare_file(script));
+}
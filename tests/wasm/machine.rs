@@ -0,0 +1,722 @@
+use gm_language::error::Rekt;
+use gm_language::wasm::encode::*;
+use gm_language::wasm::module::{FuncType, Instr, ValType, HOST_MODULE};
+
+// A small WebAssembly interpreter for the modules the code generator
+// writes, so they can be run and checked without leaving the process. It
+// reads the binary format back (any encoder's, as long as the module keeps
+// to the instructions in `Instr`) and provides the "gm" host functions.
+
+// Deep enough for the recursion scripts do, shallow enough that the
+// interpreter's own stack holds.
+const MAX_DEPTH: usize = 2_000;
+const PAGE: usize = 65_536;
+const FRAME_ROOM: usize = 64 * 1024;
+
+// What an f64 has to be below to fit in an i64.
+const TWO_63: f64 = 9_223_372_036_854_775_808.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Val {
+    I32(i32),
+    I64(i64),
+    F64(f64),
+}
+
+impl Val {
+    fn zero(ty: ValType) -> Val {
+        match ty {
+            ValType::I32 => Val::I32(0),
+            ValType::I64 => Val::I64(0),
+            ValType::F64 => Val::F64(0.0),
+        }
+    }
+}
+
+struct Body {
+    locals: Vec<ValType>,
+    code: Vec<Instr>,
+    ends: Vec<usize>,  // for each block, loop and if: where its `end` is
+    elses: Vec<usize>, // for each if: where its `else` is, or its `end`
+}
+
+// A decoded module, ready to run.
+pub struct Decoded {
+    types: Vec<FuncType>,
+    imports: Vec<(String, u32)>, // name and type, all from "gm"
+    functions: Vec<(u32, Body)>,
+    exports: Vec<(String, u32)>,
+    pages: u32,
+    globals: Vec<Val>,
+    data: Vec<(u32, Vec<u8>)>,
+}
+
+// What a run did: everything broadcast, and the error it stopped on.
+pub struct Run {
+    pub events: Vec<String>,
+    pub error: Option<String>,
+}
+
+fn malformed(what: &str) -> Rekt {
+    Rekt::Runtime(format!("Malformed WebAssembly module: {}", what))
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Result<u8, Rekt> {
+        let b = *self.bytes.get(self.pos).ok_or_else(|| malformed("unexpected end"))?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Rekt> {
+        let end = self.pos.checked_add(n).filter(|end| *end <= self.bytes.len());
+        let end = end.ok_or_else(|| malformed("unexpected end"))?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn unsigned(&mut self) -> Result<u64, Rekt> {
+        let (mut n, mut shift) = (0u64, 0);
+        loop {
+            let b = self.byte()?;
+            if shift >= 64 {
+                return Err(malformed("integer too long"));
+            }
+            n |= ((b & 0x7f) as u64) << shift;
+            shift += 7;
+            if b & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+    }
+
+    fn u32(&mut self) -> Result<u32, Rekt> {
+        u32::try_from(self.unsigned()?).map_err(|_| malformed("integer too big"))
+    }
+
+    fn signed(&mut self) -> Result<i64, Rekt> {
+        let (mut n, mut shift) = (0i64, 0);
+        loop {
+            let b = self.byte()?;
+            if shift >= 64 {
+                return Err(malformed("integer too long"));
+            }
+            n |= ((b & 0x7f) as i64) << shift;
+            shift += 7;
+            if b & 0x80 == 0 {
+                if shift < 64 && b & 0x40 != 0 {
+                    n |= -1 << shift;
+                }
+                return Ok(n);
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<String, Rekt> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| malformed("name isn't UTF-8"))
+    }
+
+    fn valtype(&mut self) -> Result<ValType, Rekt> {
+        match self.byte()? {
+            0x7f => Ok(ValType::I32),
+            0x7e => Ok(ValType::I64),
+            0x7c => Ok(ValType::F64),
+            other => Err(malformed(&format!("unsupported value type 0x{:02x}", other))),
+        }
+    }
+
+    fn valtypes(&mut self) -> Result<Vec<ValType>, Rekt> {
+        let count = self.u32()?;
+        (0..count).map(|_| self.valtype()).collect()
+    }
+
+    fn block_type(&mut self) -> Result<Option<ValType>, Rekt> {
+        if self.bytes.get(self.pos) == Some(&EMPTY_BLOCK) {
+            self.pos += 1;
+            return Ok(None);
+        }
+        self.valtype().map(Some)
+    }
+
+    // Reads instructions up to and including the `end` that closes the
+    // expression, which is left off.
+    fn expression(&mut self) -> Result<Vec<Instr>, Rekt> {
+        let mut code = Vec::new();
+        let mut depth = 0;
+        loop {
+            let instr = self.instruction()?;
+            match instr {
+                Instr::Block(_) | Instr::Loop(_) | Instr::If(_) => depth += 1,
+                Instr::End if depth == 0 => return Ok(code),
+                Instr::End => depth -= 1,
+                _ => {}
+            }
+            code.push(instr);
+        }
+    }
+
+    fn memarg(&mut self) -> Result<u32, Rekt> {
+        self.u32()?; // alignment is only a hint
+        self.u32()
+    }
+
+    fn instruction(&mut self) -> Result<Instr, Rekt> {
+        let opcode = self.byte()?;
+        Ok(match opcode {
+            0x02 => Instr::Block(self.block_type()?),
+            0x03 => Instr::Loop(self.block_type()?),
+            0x04 => Instr::If(self.block_type()?),
+            0x0c => Instr::Br(self.u32()?),
+            0x0d => Instr::BrIf(self.u32()?),
+            0x10 => Instr::Call(self.u32()?),
+            0x20 => Instr::LocalGet(self.u32()?),
+            0x21 => Instr::LocalSet(self.u32()?),
+            0x22 => Instr::LocalTee(self.u32()?),
+            0x23 => Instr::GlobalGet(self.u32()?),
+            0x24 => Instr::GlobalSet(self.u32()?),
+            0x28 => Instr::I32Load(self.memarg()?),
+            0x2d => Instr::I32Load8U(self.memarg()?),
+            0x36 => Instr::I32Store(self.memarg()?),
+            0x3a => Instr::I32Store8(self.memarg()?),
+            0x3f | 0x40 => {
+                self.byte()?; // memory index
+                if opcode == 0x3f { Instr::MemorySize } else { Instr::MemoryGrow }
+            }
+            0x41 => Instr::I32Const(self.signed()? as i32),
+            0x42 => Instr::I64Const(self.signed()?),
+            0x44 => Instr::F64Const(f64::from_le_bytes(self.take(8)?.try_into().unwrap_or([0; 8]))),
+            MISC_PREFIX => match self.u32()? {
+                MEMORY_COPY => {
+                    self.take(2)?; // both memory indices
+                    Instr::MemoryCopy
+                }
+                other => return Err(malformed(&format!("unsupported instruction 0xfc {}", other))),
+            },
+            other => match OPCODES.iter().find(|(code, _)| *code == other) {
+                Some((_, instr)) => *instr,
+                None => return Err(malformed(&format!("unsupported instruction 0x{:02x}", other))),
+            },
+        })
+    }
+
+    fn constant(&mut self) -> Result<Val, Rekt> {
+        match self.expression()?.as_slice() {
+            [Instr::I32Const(n)] => Ok(Val::I32(*n)),
+            [Instr::I64Const(n)] => Ok(Val::I64(*n)),
+            [Instr::F64Const(n)] => Ok(Val::F64(*n)),
+            _ => Err(malformed("initializer isn't a constant")),
+        }
+    }
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Decoded, Rekt> {
+    let mut r = Reader { bytes, pos: 0 };
+    if r.take(4)? != MAGIC || r.take(4)? != VERSION.to_le_bytes() {
+        return Err(malformed("not a version 1 WebAssembly module"));
+    }
+    let mut module = Decoded {
+        types: Vec::new(),
+        imports: Vec::new(),
+        functions: Vec::new(),
+        exports: Vec::new(),
+        pages: 0,
+        globals: Vec::new(),
+        data: Vec::new(),
+    };
+    let mut declared = Vec::new();
+    while r.pos < bytes.len() {
+        let id = r.byte()?;
+        let size = r.u32()? as usize;
+        let mut s = Reader { bytes: r.take(size)?, pos: 0 };
+        if id == 0 {
+            continue; // custom sections, like names, don't change what runs
+        }
+        let count = s.u32()?;
+        for _ in 0..count {
+            match id {
+                TYPE_SECTION => {
+                    if s.byte()? != FUNC_TYPE {
+                        return Err(malformed("type isn't a function type"));
+                    }
+                    let params = s.valtypes()?;
+                    let results = s.valtypes()?;
+                    module.types.push(FuncType { params, results });
+                }
+                IMPORT_SECTION => {
+                    let from = s.name()?;
+                    let name = s.name()?;
+                    if from != HOST_MODULE || s.byte()? != FUNC_KIND {
+                        return Err(malformed(&format!("can't import '{}.{}'", from, name)));
+                    }
+                    module.imports.push((name, s.u32()?));
+                }
+                FUNCTION_SECTION => declared.push(s.u32()?),
+                MEMORY_SECTION => {
+                    let flags = s.byte()?;
+                    module.pages = s.u32()?;
+                    if flags & 1 != 0 {
+                        s.u32()?; // maximum
+                    }
+                }
+                GLOBAL_SECTION => {
+                    s.valtype()?;
+                    s.byte()?; // mutability
+                    module.globals.push(s.constant()?);
+                }
+                EXPORT_SECTION => {
+                    let name = s.name()?;
+                    let kind = s.byte()?;
+                    let index = s.u32()?;
+                    if kind == FUNC_KIND {
+                        module.exports.push((name, index));
+                    }
+                }
+                CODE_SECTION => {
+                    let size = s.u32()? as usize;
+                    let mut c = Reader { bytes: s.take(size)?, pos: 0 };
+                    let mut locals = Vec::new();
+                    for _ in 0..c.u32()? {
+                        let n = c.u32()?;
+                        let ty = c.valtype()?;
+                        locals.extend(std::iter::repeat_n(ty, n as usize));
+                    }
+                    let code = c.expression()?;
+                    let ty = *declared.get(module.functions.len()).ok_or_else(|| malformed("code without a function"))?;
+                    module.functions.push((ty, body(locals, code)?));
+                }
+                DATA_SECTION => {
+                    if s.u32()? != 0 {
+                        return Err(malformed("only active data segments are supported"));
+                    }
+                    let offset = match s.constant()? {
+                        Val::I32(n) => n as u32,
+                        _ => return Err(malformed("data offset isn't an i32")),
+                    };
+                    let len = s.u32()? as usize;
+                    module.data.push((offset, s.take(len)?.to_vec()));
+                }
+                12 => {} // data count
+                other => return Err(malformed(&format!("unsupported section {}", other))),
+            }
+            if id == 12 {
+                break;
+            }
+        }
+    }
+    Ok(module)
+}
+
+// Matches up each block with its `end` (and each `if` with its `else`).
+fn body(locals: Vec<ValType>, code: Vec<Instr>) -> Result<Body, Rekt> {
+    let mut ends = vec![0; code.len()];
+    let mut elses = vec![0; code.len()];
+    let mut open = Vec::new();
+    for (pc, instr) in code.iter().enumerate() {
+        match instr {
+            Instr::Block(_) | Instr::Loop(_) | Instr::If(_) => {
+                elses[pc] = usize::MAX;
+                open.push(pc);
+            }
+            Instr::Else => {
+                let start = *open.last().ok_or_else(|| malformed("else outside an if"))?;
+                elses[start] = pc;
+            }
+            Instr::End => {
+                let start = open.pop().ok_or_else(|| malformed("unbalanced end"))?;
+                ends[start] = pc;
+                if elses[start] == usize::MAX {
+                    elses[start] = pc;
+                }
+            }
+            _ => {}
+        }
+    }
+    if !open.is_empty() {
+        return Err(malformed("unclosed block"));
+    }
+    Ok(Body { locals, code, ends, elses })
+}
+
+// Why a run stopped early.
+enum Trap {
+    Rekt(Rekt),    // the script failed, as reported through `gm.rekt`
+    Wasm(String), // the module itself went wrong
+}
+
+struct Label {
+    height: usize,
+    arity: usize,
+    target: usize, // where a branch to it goes
+}
+
+struct Instance<'a> {
+    module: &'a Decoded,
+    memory: Vec<u8>,
+    globals: Vec<Val>,
+    events: Vec<String>,
+    failed: Option<Rekt>,
+    depth: usize,
+}
+
+// Instantiates a module and calls its export `entry`, which takes nothing.
+pub fn run(module: &Decoded, entry: &str) -> Result<Run, Rekt> {
+    let index = module
+        .exports
+        .iter()
+        .find(|(name, _)| name == entry)
+        .map(|(_, index)| *index)
+        .ok_or_else(|| malformed(&format!("no export '{}'", entry)))?;
+    let mut memory = vec![0; module.pages as usize * PAGE];
+    for (offset, bytes) in &module.data {
+        let start = *offset as usize;
+        let end = start + bytes.len();
+        if end > memory.len() {
+            return Err(malformed("data segment out of bounds"));
+        }
+        memory[start..end].copy_from_slice(bytes);
+    }
+    let mut instance = Instance {
+        module,
+        memory,
+        globals: module.globals.clone(),
+        events: Vec::new(),
+        failed: None,
+        depth: 0,
+    };
+    // Each wasm call is a few native frames, more so in debug builds, so
+    // the module runs on a thread with room for MAX_DEPTH of them
+    let called = std::thread::scope(|scope| {
+        std::thread::Builder::new()
+            .stack_size(MAX_DEPTH * FRAME_ROOM)
+            .spawn_scoped(scope, || {
+                let result = instance.call(index, Vec::new());
+                (result, instance.events)
+            })
+            .map_err(|e| Rekt::Runtime(format!("Couldn't start the WebAssembly machine: {}", e)))?
+            .join()
+            .map_err(|_| Rekt::Runtime("The WebAssembly machine panicked".to_string()))
+    })?;
+    let (result, events) = called;
+    let error = match result {
+        Ok(_) => None,
+        Err(Trap::Rekt(e)) => Some(e.to_string()),
+        Err(Trap::Wasm(what)) => Some(format!("WebAssembly trap: {}", what)),
+    };
+    Ok(Run { events, error })
+}
+
+fn trap(what: &str) -> Trap {
+    Trap::Wasm(what.to_string())
+}
+
+impl Instance<'_> {
+    fn call(&mut self, index: u32, args: Vec<Val>) -> Result<Option<Val>, Trap> {
+        let index = index as usize;
+        if let Some((name, _)) = self.module.imports.get(index) {
+            return self.host(name, &args);
+        }
+        let (ty, body) = self
+            .module
+            .functions
+            .get(index - self.module.imports.len())
+            .ok_or_else(|| trap("call to a missing function"))?;
+        let ty = self.module.types.get(*ty as usize).ok_or_else(|| trap("missing type"))?;
+        if self.depth == MAX_DEPTH {
+            return Err(trap("call stack exhausted"));
+        }
+        self.depth += 1;
+        let mut locals = args;
+        locals.extend(body.locals.iter().map(|t| Val::zero(*t)));
+        let result = self.execute(body, &mut locals, ty.results.len());
+        self.depth -= 1;
+        result
+    }
+
+    fn host(&mut self, name: &str, args: &[Val]) -> Result<Option<Val>, Trap> {
+        match (name, args) {
+            ("broadcast", [Val::I32(ptr), Val::I32(len)]) => {
+                let text = self.text(*ptr, *len)?;
+                self.events.push(text);
+                Ok(None)
+            }
+            ("number_text", [Val::F64(n), Val::I32(dest), Val::I32(room)]) => {
+                let text = n.to_string();
+                if text.len() > *room as usize {
+                    return Err(trap("number_text ran out of room"));
+                }
+                let start = *dest as usize;
+                let slot = self.memory.get_mut(start..start + text.len()).ok_or_else(|| trap("out of bounds"))?;
+                slot.copy_from_slice(text.as_bytes());
+                Ok(Some(Val::I32(text.len() as i32)))
+            }
+            ("rekt", [Val::I32(kind), Val::I32(ptr), Val::I32(len)]) => {
+                let message = self.text(*ptr, *len)?;
+                self.failed = Some(match kind {
+                    0 => Rekt::Lexer(message),
+                    1 => Rekt::Parser(message),
+                    2 => Rekt::Runtime(message),
+                    _ => Rekt::Type(message),
+                });
+                Ok(None)
+            }
+            _ => Err(Trap::Wasm(format!("no host function 'gm.{}' taking {:?}", name, args))),
+        }
+    }
+
+    fn text(&self, ptr: i32, len: i32) -> Result<String, Trap> {
+        let (start, len) = (ptr as u32 as usize, len as u32 as usize);
+        let bytes = self.memory.get(start..start + len).ok_or_else(|| trap("out of bounds"))?;
+        Ok(String::from_utf8_lossy(bytes).into_owned())
+    }
+
+    fn address(&self, base: i32, offset: u32, width: usize) -> Result<usize, Trap> {
+        let address = base as u32 as usize + offset as usize;
+        if address + width > self.memory.len() {
+            return Err(trap("out of bounds memory access"));
+        }
+        Ok(address)
+    }
+
+    fn execute(&mut self, body: &Body, locals: &mut [Val], arity: usize) -> Result<Option<Val>, Trap> {
+        let mut stack: Vec<Val> = Vec::new();
+        let mut labels: Vec<Label> = Vec::new();
+        let mut pc = 0;
+
+        macro_rules! pop {
+            ($variant:ident) => {
+                match stack.pop() {
+                    Some(Val::$variant(v)) => v,
+                    _ => return Err(trap("type mismatch on the stack")),
+                }
+            };
+        }
+        macro_rules! binary {
+            ($variant:ident, $out:ident, |$a:ident, $b:ident| $e:expr) => {{
+                let $b = pop!($variant);
+                let $a = pop!($variant);
+                stack.push(Val::$out($e));
+            }};
+        }
+        macro_rules! unary {
+            ($variant:ident, $out:ident, |$a:ident| $e:expr) => {{
+                let $a = pop!($variant);
+                stack.push(Val::$out($e));
+            }};
+        }
+
+        while pc < body.code.len() {
+            let instr = body.code[pc];
+            pc += 1;
+            match instr {
+                Instr::Unreachable => {
+                    return Err(match self.failed.take() {
+                        Some(e) => Trap::Rekt(e),
+                        None => trap("unreachable"),
+                    })
+                }
+                Instr::Block(ty) => labels.push(Label {
+                    height: stack.len(),
+                    arity: ty.is_some() as usize,
+                    target: body.ends[pc - 1] + 1,
+                }),
+                Instr::Loop(_) => labels.push(Label { height: stack.len(), arity: 0, target: pc - 1 }),
+                Instr::If(ty) => {
+                    let condition = pop!(I32);
+                    let start = pc - 1;
+                    labels.push(Label { height: stack.len(), arity: ty.is_some() as usize, target: body.ends[start] + 1 });
+                    if condition == 0 {
+                        // Into the `else` arm, or onto the `end` that pops the label
+                        let otherwise = body.elses[start];
+                        pc = if otherwise == body.ends[start] { otherwise } else { otherwise + 1 };
+                    }
+                }
+                Instr::Else => {
+                    // Reached from the end of the `then` arm
+                    let label = labels.pop().ok_or_else(|| trap("else outside an if"))?;
+                    pc = label.target;
+                }
+                Instr::End => {
+                    labels.pop();
+                }
+                Instr::Br(depth) => pc = branch(&mut stack, &mut labels, depth)?,
+                Instr::BrIf(depth) => {
+                    if pop!(I32) != 0 {
+                        pc = branch(&mut stack, &mut labels, depth)?;
+                    }
+                }
+                Instr::Return => break,
+                Instr::Call(index) => {
+                    let ty = self.type_of(index)?;
+                    let at = stack.len().checked_sub(ty.params.len()).ok_or_else(|| trap("stack underflow"))?;
+                    let args = stack.split_off(at);
+                    if let Some(value) = self.call(index, args)? {
+                        stack.push(value);
+                    }
+                }
+                Instr::Drop => {
+                    stack.pop();
+                }
+                Instr::Select => {
+                    let condition = pop!(I32);
+                    let b = stack.pop().ok_or_else(|| trap("stack underflow"))?;
+                    let a = stack.pop().ok_or_else(|| trap("stack underflow"))?;
+                    stack.push(if condition != 0 { a } else { b });
+                }
+                Instr::LocalGet(i) => stack.push(*locals.get(i as usize).ok_or_else(|| trap("no such local"))?),
+                Instr::LocalSet(i) | Instr::LocalTee(i) => {
+                    let value = stack.pop().ok_or_else(|| trap("stack underflow"))?;
+                    *locals.get_mut(i as usize).ok_or_else(|| trap("no such local"))? = value;
+                    if let Instr::LocalTee(_) = instr {
+                        stack.push(value);
+                    }
+                }
+                Instr::GlobalGet(i) => stack.push(*self.globals.get(i as usize).ok_or_else(|| trap("no such global"))?),
+                Instr::GlobalSet(i) => {
+                    let value = stack.pop().ok_or_else(|| trap("stack underflow"))?;
+                    *self.globals.get_mut(i as usize).ok_or_else(|| trap("no such global"))? = value;
+                }
+                Instr::I32Load(offset) => {
+                    let at = self.address(pop!(I32), offset, 4)?;
+                    let bytes = self.memory[at..at + 4].try_into().unwrap_or([0; 4]);
+                    stack.push(Val::I32(i32::from_le_bytes(bytes)));
+                }
+                Instr::I32Load8U(offset) => {
+                    let at = self.address(pop!(I32), offset, 1)?;
+                    stack.push(Val::I32(self.memory[at] as i32));
+                }
+                Instr::I32Store(offset) => {
+                    let value = pop!(I32);
+                    let at = self.address(pop!(I32), offset, 4)?;
+                    self.memory[at..at + 4].copy_from_slice(&value.to_le_bytes());
+                }
+                Instr::I32Store8(offset) => {
+                    let value = pop!(I32);
+                    let at = self.address(pop!(I32), offset, 1)?;
+                    self.memory[at] = value as u8;
+                }
+                Instr::MemorySize => stack.push(Val::I32((self.memory.len() / PAGE) as i32)),
+                Instr::MemoryGrow => {
+                    let pages = pop!(I32) as u32 as usize;
+                    let old = self.memory.len() / PAGE;
+                    if old + pages > 65_536 {
+                        stack.push(Val::I32(-1));
+                    } else {
+                        self.memory.resize((old + pages) * PAGE, 0);
+                        stack.push(Val::I32(old as i32));
+                    }
+                }
+                Instr::MemoryCopy => {
+                    let len = pop!(I32) as u32 as usize;
+                    let from = self.address(pop!(I32), 0, len)?;
+                    let to = self.address(pop!(I32), 0, len)?;
+                    self.memory.copy_within(from..from + len, to);
+                }
+                Instr::I32Const(n) => stack.push(Val::I32(n)),
+                Instr::I64Const(n) => stack.push(Val::I64(n)),
+                Instr::F64Const(n) => stack.push(Val::F64(n)),
+
+                Instr::I32Eqz => unary!(I32, I32, |a| (a == 0) as i32),
+                Instr::I32Eq => binary!(I32, I32, |a, b| (a == b) as i32),
+                Instr::I32Ne => binary!(I32, I32, |a, b| (a != b) as i32),
+                Instr::I32LtU => binary!(I32, I32, |a, b| ((a as u32) < (b as u32)) as i32),
+                Instr::I32GtU => binary!(I32, I32, |a, b| ((a as u32) > (b as u32)) as i32),
+                Instr::I32GeU => binary!(I32, I32, |a, b| ((a as u32) >= (b as u32)) as i32),
+                Instr::I32Add => binary!(I32, I32, |a, b| a.wrapping_add(b)),
+                Instr::I32Sub => binary!(I32, I32, |a, b| a.wrapping_sub(b)),
+                Instr::I32Mul => binary!(I32, I32, |a, b| a.wrapping_mul(b)),
+                Instr::I32And => binary!(I32, I32, |a, b| a & b),
+                Instr::I32Or => binary!(I32, I32, |a, b| a | b),
+                Instr::I32Xor => binary!(I32, I32, |a, b| a ^ b),
+                Instr::I32ShrU => binary!(I32, I32, |a, b| ((a as u32) >> (b as u32 % 32)) as i32),
+
+                Instr::I64Eqz => unary!(I64, I32, |a| (a == 0) as i32),
+                Instr::I64Eq => binary!(I64, I32, |a, b| (a == b) as i32),
+                Instr::I64Ne => binary!(I64, I32, |a, b| (a != b) as i32),
+                Instr::I64LtS => binary!(I64, I32, |a, b| (a < b) as i32),
+                Instr::I64GtS => binary!(I64, I32, |a, b| (a > b) as i32),
+                Instr::I64GeU => binary!(I64, I32, |a, b| ((a as u64) >= (b as u64)) as i32),
+                Instr::I64Add => binary!(I64, I64, |a, b| a.wrapping_add(b)),
+                Instr::I64Sub => binary!(I64, I64, |a, b| a.wrapping_sub(b)),
+                Instr::I64Mul => binary!(I64, I64, |a, b| a.wrapping_mul(b)),
+                Instr::I64DivS | Instr::I64RemS => {
+                    let b = pop!(I64);
+                    let a = pop!(I64);
+                    if b == 0 {
+                        return Err(trap("integer divide by zero"));
+                    }
+                    let result = match instr {
+                        Instr::I64DivS => a.checked_div(b).ok_or_else(|| trap("integer overflow"))?,
+                        _ => a.wrapping_rem(b),
+                    };
+                    stack.push(Val::I64(result));
+                }
+                Instr::I64And => binary!(I64, I64, |a, b| a & b),
+                Instr::I64Or => binary!(I64, I64, |a, b| a | b),
+                Instr::I64Xor => binary!(I64, I64, |a, b| a ^ b),
+                Instr::I64Shl => binary!(I64, I64, |a, b| a.wrapping_shl(b as u32)),
+                Instr::I64ShrS => binary!(I64, I64, |a, b| a.wrapping_shr(b as u32)),
+
+                Instr::F64Eq => binary!(F64, I32, |a, b| (a == b) as i32),
+                Instr::F64Ne => binary!(F64, I32, |a, b| (a != b) as i32),
+                Instr::F64Lt => binary!(F64, I32, |a, b| (a < b) as i32),
+                Instr::F64Gt => binary!(F64, I32, |a, b| (a > b) as i32),
+                Instr::F64Le => binary!(F64, I32, |a, b| (a <= b) as i32),
+                Instr::F64Ge => binary!(F64, I32, |a, b| (a >= b) as i32),
+                Instr::F64Abs => unary!(F64, F64, |a| a.abs()),
+                Instr::F64Trunc => unary!(F64, F64, |a| a.trunc()),
+                Instr::F64Add => binary!(F64, F64, |a, b| a + b),
+                Instr::F64Sub => binary!(F64, F64, |a, b| a - b),
+                Instr::F64Mul => binary!(F64, F64, |a, b| a * b),
+                Instr::F64Div => binary!(F64, F64, |a, b| a / b),
+
+                Instr::I32WrapI64 => unary!(I64, I32, |a| a as i32),
+                Instr::I64TruncF64S => {
+                    let a = pop!(F64);
+                    if !(-TWO_63..TWO_63).contains(&a) {
+                        return Err(trap("invalid conversion to integer"));
+                    }
+                    stack.push(Val::I64(a as i64));
+                }
+                Instr::F64ConvertI64S => unary!(I64, F64, |a| a as f64),
+                Instr::F64ConvertI64U => unary!(I64, F64, |a| a as u64 as f64),
+                Instr::I64ReinterpretF64 => unary!(F64, I64, |a| a.to_bits() as i64),
+            }
+        }
+
+        if arity == 0 {
+            return Ok(None);
+        }
+        stack.pop().map(Some).ok_or_else(|| trap("function returned nothing"))
+    }
+
+    fn type_of(&self, index: u32) -> Result<&FuncType, Trap> {
+        let index = index as usize;
+        let ty = match self.module.imports.get(index) {
+            Some((_, ty)) => *ty,
+            None => self.module.functions.get(index - self.module.imports.len()).ok_or_else(|| trap("no such function"))?.0,
+        };
+        self.module.types.get(ty as usize).ok_or_else(|| trap("missing type"))
+    }
+}
+
+// Unwinds to the label `depth` out, keeping the values it carries, and
+// returns where to carry on. A loop's target is the `loop` itself, which
+// pushes its label again.
+fn branch(stack: &mut Vec<Val>, labels: &mut Vec<Label>, depth: u32) -> Result<usize, Trap> {
+    let index = labels.len().checked_sub(depth as usize + 1).ok_or_else(|| trap("branch out of range"))?;
+    let label = &labels[index];
+    let kept = stack.split_off(stack.len() - label.arity.min(stack.len()));
+    stack.truncate(label.height);
+    stack.extend(kept);
+    let target = label.target;
+    labels.truncate(index);
+    Ok(target)
+}
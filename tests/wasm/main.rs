@@ -0,0 +1,57 @@
+// Each script in examples/wasm compiled to a module, validated, and run on
+// the machine in `machine.rs` against the tree walker and the goldens, then
+// the scripts in examples/diff the compiler supports, without goldens.
+
+#[path = "../common/mod.rs"]
+mod common;
+mod machine;
+
+use std::path::Path;
+use gm_language::error::Rekt;
+use gm_language::runner::{self, BackendComparison, Golden, Outcome};
+use gm_language::wasm;
+
+// Runs a script on the tree walker and its module on the machine, once as
+// encoded and once as assembled from its text, after wasmparser has
+// validated the binary. Only the first line of an error is compared, as
+// with node.
+fn compare_wasm_file(path: &Path) -> Result<BackendComparison, Rekt> {
+    let ast = runner::load(path)?;
+    let module = wasm::compile(&ast)?;
+    let text = module.to_wat();
+    let golden = Golden::check(path, "wat", "--wasm", &text);
+
+    let binary = wasm::encode(&module);
+    wasmparser::Validator::new()
+        .validate_all(&binary)
+        .map_err(|e| Rekt::Runtime(format!("Invalid WebAssembly module: {}", e)))?;
+    let machine = run_wasm(&binary)?;
+    let assembled = wat::parse_str(&text).map_err(|e| Rekt::Runtime(format!("Invalid WebAssembly text: {}", e)))?;
+    let mut notes = Vec::new();
+    if run_wasm(&assembled)? != machine {
+        notes.push("the .wat text assembles to a module that behaves differently".to_string());
+    }
+
+    Ok(BackendComparison {
+        backend: "wasm",
+        tree_walker: runner::walk(ast).headline(),
+        outcome: machine,
+        golden,
+        notes,
+    })
+}
+
+fn run_wasm(binary: &[u8]) -> Result<Outcome, Rekt> {
+    let run = machine::run(&machine::decode(binary)?, "run")?;
+    Ok(Outcome { events: run.events, error: run.error }.headline())
+}
+
+#[test]
+fn modules_agree_with_the_tree_walker() {
+    common::assert_agree("wasm", compare_wasm_file);
+}
+
+#[test]
+fn shared_scripts_agree_where_they_compile() {
+    common::assert_agree("diff", |script| common::where_supported(compare_wasm_file(script)));
+}